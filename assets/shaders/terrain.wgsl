@@ -17,6 +17,13 @@ const SHAPE_THICKNESS = 0.15;
 // Keep these array lengths in sync with /crates/terrain/src/shader.rs.
 const MAX_KD_TREE_SIZE = 127u;
 const MAX_RECTANGLE_ARRAY_SIZE = 31u;
+const MAX_ZONE_ARRAY_SIZE = 31u;
+const MAX_ZONE_VERTICES = 8u;
+// Width (in meters) of the transition between a zone and ordinary terrain.
+const ZONE_EDGE_WIDTH = 1.5;
+const WATER_COLOR = vec3<f32>(0.09, 0.26, 0.42);
+const CLIFF_COLOR = vec3<f32>(0.36, 0.33, 0.3);
+const CRATER_COLOR = vec3<f32>(0.17, 0.14, 0.12);
 
 struct KdTreeNode {
     @align(16) location: vec2<f32>,
@@ -38,6 +45,17 @@ struct Rectangles {
     count: u32,
 };
 
+struct Zone {
+    vertices: array<vec4<f32>, MAX_ZONE_VERTICES>,
+    count: u32,
+    kind: u32,
+};
+
+struct Zones {
+    items: array<Zone, MAX_ZONE_ARRAY_SIZE>,
+    count: u32,
+};
+
 @group(1) @binding(0)
 var<uniform> circles: KdTree;
 @group(1) @binding(1)
@@ -46,6 +64,8 @@ var<uniform> rectangles: Rectangles;
 var terrain_texture: texture_2d<f32>;
 @group(1) @binding(3)
 var terrain_sampler: sampler;
+@group(1) @binding(4)
+var<uniform> zones: Zones;
 
 struct FragmentInput {
     @builtin(front_facing) is_front: bool,
@@ -171,6 +191,45 @@ fn draw_rectangles(base: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {
     return base;
 }
 
+fn zone_color(kind: u32) -> vec3<f32> {
+    // Keep these zone kinds in sync with /crates/terrain/src/shader.rs.
+    switch kind {
+        case 0u: {
+            return WATER_COLOR;
+        }
+        case 1u: {
+            return CLIFF_COLOR;
+        }
+        default: {
+            return CRATER_COLOR;
+        }
+    }
+}
+
+// Returns distance of a point to the boundary of a (convex and counter
+// clockwise) zone. The distance is negative for points outside of the zone.
+fn zone_distance(index: u32, uv: vec2<f32>) -> f32 {
+    let count = zones.items[index].count;
+    var distance = 1e9;
+    for (var i = 0u; i < count; i++) {
+        let a = zones.items[index].vertices[i].xy;
+        let b = zones.items[index].vertices[(i + 1u) % count].xy;
+        let edge = normalize(b - a);
+        let offset = uv - a;
+        distance = min(distance, edge.x * offset.y - edge.y * offset.x);
+    }
+    return distance;
+}
+
+fn draw_zones(base: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {
+    var rgb = base.rgb;
+    for (var i = 0u; i < zones.count; i++) {
+        let weight = smoothstep(0., ZONE_EDGE_WIDTH, zone_distance(i, uv));
+        rgb = mix(rgb, zone_color(zones.items[i].kind), weight);
+    }
+    return vec4<f32>(rgb, base.a);
+}
+
 @fragment
 fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
     var pbr_input: PbrInput = pbr_input_new();
@@ -183,6 +242,7 @@ fn fragment(in: FragmentInput) -> @location(0) vec4<f32> {
         terrain_sampler,
         in.uv / TEXTURE_SIZE
     );
+    pbr_input.material.base_color = draw_zones(pbr_input.material.base_color, in.uv);
 
 #ifdef VERTEX_COLORS
     pbr_input.material.base_color = pbr_input.material.base_color * in.color;
@@ -0,0 +1,98 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_combat::AttackEvent;
+use de_core::{
+    diplomacy::Diplomacy,
+    objects::{Active, ActiveObjectType, MovableSolid, ObjectType},
+    player::Player,
+    projection::ToFlat,
+};
+use de_index::SpatialQuery;
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPath};
+use de_spawner::ObjectCounter;
+
+use crate::{conf::AiConf, production::ProductionSet, turns::AiTurns, AiSet};
+
+/// Idle units further than this from their base are called back.
+const RALLY_DISTANCE: f32 = 40.;
+
+pub(crate) struct ArmyPlugin;
+
+impl Plugin for ArmyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            command_army
+                .in_set(AiSet::Decide)
+                .after(ProductionSet::Produce),
+        );
+    }
+}
+
+/// Units without any order.
+type IdleUnits = (With<MovableSolid>, Without<PathTarget>);
+
+#[derive(SystemParam)]
+struct ArmyCommands<'w> {
+    attacks: EventWriter<'w, AttackEvent>,
+    paths: EventWriter<'w, UpdateEntityPath>,
+}
+
+/// Attacks the nearest enemies once an AI player gathers enough units,
+/// otherwise gathers idle units at the base.
+fn command_army(
+    conf: Res<AiConf>,
+    mut turns: ResMut<AiTurns>,
+    counter: Res<ObjectCounter>,
+    diplomacy: Res<Diplomacy>,
+    idle: Query<(Entity, &Player, &Transform), IdleUnits>,
+    objects: SpatialQuery<(&Player, &ObjectType, &Transform), With<Active>>,
+    mut commands: ArmyCommands,
+) {
+    for (unit, &player, transform) in idle.iter() {
+        if !conf.is_ai(player) || !turns.is_active(player) {
+            continue;
+        }
+        let position = transform.translation.to_flat();
+
+        let units = counter.player(player).map_or(0, |count| count.unit_count());
+        if units >= conf.difficulty().attack_threshold() {
+            let enemies = objects.k_nearest(position, 1, f32::INFINITY, |_, &(&other, _, _)| {
+                !diplomacy.are_allies(player, other)
+            });
+            if let Some(enemy) = enemies.first() {
+                if turns.spend(player) {
+                    commands
+                        .attacks
+                        .send(AttackEvent::new(unit, enemy.entity()));
+                }
+            }
+        } else {
+            let bases = objects.k_nearest(
+                position,
+                1,
+                f32::INFINITY,
+                |_, &(&other, &object_type, _)| {
+                    other == player
+                        && matches!(
+                            object_type,
+                            ObjectType::Active(ActiveObjectType::Building(_))
+                        )
+                },
+            );
+            let Some(base) = bases.first() else {
+                continue;
+            };
+            let (_, _, base_transform) = base.item();
+            let base = base_transform.translation.to_flat();
+            if position.distance(base) > RALLY_DISTANCE && turns.spend(player) {
+                commands.paths.send(UpdateEntityPath::new(
+                    unit,
+                    PathTarget::new(
+                        base,
+                        PathQueryProps::new(0.5 * RALLY_DISTANCE, f32::INFINITY),
+                        false,
+                    ),
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{fmt, time::Duration};
+
+use bevy::prelude::*;
+use de_core::player::Player;
+
+/// Configuration of AI players of a game. AI players are enabled only when
+/// this resource is inserted before [`de_core::state::AppState::InGame`] is
+/// entered.
+///
+/// This resource is automatically removed when
+/// [`de_core::state::AppState::InGame`] is exited.
+#[derive(Resource, Clone, Debug)]
+pub struct AiConf {
+    difficulty: Difficulty,
+    players: Vec<Player>,
+}
+
+impl AiConf {
+    /// # Arguments
+    ///
+    /// * `difficulty` - difficulty of all AI players.
+    ///
+    /// * `players` - players controlled by the AI on this computer.
+    pub fn new(difficulty: Difficulty, players: impl IntoIterator<Item = Player>) -> Self {
+        Self {
+            difficulty,
+            players: players.into_iter().collect(),
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn players(&self) -> &[Player] {
+        self.players.as_slice()
+    }
+
+    pub fn is_ai(&self, player: Player) -> bool {
+        self.players.contains(&player)
+    }
+
+    /// Hands the player over to the AI. It returns false if the player was
+    /// already controlled by the AI.
+    pub(crate) fn add_player(&mut self, player: Player) -> bool {
+        if self.is_ai(player) {
+            return false;
+        }
+        self.players.push(player);
+        true
+    }
+}
+
+/// Difficulty preset of AI players.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Returns the next (harder) difficulty, wrapping around to the easiest
+    /// one.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Easy => Self::Normal,
+            Self::Normal => Self::Hard,
+            Self::Hard => Self::Easy,
+        }
+    }
+
+    /// Time between two consecutive decision turns of an AI player.
+    pub(crate) fn turn_interval(self) -> Duration {
+        match self {
+            Self::Easy => Duration::from_secs(4),
+            Self::Normal => Duration::from_secs(2),
+            Self::Hard => Duration::from_millis(700),
+        }
+    }
+
+    /// Maximum number of commands issued during a single turn.
+    pub(crate) fn budget(self) -> u32 {
+        match self {
+            Self::Easy => 2,
+            Self::Normal => 4,
+            Self::Hard => 8,
+        }
+    }
+
+    /// Number of units an AI player gathers before it attacks.
+    pub(crate) fn attack_threshold(self) -> u32 {
+        match self {
+            Self::Easy => 10,
+            Self::Normal => 6,
+            Self::Hard => 4,
+        }
+    }
+
+    /// Maximum number of units an AI player keeps.
+    pub(crate) fn max_units(self) -> u32 {
+        match self {
+            Self::Easy => 15,
+            Self::Normal => 30,
+            Self::Hard => 60,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Easy => write!(f, "Easy"),
+            Self::Normal => write!(f, "Normal"),
+            Self::Hard => write!(f, "Hard"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_player() {
+        let mut conf = AiConf::new(Difficulty::Easy, [Player::Player2]);
+        assert!(!conf.is_ai(Player::Player3));
+        assert!(conf.add_player(Player::Player3));
+        assert!(!conf.add_player(Player::Player3));
+        assert!(!conf.add_player(Player::Player2));
+        assert_eq!(conf.players(), &[Player::Player2, Player::Player3]);
+    }
+
+    #[test]
+    fn test_difficulty() {
+        let mut difficulty = Difficulty::Easy;
+        for _ in 0..2 {
+            let next = difficulty.next();
+            assert!(next.turn_interval() < difficulty.turn_interval());
+            assert!(next.budget() > difficulty.budget());
+            assert!(next.attack_threshold() < difficulty.attack_threshold());
+            difficulty = next;
+        }
+        assert_eq!(difficulty, Difficulty::Hard);
+        assert_eq!(difficulty.next(), Difficulty::Easy);
+    }
+}
@@ -0,0 +1,36 @@
+//! Computer controlled (AI) opponents.
+//!
+//! AI players are configured with [`AiConf`] and further players may be
+//! handed over to the AI during the game with [`AiTakeoverEvent`]. Each AI
+//! player decides in turns whose frequency is given by the [`Difficulty`].
+//! The number of commands issued in a single turn is limited by a budget so
+//! that AI players cannot act faster than a human player, see [`AiTurns`].
+//!
+//! AI players issue the same command events as human players, i.e. they
+//! enqueue units in factories, move units and attack enemies. Further
+//! decision systems may be plugged in via [`AiSet::Decide`].
+
+use army::ArmyPlugin;
+use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+use production::ProductionPlugin;
+
+pub use crate::{
+    conf::{AiConf, Difficulty},
+    turns::{AiControllerPlugin, AiSet, AiTakeoverEvent, AiTurns},
+};
+
+mod army;
+mod conf;
+mod production;
+mod turns;
+
+pub struct AiPluginGroup;
+
+impl PluginGroup for AiPluginGroup {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(AiControllerPlugin)
+            .add(ProductionPlugin)
+            .add(ArmyPlugin)
+    }
+}
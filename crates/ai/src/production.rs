@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use de_construction::{AssemblyLine, EnqueueAssemblyEvent};
+use de_core::{objects::UnitType, player::Player};
+use de_spawner::ObjectCounter;
+
+use crate::{conf::AiConf, turns::AiTurns, AiSet};
+
+/// Maximum number of units in an assembly line of an AI player.
+const MAX_QUEUE_LEN: usize = 2;
+
+pub(crate) struct ProductionPlugin;
+
+impl Plugin for ProductionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(produce.in_set(AiSet::Decide).in_set(ProductionSet::Produce));
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum ProductionSet {
+    Produce,
+}
+
+/// Enqueues units in factories of AI players until they have enough units.
+fn produce(
+    conf: Res<AiConf>,
+    mut turns: ResMut<AiTurns>,
+    counter: Res<ObjectCounter>,
+    factories: Query<(Entity, &Player, &AssemblyLine)>,
+    mut events: EventWriter<EnqueueAssemblyEvent>,
+) {
+    for (entity, &player, line) in factories.iter() {
+        if !conf.is_ai(player) || !turns.is_active(player) {
+            continue;
+        }
+
+        let units = counter.player(player).map_or(0, |count| count.unit_count());
+        let queued = line.queue_len();
+        if queued >= MAX_QUEUE_LEN || units + queued as u32 >= conf.difficulty().max_units() {
+            continue;
+        }
+
+        if turns.spend(player) {
+            events.send(EnqueueAssemblyEvent::new(entity, UnitType::Attacker));
+        }
+    }
+}
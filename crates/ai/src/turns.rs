@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, gamestate::GameState, player::Player, state::AppState};
+
+use crate::conf::{AiConf, Difficulty};
+
+/// This plugin drives decision turns of AI players. Decision systems are
+/// plugged in by adding them to [`AiSet::Decide`] and spending budget of
+/// [`AiTurns`] on each issued command.
+///
+/// Players may be handed over to the AI during the game with
+/// [`AiTakeoverEvent`].
+pub struct AiControllerPlugin;
+
+impl Plugin for AiControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_set(
+            AiSet::Turns
+                .in_base_set(GameSet::Input)
+                .run_if(in_state(GameState::Playing))
+                .run_if(resource_exists::<AiTurns>()),
+        )
+        .configure_set(
+            AiSet::Decide
+                .in_base_set(GameSet::Input)
+                .run_if(in_state(GameState::Playing))
+                .run_if(resource_exists::<AiTurns>())
+                .after(AiSet::Turns),
+        )
+        .add_event::<AiTakeoverEvent>()
+        .add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+        .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+        .add_system(
+            take_over
+                .in_base_set(GameSet::Input)
+                .run_if(in_state(GameState::Playing))
+                .run_if(on_event::<AiTakeoverEvent>())
+                .before(AiSet::Turns),
+        )
+        .add_system(tick.in_set(AiSet::Turns));
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum AiSet {
+    /// Turns and command budgets of AI players are updated.
+    Turns,
+    /// AI players make decisions and issue commands.
+    Decide,
+}
+
+/// Send this event to hand objects of a player over to the AI on this
+/// computer, e.g. after the player left a multiplayer game. AI players are
+/// enabled if they were not already.
+pub struct AiTakeoverEvent(Player);
+
+impl AiTakeoverEvent {
+    pub fn new(player: Player) -> Self {
+        Self(player)
+    }
+
+    pub fn player(&self) -> Player {
+        self.0
+    }
+}
+
+/// Decision turns of AI players. An AI player decides only during its turn
+/// and each issued command spends a unit of its budget.
+#[derive(Resource)]
+pub struct AiTurns(Vec<PlayerTurn>);
+
+struct PlayerTurn {
+    player: Player,
+    timer: Timer,
+    budget: u32,
+    max_budget: u32,
+}
+
+impl AiTurns {
+    fn new(conf: &AiConf) -> Self {
+        let interval = conf.difficulty().turn_interval();
+        let count = conf.players().len() as u32;
+
+        Self(
+            conf.players()
+                .iter()
+                .enumerate()
+                .map(|(index, &player)| {
+                    let mut timer = Timer::new(interval, TimerMode::Repeating);
+                    // Spread turns of individual players over the interval.
+                    timer.set_elapsed(interval * index as u32 / count);
+                    PlayerTurn {
+                        player,
+                        timer,
+                        budget: 0,
+                        max_budget: conf.difficulty().budget(),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Adds a turn of a player who was just handed over to the AI. It is a
+    /// no-op if the player already has a turn.
+    fn add(&mut self, player: Player, difficulty: Difficulty) {
+        if self.0.iter().any(|turn| turn.player == player) {
+            return;
+        }
+
+        self.0.push(PlayerTurn {
+            player,
+            timer: Timer::new(difficulty.turn_interval(), TimerMode::Repeating),
+            budget: 0,
+            max_budget: difficulty.budget(),
+        });
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        for turn in self.0.iter_mut() {
+            turn.timer.tick(delta);
+            turn.budget = if turn.timer.just_finished() {
+                turn.max_budget
+            } else {
+                0
+            };
+        }
+    }
+
+    /// Returns true if it is the player's turn and the player has not yet
+    /// spent all of its budget.
+    pub fn is_active(&self, player: Player) -> bool {
+        self.0
+            .iter()
+            .any(|turn| turn.player == player && turn.budget > 0)
+    }
+
+    /// Spends a unit of the player's budget. It returns false (and spends
+    /// nothing) if the player is not active, see [`Self::is_active`].
+    pub fn spend(&mut self, player: Player) -> bool {
+        match self
+            .0
+            .iter_mut()
+            .find(|turn| turn.player == player && turn.budget > 0)
+        {
+            Some(turn) => {
+                turn.budget -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Players whose turn it is.
+    pub fn active(&self) -> impl Iterator<Item = Player> + '_ {
+        self.0
+            .iter()
+            .filter(|turn| turn.budget > 0)
+            .map(|turn| turn.player)
+    }
+}
+
+fn setup(mut commands: Commands, conf: Option<Res<AiConf>>) {
+    if let Some(conf) = conf {
+        info!(
+            "{} AI players with difficulty {} are playing.",
+            conf.players().len(),
+            conf.difficulty()
+        );
+        commands.insert_resource(AiTurns::new(conf.as_ref()));
+    }
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<AiTurns>();
+    commands.remove_resource::<AiConf>();
+}
+
+fn take_over(
+    mut commands: Commands,
+    conf: Option<Res<AiConf>>,
+    turns: Option<ResMut<AiTurns>>,
+    mut events: EventReader<AiTakeoverEvent>,
+) {
+    let mut conf = conf.map_or_else(
+        || AiConf::new(Difficulty::default(), []),
+        |conf| conf.clone(),
+    );
+
+    let mut added = false;
+    for event in events.iter() {
+        if conf.add_player(event.player()) {
+            info!("{} was handed over to the AI.", event.player());
+            added = true;
+        }
+    }
+    if !added {
+        return;
+    }
+
+    match turns {
+        Some(mut turns) => {
+            for &player in conf.players() {
+                turns.add(player, conf.difficulty());
+            }
+        }
+        None => commands.insert_resource(AiTurns::new(&conf)),
+    }
+    commands.insert_resource(conf);
+}
+
+fn tick(time: Res<Time>, mut turns: ResMut<AiTurns>) {
+    turns.tick(time.delta());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::Difficulty;
+
+    #[test]
+    fn test_turns() {
+        let difficulty = Difficulty::Easy;
+        let interval = difficulty.turn_interval();
+        let conf = AiConf::new(difficulty, [Player::Player2, Player::Player3]);
+        let mut turns = AiTurns::new(&conf);
+        assert!(!turns.is_active(Player::Player2));
+        assert!(!turns.spend(Player::Player2));
+
+        // Turn of Player3 is shifted by half of the interval.
+        turns.tick(interval / 2);
+        assert_eq!(turns.active().collect::<Vec<_>>(), vec![Player::Player3]);
+        for _ in 0..difficulty.budget() {
+            assert!(turns.spend(Player::Player3));
+        }
+        assert!(!turns.spend(Player::Player3));
+        assert!(!turns.is_active(Player::Player3));
+
+        turns.tick(interval / 4);
+        assert_eq!(turns.active().count(), 0);
+
+        turns.tick(interval / 4);
+        assert!(turns.is_active(Player::Player2));
+        assert!(!turns.is_active(Player::Player3));
+        assert!(!turns.is_active(Player::Player1));
+    }
+
+    #[test]
+    fn test_add() {
+        let difficulty = Difficulty::Easy;
+        let interval = difficulty.turn_interval();
+        let conf = AiConf::new(difficulty, [Player::Player2]);
+        let mut turns = AiTurns::new(&conf);
+
+        turns.add(Player::Player4, difficulty);
+        turns.add(Player::Player4, difficulty);
+        assert_eq!(turns.0.len(), 2);
+
+        turns.tick(interval);
+        assert!(turns.is_active(Player::Player2));
+        assert!(turns.is_active(Player::Player4));
+        assert!(turns.spend(Player::Player4));
+    }
+}
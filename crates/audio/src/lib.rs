@@ -1,13 +1,17 @@
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+pub use spatial::{PlaySoundEvent, Sound, SpatialAudioSet};
 
-use crate::music::MusicPlugin;
+use crate::{music::MusicPlugin, spatial::SpatialAudioPlugin};
 
 mod music;
+mod spatial;
 
 pub struct AudioPluginGroup;
 
 impl PluginGroup for AudioPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(MusicPlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(MusicPlugin)
+            .add(SpatialAudioPlugin)
     }
 }
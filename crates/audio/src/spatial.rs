@@ -0,0 +1,198 @@
+use bevy::{asset::LoadState, prelude::*};
+use de_conf::{AudioConf, Configuration};
+use de_core::{baseset::GameSet, state::AppState};
+use enum_map::{enum_map, Enum, EnumMap};
+use iyes_progress::prelude::*;
+
+/// Sounds closer to the camera than this (in meters) are played with full
+/// volume.
+const FULL_VOLUME_DISTANCE: f32 = 60.;
+/// Sounds further from the camera than this (in meters) are not played at
+/// all.
+const MAX_DISTANCE: f32 = 400.;
+/// Distance between the ears of the listener.
+///
+/// Rodio attenuates spatial sound with squared distance from each ear, which
+/// is far too steep for distances between the camera and objects on the map.
+/// Therefore, the emitter is placed close to the listener, only its direction
+/// is preserved, and distance attenuation is applied via volume, see
+/// [`attenuation`].
+const EAR_GAP: f32 = 0.5;
+
+pub(crate) struct SpatialAudioPlugin;
+
+impl Plugin for SpatialAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlaySoundEvent>()
+            .add_system(setup.in_schedule(OnEnter(AppState::AppLoading)))
+            .add_system(load.track_progress().run_if(in_state(AppState::AppLoading)))
+            .add_system(
+                play.in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame))
+                    .in_set(SpatialAudioSet::Play),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum SpatialAudioSet {
+    Play,
+}
+
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sound {
+    LaserFire,
+    Explosion,
+    /// A unit confirms reception of a command.
+    Acknowledgement,
+    Construction,
+    /// An alert for the player, see [`de_core::alert::AlertEvent`].
+    Notification,
+}
+
+impl Sound {
+    fn path(self) -> &'static str {
+        match self {
+            Self::LaserFire => "audio/sounds/laser_fire.mp3",
+            Self::Explosion => "audio/sounds/explosion.mp3",
+            Self::Acknowledgement => "audio/sounds/acknowledgement.mp3",
+            Self::Construction => "audio/sounds/construction.mp3",
+            Self::Notification => "audio/sounds/notification.mp3",
+        }
+    }
+
+    /// Returns volume of the sound category of this sound.
+    fn volume(self, conf: &AudioConf) -> f32 {
+        match self {
+            Self::LaserFire | Self::Explosion => conf.combat_volume(),
+            Self::Acknowledgement => conf.unit_volume(),
+            Self::Construction => conf.construction_volume(),
+            Self::Notification => conf.notification_volume(),
+        }
+    }
+}
+
+/// Send this event to play a sound at a point in the world. The sound is
+/// attenuated with its distance from the camera.
+///
+/// At most a single instance of each sound, the loudest one, is played
+/// during a frame.
+pub struct PlaySoundEvent {
+    sound: Sound,
+    position: Option<Vec3>,
+}
+
+impl PlaySoundEvent {
+    pub fn new(sound: Sound, position: Vec3) -> Self {
+        Self {
+            sound,
+            position: Some(position),
+        }
+    }
+
+    /// Creates an event of a sound which is not related to any point in the
+    /// world. Such sounds are played with full volume.
+    pub fn global(sound: Sound) -> Self {
+        Self {
+            sound,
+            position: None,
+        }
+    }
+
+    fn sound(&self) -> Sound {
+        self.sound
+    }
+
+    fn position(&self) -> Option<Vec3> {
+        self.position
+    }
+}
+
+#[derive(Resource)]
+struct Sounds(EnumMap<Sound, Handle<AudioSource>>);
+
+fn setup(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(Sounds(
+        enum_map! { sound => server.load(Sound::path(sound)) },
+    ));
+}
+
+fn load(server: Res<AssetServer>, sounds: Res<Sounds>) -> Progress {
+    let state = server.get_group_load_state(sounds.0.values().map(|handle| handle.id()));
+    match state {
+        // Sound effects are not essential, the failure is logged by the asset
+        // server and the game continues without the sound.
+        LoadState::Loaded | LoadState::Failed => true.into(),
+        LoadState::NotLoaded | LoadState::Loading => false.into(),
+        _ => panic!("Unexpected loading state."),
+    }
+}
+
+fn play(
+    conf: Res<Configuration>,
+    audio: Res<Audio>,
+    sounds: Res<Sounds>,
+    camera: Query<&Transform, With<Camera3d>>,
+    mut events: EventReader<PlaySoundEvent>,
+) {
+    let Ok(listener) = camera.get_single() else {
+        return;
+    };
+
+    let mut loudest: EnumMap<Sound, Option<(f32, Vec3)>> = EnumMap::default();
+    for event in events.iter() {
+        let offset = event
+            .position()
+            .map_or(Vec3::ZERO, |position| position - listener.translation);
+        let volume = event.sound().volume(conf.audio()) * attenuation(offset.length());
+        if volume <= 0. {
+            continue;
+        }
+
+        let current = &mut loudest[event.sound()];
+        if !matches!(*current, Some((max, _)) if max >= volume) {
+            *current = Some((volume, offset));
+        }
+    }
+
+    for (sound, &candidate) in loudest.iter() {
+        let Some((volume, offset)) = candidate else {
+            continue;
+        };
+
+        audio.play_spatial_with_settings(
+            sounds.0[sound].clone(),
+            PlaybackSettings::ONCE.with_volume(volume),
+            *listener,
+            EAR_GAP,
+            listener.translation + 0.5 * EAR_GAP * offset.normalize_or_zero(),
+        );
+    }
+}
+
+/// Returns volume multiplier of a sound at a distance (in meters) from the
+/// listener.
+fn attenuation(distance: f32) -> f32 {
+    if distance <= FULL_VOLUME_DISTANCE {
+        1.
+    } else if distance < MAX_DISTANCE {
+        FULL_VOLUME_DISTANCE / distance
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attenuation() {
+        assert_eq!(attenuation(0.), 1.);
+        assert_eq!(attenuation(FULL_VOLUME_DISTANCE), 1.);
+        assert_eq!(attenuation(2. * FULL_VOLUME_DISTANCE), 0.5);
+        assert!(attenuation(MAX_DISTANCE - 1.) > 0.);
+        assert_eq!(attenuation(MAX_DISTANCE), 0.);
+        assert_eq!(attenuation(f32::INFINITY), 0.);
+    }
+}
@@ -0,0 +1,221 @@
+//! Planning of group movement in formations.
+//!
+//! A group of units commanded to move to a point is spread around the point
+//! so that the units keep a formation. The formation is oriented in the
+//! direction of the movement of the group. Slower speeds are planned for units
+//! closer to their destinations so that the whole group arrives at once.
+
+use std::cmp::Ordering;
+
+use glam::Vec2;
+
+/// Distance between neighbouring units of a formation in meters.
+const SPACING: f32 = 5.;
+/// Units never slow down under this fraction of their maximum speed.
+const MIN_SPEED: f32 = 0.2;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Formation {
+    /// A single row of units perpendicular to the direction of movement.
+    Line,
+    /// An arrow pointing in the direction of movement.
+    Wedge,
+    /// Rows of equal length forming a square.
+    #[default]
+    Box,
+}
+
+impl Formation {
+    /// Plans destinations of a group of units.
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - current flat positions of the units.
+    ///
+    /// * `target` - the destination of the group. The formation is centered
+    ///   around it.
+    ///
+    /// # Returns
+    ///
+    /// Destination slots in the same order as `positions`.
+    pub fn plan(self, positions: &[Vec2], target: Vec2) -> Vec<FormationSlot> {
+        if positions.is_empty() {
+            return Vec::new();
+        }
+
+        let centroid = positions.iter().sum::<Vec2>() / positions.len() as f32;
+        let forward = (target - centroid).try_normalize().unwrap_or(Vec2::Y);
+        let lateral = -forward.perp();
+
+        let rows = self.rows(positions.len());
+        let center = rows.iter().flatten().sum::<Vec2>() / positions.len() as f32;
+
+        // Units are assigned to rows by their position along the direction of
+        // movement and to slots within the rows by their lateral position. This
+        // avoids most crossings of the paths.
+        let mut order: Vec<usize> = (0..positions.len()).collect();
+        order.sort_by(|&a, &b| compare(positions[b].dot(forward), positions[a].dot(forward)));
+
+        let mut destinations = vec![Vec2::ZERO; positions.len()];
+        let mut start = 0;
+        for slots in rows {
+            let units = &mut order[start..start + slots.len()];
+            units.sort_by(|&a, &b| compare(positions[a].dot(lateral), positions[b].dot(lateral)));
+
+            for (&unit, slot) in units.iter().zip(slots.iter()) {
+                let offset = *slot - center;
+                destinations[unit] = target + offset.x * lateral + offset.y * forward;
+            }
+            start += slots.len();
+        }
+
+        let distances: Vec<f32> = positions
+            .iter()
+            .zip(destinations.iter())
+            .map(|(position, destination)| position.distance(*destination))
+            .collect();
+        let max_distance = distances.iter().copied().fold(0., f32::max);
+
+        destinations
+            .into_iter()
+            .zip(distances)
+            .map(|(position, distance)| FormationSlot {
+                position,
+                speed: if max_distance > 0. {
+                    (distance / max_distance).clamp(MIN_SPEED, 1.)
+                } else {
+                    1.
+                },
+            })
+            .collect()
+    }
+
+    /// Returns rows of formation slots ordered from the front of the
+    /// formation. Each slot is given as (lateral offset, forward offset).
+    /// Slots in each row are ordered by their lateral offset.
+    fn rows(self, size: usize) -> Vec<Vec<Vec2>> {
+        match self {
+            Self::Line => vec![row(size, 0.)],
+            Self::Wedge => {
+                let mut rows = Vec::new();
+                let mut remaining = size;
+                let mut index = 0;
+                while remaining > 0 {
+                    let forward = -(index as f32) * SPACING;
+                    if index == 0 {
+                        rows.push(vec![Vec2::new(0., forward)]);
+                        remaining -= 1;
+                    } else if remaining == 1 {
+                        rows.push(vec![Vec2::new(-(index as f32) * SPACING, forward)]);
+                        remaining = 0;
+                    } else {
+                        let lateral = index as f32 * SPACING;
+                        rows.push(vec![
+                            Vec2::new(-lateral, forward),
+                            Vec2::new(lateral, forward),
+                        ]);
+                        remaining -= 2;
+                    }
+                    index += 1;
+                }
+                rows
+            }
+            Self::Box => {
+                let columns = (size as f32).sqrt().ceil() as usize;
+                (0..size)
+                    .step_by(columns.max(1))
+                    .enumerate()
+                    .map(|(index, start)| row(columns.min(size - start), -(index as f32) * SPACING))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Returns a row of `size` slots centered around 0 laterally.
+fn row(size: usize, forward: f32) -> Vec<Vec2> {
+    let offset = 0.5 * (size as f32 - 1.);
+    (0..size)
+        .map(|i| Vec2::new((i as f32 - offset) * SPACING, forward))
+        .collect()
+}
+
+/// Destination of a single unit of a group.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FormationSlot {
+    position: Vec2,
+    speed: f32,
+}
+
+impl FormationSlot {
+    /// Flat position of the destination.
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// Speed of the unit as a fraction of its maximum speed.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+}
+
+fn compare(a: f32, b: f32) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows() {
+        assert_eq!(
+            Formation::Line.rows(3),
+            vec![vec![
+                Vec2::new(-SPACING, 0.),
+                Vec2::ZERO,
+                Vec2::new(SPACING, 0.)
+            ]]
+        );
+
+        let wedge = Formation::Wedge.rows(4);
+        assert_eq!(wedge.len(), 3);
+        assert_eq!(wedge[0], vec![Vec2::ZERO]);
+        assert_eq!(
+            wedge[1],
+            vec![Vec2::new(-SPACING, -SPACING), Vec2::new(SPACING, -SPACING)]
+        );
+        assert_eq!(wedge[2], vec![Vec2::new(-2. * SPACING, -2. * SPACING)]);
+
+        let rows = Formation::Box.rows(7);
+        assert_eq!(
+            rows.iter().map(|row| row.len()).collect::<Vec<_>>(),
+            [3, 3, 1]
+        );
+        assert!(Formation::Box.rows(0).is_empty());
+    }
+
+    #[test]
+    fn test_plan() {
+        assert!(Formation::Box.plan(&[], Vec2::ZERO).is_empty());
+
+        let single = Formation::Wedge.plan(&[Vec2::new(10., 10.)], Vec2::new(20., 10.));
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].position(), Vec2::new(20., 10.));
+        assert_eq!(single[0].speed(), 1.);
+
+        // Moving in +x direction, the line is perpendicular to it. Both units
+        // keep their sides.
+        let positions = [Vec2::new(0., -1.), Vec2::new(0., 1.)];
+        let slots = Formation::Line.plan(&positions, Vec2::new(100., 0.));
+        assert!((slots[0].position() - Vec2::new(100., -0.5 * SPACING)).length() < 1e-4);
+        assert!((slots[1].position() - Vec2::new(100., 0.5 * SPACING)).length() < 1e-4);
+
+        // The closer unit is slowed down to arrive together with the other.
+        let positions = [Vec2::new(0., 0.), Vec2::new(50., 0.)];
+        let slots = Formation::Line.plan(&positions, Vec2::new(100., 0.));
+        assert_eq!(slots[0].speed(), 1.);
+        assert!(slots[1].speed() < 0.6);
+        assert!(slots[1].speed() >= MIN_SPEED);
+    }
+}
@@ -3,13 +3,23 @@
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
 use chase::ChasePlugin;
 pub use chase::{ChaseSet, ChaseTarget, ChaseTargetEvent};
+pub use formation::{Formation, FormationSlot};
+use orders::OrdersPlugin;
+pub use orders::{
+    EnqueueCommandEvent, ExecuteMovementEvent, MovementCommand, OrderQueue, OrdersSet,
+    ScheduledMovement,
+};
 
 mod chase;
+mod formation;
+mod orders;
 
 pub struct BehaviourPluginGroup;
 
 impl PluginGroup for BehaviourPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(ChasePlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(ChasePlugin)
+            .add(OrdersPlugin)
     }
 }
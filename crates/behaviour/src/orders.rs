@@ -0,0 +1,501 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, gamestate::GameState, objects::MovableSolid, projection::ToFlat};
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPath};
+
+/// Tag of encoded movement commands. It is distinct from tags of other
+/// commands scheduled for multiplayer simulation (e.g. construction commands).
+const MOVE_TAG: u8 = 16;
+const PATROL_FLAG: u8 = 1;
+const QUEUED_FLAG: u8 = 2;
+const SPEED_FLAG: u8 = 4;
+
+pub(crate) struct OrdersPlugin;
+
+impl Plugin for OrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnqueueCommandEvent>()
+            .add_event::<ExecuteMovementEvent>()
+            .add_system(
+                forward
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(resource_exists::<ScheduledMovement>()))
+                    .before(OrdersSet::Execute),
+            )
+            .add_system(
+                execute
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(OrdersSet::Execute),
+            )
+            .add_system(
+                advance
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum OrdersSet {
+    Execute,
+}
+
+/// While this resource exists, [`EnqueueCommandEvent`]s are not executed
+/// right away. Whoever inserted the resource is responsible for scheduling of
+/// the corresponding [`MovementCommand`]s and for their execution via
+/// [`ExecuteMovementEvent`].
+///
+/// This is used to execute the commands in a particular simulation tick of a
+/// multiplayer game.
+#[derive(Resource)]
+pub struct ScheduledMovement;
+
+/// A command to move a unit through a sequence of waypoints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MovementCommand {
+    entity: Entity,
+    waypoints: Vec<Vec2>,
+    patrol: bool,
+    queued: bool,
+    speed: f32,
+}
+
+impl MovementCommand {
+    /// Maximum number of waypoints of a single command.
+    pub const MAX_WAYPOINTS: usize = 32;
+    /// Maximum length of an encoded command in bytes.
+    pub const MAX_ENCODED_LEN: usize = 15 + 8 * Self::MAX_WAYPOINTS;
+
+    /// Creates a command which replaces all current orders of the unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - the commanded unit.
+    ///
+    /// * `waypoints` - flat positions the unit visits in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `waypoints` is empty or if it has more than
+    /// [`Self::MAX_WAYPOINTS`] items.
+    pub fn new(entity: Entity, waypoints: Vec<Vec2>) -> Self {
+        assert!(!waypoints.is_empty());
+        assert!(waypoints.len() <= Self::MAX_WAYPOINTS);
+
+        Self {
+            entity,
+            waypoints,
+            patrol: false,
+            queued: false,
+            speed: 1.,
+        }
+    }
+
+    /// The unit patrols: it loops through the waypoints and the position
+    /// where the patrol started indefinitely.
+    pub fn with_patrol(mut self) -> Self {
+        self.patrol = true;
+        self
+    }
+
+    /// The waypoints are appended to current orders of the unit instead of
+    /// replacing them.
+    pub fn with_queue(mut self) -> Self {
+        self.queued = true;
+        self
+    }
+
+    /// Limits speed of the unit while it follows the waypoints to a fraction
+    /// of its maximum speed. This is used to keep groups of units together.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `speed` is not a number in the interval (0, 1].
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        assert!(speed > 0. && speed <= 1.);
+        self.speed = speed;
+        self
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn waypoints(&self) -> &[Vec2] {
+        self.waypoints.as_slice()
+    }
+
+    pub fn patrol(&self) -> bool {
+        self.patrol
+    }
+
+    pub fn queued(&self) -> bool {
+        self.queued
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Appends self-delimiting binary representation of the command to
+    /// `buf`.
+    ///
+    /// Units are encoded as local entities, thus the encoded commands are
+    /// meaningful only on the computer which encoded them.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(MOVE_TAG);
+        buf.extend_from_slice(&self.entity.to_bits().to_le_bytes());
+
+        let mut flags = 0;
+        if self.patrol {
+            flags |= PATROL_FLAG;
+        }
+        if self.queued {
+            flags |= QUEUED_FLAG;
+        }
+        if self.speed < 1. {
+            flags |= SPEED_FLAG;
+        }
+        buf.push(flags);
+        if self.speed < 1. {
+            buf.extend_from_slice(&self.speed.to_le_bytes());
+        }
+
+        buf.push(self.waypoints.len() as u8);
+        for waypoint in &self.waypoints {
+            buf.extend_from_slice(&waypoint.x.to_le_bytes());
+            buf.extend_from_slice(&waypoint.y.to_le_bytes());
+        }
+    }
+
+    /// Decodes a command from the beginning of `bytes`. It returns the
+    /// command and the number of consumed bytes or None if the bytes do not
+    /// start with a valid command.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        let (&tag, rest) = bytes.split_first()?;
+        if tag != MOVE_TAG {
+            return None;
+        }
+
+        let entity = Entity::from_bits(u64::from_le_bytes(rest.get(..8)?.try_into().unwrap()));
+        let flags = *rest.get(8)?;
+        if flags & !(PATROL_FLAG | QUEUED_FLAG | SPEED_FLAG) != 0 {
+            return None;
+        }
+
+        let (speed, rest) = if flags & SPEED_FLAG != 0 {
+            let speed = f32::from_le_bytes(rest.get(9..13)?.try_into().unwrap());
+            if !(speed > 0. && speed < 1.) {
+                return None;
+            }
+            (speed, &rest[4..])
+        } else {
+            (1., rest)
+        };
+
+        let count = usize::from(*rest.get(9)?);
+        if count == 0 || count > Self::MAX_WAYPOINTS {
+            return None;
+        }
+        let waypoints = rest
+            .get(10..10 + 8 * count)?
+            .chunks_exact(8)
+            .map(|chunk| {
+                Vec2::new(
+                    f32::from_le_bytes(chunk[..4].try_into().unwrap()),
+                    f32::from_le_bytes(chunk[4..].try_into().unwrap()),
+                )
+            })
+            .collect();
+
+        Some((
+            Self {
+                entity,
+                waypoints,
+                patrol: flags & PATROL_FLAG != 0,
+                queued: flags & QUEUED_FLAG != 0,
+                speed,
+            },
+            bytes.len() - rest.len() + 10 + 8 * count,
+        ))
+    }
+}
+
+/// Send this event to command a unit to move through a sequence of
+/// waypoints. See [`MovementCommand`].
+pub struct EnqueueCommandEvent(MovementCommand);
+
+impl EnqueueCommandEvent {
+    pub fn new(command: MovementCommand) -> Self {
+        Self(command)
+    }
+
+    pub fn command(&self) -> &MovementCommand {
+        &self.0
+    }
+}
+
+/// Send this event to execute a [`MovementCommand`], see
+/// [`ScheduledMovement`].
+pub struct ExecuteMovementEvent(MovementCommand);
+
+impl ExecuteMovementEvent {
+    pub fn new(command: MovementCommand) -> Self {
+        Self(command)
+    }
+
+    fn command(&self) -> &MovementCommand {
+        &self.0
+    }
+}
+
+/// Remaining waypoints of a unit. The first waypoint is the one the unit is
+/// currently moving to.
+#[derive(Component, Default)]
+pub struct OrderQueue {
+    waypoints: VecDeque<Waypoint>,
+    /// Number of trailing waypoints which form a patrol loop.
+    looped: usize,
+}
+
+impl OrderQueue {
+    pub fn waypoints(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.waypoints.iter().map(|waypoint| waypoint.position)
+    }
+
+    /// Returns true if the unit patrols (or is going to patrol once it
+    /// finishes its other orders).
+    pub fn patrol(&self) -> bool {
+        self.looped > 0
+    }
+
+    /// Appends waypoints of a command to the queue. The start of a patrol is
+    /// the last waypoint in the queue or `position` if the queue is empty.
+    ///
+    /// An existing patrol loop is finished once more before the appended
+    /// waypoints are followed.
+    fn extend(&mut self, position: Vec2, command: &MovementCommand) {
+        let start = self
+            .waypoints
+            .back()
+            .map_or(position, |waypoint| waypoint.position);
+        let speed = command.speed();
+        self.waypoints.extend(
+            command
+                .waypoints()
+                .iter()
+                .map(|&position| Waypoint { position, speed }),
+        );
+        self.looped = 0;
+
+        if command.patrol() {
+            self.waypoints.push_back(Waypoint {
+                position: start,
+                speed,
+            });
+            self.looped = command.waypoints().len() + 1;
+        }
+    }
+
+    /// Moves on to the next waypoint and returns it. None is returned once
+    /// the queue is exhausted.
+    fn advance(&mut self) -> Option<Waypoint> {
+        let looping = self.waypoints.len() <= self.looped;
+        let reached = self.waypoints.pop_front()?;
+        if looping {
+            self.waypoints.push_back(reached);
+        }
+        self.waypoints.front().copied()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Waypoint {
+    position: Vec2,
+    /// Fraction of the maximum speed of the unit.
+    speed: f32,
+}
+
+impl Waypoint {
+    fn path_target(&self) -> PathTarget {
+        PathTarget::new(self.position, PathQueryProps::exact(), false).with_speed(self.speed)
+    }
+}
+
+fn forward(
+    mut events: EventReader<EnqueueCommandEvent>,
+    mut out_events: EventWriter<ExecuteMovementEvent>,
+) {
+    for event in events.iter() {
+        out_events.send(ExecuteMovementEvent::new(event.command().clone()));
+    }
+}
+
+fn execute(
+    mut commands: Commands,
+    mut events: EventReader<ExecuteMovementEvent>,
+    mut units: Query<(&Transform, Option<&mut OrderQueue>), With<MovableSolid>>,
+    mut path_events: EventWriter<UpdateEntityPath>,
+) {
+    for event in events.iter() {
+        let command = event.command();
+        let Ok((transform, queue)) = units.get_mut(command.entity()) else {
+            continue;
+        };
+        let position = transform.translation.to_flat();
+
+        match queue {
+            Some(mut queue) if command.queued() => queue.extend(position, command),
+            _ => {
+                let mut queue = OrderQueue::default();
+                queue.extend(position, command);
+                path_events.send(UpdateEntityPath::new(
+                    command.entity(),
+                    queue.waypoints[0].path_target(),
+                ));
+                commands.entity(command.entity()).insert(queue);
+            }
+        }
+    }
+}
+
+/// Sends units with queued orders to their next waypoint once they reach
+/// (or fail to reach) the current one.
+fn advance(
+    mut commands: Commands,
+    mut removed: RemovedComponents<PathTarget>,
+    mut units: Query<&mut OrderQueue, Without<PathTarget>>,
+    mut path_events: EventWriter<UpdateEntityPath>,
+) {
+    for entity in removed.iter() {
+        let Ok(mut queue) = units.get_mut(entity) else {
+            continue;
+        };
+
+        match queue.advance() {
+            Some(waypoint) => {
+                path_events.send(UpdateEntityPath::new(entity, waypoint.path_target()));
+            }
+            None => {
+                commands.entity(entity).remove::<OrderQueue>();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        let commands = [
+            MovementCommand::new(Entity::from_raw(7), vec![Vec2::new(1., -2.)]),
+            MovementCommand::new(
+                Entity::from_raw(1234),
+                vec![Vec2::new(10., 20.), Vec2::new(-12.5, 300.)],
+            )
+            .with_patrol()
+            .with_queue(),
+            MovementCommand::new(Entity::from_raw(9), vec![Vec2::new(3., 4.)]).with_speed(0.5),
+            MovementCommand::new(
+                Entity::from_raw(3),
+                vec![Vec2::ZERO; MovementCommand::MAX_WAYPOINTS],
+            )
+            .with_queue(),
+        ];
+
+        let mut buf = Vec::new();
+        for command in commands.iter() {
+            let len = buf.len();
+            command.encode(&mut buf);
+            assert!(buf.len() - len <= MovementCommand::MAX_ENCODED_LEN);
+        }
+
+        let mut bytes = buf.as_slice();
+        for command in commands.iter() {
+            let (decoded, len) = MovementCommand::decode(bytes).unwrap();
+            assert_eq!(&decoded, command);
+            bytes = &bytes[len..];
+        }
+        assert!(bytes.is_empty());
+
+        assert!(MovementCommand::decode(&buf[..18]).is_none());
+        assert!(MovementCommand::decode(&buf[1..30]).is_none());
+        assert!(MovementCommand::decode(&[16, 0, 0, 0, 0, 0, 0, 0, 0, 4, 1, 0]).is_none());
+        assert!(MovementCommand::decode(&[16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]).is_none());
+        assert!(MovementCommand::decode(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]).is_none());
+        assert!(MovementCommand::decode(&[16, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0]).is_none());
+        assert!(MovementCommand::decode(&[]).is_none());
+    }
+
+    #[test]
+    fn test_queue() {
+        let entity = Entity::from_raw(1);
+        let mut queue = OrderQueue::default();
+        queue.extend(
+            Vec2::ZERO,
+            &MovementCommand::new(entity, vec![Vec2::new(1., 0.), Vec2::new(2., 0.)]),
+        );
+        queue.extend(
+            Vec2::ZERO,
+            &MovementCommand::new(entity, vec![Vec2::new(3., 0.)]).with_patrol(),
+        );
+        assert!(queue.patrol());
+        assert_eq!(
+            queue.waypoints().collect::<Vec<_>>(),
+            [
+                Vec2::new(1., 0.),
+                Vec2::new(2., 0.),
+                Vec2::new(3., 0.),
+                Vec2::new(2., 0.)
+            ]
+        );
+
+        assert_eq!(
+            queue.advance().map(|waypoint| waypoint.position),
+            Some(Vec2::new(2., 0.))
+        );
+        assert_eq!(
+            queue.advance().map(|waypoint| waypoint.position),
+            Some(Vec2::new(3., 0.))
+        );
+        // The patrol alternates between its endpoints.
+        for _ in 0..3 {
+            assert_eq!(
+                queue.advance().map(|waypoint| waypoint.position),
+                Some(Vec2::new(2., 0.))
+            );
+            assert_eq!(
+                queue.advance().map(|waypoint| waypoint.position),
+                Some(Vec2::new(3., 0.))
+            );
+        }
+
+        // Queueing after a patrol finishes the loop.
+        queue.extend(
+            Vec2::ZERO,
+            &MovementCommand::new(entity, vec![Vec2::new(4., 0.)]),
+        );
+        assert!(!queue.patrol());
+        assert_eq!(
+            queue.advance().map(|waypoint| waypoint.position),
+            Some(Vec2::new(2., 0.))
+        );
+        assert_eq!(
+            queue.advance().map(|waypoint| waypoint.position),
+            Some(Vec2::new(4., 0.))
+        );
+        assert_eq!(queue.advance().map(|waypoint| waypoint.position), None);
+
+        let mut queue = OrderQueue::default();
+        queue.extend(
+            Vec2::new(5., 5.),
+            &MovementCommand::new(entity, vec![Vec2::new(1., 0.)]),
+        );
+        assert_eq!(queue.advance().map(|waypoint| waypoint.position), None);
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use de_core::{
+    alert::UnderAttackEvent, baseset::GameSet, diplomacy::Diplomacy, gamestate::GameState,
+    gconfig::GameConfig, state::AppState,
+};
+
+use crate::{CameraSet, MoveFocusEvent};
+
+pub(crate) struct AlertsPlugin;
+
+impl Plugin for AlertsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<JumpToAlertEvent>()
+            .add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                record
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                jump.in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<JumpToAlertEvent>())
+                    .in_set(CameraSet::AlertEvent),
+            );
+    }
+}
+
+/// Send this event to move the camera to the location of the most recent
+/// attack on an object of the local player or one of their allies.
+pub struct JumpToAlertEvent;
+
+/// Location of the most recent attack on a local or allied object.
+#[derive(Default, Resource)]
+struct LastAlert(Option<Vec2>);
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<LastAlert>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<LastAlert>();
+}
+
+fn record(
+    config: Res<GameConfig>,
+    diplomacy: Res<Diplomacy>,
+    mut last: ResMut<LastAlert>,
+    mut events: EventReader<UnderAttackEvent>,
+) {
+    let local = config.locals().playable();
+    if let Some(event) = events
+        .iter()
+        .filter(|event| diplomacy.are_allies(local, event.player()))
+        .last()
+    {
+        last.0 = Some(event.position());
+    }
+}
+
+fn jump(last: Res<LastAlert>, mut focus_events: EventWriter<MoveFocusEvent>) {
+    if let Some(position) = last.0 {
+        focus_events.send(MoveFocusEvent::new(position));
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+use de_conf::CAMERA_BOOKMARKS;
+use de_core::{baseset::GameSet, gamestate::GameState, projection::ToFlat, state::AppState};
+use de_uom::{Metre, Radian};
+
+use crate::{
+    camera::{CameraFocus, DesiredAzimuth, DesiredDistance, DesiredOffNadir},
+    CameraSet, MoveFocusEvent,
+};
+
+pub(crate) struct BookmarksPlugin;
+
+impl Plugin for BookmarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveBookmarkEvent>()
+            .add_event::<RecallBookmarkEvent>()
+            .add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                save.in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CameraSet::BookmarkEvent),
+            )
+            .add_system(
+                recall
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CameraSet::BookmarkEvent)
+                    .after(save),
+            );
+    }
+}
+
+/// Send this event to store current camera location under a bookmark number.
+pub struct SaveBookmarkEvent(usize);
+
+impl SaveBookmarkEvent {
+    /// # Panics
+    ///
+    /// Panics if the bookmark number is not smaller than
+    /// [`CAMERA_BOOKMARKS`].
+    pub fn new(bookmark: usize) -> Self {
+        assert!(bookmark < CAMERA_BOOKMARKS);
+        Self(bookmark)
+    }
+
+    fn bookmark(&self) -> usize {
+        self.0
+    }
+}
+
+/// Send this event to move the camera to a previously stored location.
+/// Nothing happens if no location is stored under the bookmark number.
+pub struct RecallBookmarkEvent(usize);
+
+impl RecallBookmarkEvent {
+    /// # Panics
+    ///
+    /// Panics if the bookmark number is not smaller than
+    /// [`CAMERA_BOOKMARKS`].
+    pub fn new(bookmark: usize) -> Self {
+        assert!(bookmark < CAMERA_BOOKMARKS);
+        Self(bookmark)
+    }
+
+    fn bookmark(&self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Default, Resource)]
+struct Bookmarks([Option<Bookmark>; CAMERA_BOOKMARKS]);
+
+#[derive(Clone, Copy)]
+struct Bookmark {
+    focus: Vec2,
+    distance: Metre,
+    off_nadir: Radian,
+    azimuth: Radian,
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Bookmarks>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Bookmarks>();
+}
+
+fn save(
+    mut events: EventReader<SaveBookmarkEvent>,
+    mut bookmarks: ResMut<Bookmarks>,
+    focus: Res<CameraFocus>,
+    distance: Res<DesiredDistance>,
+    off_nadir: Res<DesiredOffNadir>,
+    azimuth: Res<DesiredAzimuth>,
+) {
+    for event in events.iter() {
+        bookmarks.0[event.bookmark()] = Some(Bookmark {
+            focus: focus.point().to_flat(),
+            distance: distance.distance(),
+            off_nadir: off_nadir.off_nadir(),
+            azimuth: azimuth.azimuth(),
+        });
+    }
+}
+
+fn recall(
+    mut events: EventReader<RecallBookmarkEvent>,
+    bookmarks: Res<Bookmarks>,
+    mut distance: ResMut<DesiredDistance>,
+    mut off_nadir: ResMut<DesiredOffNadir>,
+    mut azimuth: ResMut<DesiredAzimuth>,
+    mut focus_events: EventWriter<MoveFocusEvent>,
+) {
+    let Some(bookmark) = events
+        .iter()
+        .last()
+        .and_then(|event| bookmarks.0[event.bookmark()])
+    else {
+        return;
+    };
+
+    distance.set(bookmark.distance);
+    off_nadir.set_clamped(bookmark.off_nadir);
+    azimuth.set(bookmark.azimuth);
+    focus_events.send(MoveFocusEvent::new(bookmark.focus));
+}
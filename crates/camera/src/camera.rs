@@ -1,6 +1,6 @@
 use std::f32::consts::FRAC_PI_2;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 use de_conf::{CameraConf, Configuration};
 use de_core::{
     baseset::GameSet, cleanup::DespawnOnGameExit, events::ResendEventPlugin, gamestate::GameState,
@@ -11,8 +11,6 @@ use de_terrain::{TerrainCollider, MAX_ELEVATION};
 use de_uom::{InverseSecond, Metre, Quantity, Radian, Second};
 use parry3d::{math::Vector, query::Ray};
 
-/// Camera moves horizontally at speed `distance * CAMERA_HORIZONTAL_SPEED`.
-const CAMERA_HORIZONTAL_SPEED: InverseSecond = Quantity::new_unchecked(2.0);
 /// Minimum camera distance multiplied by this gives minimum temporary distance
 /// from terrain. Forward/backward camera motion is smooth within this range.
 /// Step adjustment is applied outside of this range.
@@ -42,6 +40,7 @@ impl Plugin for CameraPlugin {
             .add_event::<ZoomCameraEvent>()
             .add_event::<RotateCameraEvent>()
             .add_event::<TiltCameraEvent>()
+            .add_event::<FollowEntityEvent>()
             .add_plugin(ResendEventPlugin::<MoveFocusEvent>::default())
             .add_event::<FocusInvalidatedEvent>()
             .add_event::<UpdateTranslationEvent>()
@@ -60,6 +59,19 @@ impl Plugin for CameraPlugin {
                     .run_if(in_state(GameState::Playing))
                     .in_set(CameraSet::MoveHorizontallEvent),
             )
+            .add_system(
+                edge_scrolling
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CameraSet::MoveHorizontallEvent),
+            )
+            .add_system(
+                handle_follow_events
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CameraSet::FollowEvent)
+                    .after(CameraSet::MoveHorizontallEvent),
+            )
             .add_system(
                 handle_zoom_events
                     .in_base_set(GameSet::Input)
@@ -114,7 +126,14 @@ impl Plugin for CameraPlugin {
                     // Zooming changes camera focus point so do it
                     // after other types of camera movement.
                     .after(InternalCameraSet::Zoom)
-                    .after(InternalCameraSet::Pivot),
+                    .after(InternalCameraSet::Pivot)
+                    .in_set(InternalCameraSet::MoveHorizontally),
+            )
+            .add_system(
+                follow
+                    .in_base_set(GameSet::Movement)
+                    .run_if(in_state(GameState::Playing))
+                    .after(InternalCameraSet::MoveHorizontally),
             );
     }
 }
@@ -125,6 +144,9 @@ pub enum CameraSet {
     RotateEvent,
     TiltEvent,
     ZoomEvent,
+    FollowEvent,
+    BookmarkEvent,
+    AlertEvent,
 }
 
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
@@ -133,6 +155,7 @@ enum InternalCameraSet {
     Zoom,
     Pivot,
     MoveFocus,
+    MoveHorizontally,
 }
 
 pub struct MoveFocusEvent {
@@ -200,7 +223,10 @@ impl TiltCameraEvent {
 }
 
 /// Send this event to zoom the camera.
-pub struct ZoomCameraEvent(f32);
+pub struct ZoomCameraEvent {
+    factor: f32,
+    anchor: Option<Vec3>,
+}
 
 impl ZoomCameraEvent {
     /// # Arguments
@@ -208,22 +234,59 @@ impl ZoomCameraEvent {
     /// * `factor` - desired camera to terrain distance will be multiplied with
     ///   this factor.
     pub fn new(factor: f32) -> Self {
-        Self(factor)
+        Self {
+            factor,
+            anchor: None,
+        }
+    }
+
+    /// Zoom towards (or away from) a point in the world space while keeping
+    /// the point at the same position on the screen. The camera is zoomed
+    /// towards the center of the screen by default.
+    pub fn with_anchor(mut self, anchor: Vec3) -> Self {
+        self.anchor = Some(anchor);
+        self
     }
 
     fn factor(&self) -> f32 {
+        self.factor
+    }
+
+    fn anchor(&self) -> Option<Vec3> {
+        self.anchor
+    }
+}
+
+/// Send this event to make the camera follow an entity or to stop following.
+///
+/// Following stops once the entity is despawned or when the camera is moved
+/// by other means.
+pub struct FollowEntityEvent(Option<Entity>);
+
+impl FollowEntityEvent {
+    /// Starts following of an entity. Following stops instead if the entity
+    /// is already followed.
+    pub fn new(entity: Entity) -> Self {
+        Self(Some(entity))
+    }
+
+    pub fn stop() -> Self {
+        Self(None)
+    }
+
+    fn entity(&self) -> Option<Entity> {
         self.0
     }
 }
 
 #[derive(Resource)]
-struct CameraFocus {
+pub(crate) struct CameraFocus {
     point: Vec3,
     distance: Metre,
 }
 
 impl CameraFocus {
-    fn point(&self) -> Vec3 {
+    pub(crate) fn point(&self) -> Vec3 {
         self.point
     }
 
@@ -252,58 +315,104 @@ struct UpdateTranslationEvent;
 
 #[derive(Default, Resource)]
 struct HorizontalMovement {
-    movement: Vec2,
+    /// Movement requested via [`MoveCameraHorizontallyEvent`].
+    requested: Vec2,
+    /// Movement caused by mouse cursor close to a window edge.
+    edge: Vec2,
 }
 
 impl HorizontalMovement {
     fn movement(&self) -> Vec2 {
-        self.movement
+        (self.requested + self.edge).clamp(Vec2::NEG_ONE, Vec2::ONE)
+    }
+
+    fn set_requested(&mut self, movement: Vec2) {
+        self.requested = movement;
     }
 
-    fn set(&mut self, movement: Vec2) {
-        self.movement = movement;
+    fn set_edge(&mut self, movement: Vec2) {
+        self.edge = movement;
     }
 }
 
 #[derive(Resource)]
-struct DesiredDistance(Metre);
+pub(crate) struct DesiredDistance {
+    distance: Metre,
+    /// Point kept at a fixed position on the screen during zooming.
+    anchor: Option<Vec3>,
+}
 
 impl DesiredDistance {
-    fn distance(&self) -> Metre {
-        self.0
+    fn new(distance: Metre) -> Self {
+        Self {
+            distance,
+            anchor: None,
+        }
+    }
+
+    pub(crate) fn distance(&self) -> Metre {
+        self.distance
+    }
+
+    /// Sets the desired distance without an anchor. The distance is expected
+    /// to be within configured limits.
+    pub(crate) fn set(&mut self, distance: Metre) {
+        self.distance = distance;
+        self.anchor = None;
+    }
+
+    fn anchor(&self) -> Option<Vec3> {
+        self.anchor
     }
 
-    fn zoom_clamped(&mut self, conf: &CameraConf, factor: f32) {
-        self.0 = (if conf.scroll_inverted() {
-            self.0 / factor
+    fn zoom_clamped(&mut self, conf: &CameraConf, factor: f32, anchor: Option<Vec3>) {
+        self.distance = (if conf.scroll_inverted() {
+            self.distance / factor
         } else {
-            self.0 * factor
+            self.distance * factor
         })
-        .clamp(conf.min_distance(), conf.max_distance())
+        .clamp(conf.min_distance(), conf.max_distance());
+        self.anchor = anchor;
+    }
+
+    fn clear_anchor(&mut self) {
+        self.anchor = None;
     }
 }
 
+/// Entity followed by the camera.
+#[derive(Default, Resource)]
+struct Followed(Option<Entity>);
+
 #[derive(Resource)]
-struct DesiredOffNadir(Radian);
+pub(crate) struct DesiredOffNadir(Radian);
 
 impl DesiredOffNadir {
-    fn off_nadir(&self) -> Radian {
+    pub(crate) fn off_nadir(&self) -> Radian {
         self.0
     }
 
+    pub(crate) fn set_clamped(&mut self, off_nadir: Radian) {
+        self.0 = off_nadir.clamp(MIN_OFF_NADIR, MAX_OFF_NADIR);
+    }
+
     fn tilt_clamped(&mut self, delta: Radian) {
         self.0 = (self.0 + delta).clamp(MIN_OFF_NADIR, MAX_OFF_NADIR);
     }
 }
 
 #[derive(Resource)]
-struct DesiredAzimuth(Radian);
+pub(crate) struct DesiredAzimuth(Radian);
 
 impl DesiredAzimuth {
-    fn azimuth(&self) -> Radian {
+    pub(crate) fn azimuth(&self) -> Radian {
         self.0
     }
 
+    pub(crate) fn set(&mut self, azimuth: Radian) {
+        self.0 = azimuth.normalized();
+    }
+
     fn rotate(&mut self, delta: Radian) {
         self.0 = (self.0 + delta).normalized();
     }
@@ -314,7 +423,8 @@ fn setup(mut commands: Commands, conf: Res<Configuration>) {
     let distance = 0.6 * conf.min_distance() + 0.4 * conf.max_distance();
 
     commands.insert_resource(HorizontalMovement::default());
-    commands.insert_resource(DesiredDistance(distance));
+    commands.insert_resource(DesiredDistance::new(distance));
+    commands.insert_resource(Followed::default());
     commands.insert_resource(DesiredOffNadir(Radian::ZERO));
     commands.insert_resource(DesiredAzimuth(Radian::ZERO));
     commands.insert_resource(CameraFocus {
@@ -337,6 +447,7 @@ fn cleanup(mut commands: Commands) {
     commands.remove_resource::<DesiredOffNadir>();
     commands.remove_resource::<DesiredAzimuth>();
     commands.remove_resource::<CameraFocus>();
+    commands.remove_resource::<Followed>();
 }
 
 fn update_focus(
@@ -362,6 +473,7 @@ fn update_focus(
 fn process_move_focus_events(
     mut in_events: EventReader<MoveFocusEvent>,
     mut focus: ResMut<CameraFocus>,
+    mut followed: ResMut<Followed>,
     terrain: TerrainCollider,
     mut out_events: EventWriter<UpdateTranslationEvent>,
 ) {
@@ -369,6 +481,7 @@ fn process_move_focus_events(
         Some(event) => event,
         None => return,
     };
+    followed.0 = None;
 
     let origin = event.point().to_altitude(MAX_ELEVATION);
     let ray = Ray::new(origin.into(), Vector::new(0., -1., 0.));
@@ -406,7 +519,7 @@ fn move_horizontaly(
         .distance()
         .clamp(conf.min_distance(), conf.max_distance());
     let time_delta = Second::try_from(time.delta().as_secs_f32()).unwrap();
-    let delta_scalar: f32 = (time_delta * CAMERA_HORIZONTAL_SPEED * distance_factor).into();
+    let delta_scalar: f32 = (time_delta * conf.scroll_speed() * distance_factor).into();
     let delta_vec = (transform.rotation * direction.extend(0.)) * delta_scalar;
 
     let margin = Vec3::new(MAP_FOCUS_MARGIN.into(), 0., MAP_FOCUS_MARGIN.into());
@@ -418,12 +531,43 @@ fn move_horizontaly(
     event.send(FocusInvalidatedEvent);
 }
 
+fn follow(
+    conf: Res<Configuration>,
+    time: Res<Time>,
+    mut followed: ResMut<Followed>,
+    focus: Res<CameraFocus>,
+    targets: Query<&GlobalTransform>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+    mut event: EventWriter<FocusInvalidatedEvent>,
+) {
+    let Some(entity) = followed.0 else {
+        return;
+    };
+    let Ok(target) = targets.get(entity) else {
+        followed.0 = None;
+        return;
+    };
+
+    let time_delta = Second::try_from(time.delta().as_secs_f32()).unwrap();
+    // Exponential smoothing makes the movement independent of frame rate.
+    let progress = 1. - (-(time_delta * conf.camera().follow_rate())).exp();
+    let delta_vec = (target.translation().to_msl() - focus.point().to_msl()) * progress;
+    if delta_vec == Vec3::ZERO {
+        return;
+    }
+
+    camera_query.single_mut().translation += delta_vec;
+    event.send(FocusInvalidatedEvent);
+}
+
 fn zoom(
     conf: Res<Configuration>,
-    desired_distance: Res<DesiredDistance>,
+    mut desired_distance: ResMut<DesiredDistance>,
     time: Res<Time>,
+    map_bounds: Res<MapBounds>,
     mut focus: ResMut<CameraFocus>,
     mut camera_query: Query<&mut Transform, With<Camera3d>>,
+    mut event: EventWriter<FocusInvalidatedEvent>,
 ) {
     let conf = conf.camera();
     let hard_min_distance = conf.min_distance() * HARD_MIN_CAMERA_DISTANCE_FACTOR;
@@ -441,6 +585,9 @@ fn zoom(
 
         let error = focus.distance() - desired_distance.distance();
         if error.abs() <= DISTANCE_TOLERATION {
+            if desired_distance.anchor().is_some() {
+                desired_distance.clear_anchor();
+            }
             return;
         }
 
@@ -450,9 +597,35 @@ fn zoom(
     }
 
     let mut transform = camera_query.single_mut();
-    let delta_vec = f32::from(delta_scalar) * transform.forward();
-    transform.translation += delta_vec;
-    focus.update_distance(delta_scalar);
+    match desired_distance.anchor() {
+        Some(anchor) => {
+            // Scaling of camera position around the anchor keeps the anchor
+            // at the same position on the screen.
+            let distance = focus.distance() - delta_scalar;
+            let scale = f32::from(distance) / f32::from(focus.distance());
+            let mut point = anchor + scale * (focus.point() - anchor);
+            let mut translation = anchor + scale * (transform.translation - anchor);
+
+            let margin = Vec3::new(MAP_FOCUS_MARGIN.into(), 0., MAP_FOCUS_MARGIN.into());
+            let map_bounds = map_bounds.aabb().to_msl();
+            let point_msl = point.to_msl();
+            let correction = point_msl.clamp(
+                Vec3::from(map_bounds.mins) + margin,
+                Vec3::from(map_bounds.maxs) - margin,
+            ) - point_msl;
+            point += correction;
+            translation += correction;
+
+            transform.translation = translation;
+            focus.update(point, distance);
+            event.send(FocusInvalidatedEvent);
+        }
+        None => {
+            let delta_vec = f32::from(delta_scalar) * transform.forward();
+            transform.translation += delta_vec;
+            focus.update_distance(delta_scalar);
+        }
+    }
 }
 
 fn pivot(
@@ -476,7 +649,61 @@ fn handle_horizontal_events(
     mut events: EventReader<MoveCameraHorizontallyEvent>,
 ) {
     if let Some(event) = events.iter().last() {
-        movement.set(event.direction());
+        movement.set_requested(event.direction());
+    }
+}
+
+fn edge_scrolling(
+    conf: Res<Configuration>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut movement: ResMut<HorizontalMovement>,
+) {
+    let conf = conf.camera();
+    if !conf.edge_scrolling() {
+        return;
+    }
+
+    // There is no window in headless mode.
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let margin = f32::from(conf.move_margin());
+    let mut edge = Vec2::ZERO;
+    if let Some(cursor) = window.cursor_position() {
+        if cursor.x < margin {
+            edge.x -= 1.;
+        } else if cursor.x > (window.width() - margin) {
+            edge.x += 1.;
+        }
+        if cursor.y < margin {
+            edge.y -= 1.;
+        } else if cursor.y > (window.height() - margin) {
+            edge.y += 1.;
+        }
+    }
+
+    // Avoid unnecessary change detection.
+    if movement.edge != edge {
+        movement.set_edge(edge);
+    }
+}
+
+fn handle_follow_events(
+    movement: Res<HorizontalMovement>,
+    mut events: EventReader<FollowEntityEvent>,
+    mut followed: ResMut<Followed>,
+) {
+    for event in events.iter() {
+        followed.0 = if followed.0 == event.entity() {
+            None
+        } else {
+            event.entity()
+        };
+    }
+
+    // Moving the camera by other means stops following.
+    if followed.0.is_some() && movement.movement() != Vec2::ZERO {
+        followed.0 = None;
     }
 }
 
@@ -486,7 +713,11 @@ fn handle_zoom_events(
     mut desired: ResMut<DesiredDistance>,
 ) {
     for event in events.iter() {
-        desired.zoom_clamped(conf.camera(), event.factor());
+        // Events with unit factor are sent even when no zooming is
+        // requested. They must not change the anchor of an ongoing zoom.
+        if event.factor() != 1. {
+            desired.zoom_clamped(conf.camera(), event.factor(), event.anchor());
+        }
     }
 }
 
@@ -1,12 +1,18 @@
+use alerts::AlertsPlugin;
+pub use alerts::JumpToAlertEvent;
 use bevy::{app::PluginGroupBuilder, prelude::*};
+use bookmarks::BookmarksPlugin;
+pub use bookmarks::{RecallBookmarkEvent, SaveBookmarkEvent};
 use camera::CameraPlugin;
 pub use camera::{
-    CameraSet, MoveCameraHorizontallyEvent, MoveFocusEvent, RotateCameraEvent, TiltCameraEvent,
-    ZoomCameraEvent,
+    CameraSet, FollowEntityEvent, MoveCameraHorizontallyEvent, MoveFocusEvent, RotateCameraEvent,
+    TiltCameraEvent, ZoomCameraEvent,
 };
 use distance::DistancePlugin;
 pub use distance::{CameraDistance, DistanceSet};
 
+mod alerts;
+mod bookmarks;
 mod camera;
 mod distance;
 
@@ -16,6 +22,8 @@ impl PluginGroup for CameraPluginGroup {
     fn build(self) -> PluginGroupBuilder {
         PluginGroupBuilder::start::<Self>()
             .add(CameraPlugin)
+            .add(BookmarksPlugin)
+            .add(AlertsPlugin)
             .add(DistancePlugin)
     }
 }
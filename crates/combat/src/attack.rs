@@ -1,14 +1,28 @@
-use std::{cmp::Ordering, collections::BinaryHeap};
+use std::{cmp::Ordering, collections::BinaryHeap, time::Duration};
 
 use bevy::prelude::*;
-use de_behaviour::{ChaseSet, ChaseTarget, ChaseTargetEvent};
-use de_core::{baseset::GameSet, gamestate::GameState, objects::ObjectType};
-use de_index::SpatialQuery;
+use de_behaviour::{ChaseSet, ChaseTarget, ChaseTargetEvent, OrderQueue};
+use de_core::{
+    baseset::GameSet,
+    diplomacy::Diplomacy,
+    gamestate::GameState,
+    objects::{Flying, ObjectType},
+    player::Player,
+};
 use de_objects::{LaserCannon, SolidObjects};
 use parry3d::query::Ray;
 
-use crate::laser::LaserFireEvent;
-use crate::{sightline::LineOfSight, AttackingSet};
+use crate::{
+    guard::Guarding,
+    history::{PositionHistory, MAX_REWIND},
+    laser::LaserFireEvent,
+    projectile::{self, Motion, ProjectileFireEvent},
+    sightline::{Layer, LineOfSight},
+    stance::{AttackMoving, Stance},
+    stealth::{self, Detection},
+    veterancy::Experience,
+    AttackingSet,
+};
 
 /// Multiple of cannon range. The attacking entities will try to stay as close
 /// or further from attacked targets.
@@ -54,11 +68,34 @@ impl Plugin for AttackPlugin {
 pub struct AttackEvent {
     attacker: Entity,
     enemy: Entity,
+    latency: Duration,
+    automatic: bool,
 }
 
 impl AttackEvent {
     pub fn new(attacker: Entity, enemy: Entity) -> Self {
-        Self { attacker, enemy }
+        Self {
+            attacker,
+            enemy,
+            latency: Duration::ZERO,
+            automatic: false,
+        }
+    }
+
+    /// Sets by how much the commanding player sees the game delayed, e.g.
+    /// due to network latency of a remote player. The attack is evaluated
+    /// against the position of the enemy rewound by the latency (at most by
+    /// 300ms) if [`crate::LagCompensationPlugin`] is enabled.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Marks the attack as automatically started (as opposed to explicitly
+    /// commanded by a player). See [`crate::Stance`].
+    pub(crate) fn automatic(mut self) -> Self {
+        self.automatic = true;
+        self
     }
 
     fn attacker(&self) -> Entity {
@@ -68,24 +105,50 @@ impl AttackEvent {
     fn enemy(&self) -> Entity {
         self.enemy
     }
+
+    fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    fn is_automatic(&self) -> bool {
+        self.automatic
+    }
 }
 
+/// Attack order of an object, see [`AttackEvent`].
 #[derive(Component)]
-struct Attacking {
+pub struct Attacking {
     enemy: Entity,
+    latency: Duration,
     muzzle: Vec3,
     target: Option<Vec3>,
+    /// Estimated velocity of the enemy. It is used for lead prediction of
+    /// projectiles.
+    target_velocity: Vec3,
+    /// True if `target` is the centroid of the enemy at a rewound position.
+    rewound: bool,
+    /// Layer of the enemy. Only objects in this layer block the fire.
+    layer: Layer,
 }
 
 impl Attacking {
-    fn new(enemy: Entity) -> Self {
+    fn new(enemy: Entity, latency: Duration) -> Self {
         Self {
             enemy,
+            latency: latency.min(MAX_REWIND),
             muzzle: Vec3::ZERO,
             target: None,
+            target_velocity: Vec3::ZERO,
+            rewound: false,
+            layer: Layer::Ground,
         }
     }
 
+    /// The attacked entity.
+    pub fn enemy(&self) -> Entity {
+        self.enemy
+    }
+
     fn distance(&self) -> Option<f32> {
         self.target.map(|target| target.distance(self.muzzle))
     }
@@ -98,52 +161,138 @@ impl Attacking {
     }
 }
 
+/// Returns true if a cannon can attack a target.
+///
+/// # Arguments
+///
+/// * `cannon` - cannon of the attacker.
+///
+/// * `player` - owner of the attacker.
+///
+/// * `flying` - whether the target is in the air.
+///
+/// * `detection` - detection state of the target if it is cloaked.
+fn targetable(
+    cannon: &LaserCannon,
+    player: Option<Player>,
+    flying: bool,
+    detection: Option<&Detection>,
+) -> bool {
+    (cannon.anti_air() || !flying)
+        && player.map_or(true, |player| !stealth::hidden_from(detection, player))
+}
+
 fn attack(
     mut commands: Commands,
+    diplomacy: Res<Diplomacy>,
     mut attack_events: EventReader<AttackEvent>,
-    cannons: Query<&LaserCannon>,
+    cannons: Query<(&LaserCannon, Option<&Stance>, Option<&AttackMoving>)>,
+    players: Query<&Player>,
+    targets: Query<(Option<&Flying>, Option<&Detection>)>,
     mut chase_events: EventWriter<ChaseTargetEvent>,
 ) {
     for event in attack_events.iter() {
-        if let Ok(cannon) = cannons.get(event.attacker()) {
-            commands
-                .entity(event.attacker())
-                .insert(Attacking::new(event.enemy()));
-
-            let target = ChaseTarget::new(
-                event.enemy(),
-                MIN_CHASE_DISTNACE * cannon.range(),
-                MAX_CHASE_DISTNACE * cannon.range(),
-            );
-            chase_events.send(ChaseTargetEvent::new(event.attacker(), Some(target)));
+        if let (Ok(&attacker), Ok(&enemy)) =
+            (players.get(event.attacker()), players.get(event.enemy()))
+        {
+            if diplomacy.are_allies(attacker, enemy) {
+                continue;
+            }
+        }
+
+        if let Ok((cannon, stance, attack_moving)) = cannons.get(event.attacker()) {
+            if let Ok((flying, detection)) = targets.get(event.enemy()) {
+                let player = players.get(event.attacker()).ok().copied();
+                if !targetable(cannon, player, flying.is_some(), detection) {
+                    continue;
+                }
+            }
+
+            let mut entity_commands = commands.entity(event.attacker());
+            entity_commands.insert(Attacking::new(event.enemy(), event.latency()));
+            if !event.is_automatic() {
+                entity_commands
+                    .remove::<AttackMoving>()
+                    .remove::<Guarding>()
+                    .remove::<OrderQueue>();
+            }
+
+            let chase = stance.map_or(true, |stance| {
+                stance.chases(
+                    event.is_automatic(),
+                    event.is_automatic() && attack_moving.is_some(),
+                )
+            });
+            let target = chase.then(|| {
+                ChaseTarget::new(
+                    event.enemy(),
+                    MIN_CHASE_DISTNACE * cannon.range(),
+                    MAX_CHASE_DISTNACE * cannon.range(),
+                )
+            });
+            chase_events.send(ChaseTargetEvent::new(event.attacker(), target));
         }
     }
 }
 
+type TargetComponents<'a> = (
+    &'a Transform,
+    &'a ObjectType,
+    Option<&'a PositionHistory>,
+    Option<&'a Motion>,
+    Option<&'a Flying>,
+    Option<&'a Detection>,
+);
+
 fn update_positions(
     mut commands: Commands,
+    time: Res<Time>,
     solids: SolidObjects,
-    mut cannons: Query<(Entity, &Transform, &LaserCannon, &mut Attacking)>,
-    targets: Query<(&Transform, &ObjectType)>,
-    sightline: SpatialQuery<Entity>,
+    mut cannons: Query<(Entity, &Player, &Transform, &LaserCannon, &mut Attacking)>,
+    targets: Query<TargetComponents>,
+    sightline: LineOfSight,
 ) {
-    for (attacker, transform, cannon, mut attacking) in cannons.iter_mut() {
-        match targets.get(attacking.enemy) {
-            Ok((enemy_transform, &target_type)) => {
+    for (attacker, &player, transform, cannon, mut attacking) in cannons.iter_mut() {
+        // The target might have become untargetable, e.g. it is no longer
+        // detected or the cannon changed when units left a garrisoned
+        // building.
+        let target = targets
+            .get(attacking.enemy)
+            .ok()
+            .filter(|&(.., flying, detection)| {
+                targetable(cannon, Some(player), flying.is_some(), detection)
+            });
+        match target {
+            Some((enemy_transform, &target_type, history, motion, flying, _)) => {
+                attacking.layer = Layer::of(flying.is_some());
                 attacking.muzzle = transform.translation + cannon.muzzle();
+                attacking.target_velocity = motion.map_or(Vec3::ZERO, |motion| motion.velocity());
+
+                let rewound = history
+                    .filter(|_| !attacking.latency.is_zero())
+                    .zip(time.elapsed().checked_sub(attacking.latency))
+                    .and_then(|(history, rewound_time)| history.at(rewound_time));
 
                 let enemy_aabb = solids.get(target_type).collider().aabb();
-                let enemy_centroid = enemy_transform.translation + Vec3::from(enemy_aabb.center());
+                let enemy_centroid = rewound.unwrap_or(enemy_transform.translation)
+                    + Vec3::from(enemy_aabb.center());
                 let direction = (enemy_centroid - attacking.muzzle)
                     .try_normalize()
                     .expect("Attacker and target too close together");
                 let cannon_ray = Ray::new(attacking.muzzle.into(), direction.into());
 
-                attacking.target = sightline
-                    .cast_ray(&cannon_ray, cannon.range(), Some(attacker))
-                    .map(|intersection| cannon_ray.point_at(intersection.toi()).into());
+                attacking.rewound = rewound.is_some();
+                attacking.target = if attacking.rewound {
+                    Some(enemy_centroid)
+                } else {
+                    let observation =
+                        sightline.sight(&cannon_ray, cannon.range(), attacker, attacking.layer);
+                    observation
+                        .entity()
+                        .map(|_| cannon_ray.point_at(observation.toi()).into())
+                };
             }
-            Err(_) => {
+            None => {
                 commands.entity(attacker).remove::<Attacking>();
             }
         }
@@ -160,26 +309,60 @@ fn charge(time: Res<Time>, mut cannons: Query<(&mut LaserCannon, Option<&Attacki
 }
 
 fn aim_and_fire(
-    mut attackers: Query<(Entity, &mut LaserCannon, &Attacking)>,
+    mut attackers: Query<(Entity, &mut LaserCannon, &Attacking, Option<&Experience>)>,
     sightline: LineOfSight,
-    mut events: EventWriter<LaserFireEvent>,
+    mut laser_events: EventWriter<LaserFireEvent>,
+    mut projectile_events: EventWriter<ProjectileFireEvent>,
 ) {
     let attackers = attackers.iter_mut();
     // The queue is used so that attacking has the same result as if it was
     // done in real-time (unaffected by update frequency).
     let mut fire_queue = BinaryHeap::new();
 
-    for (attacker, mut cannon, attacking) in attackers {
+    for (attacker, mut cannon, attacking, experience) in attackers {
         let ray = attacking.ray().filter(|ray| {
-            sightline
-                .sight(ray, cannon.range(), attacker)
-                .entity()
-                .map_or(false, |e| e == attacking.enemy)
+            if attacking.rewound {
+                // The rewound enemy must not be obstructed by terrain or
+                // other objects.
+                let distance = attacking.distance().unwrap();
+                let observation = sightline.sight(ray, distance, attacker, attacking.layer);
+                observation
+                    .entity()
+                    .map_or(observation.toi() >= distance, |e| e == attacking.enemy)
+            } else {
+                sightline
+                    .sight(ray, cannon.range(), attacker, attacking.layer)
+                    .entity()
+                    .map_or(false, |e| e == attacking.enemy)
+            }
         });
 
         if let Some(ray) = ray {
             if cannon.charge().charged() {
-                fire_queue.push(FireScheduleItem::new(attacker, ray, cannon.into_inner()));
+                let rewound = if attacking.rewound {
+                    Some((attacking.enemy, attacking.distance().unwrap()))
+                } else {
+                    None
+                };
+                let damage = cannon.damage()
+                    * experience.map_or(1., |experience| experience.rank().damage_multiplier());
+                let launch_velocity = cannon.ballistics().map(|ballistics| {
+                    projectile::aim(
+                        attacking.muzzle,
+                        attacking.target.unwrap(),
+                        attacking.target_velocity,
+                        ballistics.speed(),
+                    )
+                });
+                fire_queue.push(FireScheduleItem::new(
+                    attacker,
+                    ray,
+                    rewound,
+                    attacking.layer,
+                    damage,
+                    launch_velocity,
+                    cannon.into_inner(),
+                ));
             }
         } else {
             cannon.charge_mut().hold();
@@ -187,7 +370,7 @@ fn aim_and_fire(
     }
 
     while let Some(mut fire_schedule_item) = fire_queue.pop() {
-        if fire_schedule_item.fire(&mut events) {
+        if fire_schedule_item.fire(&mut laser_events, &mut projectile_events) {
             fire_queue.push(fire_schedule_item);
         }
     }
@@ -196,25 +379,78 @@ fn aim_and_fire(
 struct FireScheduleItem<'a> {
     attacker: Entity,
     ray: Ray,
+    /// Attacked entity and distance to its rewound centroid if the attack
+    /// is lag compensated.
+    rewound: Option<(Entity, f32)>,
+    layer: Layer,
+    /// Damage inflicted by each fire, i.e. cannon damage adjusted by the
+    /// rank of the attacker.
+    damage: f32,
+    /// Initial velocity of fired projectiles. It is None for hitscan
+    /// cannons.
+    launch_velocity: Option<Vec3>,
     cannon: &'a mut LaserCannon,
 }
 
 impl<'a> FireScheduleItem<'a> {
-    fn new(attacker: Entity, ray: Ray, cannon: &'a mut LaserCannon) -> Self {
+    fn new(
+        attacker: Entity,
+        ray: Ray,
+        rewound: Option<(Entity, f32)>,
+        layer: Layer,
+        damage: f32,
+        launch_velocity: Option<Vec3>,
+        cannon: &'a mut LaserCannon,
+    ) -> Self {
         Self {
             attacker,
             ray,
+            rewound,
+            layer,
+            damage,
+            launch_velocity,
             cannon,
         }
     }
 
-    fn fire(&mut self, events: &mut EventWriter<LaserFireEvent>) -> bool {
-        events.send(LaserFireEvent::new(
-            self.attacker,
-            self.ray,
-            self.cannon.range(),
-            self.cannon.damage(),
-        ));
+    fn fire(
+        &mut self,
+        laser_events: &mut EventWriter<LaserFireEvent>,
+        projectile_events: &mut EventWriter<ProjectileFireEvent>,
+    ) -> bool {
+        if let Some((velocity, ballistics)) = self.launch_velocity.zip(self.cannon.ballistics()) {
+            projectile_events.send(ProjectileFireEvent::new(
+                self.attacker,
+                self.ray.origin.into(),
+                velocity,
+                self.damage,
+                self.cannon.damage_type(),
+                ballistics.blast_radius(),
+                self.layer,
+            ));
+            return self.cannon.charge_mut().fire();
+        }
+
+        let event = match self.rewound {
+            Some((enemy, distance)) => LaserFireEvent::new(
+                self.attacker,
+                self.ray,
+                distance,
+                self.damage,
+                self.cannon.damage_type(),
+                self.layer,
+            )
+            .with_rewound_target(enemy),
+            None => LaserFireEvent::new(
+                self.attacker,
+                self.ray,
+                self.cannon.range(),
+                self.damage,
+                self.cannon.damage_type(),
+                self.layer,
+            ),
+        };
+        laser_events.send(event);
         self.cannon.charge_mut().fire()
     }
 }
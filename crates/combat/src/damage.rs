@@ -0,0 +1,113 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_core::{
+    alert::UnderAttackEvent,
+    objects::{ActiveObjectType, ObjectType},
+    player::Player,
+    projection::ToFlat,
+    summary::{MatchStat, MatchStatEvent},
+};
+use de_objects::{ArmorClass, DamageMatrices, DamageType, Health, Shield};
+use de_signs::UpdateBarValueEvent;
+
+use crate::{shield::ShieldHitEvent, veterancy::KillEvent};
+
+/// System parameter for application of damage to objects.
+#[derive(SystemParam)]
+pub(crate) struct Susceptible<'w, 's> {
+    objects: Query<
+        'w,
+        's,
+        (
+            &'static mut Health,
+            Option<&'static ArmorClass>,
+            Option<&'static mut Shield>,
+        ),
+    >,
+    owners: Query<'w, 's, (&'static Player, &'static Transform)>,
+    types: Query<'w, 's, &'static ObjectType>,
+    matrices: DamageMatrices<'w>,
+    alerts: EventWriter<'w, UnderAttackEvent>,
+    bar: EventWriter<'w, UpdateBarValueEvent>,
+    kills: EventWriter<'w, KillEvent>,
+    shield_hits: EventWriter<'w, ShieldHitEvent>,
+    stats: EventWriter<'w, MatchStatEvent>,
+}
+
+impl<'w, 's> Susceptible<'w, 's> {
+    /// Returns true if the entity can be damaged and it is not destroyed.
+    pub(crate) fn is_alive(&self, entity: Entity) -> bool {
+        self.objects
+            .get(entity)
+            .map_or(false, |(health, _, _)| !health.destroyed())
+    }
+
+    /// Returns damage multiplier of a damage type against armor of an
+    /// entity or None if the entity cannot be damaged.
+    pub(crate) fn effectiveness(&self, entity: Entity, damage_type: DamageType) -> Option<f32> {
+        let (_, armor, _) = self.objects.get(entity).ok()?;
+        Some(armor.map_or(1., |&armor| {
+            self.matrices.get().effectiveness(damage_type, armor)
+        }))
+    }
+
+    /// Applies damage to an entity. The damage is absorbed by the shield of
+    /// the entity (if any) before it is applied to its hull. Owner of the
+    /// entity is alerted with [`UnderAttackEvent`] and the damage is
+    /// accounted to the owner of the attacker.
+    ///
+    /// # Arguments
+    ///
+    /// * `attacker` - the entity which caused the damage.
+    ///
+    /// * `entity` - the damaged entity. Nothing happens if it cannot be
+    ///   damaged.
+    ///
+    /// * `damage` - the damage, already adjusted by effectiveness against
+    ///   the entity armor.
+    pub(crate) fn hit(&mut self, attacker: Entity, entity: Entity, damage: f32) {
+        let Ok((mut health, _, shield)) = self.objects.get_mut(entity) else {
+            return;
+        };
+
+        let damage = match shield {
+            Some(mut shield) => {
+                if shield.energy() > 0. {
+                    self.shield_hits.send(ShieldHitEvent::new(entity));
+                }
+                shield.absorb(damage)
+            }
+            None => damage,
+        };
+
+        let destroyed = health.destroyed();
+        let attacker_owner = self.owners.get(attacker).ok().map(|(&owner, _)| owner);
+        if !destroyed {
+            if let Ok((&player, transform)) = self.owners.get(entity) {
+                self.alerts.send(UnderAttackEvent::new(
+                    player,
+                    transform.translation.to_flat(),
+                ));
+            }
+            if let Some(owner) = attacker_owner {
+                self.stats
+                    .send(MatchStatEvent::new(owner, MatchStat::DamageDealt(damage)));
+            }
+        }
+
+        health.hit(damage);
+        if !destroyed && health.destroyed() {
+            self.kills.send(KillEvent::new(attacker, entity));
+
+            let unit = matches!(
+                self.types.get(entity),
+                Ok(ObjectType::Active(ActiveObjectType::Unit(_)))
+            );
+            if let Some(owner) = attacker_owner.filter(|_| unit) {
+                self.stats
+                    .send(MatchStatEvent::new(owner, MatchStat::UnitKilled));
+            }
+        }
+        self.bar
+            .send(UpdateBarValueEvent::new(entity, health.fraction()));
+    }
+}
@@ -0,0 +1,291 @@
+use ahash::AHashMap;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_audio::{PlaySoundEvent, Sound};
+use de_core::{
+    baseset::GameSet, diplomacy::Diplomacy, gamestate::GameState, gconfig::GameConfig,
+    objects::Flying, player::Player, projection::ToFlat,
+};
+use de_index::SpatialQuery;
+use de_objects::DamageType;
+use de_spawner::SpawnerSet;
+use de_terrain::{
+    DeformTerrainEvent, TerrainCollider, TerrainDeformation, MAX_CRATER_DEPTH, MAX_CRATER_RADIUS,
+};
+use parry3d::{bounding_volume::Aabb, math::Point};
+
+use crate::{damage::Susceptible, sightline::Layer, AttackingSet};
+
+/// Explosions with at least this blast radius leave a crater if they happen
+/// close to the terrain.
+const MIN_CRATER_BLAST_RADIUS: f32 = 6.;
+/// Radius of a crater relative to the blast radius of the explosion.
+const CRATER_RADIUS_RATIO: f32 = 0.5;
+/// Depth of a crater relative to its radius.
+const CRATER_DEPTH_RATIO: f32 = 0.25;
+
+pub(crate) struct ExplosionPlugin;
+
+impl Plugin for ExplosionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExplosionEvent>().add_system(
+            explode
+                .in_base_set(GameSet::Update)
+                .run_if(in_state(GameState::Playing))
+                .in_set(ExplosionSet::Explode)
+                .after(AttackingSet::Fire)
+                .before(SpawnerSet::Destroyer),
+        );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum ExplosionSet {
+    Explode,
+}
+
+/// Send this event to inflict area damage around a point. Large explosions
+/// close to the terrain also leave a crater.
+pub(crate) struct ExplosionEvent {
+    attacker: Entity,
+    owner: Player,
+    point: Vec3,
+    direct: Option<Entity>,
+    damage: f32,
+    damage_type: DamageType,
+    radius: f32,
+    layer: Layer,
+}
+
+impl ExplosionEvent {
+    /// # Arguments
+    ///
+    /// * `attacker` - the entity which caused the explosion. It does not
+    ///   need to exist anymore.
+    ///
+    /// * `owner` - owner of the attacker. Allies of the owner are not
+    ///   damaged unless friendly fire is enabled in [`GameConfig`].
+    ///
+    /// * `point` - center of the explosion.
+    ///
+    /// * `direct` - entity directly hit by the explosion, if any. This
+    ///   entity receives full damage.
+    ///
+    /// * `damage` - damage at the center of the explosion. Objects within
+    ///   the blast radius receive damage linearly decreasing with their
+    ///   distance from the center.
+    ///
+    /// * `damage_type` - type of the inflicted damage.
+    ///
+    /// * `radius` - blast radius.
+    pub(crate) fn new(
+        attacker: Entity,
+        owner: Player,
+        point: Vec3,
+        direct: Option<Entity>,
+        damage: f32,
+        damage_type: DamageType,
+        radius: f32,
+    ) -> Self {
+        Self {
+            attacker,
+            owner,
+            point,
+            direct,
+            damage,
+            damage_type,
+            radius,
+            layer: Layer::Ground,
+        }
+    }
+
+    /// Sets the layer of objects damaged by the blast. By default, only
+    /// objects on the ground are damaged.
+    pub(crate) fn with_layer(mut self, layer: Layer) -> Self {
+        self.layer = layer;
+        self
+    }
+}
+
+#[derive(SystemParam)]
+struct Surroundings<'w, 's> {
+    conf: Res<'w, GameConfig>,
+    diplomacy: Res<'w, Diplomacy>,
+    entities: SpatialQuery<'w, 's, Entity>,
+    objects: Query<'w, 's, (&'static Transform, &'static Player, Option<&'static Flying>)>,
+}
+
+impl<'w, 's> Surroundings<'w, 's> {
+    /// Returns all entities damaged by an explosion together with fraction
+    /// of the damage they receive.
+    fn affected(&self, explosion: &ExplosionEvent) -> Vec<(Entity, f32)> {
+        let friendly_fire = self.conf.friendly_fire();
+        let spared = |entity: Entity| {
+            !friendly_fire
+                && self.objects.get(entity).map_or(false, |(_, &player, _)| {
+                    self.diplomacy.are_allies(explosion.owner, player)
+                })
+        };
+
+        let mut affected: Vec<(Entity, f32)> = explosion
+            .direct
+            .iter()
+            .filter(|&&entity| !spared(entity))
+            .map(|&entity| (entity, 1.))
+            .collect();
+
+        if explosion.radius > 0. {
+            let aabb = Aabb::new(
+                Point::from(explosion.point - Vec3::splat(explosion.radius)),
+                Point::from(explosion.point + Vec3::splat(explosion.radius)),
+            );
+            for entity in self.entities.query_aabb(&aabb, explosion.direct) {
+                if spared(entity) {
+                    continue;
+                }
+                // Object origins are used as an approximation of their
+                // distance from the center of the explosion.
+                let Ok((transform, _, flying)) = self.objects.get(entity) else {
+                    continue;
+                };
+                if Layer::of(flying.is_some()) != explosion.layer {
+                    continue;
+                }
+                let fraction = blast_fraction(
+                    explosion.point.distance(transform.translation),
+                    explosion.radius,
+                );
+                if fraction > 0. {
+                    affected.push((entity, fraction));
+                }
+            }
+        }
+
+        affected
+    }
+}
+
+/// Returns fraction of damage received by an object at a given distance from
+/// the center of an explosion.
+fn blast_fraction(distance: f32, radius: f32) -> f32 {
+    if radius <= 0. {
+        0.
+    } else {
+        (1. - distance / radius).max(0.)
+    }
+}
+
+/// Returns the crater left by an explosion or None if the explosion is too
+/// small or too far from the terrain.
+///
+/// # Arguments
+///
+/// * `point` - center of the explosion.
+///
+/// * `radius` - blast radius of the explosion.
+///
+/// * `elevation` - elevation of the terrain below the center of the
+///   explosion.
+fn crater(point: Vec3, radius: f32, elevation: f32) -> Option<TerrainDeformation> {
+    if radius < MIN_CRATER_BLAST_RADIUS {
+        return None;
+    }
+
+    let crater_radius = (CRATER_RADIUS_RATIO * radius).min(MAX_CRATER_RADIUS);
+    if point.y - elevation > crater_radius {
+        return None;
+    }
+
+    Some(TerrainDeformation::crater(
+        point.to_flat(),
+        crater_radius,
+        (CRATER_DEPTH_RATIO * crater_radius).min(MAX_CRATER_DEPTH),
+    ))
+}
+
+/// Aggregates damage per entity and sorts it by the entity. Killer of each
+/// entity is the first attacker in `damages` which damaged it.
+fn aggregate(damages: Vec<(Entity, Entity, f32)>) -> Vec<(Entity, Entity, f32)> {
+    let mut totals: AHashMap<Entity, (Entity, f32)> = AHashMap::new();
+    for (entity, attacker, damage) in damages {
+        totals.entry(entity).or_insert((attacker, 0.)).1 += damage;
+    }
+
+    let mut totals: Vec<(Entity, Entity, f32)> = totals
+        .into_iter()
+        .map(|(entity, (attacker, damage))| (entity, attacker, damage))
+        .collect();
+    totals.sort_unstable_by_key(|&(entity, _, _)| entity);
+    totals
+}
+
+fn explode(
+    mut events: EventReader<ExplosionEvent>,
+    surroundings: Surroundings,
+    mut susceptible: Susceptible,
+    terrain: TerrainCollider,
+    mut deformations: EventWriter<DeformTerrainEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
+) {
+    let mut damages = Vec::new();
+    for explosion in events.iter() {
+        sounds.send(PlaySoundEvent::new(Sound::Explosion, explosion.point));
+
+        let elevation = terrain.elevation(explosion.point.to_flat());
+        if let Some(crater) = crater(explosion.point, explosion.radius, elevation) {
+            deformations.send(DeformTerrainEvent::new(crater));
+        }
+
+        for (entity, fraction) in surroundings.affected(explosion) {
+            let Some(effectiveness) = susceptible.effectiveness(entity, explosion.damage_type)
+            else {
+                continue;
+            };
+            damages.push((
+                entity,
+                explosion.attacker,
+                explosion.damage * effectiveness * fraction,
+            ));
+        }
+    }
+
+    for (entity, attacker, damage) in aggregate(damages) {
+        susceptible.hit(attacker, entity, damage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blast_fraction() {
+        assert_eq!(blast_fraction(0., 4.), 1.);
+        assert_eq!(blast_fraction(1., 4.), 0.75);
+        assert_eq!(blast_fraction(5., 4.), 0.);
+        assert_eq!(blast_fraction(0., 0.), 0.);
+    }
+
+    #[test]
+    fn test_crater() {
+        assert!(crater(Vec3::new(1., 2., 3.), 4., 0.).is_none());
+        assert!(crater(Vec3::new(1., 10., 3.), 8., 2.).is_none());
+        assert_eq!(
+            crater(Vec3::new(1., 5., 3.), 8., 2.).unwrap(),
+            TerrainDeformation::crater(Vec2::new(1., -3.), 4., 1.)
+        );
+        assert_eq!(
+            crater(Vec3::new(1., 0., 3.), 1000., 0.).unwrap(),
+            TerrainDeformation::crater(Vec2::new(1., -3.), MAX_CRATER_RADIUS, MAX_CRATER_DEPTH)
+        );
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+        let c = Entity::from_raw(3);
+
+        let damages = aggregate(vec![(c, a, 1.), (b, a, 2.), (c, b, 0.5), (a, c, 4.)]);
+        assert_eq!(damages, vec![(a, c, 4.), (b, a, 2.), (c, a, 1.5)]);
+    }
+}
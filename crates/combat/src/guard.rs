@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use de_behaviour::{ChaseSet, ChaseTarget, ChaseTargetEvent, OrderQueue};
+use de_core::{
+    baseset::GameSet, diplomacy::Diplomacy, gamestate::GameState, objects::MovableSolid,
+    player::Player,
+};
+use de_objects::LaserCannon;
+
+use crate::{attack::Attacking, stance::AttackMoving, AttackingSet};
+
+/// Guarding units do not engage enemies further than this from the guarded
+/// entity and break off attacks of enemies which leave this radius.
+const LEASH_RADIUS: f32 = 60.;
+/// Guarding units try to stay at least this far from the guarded entity.
+const MIN_FOLLOW_DISTANCE: f32 = 5.;
+/// Guarding units try to stay at most this far from the guarded entity.
+const MAX_FOLLOW_DISTANCE: f32 = 10.;
+
+pub(crate) struct GuardPlugin;
+
+impl Plugin for GuardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GuardEvent>()
+            .add_system(
+                guard
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(GuardSet::Guard)
+                    .before(AttackingSet::Attack)
+                    .before(ChaseSet::ChaseTargetEvent),
+            )
+            .add_system(
+                leash
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                follow
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum GuardSet {
+    Guard,
+}
+
+/// Send this event to make a unit follow and protect another (allied)
+/// entity. The guarding unit automatically attacks enemies attacking the
+/// guarded entity.
+pub struct GuardEvent {
+    entity: Entity,
+    target: Entity,
+}
+
+impl GuardEvent {
+    /// # Arguments
+    ///
+    /// * `entity` - the guarding unit.
+    ///
+    /// * `target` - the guarded entity.
+    pub fn new(entity: Entity, target: Entity) -> Self {
+        Self { entity, target }
+    }
+}
+
+/// This component is attached to units which guard another entity. The
+/// component is removed once the guarded entity is despawned or when the unit
+/// is explicitly commanded to attack or to move.
+#[derive(Component)]
+pub struct Guarding(Entity);
+
+impl Guarding {
+    /// Returns the guarded entity.
+    pub fn target(&self) -> Entity {
+        self.0
+    }
+}
+
+fn follow_target(target: Entity) -> ChaseTarget {
+    ChaseTarget::new(target, MIN_FOLLOW_DISTANCE, MAX_FOLLOW_DISTANCE)
+}
+
+fn guard(
+    mut commands: Commands,
+    diplomacy: Res<Diplomacy>,
+    mut events: EventReader<GuardEvent>,
+    units: Query<&Player, (With<MovableSolid>, With<LaserCannon>)>,
+    targets: Query<&Player>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+) {
+    for event in events.iter() {
+        if event.entity == event.target {
+            continue;
+        }
+        let (Ok(&player), Ok(&target_player)) =
+            (units.get(event.entity), targets.get(event.target))
+        else {
+            continue;
+        };
+        if !diplomacy.are_allies(player, target_player) {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .remove::<Attacking>()
+            .remove::<AttackMoving>()
+            .remove::<OrderQueue>()
+            .insert(Guarding(event.target));
+        chase_events.send(ChaseTargetEvent::new(
+            event.entity,
+            Some(follow_target(event.target)),
+        ));
+    }
+}
+
+/// Breaks off attacks of enemies which are too far from the guarded entity.
+fn leash(
+    mut commands: Commands,
+    guards: Query<(Entity, &Guarding, &Attacking)>,
+    transforms: Query<&Transform>,
+) {
+    for (entity, guarding, attacking) in guards.iter() {
+        let (Ok(guarded), Ok(enemy)) = (
+            transforms.get(guarding.target()),
+            transforms.get(attacking.enemy()),
+        ) else {
+            continue;
+        };
+
+        if !within_leash(Some(guarded.translation), enemy.translation) {
+            commands.entity(entity).remove::<Attacking>();
+        }
+    }
+}
+
+/// Returns guarding units back to the guarded entity once they stop
+/// attacking and stops guarding of despawned entities.
+fn follow(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Attacking>,
+    guards: Query<(Entity, &Guarding), Without<Attacking>>,
+    targets: Query<(), With<Transform>>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+) {
+    let removed: Vec<Entity> = removed.iter().collect();
+    for (entity, guarding) in guards.iter() {
+        if targets.get(guarding.target()).is_err() {
+            commands.entity(entity).remove::<Guarding>();
+        } else if removed.contains(&entity) {
+            chase_events.send(ChaseTargetEvent::new(
+                entity,
+                Some(follow_target(guarding.target())),
+            ));
+        }
+    }
+}
+
+/// Returns true if `position` is within the leash radius of a guarded entity
+/// at `guarded` (if any).
+pub(crate) fn within_leash(guarded: Option<Vec3>, position: Vec3) -> bool {
+    guarded.map_or(true, |guarded| guarded.distance(position) <= LEASH_RADIUS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_leash() {
+        assert!(within_leash(None, Vec3::new(1000., 0., 1000.)));
+        assert!(within_leash(Some(Vec3::ZERO), Vec3::new(30., 0., -40.)));
+        assert!(!within_leash(Some(Vec3::ZERO), Vec3::new(40., 0., -50.)));
+        assert!(within_leash(
+            Some(Vec3::new(100., 0., 100.)),
+            Vec3::new(120., 0., 90.)
+        ));
+    }
+}
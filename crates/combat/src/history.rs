@@ -0,0 +1,123 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, gamestate::GameState, objects::MovableSolid};
+
+/// Maximum time by which positions of attacked objects are rewound.
+pub(crate) const MAX_REWIND: Duration = Duration::from_millis(300);
+
+/// This plugin keeps a short history of positions of all movable objects so
+/// that attacks commanded by players with high latency are evaluated against
+/// positions of the targets as the players saw them. See
+/// [`crate::AttackEvent::with_latency`].
+///
+/// Without this plugin, all attacks are evaluated against current positions
+/// of the targets.
+pub struct LagCompensationPlugin;
+
+impl Plugin for LagCompensationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            init.in_base_set(GameSet::PostUpdate)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_system(
+            record
+                .in_base_set(GameSet::PostUpdate)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Recent translations of an object.
+#[derive(Component, Default)]
+pub(crate) struct PositionHistory {
+    samples: VecDeque<(Duration, Vec3)>,
+}
+
+impl PositionHistory {
+    /// Stores translation of the object at `time` and forgets translations
+    /// no longer needed for rewinding. Out of order samples are ignored.
+    fn push(&mut self, time: Duration, translation: Vec3) {
+        if self.samples.back().map_or(false, |&(last, _)| last >= time) {
+            return;
+        }
+        self.samples.push_back((time, translation));
+
+        let oldest = time.saturating_sub(MAX_REWIND);
+        while self.samples.len() > 1 && self.samples[1].0 <= oldest {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns (interpolated) translation of the object at `time` or None if
+    /// there is no translation recorded at or before that time.
+    pub(crate) fn at(&self, time: Duration) -> Option<Vec3> {
+        let index = self.samples.partition_point(|&(sample, _)| sample <= time);
+        if index == 0 {
+            return None;
+        }
+
+        let (previous_time, previous) = self.samples[index - 1];
+        let Some(&(next_time, next)) = self.samples.get(index) else {
+            return Some(previous);
+        };
+
+        let fraction =
+            (time - previous_time).as_secs_f32() / (next_time - previous_time).as_secs_f32();
+        Some(previous.lerp(next, fraction))
+    }
+}
+
+type Uninitialized = (With<MovableSolid>, Without<PositionHistory>);
+
+fn init(mut commands: Commands, objects: Query<Entity, Uninitialized>) {
+    for entity in objects.iter() {
+        commands.entity(entity).insert(PositionHistory::default());
+    }
+}
+
+fn record(time: Res<Time>, mut objects: Query<(&Transform, &mut PositionHistory)>) {
+    let now = time.elapsed();
+    for (transform, mut history) in objects.iter_mut() {
+        history.push(now, transform.translation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = PositionHistory::default();
+        assert_eq!(history.at(ms(0)), None);
+
+        history.push(ms(100), Vec3::ZERO);
+        history.push(ms(200), Vec3::new(2., 0., -4.));
+        // Out of order samples are ignored.
+        history.push(ms(150), Vec3::ONE);
+
+        assert_eq!(history.at(ms(50)), None);
+        assert_eq!(history.at(ms(100)), Some(Vec3::ZERO));
+        assert!(history
+            .at(ms(150))
+            .unwrap()
+            .abs_diff_eq(Vec3::new(1., 0., -2.), 1e-5));
+        assert_eq!(history.at(ms(250)), Some(Vec3::new(2., 0., -4.)));
+
+        for i in 3..10 {
+            history.push(ms(i * 100), Vec3::splat(i as f32));
+        }
+        assert_eq!(history.samples.len(), 4);
+        assert_eq!(history.at(ms(550)), None);
+        assert!(history
+            .at(ms(650))
+            .unwrap()
+            .abs_diff_eq(Vec3::splat(6.5), 1e-5));
+    }
+}
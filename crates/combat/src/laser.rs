@@ -1,11 +1,16 @@
 use bevy::prelude::*;
+use de_audio::{PlaySoundEvent, Sound};
 use de_core::{baseset::GameSet, gamestate::GameState};
-use de_objects::Health;
-use de_signs::UpdateBarValueEvent;
+use de_objects::DamageType;
 use de_spawner::SpawnerSet;
 use parry3d::query::Ray;
 
-use crate::{sightline::LineOfSight, trail::TrailEvent, AttackingSet};
+use crate::{
+    damage::Susceptible,
+    sightline::{Layer, LineOfSight},
+    trail::TrailEvent,
+    AttackingSet,
+};
 
 pub(crate) struct LaserPlugin;
 
@@ -29,6 +34,9 @@ pub(crate) struct LaserFireEvent {
     ray: Ray,
     max_toi: f32,
     damage: f32,
+    damage_type: DamageType,
+    layer: Layer,
+    rewound_target: Option<Entity>,
 }
 
 impl LaserFireEvent {
@@ -44,18 +52,41 @@ impl LaserFireEvent {
     ///   point is given by formula `ray.origin + max_toi * ray.dir`.
     ///
     /// * `damage` - if an entity is hit, its health will be lowered by this
-    ///   amount.
+    ///   amount multiplied by effectiveness of the damage type against armor
+    ///   of the entity.
+    ///
+    /// * `damage_type` - type of the inflicted damage.
+    ///
+    /// * `layer` - the laser passes through objects in other layers.
     #[allow(dead_code)]
-    pub(crate) fn new(attacker: Entity, ray: Ray, max_toi: f32, damage: f32) -> Self {
+    pub(crate) fn new(
+        attacker: Entity,
+        ray: Ray,
+        max_toi: f32,
+        damage: f32,
+        damage_type: DamageType,
+        layer: Layer,
+    ) -> Self {
         Self {
             attacker,
             ray,
             max_toi,
             damage,
+            damage_type,
+            layer,
+            rewound_target: None,
         }
     }
 
-    fn attacker(&self) -> Entity {
+    /// Sets an entity which is hit if the laser reaches `max_toi` without
+    /// hitting anything. This is used for attacks evaluated against rewound
+    /// (past) positions of their targets.
+    pub(crate) fn with_rewound_target(mut self, target: Entity) -> Self {
+        self.rewound_target = Some(target);
+        self
+    }
+
+    pub(crate) fn attacker(&self) -> Entity {
         self.attacker
     }
 
@@ -70,34 +101,54 @@ impl LaserFireEvent {
     fn damage(&self) -> f32 {
         self.damage
     }
+
+    fn damage_type(&self) -> DamageType {
+        self.damage_type
+    }
+
+    fn layer(&self) -> Layer {
+        self.layer
+    }
+
+    fn rewound_target(&self) -> Option<Entity> {
+        self.rewound_target
+    }
 }
 
 fn fire(
     mut fires: EventReader<LaserFireEvent>,
     sightline: LineOfSight,
-    mut susceptible: Query<&mut Health>,
-    mut bar: EventWriter<UpdateBarValueEvent>,
+    mut susceptible: Susceptible,
     mut trail: EventWriter<TrailEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
 ) {
     for fire in fires.iter() {
-        if susceptible
-            .get(fire.attacker())
-            .map_or(true, |health| health.destroyed())
-        {
+        if !susceptible.is_alive(fire.attacker()) {
             continue;
         }
 
-        let observation = sightline.sight(fire.ray(), fire.max_toi(), fire.attacker());
+        sounds.send(PlaySoundEvent::new(
+            Sound::LaserFire,
+            fire.ray().origin.into(),
+        ));
+
+        let observation =
+            sightline.sight(fire.ray(), fire.max_toi(), fire.attacker(), fire.layer());
 
         trail.send(TrailEvent::new(Ray::new(
             fire.ray().origin,
             observation.toi() * fire.ray().dir,
         )));
 
-        if let Some(entity) = observation.entity() {
-            let mut health = susceptible.get_mut(entity).unwrap();
-            health.hit(fire.damage());
-            bar.send(UpdateBarValueEvent::new(entity, health.fraction()));
+        let hit = observation.entity().or_else(|| {
+            fire.rewound_target()
+                .filter(|_| observation.toi() >= fire.max_toi())
+        });
+        if let Some(entity) = hit {
+            let Some(effectiveness) = susceptible.effectiveness(entity, fire.damage_type()) else {
+                continue;
+            };
+            susceptible.hit(fire.attacker(), entity, fire.damage() * effectiveness);
         }
     }
 }
@@ -1,16 +1,40 @@
-pub use attack::AttackEvent;
 use attack::AttackPlugin;
+pub use attack::{AttackEvent, Attacking};
 use bevy::{
     app::PluginGroupBuilder,
     prelude::{PluginGroup, SystemSet},
 };
+use explosion::ExplosionPlugin;
+use guard::GuardPlugin;
+pub use guard::{GuardEvent, Guarding};
+pub use history::LagCompensationPlugin;
 use laser::LaserPlugin;
+use projectile::ProjectilePlugin;
+use shield::ShieldPlugin;
+use stance::StancePlugin;
+pub use stance::{AttackMoveEvent, AttackMoving, SetStanceEvent, Stance};
+use stealth::StealthPlugin;
+pub use stealth::{Concealed, Detection, StealthSet};
+use targeting::TargetingPlugin;
+pub use targeting::{SetPriorityTargetEvent, TargetPriorities, TargetWeights};
 use trail::TrailPlugin;
+use veterancy::VeterancyPlugin;
+pub use veterancy::{Experience, Rank, RankBadge};
 
 mod attack;
+mod damage;
+mod explosion;
+mod guard;
+mod history;
 mod laser;
+mod projectile;
+mod shield;
 mod sightline;
+mod stance;
+mod stealth;
+mod targeting;
 mod trail;
+mod veterancy;
 
 pub struct CombatPluginGroup;
 
@@ -19,7 +43,18 @@ impl PluginGroup for CombatPluginGroup {
         PluginGroupBuilder::start::<Self>()
             .add(LaserPlugin)
             .add(AttackPlugin)
+            .add(ProjectilePlugin)
+            .add(ExplosionPlugin)
+            .add(StancePlugin)
+            .add(GuardPlugin)
+            .add(TargetingPlugin)
+            .add(ShieldPlugin)
+            .add(StealthPlugin)
             .add(TrailPlugin)
+            .add(VeterancyPlugin)
+            // Lag compensation is opt-in.
+            .add(LagCompensationPlugin)
+            .disable::<LagCompensationPlugin>()
     }
 }
 
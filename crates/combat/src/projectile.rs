@@ -0,0 +1,292 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet, cleanup::DespawnOnGameExit, gamestate::GameState, objects::MovableSolid,
+    player::Player,
+};
+use de_objects::DamageType;
+use parry3d::query::Ray;
+
+use crate::{
+    explosion::{ExplosionEvent, ExplosionSet},
+    sightline::{Layer, LineOfSight},
+    trail::TrailEvent,
+    AttackingSet,
+};
+
+/// Gravitational acceleration applied to projectiles.
+const GRAVITY: Vec3 = Vec3::new(0., -9.81, 0.);
+/// Projectiles are removed after this time even if they did not hit
+/// anything.
+const MAX_FLIGHT_TIME: Duration = Duration::from_secs(10);
+/// Number of refinements of the time of flight during lead prediction.
+const LEAD_ITERATIONS: usize = 3;
+
+pub(crate) struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ProjectileFireEvent>()
+            .add_system(
+                track
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                launch
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(AttackingSet::Fire),
+            )
+            .add_system(
+                fly.in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(AttackingSet::Fire)
+                    .after(launch)
+                    .before(ExplosionSet::Explode),
+            );
+    }
+}
+
+/// Send this event to launch a projectile.
+pub(crate) struct ProjectileFireEvent {
+    attacker: Entity,
+    origin: Vec3,
+    velocity: Vec3,
+    damage: f32,
+    damage_type: DamageType,
+    blast_radius: f32,
+    layer: Layer,
+}
+
+impl ProjectileFireEvent {
+    /// # Arguments
+    ///
+    /// * `attacker` - the firing entity.
+    ///
+    /// * `origin` - initial position of the projectile.
+    ///
+    /// * `velocity` - initial velocity of the projectile.
+    ///
+    /// * `damage` - damage inflicted to objects hit directly. Objects within
+    ///   the blast radius receive damage linearly decreasing with their
+    ///   distance from the point of impact.
+    ///
+    /// * `damage_type` - type of the inflicted damage.
+    ///
+    /// * `blast_radius` - radius of the area damage.
+    ///
+    /// * `layer` - the projectile flies through objects in other layers and
+    ///   its explosion damages only objects in this layer.
+    pub(crate) fn new(
+        attacker: Entity,
+        origin: Vec3,
+        velocity: Vec3,
+        damage: f32,
+        damage_type: DamageType,
+        blast_radius: f32,
+        layer: Layer,
+    ) -> Self {
+        Self {
+            attacker,
+            origin,
+            velocity,
+            damage,
+            damage_type,
+            blast_radius,
+            layer,
+        }
+    }
+
+    pub(crate) fn attacker(&self) -> Entity {
+        self.attacker
+    }
+}
+
+#[derive(Component)]
+struct Projectile {
+    attacker: Entity,
+    owner: Player,
+    velocity: Vec3,
+    damage: f32,
+    damage_type: DamageType,
+    blast_radius: f32,
+    layer: Layer,
+    flight_time: Duration,
+}
+
+/// Velocity of a movable object estimated from its last two positions. It is
+/// used for lead prediction.
+#[derive(Component)]
+pub(crate) struct Motion {
+    position: Vec3,
+    velocity: Vec3,
+}
+
+impl Motion {
+    fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            velocity: Vec3::ZERO,
+        }
+    }
+
+    pub(crate) fn velocity(&self) -> Vec3 {
+        self.velocity
+    }
+
+    fn update(&mut self, position: Vec3, delta: Duration) {
+        if !delta.is_zero() {
+            self.velocity = (position - self.position) / delta.as_secs_f32();
+        }
+        self.position = position;
+    }
+}
+
+/// Returns initial velocity of a projectile launched from `muzzle` so that it
+/// hits a target currently at `target` moving with a constant velocity.
+///
+/// The time of flight is given by `speed` along the line between the muzzle
+/// and the predicted target position. The vertical component of the launch
+/// velocity compensates for gravity.
+pub(crate) fn aim(muzzle: Vec3, target: Vec3, target_velocity: Vec3, speed: f32) -> Vec3 {
+    let mut predicted = target;
+    let mut time = 0.;
+    for _ in 0..LEAD_ITERATIONS {
+        time = muzzle.distance(predicted) / speed;
+        predicted = target + target_velocity * time;
+    }
+
+    if time <= 0. {
+        return Vec3::ZERO;
+    }
+    (predicted - muzzle) / time - 0.5 * GRAVITY * time
+}
+
+type Uninitialized = (With<MovableSolid>, Without<Motion>);
+
+fn track(
+    mut commands: Commands,
+    time: Res<Time>,
+    uninitialized: Query<(Entity, &Transform), Uninitialized>,
+    mut objects: Query<(&Transform, &mut Motion)>,
+) {
+    for (entity, transform) in uninitialized.iter() {
+        commands
+            .entity(entity)
+            .insert(Motion::new(transform.translation));
+    }
+    for (transform, mut motion) in objects.iter_mut() {
+        motion.update(transform.translation, time.delta());
+    }
+}
+
+fn launch(
+    mut commands: Commands,
+    mut events: EventReader<ProjectileFireEvent>,
+    players: Query<&Player>,
+) {
+    for event in events.iter() {
+        let Ok(&owner) = players.get(event.attacker) else {
+            continue;
+        };
+
+        commands.spawn((
+            TransformBundle::from_transform(Transform::from_translation(event.origin)),
+            Projectile {
+                attacker: event.attacker,
+                owner,
+                velocity: event.velocity,
+                damage: event.damage,
+                damage_type: event.damage_type,
+                blast_radius: event.blast_radius,
+                layer: event.layer,
+                flight_time: Duration::ZERO,
+            },
+            DespawnOnGameExit,
+        ));
+    }
+}
+
+fn fly(
+    mut commands: Commands,
+    time: Res<Time>,
+    sightline: LineOfSight,
+    mut projectiles: Query<(Entity, &mut Transform, &mut Projectile)>,
+    mut trail: EventWriter<TrailEvent>,
+    mut explosions: EventWriter<ExplosionEvent>,
+) {
+    let delta = time.delta();
+    let delta_secs = delta.as_secs_f32();
+
+    for (entity, mut transform, mut projectile) in projectiles.iter_mut() {
+        projectile.flight_time += delta;
+        projectile.velocity += GRAVITY * delta_secs;
+
+        let step = projectile.velocity * delta_secs;
+        let distance = step.length();
+        if distance > 0. {
+            let ray = Ray::new(transform.translation.into(), (step / distance).into());
+            let observation =
+                sightline.sight(&ray, distance, projectile.attacker, projectile.layer);
+            let reached: Vec3 = ray.point_at(observation.toi()).into();
+
+            trail.send(TrailEvent::new(Ray::new(
+                transform.translation.into(),
+                (reached - transform.translation).into(),
+            )));
+
+            if observation.toi() < distance {
+                explosions.send(
+                    ExplosionEvent::new(
+                        projectile.attacker,
+                        projectile.owner,
+                        reached,
+                        observation.entity(),
+                        projectile.damage,
+                        projectile.damage_type,
+                        projectile.blast_radius,
+                    )
+                    .with_layer(projectile.layer),
+                );
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+            transform.translation = reached;
+        }
+
+        if projectile.flight_time >= MAX_FLIGHT_TIME {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aim() {
+        let muzzle = Vec3::new(1., 2., -3.);
+        let target = Vec3::new(31., 1., 37.);
+        let target_velocity = Vec3::new(2., 0., -1.);
+
+        let velocity = aim(muzzle, target, target_velocity, 20.);
+        // Simulate the flight with a small time step and check that the
+        // projectile passes close to the moving target.
+        let step = 0.001;
+        let mut position = muzzle;
+        let mut current = velocity;
+        let mut closest = f32::INFINITY;
+        for i in 0..5000 {
+            let time = i as f32 * step;
+            closest = closest.min(position.distance(target + target_velocity * time));
+            current += GRAVITY * step;
+            position += current * step;
+        }
+        assert!(closest < 0.1, "{closest}");
+
+        assert_eq!(aim(muzzle, muzzle, Vec3::ZERO, 20.), Vec3::ZERO);
+    }
+}
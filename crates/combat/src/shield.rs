@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::system::SystemParam,
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+};
+use de_core::{
+    baseset::GameSet, gamestate::GameState, objects::ObjectType, pool::EntityPool, state::AppState,
+};
+use de_objects::{Shield, SolidObjects};
+
+use crate::AttackingSet;
+
+const FLASH_LIFESPAN: Duration = Duration::from_millis(300);
+const FLASH_COLOR: Color = Color::rgba(0.3, 0.6, 1., 0.4);
+/// Maximum number of faded out flashes kept for reuse.
+const POOL_CAPACITY: usize = 512;
+
+pub(crate) struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShieldHitEvent>()
+            .add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                regenerate
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .before(AttackingSet::Fire),
+            )
+            .add_system(
+                spawn_flashes
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                update_flashes
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// This event is sent when a shield of an object absorbs (part of) a hit.
+pub(crate) struct ShieldHitEvent(Entity);
+
+impl ShieldHitEvent {
+    pub(crate) fn new(entity: Entity) -> Self {
+        Self(entity)
+    }
+
+    fn entity(&self) -> Entity {
+        self.0
+    }
+}
+
+#[derive(Resource)]
+struct FlashMesh(Handle<Mesh>);
+
+/// Short-lived visual effect of a shield hit.
+#[derive(Component)]
+struct Flash {
+    age: Duration,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(SystemParam)]
+struct Flashes<'w, 's> {
+    pool: ResMut<'w, EntityPool<Flash>>,
+    flashes: Query<
+        'w,
+        's,
+        (
+            &'static mut Flash,
+            &'static mut Transform,
+            &'static mut Visibility,
+        ),
+    >,
+}
+
+impl<'w, 's> Flashes<'w, 's> {
+    /// Takes a faded out flash from the pool and restarts it. Returns None if
+    /// there is no flash to be reused.
+    fn reuse(
+        &mut self,
+        materials: &mut Assets<StandardMaterial>,
+        transform: Transform,
+    ) -> Option<Entity> {
+        let flashes = &self.flashes;
+        let entity = self.pool.take(|entity| flashes.contains(entity))?;
+
+        let (mut flash, mut flash_transform, mut visibility) =
+            self.flashes.get_mut(entity).unwrap();
+        flash.age = Duration::ZERO;
+        *flash_transform = transform;
+        *visibility = Visibility::Inherited;
+        if let Some(material) = materials.get_mut(&flash.material) {
+            material.base_color = FLASH_COLOR;
+        }
+        Some(entity)
+    }
+}
+
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let mesh = meshes.add(
+        shape::UVSphere {
+            radius: 1.,
+            sectors: 24,
+            stacks: 12,
+        }
+        .into(),
+    );
+    commands.insert_resource(FlashMesh(mesh));
+    commands.insert_resource(EntityPool::<Flash>::new(POOL_CAPACITY));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<FlashMesh>();
+    commands.remove_resource::<EntityPool<Flash>>();
+}
+
+fn regenerate(time: Res<Time>, mut shields: Query<&mut Shield>) {
+    for mut shield in shields.iter_mut() {
+        shield.regenerate(time.delta());
+    }
+}
+
+fn spawn_flashes(
+    mut commands: Commands,
+    mesh: Res<FlashMesh>,
+    solids: SolidObjects,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    objects: Query<&ObjectType>,
+    mut flashes: Flashes,
+    mut events: EventReader<ShieldHitEvent>,
+) {
+    for event in events.iter() {
+        let Ok(&object_type) = objects.get(event.entity()) else {
+            continue;
+        };
+        let Some(mut entity_commands) = commands.get_entity(event.entity()) else {
+            continue;
+        };
+
+        let aabb = solids.get(object_type).collider().aabb();
+        let transform = Transform {
+            translation: aabb.center().into(),
+            scale: Vec3::from(aabb.half_extents()) * 1.2,
+            ..default()
+        };
+
+        if let Some(flash) = flashes.reuse(materials.as_mut(), transform) {
+            entity_commands.add_child(flash);
+            continue;
+        }
+
+        let material = materials.add(StandardMaterial {
+            base_color: FLASH_COLOR,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+
+        entity_commands.with_children(|parent| {
+            parent.spawn((
+                PbrBundle {
+                    mesh: mesh.0.clone(),
+                    material: material.clone(),
+                    transform,
+                    ..default()
+                },
+                Flash {
+                    age: Duration::ZERO,
+                    material,
+                },
+                NotShadowCaster,
+                NotShadowReceiver,
+            ));
+        });
+    }
+}
+
+/// Fades out all visible flashes. Finished flashes are hidden and released
+/// to the pool.
+fn update_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pool: ResMut<EntityPool<Flash>>,
+    mut flashes: Query<(Entity, &mut Flash, &mut Visibility)>,
+) {
+    for (entity, mut flash, mut visibility) in flashes.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
+        flash.age += time.delta();
+        if flash.age >= FLASH_LIFESPAN {
+            if pool.release(entity) {
+                *visibility = Visibility::Hidden;
+            } else {
+                commands.entity(entity).despawn_recursive();
+            }
+            continue;
+        }
+
+        if let Some(material) = materials.get_mut(&flash.material) {
+            let remaining = 1. - flash.age.as_secs_f32() / FLASH_LIFESPAN.as_secs_f32();
+            material.base_color = FLASH_COLOR.with_a(FLASH_COLOR.a() * remaining);
+        }
+    }
+}
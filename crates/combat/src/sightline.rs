@@ -1,16 +1,40 @@
-use bevy::{ecs::system::SystemParam, prelude::Entity};
-use de_index::SpatialQuery;
-use de_terrain::TerrainCollider;
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{Entity, With, Without},
+};
+use de_core::objects::Flying;
+use de_index::RayCaster;
 use parry3d::query::Ray;
 
+/// Layer of solid objects. Objects in one layer do not block sight into the
+/// other layer, e.g. ground units and buildings do not block sight of flying
+/// units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Layer {
+    Ground,
+    Air,
+}
+
+impl Layer {
+    /// Returns the layer of an object.
+    pub(crate) fn of(flying: bool) -> Self {
+        if flying {
+            Self::Air
+        } else {
+            Self::Ground
+        }
+    }
+}
+
 #[derive(SystemParam)]
 pub(crate) struct LineOfSight<'w, 's> {
-    terrain: TerrainCollider<'w, 's>,
-    entities: SpatialQuery<'w, 's, Entity>,
+    ground: RayCaster<'w, 's, (), Without<Flying>>,
+    air: RayCaster<'w, 's, (), With<Flying>>,
 }
 
 impl<'w, 's> LineOfSight<'w, 's> {
-    /// Looks into a direction up until some furthest point.
+    /// Looks into a direction up until some furthest point. The sight is
+    /// blocked by elevated terrain and by solid objects in a given layer.
     ///
     /// # Arguments
     ///
@@ -21,17 +45,23 @@ impl<'w, 's> LineOfSight<'w, 's> {
     ///
     /// * `observer` - the entity making the observation. This is needed so the
     ///   entity doesn't observe itself.
-    pub(crate) fn sight(&self, ray: &Ray, max_toi: f32, observer: Entity) -> Observation {
-        // It is more efficient to calculate the terrain hit. Do it first so
-        // max_toi can be lowered in case of a hit.
-        let hit = match self.terrain.cast_ray(ray, max_toi) {
-            Some(intersection) => Observation::new(intersection.toi, None),
-            None => Observation::new(max_toi, None),
+    ///
+    /// * `layer` - only objects in this layer block the sight.
+    pub(crate) fn sight(
+        &self,
+        ray: &Ray,
+        max_toi: f32,
+        observer: Entity,
+        layer: Layer,
+    ) -> Observation {
+        let hit = match layer {
+            Layer::Ground => self.ground.cast_ray(ray, max_toi, Some(observer)),
+            Layer::Air => self.air.cast_ray(ray, max_toi, Some(observer)),
         };
-        self.entities
-            .cast_ray(ray, hit.toi(), Some(observer))
-            .map(|i| Observation::new(i.toi(), Some(i.entity())))
-            .unwrap_or(hit)
+        match hit {
+            Some(hit) => Observation::new(hit.toi(), hit.entity()),
+            None => Observation::new(max_toi, None),
+        }
     }
 }
 
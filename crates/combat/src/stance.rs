@@ -0,0 +1,403 @@
+use ahash::AHashMap;
+use bevy::prelude::*;
+use de_behaviour::OrderQueue;
+use de_core::{
+    baseset::GameSet,
+    diplomacy::Diplomacy,
+    gamestate::GameState,
+    objects::{ActiveObjectType, Flying, MovableSolid, ObjectType},
+    player::Player,
+};
+use de_index::SpatialQuery;
+use de_objects::{Health, LaserCannon};
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPath};
+use parry3d::{bounding_volume::Aabb, math::Point};
+
+use crate::{
+    attack::{AttackEvent, Attacking},
+    guard::{self, GuardSet, Guarding},
+    stealth::{self, Detection},
+    targeting::{self, Candidate, PriorityTarget, TargetPriorities, TargetingSet, Threat},
+    AttackingSet,
+};
+
+pub(crate) struct StancePlugin;
+
+impl Plugin for StancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SetStanceEvent>()
+            .add_event::<AttackMoveEvent>()
+            .add_system(
+                init.in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                set_stance
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .before(AttackingSet::Attack),
+            )
+            .add_system(
+                attack_move
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .before(AttackingSet::Attack),
+            )
+            .add_system(
+                engage
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .before(AttackingSet::Attack)
+                    .after(set_stance)
+                    .after(attack_move)
+                    .after(GuardSet::Guard)
+                    .after(TargetingSet::SetPriority),
+            )
+            .add_system(
+                resume
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Stance determines how an armed object reacts to enemies which are not
+/// explicitly targeted by its owner.
+#[derive(Component, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Stance {
+    /// Automatically attacks enemies within range and chases them.
+    #[default]
+    Aggressive,
+    /// Automatically attacks enemies within range while idle but does not
+    /// chase them.
+    Defensive,
+    /// Never attacks automatically. Explicit attack orders are still
+    /// obeyed.
+    HoldFire,
+    /// Automatically attacks enemies within range and never moves to chase
+    /// any enemy, not even explicitly attacked ones.
+    HoldPosition,
+}
+
+impl Stance {
+    /// Returns true if an object with this stance automatically attacks
+    /// enemies entering its range.
+    fn engages(self, idle: bool, attack_moving: bool) -> bool {
+        match self {
+            Self::Aggressive | Self::HoldPosition => true,
+            Self::Defensive => idle || attack_moving,
+            Self::HoldFire => false,
+        }
+    }
+
+    /// Returns true if an object with this stance chases its targets.
+    pub(crate) fn chases(self, automatic: bool, attack_moving: bool) -> bool {
+        match self {
+            Self::HoldPosition => false,
+            Self::Aggressive => true,
+            Self::Defensive | Self::HoldFire => !automatic || attack_moving,
+        }
+    }
+}
+
+/// Send this event to change stance of an object.
+pub struct SetStanceEvent {
+    entity: Entity,
+    stance: Stance,
+}
+
+impl SetStanceEvent {
+    pub fn new(entity: Entity, stance: Stance) -> Self {
+        Self { entity, stance }
+    }
+}
+
+/// Send this event to move a unit to a point on the map while automatically
+/// attacking enemies which come into its range.
+pub struct AttackMoveEvent {
+    entity: Entity,
+    target: Vec2,
+}
+
+impl AttackMoveEvent {
+    pub fn new(entity: Entity, target: Vec2) -> Self {
+        Self { entity, target }
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn target(&self) -> Vec2 {
+        self.target
+    }
+}
+
+/// This component is attached to units which are attack-moving to a point on
+/// the map. The component is removed once the unit arrives or when it is
+/// explicitly commanded to attack.
+#[derive(Component)]
+pub struct AttackMoving(Vec2);
+
+impl AttackMoving {
+    pub fn target(&self) -> Vec2 {
+        self.0
+    }
+}
+
+/// Inserts the default stance to newly armed objects. Objects whose cannon is
+/// re-inserted (e.g. units leaving a carrier) keep their stance.
+fn init(mut commands: Commands, cannons: Query<Entity, (Added<LaserCannon>, Without<Stance>)>) {
+    for entity in cannons.iter() {
+        commands.entity(entity).insert(Stance::default());
+    }
+}
+
+fn set_stance(mut events: EventReader<SetStanceEvent>, mut stances: Query<&mut Stance>) {
+    for event in events.iter() {
+        if let Ok(mut stance) = stances.get_mut(event.entity) {
+            *stance = event.stance;
+        }
+    }
+}
+
+fn attack_move(
+    mut commands: Commands,
+    mut events: EventReader<AttackMoveEvent>,
+    units: Query<(), (With<MovableSolid>, With<LaserCannon>)>,
+    mut path_events: EventWriter<UpdateEntityPath>,
+) {
+    for event in events.iter() {
+        if units.get(event.entity).is_err() {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .remove::<Attacking>()
+            .remove::<Guarding>()
+            .remove::<OrderQueue>()
+            .insert(AttackMoving(event.target));
+        path_events.send(UpdateEntityPath::new(
+            event.entity,
+            PathTarget::new(event.target, PathQueryProps::exact(), false),
+        ));
+    }
+}
+
+type AttackerComponents<'a> = (
+    Entity,
+    &'a Player,
+    &'a ObjectType,
+    &'a Transform,
+    &'a LaserCannon,
+    &'a Stance,
+    Option<&'a PathTarget>,
+    Option<&'a AttackMoving>,
+    Option<&'a PriorityTarget>,
+    Option<&'a Guarding>,
+    Option<&'a Attacking>,
+);
+
+type TargetComponents<'a> = (
+    &'a Player,
+    &'a ObjectType,
+    &'a Transform,
+    &'a Health,
+    Option<&'a LaserCannon>,
+    Option<&'a Attacking>,
+    Option<&'a Flying>,
+    Option<&'a Detection>,
+);
+
+fn engage(
+    diplomacy: Res<Diplomacy>,
+    priorities: Res<TargetPriorities>,
+    attackers: Query<AttackerComponents>,
+    entities: SpatialQuery<Entity, With<Health>>,
+    targets: Query<TargetComponents>,
+    assailants: Query<(Entity, &Attacking)>,
+    mut events: EventWriter<AttackEvent>,
+) {
+    let mut attacked: AHashMap<Entity, Vec<Entity>> = AHashMap::new();
+    for (assailant, attacking) in assailants.iter() {
+        attacked
+            .entry(attacking.enemy())
+            .or_default()
+            .push(assailant);
+    }
+
+    for (
+        attacker,
+        &player,
+        &object_type,
+        transform,
+        cannon,
+        &stance,
+        path,
+        attack_moving,
+        priority,
+        guarding,
+        attacking,
+    ) in attackers.iter()
+    {
+        let attack_moving = attack_moving.is_some();
+        let position = transform.translation;
+        let range = cannon.range();
+        let guarded = guarding.map(|guarding| guarding.target());
+        let guarded_position = guarded.and_then(|guarded| {
+            targets
+                .get(guarded)
+                .ok()
+                .map(|(_, _, transform, ..)| transform.translation)
+        });
+
+        let distance_to = |target: Entity, max_distance: f32| {
+            let (&target_player, _, target_transform, .., flying, detection) =
+                targets.get(target).ok()?;
+            if diplomacy.are_allies(player, target_player) {
+                return None;
+            }
+            if flying.is_some() && !cannon.anti_air() {
+                return None;
+            }
+            if stealth::hidden_from(detection, player) {
+                return None;
+            }
+            let target_position = target_transform.translation;
+            if !guard::within_leash(guarded_position, target_position) {
+                return None;
+            }
+            let distance = position.distance(target_position);
+            (distance <= max_distance).then_some(distance)
+        };
+
+        // Explicitly prioritized targets are attacked regardless of the
+        // stance.
+        if let Some(priority) = priority.map(|priority| priority.target()) {
+            if distance_to(priority, range).is_some() {
+                if attacking.map_or(true, |attacking| attacking.enemy() != priority) {
+                    events.send(AttackEvent::new(attacker, priority).automatic());
+                }
+                continue;
+            }
+        }
+
+        // Guarding units are considered idle even while following the
+        // guarded entity.
+        let idle = path.is_none() || guarding.is_some();
+        if attacking.is_some() || !stance.engages(idle, attack_moving) {
+            continue;
+        }
+
+        let evaluate = |candidate: Entity, distance: f32| {
+            let (_, &target_type, _, health, cannon, target_attacking, ..) =
+                targets.get(candidate).unwrap();
+
+            let threat = match (cannon, target_attacking) {
+                (None, _) => Threat::None,
+                (Some(_), Some(target_attacking))
+                    if target_attacking.enemy() == attacker
+                        || Some(target_attacking.enemy()) == guarded =>
+                {
+                    Threat::AttackingSelf
+                }
+                (Some(_), _) => Threat::Armed,
+            };
+            let building = matches!(
+                target_type,
+                ObjectType::Active(ActiveObjectType::Building(_))
+            );
+
+            Candidate::new(
+                candidate,
+                distance / range,
+                building,
+                health.fraction(),
+                threat,
+            )
+        };
+
+        let unit_type = match object_type {
+            ObjectType::Active(ActiveObjectType::Unit(unit_type)) => Some(unit_type),
+            _ => None,
+        };
+        let weights = priorities.weights(unit_type);
+
+        // Attackers of the guarded entity are engaged even when they are out
+        // of range of the guarding unit.
+        let defended = guarded
+            .and_then(|guarded| attacked.get(&guarded))
+            .and_then(|assailants| {
+                targeting::select(
+                    &weights,
+                    assailants.iter().filter_map(|&assailant| {
+                        let distance = distance_to(assailant, f32::INFINITY)?;
+                        Some(evaluate(assailant, distance))
+                    }),
+                )
+            });
+
+        let enemy = defended.or_else(|| {
+            let aabb = Aabb::new(
+                Point::from(position - Vec3::splat(range)),
+                Point::from(position + Vec3::splat(range)),
+            );
+            let candidates = entities
+                .query_aabb(&aabb, Some(attacker))
+                .filter_map(|candidate| {
+                    let distance = distance_to(candidate, range)?;
+                    Some(evaluate(candidate, distance))
+                });
+            targeting::select(&weights, candidates)
+        });
+
+        if let Some(enemy) = enemy {
+            events.send(AttackEvent::new(attacker, enemy).automatic());
+        }
+    }
+}
+
+fn resume(
+    mut commands: Commands,
+    mut removed: RemovedComponents<Attacking>,
+    units: Query<(Entity, &AttackMoving, Option<&PathTarget>), Without<Attacking>>,
+    mut path_events: EventWriter<UpdateEntityPath>,
+) {
+    let removed: Vec<Entity> = removed.iter().collect();
+    for (entity, attack_moving, path_target) in units.iter() {
+        if removed.contains(&entity) {
+            // The engagement is over, continue to the attack-move target.
+            path_events.send(UpdateEntityPath::new(
+                entity,
+                PathTarget::new(attack_moving.target(), PathQueryProps::exact(), false),
+            ));
+        } else if path_target.is_none() {
+            // The target was either reached or it is unreachable.
+            commands.entity(entity).remove::<AttackMoving>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stance() {
+        assert!(Stance::Aggressive.engages(false, false));
+        assert!(!Stance::Defensive.engages(false, false));
+        assert!(Stance::Defensive.engages(true, false));
+        assert!(Stance::Defensive.engages(false, true));
+        assert!(!Stance::HoldFire.engages(true, true));
+        assert!(Stance::HoldPosition.engages(false, false));
+
+        assert!(Stance::Aggressive.chases(true, false));
+        assert!(!Stance::Defensive.chases(true, false));
+        assert!(Stance::Defensive.chases(false, false));
+        assert!(Stance::Defensive.chases(true, true));
+        assert!(Stance::HoldFire.chases(false, false));
+        assert!(!Stance::HoldPosition.chases(false, false));
+        assert!(!Stance::HoldPosition.chases(true, true));
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet,
+    diplomacy::Diplomacy,
+    flags::Flags,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    objects::{MovableSolid, StaticSolid},
+    player::Player,
+    projection::ToFlat,
+};
+use de_objects::{Cloak, Detector};
+
+use crate::{laser::LaserFireEvent, projectile::ProjectileFireEvent, AttackingSet};
+
+pub(crate) struct StealthPlugin;
+
+impl Plugin for StealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            init.in_base_set(GameSet::PostUpdate)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_system(
+            reveal
+                .in_base_set(GameSet::Update)
+                .run_if(in_state(GameState::Playing))
+                .in_set(AttackingSet::Fire),
+        )
+        .add_system(
+            detect
+                .in_base_set(GameSet::PostMovement)
+                .run_if(in_state(GameState::Playing))
+                .in_set(StealthSet::Detect),
+        )
+        .add_system(
+            conceal
+                .in_base_set(GameSet::PostMovement)
+                .run_if(in_state(GameState::Playing))
+                .in_set(StealthSet::Conceal)
+                .after(StealthSet::Detect),
+        );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum StealthSet {
+    Detect,
+    Conceal,
+}
+
+/// Players to whom a cloaked object (see [`Cloak`]) is visible. These are
+/// allies of its owner, all players while the object is revealed after it
+/// fired and players allied with the owner of a detector in range.
+///
+/// Cloaked objects cannot be attacked by players to whom they are not
+/// visible.
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Detection(Flags);
+
+impl Detection {
+    /// Returns true if the object is visible to the player.
+    pub fn detected_by(&self, player: Player) -> bool {
+        self.0.get(player.to_num().into())
+    }
+
+    fn set(&mut self, player: Player, value: bool) {
+        self.0.set(player.to_num().into(), value);
+    }
+}
+
+/// Marker of cloaked objects which are not visible to the player controlled
+/// by the user of this computer.
+#[derive(Component)]
+pub struct Concealed;
+
+/// Returns true if an object is not visible to a player. Objects without a
+/// cloak (and thus without [`Detection`]) are always visible.
+pub(crate) fn hidden_from(detection: Option<&Detection>, player: Player) -> bool {
+    detection.map_or(false, |detection| !detection.detected_by(player))
+}
+
+fn init(mut commands: Commands, cloaks: Query<Entity, (Added<Cloak>, Without<Detection>)>) {
+    for entity in cloaks.iter() {
+        commands.entity(entity).insert(Detection::default());
+    }
+}
+
+/// Reveals cloaked objects which fired and lets objects cloak again once
+/// their reveal time elapsed.
+fn reveal(
+    time: Res<Time>,
+    mut lasers: EventReader<LaserFireEvent>,
+    mut projectiles: EventReader<ProjectileFireEvent>,
+    mut cloaks: Query<&mut Cloak>,
+) {
+    for mut cloak in cloaks.iter_mut() {
+        // Avoid change detection when possible.
+        if cloak.revealed() {
+            cloak.tick(time.delta());
+        }
+    }
+
+    let attackers = lasers
+        .iter()
+        .map(|event| event.attacker())
+        .chain(projectiles.iter().map(|event| event.attacker()));
+    for attacker in attackers {
+        if let Ok(mut cloak) = cloaks.get_mut(attacker) {
+            cloak.reveal();
+        }
+    }
+}
+
+fn detect(
+    conf: Res<GameConfig>,
+    diplomacy: Res<Diplomacy>,
+    detectors: Query<(&Player, &Transform, &Detector)>,
+    mut cloaked: Query<(&Player, &Transform, &Cloak, &mut Detection)>,
+) {
+    for (&owner, transform, cloak, mut detection) in cloaked.iter_mut() {
+        let position = transform.translation.to_flat();
+
+        let mut detected = Detection::default();
+        for player in conf.players() {
+            let visible = cloak.revealed()
+                || diplomacy.are_allies(owner, player)
+                || detectors
+                    .iter()
+                    .any(|(&detector_owner, detector_transform, detector)| {
+                        diplomacy.are_allies(player, detector_owner)
+                            && detector_transform.translation.to_flat().distance(position)
+                                <= detector.range()
+                    });
+            detected.set(player, visible);
+        }
+
+        // Avoid change detection when possible.
+        if *detection != detected {
+            *detection = detected;
+        }
+    }
+}
+
+type Solids = Or<(With<MovableSolid>, With<StaticSolid>)>;
+
+/// Hides cloaked objects which are not visible to the playable player.
+///
+/// Carried units are neither movable nor static solids and they stay hidden.
+fn conceal(
+    mut commands: Commands,
+    conf: Res<GameConfig>,
+    mut objects: Query<(Entity, &Detection, &mut Visibility, Option<&Concealed>), Solids>,
+) {
+    let playable = conf.locals().playable();
+    for (entity, detection, mut visibility, concealed) in objects.iter_mut() {
+        let hidden = !detection.detected_by(playable);
+        if hidden != concealed.is_some() {
+            if hidden {
+                commands.entity(entity).insert(Concealed);
+            } else {
+                commands.entity(entity).remove::<Concealed>();
+            }
+        }
+
+        let value = if hidden {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        // Avoid change detection when possible.
+        if *visibility != value {
+            *visibility = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detection() {
+        let mut detection = Detection::default();
+        assert!(!detection.detected_by(Player::Player1));
+        assert!(hidden_from(Some(&detection), Player::Player1));
+        assert!(!hidden_from(None, Player::Player1));
+
+        detection.set(Player::Player2, true);
+        assert!(!detection.detected_by(Player::Player1));
+        assert!(detection.detected_by(Player::Player2));
+        assert!(!hidden_from(Some(&detection), Player::Player2));
+        assert!(hidden_from(Some(&detection), Player::Player4));
+    }
+}
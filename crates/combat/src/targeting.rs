@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, gamestate::GameState, objects::UnitType};
+use enum_map::{enum_map, EnumMap};
+
+use crate::AttackingSet;
+
+pub(crate) struct TargetingPlugin;
+
+impl Plugin for TargetingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TargetPriorities>()
+            .add_event::<SetPriorityTargetEvent>()
+            .add_system(
+                set_priority
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(TargetingSet::SetPriority)
+                    .before(AttackingSet::Attack),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum TargetingSet {
+    SetPriority,
+}
+
+/// Send this event to make an object focus its fire on a particular enemy
+/// whenever it is within range, or to cancel such focus.
+pub struct SetPriorityTargetEvent {
+    entity: Entity,
+    target: Option<Entity>,
+}
+
+impl SetPriorityTargetEvent {
+    /// # Arguments
+    ///
+    /// * `entity` - the attacking object.
+    ///
+    /// * `target` - the enemy to focus on or None if the focus shall be
+    ///   cancelled.
+    pub fn new(entity: Entity, target: Option<Entity>) -> Self {
+        Self { entity, target }
+    }
+}
+
+/// Enemy which is attacked, with precedence over all other targets, whenever
+/// it is within range.
+#[derive(Component)]
+pub(crate) struct PriorityTarget(Entity);
+
+impl PriorityTarget {
+    pub(crate) fn target(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Weights of individual criteria of automatic target selection. Target with
+/// the highest total score is attacked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TargetWeights {
+    distance: f32,
+    unit: f32,
+    building: f32,
+    damage: f32,
+    threat: f32,
+}
+
+impl TargetWeights {
+    /// # Arguments
+    ///
+    /// * `distance` - penalty for distance to the target relative to the
+    ///   attacker range.
+    ///
+    /// * `unit` - bonus for targets which are units.
+    ///
+    /// * `building` - bonus for targets which are buildings.
+    ///
+    /// * `damage` - bonus for lost fraction of target health.
+    ///
+    /// * `threat` - bonus for armed targets. Targets attacking the attacker
+    ///   receive full bonus, other armed targets receive half of the bonus.
+    pub const fn new(distance: f32, unit: f32, building: f32, damage: f32, threat: f32) -> Self {
+        Self {
+            distance,
+            unit,
+            building,
+            damage,
+            threat,
+        }
+    }
+
+    fn score(&self, candidate: &Candidate) -> f32 {
+        let type_bonus = if candidate.building {
+            self.building
+        } else {
+            self.unit
+        };
+        let threat = match candidate.threat {
+            Threat::None => 0.,
+            Threat::Armed => 0.5,
+            Threat::AttackingSelf => 1.,
+        };
+
+        type_bonus - self.distance * candidate.distance
+            + self.damage * (1. - candidate.health)
+            + self.threat * threat
+    }
+}
+
+impl Default for TargetWeights {
+    fn default() -> Self {
+        Self::new(1., 1., 0.5, 0.5, 1.)
+    }
+}
+
+/// Target selection weights of individual unit types.
+#[derive(Resource)]
+pub struct TargetPriorities(EnumMap<UnitType, TargetWeights>);
+
+impl TargetPriorities {
+    /// Returns target selection weights of a unit type. Objects other than
+    /// units (e.g. armed buildings) use default weights.
+    pub fn weights(&self, unit_type: Option<UnitType>) -> TargetWeights {
+        unit_type.map_or_else(TargetWeights::default, |unit_type| self.0[unit_type])
+    }
+
+    pub fn set(&mut self, unit_type: UnitType, weights: TargetWeights) {
+        self.0[unit_type] = weights;
+    }
+}
+
+impl Default for TargetPriorities {
+    fn default() -> Self {
+        Self(enum_map! {
+            UnitType::Attacker => TargetWeights::default(),
+        })
+    }
+}
+
+pub(crate) enum Threat {
+    /// The candidate cannot attack.
+    None,
+    /// The candidate is armed but it is not attacking the attacker.
+    Armed,
+    /// The candidate is attacking the attacker or the entity guarded by the
+    /// attacker.
+    AttackingSelf,
+}
+
+/// A potential target of automatic target selection.
+pub(crate) struct Candidate {
+    entity: Entity,
+    distance: f32,
+    building: bool,
+    health: f32,
+    threat: Threat,
+}
+
+impl Candidate {
+    /// # Arguments
+    ///
+    /// * `entity` - the potential target.
+    ///
+    /// * `distance` - distance to the target relative to the attacker
+    ///   range, i.e. a number between 0 and 1 for targets within the range.
+    ///
+    /// * `building` - whether the target is a building.
+    ///
+    /// * `health` - remaining fraction of target health.
+    ///
+    /// * `threat` - threat of the target to the attacker.
+    pub(crate) fn new(
+        entity: Entity,
+        distance: f32,
+        building: bool,
+        health: f32,
+        threat: Threat,
+    ) -> Self {
+        Self {
+            entity,
+            distance,
+            building,
+            health,
+            threat,
+        }
+    }
+
+    pub(crate) fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+/// Returns the candidate with the highest score. Ties are broken by the
+/// entity so that the selection is deterministic.
+pub(crate) fn select(
+    weights: &TargetWeights,
+    candidates: impl Iterator<Item = Candidate>,
+) -> Option<Entity> {
+    candidates
+        .map(|candidate| (weights.score(&candidate), candidate.entity()))
+        .max_by(|a, b| match a.0.total_cmp(&b.0) {
+            Ordering::Equal => b.1.cmp(&a.1),
+            ordering => ordering,
+        })
+        .map(|(_, entity)| entity)
+}
+
+fn set_priority(mut commands: Commands, mut events: EventReader<SetPriorityTargetEvent>) {
+    for event in events.iter() {
+        let Some(mut entity_commands) = commands.get_entity(event.entity) else {
+            continue;
+        };
+        match event.target {
+            Some(target) => entity_commands.insert(PriorityTarget(target)),
+            None => entity_commands.remove::<PriorityTarget>(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+        let c = Entity::from_raw(3);
+        let weights = TargetWeights::default();
+
+        assert_eq!(select(&weights, std::iter::empty()), None);
+
+        // Closer target wins.
+        let candidates = vec![
+            Candidate::new(a, 0.8, false, 1., Threat::None),
+            Candidate::new(b, 0.2, false, 1., Threat::None),
+        ];
+        assert_eq!(select(&weights, candidates.into_iter()), Some(b));
+
+        // Threat outweighs distance.
+        let candidates = vec![
+            Candidate::new(a, 0.8, false, 1., Threat::AttackingSelf),
+            Candidate::new(b, 0.2, false, 1., Threat::None),
+        ];
+        assert_eq!(select(&weights, candidates.into_iter()), Some(a));
+
+        // Units are preferred over buildings, damaged targets are preferred.
+        let candidates = vec![
+            Candidate::new(a, 0.5, true, 1., Threat::None),
+            Candidate::new(b, 0.5, false, 1., Threat::None),
+            Candidate::new(c, 0.5, false, 0.2, Threat::None),
+        ];
+        assert_eq!(select(&weights, candidates.into_iter()), Some(c));
+
+        // Ties are broken by the entity.
+        let candidates = vec![
+            Candidate::new(c, 0.5, false, 1., Threat::Armed),
+            Candidate::new(a, 0.5, false, 1., Threat::Armed),
+        ];
+        assert_eq!(select(&weights, candidates.into_iter()), Some(a));
+
+        let buildings_first = TargetWeights::new(1., 0., 2., 0.5, 1.);
+        let candidates = vec![
+            Candidate::new(a, 0.5, true, 1., Threat::None),
+            Candidate::new(b, 0.1, false, 1., Threat::Armed),
+        ];
+        assert_eq!(select(&buildings_first, candidates.into_iter()), Some(a));
+    }
+}
@@ -12,12 +12,15 @@ use bevy::{
     },
 };
 use de_core::{
-    baseset::GameSet, cleanup::DespawnOnGameExit, gamestate::GameState, state::AppState,
+    baseset::GameSet, cleanup::DespawnOnGameExit, gamestate::GameState, pool::EntityPool,
+    state::AppState,
 };
 use parry3d::query::Ray;
 
 const TRAIL_LIFESPAN: Duration = Duration::from_millis(500);
 const TRAIL_THICKNESS: f32 = 0.1;
+/// Maximum number of faded out trails kept for reuse.
+const POOL_CAPACITY: usize = 4096;
 
 pub(crate) struct TrailPlugin;
 
@@ -116,20 +119,41 @@ fn spawn(
     mut materials: ResMut<Assets<TrailMaterial>>,
     time: Res<Time>,
     mesh: Res<MeshHandle>,
+    mut pool: ResMut<EntityPool<Trail>>,
+    mut trails: Query<(
+        &mut Trail,
+        &mut Transform,
+        &mut Visibility,
+        &Handle<TrailMaterial>,
+    )>,
     mut events: EventReader<TrailEvent>,
 ) {
+    let start_time = time.elapsed_seconds_wrapped();
+
     for event in events.iter() {
-        let material = materials.add(TrailMaterial::new(time.elapsed_seconds_wrapped()));
+        let transform = Transform {
+            translation: event.ray().origin.into(),
+            rotation: Quat::from_rotation_arc(Vec3::X, event.ray().dir.normalize().into()),
+            scale: Vec3::new(event.ray().dir.norm(), 1., 1.),
+        };
+
+        if let Some(entity) = pool.take(|entity| trails.contains(entity)) {
+            let (mut trail, mut trail_transform, mut visibility, material) =
+                trails.get_mut(entity).unwrap();
+            *trail = Trail::default();
+            *trail_transform = transform;
+            *visibility = Visibility::Inherited;
+            if let Some(material) = materials.get_mut(material) {
+                material.start_time = start_time;
+            }
+            continue;
+        }
 
         commands.spawn((
             MaterialMeshBundle::<TrailMaterial> {
                 mesh: mesh.0.clone(),
-                material,
-                transform: Transform {
-                    translation: event.ray().origin.into(),
-                    rotation: Quat::from_rotation_arc(Vec3::X, event.ray().dir.normalize().into()),
-                    scale: Vec3::new(event.ray().dir.norm(), 1., 1.),
-                },
+                material: materials.add(TrailMaterial::new(start_time)),
+                transform,
                 ..Default::default()
             },
             Trail::default(),
@@ -140,11 +164,26 @@ fn spawn(
     }
 }
 
-fn update(mut commands: Commands, time: Res<Time>, mut query: Query<(Entity, &mut Trail)>) {
-    for (entity, mut trail) in query.iter_mut() {
+/// Ticks all visible trails. Finished trails are hidden and released to the
+/// pool.
+fn update(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pool: ResMut<EntityPool<Trail>>,
+    mut query: Query<(Entity, &mut Trail, &mut Visibility)>,
+) {
+    for (entity, mut trail, mut visibility) in query.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
         trail.tick(time.delta());
         if trail.finished() {
-            commands.entity(entity).despawn();
+            if pool.release(entity) {
+                *visibility = Visibility::Hidden;
+            } else {
+                commands.entity(entity).despawn();
+            }
         }
     }
 }
@@ -152,10 +191,12 @@ fn update(mut commands: Commands, time: Res<Time>, mut query: Query<(Entity, &mu
 fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
     let mesh = meshes.add(generate_trail_mesh());
     commands.insert_resource(MeshHandle(mesh));
+    commands.insert_resource(EntityPool::<Trail>::new(POOL_CAPACITY));
 }
 
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<MeshHandle>();
+    commands.remove_resource::<EntityPool<Trail>>();
 }
 
 /// This generates a trail mesh starting at (0, 0, 0) and pointing towards +X
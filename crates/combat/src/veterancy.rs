@@ -0,0 +1,216 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet,
+    diplomacy::Diplomacy,
+    gamestate::GameState,
+    objects::{ActiveObjectType, ObjectType},
+    player::Player,
+};
+use de_objects::{Health, LaserCannon};
+use de_spawner::SpawnerSet;
+
+use crate::AttackingSet;
+
+/// Experience awarded for destruction of an enemy unit.
+const UNIT_EXPERIENCE: u32 = 1;
+/// Experience awarded for destruction of an enemy building.
+const BUILDING_EXPERIENCE: u32 = 3;
+
+pub(crate) struct VeterancyPlugin;
+
+impl Plugin for VeterancyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<KillEvent>()
+            .add_system(
+                init.in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                award
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .after(AttackingSet::Fire)
+                    .before(SpawnerSet::Destroyer),
+            );
+    }
+}
+
+/// This event is sent when an object is destroyed by a hit of another
+/// object.
+///
+/// The event is sent by the system which applies the damage, thus
+/// experience is awarded only where the hit is simulated.
+pub(crate) struct KillEvent {
+    killer: Entity,
+    victim: Entity,
+}
+
+impl KillEvent {
+    pub(crate) fn new(killer: Entity, victim: Entity) -> Self {
+        Self { killer, victim }
+    }
+
+    fn killer(&self) -> Entity {
+        self.killer
+    }
+
+    fn victim(&self) -> Entity {
+        self.victim
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rank {
+    Recruit,
+    Veteran,
+    Elite,
+    Hero,
+}
+
+impl Rank {
+    const ALL: [Self; 4] = [Self::Recruit, Self::Veteran, Self::Elite, Self::Hero];
+
+    fn from_experience(experience: u32) -> Self {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .find(|rank| experience >= rank.threshold())
+            .unwrap()
+    }
+
+    /// Minimum experience needed to reach the rank.
+    pub fn threshold(self) -> u32 {
+        match self {
+            Self::Recruit => 0,
+            Self::Veteran => 3,
+            Self::Elite => 8,
+            Self::Hero => 15,
+        }
+    }
+
+    /// Multiplier of damage inflicted by objects of this rank.
+    pub fn damage_multiplier(self) -> f32 {
+        match self {
+            Self::Recruit => 1.,
+            Self::Veteran => 1.15,
+            Self::Elite => 1.3,
+            Self::Hero => 1.5,
+        }
+    }
+
+    /// Multiplier of maximum health of objects of this rank.
+    pub fn health_multiplier(self) -> f32 {
+        match self {
+            Self::Recruit => 1.,
+            Self::Veteran => 1.1,
+            Self::Elite => 1.2,
+            Self::Hero => 1.35,
+        }
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Recruit => write!(f, "Recruit"),
+            Self::Veteran => write!(f, "Veteran"),
+            Self::Elite => write!(f, "Elite"),
+            Self::Hero => write!(f, "Hero"),
+        }
+    }
+}
+
+/// Experience gained by an object by destroying enemy objects.
+#[derive(Component, Default)]
+pub struct Experience(u32);
+
+impl Experience {
+    pub fn experience(&self) -> u32 {
+        self.0
+    }
+
+    pub fn rank(&self) -> Rank {
+        Rank::from_experience(self.0)
+    }
+
+    /// Adds experience and returns the new rank if it has changed.
+    fn gain(&mut self, experience: u32) -> Option<Rank> {
+        let old = self.rank();
+        self.0 = self.0.saturating_add(experience);
+        let new = self.rank();
+        if new == old {
+            None
+        } else {
+            Some(new)
+        }
+    }
+}
+
+/// Rank of an object to be displayed in the UI. The component is present only
+/// on objects with a rank higher than [`Rank::Recruit`].
+#[derive(Component)]
+pub struct RankBadge(Rank);
+
+impl RankBadge {
+    pub fn rank(&self) -> Rank {
+        self.0
+    }
+}
+
+fn init(mut commands: Commands, cannons: Query<Entity, Added<LaserCannon>>) {
+    for entity in cannons.iter() {
+        commands.entity(entity).insert(Experience::default());
+    }
+}
+
+fn award(
+    mut commands: Commands,
+    diplomacy: Res<Diplomacy>,
+    mut events: EventReader<KillEvent>,
+    victims: Query<(&Player, &ObjectType)>,
+    mut killers: Query<(&Player, &mut Experience, &mut Health)>,
+) {
+    for event in events.iter() {
+        let Ok((&victim_player, &victim_type)) = victims.get(event.victim()) else {
+            continue;
+        };
+        let Ok((&killer_player, mut killer_experience, mut health)) =
+            killers.get_mut(event.killer())
+        else {
+            continue;
+        };
+        if diplomacy.are_allies(killer_player, victim_player) {
+            continue;
+        }
+
+        let experience = match victim_type {
+            ObjectType::Active(ActiveObjectType::Building(_)) => BUILDING_EXPERIENCE,
+            ObjectType::Active(ActiveObjectType::Unit(_)) => UNIT_EXPERIENCE,
+            ObjectType::Inactive(_) => continue,
+        };
+
+        let old = killer_experience.rank();
+        if let Some(new) = killer_experience.gain(experience) {
+            health.scale_max(new.health_multiplier() / old.health_multiplier());
+            commands.entity(event.killer()).insert(RankBadge(new));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_experience() {
+        let mut experience = Experience::default();
+        assert_eq!(experience.rank(), Rank::Recruit);
+        assert_eq!(experience.gain(2), None);
+        assert_eq!(experience.gain(1), Some(Rank::Veteran));
+        assert_eq!(experience.gain(4), None);
+        assert_eq!(experience.gain(100), Some(Rank::Hero));
+        assert_eq!(experience.experience(), 107);
+    }
+}
@@ -0,0 +1,650 @@
+//! This module implements mapping of game actions (e.g. unit commands or
+//! camera movement) to physical keys and mouse buttons. Default bindings can
+//! be overridden from the configuration and the bindings can be changed while
+//! the game is running.
+
+use std::{cmp::Reverse, collections::HashMap, fmt};
+
+use bevy::prelude::*;
+use enum_map::{enum_map, Enum, EnumMap};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Number of control groups, each of them has its own action.
+pub const CONTROL_GROUPS: usize = 10;
+/// Number of camera bookmarks, each of them has its own action.
+pub const CAMERA_BOOKMARKS: usize = 4;
+/// Number of buttons (slots) of the command card, each of them has its own
+/// action.
+pub const CARD_SLOTS: usize = 8;
+
+/// A game action triggered by a key or mouse button press.
+#[derive(Enum, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    CameraLeft,
+    CameraRight,
+    CameraUp,
+    CameraDown,
+    /// Camera rotates and tilts with mouse movement while this is held.
+    CameraPivot,
+    /// Make the camera follow a selected unit.
+    CameraFollow,
+    /// Move the camera to the most recent attack on an allied unit or
+    /// building.
+    JumpToAlert,
+    Select,
+    /// Move to a location, attack an enemy or guard an ally.
+    Command,
+    AttackMove,
+    Patrol,
+    Focus,
+    PlaceBase,
+    PlacePowerHub,
+    StanceAggressive,
+    StanceDefensive,
+    StanceHoldFire,
+    StanceHoldPosition,
+    FormationLine,
+    FormationWedge,
+    FormationBox,
+    /// Toggle the in-game menu or discard building drafts.
+    Menu,
+    SelectAll,
+    SelectAllVisible,
+    /// Make the next subgroup (objects of the same type) of the selection
+    /// active.
+    CycleSubgroup,
+    Pause,
+    /// Recall a control group. The group is assigned when the binding is
+    /// pressed together with Ctrl.
+    Group0,
+    Group1,
+    Group2,
+    Group3,
+    Group4,
+    Group5,
+    Group6,
+    Group7,
+    Group8,
+    Group9,
+    /// Move the camera to a bookmarked location. The bookmark is stored when
+    /// the binding is pressed together with Ctrl.
+    Bookmark0,
+    Bookmark1,
+    Bookmark2,
+    Bookmark3,
+    /// Press a button of the command card. The slots are ordered row by row
+    /// from the top left corner of the card.
+    Card0,
+    Card1,
+    Card2,
+    Card3,
+    Card4,
+    Card5,
+    Card6,
+    Card7,
+}
+
+impl Action {
+    /// Control group actions ordered by their group number.
+    pub const GROUPS: [Self; CONTROL_GROUPS] = [
+        Self::Group0,
+        Self::Group1,
+        Self::Group2,
+        Self::Group3,
+        Self::Group4,
+        Self::Group5,
+        Self::Group6,
+        Self::Group7,
+        Self::Group8,
+        Self::Group9,
+    ];
+
+    /// Camera bookmark actions ordered by their bookmark number.
+    pub const BOOKMARKS: [Self; CAMERA_BOOKMARKS] = [
+        Self::Bookmark0,
+        Self::Bookmark1,
+        Self::Bookmark2,
+        Self::Bookmark3,
+    ];
+
+    /// Command card actions ordered by their slot number.
+    pub const CARD: [Self; CARD_SLOTS] = [
+        Self::Card0,
+        Self::Card1,
+        Self::Card2,
+        Self::Card3,
+        Self::Card4,
+        Self::Card5,
+        Self::Card6,
+        Self::Card7,
+    ];
+
+    /// Returns true if the action lasts for as long as its key (or mouse
+    /// button) is held. Modifiers are ignored for such actions.
+    ///
+    /// All other actions are triggered once by a key press (or a mouse
+    /// click).
+    pub fn is_held(self) -> bool {
+        matches!(
+            self,
+            Self::CameraLeft
+                | Self::CameraRight
+                | Self::CameraUp
+                | Self::CameraDown
+                | Self::CameraPivot
+        )
+    }
+
+    /// Returns control group number of group actions and None for all other
+    /// actions.
+    pub fn group(self) -> Option<usize> {
+        Self::GROUPS.iter().position(|&group| group == self)
+    }
+
+    /// Returns bookmark number of camera bookmark actions and None for all
+    /// other actions.
+    pub fn bookmark(self) -> Option<usize> {
+        Self::BOOKMARKS
+            .iter()
+            .position(|&bookmark| bookmark == self)
+    }
+
+    /// Returns command card slot number of card actions and None for all
+    /// other actions.
+    pub fn card_slot(self) -> Option<usize> {
+        Self::CARD.iter().position(|&slot| slot == self)
+    }
+
+    /// Returns true if the binding of the action pressed together with Ctrl
+    /// stores something (e.g. a control group) for a later use of the action.
+    fn stores_with_ctrl(self) -> bool {
+        self.group().is_some() || self.bookmark().is_some()
+    }
+
+    fn default_binding(self) -> Binding {
+        match self {
+            Self::CameraLeft => Binding::key(KeyCode::Left),
+            Self::CameraRight => Binding::key(KeyCode::Right),
+            Self::CameraUp => Binding::key(KeyCode::Up),
+            Self::CameraDown => Binding::key(KeyCode::Down),
+            Self::CameraPivot => Binding::mouse(MouseButton::Middle),
+            Self::CameraFollow => Binding::key(KeyCode::F),
+            Self::JumpToAlert => Binding::key(KeyCode::Back),
+            Self::Select => Binding::mouse(MouseButton::Left),
+            Self::Command => Binding::mouse(MouseButton::Right),
+            Self::AttackMove => Binding::mouse(MouseButton::Right).with_alt(),
+            Self::Patrol => Binding::mouse(MouseButton::Right).with_ctrl().with_alt(),
+            Self::Focus => Binding::mouse(MouseButton::Right).with_ctrl(),
+            Self::PlaceBase => Binding::key(KeyCode::B),
+            Self::PlacePowerHub => Binding::key(KeyCode::P),
+            Self::StanceAggressive => Binding::key(KeyCode::F1),
+            Self::StanceDefensive => Binding::key(KeyCode::F2),
+            Self::StanceHoldFire => Binding::key(KeyCode::F3),
+            Self::StanceHoldPosition => Binding::key(KeyCode::F4),
+            Self::FormationLine => Binding::key(KeyCode::F9),
+            Self::FormationWedge => Binding::key(KeyCode::F10),
+            Self::FormationBox => Binding::key(KeyCode::F11),
+            Self::Menu => Binding::key(KeyCode::Escape),
+            Self::SelectAll => Binding::key(KeyCode::A).with_ctrl(),
+            Self::SelectAllVisible => Binding::key(KeyCode::A).with_ctrl().with_shift(),
+            Self::CycleSubgroup => Binding::key(KeyCode::Tab),
+            Self::Pause => Binding::key(KeyCode::Pause),
+            Self::Group0 => Binding::key(KeyCode::Key0),
+            Self::Group1 => Binding::key(KeyCode::Key1),
+            Self::Group2 => Binding::key(KeyCode::Key2),
+            Self::Group3 => Binding::key(KeyCode::Key3),
+            Self::Group4 => Binding::key(KeyCode::Key4),
+            Self::Group5 => Binding::key(KeyCode::Key5),
+            Self::Group6 => Binding::key(KeyCode::Key6),
+            Self::Group7 => Binding::key(KeyCode::Key7),
+            Self::Group8 => Binding::key(KeyCode::Key8),
+            Self::Group9 => Binding::key(KeyCode::Key9),
+            Self::Bookmark0 => Binding::key(KeyCode::F5),
+            Self::Bookmark1 => Binding::key(KeyCode::F6),
+            Self::Bookmark2 => Binding::key(KeyCode::F7),
+            Self::Bookmark3 => Binding::key(KeyCode::F8),
+            Self::Card0 => Binding::key(KeyCode::Q),
+            Self::Card1 => Binding::key(KeyCode::W),
+            Self::Card2 => Binding::key(KeyCode::E),
+            Self::Card3 => Binding::key(KeyCode::R),
+            Self::Card4 => Binding::key(KeyCode::Z),
+            Self::Card5 => Binding::key(KeyCode::X),
+            Self::Card6 => Binding::key(KeyCode::C),
+            Self::Card7 => Binding::key(KeyCode::V),
+        }
+    }
+}
+
+/// A physical key or a mouse button.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum InputButton {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl fmt::Display for InputButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{key:?}"),
+            Self::Mouse(MouseButton::Other(button)) => write!(f, "Mouse {button}"),
+            Self::Mouse(button) => write!(f, "{button:?} mouse"),
+        }
+    }
+}
+
+/// State of modifier keys.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    #[serde(default)]
+    ctrl: bool,
+    #[serde(default)]
+    shift: bool,
+    #[serde(default)]
+    alt: bool,
+}
+
+impl Modifiers {
+    /// Returns modifiers currently held down.
+    pub fn pressed(keys: &Input<KeyCode>) -> Self {
+        Self {
+            ctrl: keys.any_pressed([KeyCode::LControl, KeyCode::RControl]),
+            shift: keys.any_pressed([KeyCode::LShift, KeyCode::RShift]),
+            alt: keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]),
+        }
+    }
+
+    pub fn ctrl(self) -> bool {
+        self.ctrl
+    }
+
+    pub fn shift(self) -> bool {
+        self.shift
+    }
+
+    pub fn alt(self) -> bool {
+        self.alt
+    }
+
+    /// Returns true if all modifiers of `other` are present in `self`.
+    fn contains(self, other: Self) -> bool {
+        (self.ctrl || !other.ctrl) && (self.shift || !other.shift) && (self.alt || !other.alt)
+    }
+
+    fn count(self) -> usize {
+        usize::from(self.ctrl) + usize::from(self.shift) + usize::from(self.alt)
+    }
+}
+
+/// Returns true if the key is a modifier key, i.e. it cannot be bound on its
+/// own.
+pub fn is_modifier(key: KeyCode) -> bool {
+    matches!(
+        key,
+        KeyCode::LControl
+            | KeyCode::RControl
+            | KeyCode::LShift
+            | KeyCode::RShift
+            | KeyCode::LAlt
+            | KeyCode::RAlt
+    )
+}
+
+/// A key or a mouse button pressed together with zero or more modifiers.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Binding {
+    #[serde(flatten)]
+    button: InputButton,
+    #[serde(flatten)]
+    modifiers: Modifiers,
+}
+
+impl Binding {
+    pub fn new(button: InputButton, modifiers: Modifiers) -> Self {
+        Self { button, modifiers }
+    }
+
+    pub fn key(key: KeyCode) -> Self {
+        Self::new(InputButton::Key(key), Modifiers::default())
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self::new(InputButton::Mouse(button), Modifiers::default())
+    }
+
+    pub fn with_ctrl(mut self) -> Self {
+        self.modifiers.ctrl = true;
+        self
+    }
+
+    pub fn with_shift(mut self) -> Self {
+        self.modifiers.shift = true;
+        self
+    }
+
+    pub fn with_alt(mut self) -> Self {
+        self.modifiers.alt = true;
+        self
+    }
+
+    pub fn button(&self) -> InputButton {
+        self.button
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns true if the key (or mouse button) is held down. Modifiers are
+    /// ignored.
+    pub fn pressed(&self, keys: &Input<KeyCode>, buttons: &Input<MouseButton>) -> bool {
+        match self.button {
+            InputButton::Key(key) => keys.pressed(key),
+            InputButton::Mouse(button) => buttons.pressed(button),
+        }
+    }
+
+    /// Returns true if the key (or mouse button) has been pressed during the
+    /// current frame. Modifiers are ignored.
+    pub fn just_pressed(&self, keys: &Input<KeyCode>, buttons: &Input<MouseButton>) -> bool {
+        match self.button {
+            InputButton::Key(key) => keys.just_pressed(key),
+            InputButton::Mouse(button) => buttons.just_pressed(button),
+        }
+    }
+
+    /// Returns true if the key (or mouse button) has been released during the
+    /// current frame.
+    pub fn just_released(&self, keys: &Input<KeyCode>, buttons: &Input<MouseButton>) -> bool {
+        match self.button {
+            InputButton::Key(key) => keys.just_released(key),
+            InputButton::Mouse(button) => buttons.just_released(button),
+        }
+    }
+
+    /// Returns all key combinations occupied by an action bound to this
+    /// binding.
+    fn occupied(self, action: Action) -> Vec<Modifiers> {
+        let mut occupied = vec![self.modifiers];
+        if action.stores_with_ctrl() {
+            occupied.push(self.with_ctrl().modifiers);
+        }
+        occupied
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{}", self.button)
+    }
+}
+
+/// Currently used bindings of all actions.
+///
+/// When a key (or a mouse button) is pressed, the action whose binding
+/// matches the key and requires the largest number of the held modifiers is
+/// triggered. Thus for example Right mouse button triggers
+/// [`Action::Command`] even while Shift is held unless another action is
+/// bound to Shift+Right mouse button.
+#[derive(Resource, Debug, Clone)]
+pub struct KeyBindings {
+    bindings: EnumMap<Action, Binding>,
+}
+
+impl KeyBindings {
+    /// Creates bindings from defaults overridden by the given bindings.
+    pub fn with_overrides(overrides: &HashMap<Action, Binding>) -> Result<Self, BindingError> {
+        let mut bindings = Self::default();
+        for (&action, &binding) in overrides.iter() {
+            bindings.bindings[action] = binding;
+        }
+        bindings.validate()?;
+        Ok(bindings)
+    }
+
+    pub fn get(&self, action: Action) -> Binding {
+        self.bindings[action]
+    }
+
+    /// Iterates over all actions and their bindings.
+    pub fn iter(&self) -> impl Iterator<Item = (Action, Binding)> + '_ {
+        self.bindings
+            .iter()
+            .map(|(action, &binding)| (action, binding))
+    }
+
+    /// Binds an action to a new key combination. The bindings are left
+    /// unchanged if the new binding is invalid or conflicts with a binding of
+    /// another action.
+    pub fn rebind(&mut self, action: Action, binding: Binding) -> Result<(), BindingError> {
+        let mut new = self.clone();
+        new.bindings[action] = binding;
+        new.validate()?;
+        *self = new;
+        Ok(())
+    }
+
+    /// Returns the action triggered by a press of a key (or a mouse button)
+    /// while the given modifiers are held. Held actions (see
+    /// [`Action::is_held`]) are never returned.
+    pub fn resolve(&self, button: InputButton, modifiers: Modifiers) -> Option<Action> {
+        self.iter()
+            .filter(|(action, binding)| {
+                !action.is_held()
+                    && binding.button == button
+                    && modifiers.contains(binding.modifiers)
+            })
+            // The first action (in declaration order) wins if there are more
+            // equally specific actions.
+            .max_by_key(|(action, binding)| {
+                (binding.modifiers.count(), Reverse(action.into_usize()))
+            })
+            .map(|(action, _)| action)
+    }
+
+    fn validate(&self) -> Result<(), BindingError> {
+        for (action, binding) in self.iter() {
+            if let InputButton::Key(key) = binding.button {
+                if is_modifier(key) {
+                    return Err(BindingError::ModifierKey(action));
+                }
+            }
+            if action.stores_with_ctrl() && binding.modifiers.ctrl {
+                return Err(BindingError::ReservedCtrl(action));
+            }
+        }
+
+        let actions: Vec<(Action, Binding)> = self.iter().collect();
+        for (i, &(first, first_binding)) in actions.iter().enumerate() {
+            for &(second, second_binding) in &actions[i + 1..] {
+                if conflicting(first, first_binding, second, second_binding) {
+                    return Err(BindingError::Conflict {
+                        first,
+                        second,
+                        binding: second_binding,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: enum_map! { action => action.default_binding() },
+        }
+    }
+}
+
+/// Returns true if the two actions cannot be bound to the given bindings at
+/// the same time.
+fn conflicting(
+    first: Action,
+    first_binding: Binding,
+    second: Action,
+    second_binding: Binding,
+) -> bool {
+    if first_binding.button != second_binding.button {
+        return false;
+    }
+    if first.is_held() || second.is_held() {
+        return true;
+    }
+
+    let second_occupied = second_binding.occupied(second);
+    first_binding
+        .occupied(first)
+        .iter()
+        .any(|modifiers| second_occupied.contains(modifiers))
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum BindingError {
+    #[error("{first:?} and {second:?} are both bound to {binding}")]
+    Conflict {
+        first: Action,
+        second: Action,
+        binding: Binding,
+    },
+    #[error("{0:?} is bound to a modifier key")]
+    ModifierKey(Action),
+    #[error("{0:?} must not use Ctrl, it is reserved for storing of the group or bookmark")]
+    ReservedCtrl(Action),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_bindings() {
+        let bindings = KeyBindings::default();
+        assert!(bindings.validate().is_ok());
+
+        let right = InputButton::Mouse(MouseButton::Right);
+        assert_eq!(
+            bindings.resolve(right, Modifiers::default()),
+            Some(Action::Command)
+        );
+        let shift = Modifiers {
+            shift: true,
+            ..default()
+        };
+        assert_eq!(bindings.resolve(right, shift), Some(Action::Command));
+        let ctrl_alt = Modifiers {
+            ctrl: true,
+            alt: true,
+            ..default()
+        };
+        assert_eq!(bindings.resolve(right, ctrl_alt), Some(Action::Patrol));
+
+        let one = InputButton::Key(KeyCode::Key1);
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..default()
+        };
+        assert_eq!(bindings.resolve(one, ctrl), Some(Action::Group1));
+        assert_eq!(Action::Group1.group(), Some(1));
+
+        let f6 = InputButton::Key(KeyCode::F6);
+        assert_eq!(bindings.resolve(f6, ctrl), Some(Action::Bookmark1));
+        assert_eq!(
+            bindings.resolve(f6, Modifiers::default()),
+            Some(Action::Bookmark1)
+        );
+        assert_eq!(Action::Bookmark1.bookmark(), Some(1));
+        assert_eq!(Action::Group1.bookmark(), None);
+
+        let x = InputButton::Key(KeyCode::X);
+        assert_eq!(
+            bindings.resolve(x, Modifiers::default()),
+            Some(Action::Card5)
+        );
+        assert_eq!(Action::Card5.card_slot(), Some(5));
+        assert_eq!(Action::Group5.card_slot(), None);
+
+        let up = InputButton::Key(KeyCode::Up);
+        assert_eq!(bindings.resolve(up, Modifiers::default()), None);
+    }
+
+    #[test]
+    fn test_rebind() {
+        let mut bindings = KeyBindings::default();
+        bindings
+            .rebind(Action::Pause, Binding::key(KeyCode::Space))
+            .unwrap();
+        assert_eq!(bindings.get(Action::Pause), Binding::key(KeyCode::Space));
+
+        assert_eq!(
+            bindings.rebind(Action::Pause, Binding::key(KeyCode::B)),
+            Err(BindingError::Conflict {
+                first: Action::PlaceBase,
+                second: Action::Pause,
+                binding: Binding::key(KeyCode::B),
+            })
+        );
+        assert_eq!(bindings.get(Action::Pause), Binding::key(KeyCode::Space));
+
+        // Ctrl+B is free.
+        bindings
+            .rebind(Action::Pause, Binding::key(KeyCode::B).with_ctrl())
+            .unwrap();
+        // Ctrl+1 assigns group 1.
+        assert!(matches!(
+            bindings.rebind(Action::Pause, Binding::key(KeyCode::Key1).with_ctrl()),
+            Err(BindingError::Conflict { .. })
+        ));
+        // Held actions ignore modifiers.
+        assert!(matches!(
+            bindings.rebind(Action::Pause, Binding::key(KeyCode::Left).with_alt()),
+            Err(BindingError::Conflict { .. })
+        ));
+        assert_eq!(
+            bindings.rebind(Action::Group3, Binding::key(KeyCode::G).with_ctrl()),
+            Err(BindingError::ReservedCtrl(Action::Group3))
+        );
+        assert_eq!(
+            bindings.rebind(Action::Bookmark1, Binding::key(KeyCode::G).with_ctrl()),
+            Err(BindingError::ReservedCtrl(Action::Bookmark1))
+        );
+        assert_eq!(
+            bindings.rebind(Action::Menu, Binding::key(KeyCode::LShift)),
+            Err(BindingError::ModifierKey(Action::Menu))
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let overrides: HashMap<Action, Binding> =
+            serde_yaml::from_str("pause: {key: Space}\nattack_move: {mouse: Right, shift: true}")
+                .unwrap();
+        let bindings = KeyBindings::with_overrides(&overrides).unwrap();
+        assert_eq!(bindings.get(Action::Pause), Binding::key(KeyCode::Space));
+        assert_eq!(
+            bindings.get(Action::AttackMove),
+            Binding::mouse(MouseButton::Right).with_shift()
+        );
+        assert_eq!(
+            bindings.get(Action::Select),
+            Binding::mouse(MouseButton::Left)
+        );
+
+        let overrides: HashMap<Action, Binding> =
+            serde_yaml::from_str("pause: {key: Escape}").unwrap();
+        assert!(KeyBindings::with_overrides(&overrides).is_err());
+    }
+}
@@ -2,16 +2,23 @@
 //! This module implements final (i.e. parsed and validated) game configuration
 //! objects and their building from persistent configuration.
 
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
 
 use anyhow::{ensure, Context, Error, Result};
 use async_std::path::Path;
 use conf_macros::Config;
-use de_uom::{LogicalPixel, Metre};
+use de_uom::{InverseSecond, LogicalPixel, Metre};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::bundle_config;
+use crate::{
+    bindings::{Action, Binding, KeyBindings},
+    bundle_config,
+    settings::{Language, ShadowQuality},
+};
 
 // --------------------
 // Config structs hold deserialized and validated data before
@@ -47,6 +54,17 @@ pub struct Camera {
 
     #[ensure(*rotation_sensitivity > 0., "`rotation_sensitivity` must be greater than 0.0.")]
     rotation_sensitivity: f32,
+
+    #[ensure(scroll_speed.is_finite(), "`scroll_speed` must be a finite number.")]
+    #[ensure(*scroll_speed > 0., "`scroll_speed` must be greater than 0.0.")]
+    scroll_speed: f32,
+
+    edge_scrolling: bool,
+    zoom_to_cursor: bool,
+
+    #[ensure(follow_rate.is_finite(), "`follow_rate` must be a finite number.")]
+    #[ensure(*follow_rate > 0., "`follow_rate` must be greater than 0.0.")]
+    follow_rate: f32,
 }
 
 #[derive(Deserialize, Serialize, Config, Debug, Clone)]
@@ -55,6 +73,69 @@ pub struct AudioConf {
     #[ensure(*music_volume >= 0., "`music_volume` must be greater than or equal to 0.0.")]
     #[ensure(*music_volume <= 1., "`music_volume` must be smaller or equal to 1.0.")]
     music_volume: f32,
+
+    #[is_finite]
+    #[ensure(*combat_volume >= 0., "`combat_volume` must be greater than or equal to 0.0.")]
+    #[ensure(*combat_volume <= 1., "`combat_volume` must be smaller or equal to 1.0.")]
+    combat_volume: f32,
+
+    #[is_finite]
+    #[ensure(*unit_volume >= 0., "`unit_volume` must be greater than or equal to 0.0.")]
+    #[ensure(*unit_volume <= 1., "`unit_volume` must be smaller or equal to 1.0.")]
+    unit_volume: f32,
+
+    #[is_finite]
+    #[ensure(
+        *construction_volume >= 0.,
+        "`construction_volume` must be greater than or equal to 0.0."
+    )]
+    #[ensure(
+        *construction_volume <= 1.,
+        "`construction_volume` must be smaller or equal to 1.0."
+    )]
+    construction_volume: f32,
+
+    #[is_finite]
+    #[ensure(
+        *notification_volume >= 0.,
+        "`notification_volume` must be greater than or equal to 0.0."
+    )]
+    #[ensure(
+        *notification_volume <= 1.,
+        "`notification_volume` must be smaller or equal to 1.0."
+    )]
+    notification_volume: f32,
+}
+
+#[derive(Deserialize, Serialize, Config, Debug, Clone)]
+pub struct LightingConf {
+    #[is_finite]
+    #[ensure(*day_length > 0., "`day_length` must be greater than 0.0.")]
+    day_length: f32,
+}
+
+#[derive(Deserialize, Serialize, Config, Debug, Clone)]
+pub struct GraphicsConf {
+    shadows: ShadowQuality,
+    msaa: bool,
+
+    #[ensure(resolution_scale.is_finite(), "`resolution_scale` must be a finite number.")]
+    #[ensure(*resolution_scale >= 0.5, "`resolution_scale` must be greater or equal to 0.5.")]
+    #[ensure(*resolution_scale <= 2., "`resolution_scale` must be smaller or equal to 2.0.")]
+    resolution_scale: f32,
+
+    vsync: bool,
+}
+
+#[derive(Deserialize, Serialize, Config, Debug, Clone)]
+pub struct LocalizationConf {
+    language: Language,
+}
+
+#[derive(Deserialize, Serialize, Config, Debug, Clone, Default)]
+pub struct Bindings {
+    #[check(|actions: &HashMap<Action, Binding>| KeyBindings::with_overrides(actions).map(drop))]
+    actions: HashMap<Action, Binding>,
 }
 // --------------------
 
@@ -72,20 +153,63 @@ impl Default for MultiplayerConf {
 impl Default for Camera {
     fn default() -> Self {
         Self {
-            move_margin: 40.,
+            move_margin: 4.,
             min_distance: 20.,
             max_distance: 80.,
             wheel_zoom_sensitivity: 1.1,
             touchpad_zoom_sensitivity: 1.01,
             rotation_sensitivity: 0.008,
             scroll_inverted: false,
+            scroll_speed: 2.,
+            edge_scrolling: true,
+            zoom_to_cursor: true,
+            follow_rate: 5.,
         }
     }
 }
 
 impl Default for AudioConf {
     fn default() -> Self {
-        Self { music_volume: 1. }
+        Self {
+            music_volume: 1.,
+            combat_volume: 1.,
+            unit_volume: 1.,
+            construction_volume: 1.,
+            notification_volume: 1.,
+        }
+    }
+}
+
+impl Default for GraphicsConf {
+    fn default() -> Self {
+        Self {
+            shadows: ShadowQuality::High,
+            msaa: true,
+            resolution_scale: 1.,
+            vsync: true,
+        }
+    }
+}
+
+impl Default for LocalizationConf {
+    fn default() -> Self {
+        Self {
+            language: Language::English,
+        }
+    }
+}
+
+impl Default for LightingConf {
+    fn default() -> Self {
+        Self { day_length: 1200. }
+    }
+}
+
+impl TryInto<KeyBindings> for Bindings {
+    type Error = Error;
+
+    fn try_into(self) -> Result<KeyBindings> {
+        Ok(KeyBindings::with_overrides(&self.actions)?)
     }
 }
 
@@ -107,6 +231,10 @@ impl TryInto<CameraConf> for Camera {
             touchpad_zoom_sensitivity: self.touchpad_zoom_sensitivity,
             rotation_sensitivity: self.rotation_sensitivity,
             scroll_inverted: self.scroll_inverted,
+            scroll_speed: InverseSecond::new(self.scroll_speed),
+            edge_scrolling: self.edge_scrolling,
+            zoom_to_cursor: self.zoom_to_cursor,
+            follow_rate: InverseSecond::new(self.follow_rate),
         })
     }
 }
@@ -120,6 +248,10 @@ pub struct CameraConf {
     touchpad_zoom_sensitivity: f32,
     rotation_sensitivity: f32,
     scroll_inverted: bool,
+    scroll_speed: InverseSecond,
+    edge_scrolling: bool,
+    zoom_to_cursor: bool,
+    follow_rate: InverseSecond,
 }
 
 // ---- config impls ----
@@ -163,6 +295,29 @@ impl CameraConf {
     pub fn scroll_inverted(&self) -> bool {
         self.scroll_inverted
     }
+
+    /// Camera moves horizontally at speed `distance * scroll_speed`.
+    pub fn scroll_speed(&self) -> InverseSecond {
+        self.scroll_speed
+    }
+
+    /// Whether the camera moves horizontally while mouse cursor is within
+    /// [`Self::move_margin`] to a window edge.
+    pub fn edge_scrolling(&self) -> bool {
+        self.edge_scrolling
+    }
+
+    /// Whether zooming keeps the terrain point under mouse cursor fixed on
+    /// the screen.
+    pub fn zoom_to_cursor(&self) -> bool {
+        self.zoom_to_cursor
+    }
+
+    /// Camera following a unit closes distance to the unit exponentially at
+    /// this rate.
+    pub fn follow_rate(&self) -> InverseSecond {
+        self.follow_rate
+    }
 }
 
 impl MultiplayerConf {
@@ -186,11 +341,68 @@ impl AudioConf {
     pub fn music_volume(&self) -> f32 {
         self.music_volume
     }
+
+    /// Volume of weapon fire and explosions.
+    pub fn combat_volume(&self) -> f32 {
+        self.combat_volume
+    }
+
+    /// Volume of unit acknowledgements.
+    pub fn unit_volume(&self) -> f32 {
+        self.unit_volume
+    }
+
+    /// Volume of building construction.
+    pub fn construction_volume(&self) -> f32 {
+        self.construction_volume
+    }
+
+    /// Volume of alert notifications.
+    pub fn notification_volume(&self) -> f32 {
+        self.notification_volume
+    }
+}
+
+impl LightingConf {
+    /// Real time length (in seconds) of a full in-game day.
+    pub fn day_length(&self) -> f32 {
+        self.day_length
+    }
+}
+
+impl LocalizationConf {
+    /// Initial language of the user interface.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+}
+
+impl GraphicsConf {
+    pub fn shadows(&self) -> ShadowQuality {
+        self.shadows
+    }
+
+    /// Whether multi-sample anti-aliasing is enabled.
+    pub fn msaa(&self) -> bool {
+        self.msaa
+    }
+
+    pub fn resolution_scale(&self) -> f32 {
+        self.resolution_scale
+    }
+
+    pub fn vsync(&self) -> bool {
+        self.vsync
+    }
 }
 
 // Bundle configuration neatly into a single struct
 bundle_config!(
     camera: CameraConf: Camera, // Conf file -> Camera -> CameraConf
     multiplayer: MultiplayerConf: MultiplayerConf,  // Conf file -> MultiplayerConf
-    audio: AudioConf: AudioConf
+    audio: AudioConf: AudioConf,
+    lighting: LightingConf: LightingConf,
+    graphics: GraphicsConf: GraphicsConf,
+    localization: LocalizationConf: LocalizationConf,
+    bindings: KeyBindings: Bindings // Conf file -> Bindings -> KeyBindings
 );
@@ -5,19 +5,30 @@
 //!
 //! * Parsing, validation and configuration provisioning.
 
+mod bindings;
 mod conf;
 mod io;
 mod macros;
 mod plugin;
+mod settings;
 
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+pub use bindings::{
+    is_modifier, Action, Binding, BindingError, InputButton, KeyBindings, Modifiers,
+    CAMERA_BOOKMARKS, CARD_SLOTS, CONTROL_GROUPS,
+};
 pub use conf::*;
 use plugin::ConfPlugin;
+pub use plugin::ConfigLoadFailedEvent;
+use settings::SettingsPlugin;
+pub use settings::{GraphicsSettings, Language, ShadowQuality, RESOLUTION_SCALES};
 
 pub struct ConfigPluginGroup;
 
 impl PluginGroup for ConfigPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(ConfPlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(ConfPlugin)
+            .add(SettingsPlugin)
     }
 }
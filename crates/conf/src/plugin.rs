@@ -4,7 +4,6 @@ use bevy::{
 };
 use de_core::fs::conf_dir;
 use de_core::state::AppState;
-use de_gui::ToastEvent;
 use futures_lite::future;
 use iyes_progress::prelude::*;
 use tracing::error;
@@ -16,7 +15,8 @@ pub(super) struct ConfPlugin;
 
 impl Plugin for ConfPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(start_loading.in_schedule(OnEnter(AppState::AppLoading)))
+        app.add_event::<ConfigLoadFailedEvent>()
+            .add_system(start_loading.in_schedule(OnEnter(AppState::AppLoading)))
             .add_system(cleanup.in_schedule(OnExit(AppState::AppLoading)))
             .add_system(
                 poll_conf
@@ -26,6 +26,10 @@ impl Plugin for ConfPlugin {
     }
 }
 
+/// This event is sent when the configuration could not be loaded and the
+/// default configuration is used instead.
+pub struct ConfigLoadFailedEvent;
+
 #[derive(Resource)]
 struct LoadingTask(Task<Result<Configuration, ConfigLoadError>>);
 
@@ -45,7 +49,7 @@ fn poll_conf(
     mut commands: Commands,
     task: Option<ResMut<LoadingTask>>,
     conf: Option<Res<Configuration>>,
-    mut toasts: EventWriter<ToastEvent>,
+    mut failures: EventWriter<ConfigLoadFailedEvent>,
 ) -> Progress {
     if conf.is_some() {
         return true.into();
@@ -60,7 +64,7 @@ fn poll_conf(
                 }
                 Err(err) => {
                     error!("{err}");
-                    toasts.send(ToastEvent::new("Configuration loading failed."));
+                    failures.send(ConfigLoadFailedEvent);
                     commands.init_resource::<Configuration>();
                     true.into()
                 }
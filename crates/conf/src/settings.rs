@@ -0,0 +1,231 @@
+//! This module implements graphics settings which can be changed while the
+//! game is running. Initial values of the settings are given by the
+//! configuration, see [`crate::GraphicsConf`].
+//!
+//! Language of the user interface can be changed while the game is running
+//! too, see [`crate::LocalizationConf`]. The change itself is handled by the
+//! localization crate.
+//!
+//! Key bindings, which can be changed while the game is running too, are
+//! inserted as [`crate::KeyBindings`] resource at the same time.
+
+use std::fmt;
+
+use bevy::{
+    pbr::DirectionalLightShadowMap,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow},
+};
+use de_core::state::AppState;
+use enum_map::Enum;
+use serde::{Deserialize, Serialize};
+
+use crate::Configuration;
+
+/// Resolution scales offered to the user.
+pub const RESOLUTION_SCALES: [f32; 6] = [0.5, 0.75, 1., 1.25, 1.5, 2.];
+
+pub(crate) struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnExit(AppState::AppLoading)))
+            .add_system(apply.run_if(resource_exists_and_changed::<GraphicsSettings>()));
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ShadowQuality {
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl ShadowQuality {
+    /// Returns the next quality level, wrapping from the highest to off.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Low,
+            Self::Low => Self::Medium,
+            Self::Medium => Self::High,
+            Self::High => Self::Off,
+        }
+    }
+
+    pub fn enabled(self) -> bool {
+        self != Self::Off
+    }
+
+    /// Number of cascades of directional light shadow maps.
+    pub fn num_cascades(self) -> usize {
+        match self {
+            Self::Off | Self::Low => 2,
+            Self::Medium => 3,
+            Self::High => 5,
+        }
+    }
+
+    /// Size (in texels) of a side of directional light shadow maps.
+    pub fn shadow_map_size(self) -> usize {
+        match self {
+            Self::Off | Self::Low => 1024,
+            Self::Medium => 2048,
+            Self::High => 4096,
+        }
+    }
+}
+
+impl fmt::Display for ShadowQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "Off"),
+            Self::Low => write!(f, "Low"),
+            Self::Medium => write!(f, "Medium"),
+            Self::High => write!(f, "High"),
+        }
+    }
+}
+
+/// Language of the user interface.
+#[derive(Deserialize, Serialize, Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "cs")]
+    Czech,
+}
+
+impl Language {
+    /// Returns the next language, wrapping from the last to the first one.
+    pub fn next(self) -> Self {
+        match self {
+            Self::English => Self::Czech,
+            Self::Czech => Self::English,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    /// Name of the language in the language itself.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::English => write!(f, "English"),
+            Self::Czech => write!(f, "Čeština"),
+        }
+    }
+}
+
+/// Currently used graphics settings. Changes of the resource are applied to
+/// relevant Bevy resources automatically.
+#[derive(Resource, Clone)]
+pub struct GraphicsSettings {
+    shadows: ShadowQuality,
+    msaa: bool,
+    resolution_scale: f32,
+    vsync: bool,
+}
+
+impl GraphicsSettings {
+    pub fn shadows(&self) -> ShadowQuality {
+        self.shadows
+    }
+
+    pub fn set_shadows(&mut self, shadows: ShadowQuality) {
+        self.shadows = shadows;
+    }
+
+    /// Whether multi-sample anti-aliasing is enabled.
+    pub fn msaa(&self) -> bool {
+        self.msaa
+    }
+
+    pub fn set_msaa(&mut self, msaa: bool) {
+        self.msaa = msaa;
+    }
+
+    /// Scale factor applied on top of the native scale factor of the window.
+    /// Larger values lead to larger user interface.
+    pub fn resolution_scale(&self) -> f32 {
+        self.resolution_scale
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the scale is not a positive finite number.
+    pub fn set_resolution_scale(&mut self, scale: f32) {
+        assert!(scale.is_finite());
+        assert!(scale > 0.);
+        self.resolution_scale = scale;
+    }
+
+    pub fn vsync(&self) -> bool {
+        self.vsync
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) {
+        self.vsync = vsync;
+    }
+}
+
+fn setup(mut commands: Commands, conf: Res<Configuration>) {
+    let graphics = conf.graphics();
+    commands.insert_resource(GraphicsSettings {
+        shadows: graphics.shadows(),
+        msaa: graphics.msaa(),
+        resolution_scale: graphics.resolution_scale(),
+        vsync: graphics.vsync(),
+    });
+    commands.insert_resource(conf.bindings().clone());
+}
+
+fn apply(
+    settings: Res<GraphicsSettings>,
+    mut msaa: ResMut<Msaa>,
+    mut shadow_map: ResMut<DirectionalLightShadowMap>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    *msaa = if settings.msaa() {
+        Msaa::Sample4
+    } else {
+        Msaa::Off
+    };
+    shadow_map.size = settings.shadows().shadow_map_size();
+
+    for mut window in windows.iter_mut() {
+        window.present_mode = if settings.vsync() {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+
+        let scale = window.resolution.base_scale_factor() * settings.resolution_scale() as f64;
+        window.resolution.set_scale_factor_override(Some(scale));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shadow_quality() {
+        let mut quality = ShadowQuality::Off;
+        for _ in 0..4 {
+            quality = quality.next();
+        }
+        assert_eq!(quality, ShadowQuality::Off);
+        assert!(!ShadowQuality::Off.enabled());
+        assert!(ShadowQuality::High.num_cascades() > ShadowQuality::Low.num_cascades());
+    }
+
+    #[test]
+    fn test_language() {
+        assert_eq!(Language::English.next().next(), Language::English);
+        assert_eq!(
+            serde_yaml::from_str::<Language>("cs").unwrap(),
+            Language::Czech
+        );
+    }
+}
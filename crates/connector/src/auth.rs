@@ -0,0 +1,94 @@
+//! Verification of authentication tokens issued by DE Lobby.
+//!
+//! The tokens are JWTs signed with a secret shared between DE Lobby and DE
+//! Connector. A verified token proves the identity (user name) of a client.
+
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Verifier of client authentication tokens.
+#[derive(Clone)]
+pub(crate) struct Verifier {
+    key: DecodingKey,
+}
+
+impl Verifier {
+    /// # Arguments
+    ///
+    /// * `secret` - base64 encoded secret shared with DE Lobby.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` is not valid base64.
+    pub(crate) fn new(secret: &str) -> Self {
+        let secret = base64::decode(secret).expect("JWT secret is not valid base64");
+        Self {
+            key: DecodingKey::from_secret(secret.as_ref()),
+        }
+    }
+
+    /// Verifies an authentication token and returns the user name it was
+    /// issued to.
+    pub(crate) fn verify(&self, token: Option<&str>) -> Result<String, AuthError> {
+        let token = token.ok_or(AuthError::Missing)?;
+        decode::<Claims>(token, &self.key, &Validation::default())
+            .map(|data| data.claims.sub)
+            .map_err(|_| AuthError::Invalid)
+    }
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub(crate) enum AuthError {
+    #[error("authentication token is missing")]
+    Missing,
+    #[error("authentication token is invalid or expired")]
+    Invalid,
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{encode, get_current_timestamp, EncodingKey, Header};
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct TestClaims {
+        sub: &'static str,
+        exp: u64,
+    }
+
+    fn token(secret: &[u8], exp: u64) -> String {
+        encode(
+            &Header::default(),
+            &TestClaims { sub: "Indy", exp },
+            &EncodingKey::from_secret(secret),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify() {
+        // "xxxxxxxxxxxx" base64 encoded
+        let verifier = Verifier::new("eHh4eHh4eHh4eHh4");
+        let now = get_current_timestamp();
+
+        let valid = token(b"xxxxxxxxxxxx", now + 60);
+        assert_eq!(verifier.verify(Some(&valid)).unwrap(), "Indy");
+
+        assert_eq!(verifier.verify(None), Err(AuthError::Missing));
+        assert_eq!(verifier.verify(Some("garbage")), Err(AuthError::Invalid));
+
+        let forged = token(b"yyyyyyyyyyyy", now + 60);
+        assert_eq!(verifier.verify(Some(&forged)), Err(AuthError::Invalid));
+
+        let expired = token(b"xxxxxxxxxxxx", now - 3600);
+        assert_eq!(verifier.verify(Some(&expired)), Err(AuthError::Invalid));
+    }
+}
@@ -29,6 +29,12 @@ impl Clients {
         self.inner.write().await.free(addr)
     }
 
+    /// Returns the number of clients joined to a game or with a game spot
+    /// reservation.
+    pub(crate) async fn len(&self) -> usize {
+        self.inner.read().await.socket_to_game.len()
+    }
+
     /// Sets game for a client with a reservation. See [`Self::reserve`].
     ///
     /// # Panics
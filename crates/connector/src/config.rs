@@ -0,0 +1,389 @@
+//! Configuration of the connector.
+//!
+//! The configuration is loaded from an optional TOML file whose path is given
+//! by `DE_CONNECTOR_CONFIG` environment variable. All options of the file are
+//! optional, for example:
+//!
+//! ```toml
+//! address = "0.0.0.0"
+//! port = 8082
+//! game_ports = "8083-8100"
+//! max_games = 16
+//! max_players = 4
+//! peer_timeout_secs = 10
+//! grace_period_secs = 60
+//! motd = "Welcome!"
+//! metrics_addr = "127.0.0.1:9090"
+//! jwt_secret = "c2VjcmV0c2VjcmV0"
+//! ```
+//!
+//! Each option may be overridden by an environment variable, see the
+//! `*_VAR` constants below.
+
+use std::{
+    env::{self, VarError},
+    error::Error,
+    fs,
+    net::{IpAddr, SocketAddr},
+    num::ParseIntError,
+    ops::RangeInclusive,
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use de_net::{NetConf, MAX_MOTD_LEN};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::auth::Verifier;
+
+/// Name of the environment variable with the path to the configuration file.
+/// Default configuration is used if the variable is not set.
+const CONFIG_PATH_VAR: &str = "DE_CONNECTOR_CONFIG";
+/// Name of the environment variable overriding the local IP address the
+/// connector binds to, for example `127.0.0.1`.
+const ADDRESS_VAR: &str = "DE_CONNECTOR_ADDRESS";
+/// Name of the environment variable overriding the port of the main server.
+const PORT_VAR: &str = "DE_CONNECTOR_PORT";
+/// Name of the environment variable overriding the pool of ports used by game
+/// servers, for example `8083-8100`.
+const GAME_PORTS_VAR: &str = "DE_CONNECTOR_GAME_PORTS";
+/// Name of the environment variable overriding the maximum number of
+/// concurrently running games.
+const MAX_GAMES_VAR: &str = "DE_CONNECTOR_MAX_GAMES";
+/// Name of the environment variable overriding the maximum number of players
+/// in a single game.
+const MAX_PLAYERS_VAR: &str = "DE_CONNECTOR_MAX_PLAYERS";
+/// Name of the environment variable overriding the peer timeout in seconds.
+const PEER_TIMEOUT_VAR: &str = "DE_CONNECTOR_PEER_TIMEOUT_SECS";
+/// Name of the environment variable overriding the rejoin grace period in
+/// seconds.
+const GRACE_PERIOD_VAR: &str = "DE_CONNECTOR_GRACE_PERIOD_SECS";
+/// Name of the environment variable overriding the message of the day.
+const MOTD_VAR: &str = "DE_CONNECTOR_MOTD";
+/// Name of the environment variable overriding the address of the HTTP
+/// metrics endpoint, for example `127.0.0.1:9090`.
+const METRICS_ADDR_VAR: &str = "DE_CONNECTOR_METRICS_ADDR";
+/// Name of the environment variable overriding the base64 encoded secret
+/// shared with DE Lobby used to verify client authentication tokens.
+const JWT_SECRET_VAR: &str = "DE_CONNECTOR_JWT_SECRET";
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    address: Option<IpAddr>,
+    port: u16,
+    game_ports: Option<PortRange>,
+    max_games: Option<usize>,
+    max_players: Option<u8>,
+    peer_timeout_secs: u64,
+    grace_period_secs: u64,
+    motd: Option<String>,
+    metrics_addr: Option<SocketAddr>,
+    jwt_secret: Option<String>,
+}
+
+impl Config {
+    /// Loads the configuration file (if configured), applies overrides from
+    /// the environment and validates the result.
+    pub(crate) fn load() -> Result<Self> {
+        let mut config = match load_var::<String>(CONFIG_PATH_VAR)? {
+            Some(path) => {
+                let text = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read configuration file \"{path}\""))?;
+                toml::from_str(&text)
+                    .with_context(|| format!("Failed to parse configuration file \"{path}\""))?
+            }
+            None => Self::default(),
+        };
+
+        config.override_from_env()?;
+        config.validate().context("Invalid configuration")?;
+        Ok(config)
+    }
+
+    fn override_from_env(&mut self) -> Result<()> {
+        if let Some(address) = load_var(ADDRESS_VAR)? {
+            self.address = Some(address);
+        }
+        if let Some(port) = load_var(PORT_VAR)? {
+            self.port = port;
+        }
+        if let Some(game_ports) = load_var(GAME_PORTS_VAR)? {
+            self.game_ports = Some(game_ports);
+        }
+        if let Some(max_games) = load_var(MAX_GAMES_VAR)? {
+            self.max_games = Some(max_games);
+        }
+        if let Some(max_players) = load_var(MAX_PLAYERS_VAR)? {
+            self.max_players = Some(max_players);
+        }
+        if let Some(peer_timeout_secs) = load_var(PEER_TIMEOUT_VAR)? {
+            self.peer_timeout_secs = peer_timeout_secs;
+        }
+        if let Some(grace_period_secs) = load_var(GRACE_PERIOD_VAR)? {
+            self.grace_period_secs = grace_period_secs;
+        }
+        if let Some(motd) = load_var(MOTD_VAR)? {
+            self.motd = Some(motd);
+        }
+        if let Some(metrics_addr) = load_var(METRICS_ADDR_VAR)? {
+            self.metrics_addr = Some(metrics_addr);
+        }
+        if let Some(jwt_secret) = load_var(JWT_SECRET_VAR)? {
+            self.jwt_secret = Some(jwt_secret);
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.port == 0 {
+            return Err(ConfigError::Port);
+        }
+        if self.max_players == Some(0) {
+            return Err(ConfigError::MaxPlayers);
+        }
+        // The network stack requires the timeout to be longer than its
+        // keep-alive interval.
+        if self.peer_timeout_secs < 2 {
+            return Err(ConfigError::PeerTimeout);
+        }
+        if self
+            .motd
+            .as_ref()
+            .map_or(false, |motd| motd.len() > MAX_MOTD_LEN)
+        {
+            return Err(ConfigError::Motd);
+        }
+        if self
+            .jwt_secret
+            .as_ref()
+            .map_or(false, |secret| base64::decode(secret).is_err())
+        {
+            return Err(ConfigError::JwtSecret);
+        }
+        Ok(())
+    }
+
+    /// Local IP address to bind all sockets to. If None, dual-stack sockets
+    /// bound to all interfaces are used.
+    pub(crate) fn address(&self) -> Option<IpAddr> {
+        self.address
+    }
+
+    /// Port of the main server.
+    pub(crate) fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Pool of ports available to game servers. If None, system assigned
+    /// ports are used.
+    pub(crate) fn game_ports(&self) -> Option<&PortRange> {
+        self.game_ports.as_ref()
+    }
+
+    /// Maximum number of concurrently running games. If None, the number is
+    /// limited only by the available ports.
+    pub(crate) fn max_games(&self) -> Option<usize> {
+        self.max_games
+    }
+
+    /// Maximum number of players of a single game. If None, the number is
+    /// not limited.
+    pub(crate) fn max_players(&self) -> Option<u8> {
+        self.max_players
+    }
+
+    /// Configuration of the network stacks of the main server and of all game
+    /// servers.
+    pub(crate) fn net_conf(&self) -> NetConf {
+        NetConf::new(Duration::from_secs(self.peer_timeout_secs))
+    }
+
+    /// Time for which a disconnected player may rejoin a started game.
+    pub(crate) fn grace_period(&self) -> Duration {
+        Duration::from_secs(self.grace_period_secs)
+    }
+
+    /// Message of the day sent to clients as part of game listings.
+    pub(crate) fn motd(&self) -> Option<&str> {
+        self.motd.as_deref()
+    }
+
+    /// Address of the HTTP metrics endpoint. Metrics are not exported if
+    /// None.
+    pub(crate) fn metrics_addr(&self) -> Option<SocketAddr> {
+        self.metrics_addr
+    }
+
+    /// Verifier of client authentication tokens. Clients are not
+    /// authenticated if None.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration is not valid, see [`Self::load`].
+    pub(crate) fn verifier(&self) -> Option<Verifier> {
+        self.jwt_secret.as_deref().map(Verifier::new)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            address: None,
+            port: 8082,
+            game_ports: None,
+            max_games: None,
+            max_players: None,
+            peer_timeout_secs: 10,
+            grace_period_secs: 60,
+            motd: None,
+            metrics_addr: None,
+            jwt_secret: None,
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+enum ConfigError {
+    #[error("port must not be 0")]
+    Port,
+    #[error("maximum number of players must be at least 1")]
+    MaxPlayers,
+    #[error("peer timeout must be at least 2 seconds")]
+    PeerTimeout,
+    #[error("message of the day must be at most {MAX_MOTD_LEN} bytes long")]
+    Motd,
+    #[error("JWT secret must be base64 encoded")]
+    JwtSecret,
+}
+
+fn load_var<T>(name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Error + Send + Sync + 'static,
+{
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .with_context(|| format!("Failed to parse environment variable \"{name}\"")),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(error) => {
+            Err(error).with_context(|| format!("Failed to load environment variable \"{name}\""))
+        }
+    }
+}
+
+/// Non-empty inclusive range of network ports.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub(crate) struct PortRange(RangeInclusive<u16>);
+
+impl PortRange {
+    pub(crate) fn iter(&self) -> RangeInclusive<u16> {
+        self.0.clone()
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = PortRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((start, end)) = s.split_once('-') else {
+            return Err(PortRangeError::Format);
+        };
+
+        let start: u16 = start.trim().parse()?;
+        let end: u16 = end.trim().parse()?;
+        if start == 0 || start > end {
+            return Err(PortRangeError::Empty);
+        }
+
+        Ok(Self(start..=end))
+    }
+}
+
+impl TryFrom<String> for PortRange {
+    type Error = PortRangeError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub(crate) enum PortRangeError {
+    #[error("port range must be in the form `start-end`")]
+    Format,
+    #[error("invalid port number: {0}")]
+    Port(#[from] ParseIntError),
+    #[error("port range must be non-empty and must not include port 0")]
+    Empty,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config: Config = toml::from_str(
+            r#"
+            address = "127.0.0.1"
+            game_ports = "8083-8100"
+            max_players = 4
+            peer_timeout_secs = 5
+            motd = "Hello!"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.address(), Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(config.port(), 8082);
+        assert_eq!(config.game_ports(), Some(&PortRange(8083..=8100)));
+        assert_eq!(config.max_games(), None);
+        assert_eq!(config.max_players(), Some(4));
+        assert_eq!(config.net_conf().peer_timeout(), Duration::from_secs(5));
+        assert_eq!(config.grace_period(), Duration::from_secs(60));
+        assert_eq!(config.motd(), Some("Hello!"));
+        assert!(config.verifier().is_none());
+        assert!(config.validate().is_ok());
+
+        assert_eq!(toml::from_str::<Config>("").unwrap(), Config::default());
+        assert!(toml::from_str::<Config>("unknown = 1").is_err());
+        assert!(toml::from_str::<Config>("game_ports = \"8100-8083\"").is_err());
+
+        let config: Config = toml::from_str("peer_timeout_secs = 1").unwrap();
+        assert_eq!(config.validate(), Err(ConfigError::PeerTimeout));
+        let config: Config = toml::from_str("max_players = 0").unwrap();
+        assert_eq!(config.validate(), Err(ConfigError::MaxPlayers));
+        let config: Config = toml::from_str("jwt_secret = \"not base64!\"").unwrap();
+        assert_eq!(config.validate(), Err(ConfigError::JwtSecret));
+    }
+
+    #[test]
+    fn test_port_range() {
+        assert_eq!(
+            "8083-8100".parse::<PortRange>().unwrap(),
+            PortRange(8083..=8100)
+        );
+        assert_eq!(
+            " 8083 - 8083".parse::<PortRange>().unwrap(),
+            PortRange(8083..=8083)
+        );
+        assert_eq!(
+            "8083".parse::<PortRange>().unwrap_err(),
+            PortRangeError::Format
+        );
+        assert_eq!(
+            "8100-8083".parse::<PortRange>().unwrap_err(),
+            PortRangeError::Empty
+        );
+        assert!(matches!(
+            "8083-99999".parse::<PortRange>().unwrap_err(),
+            PortRangeError::Port(_)
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+use std::net::SocketAddr;
+
+use async_std::channel::{Receiver, Sender};
+use de_net::{ChatChannel, FromGame, OutPackage, Peers, MAX_CHAT_LEN};
+use tracing::{error, info, warn};
+
+use super::state::GameState;
+
+/// A chat message destined to other players in the game.
+pub(super) struct ChatMessage {
+    source: SocketAddr,
+    channel: ChatChannel,
+    text: String,
+}
+
+impl ChatMessage {
+    pub(super) fn new(source: SocketAddr, channel: ChatChannel, text: String) -> Self {
+        Self {
+            source,
+            channel,
+            text,
+        }
+    }
+}
+
+pub(super) async fn run(
+    port: u16,
+    messages: Receiver<ChatMessage>,
+    outputs: Sender<OutPackage>,
+    state: GameState,
+) {
+    info!("Starting game chat handler on port {port}...");
+
+    loop {
+        if messages.is_closed() {
+            break;
+        }
+
+        if outputs.is_closed() {
+            error!("Outputs channel on port {port} was unexpectedly closed.");
+            break;
+        }
+
+        let Ok(message) = messages.recv().await else {
+            break;
+        };
+
+        let Some(from) = state.id(message.source).await else {
+            warn!(
+                "Received a chat message from a non-participating client: {:?}.",
+                message.source
+            );
+            continue;
+        };
+
+        if message.text.len() > MAX_CHAT_LEN {
+            warn!(
+                "Received a too long chat message ({} bytes) from {:?}.",
+                message.text.len(),
+                message.source
+            );
+            continue;
+        }
+
+        let targets = match message.channel {
+            ChatChannel::All => state.targets(Some(message.source)).await,
+            ChatChannel::Team => state.team_targets(message.source).await,
+            ChatChannel::Whisper(id) => state.addr(id).await.map(|addr| addr.into()),
+        };
+        let Some(targets) = targets else {
+            continue;
+        };
+
+        let result = outputs
+            .send(
+                OutPackage::encode_single(
+                    &FromGame::Chat {
+                        from,
+                        channel: message.channel,
+                        text: message.text,
+                    },
+                    true,
+                    Peers::Server,
+                    targets,
+                )
+                .unwrap(),
+            )
+            .await;
+        if result.is_err() {
+            break;
+        }
+    }
+
+    info!("Game chat handler on port {port} finished.");
+}
@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use async_std::{channel::Sender, future::timeout};
-use de_net::{ConnErrorReceiver, ToGame};
+use de_net::{ConnErrorKind, ConnErrorReceiver, ToGame};
 use tracing::{error, info, warn};
 
 use super::greceiver::ToGameMessage;
@@ -23,10 +23,21 @@ pub(super) async fn run(port: u16, errors: ConnErrorReceiver, server: Sender<ToG
             break;
         };
 
-        warn!("In game connection lost with {:?}", error.target());
-        let _ = server
-            .send(ToGameMessage::new(error.target(), true, ToGame::Leave))
-            .await;
+        let message = match error.kind() {
+            ConnErrorKind::Undelivered => {
+                warn!("In game connection lost with {:?}", error.target());
+                ToGameMessage::disconnected(error.target())
+            }
+            ConnErrorKind::PeerDisconnected => {
+                warn!("In game peer {:?} timed out", error.target());
+                ToGameMessage::disconnected(error.target())
+            }
+            ConnErrorKind::PeerLeft => {
+                info!("In game peer {:?} closed the connection", error.target());
+                ToGameMessage::new(error.target(), true, ToGame::Leave)
+            }
+        };
+        let _ = server.send(message).await;
     }
 
     info!("Game connection error handler on port {port} finished.");
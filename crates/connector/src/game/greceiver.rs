@@ -1,29 +1,83 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 use async_std::{
     channel::{Receiver, Sender},
+    future::timeout,
     task,
 };
-use de_net::{FromGame, JoinError, OutPackage, Peers, Targets, ToGame};
-use tracing::{error, info, warn};
+use de_net::{
+    AiSlot, Capabilities, Encryption, FromGame, JoinError, OutPackage, PasswordHash, Peers,
+    Targets, ToGame, Token, PROTOCOL_VERSION,
+};
+use serde::Serialize;
+use tracing::{debug, error, info, warn};
 
-use super::state::{GameState, JoinError as JoinErrorInner};
-use crate::clients::Clients;
+use super::{
+    lobby::{Lobby, LobbyError},
+    state::{GameState, JoinError as JoinErrorInner},
+    Owner,
+};
+use crate::{auth::Verifier, clients::Clients, games::Games};
+
+/// How often are players disconnected for longer than the grace period
+/// removed from the game.
+const EXPIRATION_INTERVAL: Duration = Duration::from_secs(1);
 
 pub(super) struct ToGameMessage {
     meta: MessageMeta,
-    message: ToGame,
+    message: GameMessage,
 }
 
 impl ToGameMessage {
     pub(super) fn new(source: SocketAddr, reliable: bool, message: ToGame) -> Self {
         Self {
             meta: MessageMeta { source, reliable },
-            message,
+            message: GameMessage::Client(message),
+        }
+    }
+
+    /// Creates a message informing the game that connection to a client was
+    /// unexpectedly lost.
+    pub(super) fn disconnected(source: SocketAddr) -> Self {
+        Self {
+            meta: MessageMeta {
+                source,
+                reliable: true,
+            },
+            message: GameMessage::Disconnected,
+        }
+    }
+
+    /// Creates a message informing the game that a client exceeded the rate
+    /// limit of received packages for too long.
+    pub(super) fn flooding(source: SocketAddr) -> Self {
+        Self {
+            meta: MessageMeta {
+                source,
+                reliable: true,
+            },
+            message: GameMessage::Flooding,
         }
     }
 }
 
+enum GameMessage {
+    /// A message received from the client.
+    Client(ToGame),
+    /// Connection with the client was lost.
+    Disconnected,
+    /// The client keeps sending too many packages.
+    Flooding,
+}
+
+struct ClientMessage {
+    meta: MessageMeta,
+    message: ToGame,
+}
+
 struct MessageMeta {
     source: SocketAddr,
     reliable: bool,
@@ -32,31 +86,79 @@ struct MessageMeta {
 pub(super) struct GameProcessor {
     port: u16,
     owner: SocketAddr,
+    /// Verified user name of the owner. It is taken once the owner joins
+    /// the game.
+    owner_name: Option<String>,
+    /// Optional protocol features negotiated with the owner.
+    owner_capabilities: Capabilities,
+    verifier: Option<Verifier>,
+    encryption: Encryption,
     messages: Receiver<ToGameMessage>,
     outputs: Sender<OutPackage>,
     state: GameState,
+    lobby: Lobby,
     clients: Clients,
+    games: Games,
+    /// Last number of players reported to `games`.
+    num_players: u8,
+    /// True if the game was already reported as finished to `games`.
+    finished: bool,
+    /// Time when the game was opened. Game clock of all players is
+    /// synchronized to the time elapsed since then.
+    opened: Instant,
 }
 
 impl GameProcessor {
     pub(super) fn new(
         port: u16,
-        owner: SocketAddr,
+        owner: Owner,
         messages: Receiver<ToGameMessage>,
         outputs: Sender<OutPackage>,
         state: GameState,
         clients: Clients,
+        games: Games,
     ) -> Self {
         Self {
             port,
-            owner,
+            owner: owner.addr,
+            owner_name: owner.name,
+            owner_capabilities: owner.capabilities,
+            verifier: None,
+            encryption: Encryption::default(),
             messages,
             outputs,
             state,
+            lobby: Lobby::new(Vec::new()),
             clients,
+            games,
+            num_players: 0,
+            finished: false,
+            opened: Instant::now(),
         }
     }
 
+    /// Enables authentication of joining players. Players are not
+    /// authenticated by default.
+    pub(super) fn with_verifier(mut self, verifier: Option<Verifier>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Sets encryption sessions of the network stack of the game. Encryption
+    /// is accepted from players who negotiated
+    /// [`Capabilities::ENCRYPTION`].
+    pub(super) fn with_encryption(mut self, encryption: Encryption) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Sets AI slots of the game announced in the lobby. The game has no AI
+    /// players by default.
+    pub(super) fn with_ai_players(mut self, ai_players: Vec<AiSlot>) -> Self {
+        self.lobby = Lobby::new(ai_players);
+        self
+    }
+
     pub(super) async fn run(mut self) {
         info!(
             "Starting game server message handler on port {}...",
@@ -66,7 +168,10 @@ impl GameProcessor {
         // Wait a little to ensure that game creation message (send from main
         // server) is delivered first.
         task::sleep(Duration::from_millis(100)).await;
-        self.join(self.owner).await.unwrap();
+        let owner_name = self.owner_name.take();
+        self.join(self.owner, owner_name, self.owner_capabilities)
+            .await
+            .unwrap();
 
         loop {
             if self.outputs.is_closed() {
@@ -77,28 +182,34 @@ impl GameProcessor {
                 break;
             }
 
-            let Ok(message) = self.messages.recv().await else {
-                error!(
-                    "Game message channel on port {} is unexpectedly closed.",
-                    self.port
-                );
-                break;
+            let message = match timeout(EXPIRATION_INTERVAL, self.messages.recv()).await {
+                Ok(Ok(message)) => Some(message),
+                Ok(Err(_)) => {
+                    error!(
+                        "Game message channel on port {} is unexpectedly closed.",
+                        self.port
+                    );
+                    break;
+                }
+                Err(_) => None,
             };
 
-            if self.handle_ignore(&message).await {
-                continue;
+            self.expire().await;
+
+            if let Some(message) = message {
+                self.process(message).await;
             }
 
-            match message.message {
-                ToGame::Ping(id) => {
-                    self.process_ping(message.meta, id).await;
-                }
-                ToGame::Join => {
-                    self.process_join(message.meta).await;
-                }
-                ToGame::Leave => {
-                    self.process_leave(message.meta).await;
-                }
+            let num_players = self.state.num_players().await;
+            if num_players != self.num_players {
+                self.num_players = num_players;
+                self.games.set_players(self.port, num_players).await;
+            }
+
+            if !self.finished && self.lobby.is_started() && self.state.is_decided().await {
+                info!("Game on port {} is decided.", self.port);
+                self.finished = true;
+                self.games.finish(self.port).await;
             }
 
             if self.state.is_empty().await {
@@ -107,18 +218,97 @@ impl GameProcessor {
             }
         }
 
+        self.games.close(self.port).await;
         info!(
             "Game server message handler on port {} finished.",
             self.port
         );
     }
 
+    async fn process(&mut self, message: ToGameMessage) {
+        let message = match message.message {
+            GameMessage::Client(inner) => ClientMessage {
+                meta: message.meta,
+                message: inner,
+            },
+            GameMessage::Disconnected => {
+                self.process_disconnect(message.meta).await;
+                return;
+            }
+            GameMessage::Flooding => {
+                self.process_flooding(message.meta).await;
+                return;
+            }
+        };
+
+        if self.handle_ignore(&message).await {
+            return;
+        }
+
+        match message.message {
+            ToGame::Ping(id) => {
+                self.process_ping(message.meta, id).await;
+            }
+            ToGame::Join {
+                version,
+                capabilities,
+                auth,
+                password,
+            } => {
+                self.process_join(message.meta, version, capabilities, auth, password)
+                    .await;
+            }
+            ToGame::Rejoin(token) => {
+                self.process_rejoin(message.meta, token).await;
+            }
+            ToGame::Spectate(password) => {
+                self.process_spectate(message.meta, password).await;
+            }
+            ToGame::Leave => {
+                self.process_leave(message.meta).await;
+            }
+            ToGame::JoinLobby => {
+                self.process_join_lobby(message.meta).await;
+            }
+            ToGame::LeaveLobby => {
+                self.process_leave_lobby(message.meta).await;
+            }
+            ToGame::SetReady(ready) => {
+                self.process_set_ready(message.meta, ready).await;
+            }
+            ToGame::StartGame => {
+                self.process_start_game(message.meta).await;
+            }
+            ToGame::SetTeam(team) => {
+                self.process_set_team(message.meta, team).await;
+            }
+            ToGame::RequestResync => {
+                self.process_request_resync(message.meta).await;
+            }
+            ToGame::Kick(id) => {
+                self.process_kick(message.meta, id).await;
+            }
+            ToGame::SyncClock(id) => {
+                self.process_sync_clock(message.meta, id).await;
+            }
+            ToGame::Surrender => {
+                self.process_surrender(message.meta).await;
+            }
+            ToGame::Chat { .. } => {
+                unreachable!("Chat messages are routed to the chat handler.");
+            }
+        }
+    }
+
     /// Returns true if the massage should be ignored and further handles such
     /// messages.
-    async fn handle_ignore(&self, message: &ToGameMessage) -> bool {
-        if matches!(message.message, ToGame::Join | ToGame::Leave) {
-            // Join must be excluded from the condition because of the
-            // chicken and egg problem.
+    async fn handle_ignore(&self, message: &ClientMessage) -> bool {
+        if matches!(
+            message.message,
+            ToGame::Join { .. } | ToGame::Rejoin(_) | ToGame::Spectate(_) | ToGame::Leave
+        ) {
+            // Join, Rejoin and Spectate must be excluded from the condition
+            // because of the chicken and egg problem.
             //
             // Leave must be excluded due to possibility that the message
             // was redelivered.
@@ -164,8 +354,101 @@ impl GameProcessor {
             .await;
     }
 
+    /// Process a game clock synchronization request.
+    async fn process_sync_clock(&self, meta: MessageMeta, id: u32) {
+        let time = self.opened.elapsed().as_micros() as u64;
+        let _ = self
+            .outputs
+            .send(
+                OutPackage::encode_single(
+                    &FromGame::Clock { id, time },
+                    meta.reliable,
+                    Peers::Server,
+                    meta.source,
+                )
+                .unwrap(),
+            )
+            .await;
+    }
+
+    /// Returns true if `password` matches the password of the game. The
+    /// client at `source` is informed if it does not.
+    async fn check_password(&self, source: SocketAddr, password: Option<PasswordHash>) -> bool {
+        if self.state.check_password(password).await {
+            return true;
+        }
+
+        warn!(
+            "Client {source:?} could not join game on port {} due to a wrong password.",
+            self.port
+        );
+        self.send(&FromGame::WrongPassword, source).await;
+        false
+    }
+
     /// Process connect message.
-    async fn process_join(&mut self, meta: MessageMeta) {
+    async fn process_join(
+        &mut self,
+        meta: MessageMeta,
+        version: u16,
+        capabilities: Capabilities,
+        auth: Option<String>,
+        password: Option<PasswordHash>,
+    ) {
+        if version != PROTOCOL_VERSION {
+            warn!(
+                "Player {:?} could not join game on port {} due to protocol version {version} \
+                (expected {PROTOCOL_VERSION}).",
+                meta.source, self.port
+            );
+            self.send(
+                &FromGame::VersionMismatch {
+                    server: PROTOCOL_VERSION,
+                    client: version,
+                },
+                meta.source,
+            )
+            .await;
+            return;
+        }
+        debug!(
+            "Player {:?} supports capabilities {:?}.",
+            meta.source, capabilities
+        );
+
+        let name = match self.verifier {
+            Some(ref verifier) => match verifier.verify(auth.as_deref()) {
+                Ok(user) => Some(user),
+                Err(err) => {
+                    warn!(
+                        "Player {:?} could not join game on port {}: {err}",
+                        meta.source, self.port
+                    );
+                    self.send(
+                        &FromGame::JoinError(JoinError::Unauthenticated),
+                        meta.source,
+                    )
+                    .await;
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        if !self.check_password(meta.source, password).await {
+            return;
+        }
+
+        if self.lobby.is_started() && !self.state.contains(meta.source).await {
+            warn!(
+                "Player {:?} could not join game on port {} because the game has already started.",
+                meta.source, self.port
+            );
+            self.send(&FromGame::JoinError(JoinError::GameStarted), meta.source)
+                .await;
+            return;
+        }
+
         if let Err(err) = self.clients.reserve(meta.source).await {
             warn!("Join request error: {err}");
             self.send(&FromGame::JoinError(JoinError::DifferentGame), meta.source)
@@ -173,7 +456,8 @@ impl GameProcessor {
             return;
         }
 
-        match self.join(meta.source).await {
+        let capabilities = Capabilities::SUPPORTED.intersection(capabilities);
+        match self.join(meta.source, name, capabilities).await {
             Ok(_) => {
                 self.clients.set(meta.source, self.port).await;
             }
@@ -199,24 +483,167 @@ impl GameProcessor {
                         self.send(&FromGame::JoinError(JoinError::GameFull), meta.source)
                             .await;
                     }
+                    JoinErrorInner::InvalidToken => {
+                        unreachable!("Token is not used when joining.");
+                    }
+                    JoinErrorInner::Banned => {
+                        warn!(
+                            "Player {:?} could not join game on port {} because they are banned.",
+                            meta.source, self.port
+                        );
+
+                        self.send(&FromGame::JoinError(JoinError::Banned), meta.source)
+                            .await;
+                    }
                 }
             }
         }
     }
 
-    async fn join(&mut self, addr: SocketAddr) -> Result<(), JoinErrorInner> {
-        let id = self.state.add(addr).await?;
+    /// Process reconnect message.
+    async fn process_rejoin(&mut self, meta: MessageMeta, token: Token) {
+        if let Err(err) = self.clients.reserve(meta.source).await {
+            warn!("Rejoin request error: {err}");
+            self.send(&FromGame::JoinError(JoinError::DifferentGame), meta.source)
+                .await;
+            return;
+        }
+
+        match self.state.rejoin(meta.source, token).await {
+            Ok((id, token)) => {
+                self.clients.set(meta.source, self.port).await;
+                info!(
+                    "Player {id} on {:?} just rejoined game on port {}.",
+                    meta.source, self.port
+                );
+                let capabilities = self
+                    .state
+                    .capabilities(meta.source)
+                    .await
+                    .unwrap_or_default();
+                if capabilities.contains(Capabilities::ENCRYPTION) {
+                    self.encryption.accept(meta.source, token).await;
+                }
+                self.send(
+                    &FromGame::Joined {
+                        id,
+                        token,
+                        capabilities,
+                    },
+                    meta.source,
+                )
+                .await;
+                self.send_all(&FromGame::PeerJoined(id), Some(meta.source))
+                    .await;
+            }
+            Err(err) => {
+                self.clients.free(meta.source).await;
+                warn!(
+                    "Player {:?} could not rejoin game on port {}: {err}",
+                    meta.source, self.port
+                );
+
+                let error = match err {
+                    JoinErrorInner::AlreadyJoined => JoinError::AlreadyJoined,
+                    JoinErrorInner::GameFull => JoinError::GameFull,
+                    JoinErrorInner::InvalidToken => JoinError::InvalidToken,
+                    JoinErrorInner::Banned => JoinError::Banned,
+                };
+                self.send(&FromGame::JoinError(error), meta.source).await;
+            }
+        }
+    }
+
+    /// Process spectator connect message.
+    async fn process_spectate(&mut self, meta: MessageMeta, password: Option<PasswordHash>) {
+        if !self.check_password(meta.source, password).await {
+            return;
+        }
+
+        if let Err(err) = self.clients.reserve(meta.source).await {
+            warn!("Spectate request error: {err}");
+            self.send(&FromGame::JoinError(JoinError::DifferentGame), meta.source)
+                .await;
+            return;
+        }
+
+        match self.state.add_spectator(meta.source).await {
+            Ok(()) => {
+                self.clients.set(meta.source, self.port).await;
+                info!(
+                    "Spectator {:?} just joined game on port {}.",
+                    meta.source, self.port
+                );
+                self.send(&FromGame::Spectating, meta.source).await;
+            }
+            Err(err) => {
+                self.clients.free(meta.source).await;
+                warn!(
+                    "Spectator {:?} could not join game on port {}: {err}",
+                    meta.source, self.port
+                );
+
+                let error = match err {
+                    JoinErrorInner::AlreadyJoined => JoinError::AlreadyJoined,
+                    JoinErrorInner::GameFull => JoinError::GameFull,
+                    JoinErrorInner::InvalidToken => JoinError::InvalidToken,
+                    JoinErrorInner::Banned => JoinError::Banned,
+                };
+                self.send(&FromGame::JoinError(error), meta.source).await;
+            }
+        }
+    }
+
+    /// Adds a player to the game.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the player.
+    ///
+    /// * `name` - verified user name of the player.
+    ///
+    /// * `capabilities` - optional protocol features negotiated with the
+    ///   player.
+    async fn join(
+        &mut self,
+        addr: SocketAddr,
+        name: Option<String>,
+        capabilities: Capabilities,
+    ) -> Result<(), JoinErrorInner> {
+        let (id, token) = self.state.add(addr, name).await?;
+        self.state.set_capabilities(addr, capabilities).await;
+        if capabilities.contains(Capabilities::ENCRYPTION) {
+            self.encryption.accept(addr, token).await;
+        }
         info!(
             "Player {id} on {addr:?} just joined game on port {}.",
             self.port
         );
-        self.send(&FromGame::Joined(id), addr).await;
+        self.send(
+            &FromGame::Joined {
+                id,
+                token,
+                capabilities,
+            },
+            addr,
+        )
+        .await;
         self.send_all(&FromGame::PeerJoined(id), Some(addr)).await;
         Ok(())
     }
 
     /// Process disconnect message.
     async fn process_leave(&mut self, meta: MessageMeta) {
+        if self.state.remove_spectator(meta.source).await {
+            self.clients.free(meta.source).await;
+            info!(
+                "Spectator {:?} just left game on port {}.",
+                meta.source, self.port
+            );
+            self.send(&FromGame::Left, meta.source).await;
+            return;
+        }
+
         let Some(id) = self.state.remove(meta.source).await else {
             warn!("Tried to remove non-existent player {:?}.", meta.source);
             return;
@@ -231,6 +658,284 @@ impl GameProcessor {
 
         self.send(&FromGame::Left, meta.source).await;
         self.send_all(&FromGame::PeerLeft(id), None).await;
+
+        if self.lobby.leave(meta.source).is_ok() {
+            self.lobby_changed().await;
+        }
+    }
+
+    /// Process unexpected loss of connection with a client.
+    ///
+    /// Players of an already started game keep their slot for a grace period
+    /// during which they may rejoin with [`ToGame::Rejoin`]. All other
+    /// clients are simply removed from the game.
+    async fn process_disconnect(&mut self, meta: MessageMeta) {
+        if !self.lobby.is_started() || self.state.is_spectator(meta.source).await {
+            self.process_leave(meta).await;
+            return;
+        }
+
+        let Some(id) = self.state.disconnect(meta.source, Instant::now()).await else {
+            return;
+        };
+
+        self.clients.free(meta.source).await;
+        info!(
+            "Player {id} on {:?} got disconnected from game on port {}.",
+            meta.source, self.port
+        );
+        self.send_all(&FromGame::PeerDisconnected(id), None).await;
+    }
+
+    /// Removes players disconnected for longer than the grace period.
+    async fn expire(&mut self) {
+        for id in self.state.expire(Instant::now()).await {
+            info!(
+                "Player {id} did not rejoin game on port {} in time.",
+                self.port
+            );
+            self.send_all(&FromGame::PeerLeft(id), None).await;
+        }
+    }
+
+    /// Process game state re-synchronization request.
+    async fn process_request_resync(&mut self, meta: MessageMeta) {
+        let Some(id) = self.state.id(meta.source).await else {
+            warn!(
+                "Spectator {:?} cannot request game state re-synchronization.",
+                meta.source
+            );
+            return;
+        };
+
+        self.send_all(&FromGame::ResyncRequested(id), Some(meta.source))
+            .await;
+    }
+
+    /// Process surrender message.
+    async fn process_surrender(&mut self, meta: MessageMeta) {
+        if !self.lobby.is_started() {
+            warn!(
+                "Player {:?} cannot surrender not yet started game on port {}.",
+                meta.source, self.port
+            );
+            return;
+        }
+
+        let Some(id) = self.state.surrender(meta.source).await else {
+            warn!(
+                "Player {:?} could not surrender game on port {}.",
+                meta.source, self.port
+            );
+            return;
+        };
+
+        info!(
+            "Player {id} on {:?} surrendered game on port {}.",
+            meta.source, self.port
+        );
+        self.send_all(&FromGame::PlayerSurrendered(id), None).await;
+    }
+
+    /// Process lobby enter message.
+    async fn process_join_lobby(&mut self, meta: MessageMeta) {
+        // The player is guaranteed to be part of the game by handle_ignore().
+        let Some(id) = self.state.id(meta.source).await else {
+            return;
+        };
+
+        let Some(team) = self.state.team(meta.source).await else {
+            return;
+        };
+        let name = self.state.name(meta.source).await;
+        match self.lobby.join(meta.source, id, team, name) {
+            Ok(()) => {
+                info!(
+                    "Player {id} just joined lobby of game on port {}.",
+                    self.port
+                );
+                self.lobby_changed().await;
+            }
+            Err(LobbyError::AlreadyJoined) => {
+                // The message was probably redelivered, make sure the player
+                // has up-to-date state.
+                self.send(&FromGame::LobbyState(self.lobby.state()), meta.source)
+                    .await;
+            }
+            Err(err) => {
+                warn!(
+                    "Player {id} could not join lobby of game on port {}: {err}",
+                    self.port
+                );
+            }
+        }
+    }
+
+    /// Process lobby exit message.
+    async fn process_leave_lobby(&mut self, meta: MessageMeta) {
+        match self.lobby.leave(meta.source) {
+            Ok(()) => {
+                self.lobby_changed().await;
+            }
+            Err(err) => {
+                warn!(
+                    "Player {:?} could not leave lobby of game on port {}: {err}",
+                    meta.source, self.port
+                );
+            }
+        }
+    }
+
+    /// Process player readiness change message.
+    async fn process_set_ready(&mut self, meta: MessageMeta, ready: bool) {
+        match self.lobby.set_ready(meta.source, ready) {
+            Ok(()) => {
+                self.lobby_changed().await;
+            }
+            Err(err) => {
+                warn!(
+                    "Player {:?} could not change readiness in game on port {}: {err}",
+                    meta.source, self.port
+                );
+            }
+        }
+    }
+
+    /// Process kick message.
+    async fn process_kick(&mut self, meta: MessageMeta, id: u8) {
+        if meta.source != self.owner {
+            warn!(
+                "Player {:?} is not the host of the game on port {} and cannot kick players.",
+                meta.source, self.port
+            );
+            return;
+        }
+
+        let Some(addr) = self.state.addr(id).await else {
+            warn!(
+                "Cannot kick non-existent player {id} from game on port {}.",
+                self.port
+            );
+            return;
+        };
+        if addr == self.owner {
+            warn!(
+                "The host of the game on port {} cannot kick themselves.",
+                self.port
+            );
+            return;
+        }
+
+        self.state.remove(addr).await;
+        self.state.ban(addr.ip()).await;
+        self.clients.free(addr).await;
+
+        info!(
+            "Player {id} on {addr:?} was kicked from game on port {}.",
+            self.port
+        );
+
+        // The kicked player is no longer among the targets of send_all().
+        self.send(&FromGame::PlayerKicked(id), addr).await;
+        self.send_all(&FromGame::PlayerKicked(id), None).await;
+
+        if self.lobby.leave(addr).is_ok() {
+            self.lobby_changed().await;
+        }
+    }
+
+    /// Disconnects a client which keeps exceeding the rate limit of received
+    /// packages.
+    async fn process_flooding(&mut self, meta: MessageMeta) {
+        if self.state.remove_spectator(meta.source).await {
+            self.clients.free(meta.source).await;
+            info!(
+                "Flooding spectator {:?} was kicked from game on port {}.",
+                meta.source, self.port
+            );
+            self.send(&FromGame::Kicked, meta.source).await;
+            return;
+        }
+
+        let Some(id) = self.state.remove(meta.source).await else {
+            return;
+        };
+        self.clients.free(meta.source).await;
+
+        info!(
+            "Flooding player {id} on {:?} was kicked from game on port {}.",
+            meta.source, self.port
+        );
+
+        self.send(&FromGame::Kicked, meta.source).await;
+        self.send_all(&FromGame::PlayerKicked(id), None).await;
+
+        if self.lobby.leave(meta.source).is_ok() {
+            self.lobby_changed().await;
+        }
+    }
+
+    /// Process game start message.
+    async fn process_start_game(&mut self, meta: MessageMeta) {
+        if meta.source != self.owner {
+            warn!(
+                "Player {:?} is not the host of the game on port {} and cannot start it.",
+                meta.source, self.port
+            );
+            return;
+        }
+
+        self.start().await;
+    }
+
+    /// Process team change message.
+    async fn process_set_team(&mut self, meta: MessageMeta, team: u8) {
+        if self.lobby.is_started() {
+            warn!(
+                "Player {:?} cannot change team in already started game on port {}.",
+                meta.source, self.port
+            );
+            return;
+        }
+
+        if !self.state.set_team(meta.source, team).await {
+            warn!(
+                "Player {:?} could not join team {team} in game on port {}.",
+                meta.source, self.port
+            );
+            return;
+        }
+
+        info!(
+            "Player {:?} just joined team {team} in game on port {}.",
+            meta.source, self.port
+        );
+        if self.lobby.set_team(meta.source, team).is_ok() {
+            self.lobby_changed().await;
+        }
+    }
+
+    /// Informs all players in the lobby about its new state and starts the
+    /// game if everybody is ready.
+    async fn lobby_changed(&mut self) {
+        let state = self.lobby.state();
+        if state.all_ready() {
+            self.start().await;
+        } else if let Some(targets) = self.lobby.targets() {
+            self.send(&FromGame::LobbyState(state), targets).await;
+        }
+    }
+
+    async fn start(&mut self) {
+        match self.lobby.start() {
+            Ok(()) => {
+                info!("Game on port {} just started.", self.port);
+                self.send_all(&FromGame::GameStarted, None).await;
+            }
+            Err(err) => {
+                warn!("Game on port {} could not be started: {err}", self.port);
+            }
+        }
     }
 
     /// Send a reliable message to all players of the game.
@@ -243,7 +948,7 @@ impl GameProcessor {
     ///   this player.
     async fn send_all<E>(&self, message: &E, exclude: Option<SocketAddr>)
     where
-        E: bincode::Encode,
+        E: Serialize,
     {
         if let Some(targets) = self.state.targets(exclude).await {
             self.send(message, targets).await;
@@ -253,7 +958,7 @@ impl GameProcessor {
     /// Send message to some targets.
     async fn send<E, T>(&self, message: &E, targets: T)
     where
-        E: bincode::Encode,
+        E: Serialize,
         T: Into<Targets<'static>>,
     {
         let message = OutPackage::encode_single(message, true, Peers::Server, targets).unwrap();
@@ -0,0 +1,198 @@
+use std::{collections::hash_map::Entry, net::SocketAddr};
+
+use ahash::AHashMap;
+use de_net::{AiSlot, LobbyPlayer, LobbyState, Targets};
+use thiserror::Error;
+
+/// Lobby of a single game. It keeps track of players waiting for the game to
+/// start and of their readiness.
+pub(super) struct Lobby {
+    started: bool,
+    players: AHashMap<SocketAddr, LobbyPlayer>,
+    ai_players: Vec<AiSlot>,
+}
+
+impl Lobby {
+    /// # Arguments
+    ///
+    /// * `ai_players` - AI slots of the game sorted by ID.
+    pub(super) fn new(ai_players: Vec<AiSlot>) -> Self {
+        Self {
+            started: false,
+            players: AHashMap::new(),
+            ai_players,
+        }
+    }
+
+    /// Returns true if the game has already started, i.e. the lobby is closed.
+    pub(super) fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Adds a (not ready) player to the lobby.
+    pub(super) fn join(
+        &mut self,
+        addr: SocketAddr,
+        id: u8,
+        team: u8,
+        name: Option<String>,
+    ) -> Result<(), LobbyError> {
+        if self.started {
+            return Err(LobbyError::Started);
+        }
+
+        match self.players.entry(addr) {
+            Entry::Occupied(_) => Err(LobbyError::AlreadyJoined),
+            Entry::Vacant(vacant) => {
+                vacant.insert(LobbyPlayer::new(id, team, name, false));
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes a player from the lobby.
+    pub(super) fn leave(&mut self, addr: SocketAddr) -> Result<(), LobbyError> {
+        match self.players.remove(&addr) {
+            Some(_) => Ok(()),
+            None => Err(LobbyError::NotJoined),
+        }
+    }
+
+    /// Changes readiness of a player in the lobby.
+    pub(super) fn set_ready(&mut self, addr: SocketAddr, ready: bool) -> Result<(), LobbyError> {
+        if self.started {
+            return Err(LobbyError::Started);
+        }
+
+        match self.players.get_mut(&addr) {
+            Some(player) => {
+                *player = LobbyPlayer::new(
+                    player.id(),
+                    player.team(),
+                    player.name().map(String::from),
+                    ready,
+                );
+                Ok(())
+            }
+            None => Err(LobbyError::NotJoined),
+        }
+    }
+
+    /// Changes team of a player in the lobby.
+    pub(super) fn set_team(&mut self, addr: SocketAddr, team: u8) -> Result<(), LobbyError> {
+        if self.started {
+            return Err(LobbyError::Started);
+        }
+
+        match self.players.get_mut(&addr) {
+            Some(player) => {
+                *player = LobbyPlayer::new(
+                    player.id(),
+                    team,
+                    player.name().map(String::from),
+                    player.ready(),
+                );
+                Ok(())
+            }
+            None => Err(LobbyError::NotJoined),
+        }
+    }
+
+    /// Closes the lobby and removes all players from it.
+    pub(super) fn start(&mut self) -> Result<(), LobbyError> {
+        if self.started {
+            return Err(LobbyError::Started);
+        }
+
+        self.started = true;
+        self.players.clear();
+        Ok(())
+    }
+
+    /// Constructs and returns package targets which include all players in
+    /// the lobby. It returns None if the lobby is empty.
+    pub(super) fn targets(&self) -> Option<Targets<'static>> {
+        match self.players.len() {
+            0 => None,
+            1 => self
+                .players
+                .keys()
+                .next()
+                .map(|&addr| Targets::Single(addr)),
+            _ => Some(self.players.keys().copied().collect::<Vec<_>>().into()),
+        }
+    }
+
+    pub(super) fn state(&self) -> LobbyState {
+        let mut players: Vec<LobbyPlayer> = self.players.values().cloned().collect();
+        players.sort_unstable_by_key(|player| player.id());
+        LobbyState::new(players, self.ai_players.clone())
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub(super) enum LobbyError {
+    #[error("The game has already started.")]
+    Started,
+    #[error("The player has already joined the lobby.")]
+    AlreadyJoined,
+    #[error("The player is not in the lobby.")]
+    NotJoined,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lobby() {
+        let first: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let second: SocketAddr = "127.0.0.1:1002".parse().unwrap();
+
+        let mut lobby = Lobby::new(vec![AiSlot::new(3, 3)]);
+        assert!(!lobby.is_started());
+        assert!(!lobby.state().all_ready());
+
+        lobby.join(second, 2, 2, None).unwrap();
+        lobby.join(first, 1, 1, Some("Indy".into())).unwrap();
+        assert_eq!(
+            lobby.join(first, 1, 1, None),
+            Err(LobbyError::AlreadyJoined)
+        );
+        assert_eq!(
+            lobby.state().players(),
+            &[
+                LobbyPlayer::new(1, 1, Some("Indy".into()), false),
+                LobbyPlayer::new(2, 2, None, false)
+            ]
+        );
+        assert_eq!(lobby.state().ai_players(), &[AiSlot::new(3, 3)]);
+
+        lobby.set_team(second, 1).unwrap();
+        assert_eq!(lobby.state().players()[1].team(), 1);
+
+        lobby.set_ready(first, true).unwrap();
+        assert!(!lobby.state().all_ready());
+        assert_eq!(lobby.state().players()[0].name(), Some("Indy"));
+        lobby.set_ready(second, true).unwrap();
+        assert!(lobby.state().all_ready());
+        lobby.set_ready(second, false).unwrap();
+        assert!(!lobby.state().all_ready());
+
+        lobby.leave(second).unwrap();
+        assert_eq!(lobby.leave(second), Err(LobbyError::NotJoined));
+        assert_eq!(lobby.set_ready(second, true), Err(LobbyError::NotJoined));
+        assert!(lobby.state().all_ready());
+
+        assert_eq!(
+            lobby.targets().unwrap().into_iter().collect::<Vec<_>>(),
+            vec![first]
+        );
+        lobby.start().unwrap();
+        assert!(lobby.targets().is_none());
+        assert!(lobby.is_started());
+        assert_eq!(lobby.start(), Err(LobbyError::Started));
+        assert_eq!(lobby.join(second, 2, 2, None), Err(LobbyError::Started));
+        assert_eq!(lobby.set_team(first, 2), Err(LobbyError::Started));
+    }
+}
@@ -1,46 +1,162 @@
 use std::net::SocketAddr;
 
 use async_std::{channel::bounded, task};
-use de_net::{self, Socket};
+use de_net::{self, AiSlot, Capabilities, Encryption, GameSlots, PasswordHash, Socket, Tokens};
 
-use self::{greceiver::GameProcessor, state::GameState};
-use crate::clients::Clients;
+use self::{greceiver::GameProcessor, state::GameState, validation::Validators};
+use crate::{clients::Clients, config::Config, games::Games, metrics::GameMetrics};
 
+mod chat;
 mod ereceiver;
 mod greceiver;
+mod lobby;
 mod mreceiver;
 mod preceiver;
 mod state;
+mod validation;
+
+/// The client who opened a game.
+pub(crate) struct Owner {
+    addr: SocketAddr,
+    name: Option<String>,
+    capabilities: Capabilities,
+}
+
+impl Owner {
+    /// # Arguments
+    ///
+    /// * `addr` - address of the client.
+    ///
+    /// * `name` - verified user name of the client or None if clients are not
+    ///   authenticated. See [`crate::auth`].
+    pub(crate) fn new(addr: SocketAddr, name: Option<String>) -> Self {
+        Self {
+            addr,
+            name,
+            capabilities: Capabilities::empty(),
+        }
+    }
+
+    /// Sets optional protocol features supported by the client. Only
+    /// features supported by the server are used. No optional features are
+    /// used by default.
+    pub(crate) fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Capabilities::SUPPORTED.intersection(capabilities);
+        self
+    }
+}
+
+/// Parameters of a game requested by its creator.
+pub(crate) struct GameSetup {
+    max_players: u8,
+    slots: GameSlots,
+    password: Option<PasswordHash>,
+}
+
+impl GameSetup {
+    /// # Arguments
+    ///
+    /// * `max_players` - maximum number of players in the game, including AI
+    ///   players.
+    ///
+    /// * `slots` - team and AI slots configuration. It must be valid for
+    ///   `max_players`.
+    ///
+    /// * `password` - hash of the password required to join the game. The
+    ///   game is not password protected if None.
+    pub(crate) fn new(max_players: u8, slots: GameSlots, password: Option<PasswordHash>) -> Self {
+        Self {
+            max_players,
+            slots,
+            password,
+        }
+    }
+
+    pub(crate) fn max_players(&self) -> u8 {
+        self.max_players
+    }
+
+    pub(crate) fn slots(&self) -> GameSlots {
+        self.slots
+    }
+
+    /// Returns true if a password is required to join the game.
+    pub(crate) fn protected(&self) -> bool {
+        self.password.is_some()
+    }
+
+    /// Returns all AI slots of the game sorted by ID.
+    fn ai_players(&self) -> Vec<AiSlot> {
+        (self.slots.human_players(self.max_players) + 1..=self.max_players)
+            .filter_map(|id| self.slots.team(id).map(|team| AiSlot::new(id, team)))
+            .collect()
+    }
+}
 
 /// Startup game network server communicating via `net`.
 ///
 /// # Arguments
 ///
+/// * `config` - configuration of the connector.
+///
 /// * `clients` - global clients tracker.
 ///
+/// * `games` - global games tracker. The game is unregistered once it
+///   finishes.
+///
 /// * `socket` - socket to use for the game server.
 ///
-/// * `owner` - address of the creator of the game. This client will be
-///   automatically added to the game as if they sent [`de_net::ToGame::Join`].
+/// * `metrics` - metrics of the game, collected by the network stack and by
+///   the package handlers.
 ///
-/// * `max_players` - maximum number of clients which may connect to the game
-///   at the same time
-pub(crate) async fn startup(clients: Clients, socket: Socket, owner: SocketAddr, max_players: u8) {
+/// * `owner` - the creator of the game. This client will be automatically
+///   added to the game as if they sent [`de_net::ToGame::Join`].
+///
+/// * `setup` - parameters of the game.
+pub(crate) async fn startup(
+    config: &Config,
+    clients: Clients,
+    games: Games,
+    socket: Socket,
+    metrics: GameMetrics,
+    owner: Owner,
+    setup: GameSetup,
+) {
     let port = socket.port();
-    let (outputs, inputs, errors) = de_net::startup(
+    let encryption = Encryption::new();
+    let tokens = Tokens::new();
+    let (outputs, inputs, errors, _) = de_net::startup(
         |t| {
             task::spawn(t);
         },
         socket,
+        config
+            .net_conf()
+            .with_metrics(metrics.net().clone())
+            .with_encryption(encryption.clone())
+            .with_tokens(tokens.clone()),
     );
 
     let (server_sender, server_receiver) = bounded(16);
     task::spawn(ereceiver::run(port, errors, server_sender.clone()));
 
     let (players_sender, players_receiver) = bounded(16);
-    task::spawn(mreceiver::run(port, inputs, server_sender, players_sender));
+    let (chat_sender, chat_receiver) = bounded(16);
+    task::spawn(mreceiver::run(
+        port,
+        inputs,
+        server_sender,
+        players_sender,
+        chat_sender,
+    ));
 
-    let state = GameState::new(max_players);
+    let state = GameState::new(
+        setup.max_players,
+        setup.slots,
+        config.grace_period(),
+        setup.password,
+        tokens,
+    );
     let server = GameProcessor::new(
         port,
         owner,
@@ -48,8 +164,25 @@ pub(crate) async fn startup(clients: Clients, socket: Socket, owner: SocketAddr,
         outputs.clone(),
         state.clone(),
         clients,
-    );
+        games,
+    )
+    .with_verifier(config.verifier())
+    .with_encryption(encryption)
+    .with_ai_players(setup.ai_players());
     task::spawn(server.run());
 
-    task::spawn(preceiver::run(port, players_receiver, outputs, state));
+    task::spawn(chat::run(
+        port,
+        chat_receiver,
+        outputs.clone(),
+        state.clone(),
+    ));
+    task::spawn(preceiver::run(
+        port,
+        players_receiver,
+        outputs,
+        state,
+        Validators::default(),
+        metrics,
+    ));
 }
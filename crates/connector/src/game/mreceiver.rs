@@ -1,20 +1,26 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_std::{channel::Sender, future::timeout};
-use de_net::{PackageReceiver, Peers};
-use tracing::{error, info, warn};
+use de_net::{PackageReceiver, Peers, ToGame};
+use tracing::{error, info, trace, warn};
 
-use super::greceiver::ToGameMessage;
-use crate::game::preceiver::PlayersPackage;
+use super::{chat::ChatMessage, greceiver::ToGameMessage};
+use crate::{
+    game::preceiver::PlayersPackage,
+    limiter::{RateLimiter, Verdict},
+};
 
 pub(super) async fn run(
     port: u16,
     packages: PackageReceiver,
     server: Sender<ToGameMessage>,
     players: Sender<PlayersPackage>,
+    chat: Sender<ChatMessage>,
 ) {
     info!("Starting game server input processor on port {port}...");
 
+    let mut limiter = RateLimiter::new(Instant::now());
+
     loop {
         if server.is_closed() {
             break;
@@ -25,7 +31,16 @@ pub(super) async fn run(
             break;
         }
 
-        let Ok(package) = timeout(Duration::from_millis(500), packages.recv()).await else {
+        if chat.is_closed() {
+            error!("Chat channel on port {port} was unexpectedly closed.");
+            break;
+        }
+
+        let result = timeout(Duration::from_millis(500), packages.recv()).await;
+        let time = Instant::now();
+        limiter.clean(time);
+
+        let Ok(package) = result else {
             continue;
         };
 
@@ -34,10 +49,46 @@ pub(super) async fn run(
             break;
         };
 
+        match limiter.check(time, package.source()) {
+            Verdict::Accept => (),
+            // Reliable packages were already confirmed to the client and
+            // dropping them would break, for example, the lockstep.
+            Verdict::Throttle if package.reliable() => (),
+            Verdict::Throttle => {
+                trace!(
+                    "Dropping a package from throttled client {:?}.",
+                    package.source()
+                );
+                continue;
+            }
+            Verdict::Flooding => {
+                warn!(
+                    "Client {:?} is flooding game on port {port}.",
+                    package.source()
+                );
+                if server
+                    .send(ToGameMessage::flooding(package.source()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                continue;
+            }
+        }
+
         match package.peers() {
             Peers::Server => {
                 for message_result in package.decode() {
                     match message_result {
+                        Ok(ToGame::Chat { channel, text }) => {
+                            let result = chat
+                                .send(ChatMessage::new(package.source(), channel, text))
+                                .await;
+                            if result.is_err() {
+                                break;
+                            }
+                        }
                         Ok(message) => {
                             let result = server
                                 .send(ToGameMessage::new(
@@ -62,6 +113,7 @@ pub(super) async fn run(
                     .send(PlayersPackage::new(
                         package.reliable(),
                         package.source(),
+                        package.token(),
                         package.data(),
                     ))
                     .await;
@@ -1,23 +1,31 @@
 use std::net::SocketAddr;
 
 use async_std::channel::Receiver;
-use de_net::{FromGame, OutPackage, PackageSender, Peers};
-use tracing::{error, info, warn};
+use de_net::{Capabilities, FromGame, OutPackage, PackageSender, Peers, Token};
+use tracing::{error, info, trace, warn};
 
-use super::state::GameState;
+use super::{state::GameState, validation::Validators};
+use crate::metrics::GameMetrics;
 
 /// A package destined to other players in the game.
 pub(super) struct PlayersPackage {
     reliable: bool,
     source: SocketAddr,
+    token: Option<Token>,
     data: Vec<u8>,
 }
 
 impl PlayersPackage {
-    pub(super) fn new(reliable: bool, source: SocketAddr, data: Vec<u8>) -> Self {
+    pub(super) fn new(
+        reliable: bool,
+        source: SocketAddr,
+        token: Option<Token>,
+        data: Vec<u8>,
+    ) -> Self {
         Self {
             reliable,
             source,
+            token,
             data,
         }
     }
@@ -28,10 +36,12 @@ pub(super) async fn run(
     packages: Receiver<PlayersPackage>,
     outputs: PackageSender,
     state: GameState,
+    validators: Validators,
+    metrics: GameMetrics,
 ) {
     info!("Starting game player package handler on port {port}...");
 
-    loop {
+    'packages: loop {
         if packages.is_closed() {
             break;
         }
@@ -45,7 +55,15 @@ pub(super) async fn run(
             break;
         };
 
-        if !state.contains(package.source).await {
+        let Some(token) = state.token(package.source).await else {
+            if state.is_spectator(package.source).await {
+                trace!(
+                    "Dropping a player message from spectator {:?}.",
+                    package.source
+                );
+                continue;
+            }
+
             warn!(
                 "Received a player message from a non-participating client: {:?}.",
                 package.source
@@ -63,23 +81,63 @@ pub(super) async fn run(
                 )
                 .await;
             continue;
+        };
+
+        if package.token != Some(token) {
+            warn!(
+                "Received a player message with an invalid token from {:?}.",
+                package.source
+            );
+            continue;
         }
 
-        let Some(targets) = state.targets(Some(package.source)).await else {
+        // The player might have been removed in the meantime.
+        let Some(id) = state.id(package.source).await else {
             continue;
         };
+        if let Err(rejection) = validators.validate(id, &package.data) {
+            warn!(
+                "Rejected a player message from player {id} on {:?}: {rejection}",
+                package.source
+            );
+            metrics.record_rejected();
+            continue;
+        }
 
-        let result = outputs
-            .send(OutPackage::new(
+        let (compressing, plain) = state
+            .targets_by_capabilities(Some(package.source), Capabilities::COMPRESSION)
+            .await;
+
+        let mut packages = Vec::with_capacity(2);
+        if let Some(targets) = compressing {
+            packages.push(
+                OutPackage::new(
+                    package.data.clone(),
+                    package.reliable,
+                    Peers::Players,
+                    targets,
+                )
+                .with_compression(),
+            );
+        }
+        if let Some(targets) = plain {
+            packages.push(OutPackage::new(
                 package.data,
                 package.reliable,
                 Peers::Players,
                 targets,
-            ))
-            .await;
-        if result.is_err() {
-            break;
+            ));
+        }
+        if packages.is_empty() {
+            continue;
+        }
+
+        for out in packages {
+            if outputs.send(out).await.is_err() {
+                break 'packages;
+            }
         }
+        metrics.record_relayed();
     }
 
     info!("Game player package handler on port {port} finished.");
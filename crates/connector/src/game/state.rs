@@ -1,45 +1,234 @@
-use std::{collections::hash_map::Entry, net::SocketAddr};
+use std::{
+    collections::hash_map::Entry,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use async_std::sync::{Arc, RwLock};
-use de_net::Targets;
+use de_net::{Capabilities, GameSlots, PasswordHash, Targets, Token, Tokens};
 use thiserror::Error;
 
+/// Maximum number of spectators connected to a single game.
+const MAX_SPECTATORS: usize = 16;
+
 #[derive(Clone)]
 pub(super) struct GameState {
     inner: Arc<RwLock<GameStateInner>>,
+    /// Tokens of connected players verified by the networking stack.
+    tokens: Tokens,
 }
 
 impl GameState {
-    pub(super) fn new(max_players: u8) -> Self {
+    /// # Arguments
+    ///
+    /// * `max_players` - maximum number of players in the game, including AI
+    ///   players.
+    ///
+    /// * `slots` - team and AI slots configuration. It must be valid for
+    ///   `max_players`.
+    ///
+    /// * `grace_period` - for how long is a slot of an unexpectedly
+    ///   disconnected player kept.
+    ///
+    /// * `password` - hash of the password required to join the game. The
+    ///   game is not password protected if None.
+    ///
+    /// * `tokens` - tokens issued to players are registered here (and
+    ///   removed once the players leave), see [`de_net::NetConf::with_tokens`].
+    pub(super) fn new(
+        max_players: u8,
+        slots: GameSlots,
+        grace_period: Duration,
+        password: Option<PasswordHash>,
+        tokens: Tokens,
+    ) -> Self {
         Self {
-            inner: Arc::new(RwLock::new(GameStateInner::new(max_players))),
+            inner: Arc::new(RwLock::new(GameStateInner::new(
+                max_players,
+                slots,
+                grace_period,
+                password,
+            ))),
+            tokens,
         }
     }
 
-    /// Returns true if there is no players currently connected to the game.
+    /// Returns true if `password` matches the password of the game. Any
+    /// password, including None, matches if the game is not password
+    /// protected.
+    pub(super) async fn check_password(&self, password: Option<PasswordHash>) -> bool {
+        self.inner.read().await.check_password(password)
+    }
+
+    /// Returns true if there is no players nor spectators currently connected
+    /// to the game and no player may rejoin the game.
     pub(super) async fn is_empty(&self) -> bool {
         self.inner.read().await.is_empty()
     }
 
-    /// Returns true if a player with `addr` is connected to the game.
+    /// Returns number of player slots currently occupied in the game. This
+    /// includes players who may still rejoin the game and AI players.
+    pub(super) async fn num_players(&self) -> u8 {
+        self.inner.read().await.num_players()
+    }
+
+    /// Returns true if a player or a spectator with `addr` is connected to
+    /// the game.
     pub(super) async fn contains(&self, addr: SocketAddr) -> bool {
         self.inner.read().await.contains(addr)
     }
 
-    /// Adds a player to the game and returns ID of the added player.
-    pub(super) async fn add(&mut self, addr: SocketAddr) -> Result<u8, JoinError> {
-        self.inner.write().await.add(addr)
+    /// Returns true if a spectator with `addr` is connected to the game.
+    pub(super) async fn is_spectator(&self, addr: SocketAddr) -> bool {
+        self.inner.read().await.is_spectator(addr)
+    }
+
+    /// Returns ID of a player with `addr` or None if the player is not
+    /// connected to the game.
+    pub(super) async fn id(&self, addr: SocketAddr) -> Option<u8> {
+        self.inner.read().await.id(addr)
+    }
+
+    /// Returns address of a player with `id` or None if there is no such
+    /// player connected to the game.
+    pub(super) async fn addr(&self, id: u8) -> Option<SocketAddr> {
+        self.inner.read().await.addr(id)
+    }
+
+    /// Returns the token issued to a player with `addr` or None if the
+    /// player is not connected to the game.
+    pub(super) async fn token(&self, addr: SocketAddr) -> Option<Token> {
+        self.inner.read().await.token(addr)
+    }
+
+    /// Returns team of a player or None if the player is not connected to
+    /// the game.
+    pub(super) async fn team(&self, addr: SocketAddr) -> Option<u8> {
+        self.inner.read().await.team(addr)
+    }
+
+    /// Returns verified user name of a player or None if the player is not
+    /// connected to the game or if the player is not authenticated.
+    pub(super) async fn name(&self, addr: SocketAddr) -> Option<String> {
+        self.inner.read().await.name(addr)
+    }
+
+    /// Returns optional protocol features negotiated with a player or None
+    /// if the player is not connected to the game.
+    pub(super) async fn capabilities(&self, addr: SocketAddr) -> Option<Capabilities> {
+        self.inner.read().await.capabilities(addr)
+    }
+
+    /// Sets optional protocol features negotiated with a player. The
+    /// features are kept when the player rejoins the game.
+    pub(super) async fn set_capabilities(&mut self, addr: SocketAddr, capabilities: Capabilities) {
+        self.inner
+            .write()
+            .await
+            .set_capabilities(addr, capabilities)
+    }
+
+    /// Adds a player to the game and returns ID of the added player and a
+    /// newly issued token.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the player.
+    ///
+    /// * `name` - verified user name of the player. Each user may join the
+    ///   game only once.
+    pub(super) async fn add(
+        &mut self,
+        addr: SocketAddr,
+        name: Option<String>,
+    ) -> Result<(u8, Token), JoinError> {
+        let (id, token) = self.inner.write().await.add(addr, name)?;
+        self.tokens.insert(addr, token).await;
+        Ok((id, token))
     }
 
     /// Removes a single player from the game. It returns ID of the player if
     /// the player was part of the game or None otherwise.
     pub(super) async fn remove(&mut self, addr: SocketAddr) -> Option<u8> {
-        self.inner.write().await.remove(addr)
+        let id = self.inner.write().await.remove(addr)?;
+        self.tokens.remove(addr).await;
+        Some(id)
+    }
+
+    /// Marks a player as disconnected. The player keeps their ID and may
+    /// rejoin the game with their last token during the grace period. It
+    /// returns ID of the player or None if the player is not part of the
+    /// game.
+    pub(super) async fn disconnect(&mut self, addr: SocketAddr, now: Instant) -> Option<u8> {
+        let id = self.inner.write().await.disconnect(addr, now)?;
+        self.tokens.remove(addr).await;
+        Some(id)
+    }
+
+    /// Re-connects a disconnected player, possibly from a different address.
+    /// It returns ID of the player and a newly issued token.
+    pub(super) async fn rejoin(
+        &mut self,
+        addr: SocketAddr,
+        token: Token,
+    ) -> Result<(u8, Token), JoinError> {
+        let (id, token) = self.inner.write().await.rejoin(addr, token)?;
+        self.tokens.insert(addr, token).await;
+        Ok((id, token))
+    }
+
+    /// Removes all players disconnected for longer than the grace period and
+    /// returns their IDs.
+    pub(super) async fn expire(&mut self, now: Instant) -> Vec<u8> {
+        self.inner.write().await.expire(now)
+    }
+
+    /// Prevents all clients from `ip` from (re)joining the game.
+    pub(super) async fn ban(&mut self, ip: IpAddr) {
+        self.inner.write().await.ban(ip)
+    }
+
+    /// Adds a spectator to the game. Spectators receive all player packages
+    /// but may not send any.
+    pub(super) async fn add_spectator(&mut self, addr: SocketAddr) -> Result<(), JoinError> {
+        self.inner.write().await.add_spectator(addr)
+    }
+
+    /// Removes a single spectator from the game. It returns false if the
+    /// spectator was not part of the game.
+    pub(super) async fn remove_spectator(&mut self, addr: SocketAddr) -> bool {
+        self.inner.write().await.remove_spectator(addr)
+    }
+
+    /// Marks a player as defeated. It returns ID of the player or None if
+    /// the player is not connected to the game or was already defeated.
+    pub(super) async fn surrender(&mut self, addr: SocketAddr) -> Option<u8> {
+        self.inner.write().await.surrender(addr)
+    }
+
+    /// Returns true if all not yet defeated players, including disconnected
+    /// and AI players, are in a single team (or there are no such players).
+    pub(super) async fn is_decided(&self) -> bool {
+        self.inner.read().await.is_decided()
+    }
+
+    /// Moves a player to a team. It returns false if the player is not
+    /// connected to the game or if the team may not be joined, see
+    /// [`de_net::ToGame::SetTeam`].
+    pub(super) async fn set_team(&mut self, addr: SocketAddr, team: u8) -> bool {
+        self.inner.write().await.set_team(addr, team)
+    }
+
+    /// Constructs and returns package targets which include all players in
+    /// the same team as player `addr` (excluding the player). It returns None
+    /// if there is no matching target.
+    pub(super) async fn team_targets(&self, addr: SocketAddr) -> Option<Targets<'static>> {
+        self.inner.read().await.team_targets(addr)
     }
 
     /// Constructs and returns package targets which includes all or all but
-    /// one players connected to the game. It returns None if there is no
+    /// one players and all spectators connected to the game. It returns None if there is no
     /// matching target.
     ///
     /// # Arguments
@@ -48,36 +237,144 @@ impl GameState {
     pub(super) async fn targets(&self, exclude: Option<SocketAddr>) -> Option<Targets<'static>> {
         self.inner.read().await.targets(exclude)
     }
+
+    /// Same as [`Self::targets`] but the targets are split into players who
+    /// negotiated all `capabilities` and the others, including all
+    /// spectators.
+    pub(super) async fn targets_by_capabilities(
+        &self,
+        exclude: Option<SocketAddr>,
+        capabilities: Capabilities,
+    ) -> (Option<Targets<'static>>, Option<Targets<'static>>) {
+        self.inner
+            .read()
+            .await
+            .targets_by_capabilities(exclude, capabilities)
+    }
 }
 
 struct GameStateInner {
+    max_players: u8,
+    slots: GameSlots,
+    grace_period: Duration,
+    password: Option<PasswordHash>,
     available_ids: AvailableIds,
     players: AHashMap<SocketAddr, Player>,
+    spectators: AHashSet<SocketAddr>,
+    disconnected: AHashMap<Token, Disconnected>,
+    banned: AHashSet<IpAddr>,
 }
 
 impl GameStateInner {
-    fn new(max_players: u8) -> Self {
+    fn new(
+        max_players: u8,
+        slots: GameSlots,
+        grace_period: Duration,
+        password: Option<PasswordHash>,
+    ) -> Self {
+        debug_assert!(slots.is_valid(max_players));
         Self {
-            available_ids: AvailableIds::new(max_players),
+            max_players,
+            slots,
+            grace_period,
+            password,
+            available_ids: AvailableIds::new(slots.human_players(max_players)),
             players: AHashMap::new(),
+            spectators: AHashSet::new(),
+            disconnected: AHashMap::new(),
+            banned: AHashSet::new(),
         }
     }
 
+    fn check_password(&self, password: Option<PasswordHash>) -> bool {
+        self.password
+            .map_or(true, |expected| password == Some(expected))
+    }
+
     fn is_empty(&self) -> bool {
-        self.players.is_empty()
+        self.players.is_empty() && self.spectators.is_empty() && self.disconnected.is_empty()
+    }
+
+    fn num_players(&self) -> u8 {
+        // The number of players is limited by the number of IDs (u8).
+        u8::try_from(self.players.len() + self.disconnected.len()).unwrap()
+            + self.slots.ai_players()
     }
 
     fn contains(&self, addr: SocketAddr) -> bool {
-        self.players.contains_key(&addr)
+        self.players.contains_key(&addr) || self.is_spectator(addr)
+    }
+
+    fn is_spectator(&self, addr: SocketAddr) -> bool {
+        self.spectators.contains(&addr)
+    }
+
+    fn id(&self, addr: SocketAddr) -> Option<u8> {
+        self.players.get(&addr).map(|player| player.id)
+    }
+
+    fn team(&self, addr: SocketAddr) -> Option<u8> {
+        self.players.get(&addr).map(|player| player.team)
+    }
+
+    fn name(&self, addr: SocketAddr) -> Option<String> {
+        self.players
+            .get(&addr)
+            .and_then(|player| player.name.clone())
+    }
+
+    fn capabilities(&self, addr: SocketAddr) -> Option<Capabilities> {
+        self.players.get(&addr).map(|player| player.capabilities)
+    }
+
+    fn set_capabilities(&mut self, addr: SocketAddr, capabilities: Capabilities) {
+        if let Some(player) = self.players.get_mut(&addr) {
+            player.capabilities = capabilities;
+        }
+    }
+
+    fn addr(&self, id: u8) -> Option<SocketAddr> {
+        self.players
+            .iter()
+            .find(|(_, player)| player.id == id)
+            .map(|(&addr, _)| addr)
     }
 
-    fn add(&mut self, addr: SocketAddr) -> Result<u8, JoinError> {
+    fn token(&self, addr: SocketAddr) -> Option<Token> {
+        self.players.get(&addr).map(|player| player.token)
+    }
+
+    fn add(&mut self, addr: SocketAddr, name: Option<String>) -> Result<(u8, Token), JoinError> {
+        if self.banned.contains(&addr.ip()) {
+            return Err(JoinError::Banned);
+        }
+        if self.is_spectator(addr) {
+            return Err(JoinError::AlreadyJoined);
+        }
+        if name.is_some()
+            && self
+                .players
+                .values()
+                .chain(self.disconnected.values().map(|d| &d.player))
+                .any(|player| player.name == name)
+        {
+            return Err(JoinError::AlreadyJoined);
+        }
+
         match self.players.entry(addr) {
             Entry::Occupied(_) => Err(JoinError::AlreadyJoined),
             Entry::Vacant(vacant) => match self.available_ids.lease() {
                 Some(id) => {
-                    vacant.insert(Player { id });
-                    Ok(id)
+                    let token = Token::random();
+                    vacant.insert(Player {
+                        id,
+                        token,
+                        team: self.slots.team(id).expect("Player IDs start at 1."),
+                        name,
+                        defeated: false,
+                        capabilities: Capabilities::empty(),
+                    });
+                    Ok((id, token))
                 }
                 None => Err(JoinError::GameFull),
             },
@@ -94,31 +391,180 @@ impl GameStateInner {
         }
     }
 
-    fn targets(&self, exclude: Option<SocketAddr>) -> Option<Targets<'static>> {
-        let len = if exclude.map_or(false, |e| self.players.contains_key(&e)) {
-            self.players.len() - 1
-        } else {
-            self.players.len()
+    fn disconnect(&mut self, addr: SocketAddr, now: Instant) -> Option<u8> {
+        let player = self.players.remove(&addr)?;
+        let id = player.id;
+        self.disconnected
+            .insert(player.token, Disconnected { player, since: now });
+        Some(id)
+    }
+
+    fn rejoin(&mut self, addr: SocketAddr, token: Token) -> Result<(u8, Token), JoinError> {
+        if self.banned.contains(&addr.ip()) {
+            return Err(JoinError::Banned);
+        }
+        if self.contains(addr) {
+            return Err(JoinError::AlreadyJoined);
+        }
+
+        let Some(disconnected) = self.disconnected.remove(&token) else {
+            return Err(JoinError::InvalidToken);
         };
 
-        if len == 0 {
-            None
-        } else if len == 1 {
-            for &addr in self.players.keys() {
-                if Some(addr) != exclude {
-                    return Some(Targets::Single(addr));
-                }
+        let mut player = disconnected.player;
+        player.token = Token::random();
+        let result = (player.id, player.token);
+        self.players.insert(addr, player);
+        Ok(result)
+    }
+
+    fn expire(&mut self, now: Instant) -> Vec<u8> {
+        let grace_period = self.grace_period;
+        let mut expired = Vec::new();
+        self.disconnected.retain(|_, disconnected| {
+            if now.saturating_duration_since(disconnected.since) < grace_period {
+                true
+            } else {
+                expired.push(disconnected.player.id);
+                false
             }
+        });
 
-            unreachable!("No non-excluded player found.");
-        } else {
-            let mut addrs = Vec::with_capacity(len);
-            for &addr in self.players.keys() {
-                if Some(addr) != exclude {
-                    addrs.push(addr);
-                }
+        for &id in &expired {
+            self.available_ids.release(id);
+        }
+        expired
+    }
+
+    fn ban(&mut self, ip: IpAddr) {
+        self.banned.insert(ip);
+    }
+
+    fn add_spectator(&mut self, addr: SocketAddr) -> Result<(), JoinError> {
+        if self.banned.contains(&addr.ip()) {
+            return Err(JoinError::Banned);
+        }
+        if self.contains(addr) {
+            return Err(JoinError::AlreadyJoined);
+        }
+        if self.spectators.len() >= MAX_SPECTATORS {
+            return Err(JoinError::GameFull);
+        }
+
+        self.spectators.insert(addr);
+        Ok(())
+    }
+
+    fn remove_spectator(&mut self, addr: SocketAddr) -> bool {
+        self.spectators.remove(&addr)
+    }
+
+    fn surrender(&mut self, addr: SocketAddr) -> Option<u8> {
+        let player = self.players.get_mut(&addr)?;
+        if player.defeated {
+            return None;
+        }
+        player.defeated = true;
+        Some(player.id)
+    }
+
+    fn is_decided(&self) -> bool {
+        let humans = self
+            .players
+            .values()
+            .chain(self.disconnected.values().map(|d| &d.player))
+            .filter(|player| !player.defeated)
+            .map(|player| player.team);
+        let ai = (self.slots.human_players(self.max_players) + 1..=self.max_players)
+            .filter_map(|id| self.slots.team(id));
+        let teams: AHashSet<u8> = humans.chain(ai).collect();
+        teams.len() <= 1
+    }
+
+    fn set_team(&mut self, addr: SocketAddr, team: u8) -> bool {
+        if let Some(team_size) = self.slots.team_size(self.max_players) {
+            if team == 0 || team > self.slots.teams() {
+                return false;
+            }
+            if self.players.get(&addr).map(|player| player.team) == Some(team) {
+                return true;
+            }
+            if self.team_members(team) >= team_size {
+                return false;
+            }
+        }
+
+        match self.players.get_mut(&addr) {
+            Some(player) => {
+                player.team = team;
+                true
             }
-            Some(addrs.into())
+            None => false,
+        }
+    }
+
+    /// Returns number of players, including disconnected and AI players, in
+    /// a team.
+    fn team_members(&self, team: u8) -> u8 {
+        let humans = self
+            .players
+            .values()
+            .chain(self.disconnected.values().map(|d| &d.player))
+            .filter(|player| player.team == team)
+            .count();
+        let ai = (self.slots.human_players(self.max_players) + 1..=self.max_players)
+            .filter(|&id| self.slots.team(id) == Some(team))
+            .count();
+        // The number of players is limited by the number of IDs (u8).
+        u8::try_from(humans + ai).unwrap()
+    }
+
+    fn team_targets(&self, addr: SocketAddr) -> Option<Targets<'static>> {
+        let team = self.players.get(&addr)?.team;
+        self.filtered_targets(|other, player| other != addr && player.team == team, false)
+    }
+
+    fn targets(&self, exclude: Option<SocketAddr>) -> Option<Targets<'static>> {
+        self.filtered_targets(|addr, _| Some(addr) != exclude, true)
+    }
+
+    fn targets_by_capabilities(
+        &self,
+        exclude: Option<SocketAddr>,
+        capabilities: Capabilities,
+    ) -> (Option<Targets<'static>>, Option<Targets<'static>>) {
+        (
+            self.filtered_targets(
+                |addr, player| Some(addr) != exclude && player.capabilities.contains(capabilities),
+                false,
+            ),
+            self.filtered_targets(
+                |addr, player| Some(addr) != exclude && !player.capabilities.contains(capabilities),
+                true,
+            ),
+        )
+    }
+
+    /// Constructs targets from players matching `filter` and, if
+    /// `spectators` is true, all spectators.
+    fn filtered_targets<F>(&self, filter: F, spectators: bool) -> Option<Targets<'static>>
+    where
+        F: Fn(SocketAddr, &Player) -> bool,
+    {
+        let mut addrs: Vec<SocketAddr> = self
+            .players
+            .iter()
+            .filter(|(&addr, player)| filter(addr, player))
+            .map(|(&addr, _)| addr)
+            .collect();
+        if spectators {
+            addrs.extend(self.spectators.iter().copied());
+        }
+
+        match addrs.len() {
+            0 => None,
+            1 => Some(Targets::Single(addrs.pop().unwrap())),
+            _ => Some(addrs.into()),
         }
     }
 }
@@ -159,10 +605,24 @@ pub(super) enum JoinError {
     AlreadyJoined,
     #[error("The game is full.")]
     GameFull,
+    #[error("The token does not belong to a disconnected player.")]
+    InvalidToken,
+    #[error("The player is banned from the game.")]
+    Banned,
 }
 
 struct Player {
     id: u8,
+    token: Token,
+    team: u8,
+    name: Option<String>,
+    defeated: bool,
+    capabilities: Capabilities,
+}
+
+struct Disconnected {
+    player: Player,
+    since: Instant,
 }
 
 #[cfg(test)]
@@ -173,16 +633,33 @@ mod tests {
 
     use super::*;
 
+    const GRACE_PERIOD: Duration = Duration::from_secs(60);
+
     #[test]
     fn test_state() {
         task::block_on(task::spawn(async {
-            let mut state = GameState::new(8);
+            let mut state =
+                GameState::new(8, GameSlots::default(), GRACE_PERIOD, None, Tokens::new());
             let mut ids: HashSet<u8> = HashSet::new();
 
-            assert!(ids.insert(state.add("127.0.0.1:1001".parse().unwrap()).await.unwrap()));
+            let (id, token) = state
+                .add("127.0.0.1:1001".parse().unwrap(), None)
+                .await
+                .unwrap();
+            assert!(ids.insert(id));
             assert!(state.contains("127.0.0.1:1001".parse().unwrap()).await);
-
-            assert!(ids.insert(state.add("127.0.0.1:1002".parse().unwrap()).await.unwrap()));
+            assert_eq!(
+                state.token("127.0.0.1:1001".parse().unwrap()).await,
+                Some(token)
+            );
+            assert_eq!(state.id("127.0.0.1:1001".parse().unwrap()).await, Some(id));
+
+            let (id, other_token) = state
+                .add("127.0.0.1:1002".parse().unwrap(), None)
+                .await
+                .unwrap();
+            assert!(ids.insert(id));
+            assert_ne!(token, other_token);
             assert!(state.contains("127.0.0.1:1001".parse().unwrap()).await);
             assert!(state.contains("127.0.0.1:1002".parse().unwrap()).await);
 
@@ -193,28 +670,37 @@ mod tests {
                     .unwrap()
             ));
             assert!(!state.contains("127.0.0.1:1001".parse().unwrap()).await);
+            assert!(state
+                .token("127.0.0.1:1001".parse().unwrap())
+                .await
+                .is_none());
             assert!(state.contains("127.0.0.1:1002".parse().unwrap()).await);
 
-            assert!(ids.insert(state.add("127.0.0.1:1001".parse().unwrap()).await.unwrap()));
+            let (id, _) = state
+                .add("127.0.0.1:1001".parse().unwrap(), None)
+                .await
+                .unwrap();
+            assert!(ids.insert(id));
             assert!(state.contains("127.0.0.1:1001".parse().unwrap()).await);
             assert!(state.contains("127.0.0.1:1002".parse().unwrap()).await);
 
             assert!(matches!(
-                state.add("127.0.0.1:1001".parse().unwrap()).await,
+                state.add("127.0.0.1:1001".parse().unwrap(), None).await,
                 Err(JoinError::AlreadyJoined),
             ));
 
             for i in 3..=8 {
                 assert!(ids.insert(
                     state
-                        .add(format!("127.0.0.1:100{i}").parse().unwrap())
+                        .add(format!("127.0.0.1:100{i}").parse().unwrap(), None)
                         .await
                         .unwrap()
+                        .0
                 ));
             }
 
             assert!(matches!(
-                state.add("127.0.0.1:1020".parse().unwrap()).await,
+                state.add("127.0.0.1:1020".parse().unwrap(), None).await,
                 Err(JoinError::GameFull),
             ));
             assert!(!state.contains("127.0.0.1:1020".parse().unwrap()).await);
@@ -223,11 +709,11 @@ mod tests {
 
     #[test]
     fn test_targets() {
-        let mut state = GameStateInner::new(8);
+        let mut state = GameStateInner::new(8, GameSlots::default(), GRACE_PERIOD, None);
 
         assert!(state.targets(None).is_none());
 
-        state.add("127.0.0.1:2001".parse().unwrap()).unwrap();
+        state.add("127.0.0.1:2001".parse().unwrap(), None).unwrap();
         assert_eq!(
             HashSet::<SocketAddr>::from_iter(state.targets(None).unwrap().into_iter()),
             HashSet::from_iter(["127.0.0.1:2001".parse().unwrap()])
@@ -236,8 +722,8 @@ mod tests {
             .targets(Some("127.0.0.1:2001".parse().unwrap()))
             .is_none());
 
-        state.add("127.0.0.1:2002".parse().unwrap()).unwrap();
-        state.add("127.0.0.1:2003".parse().unwrap()).unwrap();
+        state.add("127.0.0.1:2002".parse().unwrap(), None).unwrap();
+        state.add("127.0.0.1:2003".parse().unwrap(), None).unwrap();
         assert_eq!(
             HashSet::<SocketAddr>::from_iter(state.targets(None).unwrap().into_iter()),
             HashSet::from_iter([
@@ -258,6 +744,151 @@ mod tests {
                 "127.0.0.1:2003".parse().unwrap(),
             ])
         );
+
+        assert!(state.set_team("127.0.0.1:2003".parse().unwrap(), 2));
+        assert!(!state.set_team("127.0.0.1:2004".parse().unwrap(), 2));
+        assert!(state
+            .team_targets("127.0.0.1:2001".parse().unwrap())
+            .is_none());
+        assert_eq!(
+            HashSet::<SocketAddr>::from_iter(
+                state
+                    .team_targets("127.0.0.1:2002".parse().unwrap())
+                    .unwrap()
+                    .into_iter()
+            ),
+            HashSet::from_iter(["127.0.0.1:2003".parse().unwrap()])
+        );
+        assert_eq!(state.addr(3), Some("127.0.0.1:2003".parse().unwrap()));
+        assert!(state.addr(4).is_none());
+
+        state.set_capabilities("127.0.0.1:2002".parse().unwrap(), Capabilities::SUPPORTED);
+        state.set_capabilities("127.0.0.1:2003".parse().unwrap(), Capabilities::COMPRESSION);
+        assert_eq!(
+            state.capabilities("127.0.0.1:2003".parse().unwrap()),
+            Some(Capabilities::COMPRESSION)
+        );
+        let (compressing, plain) = state.targets_by_capabilities(
+            Some("127.0.0.1:2003".parse().unwrap()),
+            Capabilities::COMPRESSION,
+        );
+        assert_eq!(
+            HashSet::<SocketAddr>::from_iter(compressing.unwrap().into_iter()),
+            HashSet::from_iter(["127.0.0.1:2002".parse().unwrap()])
+        );
+        assert_eq!(
+            HashSet::<SocketAddr>::from_iter(plain.unwrap().into_iter()),
+            HashSet::from_iter(["127.0.0.1:2001".parse().unwrap()])
+        );
+    }
+
+    #[test]
+    fn test_rejoin() {
+        let mut state = GameStateInner::new(2, GameSlots::default(), GRACE_PERIOD, None);
+        let first: SocketAddr = "127.0.0.1:5001".parse().unwrap();
+        let second: SocketAddr = "127.0.0.1:5002".parse().unwrap();
+        let now = Instant::now();
+
+        let (id, token) = state.add(first, None).unwrap();
+        assert_eq!(state.num_players(), 1);
+        assert_eq!(state.disconnect(first, now), Some(id));
+        assert_eq!(state.num_players(), 1);
+        assert!(state.disconnect(first, now).is_none());
+        assert!(!state.contains(first));
+        assert!(!state.is_empty());
+
+        assert!(matches!(
+            state.rejoin(second, Token::random()),
+            Err(JoinError::InvalidToken)
+        ));
+        let (rejoined_id, new_token) = state.rejoin(second, token).unwrap();
+        assert_eq!(rejoined_id, id);
+        assert_ne!(new_token, token);
+        assert_eq!(state.token(second), Some(new_token));
+        assert!(matches!(
+            state.rejoin(second, token),
+            Err(JoinError::AlreadyJoined)
+        ));
+
+        state.disconnect(second, now).unwrap();
+        assert!(state.expire(now + Duration::from_secs(1)).is_empty());
+        assert_eq!(state.expire(now + GRACE_PERIOD), vec![id]);
+        assert!(state.is_empty());
+        assert!(matches!(
+            state.rejoin(second, new_token),
+            Err(JoinError::InvalidToken)
+        ));
+        // The ID is available again.
+        assert_eq!(state.add(first, None).unwrap().0, id);
+    }
+
+    #[test]
+    fn test_ban() {
+        let mut state = GameStateInner::new(4, GameSlots::default(), GRACE_PERIOD, None);
+        let first: SocketAddr = "127.0.0.1:6001".parse().unwrap();
+        let second: SocketAddr = "127.0.0.2:6001".parse().unwrap();
+
+        state.add(first, None).unwrap();
+        state.remove(first).unwrap();
+        state.ban(first.ip());
+
+        assert!(matches!(state.add(first, None), Err(JoinError::Banned)));
+        assert!(matches!(
+            state.add("127.0.0.1:6002".parse().unwrap(), None),
+            Err(JoinError::Banned)
+        ));
+        assert!(matches!(state.add_spectator(first), Err(JoinError::Banned)));
+        assert!(matches!(
+            state.rejoin(first, Token::random()),
+            Err(JoinError::Banned)
+        ));
+        state.add(second, None).unwrap();
+    }
+
+    #[test]
+    fn test_spectators() {
+        let mut state = GameStateInner::new(2, GameSlots::default(), GRACE_PERIOD, None);
+        let player: SocketAddr = "127.0.0.1:3001".parse().unwrap();
+        let spectator: SocketAddr = "127.0.0.1:3002".parse().unwrap();
+
+        state.add(player, None).unwrap();
+        state.add_spectator(spectator).unwrap();
+        assert!(matches!(
+            state.add_spectator(player),
+            Err(JoinError::AlreadyJoined)
+        ));
+        assert!(matches!(
+            state.add(spectator, None),
+            Err(JoinError::AlreadyJoined)
+        ));
+
+        assert!(state.contains(spectator));
+        assert!(state.is_spectator(spectator));
+        assert!(!state.is_spectator(player));
+        assert!(state.id(spectator).is_none());
+        assert!(state.token(spectator).is_none());
+
+        assert_eq!(
+            HashSet::<SocketAddr>::from_iter(state.targets(Some(player)).unwrap().into_iter()),
+            HashSet::from_iter([spectator])
+        );
+        assert!(state.team_targets(player).is_none());
+
+        state.remove(player).unwrap();
+        assert!(!state.is_empty());
+        assert!(state.remove_spectator(spectator));
+        assert!(!state.remove_spectator(spectator));
+        assert!(state.is_empty());
+
+        for i in 0..MAX_SPECTATORS {
+            state
+                .add_spectator(format!("127.0.0.1:{}", 4000 + i).parse().unwrap())
+                .unwrap();
+        }
+        assert!(matches!(
+            state.add_spectator(spectator),
+            Err(JoinError::GameFull)
+        ));
     }
 
     #[test]
@@ -277,4 +908,100 @@ mod tests {
         assert_eq!(ids.lease().unwrap(), 3);
         assert!(ids.lease().is_none());
     }
+
+    #[test]
+    fn test_names() {
+        let first: SocketAddr = "127.0.0.1:7001".parse().unwrap();
+        let second: SocketAddr = "127.0.0.1:7002".parse().unwrap();
+        let third: SocketAddr = "127.0.0.1:7003".parse().unwrap();
+
+        let mut state = GameStateInner::new(4, GameSlots::default(), GRACE_PERIOD, None);
+        state.add(first, Some("Indy".into())).unwrap();
+        state.add(second, None).unwrap();
+        assert_eq!(state.name(first).as_deref(), Some("Indy"));
+        assert_eq!(state.name(second), None);
+
+        assert!(matches!(
+            state.add(third, Some("Indy".into())),
+            Err(JoinError::AlreadyJoined)
+        ));
+        state.disconnect(first, Instant::now()).unwrap();
+        assert!(matches!(
+            state.add(third, Some("Indy".into())),
+            Err(JoinError::AlreadyJoined)
+        ));
+        state.add(third, Some("Indy2".into())).unwrap();
+    }
+
+    #[test]
+    fn test_password() {
+        let open = GameStateInner::new(2, GameSlots::default(), GRACE_PERIOD, None);
+        assert!(open.check_password(None));
+        assert!(open.check_password(Some(PasswordHash::new("abc"))));
+
+        let protected = GameStateInner::new(
+            2,
+            GameSlots::default(),
+            GRACE_PERIOD,
+            Some(PasswordHash::new("abc")),
+        );
+        assert!(!protected.check_password(None));
+        assert!(!protected.check_password(Some(PasswordHash::new("abd"))));
+        assert!(protected.check_password(Some(PasswordHash::new("abc"))));
+    }
+
+    #[test]
+    fn test_surrender() {
+        let first: SocketAddr = "127.0.0.1:9001".parse().unwrap();
+        let second: SocketAddr = "127.0.0.1:9002".parse().unwrap();
+        let third: SocketAddr = "127.0.0.1:9003".parse().unwrap();
+        let spectator: SocketAddr = "127.0.0.1:9004".parse().unwrap();
+
+        let mut state = GameStateInner::new(3, GameSlots::default(), GRACE_PERIOD, None);
+        let (first_id, _) = state.add(first, None).unwrap();
+        state.add(second, None).unwrap();
+        state.add(third, None).unwrap();
+        state.add_spectator(spectator).unwrap();
+        assert!(!state.is_decided());
+
+        assert_eq!(state.surrender(first), Some(first_id));
+        assert_eq!(state.surrender(first), None);
+        assert_eq!(state.surrender(spectator), None);
+        assert!(!state.is_decided());
+
+        // Disconnected players may still rejoin.
+        state.disconnect(second, Instant::now()).unwrap();
+        assert!(!state.is_decided());
+        state.surrender(third).unwrap();
+        assert!(state.is_decided());
+    }
+
+    #[test]
+    fn test_slots() {
+        let first: SocketAddr = "127.0.0.1:8001".parse().unwrap();
+        let second: SocketAddr = "127.0.0.1:8002".parse().unwrap();
+        let third: SocketAddr = "127.0.0.1:8003".parse().unwrap();
+
+        // IDs 1 and 2 are human slots, 3 and 4 are AI slots of team 1 and 2.
+        let mut state = GameStateInner::new(4, GameSlots::new(2, 2), GRACE_PERIOD, None);
+        assert_eq!(state.num_players(), 2);
+        assert!(state.is_empty());
+
+        assert_eq!(state.add(first, None).unwrap().0, 1);
+        assert_eq!(state.add(second, None).unwrap().0, 2);
+        assert!(matches!(state.add(third, None), Err(JoinError::GameFull)));
+        assert_eq!(state.num_players(), 4);
+        assert_eq!(state.team(first), Some(1));
+        assert_eq!(state.team(second), Some(2));
+
+        // Team 1 is full (the first player and an AI player).
+        assert!(!state.set_team(second, 1));
+        assert!(!state.set_team(second, 3));
+        assert!(!state.set_team(second, 0));
+        assert!(state.set_team(second, 2));
+
+        state.remove(first).unwrap();
+        assert!(state.set_team(second, 1));
+        assert_eq!(state.team(second), Some(1));
+    }
 }
@@ -0,0 +1,189 @@
+//! Validation of packages relayed between players.
+//!
+//! Packages sent by players to other players are relayed as they are. A
+//! [`Validator`] may inspect them and reject those which are structurally
+//! invalid, which impersonate another player (for example lockstep commands
+//! of another player) or which break game-specific rules (for example a
+//! command issued to an entity of another player). Rejected packages are not
+//! relayed.
+
+use de_net::{BincodeCodec, Codec, ToPlayers};
+use thiserror::Error;
+
+/// A single validation rule of player packages.
+pub(super) trait Validator: Send + Sync {
+    /// Validates a package sent by a player to other players.
+    ///
+    /// # Arguments
+    ///
+    /// * `player` - ID of the sending player.
+    ///
+    /// * `data` - package payload.
+    fn validate(&self, player: u8, data: &[u8]) -> Result<(), Rejection>;
+}
+
+/// Reason of a rejection of a player package.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("{0}")]
+pub(super) struct Rejection(String);
+
+impl Rejection {
+    pub(super) fn new(reason: impl Into<String>) -> Self {
+        Self(reason.into())
+    }
+}
+
+/// An ordered set of validators. A package is accepted only if it is
+/// accepted by all of them.
+pub(super) struct Validators(Vec<Box<dyn Validator>>);
+
+impl Validators {
+    /// Creates a set of validators accepting all packages.
+    pub(super) fn empty() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Appends a validator to the set.
+    pub(super) fn with<V: Validator + 'static>(mut self, validator: V) -> Self {
+        self.0.push(Box::new(validator));
+        self
+    }
+
+    /// Validates a package with all validators. It returns rejection of the
+    /// first validator which rejected the package.
+    pub(super) fn validate(&self, player: u8, data: &[u8]) -> Result<(), Rejection> {
+        self.0
+            .iter()
+            .try_for_each(|validator| validator.validate(player, data))
+    }
+}
+
+impl Default for Validators {
+    /// Creates a set of game agnostic validators.
+    fn default() -> Self {
+        Self::empty().with(NonEmpty).with(Sender)
+    }
+}
+
+/// Rejects packages without any payload since they do not carry any message.
+struct NonEmpty;
+
+impl Validator for NonEmpty {
+    fn validate(&self, _player: u8, data: &[u8]) -> Result<(), Rejection> {
+        if data.is_empty() {
+            Err(Rejection::new("empty package"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Rejects packages which are not a sequence of [`ToPlayers`] messages and
+/// packages with a message claiming to be sent by another player.
+struct Sender;
+
+impl Validator for Sender {
+    fn validate(&self, player: u8, data: &[u8]) -> Result<(), Rejection> {
+        let mut offset = 0;
+        while offset < data.len() {
+            let (message, len): (ToPlayers, usize) = BincodeCodec
+                .decode_from_slice(&data[offset..])
+                .map_err(|err| Rejection::new(format!("invalid message: {err}")))?;
+            offset += len;
+
+            if let Some(sender) = message.sender() {
+                if sender != player {
+                    return Err(Rejection::new(format!(
+                        "message claims to be sent by player {sender}"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Only(u8);
+
+    impl Validator for Only {
+        fn validate(&self, player: u8, _data: &[u8]) -> Result<(), Rejection> {
+            if player == self.0 {
+                Ok(())
+            } else {
+                Err(Rejection::new(format!("player {player} is not allowed")))
+            }
+        }
+    }
+
+    fn encode(messages: &[ToPlayers]) -> Vec<u8> {
+        let mut data = Vec::new();
+        for message in messages {
+            data.extend(BincodeCodec.encode_to_vec(message).unwrap());
+        }
+        data
+    }
+
+    #[test]
+    fn test_validators() {
+        assert!(Validators::empty().validate(1, &[]).is_ok());
+
+        let resume = encode(&[ToPlayers::Resume { player: 1 }]);
+        let validators = Validators::default().with(Only(1));
+        assert!(validators.validate(1, &resume).is_ok());
+        assert_eq!(
+            validators.validate(1, &[]),
+            Err(Rejection::new("empty package"))
+        );
+        assert_eq!(
+            validators.validate(2, &encode(&[ToPlayers::Resume { player: 2 }])),
+            Err(Rejection::new("player 2 is not allowed"))
+        );
+    }
+
+    #[test]
+    fn test_sender() {
+        let honest = encode(&[
+            ToPlayers::Commands {
+                player: 1,
+                tick: 8,
+                commands: vec![1, 2, 3],
+            },
+            ToPlayers::Checksum {
+                player: 1,
+                tick: 8,
+                hash: 42,
+            },
+        ]);
+        assert!(Sender.validate(1, &honest).is_ok());
+
+        // Player 1 pretends to be player 2 in the second message.
+        let forged = encode(&[
+            ToPlayers::Resume { player: 1 },
+            ToPlayers::Commands {
+                player: 2,
+                tick: 8,
+                commands: vec![1, 2, 3],
+            },
+        ]);
+        assert_eq!(
+            Sender.validate(1, &forged),
+            Err(Rejection::new("message claims to be sent by player 2"))
+        );
+        assert!(Sender.validate(2, &forged).is_err());
+
+        assert!(Sender.validate(1, &[255, 255, 255]).is_err());
+
+        let commands = encode(&[ToPlayers::Commands {
+            player: 3,
+            tick: 2,
+            commands: Vec::new(),
+        }]);
+        assert!(Validators::default().validate(3, &commands).is_ok());
+        assert!(Validators::default().validate(1, &commands).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use std::{io, net::IpAddr};
+
+use ahash::AHashMap;
+use async_std::sync::{Arc, Mutex};
+use de_net::{GameListing, Socket};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::{config::PortRange, metrics::GameMetrics};
+
+/// Registry of running game servers and ports they occupy.
+///
+/// Ports of newly opened games are either taken from a configured pool of
+/// ports or assigned by the system.
+#[derive(Clone)]
+pub(crate) struct Games {
+    inner: Arc<Mutex<GamesInner>>,
+}
+
+impl Games {
+    /// # Arguments
+    ///
+    /// * `address` - local IP address game sockets are bound to. If None,
+    ///   dual-stack sockets bound to all interfaces are used.
+    ///
+    /// * `pool` - ports available to game servers. If None, system assigned
+    ///   ports are used.
+    ///
+    /// * `max_games` - maximum number of concurrently registered games. If
+    ///   None, the number is limited only by the available ports.
+    pub(crate) fn new(
+        address: Option<IpAddr>,
+        pool: Option<PortRange>,
+        max_games: Option<usize>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(GamesInner::new(address, pool, max_games))),
+        }
+    }
+
+    /// Opens a socket for a new game server and registers the game. It
+    /// returns the socket and (newly created) metrics of the game.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the game used in game listings.
+    ///
+    /// * `map` - name of the map of the game used in game listings.
+    ///
+    /// * `max_players` - maximum number of players in the game.
+    ///
+    /// * `protected` - whether the game requires a password to join.
+    pub(crate) async fn open(
+        &mut self,
+        name: String,
+        map: String,
+        max_players: u8,
+        protected: bool,
+    ) -> Result<(Socket, GameMetrics), OpenError> {
+        self.inner
+            .lock()
+            .await
+            .open(name, map, max_players, protected)
+            .await
+    }
+
+    /// Updates number of players in a registered game.
+    pub(crate) async fn set_players(&mut self, port: u16, num_players: u8) {
+        self.inner.lock().await.set_players(port, num_players)
+    }
+
+    /// Returns listings of all registered games sorted by port.
+    pub(crate) async fn list(&self) -> Vec<GameListing> {
+        self.inner.lock().await.list()
+    }
+
+    /// Returns metrics of all registered games sorted by port.
+    pub(crate) async fn metrics(&self) -> Vec<(u16, GameMetrics)> {
+        self.inner.lock().await.metrics()
+    }
+
+    /// Marks a game as decided. Such games are no longer listed.
+    pub(crate) async fn finish(&mut self, port: u16) {
+        self.inner.lock().await.finish(port)
+    }
+
+    /// Unregisters a finished game so that its port may be reused.
+    pub(crate) async fn close(&mut self, port: u16) {
+        self.inner.lock().await.close(port)
+    }
+}
+
+struct GamesInner {
+    address: Option<IpAddr>,
+    pool: Option<PortRange>,
+    max_games: Option<usize>,
+    games: AHashMap<u16, GameEntry>,
+}
+
+impl GamesInner {
+    fn new(address: Option<IpAddr>, pool: Option<PortRange>, max_games: Option<usize>) -> Self {
+        Self {
+            address,
+            pool,
+            max_games,
+            games: AHashMap::new(),
+        }
+    }
+
+    async fn open(
+        &mut self,
+        name: String,
+        map: String,
+        max_players: u8,
+        protected: bool,
+    ) -> Result<(Socket, GameMetrics), OpenError> {
+        if self
+            .max_games
+            .map_or(false, |max_games| self.games.len() >= max_games)
+        {
+            return Err(OpenError::Limit);
+        }
+
+        let socket = match self.pool {
+            Some(ref pool) => {
+                let mut socket = None;
+                for port in pool.iter().filter(|port| !self.games.contains_key(port)) {
+                    // The port might be still held by a finishing game or by
+                    // another process.
+                    match self.bind(Some(port)).await {
+                        Ok(bound) => {
+                            socket = Some(bound);
+                            break;
+                        }
+                        Err(err) => warn!("Failed to bind game port {port}: {err:?}"),
+                    }
+                }
+                socket.ok_or(OpenError::Exhausted)?
+            }
+            None => self.bind(None).await?,
+        };
+
+        let metrics = GameMetrics::default();
+        self.games.insert(
+            socket.port(),
+            GameEntry {
+                name,
+                map,
+                num_players: 0,
+                max_players,
+                protected,
+                finished: false,
+                metrics: metrics.clone(),
+            },
+        );
+        Ok((socket, metrics))
+    }
+
+    async fn bind(&self, port: Option<u16>) -> io::Result<Socket> {
+        match self.address {
+            Some(address) => Socket::bind_to(address, port).await,
+            None => Socket::bind(port).await,
+        }
+    }
+
+    fn close(&mut self, port: u16) {
+        self.games.remove(&port);
+    }
+
+    fn set_players(&mut self, port: u16, num_players: u8) {
+        if let Some(game) = self.games.get_mut(&port) {
+            game.num_players = num_players;
+        }
+    }
+
+    fn finish(&mut self, port: u16) {
+        if let Some(game) = self.games.get_mut(&port) {
+            game.finished = true;
+        }
+    }
+
+    fn list(&self) -> Vec<GameListing> {
+        let mut listings: Vec<GameListing> = self
+            .games
+            .iter()
+            .filter(|(_, game)| !game.finished)
+            .map(|(&port, game)| {
+                GameListing::new(
+                    port,
+                    game.name.clone(),
+                    game.map.clone(),
+                    game.num_players,
+                    game.max_players,
+                    game.protected,
+                )
+            })
+            .collect();
+        listings.sort_unstable_by_key(|listing| listing.port());
+        listings
+    }
+
+    fn metrics(&self) -> Vec<(u16, GameMetrics)> {
+        let mut metrics: Vec<(u16, GameMetrics)> = self
+            .games
+            .iter()
+            .map(|(&port, game)| (port, game.metrics.clone()))
+            .collect();
+        metrics.sort_unstable_by_key(|&(port, _)| port);
+        metrics
+    }
+}
+
+struct GameEntry {
+    name: String,
+    map: String,
+    num_players: u8,
+    max_players: u8,
+    protected: bool,
+    /// True if the game was already decided.
+    finished: bool,
+    metrics: GameMetrics,
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum OpenError {
+    #[error("all ports from the game port pool are in use")]
+    Exhausted,
+    #[error("maximum number of games has been reached")]
+    Limit,
+    #[error("failed to bind a socket")]
+    Io(#[from] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+
+    use super::*;
+
+    #[test]
+    fn test_pool() {
+        task::block_on(task::spawn(async {
+            let mut games = Games::new(None, Some("18391-18392".parse().unwrap()), None);
+
+            let (first, _) = games.open("A".into(), "M".into(), 2, false).await.unwrap();
+            assert_eq!(first.port(), 18391);
+            let (second, _) = games.open("B".into(), "N".into(), 3, true).await.unwrap();
+            assert_eq!(second.port(), 18392);
+            assert!(matches!(
+                games.open("C".into(), "M".into(), 2, false).await,
+                Err(OpenError::Exhausted)
+            ));
+
+            games.set_players(18392, 2).await;
+            assert_eq!(
+                games.list().await,
+                vec![
+                    GameListing::new(18391, "A".into(), "M".into(), 0, 2, false),
+                    GameListing::new(18392, "B".into(), "N".into(), 2, 3, true),
+                ]
+            );
+
+            games.close(18391).await;
+            assert_eq!(games.list().await.len(), 1);
+            assert_eq!(games.metrics().await[0].0, 18392);
+
+            games.finish(18392).await;
+            assert!(games.list().await.is_empty());
+            assert_eq!(games.metrics().await.len(), 1);
+            // The port is still bound by the socket.
+            assert!(matches!(
+                games.open("C".into(), "M".into(), 2, false).await,
+                Err(OpenError::Exhausted)
+            ));
+
+            drop(first);
+            assert_eq!(
+                games
+                    .open("C".into(), "M".into(), 2, false)
+                    .await
+                    .unwrap()
+                    .0
+                    .port(),
+                18391
+            );
+        }));
+    }
+
+    #[test]
+    fn test_limit() {
+        task::block_on(task::spawn(async {
+            let mut games = Games::new(Some("127.0.0.1".parse().unwrap()), None, Some(1));
+
+            let (socket, _) = games.open("A".into(), "M".into(), 2, false).await.unwrap();
+            assert!(matches!(
+                games.open("B".into(), "M".into(), 2, false).await,
+                Err(OpenError::Limit)
+            ));
+
+            games.close(socket.port()).await;
+            games.open("B".into(), "M".into(), 2, false).await.unwrap();
+        }));
+    }
+}
@@ -1,16 +1,19 @@
 use anyhow::Context;
 use async_std::task;
-use de_net::Socket;
+use de_net::{NetMetrics, Socket};
 use tracing::{error, info};
 
-use crate::server::MainServer;
+use crate::{clients::Clients, config::Config, games::Games, server::MainServer};
 
+mod auth;
 mod clients;
+mod config;
 mod game;
+mod games;
+mod limiter;
+mod metrics;
 mod server;
 
-const PORT: u16 = 8082;
-
 pub fn start() {
     info!("Starting...");
 
@@ -22,11 +25,35 @@ pub fn start() {
 }
 
 async fn start_inner() -> anyhow::Result<()> {
-    let socket = Socket::bind(Some(PORT))
-        .await
-        .with_context(|| format!("Failed to open network on port {PORT}"))?;
-    info!("Listening on port {PORT}");
+    let config = Config::load()?;
+
+    let port = config.port();
+    let socket = match config.address() {
+        Some(address) => Socket::bind_to(address, Some(port)).await,
+        None => Socket::bind(Some(port)).await,
+    }
+    .with_context(|| format!("Failed to open network on port {port}"))?;
+    info!("Listening on port {port}");
+
+    let games = Games::new(
+        config.address(),
+        config.game_ports().cloned(),
+        config.max_games(),
+    );
+    let clients = Clients::new();
+    let server_metrics = NetMetrics::default();
+
+    if let Some(addr) = config.metrics_addr() {
+        let games = games.clone();
+        let clients = clients.clone();
+        let server_metrics = server_metrics.clone();
+        task::spawn(async move {
+            if let Err(error) = metrics::serve(addr, games, clients, port, server_metrics).await {
+                error!("{:?}", error);
+            }
+        });
+    }
 
-    let server = MainServer::start(socket);
+    let server = MainServer::start(config, socket, games, clients, server_metrics);
     server.run().await
 }
@@ -0,0 +1,160 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+
+/// Sustained number of packages per second accepted from a single client.
+const RATE: f32 = 200.;
+/// Maximum number of packages accepted from a single client in a burst.
+const BURST: f32 = 400.;
+/// Every dropped package adds one to the penalty of the client. The penalty
+/// decays by this much every second.
+const PENALTY_DECAY: f32 = 20.;
+/// Clients whose penalty reaches this are considered flooding.
+const MAX_PENALTY: f32 = 1000.;
+/// How often are records of idle clients removed.
+const CLEAN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Per-source token bucket rate limiter of received packages.
+///
+/// Unreliable packages over the limit are dropped. Reliable packages are
+/// already confirmed by the network stack when received, thus they are never
+/// dropped, but they still count towards the limit. Clients which keep
+/// exceeding the limit accumulate a penalty and once it is too high, they are
+/// reported as flooding.
+pub(crate) struct RateLimiter {
+    buckets: AHashMap<SocketAddr, Bucket>,
+    last_clean: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(time: Instant) -> Self {
+        Self {
+            buckets: AHashMap::new(),
+            last_clean: time,
+        }
+    }
+
+    /// Registers a package received from `addr` and decides its fate.
+    pub(crate) fn check(&mut self, time: Instant, addr: SocketAddr) -> Verdict {
+        self.buckets
+            .entry(addr)
+            .or_insert_with(|| Bucket::new(time))
+            .take(time)
+    }
+
+    /// Removes records of clients which did not exceed the limit recently.
+    /// This is a no-op if it was called less than a few seconds ago.
+    pub(crate) fn clean(&mut self, time: Instant) {
+        if time.saturating_duration_since(self.last_clean) < CLEAN_INTERVAL {
+            return;
+        }
+        self.last_clean = time;
+
+        self.buckets.retain(|_, bucket| {
+            bucket.refill(time);
+            bucket.tokens < BURST || bucket.penalty > 0.
+        });
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Verdict {
+    /// The package should be processed.
+    Accept,
+    /// The package should be dropped unless it is reliable.
+    Throttle,
+    /// The package should be dropped and the client disconnected.
+    Flooding,
+}
+
+struct Bucket {
+    updated: Instant,
+    tokens: f32,
+    penalty: f32,
+}
+
+impl Bucket {
+    fn new(time: Instant) -> Self {
+        Self {
+            updated: time,
+            tokens: BURST,
+            penalty: 0.,
+        }
+    }
+
+    fn refill(&mut self, time: Instant) {
+        let elapsed = time.saturating_duration_since(self.updated).as_secs_f32();
+        self.updated = self.updated.max(time);
+        self.tokens = (self.tokens + elapsed * RATE).min(BURST);
+        self.penalty = (self.penalty - elapsed * PENALTY_DECAY).max(0.);
+    }
+
+    fn take(&mut self, time: Instant) -> Verdict {
+        self.refill(time);
+
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            return Verdict::Accept;
+        }
+
+        self.penalty += 1.;
+        if self.penalty >= MAX_PENALTY {
+            self.penalty = 0.;
+            Verdict::Flooding
+        } else {
+            Verdict::Throttle
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter() {
+        let first: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+        let second: SocketAddr = "127.0.0.1:1002".parse().unwrap();
+
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(start);
+
+        for _ in 0..(BURST as usize) {
+            assert_eq!(limiter.check(start, first), Verdict::Accept);
+        }
+        assert_eq!(limiter.check(start, first), Verdict::Throttle);
+        assert_eq!(limiter.check(start, second), Verdict::Accept);
+
+        let time = start + Duration::from_millis(100);
+        for _ in 0..((RATE / 10.) as usize) {
+            assert_eq!(limiter.check(time, first), Verdict::Accept);
+        }
+        assert_eq!(limiter.check(time, first), Verdict::Throttle);
+
+        let mut flooding = false;
+        for _ in 0..(MAX_PENALTY as usize) {
+            match limiter.check(time, first) {
+                Verdict::Accept => unreachable!(),
+                Verdict::Throttle => (),
+                Verdict::Flooding => {
+                    flooding = true;
+                    break;
+                }
+            }
+        }
+        assert!(flooding);
+
+        let time = start + CLEAN_INTERVAL;
+        for _ in 0..(BURST as usize) {
+            assert_eq!(limiter.check(time, first), Verdict::Accept);
+        }
+        assert_eq!(limiter.check(time, first), Verdict::Throttle);
+
+        limiter.clean(time);
+        assert_eq!(limiter.buckets.len(), 1);
+        assert!(limiter.buckets.contains_key(&first));
+    }
+}
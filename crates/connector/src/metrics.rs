@@ -0,0 +1,290 @@
+//! Export of connector metrics in Prometheus text exposition format over a
+//! minimal HTTP endpoint.
+
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use anyhow::Context;
+use async_std::{
+    future::timeout,
+    io::{prelude::WriteExt, ReadExt},
+    net::{TcpListener, TcpStream},
+    sync::Arc,
+    task,
+};
+use de_net::NetMetrics;
+use futures::StreamExt;
+use tracing::{info, warn};
+
+use crate::{clients::Clients, games::Games};
+
+/// Maximum accepted size of an HTTP request head.
+const MAX_REQUEST_SIZE: usize = 4096;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Metrics of a single game server.
+#[derive(Clone, Default)]
+pub(crate) struct GameMetrics {
+    net: NetMetrics,
+    relayed: Arc<AtomicU64>,
+    rejected: Arc<AtomicU64>,
+}
+
+impl GameMetrics {
+    /// Metrics of the network stack of the game server.
+    pub(crate) fn net(&self) -> &NetMetrics {
+        &self.net
+    }
+
+    /// Registers a player package relayed to other players.
+    pub(crate) fn record_relayed(&self) {
+        self.relayed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Registers a player package rejected by validation.
+    pub(crate) fn record_rejected(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn relayed(&self) -> u64 {
+        self.relayed.load(Ordering::Relaxed)
+    }
+
+    fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+}
+
+/// Serves metrics over HTTP on `addr` until an IO error occurs.
+///
+/// # Arguments
+///
+/// * `server_port` - port of the main server.
+///
+/// * `server` - network metrics of the main server.
+pub(crate) async fn serve(
+    addr: SocketAddr,
+    games: Games,
+    clients: Clients,
+    server_port: u16,
+    server: NetMetrics,
+) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind metrics endpoint to {addr}"))?;
+    info!("Serving metrics on {addr}");
+
+    let mut incoming = listener.incoming();
+    while let Some(stream) = incoming.next().await {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("Failed to accept a metrics connection: {err:?}");
+                continue;
+            }
+        };
+        let exporter = Exporter {
+            games: games.clone(),
+            clients: clients.clone(),
+            server_port,
+            server: server.clone(),
+        };
+        task::spawn(async move {
+            let result = timeout(REQUEST_TIMEOUT, exporter.respond(stream)).await;
+            match result {
+                Ok(Err(err)) => warn!("Failed to serve metrics: {err:?}"),
+                Err(_) => warn!("Metrics request timed out."),
+                Ok(Ok(())) => (),
+            }
+        });
+    }
+
+    Ok(())
+}
+
+struct Exporter {
+    games: Games,
+    clients: Clients,
+    server_port: u16,
+    server: NetMetrics,
+}
+
+impl Exporter {
+    async fn respond(self, mut stream: TcpStream) -> anyhow::Result<()> {
+        let mut buf = [0u8; MAX_REQUEST_SIZE];
+        let mut len = 0;
+        loop {
+            let read = stream.read(&mut buf[len..]).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            len += read;
+            if buf[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+                break;
+            }
+            if len == buf.len() {
+                return write_response(&mut stream, "413 Payload Too Large", "").await;
+            }
+        }
+
+        let line = buf[..len].split(|&b| b == b'\r').next().unwrap();
+        let mut parts = line.split(|&b| b == b' ');
+        let method = parts.next();
+        let path = parts.next();
+
+        if method != Some(b"GET") || path != Some(b"/metrics") {
+            return write_response(&mut stream, "404 Not Found", "").await;
+        }
+
+        let body = self.render().await;
+        write_response(&mut stream, "200 OK", &body).await
+    }
+
+    async fn render(&self) -> String {
+        let games = self.games.metrics().await;
+        let mut nets = vec![(self.server_port, self.server.clone())];
+        nets.extend(
+            games
+                .iter()
+                .map(|(port, metrics)| (*port, metrics.net().clone())),
+        );
+
+        let mut out = String::new();
+        write_family(
+            &mut out,
+            "de_connector_games",
+            "Number of running games.",
+            "gauge",
+            [(None, games.len() as u64)],
+        );
+        write_family(
+            &mut out,
+            "de_connector_clients",
+            "Number of clients joined to or reserved in a game.",
+            "gauge",
+            [(None, self.clients.len().await as u64)],
+        );
+        write_family(
+            &mut out,
+            "de_connector_datagrams_sent_total",
+            "Number of sent UDP datagrams.",
+            "counter",
+            nets.iter()
+                .map(|(port, net)| (Some(*port), net.datagrams_sent())),
+        );
+        write_family(
+            &mut out,
+            "de_connector_datagrams_received_total",
+            "Number of received UDP datagrams.",
+            "counter",
+            nets.iter()
+                .map(|(port, net)| (Some(*port), net.datagrams_received())),
+        );
+        write_family(
+            &mut out,
+            "de_connector_bytes_sent_total",
+            "Number of bytes sent in UDP datagrams.",
+            "counter",
+            nets.iter()
+                .map(|(port, net)| (Some(*port), net.bytes_sent())),
+        );
+        write_family(
+            &mut out,
+            "de_connector_bytes_received_total",
+            "Number of bytes received in UDP datagrams.",
+            "counter",
+            nets.iter()
+                .map(|(port, net)| (Some(*port), net.bytes_received())),
+        );
+        write_family(
+            &mut out,
+            "de_connector_retransmissions_total",
+            "Number of re-sent reliable packages.",
+            "counter",
+            nets.iter()
+                .map(|(port, net)| (Some(*port), net.retransmissions())),
+        );
+        write_family(
+            &mut out,
+            "de_connector_relayed_packages_total",
+            "Number of player packages relayed to other players.",
+            "counter",
+            games
+                .iter()
+                .map(|(port, metrics)| (Some(*port), metrics.relayed())),
+        );
+        write_family(
+            &mut out,
+            "de_connector_rejected_packages_total",
+            "Number of player packages rejected by validation.",
+            "counter",
+            games
+                .iter()
+                .map(|(port, metrics)| (Some(*port), metrics.rejected())),
+        );
+        out
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> anyhow::Result<()> {
+    let head = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Writes a single metric family. Each sample is optionally labeled with a
+/// port.
+fn write_family<I>(out: &mut String, name: &str, help: &str, kind: &str, samples: I)
+where
+    I: IntoIterator<Item = (Option<u16>, u64)>,
+{
+    writeln!(out, "# HELP {name} {help}").unwrap();
+    writeln!(out, "# TYPE {name} {kind}").unwrap();
+    for (port, value) in samples {
+        match port {
+            Some(port) => writeln!(out, "{name}{{port=\"{port}\"}} {value}").unwrap(),
+            None => writeln!(out, "{name} {value}").unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_family() {
+        let mut out = String::new();
+        write_family(
+            &mut out,
+            "a_total",
+            "Help of a.",
+            "counter",
+            [(Some(8082), 3), (Some(8083), 0)],
+        );
+        write_family(&mut out, "b", "Help of b.", "gauge", [(None, 7)]);
+
+        assert_eq!(
+            out,
+            "# HELP a_total Help of a.\n\
+             # TYPE a_total counter\n\
+             a_total{port=\"8082\"} 3\n\
+             a_total{port=\"8083\"} 0\n\
+             # HELP b Help of b.\n\
+             # TYPE b gauge\n\
+             b 7\n"
+        );
+    }
+}
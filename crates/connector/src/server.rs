@@ -1,36 +1,63 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Instant};
 
 use anyhow::Context;
 use async_std::task;
 use de_net::{
-    self, FromServer, GameOpenError, MessageDecoder, OutPackage, PackageReceiver, PackageSender,
-    Peers, Socket, ToServer,
+    self, Capabilities, FromServer, GameOpenError, MessageDecoder, NetMetrics, OutPackage,
+    PackageBuilder, PackageReceiver, PackageSender, Peers, Socket, ToServer, MAX_GAME_NAME_LEN,
+    MAX_MAP_NAME_LEN,
 };
-use tracing::{error, info, warn};
+use tracing::{error, info, trace, warn};
 
-use crate::{clients::Clients, game};
+use crate::{
+    auth::Verifier,
+    clients::Clients,
+    config::Config,
+    game::{self, GameSetup, Owner},
+    games::Games,
+    limiter::{RateLimiter, Verdict},
+};
 
 /// Main game server responsible for initial communication with clients and
 /// establishment of game sub-servers.
 pub(crate) struct MainServer {
+    config: Config,
+    verifier: Option<Verifier>,
     outputs: PackageSender,
     inputs: PackageReceiver,
     clients: Clients,
+    games: Games,
+    limiter: RateLimiter,
 }
 
 impl MainServer {
     /// Setup the server & startup its network stack.
-    pub(crate) fn start(socket: Socket) -> Self {
-        let (outputs, inputs, _) = de_net::startup(
+    ///
+    /// # Arguments
+    ///
+    /// * `metrics` - traffic of the main server is counted into these.
+    pub(crate) fn start(
+        config: Config,
+        socket: Socket,
+        games: Games,
+        clients: Clients,
+        metrics: NetMetrics,
+    ) -> Self {
+        let (outputs, inputs, _, _) = de_net::startup(
             |t| {
                 task::spawn(t);
             },
             socket,
+            config.net_conf().with_metrics(metrics),
         );
         Self {
+            verifier: config.verifier(),
+            config,
             outputs,
             inputs,
-            clients: Clients::new(),
+            clients,
+            games,
+            limiter: RateLimiter::new(Instant::now()),
         }
     }
 
@@ -42,6 +69,26 @@ impl MainServer {
                 .await
                 .context("Inputs channel unexpectedly closed")?;
 
+            let time = Instant::now();
+            self.limiter.clean(time);
+            match self.limiter.check(time, package.source()) {
+                Verdict::Accept => (),
+                // Reliable packages were already confirmed to the client,
+                // which would wait for a reply forever.
+                Verdict::Throttle if package.reliable() => (),
+                Verdict::Throttle => {
+                    trace!(
+                        "Dropping a package from throttled client {:?}.",
+                        package.source()
+                    );
+                    continue;
+                }
+                Verdict::Flooding => {
+                    warn!("Client {:?} is flooding the main server.", package.source());
+                    continue;
+                }
+            }
+
             match package.peers() {
                 Peers::Players => {
                     warn!("Package for players unexpectedly received.");
@@ -66,14 +113,88 @@ impl MainServer {
 
             match message {
                 ToServer::Ping(id) => self.reply(&FromServer::Pong(id), source).await?,
-                ToServer::OpenGame { max_players } => self.open_game(source, max_players).await?,
+                ToServer::OpenGame {
+                    max_players,
+                    slots,
+                    name,
+                    map,
+                    auth,
+                    password,
+                    capabilities,
+                } => {
+                    let setup = GameSetup::new(max_players, slots, password);
+                    self.open_game(source, setup, name, map, auth, capabilities)
+                        .await?
+                }
+                ToServer::ListGames => self.list_games(source).await?,
             }
         }
 
         Ok(())
     }
 
-    async fn open_game(&mut self, source: SocketAddr, max_players: u8) -> anyhow::Result<()> {
+    async fn open_game(
+        &mut self,
+        source: SocketAddr,
+        setup: GameSetup,
+        name: String,
+        map: String,
+        auth: Option<String>,
+        capabilities: Capabilities,
+    ) -> anyhow::Result<()> {
+        let owner_name = match self.verifier {
+            Some(ref verifier) => match verifier.verify(auth.as_deref()) {
+                Ok(user) => Some(user),
+                Err(err) => {
+                    warn!("OpenGame request from {source:?} not authenticated: {err}");
+                    self.reply(
+                        &FromServer::GameOpenError(GameOpenError::Unauthenticated),
+                        source,
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+
+        if name.len() > MAX_GAME_NAME_LEN || map.len() > MAX_MAP_NAME_LEN {
+            warn!("OpenGame request with too long game or map name.");
+            self.reply(
+                &FromServer::GameOpenError(GameOpenError::InvalidListing),
+                source,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        if self
+            .config
+            .max_players()
+            .map_or(false, |limit| setup.max_players() > limit)
+        {
+            warn!(
+                "OpenGame request with too many players: {}.",
+                setup.max_players()
+            );
+            self.reply(
+                &FromServer::GameOpenError(GameOpenError::TooManyPlayers),
+                source,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        if !setup.slots().is_valid(setup.max_players()) {
+            warn!("OpenGame request with invalid slots: {:?}.", setup.slots());
+            self.reply(
+                &FromServer::GameOpenError(GameOpenError::InvalidSlots),
+                source,
+            )
+            .await?;
+            return Ok(());
+        }
+
         if let Err(err) = self.clients.reserve(source).await {
             warn!("OpenGame request error: {err}");
             self.reply(
@@ -84,24 +205,64 @@ impl MainServer {
             return Ok(());
         }
 
-        match Socket::bind(None).await {
-            Ok(socket) => {
+        match self
+            .games
+            .open(name, map, setup.max_players(), setup.protected())
+            .await
+        {
+            Ok((socket, metrics)) => {
                 let port = socket.port();
                 self.clients.set(source, port).await;
 
                 info!("Starting new game on port {port}.");
                 self.reply(&FromServer::GameOpened { port }, source).await?;
-                game::startup(self.clients.clone(), socket, source, max_players).await;
+                game::startup(
+                    &self.config,
+                    self.clients.clone(),
+                    self.games.clone(),
+                    socket,
+                    metrics,
+                    Owner::new(source, owner_name).with_capabilities(capabilities),
+                    setup,
+                )
+                .await;
                 Ok(())
             }
             Err(error) => {
                 error!("Failed to open a new game: {:?}", error);
                 self.clients.free(source).await;
-                Ok(())
+                self.reply(
+                    &FromServer::GameOpenError(GameOpenError::Unavailable),
+                    source,
+                )
+                .await
             }
         }
     }
 
+    async fn list_games(&mut self, source: SocketAddr) -> anyhow::Result<()> {
+        let listings = self.games.list().await;
+        // The number of games is limited by the number of available ports.
+        let total = u16::try_from(listings.len()).unwrap();
+
+        let mut builder = PackageBuilder::new(true, Peers::Server, source);
+        if let Some(motd) = self.config.motd() {
+            builder.push(&FromServer::Motd(motd.to_owned())).unwrap();
+        }
+        for listing in listings {
+            builder.push(&FromServer::Game(listing)).unwrap();
+        }
+        builder.push(&FromServer::GamesEnd(total)).unwrap();
+
+        for package in builder.build() {
+            self.outputs
+                .send(package)
+                .await
+                .context("Failed to send a game listing")?;
+        }
+        Ok(())
+    }
+
     async fn reply(&mut self, message: &FromServer, target: SocketAddr) -> anyhow::Result<()> {
         self.outputs
             .send(OutPackage::encode_single(message, true, Peers::Server, target).unwrap())
@@ -1,9 +1,10 @@
 use std::{
+    collections::VecDeque,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     time::Duration,
 };
 
-use async_std::{prelude::FutureExt, task};
+use async_std::{future, task};
 use de_net::Socket;
 use futures::join;
 use ntest::timeout;
@@ -47,8 +48,24 @@ impl ReceivedBuffer {
         })
     }
 
-    async fn load(&mut self, net: &mut Socket, buf: &mut [u8; 1024]) {
-        let (n, _) = net.recv(buf).await.unwrap();
+    /// Finds a data datagram whose data start with `filter_prefix` and returns
+    /// its ID and the rest of the data.
+    fn find_id_prefix(&self, filter_reliable: bool, filter_prefix: &[u8]) -> Option<(u32, &[u8])> {
+        self.0.iter().find_map(|incomming| match incomming {
+            Incomming::Data { reliable, id, data } => {
+                if *reliable == filter_reliable && data.starts_with(filter_prefix) {
+                    Some((*id, &data[filter_prefix.len()..]))
+                } else {
+                    None
+                }
+            }
+            Incomming::Confirm(_) => None,
+        })
+    }
+
+    async fn load(&mut self, net: &mut Socket, buf: &mut Buffer) {
+        let buf = recv(net, buf).await;
+        let n = buf.len();
         assert!(n >= 4);
 
         let mut id_bytes = [0u8; 4];
@@ -59,16 +76,28 @@ impl ReceivedBuffer {
             assert!(buf[2] == 0);
             assert!(buf[3] == 0);
 
-            for i in (4..n - 2).step_by(3) {
+            // Each entry is a base ID followed by a bitfield of subsequent
+            // IDs.
+            assert_eq!((n - 4) % 7, 0);
+            for i in (4..n).step_by(7) {
                 id_bytes[0] = 0;
                 id_bytes[1] = buf[i];
                 id_bytes[2] = buf[i + 1];
                 id_bytes[3] = buf[i + 2];
-                let id = u32::from_be_bytes(id_bytes);
-                self.0.push(Incomming::Confirm(id));
+                let base = u32::from_be_bytes(id_bytes);
+                self.0.push(Incomming::Confirm(base));
+
+                let bits = u32::from_be_bytes(buf[i + 3..i + 7].try_into().unwrap());
+                for offset in 0..32 {
+                    if bits & (1 << offset) != 0 {
+                        self.0.push(Incomming::Confirm(base + offset + 1));
+                    }
+                }
             }
         } else {
             let reliable = buf[0] & 64 > 0;
+            // Compression is never negotiated by this client.
+            assert_eq!(buf[0] & 16, 0);
 
             id_bytes[0] = 0;
             id_bytes[1] = buf[1];
@@ -85,6 +114,81 @@ impl ReceivedBuffer {
     }
 }
 
+/// Decodes a bincode (big endian varint) encoded token and returns it in the
+/// form it is sent over the network.
+fn decode_token(data: &[u8]) -> [u8; 8] {
+    let token = match data[0] {
+        byte @ 0..=250 => byte as u64,
+        251 => u16::from_be_bytes(data[1..3].try_into().unwrap()) as u64,
+        252 => u32::from_be_bytes(data[1..5].try_into().unwrap()) as u64,
+        253 => u64::from_be_bytes(data[1..9].try_into().unwrap()),
+        byte => panic!("Unexpected varint discriminant: {byte}"),
+    };
+    token.to_be_bytes()
+}
+
+/// Prepends header and token to a datagram payload.
+fn with_token(header: [u8; 4], token: [u8; 8], payload: &[u8]) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(12 + payload.len());
+    datagram.extend_from_slice(&header);
+    datagram.extend_from_slice(&token);
+    datagram.extend_from_slice(payload);
+    datagram
+}
+
+/// Returns a long encoded player message.
+fn long_commands() -> Vec<u8> {
+    // [0, 1, 0] -> ToPlayers::Commands { player: 1, tick: 0, .. }
+    // [251, 1, 124] -> 380 bytes of commands
+    let mut payload = vec![0, 1, 0, 251, 1, 124];
+    payload.extend_from_slice(&[22; 380]);
+    payload
+}
+
+/// Receive buffer which keeps datagrams unpacked from batch datagrams.
+struct Buffer {
+    data: [u8; 1024],
+    queue: VecDeque<Vec<u8>>,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        Self {
+            data: [0; 1024],
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+/// Receives a single datagram, skipping keep-alive and encryption handshake
+/// datagrams and unpacking batch datagrams.
+async fn recv(net: &mut Socket, buf: &mut Buffer) -> Vec<u8> {
+    loop {
+        if let Some(datagram) = buf.queue.pop_front() {
+            // Keep-alive and hello (the test clients never finish the
+            // encryption handshake, thus everything is sent in plain text).
+            if datagram[0] != 129 && datagram[0] != 132 {
+                return datagram;
+            }
+            continue;
+        }
+
+        let (n, _) = net.recv(&mut buf.data).await.unwrap();
+        if buf.data[..4] == [131, 0, 0, 0] {
+            // Batch: [len_hi, len_lo, datagram...]*
+            let mut offset = 4;
+            while offset < n {
+                let len = u16::from_be_bytes([buf.data[offset], buf.data[offset + 1]]) as usize;
+                offset += 2;
+                buf.queue.push_back(buf.data[offset..offset + len].to_vec());
+                offset += len;
+            }
+        } else {
+            buf.queue.push_back(buf.data[..n].to_vec());
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Incomming {
     Confirm(u32),
@@ -100,10 +204,10 @@ enum Incomming {
 fn test() {
     let child = spawn_and_wait();
 
-    async fn first(mut client: Socket, game_port: u16) {
+    async fn first(mut client: Socket, token: [u8; 8], game_port: u16) {
         let server = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, game_port));
 
-        let mut buffer = [0u8; 1024];
+        let mut buffer = Buffer::new();
 
         let mut received = ReceivedBuffer::new();
         received.load(&mut client, &mut buffer).await;
@@ -113,24 +217,21 @@ fn test() {
         let id = received.find_id(true, &[5, 2]).unwrap().to_be_bytes();
         // And send a confirmation
         client
-            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3]])
+            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0])
             .await
             .unwrap();
 
-        let first_id = received.find_id(true, &[5, 6, 7, 8]).unwrap();
+        let first_id = received.find_id(true, &[10, 2, 1, 8]).unwrap();
 
-        let mut data = [22; 412];
-        data[0] = 64; // Reliable
-        data[1] = 0;
-        data[2] = 0;
-        data[3] = 22;
+        // [64 + 8] -> reliable + token
+        let data = with_token([64 + 8, 0, 0, 22], token, &long_commands());
         client.send(server, &data).await.unwrap();
 
         let mut received = ReceivedBuffer::new();
         received.load(&mut client, &mut buffer).await;
         received.load(&mut client, &mut buffer).await;
         received.assert_confirmed(22);
-        received.find_id(false, &[82, 83, 84]).unwrap();
+        received.find_id(false, &[9, 2, 84]).unwrap();
 
         // Try to send invalid data -- wrong header
         client
@@ -146,41 +247,53 @@ fn test() {
         // Two retries before we confirm.
         let mut received = ReceivedBuffer::new();
         received.load(&mut client, &mut buffer).await;
-        assert_eq!(received.find_id(true, &[5, 6, 7, 8]).unwrap(), first_id);
+        assert_eq!(received.find_id(true, &[10, 2, 1, 8]).unwrap(), first_id);
         let mut received = ReceivedBuffer::new();
         received.load(&mut client, &mut buffer).await;
-        assert_eq!(received.find_id(true, &[5, 6, 7, 8]).unwrap(), first_id);
+        assert_eq!(received.find_id(true, &[10, 2, 1, 8]).unwrap(), first_id);
 
         let id = first_id.to_be_bytes();
         // And send a confirmation
         client
-            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3]])
+            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0])
             .await
             .unwrap();
 
-        client.send(server, &[64, 0, 0, 92, 16]).await.unwrap();
-        client.send(server, &[64, 0, 0, 86, 23]).await.unwrap();
+        client
+            // [7, 1] -> ToPlayers::Resume { player: 1 }
+            .send(server, &with_token([64 + 8, 0, 0, 92], token, &[7, 1]))
+            .await
+            .unwrap();
+        client
+            // [5, 1, 23] -> ToPlayers::Pause { player: 1, tick: 23 }
+            .send(server, &with_token([64 + 8, 0, 0, 86], token, &[5, 1, 23]))
+            .await
+            .unwrap();
         let mut received = ReceivedBuffer::new();
         received.load(&mut client, &mut buffer).await;
         received.assert_confirmed(92);
         received.assert_confirmed(86);
 
         // No more redeliveries expected.
-        assert!(client
-            .recv(&mut buffer)
-            .timeout(Duration::from_secs(2))
-            .await
-            .is_err());
+        assert!(
+            future::timeout(Duration::from_secs(2), recv(&mut client, &mut buffer))
+                .await
+                .is_err()
+        );
     }
 
-    async fn second(mut client: Socket, game_port: u16) {
+    async fn second(mut client: Socket, token: [u8; 8], game_port: u16) {
         let server = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, game_port));
 
-        let mut buffer = [0u8; 1024];
+        let mut buffer = Buffer::new();
 
         client
             // Reliable
-            .send(server, &[64, 0, 0, 14, 5, 6, 7, 8])
+            .send(
+                server,
+                // [10, 2, 1, 8] -> ToPlayers::Pong { player: 2, target: 1, id: 8 }
+                &with_token([64 + 8, 0, 0, 14], token, &[10, 2, 1, 8]),
+            )
             .await
             .unwrap();
 
@@ -188,11 +301,14 @@ fn test() {
         received.load(&mut client, &mut buffer).await;
         received.load(&mut client, &mut buffer).await;
         received.assert_confirmed(14);
-        let id = received.find_id(true, &[22; 408]).unwrap().to_be_bytes();
+        let id = received
+            .find_id(true, &long_commands())
+            .unwrap()
+            .to_be_bytes();
         // Sending confirmation
 
         client
-            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3]])
+            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0])
             .await
             .unwrap();
 
@@ -200,56 +316,63 @@ fn test() {
             .send(
                 server,
                 // Anonymous message
-                &[0, 0, 0, 0, 82, 83, 84],
+                // [9, 2, 84] -> ToPlayers::Ping { player: 2, id: 84 }
+                &with_token([8, 0, 0, 0], token, &[9, 2, 84]),
             )
             .await
             .unwrap();
 
         let mut received = ReceivedBuffer::new();
         received.load(&mut client, &mut buffer).await;
-        let id = received.find_id(true, &[16]).unwrap().to_be_bytes();
+        let id = received.find_id(true, &[7, 1]).unwrap().to_be_bytes();
         client
-            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3]])
+            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0])
             .await
             .unwrap();
 
         let mut received = ReceivedBuffer::new();
         received.load(&mut client, &mut buffer).await;
-        let id = received.find_id(true, &[23]).unwrap().to_be_bytes();
+        let id = received.find_id(true, &[5, 1, 23]).unwrap().to_be_bytes();
         client
-            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3]])
+            .send(server, &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0])
             .await
             .unwrap();
 
-        assert!(client
-            .recv(&mut buffer)
-            .timeout(Duration::from_secs(2))
-            .await
-            .is_err());
+        assert!(
+            future::timeout(Duration::from_secs(2), recv(&mut client, &mut buffer))
+                .await
+                .is_err()
+        );
     }
 
     task::block_on(task::spawn(async {
-        let (first_client, game_port) = create_game().await;
-        let second_client = join_game(game_port).await;
+        let (first_client, first_token, game_port) = create_game().await;
+        let (second_client, second_token) = join_game(game_port).await;
         join!(
-            first(first_client, game_port),
-            second(second_client, game_port)
+            first(first_client, first_token, game_port),
+            second(second_client, second_token, game_port)
         );
     }));
 
     term_and_wait(child);
 }
 
-async fn create_game() -> (Socket, u16) {
-    let mut buffer = [0u8; 1024];
+async fn create_game() -> (Socket, [u8; 8], u16) {
+    let mut buffer = Buffer::new();
 
     let mut client = Socket::bind(None).await.unwrap();
 
     // [64 + 32] -> reliable + Peers::Server
     // [0, 0, 7] -> datagram ID = 7
-    // [1 3] -> ToGame::OpenGame { max_players: 3 }
+    // [1 3 0 0 1 71 1 77 0 0 0] -> ToServer::OpenGame { max_players: 3,
+    //                              slots: GameSlots { teams: 0, ai_players: 0 },
+    //                              name: "G", map: "M", auth: None, password: None,
+    //                              capabilities: 0 }
     client
-        .send(SERVER_ADDR, &[64 + 32, 0, 0, 7, 1, 3])
+        .send(
+            SERVER_ADDR,
+            &[64 + 32, 0, 0, 7, 1, 3, 0, 0, 1, 71, 1, 77, 0, 0, 0],
+        )
         .await
         .unwrap();
 
@@ -268,7 +391,10 @@ async fn create_game() -> (Socket, u16) {
         // Confirm
         let id = id.to_be_bytes();
         client
-            .send(SERVER_ADDR, &[128, 0, 0, 0, id[1], id[2], id[3]])
+            .send(
+                SERVER_ADDR,
+                &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0],
+            )
             .await
             .unwrap();
 
@@ -294,38 +420,45 @@ async fn create_game() -> (Socket, u16) {
     let mut received = ReceivedBuffer::new();
     received.load(&mut client, &mut buffer).await;
 
-    // [2, 1] -> FromGame::Joined(1)
-    let id = received.find_id(true, &[2, 1]).unwrap().to_be_bytes();
+    // [2, 1, ...] -> FromGame::Joined { id: 1, token }
+    let (id, token) = received.find_id_prefix(true, &[2, 1]).unwrap();
+    let token = decode_token(token);
+    let id = id.to_be_bytes();
     client
-        .send(server, &[128, 0, 0, 0, id[1], id[2], id[3]])
+        .send(server, &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0])
         .await
         .unwrap();
 
-    (client, port)
+    (client, token, port)
 }
 
-async fn join_game(game_port: u16) -> Socket {
-    let mut buffer = [0u8; 1024];
+async fn join_game(game_port: u16) -> (Socket, [u8; 8]) {
+    let mut buffer = Buffer::new();
 
     let server = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, game_port));
     let mut client = Socket::bind(None).await.unwrap();
 
     // [64 + 32] -> reliable + Peers::Server
     // [0, 0, 3] -> datagram ID = 3
-    // [1] -> ToGame::Join
-    client.send(server, &[64 + 32, 0, 0, 3, 1]).await.unwrap();
+    // [1, 1, 0, 0, 0] -> ToGame::Join { version: 1, capabilities: 0, auth: None, password: None }
+    client
+        .send(server, &[64 + 32, 0, 0, 3, 1, 1, 0, 0, 0])
+        .await
+        .unwrap();
 
     let mut received = ReceivedBuffer::new();
     received.load(&mut client, &mut buffer).await;
     received.load(&mut client, &mut buffer).await;
     received.assert_confirmed(3);
 
-    // [2, 2] -> FromGame::Joined(2)
-    let id = received.find_id(true, &[2, 2]).unwrap().to_be_bytes();
+    // [2, 2, ...] -> FromGame::Joined { id: 2, token }
+    let (id, token) = received.find_id_prefix(true, &[2, 2]).unwrap();
+    let token = decode_token(token);
+    let id = id.to_be_bytes();
     client
-        .send(server, &[128, 0, 0, 0, id[1], id[2], id[3]])
+        .send(server, &[128, 0, 0, 0, id[1], id[2], id[3], 0, 0, 0, 0])
         .await
         .unwrap();
 
-    client
+    (client, token)
 }
@@ -0,0 +1,230 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use de_audio::{PlaySoundEvent, Sound};
+use de_core::{
+    baseset::GameSet,
+    cleanup::DespawnOnGameExit,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    objects::{ActiveObjectType, BuildingType, ObjectType, PLAYER_MAX_BUILDINGS},
+    player::Player,
+    projection::ToAltitude,
+};
+use de_spawner::{ObjectCounter, PlacementValidator, SpawnBundle};
+
+use crate::commands::{ConstructionCommand, ExecuteConstructionEvent, ScheduledConstruction};
+
+/// Time it takes to construct a building.
+pub const CONSTRUCTION_TIME: Duration = Duration::from_secs(10);
+
+pub(crate) struct BuildingPlugin;
+
+impl Plugin for BuildingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ConstructBuildingEvent>()
+            .add_system(
+                forward
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(resource_exists::<ScheduledConstruction>()))
+                    .before(BuildingSet::Execute),
+            )
+            .add_system(
+                execute
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(BuildingSet::Execute),
+            )
+            .add_system(
+                construct
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+enum BuildingSet {
+    Execute,
+}
+
+/// Send this event to start construction of a new building. The event is
+/// ignored if the building cannot be placed at the given location.
+pub struct ConstructBuildingEvent {
+    player: Player,
+    building: BuildingType,
+    position: Vec2,
+    heading: f32,
+}
+
+impl ConstructBuildingEvent {
+    /// # Arguments
+    ///
+    /// * `player` - owner of the new building.
+    ///
+    /// * `building` - type of the new building.
+    ///
+    /// * `position` - flat position of the new building.
+    ///
+    /// * `heading` - counter clockwise rotation in radians of the building
+    ///   around the y axis.
+    pub fn new(player: Player, building: BuildingType, position: Vec2, heading: f32) -> Self {
+        Self {
+            player,
+            building,
+            position,
+            heading,
+        }
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn building(&self) -> BuildingType {
+        self.building
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn heading(&self) -> f32 {
+        self.heading
+    }
+}
+
+/// This component is attached to buildings which are being constructed.
+/// Buildings are not functional (e.g. they do not manufacture units) until
+/// they are fully constructed.
+#[derive(Component, Default)]
+pub struct UnderConstruction {
+    progress: f32,
+}
+
+impl UnderConstruction {
+    /// Creates a construction with already made progress, e.g. when a saved
+    /// game is continued.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `progress` is not between 0 (inclusive) and 1 (exclusive).
+    pub fn restored(progress: f32) -> Self {
+        assert!((0. ..1.).contains(&progress));
+        Self { progress }
+    }
+
+    /// Construction progress between 0 and 1.
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
+    /// Advances the construction by `delta` and returns true if the
+    /// construction has just finished.
+    fn advance(&mut self, delta: Duration) -> bool {
+        self.progress += delta.as_secs_f32() / CONSTRUCTION_TIME.as_secs_f32();
+        self.progress >= 1.
+    }
+}
+
+fn forward(
+    mut events: EventReader<ConstructBuildingEvent>,
+    mut out_events: EventWriter<ExecuteConstructionEvent>,
+) {
+    for event in events.iter() {
+        out_events.send(ExecuteConstructionEvent::new(
+            ConstructionCommand::Construct {
+                player: event.player(),
+                building: event.building(),
+                position: event.position(),
+                heading: event.heading(),
+            },
+        ));
+    }
+}
+
+fn execute(
+    mut commands: Commands,
+    conf: Res<GameConfig>,
+    counter: Res<ObjectCounter>,
+    validator: PlacementValidator,
+    mut events: EventReader<ExecuteConstructionEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
+) {
+    for event in events.iter() {
+        let ConstructionCommand::Construct {
+            player,
+            building,
+            position,
+            heading,
+        } = event.command()
+        else {
+            continue;
+        };
+
+        if !conf.players().contains(player) {
+            warn!("Ignoring construction of a building of {player}, who is not in the game.");
+            continue;
+        }
+        if counter.player(player).unwrap().building_count() >= PLAYER_MAX_BUILDINGS {
+            info!("{player} reached the maximum number of buildings.");
+            continue;
+        }
+
+        let object_type = ObjectType::Active(ActiveObjectType::Building(building));
+        let transform = Transform {
+            translation: position.to_msl(),
+            rotation: Quat::from_rotation_y(heading),
+            ..default()
+        };
+        if !validator.is_allowed(object_type, &transform) {
+            info!("{building} of {player} cannot be placed at {position:?}.");
+            continue;
+        }
+
+        info!("Starting construction of {building} of {player} at {position:?}.");
+        sounds.send(PlaySoundEvent::new(
+            Sound::Construction,
+            transform.translation,
+        ));
+        commands.spawn((
+            SpawnBundle::new(object_type, transform),
+            player,
+            UnderConstruction::default(),
+            DespawnOnGameExit,
+        ));
+    }
+}
+
+fn construct(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut buildings: Query<(Entity, &mut UnderConstruction)>,
+) {
+    for (entity, mut construction) in buildings.iter_mut() {
+        if construction.advance(time.delta()) {
+            info!("Construction of {entity:?} finished.");
+            commands.entity(entity).remove::<UnderConstruction>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_under_construction() {
+        let mut construction = UnderConstruction::default();
+        assert_eq!(construction.progress(), 0.);
+        assert!(!construction.advance(Duration::from_secs(5)));
+        assert_eq!(construction.progress(), 0.5);
+        assert!(!construction.advance(Duration::from_secs(4)));
+        assert!(construction.advance(Duration::from_secs(1)));
+
+        let mut construction = UnderConstruction::restored(0.5);
+        assert_eq!(construction.progress(), 0.5);
+        assert!(construction.advance(Duration::from_secs(5)));
+    }
+}
@@ -0,0 +1,252 @@
+use bevy::prelude::*;
+use de_core::{
+    objects::{BuildingType, UnitType},
+    player::Player,
+};
+use enum_map::Enum;
+
+use crate::DeliveryTarget;
+
+const ENQUEUE_TAG: u8 = 1;
+const CANCEL_TAG: u8 = 2;
+const CONSTRUCT_TAG: u8 = 3;
+const DELIVER_TAG: u8 = 4;
+const LOCATION_TARGET_TAG: u8 = 0;
+const ENTITY_TARGET_TAG: u8 = 1;
+
+pub(crate) struct CommandsPlugin;
+
+impl Plugin for CommandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExecuteConstructionEvent>();
+    }
+}
+
+/// While this resource exists, [`crate::EnqueueAssemblyEvent`],
+/// [`crate::CancelProductionEvent`], [`crate::ChangeDeliveryLocationEvent`]
+/// and [`crate::ConstructBuildingEvent`] are not executed right away. Whoever inserted the resource is responsible
+/// for scheduling of the corresponding [`ConstructionCommand`]s and for their
+/// execution via [`ExecuteConstructionEvent`].
+///
+/// This is used to execute the commands in a particular simulation tick of a
+/// multiplayer game.
+#[derive(Resource)]
+pub struct ScheduledConstruction;
+
+/// A construction or manufacturing command to be executed, see
+/// [`crate::ScheduledConstruction`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConstructionCommand {
+    /// Enqueue a unit to be manufactured by a factory.
+    Enqueue { factory: Entity, unit: UnitType },
+    /// Cancel manufacturing of the last enqueued unit of a factory.
+    Cancel { factory: Entity },
+    /// Start construction of a new building of a player.
+    Construct {
+        player: Player,
+        building: BuildingType,
+        position: Vec2,
+        /// Counter clockwise rotation in radians around the y axis.
+        heading: f32,
+    },
+    /// Change target (rally point) of freshly manufactured units of a
+    /// factory.
+    Deliver {
+        factory: Entity,
+        target: DeliveryTarget,
+    },
+}
+
+impl ConstructionCommand {
+    /// Maximum length of an encoded command in bytes.
+    pub const MAX_ENCODED_LEN: usize = 18;
+
+    /// Appends self-delimiting binary representation of the command to
+    /// `buf`.
+    ///
+    /// Factories and delivery targets are encoded as local entities, thus the encoded commands
+    /// are meaningful only on the computer which encoded them.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        match *self {
+            Self::Enqueue { factory, unit } => {
+                buf.push(ENQUEUE_TAG);
+                buf.extend_from_slice(&factory.to_bits().to_le_bytes());
+                // There is only a handful of unit types.
+                buf.push(unit.into_usize() as u8);
+            }
+            Self::Cancel { factory } => {
+                buf.push(CANCEL_TAG);
+                buf.extend_from_slice(&factory.to_bits().to_le_bytes());
+            }
+            Self::Construct {
+                player,
+                building,
+                position,
+                heading,
+            } => {
+                buf.push(CONSTRUCT_TAG);
+                buf.push(player.to_num());
+                buf.push(building.into_usize() as u8);
+                buf.extend_from_slice(&position.x.to_le_bytes());
+                buf.extend_from_slice(&position.y.to_le_bytes());
+                buf.extend_from_slice(&heading.to_le_bytes());
+            }
+            Self::Deliver { factory, target } => {
+                buf.push(DELIVER_TAG);
+                buf.extend_from_slice(&factory.to_bits().to_le_bytes());
+                match target {
+                    DeliveryTarget::Location(position) => {
+                        buf.push(LOCATION_TARGET_TAG);
+                        buf.extend_from_slice(&position.x.to_le_bytes());
+                        buf.extend_from_slice(&position.y.to_le_bytes());
+                    }
+                    DeliveryTarget::Entity(entity) => {
+                        buf.push(ENTITY_TARGET_TAG);
+                        buf.extend_from_slice(&entity.to_bits().to_le_bytes());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Decodes a command from the beginning of `bytes`. It returns the
+    /// command and the number of consumed bytes or None if the bytes do not
+    /// start with a valid command.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        let (&tag, rest) = bytes.split_first()?;
+
+        match tag {
+            ENQUEUE_TAG => {
+                let factory = decode_entity(rest)?;
+                let unit = usize::from(*rest.get(8)?);
+                (unit < UnitType::LENGTH).then(|| {
+                    (
+                        Self::Enqueue {
+                            factory,
+                            unit: UnitType::from_usize(unit),
+                        },
+                        10,
+                    )
+                })
+            }
+            CANCEL_TAG => Some((
+                Self::Cancel {
+                    factory: decode_entity(rest)?,
+                },
+                9,
+            )),
+            CONSTRUCT_TAG => {
+                let player = Player::try_from(*rest.first()?).ok()?;
+                let building = usize::from(*rest.get(1)?);
+                if building >= BuildingType::LENGTH {
+                    return None;
+                }
+                let x = decode_f32(rest.get(2..6)?);
+                let y = decode_f32(rest.get(6..10)?);
+                let heading = decode_f32(rest.get(10..14)?);
+
+                Some((
+                    Self::Construct {
+                        player,
+                        building: BuildingType::from_usize(building),
+                        position: Vec2::new(x, y),
+                        heading,
+                    },
+                    15,
+                ))
+            }
+            DELIVER_TAG => {
+                let factory = decode_entity(rest)?;
+                let target = match *rest.get(8)? {
+                    LOCATION_TARGET_TAG => {
+                        let x = decode_f32(rest.get(9..13)?);
+                        let y = decode_f32(rest.get(13..17)?);
+                        DeliveryTarget::Location(Vec2::new(x, y))
+                    }
+                    ENTITY_TARGET_TAG => DeliveryTarget::Entity(decode_entity(rest.get(9..)?)?),
+                    _ => return None,
+                };
+                Some((Self::Deliver { factory, target }, 18))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn decode_entity(bytes: &[u8]) -> Option<Entity> {
+    Some(Entity::from_bits(u64::from_le_bytes(
+        bytes.get(..8)?.try_into().unwrap(),
+    )))
+}
+
+fn decode_f32(bytes: &[u8]) -> f32 {
+    f32::from_le_bytes(bytes.try_into().unwrap())
+}
+
+/// Send this event to execute a [`ConstructionCommand`]. The command is
+/// validated (e.g. against energy available to a factory or against
+/// placement of a building) before it is executed.
+pub struct ExecuteConstructionEvent(ConstructionCommand);
+
+impl ExecuteConstructionEvent {
+    pub fn new(command: ConstructionCommand) -> Self {
+        Self(command)
+    }
+
+    pub(crate) fn command(&self) -> ConstructionCommand {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        let commands = [
+            ConstructionCommand::Enqueue {
+                factory: Entity::from_raw(7),
+                unit: UnitType::Attacker,
+            },
+            ConstructionCommand::Cancel {
+                factory: Entity::from_raw(1234),
+            },
+            ConstructionCommand::Construct {
+                player: Player::Player3,
+                building: BuildingType::PowerHub,
+                position: Vec2::new(-12.5, 300.),
+                heading: 1.5,
+            },
+            ConstructionCommand::Deliver {
+                factory: Entity::from_raw(12),
+                target: DeliveryTarget::Location(Vec2::new(3.25, -8.)),
+            },
+            ConstructionCommand::Deliver {
+                factory: Entity::from_raw(12),
+                target: DeliveryTarget::Entity(Entity::from_raw(99)),
+            },
+        ];
+
+        let mut buf = Vec::new();
+        for command in commands {
+            let len = buf.len();
+            command.encode(&mut buf);
+            assert!(buf.len() - len <= ConstructionCommand::MAX_ENCODED_LEN);
+        }
+
+        let mut bytes = buf.as_slice();
+        for command in commands {
+            let (decoded, len) = ConstructionCommand::decode(bytes).unwrap();
+            assert_eq!(decoded, command);
+            bytes = &bytes[len..];
+        }
+        assert!(bytes.is_empty());
+
+        assert!(ConstructionCommand::decode(&buf[..5]).is_none());
+        assert!(ConstructionCommand::decode(&buf[19..30]).is_none());
+        assert!(ConstructionCommand::decode(&[5, 0, 0, 0, 0, 0, 0, 0, 0]).is_none());
+        assert!(ConstructionCommand::decode(&[4, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0]).is_none());
+        assert!(ConstructionCommand::decode(&[]).is_none());
+    }
+}
@@ -1,13 +1,25 @@
 use bevy::{app::PluginGroupBuilder, prelude::*};
+use building::BuildingPlugin;
+pub use building::{ConstructBuildingEvent, UnderConstruction, CONSTRUCTION_TIME};
+use commands::CommandsPlugin;
+pub use commands::{ConstructionCommand, ExecuteConstructionEvent, ScheduledConstruction};
 use manufacturing::ManufacturingPlugin;
-pub use manufacturing::{AssemblyLine, ChangeDeliveryLocationEvent, EnqueueAssemblyEvent};
+pub use manufacturing::{
+    AssemblyLine, CancelProductionEvent, ChangeDeliveryLocationEvent, DeliveryTarget,
+    EnqueueAssemblyEvent, MANUFACTURING_TIME, UNIT_ENERGY_COST,
+};
 
+mod building;
+mod commands;
 mod manufacturing;
 
 pub struct ConstructionPluginGroup;
 
 impl PluginGroup for ConstructionPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(ManufacturingPlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(CommandsPlugin)
+            .add(ManufacturingPlugin)
+            .add(BuildingPlugin)
     }
 }
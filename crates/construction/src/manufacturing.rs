@@ -2,16 +2,19 @@ use std::{collections::VecDeque, time::Duration};
 
 use ahash::AHashMap;
 use bevy::prelude::*;
+use de_combat::GuardEvent;
 use de_core::{
     baseset::GameSet,
     cleanup::DespawnOnGameExit,
     gamestate::GameState,
     gconfig::GameConfig,
-    objects::{Active, ActiveObjectType, ObjectType, UnitType, PLAYER_MAX_UNITS},
+    objects::{Active, ActiveObjectType, MovableSolid, ObjectType, UnitType, PLAYER_MAX_UNITS},
     player::Player,
     projection::{ToAltitude, ToFlat},
     state::AppState,
+    summary::{MatchStat, MatchStatEvent},
 };
+use de_energy::Battery;
 use de_index::SpatialQuery;
 use de_objects::SolidObjects;
 use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPath};
@@ -22,7 +25,17 @@ use de_spawner::{ObjectCounter, SpawnBundle};
 use parry2d::bounding_volume::Aabb;
 use parry3d::math::Isometry;
 
-const MANUFACTURING_TIME: Duration = Duration::from_secs(2);
+use crate::{
+    building::UnderConstruction,
+    commands::{ConstructionCommand, ExecuteConstructionEvent, ScheduledConstruction},
+};
+
+/// Time it takes to manufacture a unit.
+pub const MANUFACTURING_TIME: Duration = Duration::from_secs(2);
+/// Energy taken from the battery of a factory for each enqueued unit.
+pub const UNIT_ENERGY_COST: f64 = 5_000_000.;
+/// Maximum number of units in an assembly line.
+const MAX_QUEUE_LEN: usize = 20;
 const DEFAULT_TARGET_DISTANCE: f32 = 20.;
 
 pub(crate) struct ManufacturingPlugin;
@@ -30,6 +43,7 @@ pub(crate) struct ManufacturingPlugin;
 impl Plugin for ManufacturingPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<EnqueueAssemblyEvent>()
+            .add_event::<CancelProductionEvent>()
             .add_event::<ChangeDeliveryLocationEvent>()
             .add_event::<DeliverEvent>()
             .add_system(
@@ -37,17 +51,31 @@ impl Plugin for ManufacturingPlugin {
                     .in_base_set(GameSet::PostUpdate)
                     .run_if(in_state(AppState::InGame)),
             )
+            .add_system(
+                forward
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(resource_exists::<ScheduledConstruction>()))
+                    .before(ManufacturingSet::Execute)
+                    .before(ManufacturingSet::ChangeLocations),
+            )
             .add_system(
                 change_locations
-                    .in_base_set(GameSet::PreUpdate)
+                    .in_base_set(GameSet::Update)
                     .run_if(in_state(GameState::Playing))
                     .in_set(ManufacturingSet::ChangeLocations),
             )
             .add_system(
-                enqueue
-                    .in_base_set(GameSet::Update)
+                follow_targets
+                    .in_base_set(GameSet::PostUpdate)
                     .run_if(in_state(GameState::Playing)),
             )
+            .add_system(
+                execute
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(ManufacturingSet::Execute),
+            )
             .add_system(
                 check_spawn_locations
                     .in_base_set(GameSet::PreUpdate)
@@ -64,8 +92,12 @@ impl Plugin for ManufacturingPlugin {
                 deliver
                     .in_base_set(GameSet::PreUpdate)
                     .run_if(in_state(GameState::Playing))
-                    .after(ManufacturingSet::ChangeLocations)
                     .after(ManufacturingSet::Produce),
+            )
+            .add_system(
+                guard_delivered
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -73,29 +105,42 @@ impl Plugin for ManufacturingPlugin {
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
 enum ManufacturingSet {
     ChangeLocations,
+    Execute,
     Produce,
 }
 
-/// Send this event to change target location of freshly manufactured units.
+/// Send this event to change target (rally point) of freshly manufactured
+/// units.
 pub struct ChangeDeliveryLocationEvent {
     factory: Entity,
-    position: Vec2,
+    target: DeliveryTarget,
 }
 
 impl ChangeDeliveryLocationEvent {
-    pub fn new(factory: Entity, position: Vec2) -> Self {
-        Self { factory, position }
+    pub fn new(factory: Entity, target: DeliveryTarget) -> Self {
+        Self { factory, target }
     }
 
-    fn factory(&self) -> Entity {
+    pub fn factory(&self) -> Entity {
         self.factory
     }
 
-    fn position(&self) -> Vec2 {
-        self.position
+    pub fn target(&self) -> DeliveryTarget {
+        self.target
     }
 }
 
+/// Target of freshly manufactured units of a factory.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeliveryTarget {
+    /// The units move to a location on the map.
+    Location(Vec2),
+    /// The units guard an allied object or move to any other object (e.g.
+    /// a resource). The target falls back to the last known location of the
+    /// object once the object is despawned.
+    Entity(Entity),
+}
+
 /// Send this event to enqueue a unit to be manufactured by a factory.
 pub struct EnqueueAssemblyEvent {
     factory: Entity,
@@ -112,15 +157,31 @@ impl EnqueueAssemblyEvent {
         Self { factory, unit }
     }
 
-    fn factory(&self) -> Entity {
+    pub fn factory(&self) -> Entity {
         self.factory
     }
 
-    fn unit(&self) -> UnitType {
+    pub fn unit(&self) -> UnitType {
         self.unit
     }
 }
 
+/// Send this event to cancel manufacturing of the last unit enqueued in a
+/// factory. Energy spent on the unit is returned to the factory.
+pub struct CancelProductionEvent {
+    factory: Entity,
+}
+
+impl CancelProductionEvent {
+    pub fn new(factory: Entity) -> Self {
+        Self { factory }
+    }
+
+    pub fn factory(&self) -> Entity {
+        self.factory
+    }
+}
+
 struct DeliverEvent {
     factory: Entity,
     unit: UnitType,
@@ -141,7 +202,11 @@ impl DeliverEvent {
 }
 
 #[derive(Component)]
-struct DeliveryLocation(Vec2);
+struct DeliveryLocation {
+    target: DeliveryTarget,
+    /// Last known position of the target.
+    position: Vec2,
+}
 
 impl DeliveryLocation {
     fn initial(local_aabb: Aabb, transform: &Transform) -> Self {
@@ -149,10 +214,19 @@ impl DeliveryLocation {
             local_aabb.maxs.x + DEFAULT_TARGET_DISTANCE,
             0.5 * (local_aabb.mins.y + local_aabb.maxs.y),
         );
-        Self(transform.transform_point(target.to_msl()).to_flat())
+        let position = transform.transform_point(target.to_msl()).to_flat();
+        Self {
+            target: DeliveryTarget::Location(position),
+            position,
+        }
     }
 }
 
+/// Freshly manufactured units with this component start guarding the entity
+/// once they are fully spawned.
+#[derive(Component)]
+struct DeliveryGuard(Entity);
+
 /// An assembly line attached to every building and capable of production of
 /// any units.
 #[derive(Component, Default)]
@@ -162,10 +236,51 @@ pub struct AssemblyLine {
 }
 
 impl AssemblyLine {
+    /// Creates an assembly line with already enqueued units, e.g. when a
+    /// saved game is continued. Manufacturing of the first unit starts from
+    /// scratch.
+    ///
+    /// # Arguments
+    ///
+    /// * `units` - units in the order of their delivery.
+    ///
+    /// * `time` - elapsed time since a fixed point in time in the past.
+    pub fn restored(units: impl IntoIterator<Item = UnitType>, time: Duration) -> Self {
+        let mut line = Self::default();
+        for unit in units {
+            line.enqueue(unit, time);
+        }
+        line
+    }
+
     fn blocks_mut(&mut self) -> &mut Blocks {
         &mut self.blocks
     }
 
+    /// Returns the number of units in the assembly line, including the one
+    /// being currently manufactured.
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns all units in the assembly line in the order of their
+    /// delivery.
+    pub fn queue(&self) -> impl Iterator<Item = UnitType> + '_ {
+        self.queue.iter().map(ProductionItem::unit)
+    }
+
+    /// Returns manufacturing progress (between 0 and 1) of the unit
+    /// currently being manufactured.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - elapsed time since a fixed point in time in the past.
+    pub fn progress(&self, time: Duration) -> Option<f32> {
+        self.queue.front().map(|item| {
+            (item.progress(time).as_secs_f32() / MANUFACTURING_TIME.as_secs_f32()).min(1.)
+        })
+    }
+
     /// Returns the first item in the assembly line (i.e. the first one to be
     /// delivered).
     fn current(&self) -> Option<UnitType> {
@@ -181,6 +296,11 @@ impl AssemblyLine {
         self.queue.push_back(item);
     }
 
+    /// Removes the last unit from the manufacturing queue and returns it.
+    fn cancel(&mut self) -> Option<UnitType> {
+        self.queue.pop_back().map(|item| item.unit())
+    }
+
     /// Update the production line.
     ///
     /// This method should be called repeatedly and during every tick until it
@@ -309,61 +429,168 @@ impl ProductionItem {
 fn configure(
     mut commands: Commands,
     solids: SolidObjects,
-    new: Query<(Entity, &Transform, &ObjectType), Added<Active>>,
+    new: Query<(Entity, &Transform, &ObjectType, Option<&AssemblyLine>), Added<Active>>,
     mut pole_events: EventWriter<UpdatePoleLocationEvent>,
     mut line_events: EventWriter<UpdateLineLocationEvent>,
 ) {
-    for (entity, transform, &object_type) in new.iter() {
+    for (entity, transform, &object_type, line) in new.iter() {
         let solid = solids.get(object_type);
         if let Some(factory) = solid.factory() {
             let start = transform.transform_point(factory.position().to_msl());
             let local_aabb = solid.ichnography().local_aabb();
             let delivery_location = DeliveryLocation::initial(local_aabb, transform);
-            pole_events.send(UpdatePoleLocationEvent::new(entity, delivery_location.0));
-            let end = delivery_location.0.to_msl();
+            pole_events.send(UpdatePoleLocationEvent::new(
+                entity,
+                delivery_location.position,
+            ));
+            let end = delivery_location.position.to_msl();
             line_events.send(UpdateLineLocationEvent::new(
                 entity,
                 LineLocation::new(start, end),
             ));
-            commands
-                .entity(entity)
-                .insert((AssemblyLine::default(), delivery_location));
+
+            let mut entity_commands = commands.entity(entity);
+            entity_commands.insert(delivery_location);
+            // Factories of a continued saved game keep their production.
+            if line.is_none() {
+                entity_commands.insert(AssemblyLine::default());
+            }
         }
     }
 }
 
 fn change_locations(
-    mut events: EventReader<ChangeDeliveryLocationEvent>,
+    mut events: EventReader<ExecuteConstructionEvent>,
     mut locations: Query<&mut DeliveryLocation>,
+    targets: Query<&Transform>,
     mut pole_events: EventWriter<UpdatePoleLocationEvent>,
     mut line_events: EventWriter<UpdateLineEndEvent>,
 ) {
     for event in events.iter() {
-        if let Ok(mut location) = locations.get_mut(event.factory()) {
-            let owner = event.factory();
-            location.0 = event.position();
-            pole_events.send(UpdatePoleLocationEvent::new(owner, event.position()));
-            let end = event.position().to_msl();
-            line_events.send(UpdateLineEndEvent::new(owner, end));
+        let ConstructionCommand::Deliver { factory, target } = event.command() else {
+            continue;
+        };
+        let Ok(mut location) = locations.get_mut(factory) else {
+            continue;
+        };
+        let position = match target {
+            DeliveryTarget::Location(position) => position,
+            DeliveryTarget::Entity(entity) => match targets.get(entity) {
+                Ok(transform) => transform.translation.to_flat(),
+                Err(_) => continue,
+            },
+        };
+
+        location.target = target;
+        location.position = position;
+        pole_events.send(UpdatePoleLocationEvent::new(factory, position));
+        line_events.send(UpdateLineEndEvent::new(factory, position.to_msl()));
+    }
+}
+
+/// Moves delivery locations together with their target objects.
+fn follow_targets(
+    mut locations: Query<(Entity, &mut DeliveryLocation)>,
+    targets: Query<&Transform>,
+    mut pole_events: EventWriter<UpdatePoleLocationEvent>,
+    mut line_events: EventWriter<UpdateLineEndEvent>,
+) {
+    for (factory, mut location) in locations.iter_mut() {
+        let DeliveryTarget::Entity(entity) = location.target else {
+            continue;
+        };
+
+        let Ok(transform) = targets.get(entity) else {
+            location.target = DeliveryTarget::Location(location.position);
+            continue;
+        };
+        let position = transform.translation.to_flat();
+        if position != location.position {
+            location.position = position;
+            pole_events.send(UpdatePoleLocationEvent::new(factory, position));
+            line_events.send(UpdateLineEndEvent::new(factory, position.to_msl()));
         }
     }
 }
 
-fn enqueue(
+type FactoryComponents<'a> = (&'a ObjectType, &'a mut AssemblyLine, &'a mut Battery);
+
+fn forward(
+    mut enqueue_events: EventReader<EnqueueAssemblyEvent>,
+    mut cancel_events: EventReader<CancelProductionEvent>,
+    mut location_events: EventReader<ChangeDeliveryLocationEvent>,
+    mut out_events: EventWriter<ExecuteConstructionEvent>,
+) {
+    for event in enqueue_events.iter() {
+        out_events.send(ExecuteConstructionEvent::new(
+            ConstructionCommand::Enqueue {
+                factory: event.factory(),
+                unit: event.unit(),
+            },
+        ));
+    }
+    for event in cancel_events.iter() {
+        out_events.send(ExecuteConstructionEvent::new(ConstructionCommand::Cancel {
+            factory: event.factory(),
+        }));
+    }
+    for event in location_events.iter() {
+        out_events.send(ExecuteConstructionEvent::new(
+            ConstructionCommand::Deliver {
+                factory: event.factory(),
+                target: event.target(),
+            },
+        ));
+    }
+}
+
+fn execute(
     time: Res<Time>,
-    mut events: EventReader<EnqueueAssemblyEvent>,
-    mut lines: Query<&mut AssemblyLine>,
+    solids: SolidObjects,
+    mut events: EventReader<ExecuteConstructionEvent>,
+    mut factories: Query<FactoryComponents, Without<UnderConstruction>>,
 ) {
     for event in events.iter() {
-        let Ok(mut line) = lines.get_mut(event.factory()) else {
-            continue;
-        };
-        info!(
-            "Enqueueing manufacturing of {} in {:?}.",
-            event.unit(),
-            event.factory()
-        );
-        line.enqueue(event.unit(), time.elapsed());
+        match event.command() {
+            ConstructionCommand::Enqueue { factory, unit } => {
+                let Ok((&object_type, mut line, mut battery)) = factories.get_mut(factory) else {
+                    continue;
+                };
+
+                if !solids
+                    .get(object_type)
+                    .factory()
+                    .unwrap()
+                    .products()
+                    .contains(&unit)
+                {
+                    warn!("{factory:?} cannot manufacture {unit}.");
+                    continue;
+                }
+                if line.queue_len() >= MAX_QUEUE_LEN {
+                    info!("Assembly line of {factory:?} is full.");
+                    continue;
+                }
+                if !battery.try_consume(UNIT_ENERGY_COST) {
+                    info!("Not enough energy to manufacture {unit} in {factory:?}.");
+                    continue;
+                }
+
+                info!("Enqueueing manufacturing of {unit} in {factory:?}.");
+                line.enqueue(unit, time.elapsed());
+            }
+            ConstructionCommand::Cancel { factory } => {
+                let Ok((_, mut line, mut battery)) = factories.get_mut(factory) else {
+                    continue;
+                };
+
+                if let Some(unit) = line.cancel() {
+                    info!("Canceling manufacturing of {unit} in {factory:?}.");
+                    battery.charge(UNIT_ENERGY_COST);
+                }
+            }
+            ConstructionCommand::Construct { .. } | ConstructionCommand::Deliver { .. } => (),
+        }
     }
 }
 
@@ -427,6 +654,7 @@ fn deliver(
     solids: SolidObjects,
     mut deliver_events: EventReader<DeliverEvent>,
     mut path_events: EventWriter<UpdateEntityPath>,
+    mut stat_events: EventWriter<MatchStatEvent>,
     factories: Query<(&Transform, &ObjectType, &Player, &DeliveryLocation)>,
 ) {
     for delivery in deliver_events.iter() {
@@ -451,14 +679,32 @@ fn deliver(
                 DespawnOnGameExit,
             ))
             .id();
+        if let DeliveryTarget::Entity(target) = delivery_location.target {
+            commands.entity(unit).insert(DeliveryGuard(target));
+        }
         path_events.send(UpdateEntityPath::new(
             unit,
             PathTarget::new(
-                delivery_location.0,
+                delivery_location.position,
                 PathQueryProps::new(0., f32::INFINITY),
                 false,
             ),
         ));
+        stat_events.send(MatchStatEvent::new(player, MatchStat::UnitBuilt));
+    }
+}
+
+/// Makes freshly manufactured units guard the delivery target. Units are
+/// given a path to the target during delivery, thus they simply arrive there
+/// if the target is not an allied object (e.g. a resource).
+fn guard_delivered(
+    mut commands: Commands,
+    units: Query<(Entity, &DeliveryGuard), With<MovableSolid>>,
+    mut guard_events: EventWriter<GuardEvent>,
+) {
+    for (unit, guard) in units.iter() {
+        commands.entity(unit).remove::<DeliveryGuard>();
+        guard_events.send(GuardEvent::new(unit, guard.0));
     }
 }
 
@@ -503,4 +749,41 @@ mod tests {
         );
         assert!(line.produce(Duration::from_secs(90)).is_none());
     }
+
+    #[test]
+    fn test_cancel() {
+        let mut line = AssemblyLine::default();
+        assert!(line.cancel().is_none());
+        assert!(line.progress(Duration::from_secs(10)).is_none());
+
+        line.enqueue(UnitType::Attacker, Duration::from_secs(10));
+        line.enqueue(UnitType::Attacker, Duration::from_secs(10));
+        assert_eq!(line.progress(Duration::from_secs(11)), Some(0.5));
+
+        assert_eq!(line.cancel(), Some(UnitType::Attacker));
+        assert_eq!(line.queue_len(), 1);
+        assert_eq!(
+            line.produce(Duration::from_secs(12)).unwrap(),
+            UnitType::Attacker
+        );
+        assert!(line.produce(Duration::from_secs(20)).is_none());
+
+        line.enqueue(UnitType::Attacker, Duration::from_secs(30));
+        assert_eq!(line.cancel(), Some(UnitType::Attacker));
+        assert!(line.produce(Duration::from_secs(40)).is_none());
+    }
+
+    #[test]
+    fn test_restored() {
+        let line = AssemblyLine::restored(
+            [UnitType::Attacker, UnitType::Attacker],
+            Duration::from_secs(10),
+        );
+        assert_eq!(line.queue_len(), 2);
+        assert_eq!(
+            line.queue().collect::<Vec<_>>(),
+            vec![UnitType::Attacker, UnitType::Attacker]
+        );
+        assert_eq!(line.progress(Duration::from_secs(11)), Some(0.5));
+    }
 }
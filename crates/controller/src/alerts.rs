@@ -0,0 +1,238 @@
+//! This module turns various game events into alerts for the local player,
+//! see [`AlertEvent`]. Similar alerts raised shortly after each other are
+//! de-duplicated.
+
+use std::time::Duration;
+
+use ahash::AHashSet;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_audio::{PlaySoundEvent, Sound};
+use de_construction::UnderConstruction;
+use de_core::{
+    alert::{AlertEvent, AlertKind, AlertPriority, UnderAttackEvent},
+    baseset::GameSet,
+    diplomacy::Diplomacy,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    objects::Playable,
+    projection::ToFlat,
+};
+use de_energy::Battery;
+use de_multiplayer::PlayerLeftEvent;
+
+/// Alerts of the same kind raised within this distance (in meters) of each
+/// other are considered to be the same alert.
+const DEDUP_RADIUS: f32 = 40.;
+/// Battery charged below this fraction of its capacity triggers an alert.
+const LOW_POWER_FRACTION: f64 = 0.1;
+/// An object stays low on power until its battery is charged above this
+/// fraction of its capacity.
+const RECOVERED_POWER_FRACTION: f64 = 0.2;
+
+pub(crate) struct AlertsPlugin;
+
+impl Plugin for AlertsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(cleanup.in_schedule(OnExit(GameState::Playing)))
+            .add_system(
+                under_attack
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(AlertsSet::Raise),
+            )
+            .add_system(
+                building_complete
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(AlertsSet::Raise),
+            )
+            .add_system(
+                player_left
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(AlertsSet::Raise),
+            )
+            .add_system(
+                low_power
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(AlertsSet::Raise),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum AlertsSet {
+    Raise,
+}
+
+/// Returns the time window during which similar alerts are suppressed.
+fn dedup_window(kind: AlertKind) -> Duration {
+    match kind {
+        AlertKind::UnderAttack => Duration::from_secs(15),
+        AlertKind::LowPower => Duration::from_secs(30),
+        AlertKind::BuildingComplete | AlertKind::PlayerLeft(_) => Duration::ZERO,
+    }
+}
+
+/// Alerts raised within their de-duplication window.
+#[derive(Resource, Default)]
+struct RecentAlerts(Vec<RecentAlert>);
+
+impl RecentAlerts {
+    /// Returns true if the alert is not similar to any recently raised alert.
+    /// The alert is remembered in such a case.
+    fn admit(&mut self, kind: AlertKind, position: Option<Vec2>, now: Duration) -> bool {
+        self.0
+            .retain(|alert| now.saturating_sub(alert.time) < dedup_window(alert.kind));
+
+        let similar = self.0.iter().any(|alert| {
+            alert.kind == kind
+                && match (alert.position, position) {
+                    (Some(first), Some(second)) => first.distance(second) <= DEDUP_RADIUS,
+                    (None, None) => true,
+                    _ => false,
+                }
+        });
+        if similar {
+            return false;
+        }
+
+        self.0.push(RecentAlert {
+            kind,
+            position,
+            time: now,
+        });
+        true
+    }
+}
+
+struct RecentAlert {
+    kind: AlertKind,
+    position: Option<Vec2>,
+    time: Duration,
+}
+
+/// Objects of the local player which are low on power.
+#[derive(Resource, Default)]
+struct LowPower(AHashSet<Entity>);
+
+#[derive(SystemParam)]
+struct Alerts<'w> {
+    time: Res<'w, Time>,
+    recent: ResMut<'w, RecentAlerts>,
+    events: EventWriter<'w, AlertEvent>,
+    sounds: EventWriter<'w, PlaySoundEvent>,
+}
+
+impl<'w> Alerts<'w> {
+    fn raise(&mut self, kind: AlertKind, position: Option<Vec2>) {
+        if !self.recent.admit(kind, position, self.time.elapsed()) {
+            return;
+        }
+
+        info!("Raising an alert: {kind:?}");
+        if kind.priority() >= AlertPriority::Normal {
+            self.sounds
+                .send(PlaySoundEvent::global(Sound::Notification));
+        }
+        self.events.send(AlertEvent::new(kind, position));
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<RecentAlerts>();
+    commands.init_resource::<LowPower>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<RecentAlerts>();
+    commands.remove_resource::<LowPower>();
+}
+
+fn under_attack(
+    config: Res<GameConfig>,
+    diplomacy: Res<Diplomacy>,
+    mut events: EventReader<UnderAttackEvent>,
+    mut alerts: Alerts,
+) {
+    let local = config.locals().playable();
+    for event in events.iter() {
+        if diplomacy.are_allies(local, event.player()) {
+            alerts.raise(AlertKind::UnderAttack, Some(event.position()));
+        }
+    }
+}
+
+fn building_complete(
+    mut removed: RemovedComponents<UnderConstruction>,
+    buildings: Query<&Transform, With<Playable>>,
+    mut alerts: Alerts,
+) {
+    for entity in removed.iter() {
+        // Buildings destroyed before completion no longer exist.
+        if let Ok(transform) = buildings.get(entity) {
+            alerts.raise(
+                AlertKind::BuildingComplete,
+                Some(transform.translation.to_flat()),
+            );
+        }
+    }
+}
+
+fn player_left(mut events: EventReader<PlayerLeftEvent>, mut alerts: Alerts) {
+    for event in events.iter() {
+        alerts.raise(AlertKind::PlayerLeft(event.player()), None);
+    }
+}
+
+fn low_power(
+    mut low: ResMut<LowPower>,
+    objects: Query<(Entity, &Battery, &Transform), With<Playable>>,
+    mut alerts: Alerts,
+) {
+    // Despawned objects are forgotten.
+    low.0.retain(|&entity| objects.contains(entity));
+
+    for (entity, battery, transform) in objects.iter() {
+        let fraction = battery.energy() / battery.capacity();
+        if fraction < LOW_POWER_FRACTION {
+            if low.0.insert(entity) {
+                alerts.raise(AlertKind::LowPower, Some(transform.translation.to_flat()));
+            }
+        } else if fraction > RECOVERED_POWER_FRACTION {
+            low.0.remove(&entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::player::Player;
+
+    use super::*;
+
+    #[test]
+    fn test_recent_alerts() {
+        let mut recent = RecentAlerts::default();
+        let second = Duration::from_secs(1);
+
+        assert!(recent.admit(AlertKind::UnderAttack, Some(Vec2::ZERO), second));
+        assert!(!recent.admit(AlertKind::UnderAttack, Some(Vec2::new(10., 0.)), 2 * second));
+        // Far away from the first attack.
+        assert!(recent.admit(
+            AlertKind::UnderAttack,
+            Some(Vec2::new(100., 0.)),
+            3 * second
+        ));
+        assert!(recent.admit(AlertKind::LowPower, Some(Vec2::ZERO), 3 * second));
+        // The de-duplication window of the first attack has passed.
+        assert!(recent.admit(AlertKind::UnderAttack, Some(Vec2::ZERO), 16 * second));
+
+        let left = AlertKind::PlayerLeft(Player::Player2);
+        assert!(recent.admit(left, None, 20 * second));
+        assert!(recent.admit(left, None, 20 * second));
+        assert!(recent.admit(AlertKind::PlayerLeft(Player::Player3), None, 20 * second));
+    }
+}
@@ -0,0 +1,100 @@
+//! Translation of key presses and mouse clicks to game actions, see
+//! [`de_conf::Action`].
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::{keyboard::KeyboardInput, ButtonState},
+    prelude::*,
+};
+use de_conf::{Action, InputButton, KeyBindings, Modifiers};
+use de_core::{
+    gconfig::GameConfig,
+    summary::{MatchStat, MatchStatEvent},
+};
+
+use crate::mouse::{MouseClicked, MouseDoubleClicked};
+
+/// System parameter for key presses and mouse clicks made during the current
+/// frame.
+#[derive(SystemParam)]
+pub(crate) struct TriggerInput<'w, 's> {
+    bindings: Res<'w, KeyBindings>,
+    keys: Res<'w, Input<KeyCode>>,
+    key_events: EventReader<'w, 's, KeyboardInput>,
+    click_events: EventReader<'w, 's, MouseClicked>,
+}
+
+impl<'w, 's> TriggerInput<'w, 's> {
+    /// Returns true if the action was triggered. All pending events are
+    /// consumed.
+    fn triggered(&mut self, action: Action) -> bool {
+        // It is desirable to exhaust the iterator, thus .filter().count() is
+        // used instead of .any()
+        self.actions()
+            .filter(|&triggered| triggered == action)
+            .count()
+            > 0
+    }
+
+    /// Returns all actions triggered during the current frame. All pending
+    /// events are consumed.
+    fn actions(&mut self) -> impl Iterator<Item = Action> + '_ {
+        let modifiers = Modifiers::pressed(self.keys.as_ref());
+        let key_buttons = self
+            .key_events
+            .iter()
+            .filter(|e| e.state == ButtonState::Pressed)
+            .filter_map(|e| e.key_code.map(InputButton::Key));
+        let mouse_buttons = self
+            .click_events
+            .iter()
+            .map(|e| InputButton::Mouse(e.button()));
+
+        let bindings = self.bindings.as_ref();
+        key_buttons
+            .chain(mouse_buttons)
+            .filter_map(move |button| bindings.resolve(button, modifiers))
+    }
+}
+
+/// Accounts all actions triggered by the user to the playable player, see
+/// [`MatchStat::Action`].
+pub(super) fn count_actions(
+    config: Res<GameConfig>,
+    mut input: TriggerInput,
+    mut stats: EventWriter<MatchStatEvent>,
+) {
+    let player = config.locals().playable();
+    for _ in input.actions() {
+        stats.send(MatchStatEvent::new(player, MatchStat::Action));
+    }
+}
+
+/// Returns a system run condition which is true if the action was triggered
+/// by a key press or a mouse click during the current frame.
+pub(crate) fn on_action(action: Action) -> impl Fn(TriggerInput) -> bool {
+    move |mut input: TriggerInput| input.triggered(action)
+}
+
+/// Returns a system run condition which is true if the action was triggered
+/// by a mouse double click during the current frame.
+pub(crate) fn on_double_click_action(
+    action: Action,
+) -> impl Fn(Res<KeyBindings>, Res<Input<KeyCode>>, EventReader<MouseDoubleClicked>) -> bool {
+    move |bindings: Res<KeyBindings>,
+          keys: Res<Input<KeyCode>>,
+          mut events: EventReader<MouseDoubleClicked>| {
+        let modifiers = Modifiers::pressed(keys.as_ref());
+        // It is desirable to exhaust the iterator, thus .filter().count() is
+        // used instead of .any()
+        events
+            .iter()
+            .filter(|e| bindings.resolve(InputButton::Mouse(e.button()), modifiers) == Some(action))
+            .count()
+            > 0
+    }
+}
+
+pub(crate) fn ctrl_pressed(keys: Res<Input<KeyCode>>) -> bool {
+    Modifiers::pressed(keys.as_ref()).ctrl()
+}
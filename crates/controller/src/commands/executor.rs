@@ -1,12 +1,23 @@
 use bevy::prelude::*;
-use de_behaviour::ChaseTargetEvent;
-use de_combat::AttackEvent;
-use de_construction::{AssemblyLine, ChangeDeliveryLocationEvent};
-use de_core::{baseset::GameSet, gamestate::GameState, objects::MovableSolid};
-use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPath};
+use de_audio::{PlaySoundEvent, Sound};
+use de_behaviour::{
+    ChaseTargetEvent, EnqueueCommandEvent, Formation, FormationSlot, MovementCommand, OrderQueue,
+};
+use de_combat::{
+    AttackEvent, AttackMoveEvent, AttackMoving, GuardEvent, Guarding, SetPriorityTargetEvent,
+    SetStanceEvent, Stance,
+};
+use de_construction::{AssemblyLine, ChangeDeliveryLocationEvent, DeliveryTarget};
+use de_core::{
+    baseset::GameSet,
+    gamestate::GameState,
+    objects::{MovableSolid, Playable},
+    projection::ToFlat,
+};
+use de_transport::{LoadEvent, Passengers, UnloadEvent};
 use glam::Vec2;
 
-use crate::selection::Selected;
+use crate::selection::SelectedQuery;
 
 pub(super) struct ExecutorPlugin;
 
@@ -15,6 +26,11 @@ impl Plugin for ExecutorPlugin {
         app.add_event::<SendSelectedEvent>()
             .add_event::<DeliveryLocationSelectedEvent>()
             .add_event::<GroupAttackEvent>()
+            .add_event::<SetSelectedStanceEvent>()
+            .add_event::<FocusSelectedEvent>()
+            .add_event::<GuardSelectedEvent>()
+            .add_event::<LoadSelectedEvent>()
+            .add_event::<UnloadSelectedEvent>()
             .add_system(
                 send_selected_system
                     .in_base_set(GameSet::Input)
@@ -32,6 +48,36 @@ impl Plugin for ExecutorPlugin {
                     .in_base_set(GameSet::Input)
                     .run_if(in_state(GameState::Playing))
                     .in_set(CommandsSet::Attack),
+            )
+            .add_system(
+                stance_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CommandsSet::Stance),
+            )
+            .add_system(
+                focus_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CommandsSet::Focus),
+            )
+            .add_system(
+                guard_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CommandsSet::Guard),
+            )
+            .add_system(
+                load_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CommandsSet::Load),
+            )
+            .add_system(
+                unload_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CommandsSet::Unload),
             );
     }
 }
@@ -41,31 +87,89 @@ pub(crate) enum CommandsSet {
     SendSelected,
     DeliveryLocation,
     Attack,
+    Stance,
+    Focus,
+    Guard,
+    Load,
+    Unload,
 }
 
 /// Send this event to send all selected movable units to a point on the map.
-pub(crate) struct SendSelectedEvent(Vec2);
+pub(crate) struct SendSelectedEvent {
+    target: Vec2,
+    attack_move: bool,
+    patrol: bool,
+    queued: bool,
+    formation: Formation,
+}
 
 impl SendSelectedEvent {
     pub(crate) fn new(target: Vec2) -> Self {
-        Self(target)
+        Self {
+            target,
+            attack_move: false,
+            patrol: false,
+            queued: false,
+            formation: Formation::default(),
+        }
     }
 
-    fn target(&self) -> Vec2 {
-        self.0
+    /// Formation of the units around the target.
+    pub(crate) fn with_formation(mut self, formation: Formation) -> Self {
+        self.formation = formation;
+        self
+    }
+
+    /// The units will automatically attack enemies on their way to the
+    /// target.
+    pub(crate) fn with_attack_move(mut self) -> Self {
+        self.attack_move = true;
+        self
+    }
+
+    /// The units will patrol between their current positions (or their last
+    /// queued waypoints) and the target.
+    pub(crate) fn with_patrol(mut self) -> Self {
+        self.patrol = true;
+        self
+    }
+
+    /// The target will be appended to current orders of the units. This has
+    /// no effect on attack-move. The formation is planned from the last
+    /// queued waypoints of the units.
+    pub(crate) fn with_queue(mut self) -> Self {
+        self.queued = true;
+        self
+    }
+
+    fn attack_move(&self) -> bool {
+        self.attack_move
+    }
+
+    fn command(&self, entity: Entity, slot: FormationSlot) -> MovementCommand {
+        let mut command = MovementCommand::new(entity, vec![slot.position()]);
+        if self.patrol {
+            command = command.with_patrol();
+        } else {
+            command = command.with_speed(slot.speed());
+        }
+        if self.queued {
+            command = command.with_queue();
+        }
+        command
     }
 }
 
-/// Send this event to set manufacturing delivery location for all selected
-/// building with a factory.
-pub(crate) struct DeliveryLocationSelectedEvent(Vec2);
+/// Send this event to set manufacturing delivery target (rally point) for
+/// all selected buildings with a factory.
+pub(crate) struct DeliveryLocationSelectedEvent(DeliveryTarget);
 
 impl DeliveryLocationSelectedEvent {
-    pub(crate) fn new(target: Vec2) -> Self {
+    pub(crate) fn new(target: DeliveryTarget) -> Self {
         Self(target)
     }
 
-    fn target(&self) -> Vec2 {
+    fn target(&self) -> DeliveryTarget {
         self.0
     }
 }
@@ -79,35 +183,117 @@ impl GroupAttackEvent {
         Self(target)
     }
 
+    pub(crate) fn target(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Send this event to change stance of all selected units.
+pub(crate) struct SetSelectedStanceEvent(Stance);
+
+impl SetSelectedStanceEvent {
+    pub(crate) fn new(stance: Stance) -> Self {
+        Self(stance)
+    }
+
+    fn stance(&self) -> Stance {
+        self.0
+    }
+}
+
+/// Send this event to make all selected units focus their fire on an enemy
+/// (or to cancel the focus).
+pub(crate) struct FocusSelectedEvent(Option<Entity>);
+
+impl FocusSelectedEvent {
+    pub(crate) fn new(target: Option<Entity>) -> Self {
+        Self(target)
+    }
+
+    fn target(&self) -> Option<Entity> {
+        self.0
+    }
+}
+
+/// Send this event to make all selected units guard an allied entity.
+pub(crate) struct GuardSelectedEvent(Entity);
+
+impl GuardSelectedEvent {
+    pub(crate) fn new(target: Entity) -> Self {
+        Self(target)
+    }
+
     fn target(&self) -> Entity {
         self.0
     }
 }
 
-type SelectedMovable = (With<Selected>, With<MovableSolid>);
+/// Send this event to make all selected movable units board an own carrier.
+pub(crate) struct LoadSelectedEvent(Entity);
+
+impl LoadSelectedEvent {
+    pub(crate) fn new(carrier: Entity) -> Self {
+        Self(carrier)
+    }
+
+    fn carrier(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Send this event to unload all units carried by selected carriers.
+pub(crate) struct UnloadSelectedEvent;
 
 fn send_selected_system(
+    mut commands: Commands,
     mut send_events: EventReader<SendSelectedEvent>,
-    selected: Query<Entity, SelectedMovable>,
-    mut path_events: EventWriter<UpdateEntityPath>,
+    selected: SelectedQuery<(Entity, &Transform, Option<&OrderQueue>), With<MovableSolid>>,
+    mut enqueue_events: EventWriter<EnqueueCommandEvent>,
     mut chase_events: EventWriter<ChaseTargetEvent>,
+    mut attack_move_events: EventWriter<AttackMoveEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
 ) {
     if let Some(send) = send_events.iter().last() {
-        for entity in selected.iter() {
-            chase_events.send(ChaseTargetEvent::new(entity, None));
-            path_events.send(UpdateEntityPath::new(
-                entity,
-                PathTarget::new(send.target(), PathQueryProps::exact(), false),
+        if let Some((_, transform, _)) = selected.iter().next() {
+            sounds.send(PlaySoundEvent::new(
+                Sound::Acknowledgement,
+                transform.translation,
             ));
         }
+
+        let (entities, positions): (Vec<Entity>, Vec<Vec2>) = selected
+            .iter()
+            .map(|(entity, transform, queue)| {
+                let position = queue
+                    .filter(|_| send.queued && !send.attack_move())
+                    .and_then(|queue| queue.waypoints().last())
+                    .unwrap_or(transform.translation.to_flat());
+                (entity, position)
+            })
+            .unzip();
+        let slots = send.formation.plan(&positions, send.target);
+
+        for (entity, slot) in entities.into_iter().zip(slots) {
+            if send.attack_move() {
+                chase_events.send(ChaseTargetEvent::new(entity, None));
+                attack_move_events.send(AttackMoveEvent::new(entity, slot.position()));
+            } else {
+                if !send.queued {
+                    chase_events.send(ChaseTargetEvent::new(entity, None));
+                    commands
+                        .entity(entity)
+                        .remove::<AttackMoving>()
+                        .remove::<Guarding>();
+                }
+                enqueue_events.send(EnqueueCommandEvent::new(send.command(entity, slot)));
+            }
+        }
     }
 }
 
-type SelectedFactory = (With<Selected>, With<AssemblyLine>);
-
 fn delivery_location_system(
     mut in_events: EventReader<DeliveryLocationSelectedEvent>,
-    selected: Query<Entity, SelectedFactory>,
+    selected: SelectedQuery<Entity, With<AssemblyLine>>,
     mut out_events: EventWriter<ChangeDeliveryLocationEvent>,
 ) {
     if let Some(event) = in_events.iter().last() {
@@ -117,14 +303,90 @@ fn delivery_location_system(
     }
 }
 
+fn stance_system(
+    mut in_events: EventReader<SetSelectedStanceEvent>,
+    selected: SelectedQuery<Entity, With<Stance>>,
+    mut out_events: EventWriter<SetStanceEvent>,
+) {
+    if let Some(event) = in_events.iter().last() {
+        for entity in selected.iter() {
+            out_events.send(SetStanceEvent::new(entity, event.stance()));
+        }
+    }
+}
+
+fn focus_system(
+    mut in_events: EventReader<FocusSelectedEvent>,
+    selected: SelectedQuery<Entity, With<Stance>>,
+    mut out_events: EventWriter<SetPriorityTargetEvent>,
+) {
+    if let Some(event) = in_events.iter().last() {
+        for entity in selected.iter() {
+            out_events.send(SetPriorityTargetEvent::new(entity, event.target()));
+        }
+    }
+}
+
 fn attack_system(
     mut group_events: EventReader<GroupAttackEvent>,
-    selected: Query<Entity, SelectedMovable>,
+    selected: SelectedQuery<(Entity, &Transform), With<MovableSolid>>,
     mut individual_events: EventWriter<AttackEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
 ) {
     if let Some(group_event) = group_events.iter().last() {
-        for attacker in selected.iter() {
+        if let Some((_, transform)) = selected.iter().next() {
+            sounds.send(PlaySoundEvent::new(
+                Sound::Acknowledgement,
+                transform.translation,
+            ));
+        }
+
+        for (attacker, _) in selected.iter() {
             individual_events.send(AttackEvent::new(attacker, group_event.target()));
         }
     }
 }
+
+fn guard_system(
+    mut in_events: EventReader<GuardSelectedEvent>,
+    selected: SelectedQuery<Entity, With<MovableSolid>>,
+    mut out_events: EventWriter<GuardEvent>,
+) {
+    if let Some(event) = in_events.iter().last() {
+        for entity in selected.iter() {
+            out_events.send(GuardEvent::new(entity, event.target()));
+        }
+    }
+}
+
+fn load_system(
+    mut in_events: EventReader<LoadSelectedEvent>,
+    selected: SelectedQuery<(Entity, &Transform), With<MovableSolid>>,
+    mut out_events: EventWriter<LoadEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
+) {
+    if let Some(event) = in_events.iter().last() {
+        if let Some((_, transform)) = selected.iter().next() {
+            sounds.send(PlaySoundEvent::new(
+                Sound::Acknowledgement,
+                transform.translation,
+            ));
+        }
+
+        for (entity, _) in selected.iter() {
+            out_events.send(LoadEvent::new(entity, event.carrier()));
+        }
+    }
+}
+
+fn unload_system(
+    mut in_events: EventReader<UnloadSelectedEvent>,
+    selected: SelectedQuery<Entity, (With<Passengers>, With<Playable>)>,
+    mut out_events: EventWriter<UnloadEvent>,
+) {
+    if in_events.iter().last().is_some() {
+        for entity in selected.iter() {
+            out_events.send(UnloadEvent::new(entity));
+        }
+    }
+}
@@ -2,168 +2,259 @@
 //! keyboard shortcuts, mouse actions events, and so on.
 
 use bevy::{
-    input::{
-        keyboard::KeyboardInput,
-        mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
-        ButtonState,
-    },
+    ecs::system::SystemParam,
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
     prelude::*,
-    window::PrimaryWindow,
 };
+use de_behaviour::Formation;
 use de_camera::{
-    CameraSet, MoveCameraHorizontallyEvent, RotateCameraEvent, TiltCameraEvent, ZoomCameraEvent,
+    CameraSet, FollowEntityEvent, JumpToAlertEvent, MoveCameraHorizontallyEvent,
+    RecallBookmarkEvent, RotateCameraEvent, SaveBookmarkEvent, TiltCameraEvent, ZoomCameraEvent,
 };
-use de_conf::Configuration;
+use de_combat::Stance;
+use de_conf::{Action, Configuration, InputButton, KeyBindings, Modifiers};
+use de_construction::DeliveryTarget;
 use de_core::{
     baseset::GameSet,
+    diplomacy::Diplomacy,
     gamestate::GameState,
     gconfig::GameConfig,
-    objects::{BuildingType, ObjectType, Playable, PLAYER_MAX_BUILDINGS},
+    objects::{BuildingType, MovableSolid, ObjectType, Playable, PLAYER_MAX_BUILDINGS},
     player::Player,
     projection::ToFlat,
     screengeom::ScreenRect,
 };
 use de_spawner::{DraftAllowed, ObjectCounter};
+use de_transport::Passengers;
 use enum_map::enum_map;
 
 use super::{
-    executor::DeliveryLocationSelectedEvent, keyboard::KeyCondition, CommandsSet, GroupAttackEvent,
-    SendSelectedEvent,
+    actions::{ctrl_pressed, on_action, on_double_click_action},
+    executor::DeliveryLocationSelectedEvent,
+    CommandsSet, FocusSelectedEvent, GroupAttackEvent, GuardSelectedEvent, LoadSelectedEvent,
+    SendSelectedEvent, SetSelectedStanceEvent,
 };
 use crate::{
     draft::{DiscardDraftsEvent, DraftSet, NewDraftEvent, SpawnDraftsEvent},
     hud::{GameMenuSet, ToggleGameMenu, UpdateSelectionBoxEvent},
-    mouse::{
-        DragUpdateType, MouseClicked, MouseDoubleClicked, MouseDragged, MouseSet, Pointer,
-        PointerSet,
-    },
+    mouse::{DragUpdateType, MouseDragged, MouseSet, Pointer, PointerSet},
     selection::{
         AreaSelectSet, SelectEvent, SelectInRectEvent, Selected, SelectionMode, SelectionSet,
     },
 };
 
-/// Horizontal camera movement is initiated if mouse cursor is within this
-/// distance to window edge.
-const MOVE_MARGIN: f32 = 2.;
-
 pub(super) struct HandlersPlugin;
 
 impl HandlersPlugin {
     fn add_place_draft_systems(app: &mut App) {
-        let key_map = enum_map! {
-            BuildingType::Base => KeyCode::B,
-            BuildingType::PowerHub => KeyCode::P,
+        let action_map = enum_map! {
+            BuildingType::Base => Action::PlaceBase,
+            BuildingType::PowerHub => Action::PlacePowerHub,
         };
 
-        for (building_type, &key) in key_map.iter() {
+        for (building_type, &action) in action_map.iter() {
             app.add_system(
                 place_draft(building_type)
                     .in_base_set(GameSet::Input)
                     .run_if(in_state(GameState::Playing))
-                    .run_if(KeyCondition::single(key).build())
+                    .run_if(on_action(action))
                     .before(DraftSet::New)
                     .after(PointerSet::Update),
             );
         }
     }
+
+    fn add_formation_systems(app: &mut App) {
+        let action_map = [
+            (Formation::Line, Action::FormationLine),
+            (Formation::Wedge, Action::FormationWedge),
+            (Formation::Box, Action::FormationBox),
+        ];
+
+        for (formation, action) in action_map {
+            app.add_system(
+                set_formation(formation)
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(action)),
+            );
+        }
+    }
+
+    fn add_stance_systems(app: &mut App) {
+        let action_map = [
+            (Stance::Aggressive, Action::StanceAggressive),
+            (Stance::Defensive, Action::StanceDefensive),
+            (Stance::HoldFire, Action::StanceHoldFire),
+            (Stance::HoldPosition, Action::StanceHoldPosition),
+        ];
+
+        for (stance, action) in action_map {
+            app.add_system(
+                set_stance(stance)
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(action))
+                    .before(CommandsSet::Stance),
+            );
+        }
+    }
+
+    fn add_bookmark_systems(app: &mut App) {
+        for (bookmark, action) in Action::BOOKMARKS.into_iter().enumerate() {
+            app.add_system(
+                save_bookmark(bookmark)
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(action))
+                    .run_if(ctrl_pressed)
+                    .before(CameraSet::BookmarkEvent),
+            )
+            .add_system(
+                recall_bookmark(bookmark)
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(action))
+                    .run_if(not(ctrl_pressed))
+                    .before(CameraSet::BookmarkEvent),
+            );
+        }
+    }
 }
 
 impl Plugin for HandlersPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(
-            right_click_handler
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .run_if(on_click(MouseButton::Right))
-                .after(PointerSet::Update)
-                .after(MouseSet::Buttons)
-                .before(CommandsSet::SendSelected)
-                .before(CommandsSet::DeliveryLocation)
-                .before(CommandsSet::Attack),
-        )
-        .add_system(
-            left_click_handler
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .run_if(on_click(MouseButton::Left))
-                .in_set(HandlersSet::LeftClick)
-                .before(SelectionSet::Update)
-                .before(DraftSet::Spawn)
-                .after(PointerSet::Update)
-                .after(MouseSet::Buttons),
-        )
-        .add_system(
-            double_click_handler
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .run_if(on_double_click(MouseButton::Left))
-                .before(SelectionSet::Update)
-                .before(DraftSet::Spawn)
-                .after(PointerSet::Update)
-                .after(MouseSet::Buttons)
-                .after(HandlersSet::LeftClick),
-        )
-        .add_system(
-            move_camera_arrows_system
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .before(CameraSet::MoveHorizontallEvent),
-        )
-        .add_system(
-            move_camera_mouse_system
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .before(CameraSet::MoveHorizontallEvent),
-        )
-        .add_system(
-            zoom_camera
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .before(CameraSet::ZoomEvent),
-        )
-        .add_system(
-            pivot_camera
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .before(CameraSet::RotateEvent)
-                .before(CameraSet::TiltEvent),
-        )
-        .add_system(
-            handle_escape
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .run_if(KeyCondition::single(KeyCode::Escape).build())
-                .before(GameMenuSet::Toggle)
-                .before(DraftSet::Discard),
-        )
-        .add_system(
-            select_all
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .run_if(KeyCondition::single(KeyCode::A).with_ctrl().build())
-                .before(SelectionSet::Update),
-        )
-        .add_system(
-            select_all_visible
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .run_if(
-                    KeyCondition::single(KeyCode::A)
-                        .with_ctrl()
-                        .with_shift()
-                        .build(),
-                )
-                .before(AreaSelectSet::SelectInArea),
-        )
-        .add_system(
-            update_drags
-                .in_base_set(GameSet::Input)
-                .run_if(in_state(GameState::Playing))
-                .before(AreaSelectSet::SelectInArea)
-                .after(MouseSet::Buttons),
-        );
+        app.init_resource::<ActiveFormation>()
+            .add_system(
+                right_click_handler
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::Command))
+                    .after(PointerSet::Update)
+                    .after(MouseSet::Buttons)
+                    .before(CommandsSet::SendSelected)
+                    .before(CommandsSet::DeliveryLocation)
+                    .before(CommandsSet::Attack)
+                    .before(CommandsSet::Guard)
+                    .before(CommandsSet::Load),
+            )
+            .add_system(
+                attack_move_handler
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::AttackMove))
+                    .after(PointerSet::Update)
+                    .after(MouseSet::Buttons)
+                    .before(CommandsSet::SendSelected),
+            )
+            .add_system(
+                patrol_handler
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::Patrol))
+                    .after(PointerSet::Update)
+                    .after(MouseSet::Buttons)
+                    .before(CommandsSet::SendSelected),
+            )
+            .add_system(
+                focus_handler
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::Focus))
+                    .after(PointerSet::Update)
+                    .after(MouseSet::Buttons)
+                    .before(CommandsSet::Focus),
+            )
+            .add_system(
+                left_click_handler
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::Select))
+                    .in_set(HandlersSet::LeftClick)
+                    .before(SelectionSet::Update)
+                    .before(DraftSet::Spawn)
+                    .after(PointerSet::Update)
+                    .after(MouseSet::Buttons),
+            )
+            .add_system(
+                double_click_handler
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_double_click_action(Action::Select))
+                    .before(SelectionSet::Update)
+                    .before(DraftSet::Spawn)
+                    .after(PointerSet::Update)
+                    .after(MouseSet::Buttons)
+                    .after(HandlersSet::LeftClick),
+            )
+            .add_system(
+                move_camera_arrows_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .before(CameraSet::MoveHorizontallEvent),
+            )
+            .add_system(
+                zoom_camera
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .after(PointerSet::Update)
+                    .before(CameraSet::ZoomEvent),
+            )
+            .add_system(
+                follow_selected
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::CameraFollow))
+                    .before(CameraSet::FollowEvent),
+            )
+            .add_system(
+                jump_to_alert
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::JumpToAlert))
+                    .before(CameraSet::AlertEvent),
+            )
+            .add_system(
+                pivot_camera
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .before(CameraSet::RotateEvent)
+                    .before(CameraSet::TiltEvent),
+            )
+            .add_system(
+                handle_escape
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::Menu))
+                    .before(GameMenuSet::Toggle)
+                    .before(DraftSet::Discard),
+            )
+            .add_system(
+                select_all
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::SelectAll))
+                    .before(SelectionSet::Update),
+            )
+            .add_system(
+                select_all_visible
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::SelectAllVisible))
+                    .before(AreaSelectSet::SelectInArea),
+            )
+            .add_system(
+                update_drags
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .before(AreaSelectSet::SelectInArea)
+                    .after(MouseSet::Buttons),
+            );
 
         Self::add_place_draft_systems(app);
+        Self::add_stance_systems(app);
+        Self::add_formation_systems(app);
+        Self::add_bookmark_systems(app);
     }
 }
 
@@ -172,47 +263,138 @@ pub(crate) enum HandlersSet {
     LeftClick,
 }
 
-fn on_click(button: MouseButton) -> impl Fn(EventReader<MouseClicked>) -> bool {
-    move |mut events: EventReader<MouseClicked>| {
-        // It is desirable to exhaust the iterator, thus .filter().count() is
-        // used instead of .any()
-        events.iter().filter(|e| e.button() == button).count() > 0
+/// Formation used by groups of units sent to a location.
+#[derive(Resource, Default)]
+struct ActiveFormation(Formation);
+
+/// System parameter for options of movement orders given by the player.
+#[derive(SystemParam)]
+struct MoveOptions<'w> {
+    keys: Res<'w, Input<KeyCode>>,
+    formation: Res<'w, ActiveFormation>,
+}
+
+impl<'w> MoveOptions<'w> {
+    /// Applies the active formation to the event and queues it if shift is
+    /// held.
+    fn apply(&self, mut event: SendSelectedEvent) -> SendSelectedEvent {
+        event = event.with_formation(self.formation.0);
+        if self.keys.pressed(KeyCode::LShift) || self.keys.pressed(KeyCode::RShift) {
+            event = event.with_queue();
+        }
+        event
     }
 }
 
-fn on_double_click(button: MouseButton) -> impl Fn(EventReader<MouseDoubleClicked>) -> bool {
-    move |mut events: EventReader<MouseDoubleClicked>| {
-        // It is desirable to exhaust the iterator, thus .filter().count() is
-        // used instead of .any()
-        events.iter().filter(|e| e.button() == button).count() > 0
+/// System parameter for determining relation of entities to the local
+/// player.
+#[derive(SystemParam)]
+struct Relations<'w, 's> {
+    config: Res<'w, GameConfig>,
+    diplomacy: Res<'w, Diplomacy>,
+    players: Query<'w, 's, &'static Player>,
+    carriers: Query<'w, 's, (), (With<Passengers>, With<Playable>)>,
+}
+
+impl<'w, 's> Relations<'w, 's> {
+    /// Returns true if the entity is owned by an enemy of the local player
+    /// and None if the entity is not owned by any player.
+    fn is_enemy(&self, entity: Entity) -> Option<bool> {
+        self.players.get(entity).ok().map(|&player| {
+            !self
+                .diplomacy
+                .are_allies(self.config.locals().playable(), player)
+        })
+    }
+
+    /// Returns true if the entity is a carrier owned by the local player.
+    fn is_own_carrier(&self, entity: Entity) -> bool {
+        self.carriers.contains(entity)
     }
 }
 
+/// System parameter for sending of commands targeting an entity to all
+/// selected units.
+#[derive(SystemParam)]
+struct TargetEvents<'w> {
+    attack: EventWriter<'w, GroupAttackEvent>,
+    guard: EventWriter<'w, GuardSelectedEvent>,
+    load: EventWriter<'w, LoadSelectedEvent>,
+}
+
 fn right_click_handler(
-    config: Res<GameConfig>,
+    relations: Relations,
     mut send_events: EventWriter<SendSelectedEvent>,
     mut location_events: EventWriter<DeliveryLocationSelectedEvent>,
-    mut attack_events: EventWriter<GroupAttackEvent>,
-    targets: Query<&Player>,
+    mut target_events: TargetEvents,
+    options: MoveOptions,
     pointer: Res<Pointer>,
 ) {
-    match pointer.entity().filter(|&entity| {
-        targets
-            .get(entity)
-            .map(|&player| !config.locals().is_playable(player))
-            .unwrap_or(false)
-    }) {
-        Some(enemy) => attack_events.send(GroupAttackEvent::new(enemy)),
+    let target = pointer
+        .entity()
+        .and_then(|entity| relations.is_enemy(entity).map(|enemy| (entity, enemy)));
+
+    match target {
+        Some((enemy, true)) => target_events.attack.send(GroupAttackEvent::new(enemy)),
+        Some((ally, false)) => {
+            if relations.is_own_carrier(ally) {
+                target_events.load.send(LoadSelectedEvent::new(ally));
+            } else {
+                target_events.guard.send(GuardSelectedEvent::new(ally));
+            }
+            location_events.send(DeliveryLocationSelectedEvent::new(DeliveryTarget::Entity(
+                ally,
+            )));
+        }
         None => {
             let Some(target) = pointer.terrain_point().map(|p| p.to_flat()) else {
                 return;
             };
-            send_events.send(SendSelectedEvent::new(target));
-            location_events.send(DeliveryLocationSelectedEvent::new(target));
+            send_events.send(options.apply(SendSelectedEvent::new(target)));
+            // Objects not owned by any player (e.g. resources) are used as
+            // rally targets directly.
+            let delivery_target = match pointer.entity() {
+                Some(entity) => DeliveryTarget::Entity(entity),
+                None => DeliveryTarget::Location(target),
+            };
+            location_events.send(DeliveryLocationSelectedEvent::new(delivery_target));
         }
     }
 }
 
+fn focus_handler(
+    relations: Relations,
+    pointer: Res<Pointer>,
+    mut events: EventWriter<FocusSelectedEvent>,
+) {
+    let enemy = pointer
+        .entity()
+        .filter(|&entity| relations.is_enemy(entity).unwrap_or(false));
+    events.send(FocusSelectedEvent::new(enemy));
+}
+
+fn attack_move_handler(
+    options: MoveOptions,
+    pointer: Res<Pointer>,
+    mut send_events: EventWriter<SendSelectedEvent>,
+) {
+    let Some(target) = pointer.terrain_point().map(|p| p.to_flat()) else {
+        return;
+    };
+    send_events.send(options.apply(SendSelectedEvent::new(target).with_attack_move()));
+}
+
+fn patrol_handler(
+    options: MoveOptions,
+    pointer: Res<Pointer>,
+    mut send_events: EventWriter<SendSelectedEvent>,
+) {
+    let Some(target) = pointer.terrain_point().map(|p| p.to_flat()) else {
+        return;
+    };
+    send_events.send(options.apply(SendSelectedEvent::new(target).with_patrol()));
+}
+
 fn double_click_handler(
     keys: Res<Input<KeyCode>>,
     pointer: Res<Pointer>,
@@ -223,11 +405,7 @@ fn double_click_handler(
     if !drafts.is_empty() {
         return;
     }
-    let selection_mode = if keys.pressed(KeyCode::LControl) {
-        SelectionMode::Add
-    } else {
-        SelectionMode::Replace
-    };
+    let selection_mode = selection_mode(keys.as_ref(), SelectionMode::Add);
 
     let Some(targeted_entity_type) = pointer
         .entity()
@@ -245,71 +423,31 @@ fn double_click_handler(
 }
 
 fn move_camera_arrows_system(
-    mut key_events: EventReader<KeyboardInput>,
-    mut move_events: EventWriter<MoveCameraHorizontallyEvent>,
-) {
-    for key_event in key_events.iter() {
-        let Some(key_code) = key_event.key_code else {
-            continue;
-        };
-
-        let mut direction = Vec2::ZERO;
-        if key_code == KeyCode::Left {
-            direction = Vec2::new(-1., 0.);
-        } else if key_code == KeyCode::Right {
-            direction = Vec2::new(1., 0.);
-        } else if key_code == KeyCode::Down {
-            direction = Vec2::new(0., -1.);
-        } else if key_code == KeyCode::Up {
-            direction = Vec2::new(0., 1.);
-        }
-
-        if direction == Vec2::ZERO {
-            continue;
-        }
-        if key_event.state == ButtonState::Released {
-            direction = Vec2::ZERO;
-        }
-
-        move_events.send(MoveCameraHorizontallyEvent::new(direction));
-    }
-}
-
-fn move_camera_mouse_system(
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    mut was_moving: Local<bool>,
+    bindings: Res<KeyBindings>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
     mut move_events: EventWriter<MoveCameraHorizontallyEvent>,
 ) {
-    let window = window_query.single();
-    let Some(cursor) = window.cursor_position() else {
-        if *was_moving {
-            *was_moving = false;
+    let directions = [
+        (Action::CameraLeft, Vec2::new(-1., 0.)),
+        (Action::CameraRight, Vec2::new(1., 0.)),
+        (Action::CameraDown, Vec2::new(0., -1.)),
+        (Action::CameraUp, Vec2::new(0., 1.)),
+    ];
+
+    for (action, direction) in directions {
+        let binding = bindings.get(action);
+        if binding.just_pressed(&keys, &buttons) {
+            move_events.send(MoveCameraHorizontallyEvent::new(direction));
+        } else if binding.just_released(&keys, &buttons) {
             move_events.send(MoveCameraHorizontallyEvent::new(Vec2::ZERO));
         }
-        return;
-    };
-
-    let mut movement = Vec2::ZERO;
-    if cursor.x < MOVE_MARGIN {
-        movement.x -= 1.;
-    } else if cursor.x > (window.width() - MOVE_MARGIN) {
-        movement.x += 1.;
-    }
-    if cursor.y < MOVE_MARGIN {
-        movement.y -= 1.;
-    } else if cursor.y > (window.height() - MOVE_MARGIN) {
-        movement.y += 1.;
     }
-
-    if (movement != Vec2::ZERO) == *was_moving {
-        return;
-    }
-    *was_moving = movement != Vec2::ZERO;
-    move_events.send(MoveCameraHorizontallyEvent::new(movement));
 }
 
 fn zoom_camera(
     conf: Res<Configuration>,
+    pointer: Res<Pointer>,
     mut wheel_events: EventReader<MouseWheel>,
     mut zoom_events: EventWriter<ZoomCameraEvent>,
 ) {
@@ -320,18 +458,66 @@ fn zoom_camera(
             MouseScrollUnit::Line => factor * conf.wheel_zoom_sensitivity().powf(event.y),
             MouseScrollUnit::Pixel => factor * conf.touchpad_zoom_sensitivity().powf(event.y),
         });
-    zoom_events.send(ZoomCameraEvent::new(factor));
+
+    let mut event = ZoomCameraEvent::new(factor);
+    if conf.zoom_to_cursor() {
+        if let Some(point) = pointer.terrain_point() {
+            event = event.with_anchor(point);
+        }
+    }
+    zoom_events.send(event);
+}
+
+fn follow_selected(
+    selected: Query<Entity, (With<Selected>, With<MovableSolid>)>,
+    mut events: EventWriter<FollowEntityEvent>,
+) {
+    events.send(match selected.iter().next() {
+        Some(entity) => FollowEntityEvent::new(entity),
+        None => FollowEntityEvent::stop(),
+    });
+}
+
+fn jump_to_alert(mut events: EventWriter<JumpToAlertEvent>) {
+    events.send(JumpToAlertEvent);
+}
+
+fn save_bookmark(bookmark: usize) -> impl Fn(EventWriter<SaveBookmarkEvent>) {
+    move |mut events: EventWriter<SaveBookmarkEvent>| {
+        events.send(SaveBookmarkEvent::new(bookmark));
+    }
+}
+
+fn recall_bookmark(bookmark: usize) -> impl Fn(EventWriter<RecallBookmarkEvent>) {
+    move |mut events: EventWriter<RecallBookmarkEvent>| {
+        events.send(RecallBookmarkEvent::new(bookmark));
+    }
+}
+
+/// System parameter for camera pivoting related input.
+#[derive(SystemParam)]
+struct PivotInput<'w> {
+    bindings: Res<'w, KeyBindings>,
+    keys: Res<'w, Input<KeyCode>>,
+    buttons: Res<'w, Input<MouseButton>>,
+}
+
+impl<'w> PivotInput<'w> {
+    fn pressed(&self) -> bool {
+        self.bindings
+            .get(Action::CameraPivot)
+            .pressed(&self.keys, &self.buttons)
+    }
 }
 
 fn pivot_camera(
     conf: Res<Configuration>,
-    buttons: Res<Input<MouseButton>>,
-    keys: Res<Input<KeyCode>>,
+    input: PivotInput,
     mut mouse_event: EventReader<MouseMotion>,
     mut rotate_event: EventWriter<RotateCameraEvent>,
     mut tilt_event: EventWriter<TiltCameraEvent>,
 ) {
-    if !buttons.pressed(MouseButton::Middle) && !keys.pressed(KeyCode::LShift) {
+    if !input.pressed() {
         return;
     }
 
@@ -345,6 +531,20 @@ fn pivot_camera(
     }
 }
 
+/// Returns mode of a selection made by a click or by a drag: `ctrl_mode` while
+/// Ctrl is held, [`SelectionMode::Add`] while Shift is held and
+/// [`SelectionMode::Replace`] otherwise.
+fn selection_mode(keys: &Input<KeyCode>, ctrl_mode: SelectionMode) -> SelectionMode {
+    let modifiers = Modifiers::pressed(keys);
+    if modifiers.ctrl() {
+        ctrl_mode
+    } else if modifiers.shift() {
+        SelectionMode::Add
+    } else {
+        SelectionMode::Replace
+    }
+}
+
 fn left_click_handler(
     mut select_events: EventWriter<SelectEvent>,
     mut draft_events: EventWriter<SpawnDraftsEvent>,
@@ -354,11 +554,7 @@ fn left_click_handler(
     drafts: Query<(), With<DraftAllowed>>,
 ) {
     if drafts.is_empty() {
-        let selection_mode = if keys.pressed(KeyCode::LControl) {
-            SelectionMode::AddToggle
-        } else {
-            SelectionMode::Replace
-        };
+        let selection_mode = selection_mode(keys.as_ref(), SelectionMode::AddToggle);
 
         let event = match pointer.entity().filter(|&e| playable.contains(e)) {
             Some(entity) => SelectEvent::single(entity, selection_mode),
@@ -407,6 +603,18 @@ fn place_draft(
     }
 }
 
+fn set_formation(formation: Formation) -> impl Fn(ResMut<ActiveFormation>) {
+    move |mut active: ResMut<ActiveFormation>| {
+        active.0 = formation;
+    }
+}
+
+fn set_stance(stance: Stance) -> impl Fn(EventWriter<SetSelectedStanceEvent>) {
+    move |mut events: EventWriter<SetSelectedStanceEvent>| {
+        events.send(SetSelectedStanceEvent::new(stance));
+    }
+}
+
 fn select_all(
     playable: Query<Entity, (With<Playable>, Without<Selected>)>,
     mut events: EventWriter<SelectEvent>,
@@ -424,13 +632,17 @@ fn select_all_visible(mut events: EventWriter<SelectInRectEvent>) {
 }
 
 fn update_drags(
+    bindings: Res<KeyBindings>,
     keys: Res<Input<KeyCode>>,
     mut drag_events: EventReader<MouseDragged>,
     mut ui_events: EventWriter<UpdateSelectionBoxEvent>,
     mut select_events: EventWriter<SelectInRectEvent>,
 ) {
+    // Area selection is done by dragging with the mouse button used for
+    // selection.
+    let select_button = bindings.get(Action::Select).button();
     for drag_event in drag_events.iter() {
-        if drag_event.button() != MouseButton::Left {
+        if select_button != InputButton::Mouse(drag_event.button()) {
             continue;
         }
 
@@ -441,12 +653,7 @@ fn update_drags(
             },
             DragUpdateType::Released => {
                 if let Some(rect) = drag_event.rect() {
-                    let mode = if keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl)
-                    {
-                        SelectionMode::Add
-                    } else {
-                        SelectionMode::Replace
-                    };
+                    let mode = selection_mode(keys.as_ref(), SelectionMode::Add);
                     select_events.send(SelectInRectEvent::new(rect, mode, None));
                 }
 
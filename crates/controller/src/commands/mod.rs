@@ -2,20 +2,30 @@
 //! actions.
 
 use bevy::prelude::*;
+use de_core::{baseset::GameSet, gamestate::GameState};
 pub(crate) use executor::{
-    CommandsSet, DeliveryLocationSelectedEvent, GroupAttackEvent, SendSelectedEvent,
+    CommandsSet, DeliveryLocationSelectedEvent, FocusSelectedEvent, GroupAttackEvent,
+    GuardSelectedEvent, LoadSelectedEvent, SendSelectedEvent, SetSelectedStanceEvent,
+    UnloadSelectedEvent,
 };
 
+pub(crate) use self::actions::{ctrl_pressed, on_action};
 use self::{executor::ExecutorPlugin, handlers::HandlersPlugin};
 
+mod actions;
 mod executor;
 mod handlers;
-mod keyboard;
 
 pub(crate) struct CommandsPlugin;
 
 impl Plugin for CommandsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(HandlersPlugin).add_plugin(ExecutorPlugin);
+        app.add_plugin(HandlersPlugin)
+            .add_plugin(ExecutorPlugin)
+            .add_system(
+                actions::count_actions
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
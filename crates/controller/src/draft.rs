@@ -1,13 +1,15 @@
 use bevy::prelude::*;
+use de_construction::ConstructBuildingEvent;
 use de_core::{
     baseset::GameSet,
     cleanup::DespawnOnGameExit,
     gamestate::GameState,
     gconfig::GameConfig,
-    objects::{BuildingType, ObjectType},
+    objects::{ActiveObjectType, BuildingType, ObjectType},
+    projection::ToFlat,
     state::AppState,
 };
-use de_spawner::{DraftAllowed, DraftBundle, SpawnBundle};
+use de_spawner::{DraftAllowed, DraftBundle};
 
 use crate::mouse::{Pointer, PointerSet};
 
@@ -84,16 +86,23 @@ fn spawn(
     mut commands: Commands,
     game_config: Res<GameConfig>,
     drafts: Query<(Entity, &Transform, &ObjectType, &DraftAllowed)>,
+    mut events: EventWriter<ConstructBuildingEvent>,
 ) {
-    for (entity, &transform, &object_type, draft) in drafts.iter() {
-        if draft.allowed() {
-            commands.entity(entity).despawn_recursive();
-            commands.spawn((
-                SpawnBundle::new(object_type, transform),
-                game_config.locals().playable(),
-                DespawnOnGameExit,
-            ));
+    for (entity, transform, &object_type, draft) in drafts.iter() {
+        if !draft.allowed() {
+            continue;
         }
+        let ObjectType::Active(ActiveObjectType::Building(building_type)) = object_type else {
+            panic!("Only buildings can be drafted, got {object_type}.");
+        };
+
+        commands.entity(entity).despawn_recursive();
+        events.send(ConstructBuildingEvent::new(
+            game_config.locals().playable(),
+            building_type,
+            transform.translation.to_flat(),
+            0.,
+        ));
     }
 }
 
@@ -0,0 +1,520 @@
+//! Command card: a grid of buttons with context-sensitive actions of the
+//! active object, i.e. the first object of the active subgroup of the
+//! selection. Factories manufacture units, the base places drafts of new
+//! buildings, carriers unload carried units and combat units change their
+//! stance.
+//!
+//! Each slot of the grid is bound to a key, see [`Action::CARD`].
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_combat::Stance;
+use de_conf::{Action, KeyBindings, CARD_SLOTS};
+use de_construction::{
+    CancelProductionEvent, EnqueueAssemblyEvent, CONSTRUCTION_TIME, MANUFACTURING_TIME,
+    UNIT_ENERGY_COST,
+};
+use de_core::{
+    baseset::GameSet,
+    cleanup::DespawnOnGameExit,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    objects::{ActiveObjectType, BuildingType, ObjectType, UnitType, PLAYER_MAX_BUILDINGS},
+};
+use de_gui::{BodyTextCommands, BodyTextOps, ButtonCommands, GuiCommands, OuterStyle};
+use de_loc::Localize;
+use de_objects::SolidObjects;
+use de_spawner::ObjectCounter;
+use de_transport::Passengers;
+use enum_map::Enum;
+
+use super::{details::format_units, interaction::InteractionBlocker, HUD_COLOR};
+use crate::{
+    commands::{on_action, CommandsSet, SetSelectedStanceEvent, UnloadSelectedEvent},
+    draft::{DraftSet, NewDraftEvent},
+    selection::Selection,
+};
+
+/// Number of buttons in a row of the card.
+const COLUMNS: usize = 4;
+const ROWS: usize = CARD_SLOTS / COLUMNS;
+const STANCES: [Stance; 4] = [
+    Stance::Aggressive,
+    Stance::Defensive,
+    Stance::HoldFire,
+    Stance::HoldPosition,
+];
+
+pub(crate) struct CommandCardPlugin;
+
+impl CommandCardPlugin {
+    fn add_slot_systems(app: &mut App) {
+        for (slot, action) in Action::CARD.into_iter().enumerate() {
+            app.add_system(
+                press_slot(slot)
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(action))
+                    .before(CommandsSet::Stance)
+                    .before(CommandsSet::Unload)
+                    .before(DraftSet::New),
+            );
+        }
+    }
+}
+
+impl Plugin for CommandCardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(cleanup.in_schedule(OnExit(GameState::Playing)))
+            .add_system(
+                detect_update
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(CardSet::DetectUpdate),
+            )
+            .add_system(
+                update
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_exists_and_changed::<ActiveEntity>())
+                    .in_set(CardSet::Update)
+                    .after(CardSet::DetectUpdate),
+            )
+            .add_system(
+                update_tooltip
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .after(CardSet::Update),
+            )
+            .add_system(
+                button_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .before(CommandsSet::Stance)
+                    .before(CommandsSet::Unload)
+                    .before(DraftSet::New),
+            );
+
+        Self::add_slot_systems(app);
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+enum CardSet {
+    DetectUpdate,
+    Update,
+}
+
+#[derive(Resource)]
+struct CardNodes {
+    grid: Entity,
+    tooltip: Entity,
+    tooltip_text: Entity,
+}
+
+#[derive(Resource, Default)]
+struct ActiveEntity(Option<Entity>);
+
+/// Actions of all slots of the card of the active entity.
+#[derive(Resource, Default)]
+struct CardSlots([Option<ButtonAction>; CARD_SLOTS]);
+
+/// A component attached to every button in the command card.
+#[derive(Component, Clone, Copy)]
+struct CardButton {
+    slot: usize,
+    action: ButtonAction,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ButtonAction {
+    /// Manufacture a unit in the active factory.
+    Manufacture(UnitType),
+    /// Cancel manufacturing of the last unit enqueued in the active factory.
+    Cancel,
+    /// Place a draft of a new building.
+    Construct(BuildingType),
+    /// Change stance of all selected combat units.
+    Stance(Stance),
+    /// Unload all units carried by selected carriers.
+    Unload,
+}
+
+impl ButtonAction {
+    fn caption(self, localize: &Localize) -> String {
+        match self {
+            Self::Manufacture(unit) => initials(&localize.object_name(unit_object(unit))),
+            Self::Cancel => "X".to_owned(),
+            Self::Construct(building) => initials(&localize.object_name(building_object(building))),
+            Self::Stance(stance) => initials(&localize.action_name(stance_action(stance))),
+            Self::Unload => initials(&localize.get("card-unload-caption")),
+        }
+    }
+
+    fn tooltip(self, localize: &Localize) -> String {
+        match self {
+            Self::Manufacture(unit) => localize.format(
+                "card-manufacture",
+                &[
+                    ("unit", &localize.object_name(unit_object(unit))),
+                    ("energy", &format_units(UNIT_ENERGY_COST, "J")),
+                    ("time", &MANUFACTURING_TIME.as_secs()),
+                ],
+            ),
+            Self::Cancel => localize.get("card-cancel"),
+            Self::Construct(building) => localize.format(
+                "card-construct",
+                &[
+                    ("building", &localize.object_name(building_object(building))),
+                    ("time", &CONSTRUCTION_TIME.as_secs()),
+                ],
+            ),
+            Self::Stance(stance) => localize.format(
+                "card-stance",
+                &[("stance", &localize.action_name(stance_action(stance)))],
+            ),
+            Self::Unload => localize.get("card-unload"),
+        }
+    }
+}
+
+fn unit_object(unit: UnitType) -> ObjectType {
+    ObjectType::Active(ActiveObjectType::Unit(unit))
+}
+
+fn building_object(building: BuildingType) -> ObjectType {
+    ObjectType::Active(ActiveObjectType::Building(building))
+}
+
+fn stance_action(stance: Stance) -> Action {
+    match stance {
+        Stance::Aggressive => Action::StanceAggressive,
+        Stance::Defensive => Action::StanceDefensive,
+        Stance::HoldFire => Action::StanceHoldFire,
+        Stance::HoldPosition => Action::StanceHoldPosition,
+    }
+}
+
+/// Returns upper case initials of all words of a name, e.g. "PH" for "Power
+/// Hub".
+fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+/// Arranges the actions to the card slots. Each group of actions starts on
+/// a new row, groups longer than a row and groups which do not fit on the
+/// card are truncated.
+fn arrange(groups: &[Vec<ButtonAction>]) -> [Option<ButtonAction>; CARD_SLOTS] {
+    let mut slots = [None; CARD_SLOTS];
+    let rows = groups.iter().filter(|group| !group.is_empty()).take(ROWS);
+    for (row, group) in rows.enumerate() {
+        for (column, &action) in group.iter().take(COLUMNS).enumerate() {
+            slots[row * COLUMNS + column] = Some(action);
+        }
+    }
+    slots
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<CardNodes>();
+    commands.remove_resource::<ActiveEntity>();
+    commands.remove_resource::<CardSlots>();
+}
+
+fn setup(mut commands: GuiCommands) {
+    let bar = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size {
+                        width: Val::Percent(60.),
+                        height: Val::Percent(15.),
+                    },
+                    position_type: PositionType::Absolute,
+                    position: UiRect::new(
+                        Val::Percent(20.),
+                        Val::Percent(80.),
+                        Val::Percent(85.),
+                        Val::Percent(100.),
+                    ),
+                    ..default()
+                },
+                background_color: HUD_COLOR.into(),
+                ..default()
+            },
+            DespawnOnGameExit,
+            InteractionBlocker,
+        ))
+        .id();
+
+    let grid = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(50.), Val::Percent(100.)),
+                flex_wrap: FlexWrap::Wrap,
+                align_content: AlignContent::FlexStart,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(bar).add_child(grid);
+
+    let tooltip = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    size: Size::new(Val::Percent(30.), Val::Percent(8.)),
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Percent(20.),
+                        bottom: Val::Percent(15.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                background_color: HUD_COLOR.into(),
+                ..default()
+            },
+            DespawnOnGameExit,
+        ))
+        .id();
+    let tooltip_text = commands
+        .spawn_body_text(
+            OuterStyle {
+                size: Size::new(Val::Percent(96.), Val::Percent(90.)),
+                margin: UiRect::all(Val::Percent(2.)),
+            },
+            "",
+        )
+        .id();
+    commands.entity(tooltip).add_child(tooltip_text);
+
+    commands.insert_resource(CardNodes {
+        grid,
+        tooltip,
+        tooltip_text,
+    });
+    commands.init_resource::<ActiveEntity>();
+    commands.init_resource::<CardSlots>();
+}
+
+/// The card controls the first entity of the active subgroup of the
+/// selection.
+fn detect_update(mut active: ResMut<ActiveEntity>, selection: Res<Selection>) {
+    let new = selection.subgroup().next();
+    if active.0 != new {
+        active.0 = new;
+    }
+}
+
+fn update(
+    mut commands: GuiCommands,
+    solids: SolidObjects,
+    nodes: Res<CardNodes>,
+    active: Res<ActiveEntity>,
+    mut slots: ResMut<CardSlots>,
+    objects: Query<(&ObjectType, Option<&Stance>, Option<&Passengers>)>,
+    localize: Res<Localize>,
+) {
+    commands.entity(nodes.grid).despawn_descendants();
+    slots.0 = [None; CARD_SLOTS];
+
+    let Some(active) = active.0 else { return };
+    let (&object_type, stance, passengers) = objects.get(active).unwrap();
+
+    let mut production = Vec::new();
+    if let Some(factory) = solids.get(object_type).factory() {
+        let mut products: Vec<UnitType> = factory.products().iter().copied().collect();
+        products.sort_by_key(|&unit| unit.into_usize());
+        production.extend(products.into_iter().map(ButtonAction::Manufacture));
+        production.push(ButtonAction::Cancel);
+    }
+    if passengers.is_some() {
+        production.push(ButtonAction::Unload);
+    }
+
+    // The base is the only constructor.
+    let construction = if object_type == building_object(BuildingType::Base) {
+        (0..BuildingType::LENGTH)
+            .map(|index| ButtonAction::Construct(BuildingType::from_usize(index)))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let stances = if stance.is_some() {
+        STANCES.into_iter().map(ButtonAction::Stance).collect()
+    } else {
+        Vec::new()
+    };
+
+    slots.0 = arrange(&[production, construction, stances]);
+    for (slot, &action) in slots.0.iter().enumerate() {
+        let style = OuterStyle {
+            size: Size::new(Val::Percent(23.), Val::Percent(44.)),
+            margin: UiRect::all(Val::Percent(1.)),
+        };
+
+        let node = match action {
+            Some(action) => commands
+                .spawn_button(style, action.caption(localize.as_ref()))
+                .insert(CardButton { slot, action })
+                .id(),
+            // Empty slots keep the other buttons at their grid position.
+            None => commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: style.size,
+                        margin: style.margin,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .id(),
+        };
+        commands.entity(nodes.grid).add_child(node);
+    }
+}
+
+fn update_tooltip(
+    nodes: Res<CardNodes>,
+    bindings: Res<KeyBindings>,
+    localize: Res<Localize>,
+    buttons: Query<(&Interaction, &CardButton)>,
+    mut styles: Query<&mut Style>,
+    mut text_ops: BodyTextOps,
+) {
+    let hovered = buttons
+        .iter()
+        .find(|(&interaction, _)| interaction != Interaction::None)
+        .map(|(_, &button)| button);
+
+    let display = match hovered {
+        Some(button) => {
+            let text = localize.format(
+                "card-tooltip",
+                &[
+                    ("action", &button.action.tooltip(localize.as_ref())),
+                    ("binding", &bindings.get(Action::CARD[button.slot])),
+                ],
+            );
+            text_ops
+                .set_text(nodes.tooltip_text, text)
+                .expect("Failed to set text of command card tooltip");
+            Display::Flex
+        }
+        None => Display::None,
+    };
+
+    let mut style = styles.get_mut(nodes.tooltip).unwrap();
+    if style.display != display {
+        style.display = display;
+    }
+}
+
+#[derive(SystemParam)]
+struct CardExecutor<'w, 's> {
+    active: Res<'w, ActiveEntity>,
+    conf: Res<'w, GameConfig>,
+    counter: Res<'w, ObjectCounter>,
+    transforms: Query<'w, 's, &'static Transform>,
+    enqueue: EventWriter<'w, EnqueueAssemblyEvent>,
+    cancel: EventWriter<'w, CancelProductionEvent>,
+    stances: EventWriter<'w, SetSelectedStanceEvent>,
+    unload: EventWriter<'w, UnloadSelectedEvent>,
+    drafts: EventWriter<'w, NewDraftEvent>,
+}
+
+impl<'w, 's> CardExecutor<'w, 's> {
+    fn execute(&mut self, action: ButtonAction) {
+        let Some(active) = self.active.0 else { return };
+
+        match action {
+            ButtonAction::Manufacture(unit) => {
+                self.enqueue.send(EnqueueAssemblyEvent::new(active, unit));
+            }
+            ButtonAction::Cancel => self.cancel.send(CancelProductionEvent::new(active)),
+            ButtonAction::Construct(building) => {
+                if self
+                    .counter
+                    .player(self.conf.locals().playable())
+                    .unwrap()
+                    .building_count()
+                    >= PLAYER_MAX_BUILDINGS
+                {
+                    warn!("Maximum number of buildings reached.");
+                    return;
+                }
+
+                // The draft follows the pointer once it is moved from the
+                // card back to the terrain.
+                let point = self.transforms.get(active).unwrap().translation;
+                self.drafts.send(NewDraftEvent::new(point, building));
+            }
+            ButtonAction::Stance(stance) => {
+                self.stances.send(SetSelectedStanceEvent::new(stance));
+            }
+            ButtonAction::Unload => self.unload.send(UnloadSelectedEvent),
+        }
+    }
+}
+
+fn button_system(
+    interactions: Query<(&Interaction, &CardButton), Changed<Interaction>>,
+    mut executor: CardExecutor,
+) {
+    for (&interaction, &button) in interactions.iter() {
+        if let Interaction::Clicked = interaction {
+            executor.execute(button.action);
+        }
+    }
+}
+
+fn press_slot(slot: usize) -> impl Fn(Res<CardSlots>, CardExecutor) {
+    move |slots: Res<CardSlots>, mut executor: CardExecutor| {
+        if let Some(action) = slots.0[slot] {
+            executor.execute(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(initials("Power Hub"), "PH");
+        assert_eq!(initials("hold  fire"), "HF");
+        assert_eq!(initials(""), "");
+    }
+
+    #[test]
+    fn test_arrange() {
+        let manufacture = ButtonAction::Manufacture(UnitType::Attacker);
+        let stances: Vec<ButtonAction> = STANCES.into_iter().map(ButtonAction::Stance).collect();
+
+        let slots = arrange(&[
+            vec![manufacture, ButtonAction::Cancel, ButtonAction::Unload],
+            Vec::new(),
+            stances.clone(),
+        ]);
+        assert_eq!(slots[0], Some(manufacture));
+        assert_eq!(slots[1], Some(ButtonAction::Cancel));
+        assert_eq!(slots[2], Some(ButtonAction::Unload));
+        assert_eq!(slots[3], None);
+        assert_eq!(slots[4], Some(ButtonAction::Stance(Stance::Aggressive)));
+        assert_eq!(slots[7], Some(ButtonAction::Stance(Stance::HoldPosition)));
+
+        let mut long = stances.clone();
+        long.push(manufacture);
+        let slots = arrange(&[long, stances.clone(), stances]);
+        assert_eq!(slots[3], Some(ButtonAction::Stance(Stance::HoldPosition)));
+        assert_eq!(slots.iter().filter(|slot| slot.is_some()).count(), 8);
+    }
+}
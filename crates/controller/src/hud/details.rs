@@ -1,8 +1,11 @@
 use bevy::prelude::*;
+use de_combat::{Rank, RankBadge};
+use de_construction::{AssemblyLine, UnderConstruction};
 use de_core::baseset::GameSet;
-use de_core::{cleanup::DespawnOnGameExit, gamestate::GameState};
+use de_core::{cleanup::DespawnOnGameExit, gamestate::GameState, objects::ObjectType};
 use de_energy::Battery;
 use de_gui::{BodyTextCommands, BodyTextOps, GuiCommands, OuterStyle};
+use de_loc::Localize;
 
 use super::{interaction::InteractionBlocker, HUD_COLOR};
 use crate::selection::Selected;
@@ -68,7 +71,7 @@ fn setup(mut commands: GuiCommands) {
     commands.insert_resource(DetailsText(details_text));
 }
 
-fn format_units(value: f64, units: &str) -> String {
+pub(super) fn format_units(value: f64, units: &str) -> String {
     let mut value = value;
     let mut i = 0;
 
@@ -90,10 +93,29 @@ fn most_significant(value: f64) -> f64 {
     }
 }
 
+fn rank_id(rank: Rank) -> &'static str {
+    match rank {
+        Rank::Recruit => "rank-recruit",
+        Rank::Veteran => "rank-veteran",
+        Rank::Elite => "rank-elite",
+        Rank::Hero => "rank-hero",
+    }
+}
+
+type StateComponents<'a> = (
+    &'a ObjectType,
+    Option<&'a RankBadge>,
+    Option<&'a UnderConstruction>,
+    Option<&'a AssemblyLine>,
+);
+
 fn update(
     ui: Res<DetailsText>,
     selected: Query<Entity, With<Selected>>,
     battery: Query<&Battery>,
+    states: Query<StateComponents>,
+    time: Res<Time>,
+    localize: Res<Localize>,
     mut text_ops: BodyTextOps,
 ) {
     let mut battery_total = 0.;
@@ -116,13 +138,58 @@ fn update(
         return;
     }
 
-    let text = format!(
-        "Battery: {} / {} ({:.1}%)\nSelected {}",
-        format_units(battery_total, "J"),
-        format_units(battery_max, "J"),
-        battery_total * 100. / battery_max,
-        selected_count,
-    );
+    let state = selected
+        .get_single()
+        .ok()
+        .and_then(|entity| states.get(entity).ok());
+
+    let mut text = String::new();
+    if let Some((&object_type, ..)) = state {
+        text.push_str(&localize.object_name(object_type));
+        text.push('\n');
+    }
+    text.push_str(&localize.format(
+        "details-battery",
+        &[
+            ("energy", &format_units(battery_total, "J")),
+            ("capacity", &format_units(battery_max, "J")),
+            (
+                "percent",
+                &format!("{:.1}", battery_total * 100. / battery_max),
+            ),
+        ],
+    ));
+    text.push('\n');
+    text.push_str(&localize.format("details-selected", &[("count", &selected_count)]));
+
+    if let Some((_, badge, construction, line)) = state {
+        if let Some(badge) = badge {
+            text.push('\n');
+            text.push_str(&localize.format(
+                "details-rank",
+                &[("rank", &localize.get(rank_id(badge.rank())))],
+            ));
+        }
+        if let Some(construction) = construction {
+            text.push('\n');
+            text.push_str(&localize.format(
+                "details-construction",
+                &[("percent", &format!("{:.0}", construction.progress() * 100.))],
+            ));
+        }
+        if let Some(line) = line {
+            if let Some(progress) = line.progress(time.elapsed()) {
+                text.push('\n');
+                text.push_str(&localize.format(
+                    "details-manufacturing",
+                    &[
+                        ("percent", &format!("{:.0}", progress * 100.)),
+                        ("queued", &line.queue_len()),
+                    ],
+                ));
+            }
+        }
+    }
 
     text_ops
         .set_text(ui.0, text)
@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use de_core::{
+    alert::{AlertEvent, AlertPriority},
+    baseset::GameSet,
+    cleanup::DespawnOnGameExit,
+    gamestate::GameState,
+};
+use de_gui::{BodyTextCommands, GuiCommands, OuterStyle};
+use de_loc::Localize;
+
+use crate::alerts::AlertsSet;
+
+/// Maximum number of alerts shown at once.
+const FEED_CAPACITY: usize = 5;
+/// For how long is an alert shown.
+const FEED_DURATION: Duration = Duration::from_secs(8);
+
+pub(super) struct FeedPlugin;
+
+impl Plugin for FeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(cleanup.in_schedule(OnExit(GameState::Playing)))
+            .add_system(
+                update
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .after(AlertsSet::Raise),
+            );
+    }
+}
+
+/// Alerts currently shown in the feed.
+#[derive(Resource)]
+struct Feed {
+    node: Entity,
+    entries: Vec<FeedEntry>,
+}
+
+impl Feed {
+    fn new(node: Entity) -> Self {
+        Self {
+            node,
+            entries: Vec::with_capacity(FEED_CAPACITY),
+        }
+    }
+
+    /// Removes and returns entries expired at time `now`.
+    fn expire(&mut self, now: Duration) -> Vec<FeedEntry> {
+        let (expired, kept) = self
+            .entries
+            .drain(..)
+            .partition(|entry| entry.expiration <= now);
+        self.entries = kept;
+        expired
+    }
+
+    /// Makes space for a new alert of the given priority. Returns false if
+    /// the feed is full of alerts of higher priority, otherwise returns true
+    /// and the entry which had to be removed (if any).
+    fn make_space(&mut self, priority: AlertPriority) -> (bool, Option<FeedEntry>) {
+        if self.entries.len() < FEED_CAPACITY {
+            return (true, None);
+        }
+
+        // The oldest of the lowest priority entries.
+        let (index, entry) = self
+            .entries
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, entry)| entry.priority)
+            .unwrap();
+        if entry.priority > priority {
+            return (false, None);
+        }
+        (true, Some(self.entries.remove(index)))
+    }
+
+    fn push(&mut self, entry: FeedEntry) {
+        self.entries.push(entry);
+    }
+}
+
+struct FeedEntry {
+    entity: Entity,
+    priority: AlertPriority,
+    expiration: Duration,
+}
+
+fn setup(mut commands: Commands) {
+    let node = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    size: Size::new(Val::Percent(25.), Val::Percent(30.)),
+                    position_type: PositionType::Absolute,
+                    position: UiRect::new(
+                        Val::Percent(1.),
+                        Val::Percent(74.),
+                        Val::Percent(5.),
+                        Val::Percent(65.),
+                    ),
+                    ..default()
+                },
+                ..default()
+            },
+            DespawnOnGameExit,
+        ))
+        .id();
+    commands.insert_resource(Feed::new(node));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Feed>();
+}
+
+fn update(
+    mut commands: GuiCommands,
+    time: Res<Time>,
+    localize: Res<Localize>,
+    mut feed: ResMut<Feed>,
+    mut events: EventReader<AlertEvent>,
+) {
+    let now = time.elapsed();
+    for entry in feed.expire(now) {
+        commands.entity(entry.entity).despawn_recursive();
+    }
+
+    for event in events.iter() {
+        let (admitted, removed) = feed.make_space(event.priority());
+        if let Some(removed) = removed {
+            commands.entity(removed.entity).despawn_recursive();
+        }
+        if !admitted {
+            continue;
+        }
+
+        let entity = commands
+            .spawn_body_text(
+                OuterStyle {
+                    size: Size::new(Val::Percent(100.), Val::Percent(18.)),
+                    margin: UiRect::bottom(Val::Percent(1.)),
+                },
+                localize.alert(event.kind()),
+            )
+            .insert(BackgroundColor(color(event.priority())))
+            .id();
+        commands.entity(feed.node).add_child(entity);
+        feed.push(FeedEntry {
+            entity,
+            priority: event.priority(),
+            expiration: now + FEED_DURATION,
+        });
+    }
+}
+
+fn color(priority: AlertPriority) -> Color {
+    match priority {
+        AlertPriority::Low => Color::rgba(0.1, 0.1, 0.1, 0.7),
+        AlertPriority::Normal => Color::rgba(0.5, 0.4, 0.05, 0.7),
+        AlertPriority::High => Color::rgba(0.6, 0.05, 0.05, 0.7),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed() {
+        let mut feed = Feed::new(Entity::from_raw(100));
+        for i in 0..FEED_CAPACITY {
+            let priority = if i == 2 {
+                AlertPriority::Low
+            } else {
+                AlertPriority::High
+            };
+            assert!(matches!(feed.make_space(priority), (true, None)));
+            feed.push(FeedEntry {
+                entity: Entity::from_raw(i as u32),
+                priority,
+                expiration: Duration::from_secs(i as u64 + 1),
+            });
+        }
+
+        // The low priority entry is replaced.
+        let (admitted, removed) = feed.make_space(AlertPriority::Normal);
+        assert!(admitted);
+        assert_eq!(removed.unwrap().entity, Entity::from_raw(2));
+        feed.push(FeedEntry {
+            entity: Entity::from_raw(10),
+            priority: AlertPriority::Normal,
+            expiration: Duration::from_secs(10),
+        });
+        assert!(matches!(feed.make_space(AlertPriority::Low), (false, None)));
+
+        let expired: Vec<Entity> = feed
+            .expire(Duration::from_secs(2))
+            .iter()
+            .map(|entry| entry.entity)
+            .collect();
+        assert_eq!(expired, vec![Entity::from_raw(0), Entity::from_raw(1)]);
+        assert_eq!(feed.entries.len(), FEED_CAPACITY - 2);
+    }
+}
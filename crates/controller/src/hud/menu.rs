@@ -1,10 +1,14 @@
-use core::fmt;
-
-use bevy::prelude::*;
-use de_core::{baseset::GameSet, gamestate::GameState, state::AppState};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_conf::GraphicsSettings;
+use de_core::{baseset::GameSet, gamestate::GameState, gresult::GameResult, state::AppState};
 use de_gui::{ButtonCommands, GuiCommands, OuterStyle};
+use de_loc::Localize;
+use de_menu::SettingsButton;
+use de_multiplayer::{NetState, ShutdownMultiplayerEvent, SurrenderEvent};
+use de_persistence::SaveGameEvent;
 
 use super::interaction::InteractionBlocker;
+use crate::pause::MenuPauseEvent;
 
 pub(crate) struct MenuPlugin;
 
@@ -22,7 +26,8 @@ impl Plugin for MenuPlugin {
             .add_system(
                 button_system
                     .in_base_set(GameSet::Input)
-                    .run_if(in_state(GameState::Playing)),
+                    .run_if(in_state(GameState::Playing))
+                    .before(GameMenuSet::Toggle),
             );
     }
 }
@@ -37,20 +42,79 @@ pub(crate) struct ToggleGameMenu;
 #[derive(Component)]
 struct PopUpMenu;
 
+/// Panels of the in-game menu. Only one of them is visible at a time.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum MenuPanel {
+    Main,
+    Settings,
+}
+
 #[derive(Component, Clone, Copy)]
 enum ButtonAction {
+    Resume,
+    Settings,
+    Surrender,
+    Save,
     Quit,
+    Back,
 }
 
-impl fmt::Display for ButtonAction {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl ButtonAction {
+    fn caption(self, localize: &Localize) -> String {
         match self {
-            Self::Quit => write!(f, "Quit Game"),
+            Self::Resume => localize.get("game-menu-resume"),
+            Self::Settings => localize.get("game-menu-settings"),
+            Self::Surrender => localize.get("game-menu-surrender"),
+            Self::Save => localize.get("game-menu-save"),
+            Self::Quit => localize.get("game-menu-quit"),
+            Self::Back => localize.get("game-menu-back"),
+        }
+    }
+}
+
+#[derive(SystemParam)]
+struct GameActions<'w, 's> {
+    commands: Commands<'w, 's>,
+    net_state: Res<'w, State<NetState>>,
+    next_state: ResMut<'w, NextState<AppState>>,
+    toggle: EventWriter<'w, ToggleGameMenu>,
+    save: EventWriter<'w, SaveGameEvent>,
+    surrender: EventWriter<'w, SurrenderEvent>,
+    shutdown: EventWriter<'w, ShutdownMultiplayerEvent>,
+}
+
+impl<'w, 's> GameActions<'w, 's> {
+    fn single_player(&self) -> bool {
+        self.net_state.0 == NetState::None
+    }
+
+    fn surrender(&mut self) {
+        if self.single_player() {
+            self.commands.insert_resource(GameResult::finished(false));
+            self.next_state.set(AppState::InMenu);
+        } else {
+            // The game is finished once the server confirms the surrender.
+            self.surrender.send(SurrenderEvent);
+            self.toggle.send(ToggleGameMenu);
+        }
+    }
+
+    fn quit(&mut self) {
+        if self.single_player() {
+            self.next_state.set(AppState::InMenu);
+        } else {
+            // The menu is entered after the multiplayer is shut down.
+            self.shutdown.send(ShutdownMultiplayerEvent);
         }
     }
 }
 
-fn setup(mut commands: GuiCommands) {
+fn setup(
+    mut commands: GuiCommands,
+    localize: Res<Localize>,
+    settings: Res<GraphicsSettings>,
+    net_state: Res<State<NetState>>,
+) {
     let root_node = commands
         .spawn(NodeBundle {
             style: Style {
@@ -68,11 +132,34 @@ fn setup(mut commands: GuiCommands) {
         .insert((PopUpMenu, InteractionBlocker))
         .id();
 
-    let menu_node = commands
+    let main_node = panel(&mut commands, root_node, MenuPanel::Main);
+    let mut actions = vec![ButtonAction::Resume, ButtonAction::Settings];
+    // Multiplayer games cannot be saved.
+    if net_state.0 == NetState::None {
+        actions.push(ButtonAction::Save);
+    }
+    actions.extend([ButtonAction::Surrender, ButtonAction::Quit]);
+    for action in actions {
+        let caption = action.caption(localize.as_ref());
+        button(&mut commands, main_node, action, caption);
+    }
+
+    let settings_node = panel(&mut commands, root_node, MenuPanel::Settings);
+    for action in SettingsButton::GRAPHICS {
+        let caption = action.caption(settings.as_ref(), localize.as_ref());
+        button(&mut commands, settings_node, action, caption);
+    }
+    let caption = ButtonAction::Back.caption(localize.as_ref());
+    button(&mut commands, settings_node, ButtonAction::Back, caption);
+}
+
+fn panel(commands: &mut GuiCommands, parent: Entity, panel: MenuPanel) -> Entity {
+    let node = commands
         .spawn(NodeBundle {
             style: Style {
+                display: panel_display(panel, MenuPanel::Main),
                 flex_direction: FlexDirection::Column,
-                size: Size::new(Val::Percent(25.), Val::Percent(50.)),
+                size: Size::new(Val::Percent(25.), Val::Percent(60.)),
                 padding: UiRect::horizontal(Val::Percent(1.)),
                 align_items: AlignItems::Center,
                 justify_content: JustifyContent::Center,
@@ -81,17 +168,25 @@ fn setup(mut commands: GuiCommands) {
             background_color: Color::BLACK.into(),
             ..default()
         })
+        .insert(panel)
         .id();
-    commands.entity(root_node).add_child(menu_node);
+    commands.entity(parent).add_child(node);
+    node
+}
 
-    button(&mut commands, menu_node, ButtonAction::Quit);
+fn panel_display(panel: MenuPanel, active: MenuPanel) -> Display {
+    if panel == active {
+        Display::Flex
+    } else {
+        Display::None
+    }
 }
 
-fn button(commands: &mut GuiCommands, parent: Entity, action: ButtonAction) {
+fn button<C: Component>(commands: &mut GuiCommands, parent: Entity, action: C, caption: String) {
     let button = commands
         .spawn_button(
             OuterStyle {
-                size: Size::new(Val::Percent(100.), Val::Percent(16.)),
+                size: Size::new(Val::Percent(100.), Val::Percent(14.)),
                 margin: UiRect::new(
                     Val::Percent(0.),
                     Val::Percent(0.),
@@ -99,7 +194,7 @@ fn button(commands: &mut GuiCommands, parent: Entity, action: ButtonAction) {
                     Val::Percent(2.),
                 ),
             },
-            format!("{action}"),
+            caption,
         )
         .insert(action)
         .id();
@@ -114,27 +209,46 @@ fn cleanup(mut commands: Commands, query: Query<Entity, With<PopUpMenu>>) {
 
 fn toggle_system(
     mut events: EventReader<ToggleGameMenu>,
-    mut query: Query<&mut Visibility, With<PopUpMenu>>,
+    mut menu: Query<&mut Visibility, With<PopUpMenu>>,
+    mut panels: Query<(&MenuPanel, &mut Style)>,
+    mut pause: EventWriter<MenuPauseEvent>,
 ) {
     if events.iter().count() % 2 == 0 {
         return;
     }
 
-    *query.single_mut() = if query.single() == Visibility::Hidden {
-        Visibility::Inherited
+    let mut visibility = menu.single_mut();
+    if *visibility == Visibility::Hidden {
+        *visibility = Visibility::Inherited;
+        pause.send(MenuPauseEvent::Pause);
     } else {
-        Visibility::Hidden
-    };
+        *visibility = Visibility::Hidden;
+        pause.send(MenuPauseEvent::Resume);
+        // The menu is always opened at the main panel.
+        switch_panel(&mut panels, MenuPanel::Main);
+    }
+}
+
+fn switch_panel(panels: &mut Query<(&MenuPanel, &mut Style)>, active: MenuPanel) {
+    for (&panel, mut style) in panels.iter_mut() {
+        style.display = panel_display(panel, active);
+    }
 }
 
 fn button_system(
-    mut next_state: ResMut<NextState<AppState>>,
+    mut actions: GameActions,
+    mut panels: Query<(&MenuPanel, &mut Style)>,
     interactions: Query<(&Interaction, &ButtonAction), Changed<Interaction>>,
 ) {
     for (&interaction, &action) in interactions.iter() {
         if let Interaction::Clicked = interaction {
             match action {
-                ButtonAction::Quit => next_state.set(AppState::InMenu),
+                ButtonAction::Resume => actions.toggle.send(ToggleGameMenu),
+                ButtonAction::Settings => switch_panel(&mut panels, MenuPanel::Settings),
+                ButtonAction::Surrender => actions.surrender(),
+                ButtonAction::Save => actions.save.send(SaveGameEvent),
+                ButtonAction::Quit => actions.quit(),
+                ButtonAction::Back => switch_panel(&mut panels, MenuPanel::Main),
             }
         }
     }
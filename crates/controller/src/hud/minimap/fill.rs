@@ -1,7 +1,8 @@
 use bevy::{ecs::system::SystemParam, prelude::*};
+use de_combat::Concealed;
 use de_core::{
-    baseset::GameSet, gamestate::GameState, gconfig::GameConfig, objects::ObjectType,
-    player::Player, projection::ToFlat,
+    baseset::GameSet, diplomacy::Diplomacy, gamestate::GameState, gconfig::GameConfig,
+    objects::ObjectType, player::Player, projection::ToFlat,
 };
 use de_map::size::MapBounds;
 use de_objects::SolidObjects;
@@ -17,6 +18,7 @@ use crate::ray::ScreenRay;
 
 const TERRAIN_COLOR: Color = Color::rgb(0.61, 0.46, 0.32);
 const PLAYER_COLOR: Color = Color::rgb(0.1, 0.1, 0.9);
+const ALLY_COLOR: Color = Color::rgb(0.1, 0.8, 0.1);
 const ENEMY_COLOR: Color = Color::rgb(0.9, 0.1, 0.1);
 const MIN_ENTITY_SIZE: Vec2 = Vec2::splat(0.02);
 const CAMERA_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
@@ -48,26 +50,26 @@ impl Plugin for FillPlugin {
 }
 
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
-enum FillSet {
+pub(super) enum FillSet {
     Clear,
     DrawEntities,
 }
 
 #[derive(SystemParam)]
-struct UiCoords<'w> {
+pub(super) struct UiCoords<'w> {
     bounds: Res<'w, MapBounds>,
 }
 
 impl<'w> UiCoords<'w> {
     /// Transforms 2D flat position (in meters from origin) to relative UI
     /// position (from 0 to 1 from top-right corner).
-    fn flat_to_rel(&self, point: Vec2) -> Vec2 {
+    pub(super) fn flat_to_rel(&self, point: Vec2) -> Vec2 {
         Vec2::new(point.x - self.bounds.min().x, self.bounds.max().y - point.y) / self.bounds.size()
     }
 
     /// Transforms 2D flat position (in meters from origin) to relative UI
     /// position (from 0 to 1 from top-right corner).
-    fn size_to_rel(&self, size: Vec2) -> Vec2 {
+    pub(super) fn size_to_rel(&self, size: Vec2) -> Vec2 {
         size / self.bounds.size()
     }
 }
@@ -82,7 +84,8 @@ fn draw_entities_system(
     ui_coords: UiCoords,
     solids: SolidObjects,
     game: Res<GameConfig>,
-    entities: Query<(&Transform, &Player, &ObjectType)>,
+    diplomacy: Res<Diplomacy>,
+    entities: Query<(&Transform, &Player, &ObjectType), Without<Concealed>>,
 ) {
     let mut drawing = drawing.drawing();
 
@@ -90,6 +93,8 @@ fn draw_entities_system(
         let minimap_position = ui_coords.flat_to_rel(transform.translation.to_flat());
         let color = if game.locals().is_playable(player) {
             PLAYER_COLOR
+        } else if diplomacy.are_allies(game.locals().playable(), player) {
+            ALLY_COLOR
         } else {
             ENEMY_COLOR
         };
@@ -6,7 +6,8 @@ use bevy::{
     window::PrimaryWindow,
 };
 use de_camera::MoveFocusEvent;
-use de_core::{baseset::GameSet, gamestate::GameState};
+use de_construction::DeliveryTarget;
+use de_core::{baseset::GameSet, gamestate::GameState, gconfig::GameConfig, ping::MapPingEvent};
 use de_map::size::MapBounds;
 
 use super::nodes::MinimapNode;
@@ -27,7 +28,12 @@ impl Plugin for InteractionPlugin {
                     .in_set(InteractionSet::ClickHandler),
             )
             .add_system(
-                move_camera_system
+                drag_camera_system
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                ping_system
                     .in_base_set(GameSet::Input)
                     .run_if(in_state(GameState::Playing))
                     .after(InteractionSet::ClickHandler),
@@ -105,15 +111,50 @@ fn click_handler(
     }
 }
 
-fn move_camera_system(
-    mut click_events: EventReader<MinimapClickEvent>,
+/// Moves the camera focus to the cursor while the left mouse button is held
+/// down after it was pressed over the minimap.
+fn drag_camera_system(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    buttons: Res<Input<MouseButton>>,
+    hud: HudNodes<With<MinimapNode>>,
+    bounds: Res<MapBounds>,
+    mut dragging: Local<bool>,
     mut camera_events: EventWriter<MoveFocusEvent>,
+) {
+    let relative = window_query
+        .single()
+        .cursor_position()
+        .and_then(|cursor| hud.relative_position(cursor));
+
+    if buttons.just_pressed(MouseButton::Left) {
+        *dragging = relative.is_some();
+    }
+    if !buttons.pressed(MouseButton::Left) {
+        *dragging = false;
+    }
+    if !*dragging {
+        return;
+    }
+
+    if let Some(mut relative) = relative {
+        relative.y = 1. - relative.y;
+        camera_events.send(MoveFocusEvent::new(bounds.rel_to_abs(relative)));
+    }
+}
+
+fn ping_system(
+    game: Res<GameConfig>,
+    mut click_events: EventReader<MinimapClickEvent>,
+    mut ping_events: EventWriter<MapPingEvent>,
 ) {
     for click in click_events.iter() {
-        if click.button() != MouseButton::Left {
+        if click.button() != MouseButton::Middle {
             continue;
         }
-        camera_events.send(MoveFocusEvent::new(click.position()));
+        ping_events.send(MapPingEvent::new(
+            game.locals().playable(),
+            click.position(),
+        ));
     }
 }
 
@@ -137,6 +178,8 @@ fn delivery_location_system(
         if click.button() != MouseButton::Right {
             continue;
         }
-        location_events.send(DeliveryLocationSelectedEvent::new(click.position()));
+        location_events.send(DeliveryLocationSelectedEvent::new(
+            DeliveryTarget::Location(click.position()),
+        ));
     }
 }
@@ -1,11 +1,14 @@
 use bevy::prelude::*;
 
-use self::{fill::FillPlugin, interaction::InteractionPlugin, nodes::NodesPlugin};
+use self::{
+    fill::FillPlugin, interaction::InteractionPlugin, nodes::NodesPlugin, pings::PingsPlugin,
+};
 
 mod draw;
 mod fill;
 mod interaction;
 mod nodes;
+mod pings;
 
 pub(crate) struct MinimapPlugin;
 
@@ -13,6 +16,7 @@ impl Plugin for MinimapPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(NodesPlugin)
             .add_plugin(FillPlugin)
-            .add_plugin(InteractionPlugin);
+            .add_plugin(InteractionPlugin)
+            .add_plugin(PingsPlugin);
     }
 }
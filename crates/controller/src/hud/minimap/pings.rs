@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use de_core::{
+    alert::AlertEvent, baseset::GameSet, diplomacy::Diplomacy, gamestate::GameState,
+    gconfig::GameConfig, ping::MapPingEvent,
+};
+
+use super::{
+    draw::DrawingParam,
+    fill::{FillSet, UiCoords},
+};
+use crate::alerts::AlertsSet;
+
+/// For how long is a ping shown on the minimap.
+const PING_DURATION: Duration = Duration::from_secs(5);
+/// Duration of a single pulse of a ping marker.
+const PULSE_DURATION: f32 = 1.;
+/// Maximum size of a ping marker relative to the minimap size.
+const PING_SIZE: f32 = 0.1;
+const PING_COLOR: Color = Color::rgb(0.95, 0.85, 0.1);
+
+pub(super) struct PingsPlugin;
+
+impl Plugin for PingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(cleanup.in_schedule(OnExit(GameState::Playing)))
+            .add_system(
+                update_system
+                    .in_base_set(GameSet::PostMovement)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(PingsSet::Update)
+                    .after(AlertsSet::Raise),
+            )
+            .add_system(
+                draw_pings_system
+                    .in_base_set(GameSet::PostMovement)
+                    .run_if(in_state(GameState::Playing))
+                    .after(PingsSet::Update)
+                    .after(FillSet::DrawEntities),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+enum PingsSet {
+    Update,
+}
+
+/// Pings currently shown on the minimap.
+#[derive(Resource, Default)]
+struct Pings(Vec<Ping>);
+
+impl Pings {
+    fn push(&mut self, position: Vec2, time: Duration) {
+        self.0.push(Ping { position, time });
+    }
+
+    /// Removes all pings placed more than [`PING_DURATION`] before `now`.
+    fn expire(&mut self, now: Duration) {
+        self.0
+            .retain(|ping| now.saturating_sub(ping.time) < PING_DURATION);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Ping> {
+        self.0.iter()
+    }
+}
+
+struct Ping {
+    /// Position in 2D flat coordinates.
+    position: Vec2,
+    /// Time (since app startup) at which the ping was placed.
+    time: Duration,
+}
+
+impl Ping {
+    /// Relative size of the pulsing marker at time `now`.
+    fn size(&self, now: Duration) -> f32 {
+        let age = now.saturating_sub(self.time).as_secs_f32();
+        PING_SIZE * (1. - (age % PULSE_DURATION) / PULSE_DURATION)
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Pings>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Pings>();
+}
+
+fn update_system(
+    time: Res<Time>,
+    game: Res<GameConfig>,
+    diplomacy: Res<Diplomacy>,
+    mut pings: ResMut<Pings>,
+    mut events: EventReader<MapPingEvent>,
+    mut alerts: EventReader<AlertEvent>,
+) {
+    let now = time.elapsed();
+    pings.expire(now);
+
+    for event in events.iter() {
+        if diplomacy.are_allies(game.locals().playable(), event.player()) {
+            pings.push(event.position(), now);
+        }
+    }
+    for position in alerts.iter().filter_map(|alert| alert.position()) {
+        pings.push(position, now);
+    }
+}
+
+fn draw_pings_system(
+    mut drawing: DrawingParam,
+    ui_coords: UiCoords,
+    time: Res<Time>,
+    pings: Res<Pings>,
+) {
+    let mut drawing = drawing.drawing();
+    let now = time.elapsed();
+
+    for ping in pings.iter() {
+        let center = ui_coords.flat_to_rel(ping.position);
+        let half_size = Vec2::splat(0.5 * ping.size(now));
+
+        // Clamping keeps the outline axis aligned, thus it is still a
+        // (possibly cropped) rectangle.
+        let min = (center - half_size).clamp(Vec2::ZERO, Vec2::ONE);
+        let max = (center + half_size).clamp(Vec2::ZERO, Vec2::ONE);
+        let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+        for i in 0..corners.len() {
+            drawing.line(corners[i], corners[(i + 1) % corners.len()], PING_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pings() {
+        let mut pings = Pings::default();
+        pings.push(Vec2::new(1., 2.), Duration::from_secs(1));
+        pings.push(Vec2::new(3., 4.), Duration::from_secs(4));
+
+        let ping = pings.iter().next().unwrap();
+        assert_eq!(ping.size(Duration::from_secs(1)), PING_SIZE);
+        assert!(ping.size(Duration::from_millis(1500)) < PING_SIZE);
+
+        pings.expire(Duration::from_secs(7));
+        let positions: Vec<Vec2> = pings.iter().map(|ping| ping.position).collect();
+        assert_eq!(positions, vec![Vec2::new(3., 4.)]);
+    }
+}
@@ -1,10 +1,12 @@
 use bevy::prelude::*;
 
-mod actionbar;
+mod card;
 mod details;
+mod feed;
 mod interaction;
 mod menu;
 mod minimap;
+mod overlay;
 mod selection;
 
 pub(crate) use interaction::HudNodes;
@@ -12,8 +14,8 @@ pub(crate) use menu::{GameMenuSet, ToggleGameMenu};
 pub(crate) use selection::UpdateSelectionBoxEvent;
 
 use self::{
-    actionbar::ActionBarPlugin, details::DetailsPlugin, menu::MenuPlugin, minimap::MinimapPlugin,
-    selection::SelectionPlugin,
+    card::CommandCardPlugin, details::DetailsPlugin, feed::FeedPlugin, menu::MenuPlugin,
+    minimap::MinimapPlugin, overlay::OverlayPlugin, selection::SelectionPlugin,
 };
 
 const HUD_COLOR: Color = Color::BLACK;
@@ -24,8 +26,10 @@ impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(SelectionPlugin)
             .add_plugin(DetailsPlugin)
-            .add_plugin(ActionBarPlugin)
+            .add_plugin(CommandCardPlugin)
             .add_plugin(MenuPlugin)
-            .add_plugin(MinimapPlugin);
+            .add_plugin(MinimapPlugin)
+            .add_plugin(FeedPlugin)
+            .add_plugin(OverlayPlugin);
     }
 }
@@ -0,0 +1,366 @@
+//! Screen space overlay with health and shield bars and status icons
+//! displayed above selected, damaged and under-construction objects.
+//!
+//! The overlay is composed of reusable UI nodes (plates). Each frame, every
+//! displayed object is assigned one of the plates and the remaining plates
+//! are hidden. Plates are not despawned during the game and only their
+//! styles and colors are updated (and only when they change), thus all bars
+//! are drawn in a single UI batch even with hundreds of displayed objects.
+
+use bevy::prelude::*;
+use de_camera::{CameraDistance, DistanceSet};
+use de_combat::{Rank, RankBadge};
+use de_construction::UnderConstruction;
+use de_core::{
+    baseset::GameSet, cleanup::DespawnOnGameExit, gamestate::GameState, objects::ObjectType,
+    screengeom::ScreenRect,
+};
+use de_objects::{Health, Shield, SolidObjects};
+
+use crate::selection::Selected;
+
+/// Overlays of objects further than this from the camera are not displayed.
+const MAX_DISTANCE: f32 = 140.;
+/// Maximum number of simultaneously displayed plates.
+const MAX_PLATES: usize = 512;
+/// Maximum number of status icons of a single plate.
+const MAX_ICONS: usize = 4;
+/// Vertical distance in meters between the plate bottom and the top of the
+/// object collider.
+const PLATE_ELEVATION: f32 = 1.;
+/// Plate width in logical pixels.
+const PLATE_WIDTH: f32 = 40.;
+/// Bar height in logical pixels.
+const BAR_HEIGHT: f32 = 4.;
+/// Icon size in logical pixels.
+const ICON_SIZE: f32 = 6.;
+/// Space between bars and icons in logical pixels.
+const GAP: f32 = 1.;
+const BAR_BACKGROUND_COLOR: Color = Color::rgba(0., 0., 0., 0.6);
+const SHIELD_COLOR: Color = Color::rgb(0.3, 0.6, 1.);
+const CONSTRUCTION_COLOR: Color = Color::rgb(1., 0.6, 0.);
+const RANK_COLOR: Color = Color::rgb(1., 0.85, 0.2);
+
+pub(crate) struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(cleanup.in_schedule(OnExit(GameState::Playing)))
+            .add_system(
+                update
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .after(DistanceSet::Update),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct Plates {
+    root: Entity,
+    plates: Vec<Plate>,
+}
+
+impl Plates {
+    /// Spawns a new hidden plate. Returns false if the maximum number of
+    /// plates has been reached.
+    fn grow(&mut self, commands: &mut Commands) -> bool {
+        if self.plates.len() >= MAX_PLATES {
+            return false;
+        }
+        self.plates.push(Plate::spawn(commands, self.root));
+        true
+    }
+}
+
+/// UI nodes of overlay of a single object.
+struct Plate {
+    node: Entity,
+    health: Entity,
+    shield_bar: Entity,
+    shield: Entity,
+    icons: [Entity; MAX_ICONS],
+}
+
+impl Plate {
+    fn spawn(commands: &mut Commands, root: Entity) -> Self {
+        let icons = [(); MAX_ICONS].map(|_| {
+            commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::None,
+                        size: Size::all(Val::Px(ICON_SIZE)),
+                        margin: UiRect::new(Val::Px(0.), Val::Px(GAP), Val::Px(0.), Val::Px(GAP)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .id()
+        });
+        let icon_row = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            })
+            .push_children(&icons)
+            .id();
+
+        let shield = commands.spawn(fill_bundle(SHIELD_COLOR)).id();
+        let shield_bar = commands.spawn(bar_bundle()).add_child(shield).id();
+        let health = commands.spawn(fill_bundle(health_color(1.))).id();
+        let health_bar = commands.spawn(bar_bundle()).add_child(health).id();
+
+        let node = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Column,
+                    size: Size::width(Val::Px(PLATE_WIDTH)),
+                    ..default()
+                },
+                ..default()
+            })
+            .push_children(&[icon_row, shield_bar, health_bar])
+            .id();
+        commands.entity(root).add_child(node);
+
+        Self {
+            node,
+            health,
+            shield_bar,
+            shield,
+            icons,
+        }
+    }
+}
+
+fn bar_bundle() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.), Val::Px(BAR_HEIGHT)),
+            margin: UiRect::top(Val::Px(GAP)),
+            ..default()
+        },
+        background_color: BAR_BACKGROUND_COLOR.into(),
+        ..default()
+    }
+}
+
+fn fill_bundle(color: Color) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::height(Val::Percent(100.)),
+            ..default()
+        },
+        background_color: color.into(),
+        ..default()
+    }
+}
+
+/// Returns color of a health bar which gradually changes from green (full
+/// health) to red (no health).
+fn health_color(fraction: f32) -> Color {
+    Color::rgb(1. - fraction, fraction, 0.)
+}
+
+/// Returns colors of status icons of an object.
+fn icons(construction: bool, rank: Option<Rank>) -> impl Iterator<Item = Color> {
+    // One pip per rank above recruit.
+    let pips = rank.map_or(0, |rank| rank as usize);
+    construction
+        .then_some(CONSTRUCTION_COLOR)
+        .into_iter()
+        .chain((0..pips).map(|_| RANK_COLOR))
+}
+
+/// Updates a style without triggering change detection (and thus UI
+/// re-layout) if the style does not change.
+fn update_style(styles: &mut Query<&mut Style>, entity: Entity, update: impl FnOnce(&mut Style)) {
+    let mut style = styles.get_mut(entity).unwrap();
+    let mut new = style.clone();
+    update(&mut new);
+    style.set_if_neq(new);
+}
+
+fn update_color(colors: &mut Query<&mut BackgroundColor>, entity: Entity, color: Color) {
+    let mut background = colors.get_mut(entity).unwrap();
+    if background.0 != color {
+        background.0 = color;
+    }
+}
+
+fn display(visible: bool) -> Display {
+    if visible {
+        Display::Flex
+    } else {
+        Display::None
+    }
+}
+
+fn setup(mut commands: Commands) {
+    let root = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::all(Val::Percent(100.)),
+                    ..default()
+                },
+                // Keep the overlay below all other HUD nodes.
+                z_index: ZIndex::Global(-1),
+                ..default()
+            },
+            DespawnOnGameExit,
+        ))
+        .id();
+
+    commands.insert_resource(Plates {
+        root,
+        plates: Vec::new(),
+    });
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Plates>();
+}
+
+type Overlaid = (
+    &'static Transform,
+    &'static ObjectType,
+    &'static ComputedVisibility,
+    &'static CameraDistance,
+    &'static Health,
+    Option<&'static Shield>,
+    Option<&'static RankBadge>,
+    Option<&'static UnderConstruction>,
+    Option<&'static Selected>,
+);
+
+fn update(
+    mut commands: Commands,
+    mut plates: ResMut<Plates>,
+    solids: SolidObjects,
+    camera: Query<(&Camera, &Transform), With<Camera3d>>,
+    objects: Query<Overlaid>,
+    mut styles: Query<&mut Style>,
+    mut colors: Query<&mut BackgroundColor>,
+) {
+    let Ok((camera, &camera_transform)) = camera.get_single() else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    // Camera transform is used directly (the camera has no parent) so that
+    // the overlay does not lag behind camera movement by a frame.
+    let camera_transform = GlobalTransform::from(camera_transform);
+
+    let mut used = 0;
+    for (
+        transform,
+        &object_type,
+        visibility,
+        distance,
+        health,
+        shield,
+        badge,
+        construction,
+        selected,
+    ) in objects.iter()
+    {
+        if !visibility.is_visible_in_hierarchy() || distance.distance() > MAX_DISTANCE {
+            continue;
+        }
+
+        let damaged = health.fraction() < 1. || matches!(shield, Some(s) if s.fraction() < 1.);
+        if selected.is_none() && !damaged && construction.is_none() {
+            continue;
+        }
+
+        let height = solids.get(object_type).collider().aabb().maxs.y + PLATE_ELEVATION;
+        let Some(ndc) = camera.world_to_ndc(
+            &camera_transform,
+            transform.transform_point(height * Vec3::Y),
+        ) else {
+            continue;
+        };
+        if ndc.z <= 0. || !ScreenRect::full().contains(ndc.truncate()) {
+            continue;
+        }
+
+        if used == plates.plates.len() {
+            if !plates.grow(&mut commands) {
+                break;
+            }
+            // The plate is spawned by the commands, it is displayed since
+            // the next frame.
+            used += 1;
+            continue;
+        }
+        let plate = &plates.plates[used];
+        used += 1;
+
+        let position = (0.5 * (ndc.truncate() + Vec2::ONE) * viewport).round();
+        update_style(&mut styles, plate.node, |style| {
+            style.display = Display::Flex;
+            style.position = UiRect {
+                left: Val::Px(position.x - 0.5 * PLATE_WIDTH),
+                bottom: Val::Px(position.y),
+                ..default()
+            };
+        });
+
+        let fraction = health.fraction();
+        update_style(&mut styles, plate.health, |style| {
+            style.size.width = Val::Percent(100. * fraction);
+        });
+        update_color(&mut colors, plate.health, health_color(fraction));
+
+        update_style(&mut styles, plate.shield_bar, |style| {
+            style.display = display(shield.is_some());
+        });
+        if let Some(shield) = shield {
+            update_style(&mut styles, plate.shield, |style| {
+                style.size.width = Val::Percent(100. * shield.fraction());
+            });
+        }
+
+        let mut icons = icons(construction.is_some(), badge.map(|badge| badge.rank()));
+        for &icon in plate.icons.iter() {
+            let color = icons.next();
+            update_style(&mut styles, icon, |style| {
+                style.display = display(color.is_some());
+            });
+            if let Some(color) = color {
+                update_color(&mut colors, icon, color);
+            }
+        }
+    }
+
+    for plate in &plates.plates[used.min(plates.plates.len())..] {
+        update_style(&mut styles, plate.node, |style| {
+            style.display = Display::None;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icons() {
+        assert_eq!(icons(false, None).count(), 0);
+        assert_eq!(icons(false, Some(Rank::Recruit)).count(), 0);
+        assert_eq!(
+            icons(true, Some(Rank::Elite)).collect::<Vec<_>>(),
+            vec![CONSTRUCTION_COLOR, RANK_COLOR, RANK_COLOR]
+        );
+        assert_eq!(icons(true, Some(Rank::Hero)).count(), MAX_ICONS);
+    }
+}
@@ -1,17 +1,23 @@
 //! This crate implements handling of user input.
 
+use alerts::AlertsPlugin;
 use bevy::{app::PluginGroupBuilder, prelude::*};
 use commands::CommandsPlugin;
 use draft::DraftPlugin;
 use hud::HudPlugin;
 use mouse::MousePlugin;
+use orders::OrdersPlugin;
+use pause::PausePlugin;
 use selection::SelectionPlugin;
 
+mod alerts;
 mod commands;
 mod draft;
 mod frustum;
 mod hud;
 mod mouse;
+mod orders;
+mod pause;
 mod ray;
 mod selection;
 
@@ -24,9 +30,12 @@ impl PluginGroup for ControllerPluginGroup {
     fn build(self) -> PluginGroupBuilder {
         PluginGroupBuilder::start::<Self>()
             .add(MousePlugin)
+            .add(AlertsPlugin)
             .add(CommandsPlugin)
             .add(SelectionPlugin)
             .add(DraftPlugin)
             .add(HudPlugin)
+            .add(OrdersPlugin)
+            .add(PausePlugin)
     }
 }
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use de_combat::Concealed;
 use de_core::{baseset::GameSet, gamestate::GameState, state::AppState};
 use de_index::SpatialQuery;
 use de_signs::UpdateBarVisibilityEvent;
@@ -78,7 +79,7 @@ fn pointer_update_system(
     mut resource: ResMut<Pointer>,
     mouse: Res<MousePosition>,
     screen_ray: ScreenRay,
-    entities: SpatialQuery<()>,
+    entities: SpatialQuery<(), Without<Concealed>>,
     terrain: TerrainCollider,
 ) {
     let ray = mouse.ndc().map(|cursor| screen_ray.ray(cursor));
@@ -0,0 +1,312 @@
+//! Visual feedback of orders given to selected units.
+//!
+//! Lines along the paths of newly given orders and markers at their
+//! destinations are briefly displayed and they fade out over time. Orders
+//! already queued for a unit are included so that the player sees the full
+//! route of the unit.
+
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::{
+    ecs::system::SystemParam,
+    pbr::{NotShadowCaster, NotShadowReceiver},
+    prelude::*,
+};
+use de_behaviour::{EnqueueCommandEvent, OrderQueue};
+use de_combat::AttackMoveEvent;
+use de_core::{
+    baseset::GameSet,
+    cleanup::DespawnOnGameExit,
+    gamestate::GameState,
+    objects::MovableSolid,
+    projection::{ToAltitude, ToFlat},
+    state::AppState,
+};
+
+use crate::{
+    commands::{CommandsSet, GroupAttackEvent},
+    selection::Selected,
+};
+
+const SIGN_LIFESPAN: Duration = Duration::from_millis(1500);
+const LINE_WIDTH: f32 = 0.3;
+const MARKER_RADIUS: f32 = 1.;
+/// Offset above mean sea level of the signs, stopping z-fighting with the
+/// floor.
+const ELEVATION: f32 = 0.01;
+
+pub(crate) struct OrdersPlugin;
+
+impl Plugin for OrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                movement
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .after(CommandsSet::SendSelected),
+            )
+            .add_system(
+                attack_move
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .after(CommandsSet::SendSelected),
+            )
+            .add_system(
+                attack
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .after(CommandsSet::Attack),
+            )
+            .add_system(
+                fade.in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum OrderKind {
+    Move,
+    Attack,
+    Patrol,
+}
+
+impl OrderKind {
+    fn color(self) -> Color {
+        match self {
+            Self::Move => Color::rgba(0.2, 0.9, 0.3, 0.8),
+            Self::Attack => Color::rgba(0.95, 0.2, 0.15, 0.8),
+            Self::Patrol => Color::rgba(0.2, 0.6, 1., 0.8),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct SignMeshes {
+    line: Handle<Mesh>,
+    marker: Handle<Mesh>,
+}
+
+/// A fading line segment or a destination marker. All signs of a single order
+/// share a material.
+#[derive(Component)]
+struct OrderSign {
+    age: Duration,
+    color: Color,
+    material: Handle<StandardMaterial>,
+}
+
+#[derive(SystemParam)]
+struct Painter<'w, 's> {
+    commands: Commands<'w, 's>,
+    meshes: Res<'w, SignMeshes>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+}
+
+impl<'w, 's> Painter<'w, 's> {
+    /// Paints a path starting at `path[0]` (the position of a unit) with a
+    /// marker at each of the other points.
+    fn paint(&mut self, kind: OrderKind, path: &[Vec2]) {
+        if path.len() < 2 {
+            return;
+        }
+
+        let color = kind.color();
+        let material = self.materials.add(StandardMaterial {
+            base_color: color,
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+
+        for segment in path.windows(2) {
+            let mesh = self.meshes.line.clone();
+            self.spawn(
+                mesh,
+                material.clone(),
+                color,
+                segment_transform(segment[0], segment[1]),
+            );
+        }
+        for &point in &path[1..] {
+            let mesh = self.meshes.marker.clone();
+            self.spawn(mesh, material.clone(), color, marker_transform(point));
+        }
+    }
+
+    fn spawn(
+        &mut self,
+        mesh: Handle<Mesh>,
+        material: Handle<StandardMaterial>,
+        color: Color,
+        transform: Transform,
+    ) {
+        self.commands.spawn((
+            PbrBundle {
+                mesh,
+                material: material.clone(),
+                transform,
+                ..default()
+            },
+            OrderSign {
+                age: Duration::ZERO,
+                color,
+                material,
+            },
+            NotShadowCaster,
+            NotShadowReceiver,
+            DespawnOnGameExit,
+        ));
+    }
+}
+
+fn setup(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+    let line = meshes.add(
+        shape::Plane {
+            size: 1.,
+            subdivisions: 0,
+        }
+        .into(),
+    );
+    let marker = meshes.add(shape::Circle::new(1.).into());
+    commands.insert_resource(SignMeshes { line, marker });
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<SignMeshes>();
+}
+
+fn movement(
+    mut painter: Painter,
+    mut events: EventReader<EnqueueCommandEvent>,
+    units: Query<(&Transform, Option<&OrderQueue>), With<Selected>>,
+) {
+    for event in events.iter() {
+        let command = event.command();
+        let Ok((transform, queue)) = units.get(command.entity()) else {
+            continue;
+        };
+
+        let mut path = vec![transform.translation.to_flat()];
+        if command.queued() {
+            if let Some(queue) = queue {
+                path.extend(queue.waypoints());
+            }
+        }
+        let start = *path.last().unwrap();
+        path.extend_from_slice(command.waypoints());
+
+        let kind = if command.patrol() {
+            path.push(start);
+            OrderKind::Patrol
+        } else {
+            OrderKind::Move
+        };
+        painter.paint(kind, &path);
+    }
+}
+
+fn attack_move(
+    mut painter: Painter,
+    mut events: EventReader<AttackMoveEvent>,
+    units: Query<&Transform, With<Selected>>,
+) {
+    for event in events.iter() {
+        let Ok(transform) = units.get(event.entity()) else {
+            continue;
+        };
+        painter.paint(
+            OrderKind::Attack,
+            &[transform.translation.to_flat(), event.target()],
+        );
+    }
+}
+
+fn attack(
+    mut painter: Painter,
+    mut events: EventReader<GroupAttackEvent>,
+    units: Query<&Transform, (With<Selected>, With<MovableSolid>)>,
+    targets: Query<&Transform>,
+) {
+    let Some(event) = events.iter().last() else {
+        return;
+    };
+    let Ok(target) = targets.get(event.target()) else {
+        return;
+    };
+    let target = target.translation.to_flat();
+
+    for transform in units.iter() {
+        painter.paint(
+            OrderKind::Attack,
+            &[transform.translation.to_flat(), target],
+        );
+    }
+}
+
+fn fade(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut signs: Query<(Entity, &mut OrderSign)>,
+) {
+    for (entity, mut sign) in signs.iter_mut() {
+        sign.age += time.delta();
+        if sign.age >= SIGN_LIFESPAN {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        if let Some(material) = materials.get_mut(&sign.material) {
+            let remaining = 1. - sign.age.as_secs_f32() / SIGN_LIFESPAN.as_secs_f32();
+            material.base_color = sign.color.with_a(sign.color.a() * remaining);
+        }
+    }
+}
+
+/// Returns transform of a unit plane (in XZ) which becomes a line from `start`
+/// to `end`.
+fn segment_transform(start: Vec2, end: Vec2) -> Transform {
+    let direction = end - start;
+    Transform {
+        translation: (start + 0.5 * direction).to_altitude(ELEVATION),
+        rotation: Quat::from_rotation_y(direction.y.atan2(direction.x)),
+        scale: Vec3::new(direction.length(), 1., LINE_WIDTH),
+    }
+}
+
+/// Returns transform of a unit circle (in XY) which becomes a flat marker at
+/// `point`.
+fn marker_transform(point: Vec2) -> Transform {
+    Transform {
+        translation: point.to_altitude(ELEVATION),
+        rotation: Quat::from_rotation_x(-FRAC_PI_2),
+        scale: Vec3::splat(MARKER_RADIUS),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_transform() {
+        let start = Vec2::new(10., -5.);
+        let end = Vec2::new(13., -1.);
+        let transform = segment_transform(start, end);
+
+        let from = transform.transform_point(Vec3::new(-0.5, 0., 0.));
+        let to = transform.transform_point(Vec3::new(0.5, 0., 0.));
+        assert!(from.distance(start.to_altitude(ELEVATION)) < 1e-4);
+        assert!(to.distance(end.to_altitude(ELEVATION)) < 1e-4);
+
+        let side = transform.transform_point(Vec3::new(0., 0., 0.5));
+        assert!((side.distance(transform.translation) - 0.5 * LINE_WIDTH).abs() < 1e-4);
+        assert!((side.y - ELEVATION).abs() < 1e-4);
+
+        let marker = marker_transform(end).transform_point(Vec3::new(0., 1., 0.));
+        assert!((marker.y - ELEVATION).abs() < 1e-4);
+    }
+}
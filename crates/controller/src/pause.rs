@@ -0,0 +1,158 @@
+//! Pausing of the game by the local player.
+//!
+//! Single player games are paused by pausing of the game time. Multiplayer
+//! games are paused via the pause protocol of the multiplayer crate, see
+//! [`PauseRequestEvent`].
+//!
+//! The game is paused while the in-game menu is open, see
+//! [`MenuPauseEvent`]. Closing of the menu resumes the game only if it was
+//! paused by opening of the menu.
+
+use bevy::prelude::*;
+use de_conf::Action;
+use de_core::{baseset::GameSet, gamestate::GameState, state::AppState};
+use de_multiplayer::{GamePausedEvent, GameResumedEvent, NetState, PauseRequestEvent};
+
+use crate::{commands::on_action, hud::GameMenuSet};
+
+pub(crate) struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MenuPauseEvent>()
+            .add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                track_multiplayer
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(resource_exists::<MultiplayerPause>()),
+            )
+            .add_system(
+                toggle_single_player
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(in_state(NetState::None))
+                    .run_if(on_action(Action::Pause)),
+            )
+            .add_system(
+                toggle_multiplayer
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_exists::<MultiplayerPause>())
+                    .run_if(not(in_state(NetState::None)))
+                    .run_if(on_action(Action::Pause)),
+            )
+            .add_system(
+                menu_pause
+                    .in_base_set(GameSet::Input)
+                    .run_if(resource_exists::<MenuPause>())
+                    .after(GameMenuSet::Toggle),
+            );
+    }
+}
+
+/// Send this event when the in-game menu is opened (`Pause`) or closed
+/// (`Resume`).
+pub(crate) enum MenuPauseEvent {
+    Pause,
+    Resume,
+}
+
+/// Whether a multiplayer game is paused (by any of the players).
+#[derive(Resource, Default)]
+struct MultiplayerPause(bool);
+
+/// Whether the game is paused due to the opened in-game menu.
+#[derive(Resource, Default)]
+struct MenuPause(bool);
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<MultiplayerPause>();
+    commands.init_resource::<MenuPause>();
+}
+
+fn cleanup(mut commands: Commands, mut time: ResMut<Time>) {
+    commands.remove_resource::<MultiplayerPause>();
+    commands.remove_resource::<MenuPause>();
+    // The game might have been left while paused.
+    time.unpause();
+}
+
+fn track_multiplayer(
+    mut paused_events: EventReader<GamePausedEvent>,
+    mut resumed_events: EventReader<GameResumedEvent>,
+    mut pause: ResMut<MultiplayerPause>,
+) {
+    if paused_events.iter().count() > 0 {
+        pause.0 = true;
+    }
+    if resumed_events.iter().count() > 0 {
+        pause.0 = false;
+    }
+}
+
+fn toggle_single_player(mut time: ResMut<Time>) {
+    if time.is_paused() {
+        info!("Resuming the game.");
+        time.unpause();
+    } else {
+        info!("Pausing the game.");
+        time.pause();
+    }
+}
+
+fn toggle_multiplayer(pause: Res<MultiplayerPause>, mut events: EventWriter<PauseRequestEvent>) {
+    events.send(if pause.0 {
+        PauseRequestEvent::Resume
+    } else {
+        PauseRequestEvent::Pause
+    });
+}
+
+fn menu_pause(
+    net_state: Res<State<NetState>>,
+    multiplayer: Res<MultiplayerPause>,
+    mut menu: ResMut<MenuPause>,
+    mut time: ResMut<Time>,
+    mut events: EventReader<MenuPauseEvent>,
+    mut requests: EventWriter<PauseRequestEvent>,
+) {
+    let single_player = net_state.0 == NetState::None;
+
+    for event in events.iter() {
+        match event {
+            MenuPauseEvent::Pause => {
+                let paused = if single_player {
+                    time.is_paused()
+                } else {
+                    multiplayer.0
+                };
+                // Somebody else's pause must not be resumed by closing of the
+                // menu.
+                if menu.0 || paused {
+                    continue;
+                }
+
+                menu.0 = true;
+                info!("Pausing the game due to the in-game menu.");
+                if single_player {
+                    time.pause();
+                } else {
+                    requests.send(PauseRequestEvent::Pause);
+                }
+            }
+            MenuPauseEvent::Resume => {
+                if !std::mem::take(&mut menu.0) {
+                    continue;
+                }
+
+                info!("Resuming the game after the in-game menu was closed.");
+                if single_player {
+                    time.unpause();
+                } else if multiplayer.0 {
+                    requests.send(PauseRequestEvent::Resume);
+                }
+            }
+        }
+    }
+}
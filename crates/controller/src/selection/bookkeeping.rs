@@ -1,10 +1,19 @@
 use ahash::AHashSet;
-use bevy::{ecs::system::SystemParam, prelude::*};
-use de_core::{baseset::GameSet, gamestate::GameState};
+use bevy::{
+    ecs::{
+        query::{ROQueryItem, ReadOnlyWorldQuery, WorldQuery},
+        system::SystemParam,
+    },
+    prelude::*,
+};
+use de_conf::Action;
+use de_core::{baseset::GameSet, gamestate::GameState, objects::ObjectType, state::AppState};
 use de_signs::{UpdateBarVisibilityEvent, UpdateLineVisibilityEvent, UpdatePoleVisibilityEvent};
 use de_terrain::MarkerVisibility;
+use de_transport::Transported;
+use enum_map::Enum;
 
-use crate::SELECTION_BAR_ID;
+use crate::{commands::on_action, SELECTION_BAR_ID};
 
 pub(super) struct BookkeepingPlugin;
 
@@ -13,12 +22,32 @@ impl Plugin for BookkeepingPlugin {
         app.add_event::<SelectEvent>()
             .add_event::<SelectedEvent>()
             .add_event::<DeselectedEvent>()
+            .add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
             .add_system(
                 update_selection
                     .in_base_set(GameSet::Input)
                     .run_if(in_state(GameState::Playing))
                     .in_set(SelectionSet::Update),
             )
+            .add_system(
+                deselect_transported
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .before(SelectionSet::Update),
+            )
+            .add_system(
+                cycle_subgroup
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(Action::CycleSubgroup))
+                    .after(SelectionSet::Update),
+            )
+            .add_system(
+                prune
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            )
             .add_system(
                 selected_system
                     .in_base_set(GameSet::Input)
@@ -79,6 +108,106 @@ struct DeselectedEvent(Entity);
 #[derive(Component)]
 pub(crate) struct Selected;
 
+/// Selection of the local player. All commands given to the selection are
+/// issued to the entities stored here. [`Selected`] component is kept in sync
+/// with this resource.
+///
+/// Selected entities of the same object type form a subgroup. One of the
+/// subgroups is active, e.g. its first entity is controlled from the action
+/// bar.
+#[derive(Resource, Default)]
+pub(crate) struct Selection {
+    /// Selected entities in the order of their selection.
+    entities: Vec<(Entity, ObjectType)>,
+    active: Option<ObjectType>,
+}
+
+impl Selection {
+    /// Iterates over all selected entities in the order of their selection.
+    pub(crate) fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities.iter().map(|&(entity, _)| entity)
+    }
+
+    /// Iterates over entities of the active subgroup.
+    pub(crate) fn subgroup(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.entities
+            .iter()
+            .filter(|&&(_, object_type)| Some(object_type) == self.active)
+            .map(|&(entity, _)| entity)
+    }
+
+    /// Returns object types of all subgroups in a stable order.
+    fn subgroups(&self) -> Vec<ObjectType> {
+        let mut subgroups: Vec<ObjectType> = self
+            .entities
+            .iter()
+            .map(|&(_, object_type)| object_type)
+            .collect();
+        subgroups.sort_unstable_by_key(|&object_type| object_type.into_usize());
+        subgroups.dedup();
+        subgroups
+    }
+
+    fn insert(&mut self, entity: Entity, object_type: ObjectType) {
+        if self.entities.iter().all(|&(other, _)| other != entity) {
+            self.entities.push((entity, object_type));
+        }
+        self.update_active();
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        self.entities.retain(|&(other, _)| other != entity);
+        self.update_active();
+    }
+
+    /// Makes the next subgroup active.
+    fn cycle(&mut self) {
+        let subgroups = self.subgroups();
+        let next = subgroups
+            .iter()
+            .position(|&object_type| Some(object_type) == self.active)
+            .map_or(0, |index| index + 1);
+        self.active = subgroups.get(next % subgroups.len().max(1)).copied();
+    }
+
+    /// Keeps the active subgroup while it is not empty, otherwise the first
+    /// subgroup is activated.
+    fn update_active(&mut self) {
+        let present = self
+            .entities
+            .iter()
+            .any(|&(_, object_type)| Some(object_type) == self.active);
+        if !present {
+            self.active = self.subgroups().first().copied();
+        }
+    }
+}
+
+/// System parameter for querying of the selected entities.
+#[derive(SystemParam)]
+pub(crate) struct SelectedQuery<'w, 's, Q, F = ()>
+where
+    Q: WorldQuery + Sync + Send + 'static,
+    F: ReadOnlyWorldQuery + Sync + Send + 'static,
+{
+    selection: Res<'w, Selection>,
+    query: Query<'w, 's, Q, F>,
+}
+
+impl<'w, 's, Q, F> SelectedQuery<'w, 's, Q, F>
+where
+    Q: WorldQuery + Sync + Send + 'static,
+    F: ReadOnlyWorldQuery + Sync + Send + 'static,
+{
+    /// Iterates over query items of the selected entities matching the
+    /// query, in the order of their selection.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = ROQueryItem<'_, Q>> + '_ {
+        self.selection
+            .entities()
+            .filter_map(|entity| self.query.get(entity).ok())
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub(crate) enum SelectionMode {
     Replace,
@@ -93,16 +222,19 @@ pub(crate) enum SelectionMode {
 #[derive(SystemParam)]
 struct SelectorBuilder<'w, 's> {
     commands: Commands<'w, 's>,
-    selected: Query<'w, 's, Entity, With<Selected>>,
+    selection: ResMut<'w, Selection>,
+    objects: Query<'w, 's, &'static ObjectType>,
     selected_events: EventWriter<'w, SelectedEvent>,
     deselected_events: EventWriter<'w, DeselectedEvent>,
 }
 
 impl<'w, 's> SelectorBuilder<'w, 's> {
     fn build(self) -> Selector<'w, 's> {
-        let selected: AHashSet<Entity> = self.selected.iter().collect();
+        let selected: AHashSet<Entity> = self.selection.entities().collect();
         Selector {
             commands: self.commands,
+            selection: self.selection,
+            objects: self.objects,
             selected,
             to_select: AHashSet::new(),
             to_deselect: AHashSet::new(),
@@ -114,6 +246,8 @@ impl<'w, 's> SelectorBuilder<'w, 's> {
 
 struct Selector<'w, 's> {
     commands: Commands<'w, 's>,
+    selection: ResMut<'w, Selection>,
+    objects: Query<'w, 's, &'static ObjectType>,
     selected: AHashSet<Entity>,
     to_select: AHashSet<Entity>,
     to_deselect: AHashSet<Entity>,
@@ -143,17 +277,30 @@ impl<'w, 's> Selector<'w, 's> {
 
     fn execute(mut self) {
         for entity in self.to_deselect {
+            self.selection.remove(entity);
             self.commands.entity(entity).remove::<Selected>();
             self.deselected_events.send(DeselectedEvent(entity));
         }
 
         for entity in self.to_select {
+            let Ok(&object_type) = self.objects.get(entity) else {
+                continue;
+            };
+            self.selection.insert(entity, object_type);
             self.commands.entity(entity).insert(Selected);
             self.selected_events.send(SelectedEvent(entity));
         }
     }
 }
 
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Selection>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Selection>();
+}
+
 fn update_selection(mut events: EventReader<SelectEvent>, selector_builder: SelectorBuilder) {
     let mut selector = selector_builder.build();
     for event in events.iter() {
@@ -162,6 +309,28 @@ fn update_selection(mut events: EventReader<SelectEvent>, selector_builder: Sele
     selector.execute();
 }
 
+/// Deselects units which boarded a carrier.
+fn deselect_transported(
+    transported: Query<Entity, (With<Selected>, Added<Transported>)>,
+    mut events: EventWriter<SelectEvent>,
+) {
+    let entities: Vec<Entity> = transported.iter().collect();
+    if !entities.is_empty() {
+        events.send(SelectEvent::many(entities, SelectionMode::AddToggle));
+    }
+}
+
+fn cycle_subgroup(mut selection: ResMut<Selection>) {
+    selection.cycle();
+}
+
+/// Removes despawned (e.g. destroyed) entities from the selection.
+fn prune(mut selection: ResMut<Selection>, mut removed: RemovedComponents<Selected>) {
+    for entity in removed.iter() {
+        selection.remove(entity);
+    }
+}
+
 fn selected_system(
     mut events: EventReader<SelectedEvent>,
     mut markers: Query<&mut MarkerVisibility>,
@@ -207,3 +376,58 @@ fn deselected_system(
         lines.send(UpdateLineVisibilityEvent::new(event.0, false));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use de_core::objects::{ActiveObjectType, BuildingType, UnitType};
+
+    use super::*;
+
+    #[test]
+    fn test_subgroups() {
+        let attacker = ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker));
+        let base = ObjectType::Active(ActiveObjectType::Building(BuildingType::Base));
+        let hub = ObjectType::Active(ActiveObjectType::Building(BuildingType::PowerHub));
+
+        let mut selection = Selection::default();
+        assert!(selection.entities().next().is_none());
+        selection.cycle();
+        assert!(selection.subgroup().next().is_none());
+
+        selection.insert(Entity::from_raw(1), attacker);
+        selection.insert(Entity::from_raw(2), hub);
+        selection.insert(Entity::from_raw(3), attacker);
+        selection.insert(Entity::from_raw(4), base);
+        selection.insert(Entity::from_raw(3), attacker);
+        assert_eq!(selection.entities().count(), 4);
+        assert_eq!(
+            selection.subgroup().collect::<Vec<_>>(),
+            vec![Entity::from_raw(1), Entity::from_raw(3)]
+        );
+
+        selection.cycle();
+        let first = selection.subgroup().collect::<Vec<_>>();
+        selection.cycle();
+        let second = selection.subgroup().collect::<Vec<_>>();
+        assert_ne!(first, second);
+        assert_eq!(first.len() + second.len(), 2);
+        selection.cycle();
+        assert_eq!(
+            selection.subgroup().collect::<Vec<_>>(),
+            vec![Entity::from_raw(1), Entity::from_raw(3)]
+        );
+
+        selection.cycle();
+        let active = selection.subgroup().next().unwrap();
+        selection.remove(active);
+        assert_eq!(selection.subgroup().count(), 1);
+        assert!(!selection.subgroup().any(|entity| entity == active));
+
+        selection.remove(Entity::from_raw(1));
+        selection.remove(Entity::from_raw(2));
+        selection.remove(Entity::from_raw(3));
+        selection.remove(Entity::from_raw(4));
+        assert!(selection.entities().next().is_none());
+        assert!(selection.subgroup().next().is_none());
+    }
+}
@@ -0,0 +1,172 @@
+//! Control groups: the player may store the current selection under a number
+//! and recall it later.
+//!
+//! Control groups are purely local, they are never sent to other players in a
+//! multiplayer game.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use de_camera::MoveFocusEvent;
+use de_conf::{Action, CONTROL_GROUPS};
+use de_core::{
+    baseset::GameSet, gamestate::GameState, objects::Playable, projection::ToFlat, state::AppState,
+};
+
+use super::{SelectEvent, Selected, SelectionMode, SelectionSet};
+use crate::commands::{ctrl_pressed, on_action};
+
+/// Recalling the same group twice within this duration centers the camera on
+/// the group.
+const DOUBLE_TAP_INTERVAL: Duration = Duration::from_millis(400);
+
+pub(super) struct GroupsPlugin;
+
+impl Plugin for GroupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                prune
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+
+        for (group, action) in Action::GROUPS.into_iter().enumerate() {
+            app.add_system(
+                assign(group)
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(action))
+                    .run_if(ctrl_pressed),
+            )
+            .add_system(
+                recall(group)
+                    .in_base_set(GameSet::Input)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_action(action))
+                    .run_if(not(ctrl_pressed))
+                    .before(SelectionSet::Update),
+            );
+        }
+    }
+}
+
+/// Control groups of the local player.
+#[derive(Resource, Default)]
+struct ControlGroups {
+    groups: [Vec<Entity>; CONTROL_GROUPS],
+    /// Last recalled group and the time of the recall.
+    last_recall: Option<(usize, Duration)>,
+}
+
+impl ControlGroups {
+    fn assign(&mut self, group: usize, entities: Vec<Entity>) {
+        self.groups[group] = entities;
+    }
+
+    fn get(&self, group: usize) -> &[Entity] {
+        self.groups[group].as_slice()
+    }
+
+    /// Registers a recall of a group and returns true if the same group was
+    /// recalled just before.
+    fn recall(&mut self, group: usize, time: Duration) -> bool {
+        let double = self.last_recall.map_or(false, |(last, last_time)| {
+            last == group && time.saturating_sub(last_time) <= DOUBLE_TAP_INTERVAL
+        });
+        self.last_recall = Some((group, time));
+        double
+    }
+
+    /// Removes an entity from all groups.
+    fn remove(&mut self, entity: Entity) {
+        for group in self.groups.iter_mut() {
+            group.retain(|&member| member != entity);
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<ControlGroups>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ControlGroups>();
+}
+
+/// Removes despawned (e.g. destroyed) entities from the groups.
+fn prune(mut groups: ResMut<ControlGroups>, mut removed: RemovedComponents<Playable>) {
+    for entity in removed.iter() {
+        groups.remove(entity);
+    }
+}
+
+type SelectedQuery<'w, 's> = Query<'w, 's, Entity, (With<Selected>, With<Playable>)>;
+
+fn assign(group: usize) -> impl Fn(ResMut<ControlGroups>, SelectedQuery) {
+    move |mut groups: ResMut<ControlGroups>, selected: SelectedQuery| {
+        groups.assign(group, selected.iter().collect());
+    }
+}
+
+type MembersQuery<'w, 's> = Query<'w, 's, &'static Transform, With<Playable>>;
+
+fn recall(
+    group: usize,
+) -> impl Fn(
+    Res<Time>,
+    ResMut<ControlGroups>,
+    MembersQuery,
+    EventWriter<SelectEvent>,
+    EventWriter<MoveFocusEvent>,
+) {
+    move |time: Res<Time>,
+          mut groups: ResMut<ControlGroups>,
+          members: MembersQuery,
+          mut select_events: EventWriter<SelectEvent>,
+          mut focus_events: EventWriter<MoveFocusEvent>| {
+        let entities: Vec<Entity> = groups
+            .get(group)
+            .iter()
+            .copied()
+            .filter(|&entity| members.contains(entity))
+            .collect();
+        if entities.is_empty() {
+            return;
+        }
+
+        if groups.recall(group, time.elapsed()) {
+            let centroid = entities
+                .iter()
+                .map(|&entity| members.get(entity).unwrap().translation.to_flat())
+                .sum::<Vec2>()
+                / entities.len() as f32;
+            focus_events.send(MoveFocusEvent::new(centroid));
+        }
+
+        select_events.send(SelectEvent::many(entities, SelectionMode::Replace));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups() {
+        let mut groups = ControlGroups::default();
+        groups.assign(1, vec![Entity::from_raw(1), Entity::from_raw(2)]);
+        groups.assign(2, vec![Entity::from_raw(2)]);
+        assert_eq!(groups.get(1), &[Entity::from_raw(1), Entity::from_raw(2)]);
+
+        groups.remove(Entity::from_raw(2));
+        assert_eq!(groups.get(1), &[Entity::from_raw(1)]);
+        assert!(groups.get(2).is_empty());
+
+        assert!(!groups.recall(1, Duration::from_secs(10)));
+        assert!(groups.recall(1, Duration::from_millis(10_300)));
+        assert!(!groups.recall(2, Duration::from_millis(10_400)));
+        assert!(!groups.recall(2, Duration::from_secs(11)));
+    }
+}
@@ -2,15 +2,21 @@ use area::AreaPlugin;
 pub(crate) use area::{AreaSelectSet, SelectInRectEvent};
 use bevy::prelude::*;
 use bookkeeping::BookkeepingPlugin;
-pub(crate) use bookkeeping::{SelectEvent, Selected, SelectionMode, SelectionSet};
+pub(crate) use bookkeeping::{
+    SelectEvent, Selected, SelectedQuery, Selection, SelectionMode, SelectionSet,
+};
+use groups::GroupsPlugin;
 
 mod area;
 mod bookkeeping;
+mod groups;
 
 pub(crate) struct SelectionPlugin;
 
 impl Plugin for SelectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(BookkeepingPlugin).add_plugin(AreaPlugin);
+        app.add_plugin(BookkeepingPlugin)
+            .add_plugin(AreaPlugin)
+            .add_plugin(GroupsPlugin);
     }
 }
@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use de_core::pool::EntityPool;
+
+/// Number of frames an effect is visible.
+const LIFESPAN: u32 = 30;
+
+/// Number of effects started each frame.
+#[derive(Resource)]
+struct Rate(u32);
+
+#[derive(Component, Default)]
+struct Effect(u32);
+
+fn effect_bundle() -> impl Bundle {
+    (SpatialBundle::default(), Effect::default())
+}
+
+fn spawn_despawn(
+    mut commands: Commands,
+    rate: Res<Rate>,
+    mut effects: Query<(Entity, &mut Effect)>,
+) {
+    for (entity, mut effect) in effects.iter_mut() {
+        effect.0 += 1;
+        if effect.0 >= LIFESPAN {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for _ in 0..rate.0 {
+        commands.spawn(effect_bundle());
+    }
+}
+
+fn pooled(
+    mut commands: Commands,
+    rate: Res<Rate>,
+    mut pool: ResMut<EntityPool<Effect>>,
+    mut effects: Query<(Entity, &mut Effect, &mut Visibility)>,
+) {
+    for (entity, mut effect, mut visibility) in effects.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
+        effect.0 += 1;
+        if effect.0 >= LIFESPAN {
+            if pool.release(entity) {
+                *visibility = Visibility::Hidden;
+            } else {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    for _ in 0..rate.0 {
+        match pool.take(|entity| effects.contains(entity)) {
+            Some(entity) => {
+                let (_, mut effect, mut visibility) = effects.get_mut(entity).unwrap();
+                effect.0 = 0;
+                *visibility = Visibility::Inherited;
+            }
+            None => {
+                commands.spawn(effect_bundle());
+            }
+        }
+    }
+}
+
+fn pool_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Effects");
+
+    for rate in [10, 100, 1000] {
+        group.throughput(Throughput::Elements(rate.into()));
+
+        let mut app = App::new();
+        app.insert_resource(Rate(rate)).add_system(spawn_despawn);
+        group.bench_function(BenchmarkId::new("Spawn & Despawn", rate), |b| {
+            b.iter(|| app.update());
+        });
+
+        let mut app = App::new();
+        app.insert_resource(Rate(rate))
+            .insert_resource(EntityPool::<Effect>::new((2 * rate * LIFESPAN) as usize))
+            .add_system(pooled);
+        group.bench_function(BenchmarkId::new("Pooled", rate), |b| {
+            b.iter(|| app.update());
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, pool_benchmark);
+criterion_main!(benches);
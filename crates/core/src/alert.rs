@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+
+use crate::player::Player;
+
+pub(crate) struct AlertPlugin;
+
+impl Plugin for AlertPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UnderAttackEvent>()
+            .add_event::<AlertEvent>();
+    }
+}
+
+/// This event is sent whenever an object owned by a player is damaged by an
+/// attack.
+pub struct UnderAttackEvent {
+    player: Player,
+    position: Vec2,
+}
+
+impl UnderAttackEvent {
+    /// # Arguments
+    ///
+    /// * `player` - owner of the attacked object.
+    ///
+    /// * `position` - position of the attacked object in 2D flat coordinates.
+    pub fn new(player: Player, position: Vec2) -> Self {
+        Self { player, position }
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+}
+
+/// A notification for the local player. The event is meant to be consumed by
+/// the user interface.
+///
+/// Similar alerts raised shortly after each other are de-duplicated before
+/// this event is sent, thus each event should be presented to the player.
+pub struct AlertEvent {
+    kind: AlertKind,
+    position: Option<Vec2>,
+}
+
+impl AlertEvent {
+    /// # Arguments
+    ///
+    /// * `kind` - what happened.
+    ///
+    /// * `position` - position (in 2D flat coordinates) at which it happened
+    ///   or None if the alert is not related to a particular place.
+    pub fn new(kind: AlertKind, position: Option<Vec2>) -> Self {
+        Self { kind, position }
+    }
+
+    pub fn kind(&self) -> AlertKind {
+        self.kind
+    }
+
+    pub fn position(&self) -> Option<Vec2> {
+        self.position
+    }
+
+    pub fn priority(&self) -> AlertPriority {
+        self.kind.priority()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertKind {
+    /// A unit or a building of the local player or of an ally is attacked.
+    UnderAttack,
+    /// Construction of a building of the local player has finished.
+    BuildingComplete,
+    /// Another player left the game.
+    PlayerLeft(Player),
+    /// Battery of an object of the local player is almost empty.
+    LowPower,
+}
+
+impl AlertKind {
+    pub fn priority(self) -> AlertPriority {
+        match self {
+            Self::UnderAttack | Self::PlayerLeft(_) => AlertPriority::High,
+            Self::LowPower => AlertPriority::Normal,
+            Self::BuildingComplete => AlertPriority::Low,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AlertPriority {
+    Low,
+    Normal,
+    High,
+}
@@ -0,0 +1,282 @@
+//! Frustum culling of entities with [`VisibilityFlags`].
+//!
+//! World space bounding boxes of the entities are kept in a bounding volume
+//! hierarchy which is updated incrementally: only entities whose transform
+//! or bounds changed are refitted. Each frame, the hierarchy is traversed
+//! with the camera frustum and only entities whose culling state changed
+//! since the previous frame are touched, thus the cost is proportional to
+//! the number of moving and visible entities rather than to the number of
+//! all entities.
+
+use bevy::{
+    prelude::*,
+    render::{
+        primitives::{Aabb, Frustum},
+        view::{NoFrustumCulling, VisibilitySystems},
+    },
+    transform::TransformSystem,
+    utils::{HashMap, HashSet},
+};
+use parry3d::{
+    bounding_volume::{Aabb as AabbP, SimdAabb},
+    math::{SimdBool, SIMD_WIDTH},
+    partitioning::{IndexedData, Qbvh, QbvhUpdateWorkspace, SimdVisitStatus},
+};
+
+use crate::{frustum, state::AppState, visibility::VisibilityFlags, visibility::VisibilitySet};
+
+/// Leaf AABBs of the hierarchy are loosened by this margin so that small
+/// movements do not propagate to the upper levels of the hierarchy.
+const MARGIN: f32 = 1.;
+
+pub(crate) struct CullingPlugin;
+
+impl Plugin for CullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                update_tree
+                    .in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame))
+                    .in_set(CullingSet::UpdateTree)
+                    .after(VisibilitySystems::CalculateBoundsFlush)
+                    .after(TransformSystem::TransformPropagate),
+            )
+            .add_system(
+                cull.in_base_set(CoreSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame))
+                    .in_set(CullingSet::Cull)
+                    .after(CullingSet::UpdateTree)
+                    .after(VisibilitySystems::UpdatePerspectiveFrusta)
+                    .after(VisibilitySystems::UpdateProjectionFrusta)
+                    .before(VisibilitySet::Update),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum CullingSet {
+    UpdateTree,
+    Cull,
+}
+
+/// Entity stored in leaves of the bounding volume hierarchy.
+#[derive(Clone, Copy)]
+struct Leaf(Entity);
+
+impl IndexedData for Leaf {
+    fn default() -> Self {
+        Self(Entity::PLACEHOLDER)
+    }
+
+    fn index(&self) -> usize {
+        self.0.index() as usize
+    }
+}
+
+#[derive(Resource)]
+struct CullingTree {
+    tree: Qbvh<Leaf>,
+    workspace: QbvhUpdateWorkspace,
+    aabbs: HashMap<Entity, AabbP>,
+    /// Entities which were not culled during the last culling.
+    visible: HashSet<Entity>,
+}
+
+impl CullingTree {
+    fn new() -> Self {
+        Self {
+            tree: Qbvh::new(),
+            workspace: QbvhUpdateWorkspace::default(),
+            aabbs: HashMap::new(),
+            visible: HashSet::new(),
+        }
+    }
+
+    /// Inserts a new entity or updates world space AABB of an already
+    /// inserted entity. The change takes effect after [`Self::refit`].
+    fn update(&mut self, entity: Entity, aabb: AabbP) {
+        if self.aabbs.insert(entity, aabb).is_none() {
+            // New entities are considered visible so that they are culled
+            // during the next culling if they are outside of the frustum.
+            self.visible.insert(entity);
+        }
+        self.tree.pre_update_or_insert(Leaf(entity));
+    }
+
+    fn remove(&mut self, entity: Entity) {
+        if self.aabbs.remove(&entity).is_some() {
+            self.tree.remove(Leaf(entity));
+            self.visible.remove(&entity);
+        }
+    }
+
+    fn refit(&mut self) {
+        let aabbs = &self.aabbs;
+        self.tree.refit(MARGIN, &mut self.workspace, |leaf| {
+            *aabbs.get(&leaf.0).unwrap()
+        });
+        self.tree.rebalance(MARGIN, &mut self.workspace);
+    }
+
+    /// Returns all entities whose AABB intersects the frustum.
+    fn intersecting(&self, frustum: &Frustum) -> HashSet<Entity> {
+        let mut entities = HashSet::with_capacity(self.visible.len());
+        let mut visitor = |bv: &SimdAabb, leaves: Option<[Option<&Leaf>; SIMD_WIDTH]>| {
+            let mut mask = [false; SIMD_WIDTH];
+            for (lane, mask) in mask.iter_mut().enumerate() {
+                let aabb = match leaves {
+                    Some(leaves) => match leaves[lane] {
+                        Some(leaf) => match self.aabbs.get(&leaf.0) {
+                            Some(aabb) => *aabb,
+                            None => continue,
+                        },
+                        None => continue,
+                    },
+                    None => bv.extract(lane),
+                };
+
+                if frustum::intersects_parry(frustum, Transform::IDENTITY, &aabb) {
+                    *mask = true;
+                    if let Some(leaves) = leaves {
+                        entities.insert(leaves[lane].unwrap().0);
+                    }
+                }
+            }
+            SimdVisitStatus::MaybeContinue(SimdBool::from(mask))
+        };
+        self.tree.traverse_depth_first(&mut visitor);
+        entities
+    }
+
+    /// Culls all entities outside of the frustum. Returns entities which
+    /// were culled and entities which were un-culled since the last call.
+    fn cull(&mut self, frustum: &Frustum) -> (Vec<Entity>, Vec<Entity>) {
+        let visible = self.intersecting(frustum);
+        let culled = self.visible.difference(&visible).copied().collect();
+        let unculled = visible.difference(&self.visible).copied().collect();
+        self.visible = visible;
+        (culled, unculled)
+    }
+}
+
+/// Returns world space AABB of an object space `aabb` transformed by
+/// `transform`.
+fn world_aabb(transform: &GlobalTransform, aabb: &Aabb) -> AabbP {
+    let affine = transform.affine();
+    let center = affine.transform_point3a(aabb.center);
+    let matrix = affine.matrix3;
+    let half_extents = matrix.x_axis.abs() * aabb.half_extents.x
+        + matrix.y_axis.abs() * aabb.half_extents.y
+        + matrix.z_axis.abs() * aabb.half_extents.z;
+    AabbP::new(
+        Vec3::from(center - half_extents).into(),
+        Vec3::from(center + half_extents).into(),
+    )
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(CullingTree::new());
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<CullingTree>();
+}
+
+type Culled = (
+    With<VisibilityFlags>,
+    Without<NoFrustumCulling>,
+    Or<(Changed<GlobalTransform>, Changed<Aabb>)>,
+);
+
+fn update_tree(
+    mut tree: ResMut<CullingTree>,
+    mut removed: RemovedComponents<VisibilityFlags>,
+    entities: Query<(Entity, &GlobalTransform, &Aabb), Culled>,
+) {
+    // Removals must be processed first because indices of despawned
+    // entities may be reused by newly spawned entities.
+    for entity in removed.iter() {
+        tree.remove(entity);
+    }
+    for (entity, transform, aabb) in entities.iter() {
+        tree.update(entity, world_aabb(transform, aabb));
+    }
+    tree.refit();
+}
+
+fn cull(
+    mut tree: ResMut<CullingTree>,
+    camera: Query<&Frustum, With<Camera3d>>,
+    mut entities: Query<&mut VisibilityFlags>,
+) {
+    let Ok(frustum) = camera.get_single() else {
+        return;
+    };
+
+    let (culled, unculled) = tree.cull(frustum);
+    for (entities_to_update, value) in [(culled, true), (unculled, false)] {
+        for entity in entities_to_update {
+            if let Ok(mut flags) = entities.get_mut(entity) {
+                flags.set_culled(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    #[test]
+    fn test_world_aabb() {
+        let aabb = Aabb::from_min_max(Vec3::new(-1., -2., -3.), Vec3::new(1., 2., 3.));
+        let transform = GlobalTransform::from(
+            Transform::from_xyz(10., 0., -5.).with_rotation(Quat::from_rotation_y(0.5 * PI)),
+        );
+        let world = world_aabb(&transform, &aabb);
+        assert!((Vec3::from(world.mins) - Vec3::new(7., -2., -6.)).length() < 1e-5);
+        assert!((Vec3::from(world.maxs) - Vec3::new(13., 2., -4.)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_cull() {
+        // Camera at the origin looking towards -Z.
+        let projection = Mat4::perspective_rh(0.5 * PI, 1., 0.1, 100.);
+        let frustum = Frustum::from_view_projection(&projection);
+
+        let entity = |index: u32| Entity::from_raw(index);
+        let aabb =
+            |center: Vec3| AabbP::new((center - Vec3::ONE).into(), (center + Vec3::ONE).into());
+
+        let mut tree = CullingTree::new();
+        for i in 0..20 {
+            tree.update(entity(i), aabb(Vec3::new(0., 0., -10. - i as f32)));
+        }
+        tree.update(entity(20), aabb(Vec3::new(0., 0., 10.)));
+        tree.update(entity(21), aabb(Vec3::new(0., 0., -200.)));
+        tree.refit();
+
+        let (culled, unculled) = tree.cull(&frustum);
+        let mut culled = culled;
+        culled.sort();
+        assert_eq!(culled, vec![entity(20), entity(21)]);
+        assert!(unculled.is_empty());
+
+        tree.update(entity(20), aabb(Vec3::new(0., 0., -10.)));
+        tree.update(entity(3), aabb(Vec3::new(50., 0., -10.)));
+        tree.remove(entity(4));
+        tree.refit();
+
+        let (culled, unculled) = tree.cull(&frustum);
+        assert_eq!(culled, vec![entity(3)]);
+        assert_eq!(unculled, vec![entity(20)]);
+
+        let (culled, unculled) = tree.cull(&frustum);
+        assert!(culled.is_empty());
+        assert!(unculled.is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+
+use crate::{baseset::GameSet, gamestate::GameState};
+
+/// Number of in-game hours of a single day.
+pub const HOURS_PER_DAY: f32 = 24.;
+/// Time of day (in hours) at which games start unless specified otherwise by
+/// the map.
+pub const DEFAULT_START_HOUR: f32 = 10.;
+
+pub(crate) struct DaytimePlugin;
+
+impl Plugin for DaytimePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DayPhaseEvent>().add_system(
+            advance
+                .in_base_set(GameSet::PreUpdate)
+                .run_if(in_state(GameState::Playing))
+                .run_if(resource_exists::<TimeOfDay>()),
+        );
+    }
+}
+
+/// In-game time of day. The resource is present during a game only.
+#[derive(Resource)]
+pub struct TimeOfDay {
+    hour: f32,
+    cycle_length: f32,
+    phase: DayPhase,
+}
+
+impl TimeOfDay {
+    /// # Arguments
+    ///
+    /// * `hour` - initial time of day in hours. It must be a number between 0
+    ///   (inclusive) and [`HOURS_PER_DAY`] (exclusive).
+    ///
+    /// * `cycle_length` - real time length of a full day in seconds. It must
+    ///   be a positive finite number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the arguments is invalid.
+    pub fn new(hour: f32, cycle_length: f32) -> Self {
+        assert!((0. ..HOURS_PER_DAY).contains(&hour));
+        assert!(cycle_length.is_finite());
+        assert!(cycle_length > 0.);
+
+        Self {
+            hour,
+            cycle_length,
+            phase: DayPhase::from_hour(hour),
+        }
+    }
+
+    /// Time of day in hours (between 0 and [`HOURS_PER_DAY`]).
+    pub fn hour(&self) -> f32 {
+        self.hour
+    }
+
+    pub fn phase(&self) -> DayPhase {
+        self.phase
+    }
+
+    /// Moves the time forward by given number of real time seconds. Returns
+    /// true if phase of the day has changed.
+    fn advance(&mut self, seconds: f32) -> bool {
+        self.hour = (self.hour + HOURS_PER_DAY * seconds / self.cycle_length) % HOURS_PER_DAY;
+
+        let phase = DayPhase::from_hour(self.hour);
+        let changed = self.phase != phase;
+        self.phase = phase;
+        changed
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DayPhase {
+    Night,
+    Dawn,
+    Day,
+    Dusk,
+}
+
+impl DayPhase {
+    fn from_hour(hour: f32) -> Self {
+        if hour < 5. {
+            Self::Night
+        } else if hour < 7. {
+            Self::Dawn
+        } else if hour < 17. {
+            Self::Day
+        } else if hour < 19. {
+            Self::Dusk
+        } else {
+            Self::Night
+        }
+    }
+}
+
+/// This event is sent at the beginning of each game and whenever phase of the
+/// day changes.
+pub struct DayPhaseEvent(DayPhase);
+
+impl DayPhaseEvent {
+    pub fn phase(&self) -> DayPhase {
+        self.0
+    }
+}
+
+fn advance(
+    time: Res<Time>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    mut events: EventWriter<DayPhaseEvent>,
+) {
+    let changed = time_of_day.advance(time.delta_seconds());
+    if changed || time_of_day.is_added() {
+        events.send(DayPhaseEvent(time_of_day.phase()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        let mut time_of_day = TimeOfDay::new(6., 240.);
+        assert_eq!(time_of_day.phase(), DayPhase::Dawn);
+
+        assert!(!time_of_day.advance(5.));
+        assert_eq!(time_of_day.hour(), 6.5);
+        assert!(time_of_day.advance(10.));
+        assert_eq!(time_of_day.hour(), 7.5);
+        assert_eq!(time_of_day.phase(), DayPhase::Day);
+
+        assert!(time_of_day.advance(180.));
+        assert_eq!(time_of_day.hour(), 1.5);
+        assert_eq!(time_of_day.phase(), DayPhase::Night);
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use crate::{
+    gconfig::GameConfig,
+    player::{Player, PlayerRange, Teams},
+    state::AppState,
+};
+
+/// Maximum number of players.
+const MAX_PLAYERS: usize = Player::Player4 as usize + 1;
+
+pub(crate) struct DiplomacyPlugin;
+
+impl Plugin for DiplomacyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+/// Alliances among players of the current game. Allies do not attack each
+/// other and win or lose together.
+///
+/// Players start allied with the members of their team (see
+/// [`GameConfig::teams`]) and further alliances may be formed during the
+/// game.
+///
+/// This resource exists only during a game.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct Diplomacy([[bool; MAX_PLAYERS]; MAX_PLAYERS]);
+
+impl Diplomacy {
+    /// Creates diplomacy where players are allied with their teams only.
+    pub fn new(teams: &Teams) -> Self {
+        let mut alliances = [[false; MAX_PLAYERS]; MAX_PLAYERS];
+        for first in PlayerRange::up_to(Player::Player4) {
+            for second in PlayerRange::up_to(Player::Player4) {
+                alliances[Self::index(first)][Self::index(second)] =
+                    teams.are_allies(first, second);
+            }
+        }
+        Self(alliances)
+    }
+
+    /// Returns true if the players are allied. Each player is an ally of
+    /// themselves.
+    pub fn are_allies(&self, first: Player, second: Player) -> bool {
+        self.0[Self::index(first)][Self::index(second)]
+    }
+
+    /// Makes the players allies. Alliances are mutual but not transitive.
+    pub fn ally(&mut self, first: Player, second: Player) {
+        self.0[Self::index(first)][Self::index(second)] = true;
+        self.0[Self::index(second)][Self::index(first)] = true;
+    }
+
+    fn index(player: Player) -> usize {
+        (player.to_num() - 1) as usize
+    }
+}
+
+fn setup(mut commands: Commands, conf: Res<GameConfig>) {
+    commands.insert_resource(Diplomacy::new(conf.teams()));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Diplomacy>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::Team;
+
+    #[test]
+    fn test_diplomacy() {
+        let mut teams = Teams::default();
+        teams.set(Player::Player2, Team::new(1));
+
+        let mut diplomacy = Diplomacy::new(&teams);
+        assert!(diplomacy.are_allies(Player::Player1, Player::Player1));
+        assert!(diplomacy.are_allies(Player::Player1, Player::Player2));
+        assert!(diplomacy.are_allies(Player::Player2, Player::Player1));
+        assert!(!diplomacy.are_allies(Player::Player3, Player::Player4));
+
+        diplomacy.ally(Player::Player4, Player::Player3);
+        assert!(diplomacy.are_allies(Player::Player3, Player::Player4));
+        assert!(diplomacy.are_allies(Player::Player4, Player::Player3));
+        assert!(!diplomacy.are_allies(Player::Player1, Player::Player3));
+    }
+}
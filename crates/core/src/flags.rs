@@ -1,5 +1,5 @@
 /// Bit flags.
-#[derive(Default)]
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub struct Flags(u32);
 
 impl Flags {
@@ -13,6 +13,21 @@ pub fn logs_dir() -> Result<AsyncPathBuf, DirError> {
     dir(dirs::cache_dir).map(|d| d.join("logs"))
 }
 
+/// Returns DE directory of maps downloaded from other players.
+pub fn maps_cache_dir() -> Result<AsyncPathBuf, DirError> {
+    dir(dirs::cache_dir).map(|d| d.join("maps"))
+}
+
+/// Returns DE directory of recorded game replays.
+pub fn replays_dir() -> Result<AsyncPathBuf, DirError> {
+    dir(dirs::data_dir).map(|d| d.join("replays"))
+}
+
+/// Returns DE directory of saved single-player games.
+pub fn saves_dir() -> Result<AsyncPathBuf, DirError> {
+    dir(dirs::data_dir).map(|d| d.join("saves"))
+}
+
 fn dir<F>(base_dir: F) -> Result<AsyncPathBuf, DirError>
 where
     F: Fn() -> Option<SyncPathBuf>,
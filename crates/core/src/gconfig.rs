@@ -2,15 +2,22 @@ use std::path::{Path, PathBuf};
 
 use bevy::prelude::Resource;
 
-use crate::player::{Player, PlayerRange};
+use crate::{
+    player::{Player, PlayerRange, Teams},
+    victory::VictoryCondition,
+};
 
 /// This resource is automatically removed when
 /// [`crate::state::AppState::InGame`] is exited.
 #[derive(Resource)]
 pub struct GameConfig {
     map_path: PathBuf,
+    map_seed: Option<u64>,
     max_player: Player,
     locals: LocalPlayers,
+    teams: Teams,
+    victory: Vec<VictoryCondition>,
+    friendly_fire: bool,
 }
 
 impl GameConfig {
@@ -21,15 +28,67 @@ impl GameConfig {
 
         Self {
             map_path: map_path.into(),
+            map_seed: None,
             max_player,
             locals,
+            teams: Teams::default(),
+            victory: vec![VictoryCondition::Annihilation],
+            friendly_fire: false,
         }
     }
 
+    /// Plays the game on a map randomly generated from `seed` instead of the
+    /// map at the map path. All players of a multiplayer game generate an
+    /// identical map from the same seed.
+    pub fn with_map_seed(mut self, seed: u64) -> Self {
+        self.map_seed = Some(seed);
+        self
+    }
+
+    /// Sets assignment of players to teams. Each player is alone in a team
+    /// by default.
+    pub fn with_teams(mut self, teams: Teams) -> Self {
+        self.teams = teams;
+        self
+    }
+
+    /// Sets conditions of the game end. The game ends once any of the
+    /// conditions is fulfilled. The game ends by
+    /// [`VictoryCondition::Annihilation`] only by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `victory` is empty.
+    pub fn with_victory(mut self, victory: Vec<VictoryCondition>) -> Self {
+        assert!(!victory.is_empty());
+        self.victory = victory;
+        self
+    }
+
+    /// Sets whether area damage (e.g. explosions) damages allies of the
+    /// attacker. Friendly fire is disabled by default.
+    pub fn with_friendly_fire(mut self, friendly_fire: bool) -> Self {
+        self.friendly_fire = friendly_fire;
+        self
+    }
+
+    /// Path to the map file. It is not used if the map is generated, see
+    /// [`Self::map_seed`].
     pub fn map_path(&self) -> &Path {
         self.map_path.as_path()
     }
 
+    /// Seed of the randomly generated map or None if the map is loaded from
+    /// the map path.
+    pub fn map_seed(&self) -> Option<u64> {
+        self.map_seed
+    }
+
+    /// The player with the highest number participating in the game.
+    pub fn max_player(&self) -> Player {
+        self.max_player
+    }
+
     pub fn players(&self) -> PlayerRange {
         PlayerRange::up_to(self.max_player)
     }
@@ -37,6 +96,18 @@ impl GameConfig {
     pub fn locals(&self) -> &LocalPlayers {
         &self.locals
     }
+
+    pub fn teams(&self) -> &Teams {
+        &self.teams
+    }
+
+    pub fn victory(&self) -> &[VictoryCondition] {
+        self.victory.as_slice()
+    }
+
+    pub fn friendly_fire(&self) -> bool {
+        self.friendly_fire
+    }
 }
 
 /// Info about players directly controlled or simulated on this computer.
@@ -93,5 +164,7 @@ mod tests {
             LocalPlayers::new(Player::Player1),
         );
         assert_eq!(config.map_path().to_string_lossy(), "/some/path");
+        assert_eq!(config.map_seed(), None);
+        assert_eq!(config.with_map_seed(42).map_seed(), Some(42));
     }
 }
@@ -1,14 +1,25 @@
+use alert::AlertPlugin;
 use baseset::GameSetsPlugin;
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
 use cleanup::CleanupPlugin;
+use culling::CullingPlugin;
+use daytime::DaytimePlugin;
+use diplomacy::DiplomacyPlugin;
 use gamestate::GameStatePlugin;
 use iyes_progress::prelude::*;
+use ping::PingPlugin;
 use state::AppState;
+use summary::SummaryPlugin;
+use victory::VictoryPlugin;
 use visibility::VisibilityPlugin;
 
+pub mod alert;
 pub mod assets;
 pub mod baseset;
 pub mod cleanup;
+pub mod culling;
+pub mod daytime;
+pub mod diplomacy;
 mod errors;
 pub mod events;
 pub mod flags;
@@ -18,12 +29,16 @@ pub mod gamestate;
 pub mod gconfig;
 pub mod gresult;
 pub mod objects;
+pub mod ping;
 pub mod player;
+pub mod pool;
 pub mod projection;
 pub mod screengeom;
 pub mod state;
+pub mod summary;
 pub mod transition;
 pub mod vecord;
+pub mod victory;
 pub mod visibility;
 
 pub struct CorePluginGroup;
@@ -35,6 +50,13 @@ impl PluginGroup for CorePluginGroup {
             .add(GameSetsPlugin)
             .add(GameStatePlugin)
             .add(VisibilityPlugin)
+            .add(CullingPlugin)
             .add(CleanupPlugin)
+            .add(DiplomacyPlugin)
+            .add(PingPlugin)
+            .add(AlertPlugin)
+            .add(DaytimePlugin)
+            .add(VictoryPlugin)
+            .add(SummaryPlugin)
     }
 }
@@ -29,7 +29,14 @@ pub struct StaticSolid;
 #[derive(Component)]
 pub struct MovableSolid;
 
-#[derive(Enum, Sequence, Component, Copy, Clone, PartialEq, Eq, Hash)]
+/// A movable object in the air layer. It flies over ground obstacles and it
+/// can be hit only by anti-air weapons.
+#[derive(Component)]
+pub struct Flying;
+
+#[derive(
+    Enum, Sequence, Component, Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash,
+)]
 pub enum ObjectType {
     Active(ActiveObjectType),
     Inactive(InactiveObjectType),
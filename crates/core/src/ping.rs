@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::player::Player;
+
+pub(crate) struct PingPlugin;
+
+impl Plugin for PingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MapPingEvent>();
+    }
+}
+
+/// A ping marker placed on the map by a player. Pings are shown to the
+/// allies of the player only.
+///
+/// Pings placed by local players are sent to allied players over the network
+/// during a multiplayer game.
+pub struct MapPingEvent {
+    player: Player,
+    position: Vec2,
+}
+
+impl MapPingEvent {
+    /// # Arguments
+    ///
+    /// * `player` - the player who placed the ping.
+    ///
+    /// * `position` - position of the ping in 2D flat coordinates.
+    pub fn new(player: Player, position: Vec2) -> Self {
+        Self { player, position }
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+}
@@ -63,6 +63,65 @@ impl TryFrom<u8> for Player {
     }
 }
 
+/// A team of players. Players in the same team are allies: they win or lose
+/// together and may chat privately.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Team(u8);
+
+impl Team {
+    pub fn new(id: u8) -> Self {
+        Self(id)
+    }
+
+    pub fn id(self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Display for Team {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "team {}", self.0)
+    }
+}
+
+/// Assignment of players to teams.
+///
+/// By default, each player is alone in a team with the same ID as the
+/// player (free for all).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Teams([Team; Player::Player4 as usize + 1]);
+
+impl Teams {
+    pub fn team(&self, player: Player) -> Team {
+        self.0[Self::index(player)]
+    }
+
+    pub fn set(&mut self, player: Player, team: Team) {
+        self.0[Self::index(player)] = team;
+    }
+
+    /// Returns true if both players are in the same team. Each player is an
+    /// ally of themselves.
+    pub fn are_allies(&self, first: Player, second: Player) -> bool {
+        self.team(first) == self.team(second)
+    }
+
+    fn index(player: Player) -> usize {
+        (player.to_num() - 1) as usize
+    }
+}
+
+impl Default for Teams {
+    fn default() -> Self {
+        Self([
+            Team(Player::Player1.to_num()),
+            Team(Player::Player2.to_num()),
+            Team(Player::Player3.to_num()),
+            Team(Player::Player4.to_num()),
+        ])
+    }
+}
+
 pub struct PlayerRange {
     start: Player,
     stop: Player,
@@ -100,7 +159,7 @@ impl Iterator for PlayerRange {
     fn next(&mut self) -> Option<Self::Item> {
         match self.current {
             Some(current) => {
-                self.current = current.next();
+                self.current = current.next().filter(|&next| next <= self.stop);
                 Some(current)
             }
             None => {
@@ -138,5 +197,28 @@ mod tests {
         assert_eq!(range.next(), Some(Player::Player3));
         assert_eq!(range.next(), Some(Player::Player4));
         assert_eq!(range.next(), None);
+
+        let range = PlayerRange::up_to(Player::Player2);
+        assert_eq!(range.len(), 2);
+        assert_eq!(
+            range.collect::<Vec<_>>(),
+            vec![Player::Player1, Player::Player2]
+        );
+    }
+
+    #[test]
+    fn test_teams() {
+        let mut teams = Teams::default();
+        assert_eq!(teams.team(Player::Player3), Team::new(3));
+        assert!(teams.are_allies(Player::Player2, Player::Player2));
+        assert!(!teams.are_allies(Player::Player1, Player::Player2));
+
+        teams.set(Player::Player1, Team::new(1));
+        teams.set(Player::Player2, Team::new(1));
+        teams.set(Player::Player3, Team::new(2));
+        teams.set(Player::Player4, Team::new(2));
+        assert!(teams.are_allies(Player::Player1, Player::Player2));
+        assert!(teams.are_allies(Player::Player4, Player::Player3));
+        assert!(!teams.are_allies(Player::Player2, Player::Player3));
     }
 }
@@ -0,0 +1,102 @@
+//! Pooling of short-lived entities, e.g. visual effects spawned in large
+//! numbers during battles.
+//!
+//! Instead of being despawned, entities are hidden and released to a pool
+//! from which they are later taken and reused. This avoids the allocation
+//! churn and archetype moves caused by frequent spawning and despawning.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+/// A pool of released (inactive) entities of a single kind. The kind is
+/// distinguished by the marker type `T`, typically the component of the
+/// pooled entities.
+///
+/// The pool does not own the entities: it is the responsibility of the user
+/// to hide released entities, to reset them when they are taken and to
+/// despawn the entities which do not fit into the pool.
+#[derive(Resource)]
+pub struct EntityPool<T> {
+    capacity: usize,
+    free: Vec<Entity>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> EntityPool<T> {
+    /// # Arguments
+    ///
+    /// * `capacity` - maximum number of released entities kept in the pool.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            free: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Number of released entities currently in the pool.
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+
+    /// Takes a released entity from the pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `alive` - returns true if the entity still exists and can be
+    ///   reused. Entities for which this returns false (e.g. entities
+    ///   despawned together with their parent) are dropped from the pool.
+    pub fn take<F>(&mut self, mut alive: F) -> Option<Entity>
+    where
+        F: FnMut(Entity) -> bool,
+    {
+        while let Some(entity) = self.free.pop() {
+            if alive(entity) {
+                return Some(entity);
+            }
+        }
+        None
+    }
+
+    /// Releases an entity to the pool. Returns false if the pool is full, in
+    /// which case the entity is not stored and should be despawned.
+    pub fn release(&mut self, entity: Entity) -> bool {
+        if self.free.len() >= self.capacity {
+            return false;
+        }
+        self.free.push(entity);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Marker;
+
+    #[test]
+    fn test_pool() {
+        let mut pool = EntityPool::<Marker>::new(2);
+        assert!(pool.is_empty());
+        assert!(pool.take(|_| true).is_none());
+
+        assert!(pool.release(Entity::from_raw(1)));
+        assert!(pool.release(Entity::from_raw(2)));
+        assert!(!pool.release(Entity::from_raw(3)));
+        assert_eq!(pool.len(), 2);
+
+        assert_eq!(pool.take(|_| true), Some(Entity::from_raw(2)));
+        assert!(pool.release(Entity::from_raw(4)));
+        assert_eq!(
+            pool.take(|entity| entity != Entity::from_raw(4)),
+            Some(Entity::from_raw(1))
+        );
+        assert!(pool.is_empty());
+    }
+}
@@ -81,6 +81,11 @@ impl ScreenRect {
     pub fn size(&self) -> Vec2 {
         self.1 - self.0
     }
+
+    /// Returns true if the point lies inside the rectangle or on its edge.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.0).all() && point.cmple(self.1).all()
+    }
 }
 
 #[cfg(test)]
@@ -95,4 +100,14 @@ mod tests {
         assert_eq!(rect.bottom(), 0.2);
         assert_eq!(rect.top(), 0.3);
     }
+
+    #[test]
+    fn test_contains() {
+        let rect = ScreenRect::new(Vec2::new(-0.5, 0.), Vec2::new(0.5, 1.));
+        assert!(rect.contains(Vec2::new(0., 0.5)));
+        assert!(rect.contains(Vec2::new(-0.5, 1.)));
+        assert!(!rect.contains(Vec2::new(0.6, 0.5)));
+        assert!(!rect.contains(Vec2::new(0., -0.1)));
+        assert!(ScreenRect::full().contains(Vec2::new(-1., 1.)));
+    }
 }
@@ -21,6 +21,11 @@ pub enum AppState {
     /// Before a game is started, make sure it is properly configured. Resource
     /// [`crate::gconfig::GameConfig`] must exist.
     InGame,
+    /// A map is being edited in the map editor.
+    ///
+    /// Before the editor is entered, resource `de_editor::EditorConfig` must
+    /// exist.
+    InEditor,
 }
 
 impl StateWithSet for AppState {
@@ -0,0 +1,335 @@
+//! Per-player statistics of a match.
+//!
+//! Changes of the statistics are reported with [`MatchStatEvent`] and
+//! accumulated to [`MatchSummary`]. The summary is sampled periodically so
+//! that the course of the match can be presented once it is over, thus the
+//! resource is kept after the game ends. It is replaced at the start of the
+//! next game.
+//!
+//! During a multiplayer game, objects are simulated only on the computer of
+//! their owner. Statistics of remote players are therefore replaced with
+//! those received over the network, see [`MatchSummary::replace`].
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    baseset::GameSet,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    player::{Player, PlayerRange},
+    state::AppState,
+};
+
+/// Time between two consecutive samples of the statistics.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+pub(crate) struct SummaryPlugin;
+
+impl Plugin for SummaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MatchStatEvent>()
+            .add_system(setup.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(finish.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                record
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_exists::<MatchSummary>()),
+            );
+    }
+}
+
+/// Send this event when a statistic of a player changes.
+pub struct MatchStatEvent {
+    player: Player,
+    stat: MatchStat,
+}
+
+impl MatchStatEvent {
+    pub fn new(player: Player, stat: MatchStat) -> Self {
+        Self { player, stat }
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn stat(&self) -> MatchStat {
+        self.stat
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchStat {
+    /// A unit of the player was manufactured.
+    UnitBuilt,
+    /// A unit of the player was destroyed.
+    UnitLost,
+    /// A unit of the player destroyed an enemy unit.
+    UnitKilled,
+    /// Objects of the player dealt the given damage.
+    DamageDealt(f32),
+    /// The player gathered the given amount of resources.
+    ResourcesGathered(f32),
+    /// The player made an action, e.g. a key press or a mouse click bound
+    /// to a game action.
+    Action,
+}
+
+/// Accumulated statistics of a single player.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlayerStats {
+    units_built: u32,
+    units_lost: u32,
+    units_killed: u32,
+    damage_dealt: f32,
+    resources_gathered: f32,
+    actions: u32,
+}
+
+impl PlayerStats {
+    pub fn new(
+        units_built: u32,
+        units_lost: u32,
+        units_killed: u32,
+        damage_dealt: f32,
+        resources_gathered: f32,
+        actions: u32,
+    ) -> Self {
+        Self {
+            units_built,
+            units_lost,
+            units_killed,
+            damage_dealt,
+            resources_gathered,
+            actions,
+        }
+    }
+
+    pub fn units_built(&self) -> u32 {
+        self.units_built
+    }
+
+    pub fn units_lost(&self) -> u32 {
+        self.units_lost
+    }
+
+    pub fn units_killed(&self) -> u32 {
+        self.units_killed
+    }
+
+    pub fn damage_dealt(&self) -> f32 {
+        self.damage_dealt
+    }
+
+    pub fn resources_gathered(&self) -> f32 {
+        self.resources_gathered
+    }
+
+    pub fn actions(&self) -> u32 {
+        self.actions
+    }
+
+    /// Average number of actions per minute over the given time.
+    pub fn apm(&self, elapsed: Duration) -> f32 {
+        let minutes = elapsed.as_secs_f32() / 60.;
+        if minutes > 0. {
+            self.actions as f32 / minutes
+        } else {
+            0.
+        }
+    }
+
+    fn record(&mut self, stat: MatchStat) {
+        match stat {
+            MatchStat::UnitBuilt => self.units_built += 1,
+            MatchStat::UnitLost => self.units_lost += 1,
+            MatchStat::UnitKilled => self.units_killed += 1,
+            MatchStat::DamageDealt(damage) => self.damage_dealt += damage,
+            MatchStat::ResourcesGathered(amount) => self.resources_gathered += amount,
+            MatchStat::Action => self.actions += 1,
+        }
+    }
+}
+
+/// Statistics of all players of the current (or the last) match.
+#[derive(Resource)]
+pub struct MatchSummary {
+    duration: Duration,
+    /// Time of the last sample.
+    sampled: Duration,
+    times: Vec<Duration>,
+    players: Vec<PlayerSummary>,
+}
+
+impl MatchSummary {
+    fn new(players: PlayerRange) -> Self {
+        Self {
+            duration: Duration::ZERO,
+            sampled: Duration::ZERO,
+            times: vec![Duration::ZERO],
+            players: players.map(PlayerSummary::new).collect(),
+        }
+    }
+
+    /// Game time elapsed since the start of the match. Pauses are excluded.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Times of all samples since the start of the match. The first sample
+    /// is taken at the very start and the last one at the end of the match.
+    pub fn times(&self) -> &[Duration] {
+        self.times.as_slice()
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &PlayerSummary> {
+        self.players.iter()
+    }
+
+    pub fn player(&self, player: Player) -> Option<&PlayerSummary> {
+        self.players.iter().find(|summary| summary.player == player)
+    }
+
+    /// Replaces current statistics of a player, e.g. with statistics
+    /// received from the computer simulating the player.
+    pub fn replace(&mut self, player: Player, stats: PlayerStats) {
+        if let Some(summary) = self.player_mut(player) {
+            summary.totals = stats;
+        }
+    }
+
+    fn player_mut(&mut self, player: Player) -> Option<&mut PlayerSummary> {
+        self.players
+            .iter_mut()
+            .find(|summary| summary.player == player)
+    }
+
+    /// Advances the match by `delta` and takes a sample of the statistics of
+    /// all players if it is due.
+    fn advance(&mut self, delta: Duration) {
+        self.duration += delta;
+        if self.duration - self.sampled >= SAMPLE_INTERVAL {
+            self.sample();
+        }
+    }
+
+    /// Takes a final sample unless it has just been taken.
+    fn finish(&mut self) {
+        if self.duration > self.sampled {
+            self.sample();
+        }
+    }
+
+    fn sample(&mut self) {
+        self.sampled = self.duration;
+        self.times.push(self.duration);
+        for summary in self.players.iter_mut() {
+            summary.history.push(summary.totals);
+        }
+    }
+}
+
+pub struct PlayerSummary {
+    player: Player,
+    totals: PlayerStats,
+    history: Vec<PlayerStats>,
+}
+
+impl PlayerSummary {
+    fn new(player: Player) -> Self {
+        Self {
+            player,
+            totals: PlayerStats::default(),
+            history: vec![PlayerStats::default()],
+        }
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Current (or final) statistics of the player.
+    pub fn totals(&self) -> &PlayerStats {
+        &self.totals
+    }
+
+    /// Statistics of the player at times given by [`MatchSummary::times`].
+    pub fn history(&self) -> &[PlayerStats] {
+        self.history.as_slice()
+    }
+}
+
+fn setup(mut commands: Commands, config: Res<GameConfig>) {
+    commands.insert_resource(MatchSummary::new(config.players()));
+}
+
+fn finish(summary: Option<ResMut<MatchSummary>>) {
+    if let Some(mut summary) = summary {
+        summary.finish();
+    }
+}
+
+fn record(
+    time: Res<Time>,
+    mut summary: ResMut<MatchSummary>,
+    mut events: EventReader<MatchStatEvent>,
+) {
+    for event in events.iter() {
+        if let Some(player) = summary.player_mut(event.player()) {
+            player.totals.record(event.stat());
+        }
+    }
+    summary.advance(time.delta());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut summary = MatchSummary::new(PlayerRange::up_to(Player::Player2));
+        summary
+            .player_mut(Player::Player1)
+            .unwrap()
+            .totals
+            .record(MatchStat::UnitBuilt);
+        summary.advance(Duration::from_secs(6));
+        assert_eq!(summary.times(), &[Duration::ZERO]);
+
+        summary
+            .player_mut(Player::Player1)
+            .unwrap()
+            .totals
+            .record(MatchStat::DamageDealt(12.5));
+        summary.advance(Duration::from_secs(6));
+        assert_eq!(summary.times(), &[Duration::ZERO, Duration::from_secs(12)]);
+
+        summary.replace(Player::Player2, PlayerStats::new(1, 2, 3, 4., 5., 6));
+        summary.replace(Player::Player3, PlayerStats::new(1, 2, 3, 4., 5., 6));
+        summary.advance(Duration::from_secs(3));
+        summary.finish();
+        summary.finish();
+        assert_eq!(
+            summary.times(),
+            &[
+                Duration::ZERO,
+                Duration::from_secs(12),
+                Duration::from_secs(15)
+            ]
+        );
+        assert!(summary.player(Player::Player3).is_none());
+
+        let first = summary.player(Player::Player1).unwrap();
+        assert_eq!(first.history().len(), 3);
+        assert_eq!(first.history()[0], PlayerStats::default());
+        assert_eq!(first.history()[1], PlayerStats::new(1, 0, 0, 12.5, 0., 0));
+        let second = summary.player(Player::Player2).unwrap();
+        assert_eq!(second.history()[1], PlayerStats::default());
+        assert_eq!(second.totals().actions(), 6);
+        assert_eq!(second.totals().apm(Duration::from_secs(30)), 12.);
+    }
+}
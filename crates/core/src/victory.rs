@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    baseset::GameSet, diplomacy::Diplomacy, gamestate::GameState, gconfig::GameConfig,
+    gresult::GameResult, player::Player, state::AppState,
+};
+
+pub(crate) struct VictoryPlugin;
+
+impl Plugin for VictoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameEndedEvent>()
+            .configure_set(VictorySet::Detect.before(VictorySet::End))
+            .add_system(
+                end_game
+                    .in_base_set(GameSet::PostUpdate)
+                    .in_set(VictorySet::End)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<GameEndedEvent>()),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum VictorySet {
+    /// Systems detecting fulfillment of [`VictoryCondition`]s and sending
+    /// [`GameEndedEvent`] are placed in this set.
+    Detect,
+    End,
+}
+
+/// A condition of a game end, see [`GameConfig::victory`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VictoryCondition {
+    /// Players are eliminated once all of their objects are destroyed.
+    Annihilation,
+    /// Players are eliminated once all of their bases (headquarters) are
+    /// destroyed.
+    DestroyHq,
+    /// The game ends after the given time (since the start of the game) and
+    /// the player with the highest score wins.
+    TimeLimit(Duration),
+    /// The first player whose score reaches the given value wins.
+    Score(u32),
+}
+
+/// This event is sent when the game ends. All clients of a multiplayer game
+/// receive the event.
+///
+/// The winner wins together with their allies. There is no winner if the
+/// game ended with a draw.
+pub struct GameEndedEvent {
+    winner: Option<Player>,
+    received: bool,
+}
+
+impl GameEndedEvent {
+    /// Creates an event of a game end detected locally.
+    pub fn new(winner: Option<Player>) -> Self {
+        Self {
+            winner,
+            received: false,
+        }
+    }
+
+    /// Creates an event of a game end received from another client of a
+    /// multiplayer game.
+    pub fn received(winner: Option<Player>) -> Self {
+        Self {
+            winner,
+            received: true,
+        }
+    }
+
+    pub fn winner(&self) -> Option<Player> {
+        self.winner
+    }
+
+    /// Returns true if the game end was detected by a different client.
+    pub fn is_received(&self) -> bool {
+        self.received
+    }
+}
+
+fn end_game(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    conf: Res<GameConfig>,
+    diplomacy: Res<Diplomacy>,
+    mut events: EventReader<GameEndedEvent>,
+) {
+    // Only the first game end counts.
+    let Some(winner) = events.iter().next().map(GameEndedEvent::winner) else {
+        return;
+    };
+    events.clear();
+
+    match winner {
+        Some(winner) => info!("The game ended, {winner} won."),
+        None => info!("The game ended with a draw."),
+    }
+
+    // A draw is not a victory.
+    let won = winner.map_or(false, |winner| {
+        diplomacy.are_allies(conf.locals().playable(), winner)
+    });
+    commands.insert_resource(GameResult::finished(won));
+    next_state.set(AppState::InMenu);
+}
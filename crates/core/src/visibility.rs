@@ -1,6 +1,9 @@
-use bevy::prelude::*;
+use bevy::{ecs::query::BatchingStrategy, prelude::*, render::view::VisibilitySystems};
 
-use crate::{baseset::GameSet, flags::Flags, state::AppState};
+use crate::{flags::Flags, state::AppState};
+
+/// Number of entities updated in a single parallel batch.
+const BATCH_SIZE: usize = 256;
 
 pub(crate) struct VisibilityPlugin;
 
@@ -8,9 +11,10 @@ impl Plugin for VisibilityPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(
             update
-                .in_base_set(GameSet::PostUpdate)
+                .in_base_set(CoreSet::PostUpdate)
                 .run_if(in_state(AppState::InGame))
-                .in_set(VisibilitySet::Update),
+                .in_set(VisibilitySet::Update)
+                .before(VisibilitySystems::VisibilityPropagate),
         );
     }
 }
@@ -24,13 +28,18 @@ pub enum VisibilitySet {
 /// "visible" flag is set to true and none of "invisible" flag is true. The
 /// individual flags can be controlled independently.
 ///
+/// Entities with this component and with an
+/// [`bevy::render::primitives::Aabb`] are additionally invisible while
+/// outside of the camera frustum, see [`crate::culling`].
+///
 /// The system [`VisibilitySet::Update`] executed during
-/// [`GameSet::PostUpdate`] automatically updates
+/// [`CoreSet::PostUpdate`] automatically updates
 /// [`bevy::render::prelude::Visibility`] of entities with this component.
 #[derive(Component, Default)]
 pub struct VisibilityFlags {
     visible: Flags,
     invisible: Flags,
+    culled: bool,
 }
 
 impl VisibilityFlags {
@@ -52,19 +61,30 @@ impl VisibilityFlags {
         self.invisible.get(bit)
     }
 
+    pub(crate) fn set_culled(&mut self, culled: bool) {
+        self.culled = culled;
+    }
+
     pub fn visible(&self) -> bool {
-        !self.invisible.any() && self.visible.any()
+        !self.culled && !self.invisible.any() && self.visible.any()
     }
 }
 
 fn update(mut entities: Query<(&VisibilityFlags, &mut Visibility), Changed<VisibilityFlags>>) {
-    for (flags, mut visibility) in entities.iter_mut() {
-        *visibility = if flags.visible() {
-            Visibility::Inherited
-        } else {
-            Visibility::Hidden
-        };
-    }
+    entities
+        .par_iter_mut()
+        .batching_strategy(BatchingStrategy::fixed(BATCH_SIZE))
+        .for_each_mut(|(flags, mut visibility)| {
+            let value = if flags.visible() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            // Do not trigger change detection unnecessarily.
+            if *visibility != value {
+                *visibility = value;
+            }
+        });
 }
 
 #[cfg(test)]
@@ -95,5 +115,10 @@ mod tests {
         flags.update_invisible(1, false);
         assert!(flags.visible());
         assert!(!flags.invisible_value(1));
+
+        flags.set_culled(true);
+        assert!(!flags.visible());
+        flags.set_culled(false);
+        assert!(flags.visible());
     }
 }
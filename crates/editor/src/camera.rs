@@ -0,0 +1,240 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    pbr::CascadeShadowConfigBuilder,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use de_conf::Configuration;
+use de_core::{
+    projection::{ToAltitude, ToFlat},
+    state::AppState,
+};
+use de_terrain::TerrainCollider;
+use parry3d::query::Ray;
+
+use crate::{document::EditedMap, loading::DespawnOnEditorExit};
+
+/// Angle between the camera view direction and nadir.
+const OFF_NADIR: f32 = FRAC_PI_4;
+/// Horizontal camera speed relative to the camera distance, per second.
+const MOVEMENT_SPEED: f32 = 1.2;
+/// Camera rotation speed in radians per second.
+const ROTATION_SPEED: f32 = 1.5;
+
+pub(crate) struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InEditor)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InEditor)))
+            .add_system(
+                move_camera
+                    .run_if(in_state(AppState::InEditor))
+                    .in_set(CameraSet::Move),
+            )
+            .add_system(
+                update_pointer
+                    .run_if(in_state(AppState::InEditor))
+                    .in_set(CameraSet::Pointer)
+                    .after(CameraSet::Move),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum CameraSet {
+    Move,
+    /// [`Pointer`] is updated in this set.
+    Pointer,
+}
+
+/// Point of the map under the mouse cursor. It is None when the cursor is
+/// out of the window or above a [`PointerBlocker`].
+#[derive(Resource, Default)]
+pub(crate) struct Pointer(Option<Vec2>);
+
+impl Pointer {
+    pub(crate) fn position(&self) -> Option<Vec2> {
+        self.0
+    }
+}
+
+/// The map is not pointed at while the mouse cursor is above a UI node with
+/// this component. The node must have [`Interaction`] component.
+#[derive(Component)]
+pub(crate) struct PointerBlocker;
+
+#[derive(Resource)]
+struct EditorCamera {
+    focus: Vec2,
+    distance: f32,
+    azimuth: f32,
+}
+
+impl EditorCamera {
+    fn transform(&self) -> Transform {
+        let focus = self.focus.to_msl();
+        let offset = Quat::from_rotation_y(self.azimuth)
+            * Vec3::new(0., OFF_NADIR.cos(), OFF_NADIR.sin())
+            * self.distance;
+        Transform::from_translation(focus + offset).looking_at(focus, Vec3::Y)
+    }
+
+    /// Returns flat direction to the top and to the right of the screen.
+    fn directions(&self) -> (Vec2, Vec2) {
+        let rotation = Quat::from_rotation_y(self.azimuth);
+        (
+            (rotation * Vec3::NEG_Z).to_flat(),
+            (rotation * Vec3::X).to_flat(),
+        )
+    }
+}
+
+fn setup(mut commands: Commands, conf: Res<Configuration>) {
+    let conf = conf.camera();
+    let min_distance: f32 = conf.min_distance().into();
+    let max_distance: f32 = conf.max_distance().into();
+
+    let camera = EditorCamera {
+        focus: Vec2::ZERO,
+        distance: 0.5 * (min_distance + max_distance),
+        azimuth: 0.,
+    };
+    commands.spawn((
+        Camera3dBundle {
+            transform: camera.transform(),
+            ..default()
+        },
+        DespawnOnEditorExit,
+    ));
+    commands.insert_resource(camera);
+    commands.init_resource::<Pointer>();
+
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 0.6,
+    });
+    let mut transform = Transform::IDENTITY;
+    transform.look_at(Vec3::new(1., -1., 0.), Vec3::new(1., 1., 0.));
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: Color::WHITE,
+                illuminance: 30000.,
+                shadows_enabled: true,
+                ..default()
+            },
+            cascade_shadow_config: CascadeShadowConfigBuilder {
+                maximum_distance: 2. * max_distance,
+                first_cascade_far_bound: 2. * min_distance,
+                ..default()
+            }
+            .build(),
+            transform,
+            ..default()
+        },
+        DespawnOnEditorExit,
+    ));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<EditorCamera>();
+    commands.remove_resource::<Pointer>();
+}
+
+fn move_camera(
+    conf: Res<Configuration>,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    mut wheel_events: EventReader<MouseWheel>,
+    map: Option<Res<EditedMap>>,
+    mut camera: ResMut<EditorCamera>,
+    mut camera_query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let conf = conf.camera();
+    let delta = time.delta_seconds();
+
+    let (up, right) = camera.directions();
+    let mut direction = Vec2::ZERO;
+    for (keys_pressed, key_direction) in [
+        ([KeyCode::W, KeyCode::Up], up),
+        ([KeyCode::S, KeyCode::Down], -up),
+        ([KeyCode::D, KeyCode::Right], right),
+        ([KeyCode::A, KeyCode::Left], -right),
+    ] {
+        if keys.any_pressed(keys_pressed) {
+            direction += key_direction;
+        }
+    }
+    if direction != Vec2::ZERO {
+        let mut focus = camera.focus + direction * MOVEMENT_SPEED * camera.distance * delta;
+        if let Some(map) = map {
+            let bounds = map.map().metadata().bounds();
+            focus = focus.clamp(bounds.min(), bounds.max());
+        }
+        camera.focus = focus;
+    }
+
+    if keys.pressed(KeyCode::Q) {
+        camera.azimuth -= ROTATION_SPEED * delta;
+    }
+    if keys.pressed(KeyCode::E) {
+        camera.azimuth += ROTATION_SPEED * delta;
+    }
+
+    let factor = wheel_events
+        .iter()
+        .fold(1.0, |factor, event| match event.unit {
+            MouseScrollUnit::Line => factor * conf.wheel_zoom_sensitivity().powf(event.y),
+            MouseScrollUnit::Pixel => factor * conf.touchpad_zoom_sensitivity().powf(event.y),
+        });
+    if factor != 1. {
+        let distance = if conf.scroll_inverted() {
+            camera.distance / factor
+        } else {
+            camera.distance * factor
+        };
+        camera.distance = distance.clamp(conf.min_distance().into(), conf.max_distance().into());
+    }
+
+    if camera.is_changed() {
+        *camera_query.single_mut() = camera.transform();
+    }
+}
+
+fn update_pointer(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &Camera), With<Camera3d>>,
+    blockers: Query<&Interaction, With<PointerBlocker>>,
+    terrain: TerrainCollider,
+    mut pointer: ResMut<Pointer>,
+) {
+    let window = window_query.single();
+    let blocked = blockers
+        .iter()
+        .any(|&interaction| interaction != Interaction::None);
+    let position = window
+        .cursor_position()
+        .filter(|_| !blocked)
+        .and_then(|position| {
+            let ndc = 2. * position / Vec2::new(window.width(), window.height()) - Vec2::ONE;
+
+            let (camera_transform, camera) = camera_query.single();
+            let ndc_to_world =
+                camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+            let ray_origin = ndc_to_world.project_point3(ndc.extend(1.));
+            let ray_direction = (ray_origin - camera_transform.translation).normalize();
+            let ray = Ray::new(ray_origin.into(), ray_direction.into());
+
+            terrain
+                .cast_ray_msl(&ray, f32::INFINITY)
+                .map(|intersection| Vec3::from(ray.point_at(intersection.toi)).to_flat())
+        });
+
+    // Avoid unnecessary change detection.
+    if pointer.0 != position {
+        pointer.0 = position;
+    }
+}
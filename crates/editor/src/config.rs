@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::Resource;
+
+/// Configuration of a map editing session.
+///
+/// This resource is automatically removed when
+/// [`de_core::state::AppState::InEditor`] is exited.
+#[derive(Resource)]
+pub struct EditorConfig {
+    map_path: Option<PathBuf>,
+    map_seed: Option<u64>,
+}
+
+impl EditorConfig {
+    /// Creates configuration for editing of a new empty map.
+    pub fn new_map() -> Self {
+        Self {
+            map_path: None,
+            map_seed: None,
+        }
+    }
+
+    /// Creates configuration for editing of a new map randomly generated
+    /// from `seed`, see [`de_map::generator::generate`].
+    pub fn generated(seed: u64) -> Self {
+        Self {
+            map_path: None,
+            map_seed: Some(seed),
+        }
+    }
+
+    /// Creates configuration for editing of an existing map.
+    ///
+    /// # Arguments
+    ///
+    /// * `map_path` - path to the map file. Relative paths are relative to
+    ///   the assets directory.
+    pub fn open<P: Into<PathBuf>>(map_path: P) -> Self {
+        Self {
+            map_path: Some(map_path.into()),
+            map_seed: None,
+        }
+    }
+
+    /// Path to the edited map file or None if a new map is edited.
+    pub fn map_path(&self) -> Option<&Path> {
+        self.map_path.as_deref()
+    }
+
+    /// Seed of the edited new map or None if the new map is empty or an
+    /// existing map is edited.
+    pub fn map_seed(&self) -> Option<u64> {
+        self.map_seed
+    }
+}
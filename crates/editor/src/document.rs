@@ -0,0 +1,598 @@
+use std::{
+    error::Error,
+    f32::consts::TAU,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::{Resource, Vec2};
+use de_core::{
+    objects::{ActiveObjectType, BuildingType, ObjectType},
+    player::Player,
+};
+use de_map::{
+    content::{ActiveObject, InactiveObject, InnerObject, Object},
+    heightmap::{Heightmap, MAX_HEIGHTMAP_RESOLUTION, MAX_TERRAIN_HEIGHT},
+    map::{Map, MapValidationError},
+    meta::MapMetadata,
+    size::{MapBounds, MAX_MAP_SIZE},
+    zones::{TerrainZone, ZoneKind, MAX_ZONES, MAX_ZONE_VERTICES, ZONE_BOUNDARY_MARGIN},
+};
+use thiserror::Error;
+
+/// Distance between neighbouring samples of heightmaps created by the
+/// editor.
+const HEIGHTMAP_SPACING: f32 = 8.;
+/// Objects closer than this to a point of the map can be picked by the
+/// point.
+const PICK_DISTANCE: f32 = 10.;
+/// Minimum size of a side of an edited map in meters.
+pub(crate) const MIN_MAP_SIZE: f32 = 200.;
+
+/// The map being edited together with its pending changes.
+#[derive(Resource)]
+pub(crate) struct EditedMap {
+    map: Map,
+    path: Option<PathBuf>,
+    unsaved: bool,
+    terrain_changed: bool,
+    objects_changed: bool,
+}
+
+impl EditedMap {
+    /// # Arguments
+    ///
+    /// * `map` - the map to be edited.
+    ///
+    /// * `path` - path to the map file. It is None for new maps which have
+    ///   never been stored.
+    pub(crate) fn new(map: Map, path: Option<PathBuf>) -> Self {
+        Self {
+            map,
+            path,
+            unsaved: false,
+            terrain_changed: true,
+            objects_changed: true,
+        }
+    }
+
+    pub(crate) fn map(&self) -> &Map {
+        &self.map
+    }
+
+    pub(crate) fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Returns true if the map has been modified since it was loaded or last
+    /// stored.
+    pub(crate) fn unsaved(&self) -> bool {
+        self.unsaved
+    }
+
+    /// Marks the map as stored to a file.
+    pub(crate) fn mark_saved(&mut self, path: PathBuf) {
+        self.path = Some(path);
+        self.unsaved = false;
+    }
+
+    /// Returns true (only once) if the terrain, zones or bounds have been
+    /// modified since the last call.
+    pub(crate) fn take_terrain_changed(&mut self) -> bool {
+        std::mem::take(&mut self.terrain_changed)
+    }
+
+    /// Returns true (only once) if objects have been modified since the last
+    /// call.
+    pub(crate) fn take_objects_changed(&mut self) -> bool {
+        std::mem::take(&mut self.objects_changed)
+    }
+
+    /// Returns elevation of the terrain of the map at a point.
+    pub(crate) fn elevation(&self, point: Vec2) -> f32 {
+        self.map.heightmap().map_or(0., |heightmap| {
+            heightmap.height(self.map.metadata().bounds(), point)
+        })
+    }
+
+    /// Returns index of the object closest to a point or None if there is
+    /// no object near the point.
+    pub(crate) fn object_at(&self, point: Vec2) -> Option<usize> {
+        self.map
+            .content()
+            .objects()
+            .iter()
+            .enumerate()
+            .map(|(index, object)| (index, object.placement().position().distance(point)))
+            .filter(|&(_, distance)| distance <= PICK_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(index, _)| index)
+    }
+
+    /// Places a new object to the map.
+    ///
+    /// # Arguments
+    ///
+    /// * `object_type` - type of the object.
+    ///
+    /// * `player` - owner of the object. It is ignored for inactive objects.
+    ///
+    /// * `position` - position of the object on the map.
+    ///
+    /// * `heading` - rotation of the object in radians, see
+    ///   [`Map::new_placement`].
+    pub(crate) fn place_object(
+        &mut self,
+        object_type: ObjectType,
+        player: Player,
+        position: Vec2,
+        heading: f32,
+    ) -> Result<(), EditError> {
+        self.check_position(position)?;
+
+        let inner = match object_type {
+            ObjectType::Active(active_type) => {
+                if player > self.map.metadata().max_player() {
+                    return Err(EditError::InvalidPlayer(player));
+                }
+                if self.map.zones().impassable_at(position).is_some() {
+                    return Err(EditError::Impassable);
+                }
+                InnerObject::Active(ActiveObject::new(active_type, player))
+            }
+            ObjectType::Inactive(inactive_type) => {
+                InnerObject::Inactive(InactiveObject::new(inactive_type))
+            }
+        };
+
+        let placement = self.map.new_placement(position, normalize_heading(heading));
+        self.map.insert_object(Object::new(placement, inner));
+        self.objects_modified();
+        Ok(())
+    }
+
+    /// Rotates an object counter clockwise by `angle` radians.
+    pub(crate) fn rotate_object(&mut self, index: usize, angle: f32) {
+        let object = self.map.remove_object(index);
+        let placement = object.placement();
+        let placement = self.map.new_placement(
+            placement.position(),
+            normalize_heading(placement.heading() + angle),
+        );
+        self.map
+            .insert_object(Object::new(placement, object.inner().clone()));
+        self.objects_modified();
+    }
+
+    pub(crate) fn remove_object(&mut self, index: usize) {
+        self.map.remove_object(index);
+        self.objects_modified();
+    }
+
+    /// Sets the spawn position of a player, i.e. the position of its base.
+    /// Any previous base of the player is removed.
+    pub(crate) fn set_spawn(&mut self, player: Player, position: Vec2) -> Result<(), EditError> {
+        self.check_position(position)?;
+        if player > self.map.metadata().max_player() {
+            return Err(EditError::InvalidPlayer(player));
+        }
+        if self.map.zones().impassable_at(position).is_some() {
+            return Err(EditError::Impassable);
+        }
+
+        while let Some(index) = self.spawn_index(player) {
+            self.map.remove_object(index);
+        }
+
+        self.place_object(
+            ObjectType::Active(ActiveObjectType::Building(BuildingType::Base)),
+            player,
+            position,
+            0.,
+        )
+    }
+
+    /// Returns the spawn position of a player, i.e. the position of its base.
+    pub(crate) fn spawn(&self, player: Player) -> Option<Vec2> {
+        self.spawn_index(player)
+            .map(|index| self.map.content().objects()[index].placement().position())
+    }
+
+    fn spawn_index(&self, player: Player) -> Option<usize> {
+        self.map
+            .content()
+            .objects()
+            .iter()
+            .position(|object| match object.inner() {
+                InnerObject::Active(active) => {
+                    active.player() == player
+                        && active.object_type() == ActiveObjectType::Building(BuildingType::Base)
+                }
+                InnerObject::Inactive(_) => false,
+            })
+    }
+
+    /// Raises (or lowers if `delta` is negative) the terrain around a point.
+    /// The change smoothly decreases with the distance from `center` and is
+    /// zero at `radius`.
+    pub(crate) fn paint_height(&mut self, center: Vec2, radius: f32, delta: f32) {
+        let bounds = self.map.metadata().bounds();
+        let (columns, rows, mut heights) = match self.map.heightmap() {
+            Some(heightmap) => (
+                heightmap.columns(),
+                heightmap.rows(),
+                heightmap.heights().to_vec(),
+            ),
+            None => {
+                let samples = |size: f32| {
+                    ((size / HEIGHTMAP_SPACING).ceil() as usize + 1)
+                        .clamp(2, MAX_HEIGHTMAP_RESOLUTION)
+                };
+                let columns = samples(bounds.size().x);
+                let rows = samples(bounds.size().y);
+                (columns, rows, vec![0.; columns * rows])
+            }
+        };
+
+        let cell = bounds.size() / Vec2::new((columns - 1) as f32, (rows - 1) as f32);
+        let mut changed = false;
+        for row in 0..rows {
+            for column in 0..columns {
+                let point = bounds.min() + Vec2::new(column as f32, row as f32) * cell;
+                let distance = point.distance_squared(center) / (radius * radius);
+                if distance >= 1. {
+                    continue;
+                }
+
+                let height = &mut heights[row * columns + column];
+                let new_height = (*height + delta * (1. - distance)).clamp(0., MAX_TERRAIN_HEIGHT);
+                changed |= new_height != *height;
+                *height = new_height;
+            }
+        }
+
+        if changed {
+            self.map
+                .set_heightmap(Heightmap::new(columns, rows, heights));
+            self.terrain_modified();
+            // Objects are placed on the terrain.
+            self.objects_modified();
+        }
+    }
+
+    /// Inserts a regular polygon shaped terrain zone to the map.
+    pub(crate) fn insert_zone(
+        &mut self,
+        kind: ZoneKind,
+        center: Vec2,
+        radius: f32,
+    ) -> Result<(), EditError> {
+        if self.map.zones().zones().len() >= MAX_ZONES {
+            return Err(EditError::MaxZones);
+        }
+
+        let vertices: Vec<Vec2> = (0..MAX_ZONE_VERTICES)
+            .map(|index| {
+                let angle = TAU * index as f32 / MAX_ZONE_VERTICES as f32;
+                center + radius * Vec2::from_angle(angle)
+            })
+            .collect();
+
+        let bounds = self.map.metadata().bounds();
+        let margin = Vec2::splat(ZONE_BOUNDARY_MARGIN);
+        if vertices.iter().any(|vertex| {
+            vertex.cmplt(bounds.min() + margin).any() || vertex.cmpgt(bounds.max() - margin).any()
+        }) {
+            return Err(EditError::ZoneBoundary);
+        }
+
+        let zone = TerrainZone::new(kind, vertices);
+        if kind.impassable()
+            && self
+                .map
+                .content()
+                .objects()
+                .iter()
+                .any(|object| match object.inner() {
+                    InnerObject::Active(_) => zone.contains(object.placement().position()),
+                    InnerObject::Inactive(_) => false,
+                })
+        {
+            return Err(EditError::Impassable);
+        }
+
+        self.map.insert_zone(zone);
+        self.terrain_modified();
+        Ok(())
+    }
+
+    /// Removes the top-most terrain zone containing a point.
+    pub(crate) fn remove_zone_at(&mut self, point: Vec2) -> Result<(), EditError> {
+        let index = self
+            .map
+            .zones()
+            .zones()
+            .iter()
+            .rposition(|zone| zone.contains(point))
+            .ok_or(EditError::NoZone(point))?;
+        self.map.remove_zone(index);
+        self.terrain_modified();
+        Ok(())
+    }
+
+    /// Sets the maximum number of players of the map.
+    pub(crate) fn set_max_player(&mut self, max_player: Player) -> Result<(), EditError> {
+        let metadata = self.map.metadata();
+        if max_player < Player::Player2 {
+            return Err(EditError::MinPlayers);
+        }
+
+        self.set_metadata(MapMetadata::new(
+            metadata.name().to_owned(),
+            metadata.bounds(),
+            max_player,
+        ))
+    }
+
+    /// Resizes the map. Objects and zones are kept at their positions, thus
+    /// they have to fit into the new map bounds.
+    pub(crate) fn resize(&mut self, size: Vec2) -> Result<(), EditError> {
+        if size.cmplt(Vec2::splat(MIN_MAP_SIZE)).any()
+            || size.cmpgt(Vec2::splat(MAX_MAP_SIZE)).any()
+        {
+            return Err(EditError::MapSize(size));
+        }
+
+        let metadata = self.map.metadata();
+        self.set_metadata(MapMetadata::new(
+            metadata.name().to_owned(),
+            MapBounds::new(size),
+            metadata.max_player(),
+        ))?;
+        self.terrain_modified();
+        Ok(())
+    }
+
+    fn set_metadata(&mut self, metadata: MapMetadata) -> Result<(), EditError> {
+        self.map
+            .set_metadata(metadata)
+            .map_err(|source| EditError::Validation { source })?;
+        self.unsaved = true;
+        Ok(())
+    }
+
+    fn check_position(&self, position: Vec2) -> Result<(), EditError> {
+        if self.map.metadata().bounds().contains(position) {
+            Ok(())
+        } else {
+            Err(EditError::OutOfBounds(position))
+        }
+    }
+
+    fn terrain_modified(&mut self) {
+        self.terrain_changed = true;
+        self.unsaved = true;
+    }
+
+    fn objects_modified(&mut self) {
+        self.objects_changed = true;
+        self.unsaved = true;
+    }
+}
+
+/// Returns an equivalent heading between 0 (inclusive) and 2π (exclusive).
+fn normalize_heading(heading: f32) -> f32 {
+    let heading = heading.rem_euclid(TAU);
+    // Rounding may lead to 2π.
+    if heading < TAU {
+        heading
+    } else {
+        0.
+    }
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum EditError {
+    #[error("position ({}, {}) is out of map bounds", .0.x, .0.y)]
+    OutOfBounds(Vec2),
+    #[error("{0} cannot play on the map")]
+    InvalidPlayer(Player),
+    #[error("map has to have at least 2 players")]
+    MinPlayers,
+    #[error("active objects cannot be placed on water or cliffs")]
+    Impassable,
+    #[error("the map already has the maximum number of terrain zones ({MAX_ZONES})")]
+    MaxZones,
+    #[error(
+        "terrain zones have to be at least {ZONE_BOUNDARY_MARGIN} meters from the map boundaries"
+    )]
+    ZoneBoundary,
+    #[error("there is no terrain zone at ({}, {})", .0.x, .0.y)]
+    NoZone(Vec2),
+    #[error(
+        "map size has to be between {MIN_MAP_SIZE} and {MAX_MAP_SIZE}, got ({}, {})",
+        .0.x, .0.y
+    )]
+    MapSize(Vec2),
+    #[error("the map would become invalid")]
+    Validation { source: MapValidationError },
+}
+
+/// Returns the error message followed by messages of all its sources.
+pub(crate) fn full_error_message(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut error = error;
+    while let Some(source) = error.source() {
+        error = source;
+        write!(&mut message, ": {error}").unwrap();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+    use de_core::objects::{InactiveObjectType, UnitType};
+    use de_map::io::{load_map, store_map};
+    use tempfile::Builder;
+
+    use super::*;
+
+    fn new_map() -> EditedMap {
+        EditedMap::new(
+            Map::empty(MapMetadata::new(
+                "Test".into(),
+                MapBounds::new(Vec2::new(400., 300.)),
+                Player::Player2,
+            )),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_objects() {
+        let mut map = new_map();
+        assert!(map.take_objects_changed());
+        assert!(!map.take_objects_changed());
+        assert!(!map.unsaved());
+
+        map.place_object(
+            ObjectType::Inactive(InactiveObjectType::Tree),
+            Player::Player4,
+            Vec2::new(10., 20.),
+            -1.,
+        )
+        .unwrap();
+        assert!(map.unsaved());
+        assert!(map.take_objects_changed());
+        assert_eq!(
+            map.place_object(
+                ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)),
+                Player::Player3,
+                Vec2::new(10., 20.),
+                0.,
+            )
+            .unwrap_err()
+            .to_string(),
+            "player 3 cannot play on the map"
+        );
+        assert!(map
+            .place_object(
+                ObjectType::Inactive(InactiveObjectType::Tree),
+                Player::Player1,
+                Vec2::new(300., 20.),
+                0.,
+            )
+            .is_err());
+
+        assert_eq!(map.object_at(Vec2::new(14., 17.)), Some(0));
+        assert_eq!(map.object_at(Vec2::new(30., 20.)), None);
+        let heading = map.map().content().objects()[0].placement().heading();
+        assert!((heading - (TAU - 1.)).abs() < 1e-5);
+
+        map.rotate_object(0, 2.);
+        let heading = map.map().content().objects()[0].placement().heading();
+        assert!((heading - 1.).abs() < 1e-5);
+
+        map.set_spawn(Player::Player1, Vec2::new(-100., 0.))
+            .unwrap();
+        map.set_spawn(Player::Player1, Vec2::new(-120., 0.))
+            .unwrap();
+        map.set_spawn(Player::Player2, Vec2::new(120., 0.)).unwrap();
+        assert_eq!(map.map().content().objects().len(), 3);
+        assert_eq!(map.spawn(Player::Player1), Some(Vec2::new(-120., 0.)));
+        assert_eq!(map.spawn(Player::Player2), Some(Vec2::new(120., 0.)));
+
+        map.remove_object(0);
+        assert_eq!(map.map().content().objects().len(), 2);
+        assert_eq!(map.object_at(Vec2::new(10., 20.)), None);
+    }
+
+    #[test]
+    fn test_terrain() {
+        let mut map = new_map();
+        assert!(map.take_terrain_changed());
+
+        map.paint_height(Vec2::new(0., 0.), 20., 4.);
+        assert!(map.take_terrain_changed());
+        let heightmap = map.map().heightmap().unwrap();
+        assert_eq!(heightmap.columns(), 51);
+        assert_eq!(heightmap.rows(), 39);
+        assert!((map.elevation(Vec2::ZERO) - 4.).abs() < 0.2);
+        assert_eq!(map.elevation(Vec2::new(30., 0.)), 0.);
+
+        map.paint_height(Vec2::new(0., 0.), 20., -100.);
+        assert_eq!(map.elevation(Vec2::ZERO), 0.);
+
+        map.insert_zone(ZoneKind::Water, Vec2::new(50., 50.), 20.)
+            .unwrap();
+        assert!(map.take_terrain_changed());
+        assert!(map
+            .insert_zone(ZoneKind::Water, Vec2::new(190., 0.), 20.)
+            .is_err());
+        assert!(map
+            .place_object(
+                ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)),
+                Player::Player1,
+                Vec2::new(50., 50.),
+                0.,
+            )
+            .is_err());
+
+        assert!(map.remove_zone_at(Vec2::new(-50., -50.)).is_err());
+        map.remove_zone_at(Vec2::new(55., 45.)).unwrap();
+        assert!(map.map().zones().is_empty());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut map = new_map();
+        map.set_spawn(Player::Player2, Vec2::new(180., 0.)).unwrap();
+
+        assert!(map.set_max_player(Player::Player1).is_err());
+        map.set_max_player(Player::Player4).unwrap();
+        map.set_spawn(Player::Player4, Vec2::new(-180., 0.))
+            .unwrap();
+        assert!(map.set_max_player(Player::Player3).is_err());
+        assert_eq!(map.map().metadata().max_player(), Player::Player4);
+
+        assert!(map.resize(Vec2::new(100., 300.)).is_err());
+        assert_eq!(
+            full_error_message(&map.resize(Vec2::new(300., 300.)).unwrap_err()),
+            "the map would become invalid: invalid map content: invalid objects[0]: \
+             invalid object placement: position (180, 0) is out of map bounds"
+        );
+        map.resize(Vec2::new(500., 300.)).unwrap();
+        assert_eq!(map.map().metadata().bounds().size(), Vec2::new(500., 300.));
+    }
+
+    #[test]
+    fn test_store_load() {
+        let mut map = new_map();
+        map.set_spawn(Player::Player1, Vec2::new(-150., 0.))
+            .unwrap();
+        map.set_spawn(Player::Player2, Vec2::new(150., 0.)).unwrap();
+        map.place_object(
+            ObjectType::Inactive(InactiveObjectType::Tree),
+            Player::Player1,
+            Vec2::new(0., 100.),
+            1.,
+        )
+        .unwrap();
+        map.paint_height(Vec2::new(-50., 50.), 40., 10.);
+        map.insert_zone(ZoneKind::Crater, Vec2::new(50., -50.), 30.)
+            .unwrap();
+        map.map().validate().unwrap();
+
+        let tmp_dir = Builder::new().prefix("de_editor_").tempdir().unwrap();
+        let path = tmp_dir.path().join("edited.dem.tar");
+        task::block_on(store_map(map.map(), path.as_path())).unwrap();
+        map.mark_saved(path.clone());
+        assert!(!map.unsaved());
+
+        let loaded = task::block_on(load_map(path.as_path())).unwrap();
+        assert_eq!(loaded.compute_hash(), map.map().compute_hash());
+        assert_eq!(loaded.heightmap(), map.map().heightmap());
+        assert_eq!(loaded.zones(), map.map().zones());
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt::Write;
+
+use bevy::prelude::*;
+use de_core::{player::Player, state::AppState};
+use de_gui::{BodyTextCommands, BodyTextOps, GuiCommands, OuterStyle};
+use de_loc::Localize;
+
+use crate::{
+    camera::PointerBlocker,
+    document::EditedMap,
+    loading::DespawnOnEditorExit,
+    tools::{Tools, ToolsSet},
+};
+
+const HUD_COLOR: Color = Color::rgba(0., 0., 0., 0.6);
+/// Messages describing key bindings of the editor, one line each.
+const KEY_BINDINGS: [&str; 4] = [
+    "editor-keys-tools",
+    "editor-keys-types",
+    "editor-keys-map",
+    "editor-keys-general",
+];
+
+pub(crate) struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InEditor)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InEditor)))
+            .add_system(
+                update
+                    .run_if(in_state(AppState::InEditor))
+                    .after(ToolsSet::Apply),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct HudText(Entity);
+
+fn setup(mut commands: GuiCommands, localize: Res<Localize>) {
+    let node = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(45.), Val::Percent(25.)),
+                    position_type: PositionType::Absolute,
+                    position: UiRect::new(
+                        Val::Percent(0.),
+                        Val::Percent(55.),
+                        Val::Percent(0.),
+                        Val::Percent(75.),
+                    ),
+                    ..default()
+                },
+                background_color: HUD_COLOR.into(),
+                ..default()
+            },
+            Interaction::default(),
+            PointerBlocker,
+            DespawnOnEditorExit,
+        ))
+        .id();
+    let text = commands
+        .spawn_body_text(
+            OuterStyle {
+                size: Size::new(Val::Percent(96.), Val::Percent(90.)),
+                margin: UiRect::all(Val::Percent(2.)),
+            },
+            localize.get("editor-loading"),
+        )
+        .id();
+    commands.entity(node).add_child(text);
+    commands.insert_resource(HudText(text));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<HudText>();
+}
+
+fn update(
+    hud: Res<HudText>,
+    map: Option<Res<EditedMap>>,
+    tools: Res<Tools>,
+    localize: Res<Localize>,
+    mut text_ops: BodyTextOps,
+) {
+    let Some(map) = map else {
+        return;
+    };
+
+    let metadata = map.map().metadata();
+    let size = metadata.bounds().size();
+    let mut text = localize.format(
+        if map.unsaved() {
+            "editor-map-unsaved"
+        } else {
+            "editor-map"
+        },
+        &[
+            ("name", &metadata.name()),
+            ("width", &size.x),
+            ("height", &size.y),
+            ("players", &metadata.max_player().to_num()),
+        ],
+    );
+    text.push('\n');
+    let file = match map.path() {
+        Some(path) => localize.format("editor-file", &[("path", &path.display())]),
+        None => localize.get("editor-file-none"),
+    };
+    writeln!(text, "{file}").unwrap();
+    text.push_str(&localize.get("editor-spawns"));
+    for num in 1..=metadata.max_player().to_num() {
+        let player = Player::try_from(num).unwrap();
+        match map.spawn(player) {
+            Some(position) => write!(text, "  {num}: ({:.0}, {:.0})", position.x, position.y),
+            None => write!(text, "  {num}: {}", localize.get("editor-spawn-none")),
+        }
+        .unwrap();
+    }
+    let tool = localize.format("editor-tool", &[("tool", &tools.description(&localize))]);
+    writeln!(text, "\n{tool}\n").unwrap();
+    for (i, id) in KEY_BINDINGS.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        text.push_str(&localize.get(id));
+    }
+
+    text_ops.set_text(hud.0, text).unwrap();
+}
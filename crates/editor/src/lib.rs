@@ -0,0 +1,40 @@
+//! In-game map editor.
+//!
+//! The editor is entered via [`de_core::state::AppState::InEditor`] after
+//! [`EditorConfig`] is inserted. Objects, player spawn positions, terrain
+//! elevation, terrain zones and map parameters are edited with tools selected
+//! by the keyboard and applied with the mouse. Key bindings are listed in
+//! the editor HUD. The map is stored in the standard `de_map` format so that
+//! it can be played right away.
+
+use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+use camera::CameraPlugin;
+pub use config::EditorConfig;
+use hud::HudPlugin;
+use loading::LoadingPlugin;
+use save::SavePlugin;
+use tools::ToolsPlugin;
+use visuals::VisualsPlugin;
+
+mod camera;
+mod config;
+mod document;
+mod hud;
+mod loading;
+mod save;
+mod tools;
+mod visuals;
+
+pub struct EditorPluginGroup;
+
+impl PluginGroup for EditorPluginGroup {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(LoadingPlugin)
+            .add(CameraPlugin)
+            .add(ToolsPlugin)
+            .add(VisualsPlugin)
+            .add(HudPlugin)
+            .add(SavePlugin)
+    }
+}
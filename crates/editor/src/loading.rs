@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task},
+};
+use de_core::{assets::asset_path, log_full_error, player::Player, state::AppState};
+use de_gui::ToastEvent;
+use de_loc::Localize;
+use de_map::{
+    generator,
+    io::{load_map, MapLoadingError},
+    map::Map,
+    meta::MapMetadata,
+    size::MapBounds,
+    zones::TerrainZones,
+};
+use futures_lite::future;
+
+use crate::{config::EditorConfig, document::EditedMap};
+
+const NEW_MAP_NAME: &str = "New Map";
+const NEW_MAP_SIZE: Vec2 = Vec2::splat(1000.);
+
+pub(crate) struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InEditor)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InEditor)))
+            .add_system(
+                finish_loading
+                    .run_if(in_state(AppState::InEditor))
+                    .run_if(resource_exists::<MapLoadingTask>()),
+            );
+    }
+}
+
+/// Mark all entities which should be recursively despawned after the editor
+/// is exited with this component.
+#[derive(Component)]
+pub(crate) struct DespawnOnEditorExit;
+
+#[derive(Resource)]
+struct MapLoadingTask(Task<Result<(Map, PathBuf), MapLoadingError>>);
+
+fn setup(mut commands: Commands, config: Res<EditorConfig>) {
+    let Some(path) = config.map_path() else {
+        info!("Editing a new map");
+        let map = match config.map_seed() {
+            Some(seed) => generator::generate(seed, Player::Player4),
+            None => Map::empty(MapMetadata::new(
+                NEW_MAP_NAME.to_owned(),
+                MapBounds::new(NEW_MAP_SIZE),
+                Player::Player4,
+            )),
+        };
+        commands.insert_resource(EditedMap::new(map, None));
+        return;
+    };
+
+    let path = if path.is_relative() {
+        asset_path(path)
+    } else {
+        path.to_owned()
+    };
+
+    info!("Loading map to be edited from {}", path.display());
+    let task = IoTaskPool::get().spawn(async move {
+        let map = load_map(&path).await?;
+        Ok((map, path))
+    });
+    commands.insert_resource(MapLoadingTask(task));
+}
+
+fn cleanup(mut commands: Commands, entities: Query<Entity, With<DespawnOnEditorExit>>) {
+    commands.remove_resource::<EditorConfig>();
+    commands.remove_resource::<MapLoadingTask>();
+    commands.remove_resource::<EditedMap>();
+    commands.remove_resource::<MapBounds>();
+    commands.remove_resource::<TerrainZones>();
+
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn finish_loading(
+    mut commands: Commands,
+    mut task: ResMut<MapLoadingTask>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<MapLoadingTask>();
+
+    match result {
+        Ok((map, path)) => {
+            info!("Map loaded, editing");
+            commands.insert_resource(EditedMap::new(map, Some(path)));
+        }
+        Err(err) => {
+            toasts.send(ToastEvent::new(
+                localize.format("toast-map-loading-failed", &[("error", &err)]),
+            ));
+            log_full_error!(err);
+            next_state.set(AppState::InMenu);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+
+use async_std::fs;
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task},
+};
+use de_core::{assets::asset_path, state::AppState};
+use de_gui::ToastEvent;
+use de_loc::Localize;
+use de_map::{
+    io::{store_map, MAP_FILE_SUFFIX},
+    lint,
+    map::Map,
+};
+use futures_lite::future;
+
+use crate::{
+    document::{full_error_message, EditedMap},
+    tools::ToolsSet,
+};
+
+pub(crate) struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(cleanup.in_schedule(OnExit(AppState::InEditor)))
+            .add_system(
+                save.run_if(in_state(AppState::InEditor))
+                    .run_if(resource_exists::<EditedMap>())
+                    .after(ToolsSet::Apply),
+            )
+            .add_system(
+                check_task
+                    .run_if(in_state(AppState::InEditor))
+                    .run_if(resource_exists::<SaveTask>()),
+            )
+            .add_system(exit.run_if(in_state(AppState::InEditor)));
+    }
+}
+
+#[derive(Resource)]
+struct SaveTask(Task<anyhow::Result<PathBuf>>);
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<SaveTask>();
+}
+
+fn save(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    map: Res<EditedMap>,
+    task: Option<Res<SaveTask>>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl]) || !keys.just_pressed(KeyCode::S) {
+        return;
+    }
+
+    if task.is_some() {
+        toasts.send(ToastEvent::new(localize.get("toast-map-saving")));
+        return;
+    }
+
+    if let Err(error) = map.map().validate() {
+        toasts.send(ToastEvent::new(localize.format(
+            "toast-map-invalid",
+            &[("error", &full_error_message(&error))],
+        )));
+        return;
+    }
+
+    let issues = lint::validate(map.map());
+    for issue in issues.iter() {
+        warn!("Map issue: {issue}");
+    }
+    if let Some(issue) = issues.first() {
+        toasts.send(ToastEvent::new(localize.format(
+            "toast-map-issues",
+            &[("count", &issues.len()), ("issue", issue)],
+        )));
+    }
+
+    let path = map.path().map(|path| path.to_owned());
+    info!("Saving the edited map.");
+    let task = IoTaskPool::get().spawn(store(map.map().clone(), path));
+    commands.insert_resource(SaveTask(task));
+}
+
+fn check_task(
+    mut commands: Commands,
+    mut task: ResMut<SaveTask>,
+    mut map: ResMut<EditedMap>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<SaveTask>();
+
+    match result {
+        Ok(path) => {
+            info!("Map saved to {path:?}.");
+            toasts.send(ToastEvent::new(localize.get("toast-map-saved")));
+            map.mark_saved(path);
+        }
+        Err(err) => {
+            error!("Failed to save the map: {err:?}");
+            toasts.send(ToastEvent::new(
+                localize.format("toast-map-saving-failed", &[("error", &err)]),
+            ));
+        }
+    }
+}
+
+fn exit(keys: Res<Input<KeyCode>>, mut next_state: ResMut<NextState<AppState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(AppState::InMenu);
+    }
+}
+
+/// Stores the map to `path` or to a new file in the maps directory if `path`
+/// is None. It returns the path of the stored map.
+async fn store(map: Map, path: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let dir = asset_path("maps");
+            fs::create_dir_all(&dir).await?;
+            new_map_path(dir, map.metadata().name()).await
+        }
+    };
+
+    store_map(&map, &path).await?;
+    Ok(path)
+}
+
+/// Returns a path of a not yet existing map file whose name is derived from
+/// the map name.
+async fn new_map_path(dir: PathBuf, name: &str) -> PathBuf {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+
+    let mut path = dir.join(format!("{stem}{MAP_FILE_SUFFIX}"));
+    let mut counter = 1;
+    while async_std::path::Path::new(&path).exists().await {
+        counter += 1;
+        path = dir.join(format!("{stem}-{counter}{MAP_FILE_SUFFIX}"));
+    }
+    path
+}
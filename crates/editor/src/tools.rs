@@ -0,0 +1,274 @@
+use std::f32::consts::FRAC_PI_8;
+
+use bevy::prelude::*;
+use de_core::{
+    objects::{ActiveObjectType, BuildingType, ObjectType},
+    player::Player,
+    state::AppState,
+};
+use de_gui::ToastEvent;
+use de_loc::Localize;
+use de_map::zones::ZoneKind;
+use enum_iterator::next_cycle;
+
+use crate::{
+    camera::{CameraSet, Pointer},
+    document::{full_error_message, EditError, EditedMap},
+};
+
+/// Radius of the height brush in meters.
+const BRUSH_RADIUS: f32 = 24.;
+/// Speed of terrain elevation change at the center of the height brush in
+/// meters per second.
+const BRUSH_SPEED: f32 = 8.;
+/// Radius of newly painted terrain zones in meters.
+const ZONE_RADIUS: f32 = 24.;
+/// Objects are rotated by this angle (in radians) with each click.
+const ROTATION_STEP: f32 = FRAC_PI_8;
+/// Map is grown or shrunk by this many meters along each axis.
+const RESIZE_STEP: f32 = 200.;
+
+pub(crate) struct ToolsPlugin;
+
+impl Plugin for ToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(AppState::InEditor)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InEditor)))
+            .add_system(
+                select_tool
+                    .run_if(in_state(AppState::InEditor))
+                    .in_set(ToolsSet::Select),
+            )
+            .add_system(
+                apply_tool
+                    .run_if(in_state(AppState::InEditor))
+                    .run_if(resource_exists::<EditedMap>())
+                    .in_set(ToolsSet::Apply)
+                    .after(ToolsSet::Select)
+                    .after(CameraSet::Pointer),
+            )
+            .add_system(
+                edit_map_parameters
+                    .run_if(in_state(AppState::InEditor))
+                    .run_if(resource_exists::<EditedMap>())
+                    .in_set(ToolsSet::Apply),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum ToolsSet {
+    Select,
+    /// [`EditedMap`] is modified in this set.
+    Apply,
+}
+
+/// Currently selected editing tool and its options.
+#[derive(Resource)]
+pub(crate) struct Tools {
+    tool: Tool,
+    object_type: ObjectType,
+    player: Player,
+    zone_kind: ZoneKind,
+}
+
+impl Tools {
+    /// Returns human readable description of the selected tool.
+    pub(crate) fn description(&self, localize: &Localize) -> String {
+        let player = self.player.to_num();
+        match self.tool {
+            Tool::Place => match self.object_type {
+                ObjectType::Active(_) => localize.format(
+                    "editor-tool-place-active",
+                    &[
+                        ("object", &localize.object_name(self.object_type)),
+                        ("player", &player),
+                    ],
+                ),
+                ObjectType::Inactive(_) => localize.format(
+                    "editor-tool-place-inactive",
+                    &[("object", &localize.object_name(self.object_type))],
+                ),
+            },
+            Tool::Rotate => localize.get("editor-tool-rotate"),
+            Tool::Delete => localize.get("editor-tool-delete"),
+            Tool::Height => localize.get("editor-tool-height"),
+            Tool::Zones => localize.format(
+                "editor-tool-zones",
+                &[("zones", &localize.get(zone_kind_id(self.zone_kind)))],
+            ),
+            Tool::Spawn => localize.format("editor-tool-spawn", &[("player", &player)]),
+        }
+    }
+}
+
+impl Default for Tools {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Place,
+            object_type: ObjectType::Active(ActiveObjectType::Building(BuildingType::Base)),
+            player: Player::Player1,
+            zone_kind: ZoneKind::Water,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Place,
+    Rotate,
+    Delete,
+    Height,
+    Zones,
+    Spawn,
+}
+
+fn zone_kind_id(kind: ZoneKind) -> &'static str {
+    match kind {
+        ZoneKind::Water => "editor-zones-water",
+        ZoneKind::Cliff => "editor-zones-cliffs",
+        ZoneKind::Crater => "editor-zones-craters",
+    }
+}
+
+fn next_zone_kind(kind: ZoneKind) -> ZoneKind {
+    match kind {
+        ZoneKind::Water => ZoneKind::Cliff,
+        ZoneKind::Cliff => ZoneKind::Crater,
+        ZoneKind::Crater => ZoneKind::Water,
+    }
+}
+
+fn next_player(player: Player, max_player: Player) -> Player {
+    Player::try_from(player.to_num() % max_player.to_num() + 1).unwrap()
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Tools>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Tools>();
+}
+
+fn select_tool(keys: Res<Input<KeyCode>>, map: Option<Res<EditedMap>>, mut tools: ResMut<Tools>) {
+    for (key, tool) in [
+        (KeyCode::Key1, Tool::Place),
+        (KeyCode::Key2, Tool::Rotate),
+        (KeyCode::Key3, Tool::Delete),
+        (KeyCode::Key4, Tool::Height),
+        (KeyCode::Key5, Tool::Zones),
+        (KeyCode::Key6, Tool::Spawn),
+    ] {
+        if keys.just_pressed(key) {
+            tools.tool = tool;
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Tab) {
+        match tools.tool {
+            Tool::Place => tools.object_type = next_cycle(&tools.object_type).unwrap(),
+            Tool::Zones => tools.zone_kind = next_zone_kind(tools.zone_kind),
+            _ => (),
+        }
+    }
+
+    if keys.just_pressed(KeyCode::P) {
+        let max_player = map.map_or(Player::Player4, |map| map.map().metadata().max_player());
+        tools.player = next_player(tools.player, max_player);
+    }
+}
+
+fn apply_tool(
+    time: Res<Time>,
+    buttons: Res<Input<MouseButton>>,
+    pointer: Res<Pointer>,
+    tools: Res<Tools>,
+    mut map: ResMut<EditedMap>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let Some(position) = pointer.position() else {
+        return;
+    };
+
+    let primary = buttons.just_pressed(MouseButton::Left);
+    let secondary = buttons.just_pressed(MouseButton::Right);
+
+    let result = match tools.tool {
+        Tool::Place if primary => map.place_object(tools.object_type, tools.player, position, 0.),
+        Tool::Rotate if primary || secondary => {
+            if let Some(index) = map.object_at(position) {
+                let angle = if primary {
+                    ROTATION_STEP
+                } else {
+                    -ROTATION_STEP
+                };
+                map.rotate_object(index, angle);
+            }
+            Ok(())
+        }
+        Tool::Delete if primary => {
+            if let Some(index) = map.object_at(position) {
+                map.remove_object(index);
+            }
+            Ok(())
+        }
+        Tool::Height => {
+            let mut delta = 0.;
+            if buttons.pressed(MouseButton::Left) {
+                delta += BRUSH_SPEED * time.delta_seconds();
+            }
+            if buttons.pressed(MouseButton::Right) {
+                delta -= BRUSH_SPEED * time.delta_seconds();
+            }
+            if delta != 0. {
+                map.paint_height(position, BRUSH_RADIUS, delta);
+            }
+            Ok(())
+        }
+        Tool::Zones if primary => map.insert_zone(tools.zone_kind, position, ZONE_RADIUS),
+        Tool::Zones if secondary => map.remove_zone_at(position),
+        Tool::Spawn if primary => map.set_spawn(tools.player, position),
+        _ => Ok(()),
+    };
+
+    report(result, &mut toasts);
+}
+
+fn edit_map_parameters(
+    keys: Res<Input<KeyCode>>,
+    mut map: ResMut<EditedMap>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let metadata = map.map().metadata();
+    let max_player = metadata.max_player().to_num();
+    let size = metadata.bounds().size();
+
+    if keys.just_pressed(KeyCode::RBracket) {
+        let result = match Player::try_from(max_player + 1) {
+            Ok(player) => map.set_max_player(player),
+            Err(_) => Ok(()),
+        };
+        report(result, &mut toasts);
+    }
+    if keys.just_pressed(KeyCode::LBracket) {
+        let result = match Player::try_from(max_player - 1) {
+            Ok(player) => map.set_max_player(player),
+            Err(_) => Ok(()),
+        };
+        report(result, &mut toasts);
+    }
+
+    if keys.just_pressed(KeyCode::Equals) {
+        report(map.resize(size + RESIZE_STEP), &mut toasts);
+    }
+    if keys.just_pressed(KeyCode::Minus) {
+        report(map.resize(size - RESIZE_STEP), &mut toasts);
+    }
+}
+
+fn report(result: Result<(), EditError>, toasts: &mut EventWriter<ToastEvent>) {
+    if let Err(error) = result {
+        toasts.send(ToastEvent::new(full_error_message(&error)));
+    }
+}
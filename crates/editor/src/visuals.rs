@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use de_core::{objects::ObjectType, state::AppState};
+use de_map::content::InnerObject;
+use de_objects::{AssetCollection, SceneType, Scenes};
+use de_terrain::TerrainBundle;
+
+use crate::{document::EditedMap, loading::DespawnOnEditorExit, tools::ToolsSet};
+
+pub(crate) struct VisualsPlugin;
+
+impl Plugin for VisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            update_terrain
+                .run_if(in_state(AppState::InEditor))
+                .run_if(resource_exists::<EditedMap>())
+                .after(ToolsSet::Apply),
+        )
+        .add_system(
+            update_objects
+                .run_if(in_state(AppState::InEditor))
+                .run_if(resource_exists::<EditedMap>())
+                .after(ToolsSet::Apply),
+        );
+    }
+}
+
+#[derive(Component)]
+struct EditorTerrain;
+
+#[derive(Component)]
+struct EditorObject;
+
+/// Re-spawns the terrain after its elevation, zones or the map bounds have
+/// changed.
+fn update_terrain(
+    mut commands: Commands,
+    mut map: ResMut<EditedMap>,
+    terrains: Query<Entity, With<EditorTerrain>>,
+) {
+    if !map.take_terrain_changed() {
+        return;
+    }
+
+    for entity in terrains.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let map = map.map();
+    let bounds = map.metadata().bounds();
+    let terrain = match map.heightmap() {
+        Some(heightmap) => TerrainBundle::from_heightmap(bounds, heightmap),
+        None => TerrainBundle::flat(bounds),
+    };
+    // Terrain materials are created from these resources.
+    commands.insert_resource(bounds);
+    commands.insert_resource(map.zones().clone());
+    commands.spawn((terrain, EditorTerrain, DespawnOnEditorExit));
+}
+
+/// Re-spawns all objects after any of them has changed.
+fn update_objects(
+    mut commands: Commands,
+    mut map: ResMut<EditedMap>,
+    scenes: Res<Scenes>,
+    objects: Query<Entity, With<EditorObject>>,
+) {
+    if !map.take_objects_changed() {
+        return;
+    }
+
+    for entity in objects.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for object in map.map().content().objects() {
+        let object_type = match object.inner() {
+            InnerObject::Active(active) => ObjectType::Active(active.object_type()),
+            InnerObject::Inactive(inactive) => ObjectType::Inactive(inactive.object_type()),
+        };
+
+        let placement = object.placement();
+        let mut transform = placement.to_transform();
+        transform.translation.y = map.elevation(placement.position());
+
+        commands.spawn((
+            SceneBundle {
+                scene: scenes.get(SceneType::Solid(object_type)).clone(),
+                transform,
+                ..default()
+            },
+            EditorObject,
+            DespawnOnEditorExit,
+        ));
+    }
+}
@@ -40,6 +40,15 @@ impl Battery {
         Self { capacity, energy }
     }
 
+    /// Sets the current energy level of the battery in joules, e.g. when a
+    /// saved game is continued. The energy is clamped to the capacity of the
+    /// battery.
+    pub fn with_energy(mut self, energy: f64) -> Self {
+        debug_assert!(energy.is_finite());
+        self.energy = energy.clamp(0., self.capacity);
+        self
+    }
+
     /// The maximum capacity of the battery in joules.
     pub fn capacity(&self) -> f64 {
         self.capacity
@@ -50,6 +59,25 @@ impl Battery {
         self.energy
     }
 
+    /// Takes the given amount of energy from the battery if it holds enough
+    /// energy. Returns false (and leaves the battery intact) otherwise.
+    pub fn try_consume(&mut self, energy: f64) -> bool {
+        debug_assert!(energy >= 0.);
+
+        if self.energy < energy {
+            return false;
+        }
+        self.change(-energy);
+        true
+    }
+
+    /// Stores the given amount of energy to the battery. Energy above the
+    /// capacity of the battery is lost.
+    pub fn charge(&mut self, energy: f64) {
+        debug_assert!(energy >= 0.);
+        self.change(energy);
+    }
+
     /// Directly changes the energy level of the battery by the given amount of energy.
     fn change(&mut self, delta: f64) {
         debug_assert!(delta.is_finite());
@@ -113,4 +141,16 @@ mod tests {
         assert!(battery.energy() <= DEFAULT_CAPACITY - DISCHARGE_RATE);
         assert!(battery.energy() >= DEFAULT_CAPACITY - DISCHARGE_RATE * 1.5);
     }
+
+    #[test]
+    fn test_consume() {
+        let mut battery = Battery::new(100., 60.);
+        assert!(battery.try_consume(50.));
+        assert_eq!(battery.energy(), 10.);
+        assert!(!battery.try_consume(11.));
+        assert_eq!(battery.energy(), 10.);
+
+        battery.charge(200.);
+        assert_eq!(battery.energy(), 100.);
+    }
 }
@@ -111,6 +111,16 @@ impl TileGrid {
         self.tiles.get(&tile_coords)
     }
 
+    /// Returns number of non-empty tiles.
+    pub(crate) fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns an iterator over entity sets of all non-empty tiles.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &AHashSet<Entity>> {
+        self.tiles.values()
+    }
+
     fn insert_to_tile(&mut self, entity: Entity, tile_coords: IVec2) {
         let inserted = self
             .tiles
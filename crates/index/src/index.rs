@@ -3,7 +3,7 @@
 
 use std::cmp::Ordering;
 
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use bevy::{
     ecs::{
         query::{ReadOnlyWorldQuery, WorldQuery},
@@ -11,6 +11,8 @@ use bevy::{
     },
     prelude::*,
 };
+use de_core::projection::ToFlat;
+use glam::{IVec2, Vec2};
 use parry3d::{
     bounding_volume::{Aabb, BoundingVolume},
     math::{Isometry, Point},
@@ -19,7 +21,12 @@ use parry3d::{
 };
 
 use super::{collider::LocalCollider, grid::TileGrid, segment::SegmentCandidates};
-use crate::{aabb::AabbCandidates, collider::ColliderWithCache};
+use crate::{
+    aabb::AabbCandidates,
+    collider::ColliderWithCache,
+    nearest::{ring, ring_distance, Nearest},
+    TILE_SIZE,
+};
 
 /// 2D rectangular grid based spatial index of entities.
 #[derive(Resource)]
@@ -91,6 +98,75 @@ impl EntityIndex {
         AabbCandidates::new(&self.grid, aabb)
     }
 
+    /// Returns up to `k` entities nearest to a point sorted by their
+    /// distance. See [`SpatialQuery::k_nearest`].
+    ///
+    /// # Arguments
+    ///
+    /// * `accept` - only entities for which this function returns true are
+    ///   included in the result.
+    fn k_nearest<F>(
+        &self,
+        point: Vec2,
+        k: usize,
+        max_distance: f32,
+        mut accept: F,
+    ) -> Vec<(Entity, f32)>
+    where
+        F: FnMut(Entity) -> bool,
+    {
+        let mut nearest = Nearest::new(k, max_distance);
+        let mut visited = AHashSet::new();
+        let mut visit = |entity: Entity, nearest: &mut Nearest| {
+            if visited.insert(entity) && accept(entity) {
+                nearest.insert(entity, self.flat_distance(entity, point));
+            }
+        };
+
+        let center = tile_coords(point);
+        // All entities are within tiles covering the world bounds.
+        let bounds = self.world_bounds.to_flat();
+        let max_radius = (center - tile_coords(bounds.mins.into()))
+            .abs()
+            .max((tile_coords(bounds.maxs.into()) - center).abs())
+            .max_element();
+
+        for radius in 0..=max_radius {
+            if nearest.is_complete(ring_distance(point, center, radius)) {
+                break;
+            }
+
+            // Rings far from the point are larger than the whole populated
+            // part of the grid, thus it is cheaper to visit all entities.
+            let ring_len = 8 * radius.max(1) as usize;
+            if ring_len > self.grid.len() {
+                for &entity in self.grid.iter().flatten() {
+                    visit(entity, &mut nearest);
+                }
+                break;
+            }
+
+            for tile in ring(center, radius) {
+                if let Some(entities) = self.grid.get_tile_entities(tile) {
+                    for &entity in entities {
+                        visit(entity, &mut nearest);
+                    }
+                }
+            }
+        }
+
+        nearest.into_vec()
+    }
+
+    /// Returns distance between a point and the ground projection of the
+    /// world-space bounding box of an entity.
+    fn flat_distance(&self, entity: Entity, point: Vec2) -> f32 {
+        let aabb = self.get_collider(entity).world_aabb().to_flat();
+        let min: Vec2 = aabb.mins.into();
+        let max: Vec2 = aabb.maxs.into();
+        point.distance(point.clamp(min, max))
+    }
+
     fn get_collider(&self, entity: Entity) -> &LocalCollider {
         self.colliders
             .get(&entity)
@@ -104,6 +180,11 @@ impl Default for EntityIndex {
     }
 }
 
+/// Returns coordinates of the tile containing a point.
+fn tile_coords(point: Vec2) -> IVec2 {
+    (point / TILE_SIZE).floor().as_ivec2()
+}
+
 /// System parameter implementing various spatial queries.
 ///
 /// Only entities automatically indexed by systems from
@@ -183,6 +264,47 @@ where
         })
     }
 
+    /// Returns up to `k` queried entities nearest to a point sorted by their
+    /// distance in ascending order.
+    ///
+    /// Distance of an entity is measured between the point and the ground
+    /// projection of the world-space bounding box of the entity, as indexed
+    /// by systems registered by [`super::systems::IndexPlugin`].
+    ///
+    /// # Arguments
+    ///
+    /// * `point` - a point on the map.
+    ///
+    /// * `k` - maximum number of returned entities.
+    ///
+    /// * `max_distance` - entities farther than this are not returned.
+    ///
+    /// * `filter` - only entities for which this function returns true are
+    ///   returned.
+    pub fn k_nearest<P>(
+        &self,
+        point: Vec2,
+        k: usize,
+        max_distance: f32,
+        mut filter: P,
+    ) -> Vec<NearestEntity<<<Q as WorldQuery>::ReadOnly as WorldQuery>::Item<'_>>>
+    where
+        P: FnMut(Entity, &<<Q as WorldQuery>::ReadOnly as WorldQuery>::Item<'_>) -> bool,
+    {
+        self.index
+            .k_nearest(point, k, max_distance, |entity| {
+                match self.entities.get(entity) {
+                    Ok(item) => filter(entity, &item),
+                    Err(_) => false,
+                }
+            })
+            .into_iter()
+            .map(|(entity, distance)| {
+                NearestEntity::new(entity, distance, self.entities.get(entity).unwrap())
+            })
+            .collect()
+    }
+
     pub fn query_aabb<'a, 'b>(
         &'a self,
         aabb: &'b Aabb,
@@ -220,6 +342,37 @@ impl<T> RayEntityIntersection<T> {
     }
 }
 
+/// An entity returned by [`SpatialQuery::k_nearest`].
+pub struct NearestEntity<T> {
+    entity: Entity,
+    distance: f32,
+    item: T,
+}
+
+impl<T> NearestEntity<T> {
+    fn new(entity: Entity, distance: f32, item: T) -> Self {
+        Self {
+            entity,
+            distance,
+            item,
+        }
+    }
+
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Distance between the query point and the entity.
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Single item (ECS world query result) associated with the entity.
+    pub fn item(&self) -> &T {
+        &self.item
+    }
+}
+
 impl<T> PartialOrd for RayEntityIntersection<T> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -393,6 +546,62 @@ mod tests {
         assert!(index.cast_ray(&ray_b, 120.).is_none());
     }
 
+    #[test]
+    fn test_k_nearest() {
+        let mut index = EntityIndex::new();
+        assert!(index
+            .k_nearest(Vec2::ZERO, 3, f32::INFINITY, |_| true)
+            .is_empty());
+
+        // Cubes with side 2 centered at the given flat positions.
+        let positions = [
+            Vec2::new(0., 0.),
+            Vec2::new(15., 0.),
+            Vec2::new(-40., 0.),
+            Vec2::new(0., 100.),
+            Vec2::new(500., -500.),
+        ];
+        for (i, position) in positions.iter().enumerate() {
+            let mut trimesh: TriMesh = Cuboid::new(Vector::new(1., 1., 1.)).into();
+            trimesh.set_flags(TriMeshFlags::ORIENTED).unwrap();
+            let collider = LocalCollider::new(
+                ObjectCollider::from(trimesh),
+                Isometry::new(
+                    Vector::new(position.x, 0., -position.y),
+                    Vector::new(0., 0., 0.),
+                ),
+            );
+            index.insert(Entity::from_raw(i as u32), collider);
+        }
+
+        let nearest = index.k_nearest(Vec2::new(11., 0.), 3, f32::INFINITY, |_| true);
+        assert_eq!(
+            nearest,
+            vec![
+                (Entity::from_raw(1), 3.),
+                (Entity::from_raw(0), 10.),
+                (Entity::from_raw(2), 50.)
+            ]
+        );
+
+        let nearest = index.k_nearest(Vec2::new(11., 0.), 3, 20., |_| true);
+        assert_eq!(
+            nearest,
+            vec![(Entity::from_raw(1), 3.), (Entity::from_raw(0), 10.)]
+        );
+
+        let nearest = index.k_nearest(Vec2::new(11., 0.), 2, f32::INFINITY, |entity| {
+            entity.index() > 2
+        });
+        assert_eq!(nearest.len(), 2);
+        assert_eq!(nearest[0].0, Entity::from_raw(3));
+        assert_eq!(nearest[1].0, Entity::from_raw(4));
+
+        // Points outside of the populated area are handled too.
+        let nearest = index.k_nearest(Vec2::new(2000., -2000.), 1, f32::INFINITY, |_| true);
+        assert_eq!(nearest[0].0, Entity::from_raw(4));
+    }
+
     #[test]
     fn test_entity_collider() {
         let mut trimesh: TriMesh = Cuboid::new(Vector::new(1., 2., 3.)).into();
@@ -1,6 +1,6 @@
 #![allow(rustdoc::private_intra_doc_links)]
 //! This module implements 2D object partitioning for fast geometric lookup,
-//! for example ray casting.
+//! for example ray casting or k-nearest neighbor search.
 //!
 //! The core structure is a square tile grid which points to Bevy ECS entities.
 //! Newly spawned entities are automatically added, despawned entities removed
@@ -10,7 +10,9 @@ mod aabb;
 mod collider;
 mod grid;
 mod index;
+mod nearest;
 mod range;
+mod ray;
 mod segment;
 mod systems;
 
@@ -19,7 +21,8 @@ use systems::IndexPlugin;
 
 pub use self::{
     collider::{ColliderWithCache, LocalCollider, QueryCollider},
-    index::{EntityIndex, RayEntityIntersection, SpatialQuery},
+    index::{EntityIndex, NearestEntity, RayEntityIntersection, SpatialQuery},
+    ray::{RayCaster, RayHit},
     systems::IndexSet,
 };
 
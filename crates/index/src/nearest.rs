@@ -0,0 +1,148 @@
+//! This module contains utilities for k-nearest neighbor search over the
+//! tile grid.
+//!
+//! Tiles are searched in square rings of increasing (Chebyshev) radius
+//! around the tile of the query point. An entity found in a ring for the
+//! first time lies outside of the square covered by all previous rings,
+//! therefore distance to the boundary of the square is a lower bound of
+//! distance of all entities not yet found. The search finishes once the
+//! k-th nearest found entity is closer than this bound.
+
+use bevy::prelude::Entity;
+use glam::{IVec2, Vec2};
+
+use crate::TILE_SIZE;
+
+/// Returns an iterator over coordinates of tiles whose Chebyshev distance
+/// from the `center` tile is exactly `radius`.
+pub(crate) fn ring(center: IVec2, radius: i32) -> impl Iterator<Item = IVec2> {
+    let horizontal = (-radius..=radius).flat_map(move |x| {
+        let bottom = center + IVec2::new(x, -radius);
+        let top = center + IVec2::new(x, radius);
+        // A ring with zero radius is a single tile.
+        std::iter::once(bottom).chain((radius > 0).then_some(top))
+    });
+    let vertical = (1 - radius..radius).flat_map(move |y| {
+        [
+            center + IVec2::new(-radius, y),
+            center + IVec2::new(radius, y),
+        ]
+    });
+    horizontal.chain(vertical)
+}
+
+/// Returns a lower bound of distance between `point` and any entity which
+/// does not intersect any tile of rings with radius smaller than `radius`
+/// around the `center` tile.
+///
+/// `point` must be inside the `center` tile.
+pub(crate) fn ring_distance(point: Vec2, center: IVec2, radius: i32) -> f32 {
+    if radius == 0 {
+        return 0.;
+    }
+
+    let min = (center - IVec2::splat(radius - 1)).as_vec2() * TILE_SIZE;
+    let max = (center + IVec2::splat(radius)).as_vec2() * TILE_SIZE;
+    (point - min).min(max - point).min_element()
+}
+
+/// Up to `k` entities sorted by their distance in ascending order.
+pub(crate) struct Nearest {
+    k: usize,
+    max_distance: f32,
+    entities: Vec<(Entity, f32)>,
+}
+
+impl Nearest {
+    pub(crate) fn new(k: usize, max_distance: f32) -> Self {
+        Self {
+            k,
+            max_distance,
+            entities: Vec::with_capacity(k + 1),
+        }
+    }
+
+    /// Returns true if no entity farther than `distance` could be inserted.
+    pub(crate) fn is_complete(&self, distance: f32) -> bool {
+        if distance > self.max_distance {
+            return true;
+        }
+        match self.k.checked_sub(1) {
+            Some(last) => matches!(self.entities.get(last), Some(&(_, kth)) if kth <= distance),
+            None => true,
+        }
+    }
+
+    /// Inserts an entity unless it is too far or there are already `k`
+    /// nearer entities.
+    pub(crate) fn insert(&mut self, entity: Entity, distance: f32) {
+        if distance > self.max_distance {
+            return;
+        }
+        let index = self
+            .entities
+            .partition_point(|&(_, other)| other <= distance);
+        if index < self.k {
+            self.entities.insert(index, (entity, distance));
+            self.entities.truncate(self.k);
+        }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<(Entity, f32)> {
+        self.entities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::AHashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_ring() {
+        let center = IVec2::new(3, -2);
+        assert_eq!(ring(center, 0).collect::<Vec<_>>(), vec![center]);
+
+        for radius in 1..4 {
+            let tiles: AHashSet<IVec2> = ring(center, radius).collect();
+            assert_eq!(tiles.len(), 8 * radius as usize);
+            assert_eq!(ring(center, radius).count(), tiles.len());
+            for tile in tiles {
+                let offset = (tile - center).abs();
+                assert_eq!(offset.max_element(), radius);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ring_distance() {
+        let point = Vec2::new(12., -7.);
+        let center = IVec2::new(1, -1);
+        assert_eq!(ring_distance(point, center, 0), 0.);
+        assert_eq!(ring_distance(point, center, 1), 2.);
+        assert_eq!(ring_distance(point, center, 2), 12.);
+        assert_eq!(ring_distance(point, center, 3), 22.);
+    }
+
+    #[test]
+    fn test_nearest() {
+        let mut nearest = Nearest::new(2, 10.);
+        assert!(!nearest.is_complete(5.));
+        assert!(nearest.is_complete(10.5));
+
+        nearest.insert(Entity::from_raw(1), 4.);
+        nearest.insert(Entity::from_raw(2), 11.);
+        assert!(!nearest.is_complete(5.));
+        nearest.insert(Entity::from_raw(3), 1.);
+        assert!(nearest.is_complete(4.));
+        assert!(!nearest.is_complete(3.));
+        nearest.insert(Entity::from_raw(4), 2.);
+        nearest.insert(Entity::from_raw(5), 3.);
+
+        assert_eq!(
+            nearest.into_vec(),
+            vec![(Entity::from_raw(3), 1.), (Entity::from_raw(4), 2.)]
+        );
+    }
+}
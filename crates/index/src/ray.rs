@@ -0,0 +1,88 @@
+//! This module implements ray casting against both the terrain and indexed
+//! solid entities.
+
+use bevy::{
+    ecs::{
+        query::{ReadOnlyWorldQuery, WorldQuery},
+        system::SystemParam,
+    },
+    prelude::*,
+};
+use de_terrain::TerrainCollider;
+use parry3d::query::Ray;
+
+use crate::index::{RayEntityIntersection, SpatialQuery};
+
+/// System parameter casting rays which are blocked by elevated terrain and
+/// by solid entities.
+///
+/// Only entities retrieved by [`SpatialQuery`] with the same world query and
+/// filter block the rays.
+#[derive(SystemParam)]
+pub struct RayCaster<'w, 's, Q, F = ()>
+where
+    Q: WorldQuery + Sync + Send + 'static,
+    F: ReadOnlyWorldQuery + Sync + Send + 'static,
+{
+    terrain: TerrainCollider<'w, 's>,
+    entities: SpatialQuery<'w, 's, Q, F>,
+}
+
+impl<'w, 's, Q, F> RayCaster<'w, 's, Q, F>
+where
+    Q: WorldQuery + Sync + Send + 'static,
+    F: ReadOnlyWorldQuery + Sync + Send + 'static,
+{
+    /// Returns the first obstacle, either the terrain or an entity, hit by a
+    /// ray.
+    ///
+    /// # Arguments
+    ///
+    /// * `ray` - the cast ray.
+    ///
+    /// * `max_toi` - maximum obstacle distance given as a multiple of ray
+    ///   direction.
+    ///
+    /// * `ignore` - if not None, this entity does not block the ray.
+    pub fn cast_ray(
+        &self,
+        ray: &Ray,
+        max_toi: f32,
+        ignore: Option<Entity>,
+    ) -> Option<RayHit<<<Q as WorldQuery>::ReadOnly as WorldQuery>::Item<'_>>> {
+        // It is more efficient to calculate the terrain hit. Do it first so
+        // max_toi can be lowered in case of a hit.
+        let terrain = self.terrain.cast_ray(ray, max_toi);
+        let max_toi = terrain.map_or(max_toi, |intersection| intersection.toi);
+        match self.entities.cast_ray(ray, max_toi, ignore) {
+            Some(intersection) => Some(RayHit::Entity(intersection)),
+            None => terrain.map(|intersection| RayHit::Terrain(intersection.toi)),
+        }
+    }
+}
+
+/// The first obstacle hit by a ray, see [`RayCaster::cast_ray`].
+pub enum RayHit<T> {
+    /// The ray hit the terrain at the given time of impact.
+    Terrain(f32),
+    Entity(RayEntityIntersection<T>),
+}
+
+impl<T> RayHit<T> {
+    /// Intersection time of impact. Intersection point is equal to
+    /// `ray.origin + hit.toi() * ray.dir`.
+    pub fn toi(&self) -> f32 {
+        match self {
+            Self::Terrain(toi) => *toi,
+            Self::Entity(intersection) => intersection.toi(),
+        }
+    }
+
+    /// The hit entity, if any.
+    pub fn entity(&self) -> Option<Entity> {
+        match self {
+            Self::Terrain(_) => None,
+            Self::Entity(intersection) => Some(intersection.entity()),
+        }
+    }
+}
@@ -24,6 +24,9 @@ type SolidEntityQuery<'w, 's> = Query<
     ),
 >;
 
+type UnindexedQuery<'w, 's> =
+    Query<'w, 's, Entity, (With<Indexed>, Without<StaticSolid>, Without<MovableSolid>)>;
+
 type MovedQuery<'w, 's> =
     Query<'w, 's, (Entity, &'static Transform), (With<Indexed>, Changed<Transform>)>;
 
@@ -37,7 +40,8 @@ type MovedQuery<'w, 's> =
 /// [`de_core::gamestate::GameState::Playing`]. The systems automatically
 /// insert newly spawned solid entities to the index, update their position
 /// when [`bevy::prelude::Transform`] is changed and remove the entities from
-/// the index when they are de-spawned.
+/// the index when they are de-spawned or when they lose their solid marker
+/// component (e.g. units carried inside of other objects).
 pub(crate) struct IndexPlugin;
 
 impl Plugin for IndexPlugin {
@@ -51,11 +55,20 @@ impl Plugin for IndexPlugin {
                     .in_set(IndexSet::Index),
             )
             .add_system(
-                remove
+                unindex
                     .in_base_set(GameSet::PostUpdate)
                     .run_if(in_state(GameState::Playing))
                     .in_set(IndexSet::Index),
             )
+            .add_system(
+                remove
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(IndexSet::Index)
+                    // Entities which were removed and got their solid marker
+                    // back must be re-inserted after they are removed.
+                    .before(insert),
+            )
             .add_system(
                 update
                     .in_base_set(GameSet::PostMovement)
@@ -104,6 +117,13 @@ fn insert(
     }
 }
 
+/// Marks entities which are no longer solid for removal from the index.
+fn unindex(mut commands: Commands, query: UnindexedQuery) {
+    for entity in query.iter() {
+        commands.entity(entity).remove::<Indexed>();
+    }
+}
+
 fn remove(mut index: ResMut<EntityIndex>, mut removed: RemovedComponents<Indexed>) {
     for entity in removed.iter() {
         index.remove(entity);
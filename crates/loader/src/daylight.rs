@@ -0,0 +1,204 @@
+use std::f32::consts::{FRAC_PI_4, TAU};
+
+use bevy::{
+    pbr::{CascadeShadowConfig, CascadeShadowConfigBuilder},
+    prelude::*,
+};
+use de_conf::{Configuration, GraphicsSettings, ShadowQuality};
+use de_core::{
+    baseset::GameSet,
+    cleanup::DespawnOnGameExit,
+    daytime::{TimeOfDay, HOURS_PER_DAY},
+    state::AppState,
+};
+
+/// Illuminance (in lux) of the sun high above the horizon.
+const SUN_ILLUMINANCE: f32 = 30000.;
+/// Illuminance (in lux) of the moon high above the horizon.
+const MOON_ILLUMINANCE: f32 = 3000.;
+/// Ambient light brightness during the day.
+const DAY_AMBIENT: f32 = 0.6;
+/// Ambient light brightness during the night.
+const NIGHT_AMBIENT: f32 = 0.15;
+/// Sine of the elevation above which the sun (or the moon) shines with full
+/// strength. The light fades out linearly below this elevation.
+const FULL_LIGHT_ELEVATION: f32 = 0.2;
+/// Shadows are cast only by light with at least this fraction of its full
+/// strength. This avoids extremely long shadows near the horizon.
+const SHADOW_STRENGTH: f32 = 0.5;
+/// Angle (in radians) between the sun's path and the zenith.
+const SUN_PATH_TILT: f32 = FRAC_PI_4;
+
+pub(crate) struct DaylightPlugin;
+
+impl Plugin for DaylightPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                update_light
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame))
+                    .run_if(resource_exists::<TimeOfDay>()),
+            );
+    }
+}
+
+/// Marker of the directional light which represents the sun during the day
+/// and the moon during the night.
+#[derive(Component)]
+struct Sky;
+
+/// Starts the time of day and spawns the directional light.
+///
+/// # Arguments
+///
+/// * `start_hour` - time of day (in hours) at the beginning of the game.
+pub(crate) fn setup_light(
+    commands: &mut Commands,
+    conf: &Configuration,
+    settings: &GraphicsSettings,
+    start_hour: f32,
+) {
+    commands.insert_resource(TimeOfDay::new(start_hour, conf.lighting().day_length()));
+
+    // Light parameters are updated from the time of day before the first
+    // frame is rendered.
+    commands.spawn((
+        DirectionalLightBundle {
+            cascade_shadow_config: cascade_config(conf, settings.shadows()),
+            ..Default::default()
+        },
+        Sky,
+        DespawnOnGameExit,
+    ));
+}
+
+fn cascade_config(conf: &Configuration, quality: ShadowQuality) -> CascadeShadowConfig {
+    CascadeShadowConfigBuilder {
+        num_cascades: quality.num_cascades(),
+        maximum_distance: 1000.,
+        first_cascade_far_bound: conf.camera().min_distance().inner() * 2.,
+        ..default()
+    }
+    .build()
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<TimeOfDay>();
+}
+
+type SkyLights<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut DirectionalLight,
+        &'static mut CascadeShadowConfig,
+        &'static mut Transform,
+    ),
+    With<Sky>,
+>;
+
+fn update_light(
+    conf: Res<Configuration>,
+    settings: Res<GraphicsSettings>,
+    time_of_day: Res<TimeOfDay>,
+    mut ambient: ResMut<AmbientLight>,
+    mut lights: SkyLights,
+) {
+    if !time_of_day.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    let lighting = Lighting::at(time_of_day.hour());
+    ambient.color = Color::WHITE;
+    ambient.brightness = lighting.ambient;
+
+    for (mut light, mut cascades, mut transform) in lights.iter_mut() {
+        if settings.is_changed() {
+            *cascades = cascade_config(conf.as_ref(), settings.shadows());
+        }
+
+        light.color = lighting.color;
+        light.illuminance = lighting.illuminance;
+        light.shadows_enabled = lighting.shadows && settings.shadows().enabled();
+        *transform = Transform::IDENTITY.looking_at(-lighting.source, Vec3::Y);
+    }
+}
+
+struct Lighting {
+    /// Unit vector pointing towards the source of the directional light.
+    source: Vec3,
+    color: Color,
+    illuminance: f32,
+    shadows: bool,
+    ambient: f32,
+}
+
+impl Lighting {
+    /// Computes lighting parameters at a time of day given in hours.
+    fn at(hour: f32) -> Self {
+        // The sun rises at 6:00, culminates at 12:00 and sets at 18:00.
+        let angle = TAU * (hour / HOURS_PER_DAY - 0.25);
+        let sun = Vec3::new(
+            -angle.cos(),
+            angle.sin() * SUN_PATH_TILT.cos(),
+            angle.sin() * SUN_PATH_TILT.sin(),
+        );
+        let elevation = sun.y;
+
+        let daylight =
+            ((elevation + FULL_LIGHT_ELEVATION) / (2. * FULL_LIGHT_ELEVATION)).clamp(0., 1.);
+        let ambient = NIGHT_AMBIENT + daylight * (DAY_AMBIENT - NIGHT_AMBIENT);
+
+        // The moon is always on the opposite side of the sky.
+        let (source, strength, illuminance, color) = if elevation >= 0. {
+            let strength = (elevation / FULL_LIGHT_ELEVATION).min(1.);
+            // The sun is reddish close to the horizon.
+            let whiteness = (2. * elevation).min(1.);
+            let color = Vec3::new(1., 0.6, 0.35).lerp(Vec3::ONE, whiteness);
+            (sun, strength, SUN_ILLUMINANCE, color)
+        } else {
+            let strength = (-elevation / FULL_LIGHT_ELEVATION).min(1.);
+            (-sun, strength, MOON_ILLUMINANCE, Vec3::new(0.6, 0.7, 1.))
+        };
+
+        Self {
+            source,
+            color: Color::rgb(color.x, color.y, color.z),
+            illuminance: strength * illuminance,
+            shadows: strength >= SHADOW_STRENGTH,
+            ambient,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lighting() {
+        let noon = Lighting::at(12.);
+        assert!(noon.source.y > 0.7);
+        assert_eq!(noon.illuminance, SUN_ILLUMINANCE);
+        assert!((noon.ambient - DAY_AMBIENT).abs() < 1e-6);
+        assert!(noon.shadows);
+
+        let midnight = Lighting::at(0.);
+        assert!(midnight.source.y > 0.7);
+        assert_eq!(midnight.illuminance, MOON_ILLUMINANCE);
+        assert!((midnight.ambient - NIGHT_AMBIENT).abs() < 1e-6);
+        assert!(midnight.shadows);
+
+        for hour in [6., 18.] {
+            let twilight = Lighting::at(hour);
+            assert!(twilight.illuminance < 1.);
+            assert!(!twilight.shadows);
+            assert!(NIGHT_AMBIENT < twilight.ambient && twilight.ambient < DAY_AMBIENT);
+        }
+
+        // The sun moves across the sky from -x to +x.
+        assert!(Lighting::at(8.).source.x < 0.);
+        assert!(Lighting::at(16.).source.x > 0.);
+    }
+}
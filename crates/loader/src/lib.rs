@@ -1,12 +1,20 @@
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+use daylight::DaylightPlugin;
 use map::MapLoaderPlugin;
+use progress::LoadingProgressPlugin;
+pub use progress::{LoadingPhase, LoadingProgressEvent};
 
+mod daylight;
 mod map;
+mod progress;
 
 pub struct LoaderPluginGroup;
 
 impl PluginGroup for LoaderPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(MapLoaderPlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(MapLoaderPlugin)
+            .add(LoadingProgressPlugin)
+            .add(DaylightPlugin)
     }
 }
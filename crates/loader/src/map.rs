@@ -1,30 +1,55 @@
 use bevy::{
-    pbr::CascadeShadowConfigBuilder,
+    ecs::system::SystemParam,
     prelude::*,
     tasks::{IoTaskPool, Task},
 };
 use de_camera::MoveFocusEvent;
-use de_conf::Configuration;
+use de_combat::AttackEvent;
+use de_conf::{Configuration, GraphicsSettings};
+use de_construction::{AssemblyLine, UnderConstruction};
 use de_core::{
     assets::asset_path,
+    baseset::GameSet,
     cleanup::DespawnOnGameExit,
+    daytime::DEFAULT_START_HOUR,
     gamestate::GameState,
     gconfig::GameConfig,
     log_full_error,
-    objects::{ActiveObjectType, BuildingType, ObjectType},
+    objects::{Active, ActiveObjectType, BuildingType, ObjectType},
+    player::PlayerRange,
+    projection::ToFlat,
     state::AppState,
 };
+use de_energy::Battery;
 use de_map::{
-    content::InnerObject,
+    content::{InnerObject, Object},
+    generator,
+    heightmap::Heightmap,
     io::{load_map, MapLoadingError},
+    lint,
     map::Map,
+    scenario::Scenario,
     size::MapBounds,
+    zones::TerrainZones,
 };
+use de_objects::InitialHealths;
+use de_pathing::UpdateEntityPath;
+use de_persistence::{LoadedGame, SaveFile, SavedObject};
 use de_spawner::SpawnBundle;
 use de_terrain::TerrainBundle;
 use futures_lite::future;
 use iyes_progress::prelude::*;
 
+use crate::{
+    daylight::setup_light,
+    progress::{LoadingPhase, LoadingStatus},
+};
+
+/// Maximum number of objects spawned in a single frame. Spawning is spread
+/// over multiple frames so that the game stays responsive while large maps
+/// are loaded.
+const SPAWN_BUDGET: usize = 256;
+
 pub(crate) struct MapLoaderPlugin;
 
 impl Plugin for MapLoaderPlugin {
@@ -33,8 +58,19 @@ impl Plugin for MapLoaderPlugin {
             .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
             .add_system(
                 spawn_map
+                    .track_progress()
+                    .run_if(in_state(GameState::Loading))
+                    .before(spawn_objects),
+            )
+            .add_system(
+                spawn_objects
                     .track_progress()
                     .run_if(in_state(GameState::Loading)),
+            )
+            .add_system(
+                restore_attacks
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
@@ -42,12 +78,40 @@ impl Plugin for MapLoaderPlugin {
 #[derive(Resource)]
 struct MapLoadingTask(Task<Result<Map, MapLoadingError>>);
 
+/// Objects of a loaded map (or of a saved game) which are being spawned.
+#[derive(Resource)]
+struct ObjectSpawner {
+    map: Map,
+    /// Index of the next object to be spawned.
+    next: usize,
+    /// Entities of already spawned objects of a saved game.
+    spawned: Vec<Entity>,
+}
+
+/// Attack order of an object of a continued saved game. The order is
+/// restored once the object is fully spawned.
+#[derive(Component)]
+struct SavedAttack(Entity);
+
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<MapLoadingTask>();
+    commands.remove_resource::<ObjectSpawner>();
     commands.remove_resource::<MapBounds>();
+    commands.remove_resource::<Scenario>();
+    commands.remove_resource::<Heightmap>();
+    commands.remove_resource::<TerrainZones>();
 }
 
 fn load_map_system(mut commands: Commands, game_config: Res<GameConfig>) {
+    if let Some(seed) = game_config.map_seed() {
+        info!("Generating map from seed {seed}");
+        let max_player = game_config.max_player();
+        let task =
+            IoTaskPool::get().spawn(async move { Ok(generator::generate(seed, max_player)) });
+        commands.insert_resource(MapLoadingTask(task));
+        return;
+    }
+
     let map_path = if game_config.map_path().is_relative() {
         asset_path(game_config.map_path())
     } else {
@@ -55,16 +119,43 @@ fn load_map_system(mut commands: Commands, game_config: Res<GameConfig>) {
     };
 
     info!("Loading map from {}", map_path.display());
-    let task = IoTaskPool::get().spawn(async { load_map(map_path).await });
+    let task = IoTaskPool::get().spawn(async {
+        let map = load_map(map_path).await?;
+        // Issues are only reported so that imperfect maps stay playable.
+        for issue in lint::validate(&map) {
+            warn!("Map issue: {issue}");
+        }
+        Ok(map)
+    });
     commands.insert_resource(MapLoadingTask(task));
 }
 
+/// State of a saved game to be continued, see [`LoadedGame`].
+#[derive(SystemParam)]
+struct SavedGame<'w> {
+    save: Option<Res<'w, LoadedGame>>,
+    time: Res<'w, Time>,
+    healths: Res<'w, InitialHealths>,
+    path_events: EventWriter<'w, UpdateEntityPath>,
+}
+
+/// User configuration relevant to setup of the game environment.
+#[derive(SystemParam)]
+struct UserConf<'w> {
+    conf: Res<'w, Configuration>,
+    graphics: Res<'w, GraphicsSettings>,
+}
+
+/// Spawns the terrain and other map-wide entities once the map is loaded.
+/// Objects are spawned later by [`spawn_objects`].
 fn spawn_map(
     mut commands: Commands,
     task: Option<ResMut<MapLoadingTask>>,
     mut move_focus_events: EventWriter<MoveFocusEvent>,
-    user_config: Res<Configuration>,
+    user_conf: UserConf,
     game_config: Res<GameConfig>,
+    save: Option<Res<LoadedGame>>,
+    status: Option<ResMut<LoadingStatus>>,
 ) -> Progress {
     let mut task = match task {
         Some(task) => task,
@@ -76,6 +167,10 @@ fn spawn_map(
         None => return false.into(),
     };
 
+    if let Some(mut status) = status {
+        status.set(LoadingPhase::Terrain, true.into());
+    }
+
     info!("Map loaded, spawning");
     commands.remove_resource::<MapLoadingTask>();
 
@@ -87,8 +182,130 @@ fn spawn_map(
         }
     };
 
-    let initial_focus = map
-        .content()
+    let initial_focus = match save {
+        Some(ref save) => saved_focus(save, game_config.as_ref()),
+        None => map_focus(&map, game_config.as_ref()),
+    };
+    if let Some(focus) = initial_focus {
+        move_focus_events.send(MoveFocusEvent::new(focus));
+    }
+
+    let start_hour = map.metadata().start_time().unwrap_or(DEFAULT_START_HOUR);
+    setup_light(
+        &mut commands,
+        user_conf.conf.as_ref(),
+        user_conf.graphics.as_ref(),
+        start_hour,
+    );
+    let bounds = map.metadata().bounds();
+    let terrain = match map.heightmap() {
+        Some(heightmap) => {
+            commands.insert_resource(heightmap.clone());
+            TerrainBundle::from_heightmap(bounds, heightmap)
+        }
+        None => TerrainBundle::flat(bounds),
+    };
+    commands.spawn((terrain, DespawnOnGameExit));
+    commands.insert_resource(map.zones().clone());
+
+    match save {
+        Some(ref save) => info!("Spawning {} saved objects.", save.objects().len()),
+        None => {
+            info!("Spawning {} map objects.", map.content().objects().len());
+            // Scenarios are not continued in saved games.
+            if let Some(scenario) = map.scenario() {
+                commands.insert_resource(scenario.clone());
+            }
+        }
+    }
+
+    commands.insert_resource(map.metadata().bounds());
+    commands.insert_resource(ObjectSpawner {
+        map,
+        next: 0,
+        spawned: Vec::new(),
+    });
+    true.into()
+}
+
+/// Spawns objects of the loaded map or of the continued saved game, at most
+/// [`SPAWN_BUDGET`] of them per frame.
+fn spawn_objects(
+    mut commands: Commands,
+    task: Option<Res<MapLoadingTask>>,
+    spawner: Option<ResMut<ObjectSpawner>>,
+    game_config: Res<GameConfig>,
+    status: Option<ResMut<LoadingStatus>>,
+    mut saved: SavedGame,
+) -> Progress {
+    let Some(mut spawner) = spawner else {
+        // The number of objects is not known until the map is loaded.
+        let spawned = task.is_none();
+        if spawned {
+            if let Some(mut status) = status {
+                status.set(LoadingPhase::Assets, true.into());
+            }
+        }
+        return spawned.into();
+    };
+
+    let total = match saved.save {
+        Some(ref save) => save.objects().len(),
+        None => spawner.map.content().objects().len(),
+    };
+    let end = total.min(spawner.next + SPAWN_BUDGET);
+
+    match saved.save {
+        Some(ref save) => {
+            for object in &save.objects()[spawner.next..end] {
+                let entity = spawn_saved_object(
+                    &mut commands,
+                    object,
+                    saved.time.as_ref(),
+                    saved.healths.as_ref(),
+                    &mut saved.path_events,
+                );
+                spawner.spawned.push(entity);
+            }
+
+            // All entities must exist before attack orders are assigned.
+            if end == total {
+                for (object, &entity) in save.objects().iter().zip(&spawner.spawned) {
+                    if let Some(enemy) = object.attack() {
+                        commands
+                            .entity(entity)
+                            .insert(SavedAttack(spawner.spawned[enemy]));
+                    }
+                }
+            }
+        }
+        None => {
+            let players = game_config.players();
+            for object in &spawner.map.content().objects()[spawner.next..end] {
+                spawn_map_object(&mut commands, object, &players);
+            }
+        }
+    }
+
+    if end == total {
+        commands.remove_resource::<ObjectSpawner>();
+    } else {
+        spawner.next = end;
+    }
+
+    let progress = Progress {
+        done: end as u32,
+        total: total as u32,
+    };
+    if let Some(mut status) = status {
+        status.set(LoadingPhase::Objects, progress);
+    }
+    progress
+}
+
+/// Returns position of the base of the playable player.
+fn map_focus(map: &Map, game_config: &GameConfig) -> Option<Vec2> {
+    map.content()
         .objects()
         .iter()
         .filter_map(|object| match object.inner() {
@@ -103,76 +320,98 @@ fn spawn_map(
             }
             _ => None,
         })
-        .next();
-    if let Some(focus) = initial_focus {
-        move_focus_events.send(MoveFocusEvent::new(focus));
-    }
+        .next()
+}
+
+/// Returns position of a base of the playable player in a saved game.
+fn saved_focus(save: &SaveFile, game_config: &GameConfig) -> Option<Vec2> {
+    save.objects()
+        .iter()
+        .find(|object| {
+            object
+                .player()
+                .map_or(false, |player| game_config.locals().is_playable(player))
+                && object.object_type()
+                    == ObjectType::Active(ActiveObjectType::Building(BuildingType::Base))
+        })
+        .map(|object| object.transform().translation.to_flat())
+}
 
-    setup_light(&mut commands, user_config.as_ref());
-    commands.spawn((
-        TerrainBundle::flat(map.metadata().bounds()),
+fn spawn_map_object(commands: &mut Commands, object: &Object, players: &PlayerRange) {
+    let (mut entity_commands, object_type) = match object.inner() {
+        InnerObject::Active(object) => {
+            let player = object.player();
+            if !players.contains(player) {
+                return;
+            }
+
+            (
+                commands.spawn(player),
+                ObjectType::Active(object.object_type()),
+            )
+        }
+        InnerObject::Inactive(object) => (
+            commands.spawn_empty(),
+            ObjectType::Inactive(object.object_type()),
+        ),
+    };
+
+    entity_commands.insert((
+        SpawnBundle::new(object_type, object.placement().to_transform()),
+        DespawnOnGameExit,
+    ));
+}
+
+fn spawn_saved_object(
+    commands: &mut Commands,
+    object: &SavedObject,
+    time: &Time,
+    healths: &InitialHealths,
+    path_events: &mut EventWriter<UpdateEntityPath>,
+) -> Entity {
+    let object_type = object.object_type();
+    let mut entity_commands = commands.spawn((
+        SpawnBundle::new(object_type, object.transform()),
         DespawnOnGameExit,
     ));
 
-    let players = game_config.players();
-    for object in map.content().objects() {
-        let (mut entity_commands, object_type) = match object.inner() {
-            InnerObject::Active(object) => {
-                let player = object.player();
-                if !players.contains(player) {
-                    continue;
-                }
+    if let ObjectType::Active(active_type) = object_type {
+        // Presence of both is guaranteed by save validation.
+        let player = object.player().unwrap();
+        let mut health = healths.health(active_type).clone();
+        health.set_fraction(object.health().unwrap());
+        entity_commands.insert((player, health));
 
-                (
-                    commands.spawn(player),
-                    ObjectType::Active(object.object_type()),
-                )
-            }
-            InnerObject::Inactive(object) => (
-                commands.spawn_empty(),
-                ObjectType::Inactive(object.object_type()),
-            ),
-        };
-
-        entity_commands.insert((
-            SpawnBundle::new(object_type, object.placement().to_transform()),
-            DespawnOnGameExit,
-        ));
+        if let Some(energy) = object.energy() {
+            entity_commands.insert(Battery::default().with_energy(energy));
+        }
+        if !object.production().is_empty() {
+            entity_commands.insert(AssemblyLine::restored(
+                object.production().iter().copied(),
+                time.elapsed(),
+            ));
+        }
+        if let Some(progress) = object.construction() {
+            entity_commands.insert(UnderConstruction::restored(progress));
+        }
     }
 
-    commands.insert_resource(map.metadata().bounds());
-    true.into()
+    let entity = entity_commands.id();
+    if let Some(target) = object.target() {
+        path_events.send(UpdateEntityPath::new(entity, target));
+    }
+    entity
 }
 
-fn setup_light(commands: &mut Commands, conf: &Configuration) {
-    commands.insert_resource(AmbientLight {
-        color: Color::WHITE,
-        brightness: 0.6,
-    });
-
-    let mut transform = Transform::IDENTITY;
-    transform.look_at(Vec3::new(1., -1., 0.), Vec3::new(1., 1., 0.));
-
-    let cascade_shadow_config = CascadeShadowConfigBuilder {
-        num_cascades: 5,
-        maximum_distance: 1000.,
-        first_cascade_far_bound: conf.camera().min_distance().inner() * 2.,
-        ..default()
+/// Restores attack orders of a continued saved game once the attacking
+/// objects are spawned (and thus have their cannons).
+fn restore_attacks(
+    mut commands: Commands,
+    attacks: Query<(Entity, &SavedAttack), With<Active>>,
+    mut attack_events: EventWriter<AttackEvent>,
+) {
+    for (attacker, attack) in attacks.iter() {
+        commands.entity(attacker).remove::<SavedAttack>();
+        attack_events.send(AttackEvent::new(attacker, attack.0));
     }
-    .build();
-
-    commands.spawn((
-        DirectionalLightBundle {
-            directional_light: DirectionalLight {
-                color: Color::WHITE,
-                illuminance: 30000.,
-                shadows_enabled: true,
-                ..Default::default()
-            },
-            cascade_shadow_config,
-            transform,
-            ..Default::default()
-        },
-        DespawnOnGameExit,
-    ));
 }
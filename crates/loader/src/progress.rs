@@ -0,0 +1,175 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use de_core::gamestate::GameState;
+use iyes_progress::{prelude::*, ProgressSystemSet};
+
+pub(crate) struct LoadingProgressPlugin;
+
+impl Plugin for LoadingProgressPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadingProgressEvent>()
+            .add_system(setup.in_schedule(OnEnter(GameState::Loading)))
+            .add_system(cleanup.in_schedule(OnExit(GameState::Loading)))
+            .add_system(
+                report
+                    .in_base_set(ProgressSystemSet::CheckProgress)
+                    .run_if(in_state(GameState::Loading))
+                    .run_if(resource_exists::<LoadingStatus>()),
+            );
+    }
+}
+
+/// Phase of game loading. The phases follow one after another in the order
+/// of declaration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadingPhase {
+    /// The map is being read from disk (or generated) and parsed.
+    MapParse,
+    /// Terrain and other map-wide entities are being spawned.
+    Terrain,
+    /// Map objects (or objects of a saved game) are being spawned.
+    Objects,
+    /// The map is fully spawned and the game waits for the remaining assets.
+    Assets,
+}
+
+impl fmt::Display for LoadingPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MapParse => write!(f, "Loading map"),
+            Self::Terrain => write!(f, "Preparing terrain"),
+            Self::Objects => write!(f, "Spawning objects"),
+            Self::Assets => write!(f, "Loading assets"),
+        }
+    }
+}
+
+/// This event is sent during [`GameState::Loading`] whenever the loading
+/// phase, its progress or the overall loading progress changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadingProgressEvent {
+    phase: LoadingPhase,
+    done: u32,
+    total: u32,
+    percent: f32,
+}
+
+impl LoadingProgressEvent {
+    pub fn phase(&self) -> LoadingPhase {
+        self.phase
+    }
+
+    /// Number of already processed units of work (for example spawned
+    /// objects) in the current phase.
+    pub fn done(&self) -> u32 {
+        self.done
+    }
+
+    /// Total number of units of work in the current phase.
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Overall loading progress in percent (between 0 and 100).
+    pub fn percent(&self) -> f32 {
+        self.percent
+    }
+}
+
+impl fmt::Display for LoadingProgressEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.phase {
+            LoadingPhase::Objects => write!(f, "{} {}/{}", self.phase, self.done, self.total),
+            _ => write!(f, "{}", self.phase),
+        }
+    }
+}
+
+/// Current loading phase and progress within the phase. It is updated by the
+/// loading systems and reported via [`LoadingProgressEvent`].
+#[derive(Resource)]
+pub(crate) struct LoadingStatus {
+    phase: LoadingPhase,
+    progress: Progress,
+    /// Last sent event, used to avoid sending the same event repeatedly.
+    reported: Option<LoadingProgressEvent>,
+}
+
+impl LoadingStatus {
+    pub(crate) fn set(&mut self, phase: LoadingPhase, progress: Progress) {
+        self.phase = phase;
+        self.progress = progress;
+    }
+}
+
+impl Default for LoadingStatus {
+    fn default() -> Self {
+        Self {
+            phase: LoadingPhase::MapParse,
+            progress: false.into(),
+            reported: None,
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<LoadingStatus>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<LoadingStatus>();
+}
+
+fn report(
+    mut status: ResMut<LoadingStatus>,
+    counter: Res<ProgressCounter>,
+    mut events: EventWriter<LoadingProgressEvent>,
+) {
+    let overall = counter.progress();
+    let (done, total) = match status.phase {
+        // All other tracked systems are waited for in this phase.
+        LoadingPhase::Assets => (overall.done, overall.total),
+        _ => (status.progress.done, status.progress.total),
+    };
+    let percent = if overall.total == 0 {
+        0.
+    } else {
+        100. * (overall.done.min(overall.total) as f32) / (overall.total as f32)
+    };
+
+    let event = LoadingProgressEvent {
+        phase: status.phase,
+        done,
+        total,
+        percent,
+    };
+    if status.reported.as_ref() != Some(&event) {
+        status.reported = Some(event);
+        events.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_display() {
+        let event = LoadingProgressEvent {
+            phase: LoadingPhase::Objects,
+            done: 340,
+            total: 800,
+            percent: 52.5,
+        };
+        assert_eq!(event.to_string(), "Spawning objects 340/800");
+
+        let event = LoadingProgressEvent {
+            phase: LoadingPhase::MapParse,
+            done: 0,
+            total: 1,
+            percent: 0.,
+        };
+        assert_eq!(event.to_string(), "Loading map");
+    }
+}
@@ -18,9 +18,10 @@ use crate::{
     db_error,
 };
 
-// This should correspond to the longest valid socket address. IPv6 hast up to
-// 39 characters + colon + 5 characters for port number.
-const SERVER_LEN: usize = 45;
+// This should correspond to the longest valid socket address. IPv6 has up to
+// 45 characters (with embedded IPv4) + 11 characters of scope ID + 2 brackets
+// + colon + 5 characters for port number.
+const SERVER_LEN: usize = 64;
 
 #[derive(Clone)]
 pub(super) struct Games {
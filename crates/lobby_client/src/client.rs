@@ -45,7 +45,9 @@ impl Authentication {
         self.token.is_some()
     }
 
-    fn token(&self) -> Option<&str> {
+    /// Authentication token of the signed in user. It is also used to
+    /// authenticate the user to DE Connector.
+    pub fn token(&self) -> Option<&str> {
         self.token.as_deref()
     }
 
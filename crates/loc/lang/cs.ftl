@@ -0,0 +1,241 @@
+# Czech translations of the user interface. Missing messages fall back to
+# English.
+
+## Main menu
+
+main-menu-singleplayer = Hra jednoho hráče
+main-menu-multiplayer = Hra více hráčů
+main-menu-map-editor = Editor map
+main-menu-settings = Nastavení
+main-menu-controls = Ovládání
+main-menu-quit = Ukončit hru
+
+## Single player game
+
+singleplayer-start-game = Spustit hru
+singleplayer-select-map = Vybrat mapu
+singleplayer-load-game = Nahrát hru
+singleplayer-difficulty = AI: { $difficulty }
+difficulty-easy = Lehká
+difficulty-normal = Normální
+difficulty-hard = Těžká
+
+## Map selection
+
+map-selection-random = Náhodná mapa
+map-selection-hint = Najeďte na mapu pro zobrazení podrobností.
+map-details-author = autor: { $author }
+map-details-players = Hráči: až { $players }
+map-details-recommended = (doporučeno { $players })
+map-details-size = Velikost: { $width } × { $height } m
+
+## Multiplayer
+
+create-game-name = Název
+create-game-max-players = Max. hráčů
+create-game-password = Heslo
+create-game-map = Mapa
+create-game-create = Vytvořit hru
+game-listing-create = Vytvořit hru
+game-listing-game = { $name } - { $map } ({ $players }/{ $max-players })
+game-listing-protected-game = { $name } - { $map } ({ $players }/{ $max-players }) [heslo]
+game-listing-join = Připojit
+sign-in-username = Uživatel:
+sign-in-password = Heslo:
+sign-in-sign-in = Přihlásit
+sign-in-sign-up = Registrovat
+
+## Saved games
+
+load-game-no-saves = Žádné uložené hry.
+
+## Game result
+
+after-game-won = Vyhráli jste!
+after-game-lost = Prohráli jste!
+after-game-error = Chyba: { $message }
+after-game-stats = Hráč { $player }: postaveno { $built }, ztraceno { $lost }, zničeno { $killed }, poškození { $damage }, suroviny { $resources }, APM { $apm }
+after-game-units-built = Postaveno
+after-game-units-lost = Ztraceno
+after-game-units-killed = Zničeno
+after-game-damage-dealt = Poškození
+after-game-resources-gathered = Suroviny
+after-game-apm = APM
+
+## Multiplayer errors
+
+mp-error-map-unavailable = Mapa hry není dostupná lokálně.
+mp-error-map-download = Mapu hry se nepodařilo stáhnout.
+mp-error-map-invalid = Stažená mapa není platná: { $error }
+mp-error-network-overloaded = Síťová vrstva nestíhá.
+mp-error-network-output-closed = Výstupní síťový kanál byl nečekaně uzavřen.
+mp-error-network-receiver-closed = Příjem síťových zpráv byl nečekaně uzavřen.
+mp-error-network-errors-closed = Příjem chyb síťového spojení byl nečekaně uzavřen.
+mp-error-invalid-data = Přijata neplatná data: { $error }
+mp-error-invalid-commands = Přijaty neplatné příkazy hráče { $player } pro tik { $tick }: { $error }
+mp-error-connection = Chyba spojení s { $address }.
+mp-error-connection-lost = Spojení se serverem bylo ztraceno.
+mp-error-spectator-open = Divák nemůže založit novou hru.
+mp-error-invalid-game-opened = Neplatná zpráva GameOpened: { $error }
+mp-error-open-different-game = Hru nelze založit, hráč se již připojil k jiné hře.
+mp-error-open-invalid-listing = Hru nelze založit, název hry nebo mapy je příliš dlouhý.
+mp-error-open-too-many-players = Hru nelze založit, server nepovoluje tolik hráčů.
+mp-error-open-unavailable = Hru nelze založit, server nemůže hostit další hry.
+mp-error-open-unauthenticated = Hru nelze založit, hráč není přihlášen.
+mp-error-open-invalid-slots = Hru nelze založit, neplatné týmy nebo sloty AI.
+mp-error-not-in-game = Hráč již není součástí hry.
+mp-error-rejoined-different-player = Opětovně připojen jako jiný hráč { $player }.
+mp-error-invalid-player = Server přidělil neplatného hráče: { $error }
+mp-error-join-full = Hra je plná, nelze se připojit.
+mp-error-join-already-joined = Již připojen ke hře, nelze se připojit znovu.
+mp-error-join-different-game = Hráč se již připojil k jiné hře.
+mp-error-join-started = Hra již začala, nelze se připojit.
+mp-error-join-invalid-token = Hráč již není součástí hry, nelze se znovu připojit.
+mp-error-join-banned = Hráč byl ze hry vyhozen, nelze se připojit.
+mp-error-join-unauthenticated = Hráč není přihlášen, nelze se připojit.
+mp-error-kicked = Hráč byl ze hry vyhozen.
+mp-error-invalid-player-left = Hru opustil neplatný hráč: { $error }
+mp-error-invalid-player-kicked = Ze hry byl vyhozen neplatný hráč: { $error }
+mp-error-rate-limited = Odpojen ze hry kvůli odesílání příliš mnoha zpráv.
+mp-error-version-outdated-client = Nelze se připojit ke hře: nekompatibilní verze protokolu { $client } (server používá { $server }), aktualizujte hru.
+mp-error-version-outdated-server = Nelze se připojit ke hře: nekompatibilní verze protokolu { $client } (server používá { $server }), server je zastaralý.
+mp-error-wrong-password = Nelze se připojit ke hře: špatné heslo.
+mp-error-invalid-state-request = Neplatný hráč si vyžádal stav hry: { $error }
+mp-error-rejoin-timeout = Nepodařilo se včas znovu připojit ke hře.
+
+## Settings
+
+settings-shadows = Stíny: { $value }
+settings-msaa = Vyhlazování: { $value }
+settings-scale = Měřítko: { $value } %
+settings-vsync = VSync: { $value }
+settings-language = Jazyk: { $value }
+settings-on = Zapnuto
+settings-off = Vypnuto
+settings-low = Nízké
+settings-medium = Střední
+settings-high = Vysoké
+
+## Controls
+
+controls-binding = { $action }: { $binding }
+controls-waiting = { $action }: stiskněte klávesu...
+controls-error = Nelze přiřadit { $binding }: { $error }.
+controls-error-conflict = { $first } a { $second } by měly stejnou klávesu
+controls-error-modifier = { $action } nelze přiřadit modifikační klávese
+controls-error-ctrl = { $action } nesmí používat Ctrl, je vyhrazen pro uložení skupiny nebo záložky
+action-group = Skupina { $number }
+action-bookmark = Záložka { $number }
+action-card = Příkazová karta { $number }
+action-camera-left = Kamera vlevo
+action-camera-right = Kamera vpravo
+action-camera-up = Kamera nahoru
+action-camera-down = Kamera dolů
+action-camera-pivot = Otáčení kamery
+action-camera-follow = Sledovat jednotku
+action-jump-to-alert = Přejít na upozornění
+action-select = Výběr
+action-command = Rozkaz
+action-attack-move = Útočný přesun
+action-patrol = Hlídka
+action-focus = Soustředěná palba
+action-place-base = Postavit základnu
+action-place-power-hub = Postavit rozvodnu
+action-stance-aggressive = Útočný postoj
+action-stance-defensive = Obranný postoj
+action-stance-hold-fire = Zastavit palbu
+action-stance-hold-position = Držet pozici
+action-formation-line = Formace v řadě
+action-formation-wedge = Formace klín
+action-formation-box = Formace čtverec
+action-menu = Nabídka
+action-select-all = Vybrat vše
+action-select-visible = Vybrat viditelné
+action-cycle-subgroup = Přepnout podskupinu
+action-pause = Pauza
+
+## Map editor
+
+editor-menu-new-map = Nová mapa
+editor-menu-open-map = Otevřít mapu
+editor-loading = Nahrávání mapy...
+editor-map = { $name } -- { $width } x { $height } m, hráčů: { $players }
+editor-map-unsaved = { $name } (neuloženo) -- { $width } x { $height } m, hráčů: { $players }
+editor-file = Soubor: { $path }
+editor-file-none = Soubor: zatím neuloženo
+editor-spawns = Starty:
+editor-spawn-none = žádný
+editor-tool = Nástroj: { $tool }
+editor-tool-place-active = umístit { $object } hráče { $player }
+editor-tool-place-inactive = umístit { $object }
+editor-tool-rotate = otáčet objekty (LTM / PTM)
+editor-tool-delete = mazat objekty
+editor-tool-height = zvýšit (LTM) / snížit (PTM) terén
+editor-tool-zones = malovat { $zones } (LTM) / mazat zóny (PTM)
+editor-tool-spawn = nastavit start hráče { $player }
+editor-zones-water = vodu
+editor-zones-cliffs = útesy
+editor-zones-craters = krátery
+editor-keys-tools = [1] umístit objekty  [2] otáčet  [3] mazat  [4] výška terénu  [5] zóny  [6] starty
+editor-keys-types = [Tab] další typ objektu nebo zóny  [P] další hráč
+editor-keys-map = [ / ] méně / více hráčů  [-] / [=] menší / větší mapa
+editor-keys-general = [WASD] pohyb  [Q] / [E] otáčení  [kolečko] přiblížení  [Ctrl+S] uložit  [Esc] konec
+
+## In-game menu and HUD
+
+game-menu-resume = Pokračovat
+game-menu-settings = Nastavení
+game-menu-save = Uložit hru
+game-menu-surrender = Vzdát se
+game-menu-quit = Ukončit do nabídky
+game-menu-back = Zpět
+details-battery = Baterie: { $energy } / { $capacity } ({ $percent } %)
+details-selected = Vybráno: { $count }
+details-rank = Hodnost: { $rank }
+details-construction = Stavba: { $percent } %
+details-manufacturing = Výroba: { $percent } %, ve frontě { $queued }
+card-tooltip = { $action } [{ $binding }]
+card-manufacture = Vyrobit { $unit }: { $energy }, { $time } s
+card-cancel = Zrušit výrobu
+card-construct = Postavit { $building }: { $time } s
+card-stance = Postoj: { $stance }
+card-unload-caption = Vyložit
+card-unload = Vyložit všechny přepravované jednotky
+alert-under-attack = Jsme pod útokem
+alert-building-complete = Stavba dokončena
+alert-player-left = Hráč { $player } opustil hru
+alert-low-power = Nedostatek energie
+rank-recruit = Nováček
+rank-veteran = Veterán
+rank-elite = Elita
+rank-hero = Hrdina
+
+## Objects
+
+object-base = Základna
+object-power-hub = Rozvodna
+object-attacker = Útočník
+object-tree = Strom
+
+## Toasts
+
+toast-conf-loading-failed = Nahrání konfigurace selhalo.
+toast-no-map-selected = Není vybrána žádná mapa.
+toast-invalid-seed = Neplatné semínko: { $error }
+toast-map-error = Chyba mapy: { $error }
+toast-invalid-max-players = Neplatný počet hráčů: { $error }
+toast-not-implemented = Zatím není implementováno (issue #301).
+toast-password-required = Hra je chráněna heslem, zadejte heslo.
+toast-game-listing-failed = Nepodařilo se načíst seznam her: { $error }
+toast-game-listing-timeout = Načítání seznamu her vypršelo.
+toast-save-listing-failed = Nepodařilo se načíst seznam uložených her: { $error }
+toast-game-loading-failed = Nepodařilo se nahrát hru: { $error }
+toast-game-saving = Hra se již ukládá.
+toast-game-saved = Hra uložena.
+toast-game-saving-failed = Nepodařilo se uložit hru: { $error }
+toast-map-saving = Mapa se již ukládá.
+toast-map-invalid = Mapu nelze uložit: { $error }
+toast-map-issues = Mapa má problémů: { $count }, např. { $issue }.
+toast-map-saved = Mapa uložena.
+toast-map-saving-failed = Nepodařilo se uložit mapu: { $error }
+toast-map-loading-failed = Nahrání mapy selhalo: { $error }
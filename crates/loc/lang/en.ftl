@@ -0,0 +1,241 @@
+# English translations of the user interface. English is the fallback
+# language: all messages must be present here.
+
+## Main menu
+
+main-menu-singleplayer = Singleplayer
+main-menu-multiplayer = Multiplayer
+main-menu-map-editor = Map Editor
+main-menu-settings = Settings
+main-menu-controls = Controls
+main-menu-quit = Quit Game
+
+## Single player game
+
+singleplayer-start-game = Start Game
+singleplayer-select-map = Select Map
+singleplayer-load-game = Load Game
+singleplayer-difficulty = AI: { $difficulty }
+difficulty-easy = Easy
+difficulty-normal = Normal
+difficulty-hard = Hard
+
+## Map selection
+
+map-selection-random = Random Map
+map-selection-hint = Point at a map to see its details.
+map-details-author = by { $author }
+map-details-players = Players: up to { $players }
+map-details-recommended = ({ $players } recommended)
+map-details-size = Size: { $width } × { $height } m
+
+## Multiplayer
+
+create-game-name = Name
+create-game-max-players = Max Players
+create-game-password = Password
+create-game-map = Map
+create-game-create = Create Game
+game-listing-create = Create Game
+game-listing-game = { $name } - { $map } ({ $players }/{ $max-players })
+game-listing-protected-game = { $name } - { $map } ({ $players }/{ $max-players }) [password]
+game-listing-join = Join
+sign-in-username = Username:
+sign-in-password = Password:
+sign-in-sign-in = Sign In
+sign-in-sign-up = Sign Up
+
+## Saved games
+
+load-game-no-saves = No saved games.
+
+## Game result
+
+after-game-won = You have won!
+after-game-lost = You have lost!
+after-game-error = Error: { $message }
+after-game-stats = Player { $player }: built { $built }, lost { $lost }, killed { $killed }, damage { $damage }, resources { $resources }, APM { $apm }
+after-game-units-built = Built
+after-game-units-lost = Lost
+after-game-units-killed = Killed
+after-game-damage-dealt = Damage
+after-game-resources-gathered = Resources
+after-game-apm = APM
+
+## Multiplayer errors
+
+mp-error-map-unavailable = Map of the game is not available locally.
+mp-error-map-download = Map of the game could not be downloaded.
+mp-error-map-invalid = Downloaded map is not valid: { $error }
+mp-error-network-overloaded = Network stack is not keeping up.
+mp-error-network-output-closed = Network output channel is unexpectedly closed.
+mp-error-network-receiver-closed = Network message receiver is unexpectedly closed.
+mp-error-network-errors-closed = Network connection errors receiver is unexpectedly closed.
+mp-error-invalid-data = Invalid data received: { $error }
+mp-error-invalid-commands = Invalid commands of { $player } for tick { $tick } received: { $error }
+mp-error-connection = Connection error with { $address }.
+mp-error-connection-lost = Connection to the server was lost.
+mp-error-spectator-open = A new game cannot be opened by a spectator.
+mp-error-invalid-game-opened = Invalid GameOpened: { $error }
+mp-error-open-different-game = Cannot open game, the player already joined a game.
+mp-error-open-invalid-listing = Cannot open game, game or map name is too long.
+mp-error-open-too-many-players = Cannot open game, the server does not allow so many players.
+mp-error-open-unavailable = Cannot open game, the server cannot host more games.
+mp-error-open-unauthenticated = Cannot open game, the player is not signed in.
+mp-error-open-invalid-slots = Cannot open game, invalid team or AI slots.
+mp-error-not-in-game = Player is no longer part of the game.
+mp-error-rejoined-different-player = Rejoined game as a different player { $player }.
+mp-error-invalid-player = Invalid player assigned by the server: { $error }
+mp-error-join-full = Game is full, cannot join.
+mp-error-join-already-joined = Already joined the game, cannot re-join.
+mp-error-join-different-game = Player already joined a different game.
+mp-error-join-started = Game has already started, cannot join.
+mp-error-join-invalid-token = Player is no longer part of the game, cannot rejoin.
+mp-error-join-banned = Player was kicked from the game, cannot join.
+mp-error-join-unauthenticated = Player is not signed in, cannot join.
+mp-error-kicked = Player was kicked from the game.
+mp-error-invalid-player-left = Invalid player left the game: { $error }
+mp-error-invalid-player-kicked = Invalid player kicked from the game: { $error }
+mp-error-rate-limited = Disconnected from the game for sending too many messages.
+mp-error-version-outdated-client = Could not join the game: incompatible protocol version { $client } (server uses { $server }), update the game.
+mp-error-version-outdated-server = Could not join the game: incompatible protocol version { $client } (server uses { $server }), the server is outdated.
+mp-error-wrong-password = Could not join the game: wrong password.
+mp-error-invalid-state-request = Invalid player requested game state: { $error }
+mp-error-rejoin-timeout = Could not rejoin the game in time.
+
+## Settings
+
+settings-shadows = Shadows: { $value }
+settings-msaa = Anti-aliasing: { $value }
+settings-scale = Scale: { $value }%
+settings-vsync = VSync: { $value }
+settings-language = Language: { $value }
+settings-on = On
+settings-off = Off
+settings-low = Low
+settings-medium = Medium
+settings-high = High
+
+## Controls
+
+controls-binding = { $action }: { $binding }
+controls-waiting = { $action }: press a key...
+controls-error = Cannot bind { $binding }: { $error }.
+controls-error-conflict = { $first } and { $second } would be bound to the same key
+controls-error-modifier = { $action } cannot be bound to a modifier key
+controls-error-ctrl = { $action } must not use Ctrl, it is reserved for storing of the group or bookmark
+action-group = Group { $number }
+action-bookmark = Bookmark { $number }
+action-card = Command card { $number }
+action-camera-left = Camera left
+action-camera-right = Camera right
+action-camera-up = Camera up
+action-camera-down = Camera down
+action-camera-pivot = Camera pivot
+action-camera-follow = Follow unit
+action-jump-to-alert = Jump to alert
+action-select = Select
+action-command = Command
+action-attack-move = Attack move
+action-patrol = Patrol
+action-focus = Focus fire
+action-place-base = Place base
+action-place-power-hub = Place power hub
+action-stance-aggressive = Aggressive
+action-stance-defensive = Defensive
+action-stance-hold-fire = Hold fire
+action-stance-hold-position = Hold position
+action-formation-line = Line formation
+action-formation-wedge = Wedge formation
+action-formation-box = Box formation
+action-menu = Menu
+action-select-all = Select all
+action-select-visible = Select visible
+action-cycle-subgroup = Cycle subgroup
+action-pause = Pause
+
+## Map editor
+
+editor-menu-new-map = New Map
+editor-menu-open-map = Open Map
+editor-loading = Loading map...
+editor-map = { $name } -- { $width } x { $height } m, { $players } players
+editor-map-unsaved = { $name } (unsaved) -- { $width } x { $height } m, { $players } players
+editor-file = File: { $path }
+editor-file-none = File: not stored yet
+editor-spawns = Spawns:
+editor-spawn-none = none
+editor-tool = Tool: { $tool }
+editor-tool-place-active = place { $object } of player { $player }
+editor-tool-place-inactive = place { $object }
+editor-tool-rotate = rotate objects (LMB / RMB)
+editor-tool-delete = delete objects
+editor-tool-height = raise (LMB) / lower (RMB) terrain
+editor-tool-zones = paint { $zones } (LMB) / erase zones (RMB)
+editor-tool-spawn = set spawn position of player { $player }
+editor-zones-water = water
+editor-zones-cliffs = cliffs
+editor-zones-craters = craters
+editor-keys-tools = [1] place objects  [2] rotate  [3] delete  [4] terrain height  [5] zones  [6] spawns
+editor-keys-types = [Tab] next object or zone type  [P] next player
+editor-keys-map = [ / ] fewer / more players  [-] / [=] smaller / larger map
+editor-keys-general = [WASD] move  [Q] / [E] rotate  [wheel] zoom  [Ctrl+S] save  [Esc] exit
+
+## In-game menu and HUD
+
+game-menu-resume = Resume
+game-menu-settings = Settings
+game-menu-save = Save Game
+game-menu-surrender = Surrender
+game-menu-quit = Quit to Menu
+game-menu-back = Back
+details-battery = Battery: { $energy } / { $capacity } ({ $percent }%)
+details-selected = Selected { $count }
+details-rank = Rank: { $rank }
+details-construction = Construction: { $percent }%
+details-manufacturing = Manufacturing: { $percent }%, { $queued } queued
+card-tooltip = { $action } [{ $binding }]
+card-manufacture = Manufacture { $unit }: { $energy }, { $time } s
+card-cancel = Cancel manufacturing
+card-construct = Construct { $building }: { $time } s
+card-stance = Stance: { $stance }
+card-unload-caption = Unload
+card-unload = Unload all carried units
+alert-under-attack = We are under attack
+alert-building-complete = Construction complete
+alert-player-left = Player { $player } left the game
+alert-low-power = Low power
+rank-recruit = Recruit
+rank-veteran = Veteran
+rank-elite = Elite
+rank-hero = Hero
+
+## Objects
+
+object-base = Base
+object-power-hub = Power Hub
+object-attacker = Attacker
+object-tree = Tree
+
+## Toasts
+
+toast-conf-loading-failed = Configuration loading failed.
+toast-no-map-selected = No map selected.
+toast-invalid-seed = Invalid seed: { $error }
+toast-map-error = Map error: { $error }
+toast-invalid-max-players = Invalid max players: { $error }
+toast-not-implemented = Not yet implemented (issue #301).
+toast-password-required = The game is password protected, enter the password.
+toast-game-listing-failed = Failed to list games: { $error }
+toast-game-listing-timeout = Game listing timed out.
+toast-save-listing-failed = Failed to list saved games: { $error }
+toast-game-loading-failed = Failed to load the game: { $error }
+toast-game-saving = The game is already being saved.
+toast-game-saved = Game saved.
+toast-game-saving-failed = Failed to save the game: { $error }
+toast-map-saving = The map is already being saved.
+toast-map-invalid = The map cannot be saved: { $error }
+toast-map-issues = The map has { $count } issue(s), e.g. { $issue }.
+toast-map-saved = Map saved.
+toast-map-saving-failed = Failed to save the map: { $error }
+toast-map-loading-failed = Map loading failed: { $error }
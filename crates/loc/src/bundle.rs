@@ -0,0 +1,215 @@
+//! This module implements parsing and formatting of Fluent resources.
+//!
+//! Only a subset of Fluent syntax is supported: comments, simple messages
+//! (possibly spanning multiple indented lines) and variable placeables, e.g.
+//! `{ $name }`.
+
+use std::fmt;
+
+use ahash::AHashMap;
+use thiserror::Error;
+
+/// Parsed messages of a single language.
+pub(crate) struct Bundle {
+    messages: AHashMap<String, Vec<Element>>,
+}
+
+impl Bundle {
+    /// Parses a Fluent resource.
+    pub(crate) fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut messages = AHashMap::new();
+        let mut current: Option<(String, String)> = None;
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+
+            if line.starts_with(' ') && !line.trim().is_empty() {
+                match current.as_mut() {
+                    Some((_, value)) => {
+                        if !value.is_empty() {
+                            value.push('\n');
+                        }
+                        value.push_str(line.trim());
+                    }
+                    None => return Err(ParseError::Syntax(line_number)),
+                }
+                continue;
+            }
+
+            if let Some((id, value)) = current.take() {
+                insert(&mut messages, id, &value, line_number)?;
+            }
+
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((id, value)) = line.split_once('=') else {
+                return Err(ParseError::Syntax(line_number));
+            };
+            let id = id.trim_end();
+            if !is_identifier(id) {
+                return Err(ParseError::Identifier(line_number));
+            }
+            current = Some((id.to_owned(), value.trim_start().to_owned()));
+        }
+
+        if let Some((id, value)) = current.take() {
+            insert(&mut messages, id, &value, source.lines().count())?;
+        }
+
+        Ok(Self { messages })
+    }
+
+    /// Iterates over identifiers of all messages.
+    #[cfg(test)]
+    pub(crate) fn ids(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map(String::as_str)
+    }
+
+    /// Formats a message or returns None if the message does not exist.
+    /// Variables which are not given in `args` are formatted as their names
+    /// in braces.
+    pub(crate) fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> Option<String> {
+        let elements = self.messages.get(id)?;
+
+        let mut text = String::new();
+        for element in elements {
+            match element {
+                Element::Text(part) => text.push_str(part),
+                Element::Variable(name) => match args.iter().find(|(arg, _)| arg == name) {
+                    Some((_, value)) => text.push_str(&value.to_string()),
+                    None => {
+                        text.push_str("{$");
+                        text.push_str(name);
+                        text.push('}');
+                    }
+                },
+            }
+        }
+        Some(text)
+    }
+}
+
+fn insert(
+    messages: &mut AHashMap<String, Vec<Element>>,
+    id: String,
+    value: &str,
+    line_number: usize,
+) -> Result<(), ParseError> {
+    if value.is_empty() {
+        return Err(ParseError::Empty(line_number));
+    }
+    let elements = parse_value(value).ok_or(ParseError::Placeable(line_number))?;
+    if messages.insert(id, elements).is_some() {
+        return Err(ParseError::Duplicate(line_number));
+    }
+    Ok(())
+}
+
+fn is_identifier(id: &str) -> bool {
+    let mut chars = id.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parses text of a message or returns None if it contains an invalid or
+/// unsupported placeable.
+fn parse_value(value: &str) -> Option<Vec<Element>> {
+    let mut elements = Vec::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            elements.push(Element::Text(rest[..start].to_owned()));
+        }
+        let end = start + rest[start..].find('}')?;
+        let name = rest[start + 1..end].trim().strip_prefix('$')?;
+        if !is_identifier(name) {
+            return None;
+        }
+        elements.push(Element::Variable(name.to_owned()));
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+        return None;
+    }
+    if !rest.is_empty() {
+        elements.push(Element::Text(rest.to_owned()));
+    }
+
+    Some(elements)
+}
+
+enum Element {
+    Text(String),
+    Variable(String),
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("invalid syntax on line {0}")]
+    Syntax(usize),
+    #[error("invalid message identifier on line {0}")]
+    Identifier(usize),
+    #[error("message ending before line {0} is empty")]
+    Empty(usize),
+    #[error("invalid or unsupported placeable in message ending before line {0}")]
+    Placeable(usize),
+    #[error("duplicate message ending before line {0}")]
+    Duplicate(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let bundle = Bundle::parse(
+            "# A comment.\n\
+             hello = Hello, { $name }!\n\
+             \n\
+             multi =\n    First line\n    second { $n } line\n\
+             plain = Plain text\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            bundle.format("hello", &[("name", &"World")]).unwrap(),
+            "Hello, World!"
+        );
+        assert_eq!(bundle.format("hello", &[]).unwrap(), "Hello, {$name}!");
+        assert_eq!(
+            bundle.format("multi", &[("n", &2)]).unwrap(),
+            "First line\nsecond 2 line"
+        );
+        assert_eq!(bundle.format("plain", &[]).unwrap(), "Plain text");
+        assert!(bundle.format("missing", &[]).is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Bundle::parse("hello world").err(),
+            Some(ParseError::Syntax(1))
+        );
+        assert_eq!(
+            Bundle::parse("-hello = world").err(),
+            Some(ParseError::Identifier(1))
+        );
+        assert_eq!(
+            Bundle::parse("a = b\nhello =\n").err(),
+            Some(ParseError::Empty(2))
+        );
+        assert_eq!(
+            Bundle::parse("hello = { name }").err(),
+            Some(ParseError::Placeable(1))
+        );
+        assert_eq!(
+            Bundle::parse("a = b\na = c").err(),
+            Some(ParseError::Duplicate(2))
+        );
+    }
+}
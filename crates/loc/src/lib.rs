@@ -0,0 +1,21 @@
+//! This crate implements localization of all user facing texts.
+//!
+//! Texts are stored in [Fluent](https://projectfluent.org/) resources, one
+//! per language, and they are resolved through [`Localize`] resource.
+
+use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+pub use bundle::ParseError;
+pub use localize::Localize;
+use localize::LocalizePlugin;
+
+mod bundle;
+mod localize;
+mod names;
+
+pub struct LocPluginGroup;
+
+impl PluginGroup for LocPluginGroup {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>().add(LocalizePlugin)
+    }
+}
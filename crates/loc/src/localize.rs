@@ -0,0 +1,125 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use de_conf::{ConfigLoadFailedEvent, Configuration, Language};
+use de_core::state::AppState;
+use de_gui::ToastEvent;
+use enum_map::{enum_map, EnumMap};
+
+use crate::bundle::Bundle;
+
+/// Language used whenever a message is missing in the selected language.
+const FALLBACK: Language = Language::English;
+
+pub(crate) struct LocalizePlugin;
+
+impl Plugin for LocalizePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Localize::new(FALLBACK))
+            .add_system(setup.in_schedule(OnExit(AppState::AppLoading)))
+            .add_system(conf_failed.run_if(on_event::<ConfigLoadFailedEvent>()));
+    }
+}
+
+/// Resolves user facing texts in the currently selected language.
+///
+/// The resource is available from the start of the application. The language
+/// is switched to the configured one once the configuration is loaded and it
+/// can be changed at any time afterwards.
+#[derive(Resource)]
+pub struct Localize {
+    language: Language,
+    bundles: EnumMap<Language, Bundle>,
+}
+
+impl Localize {
+    pub(crate) fn new(language: Language) -> Self {
+        Self {
+            language,
+            bundles: enum_map! { language => load(language) },
+        }
+    }
+
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Changes the language of newly resolved texts. Already displayed texts
+    /// are not changed.
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+    }
+
+    /// Returns a message without any arguments. See [`Self::format`].
+    pub fn get(&self, id: &str) -> String {
+        self.format(id, &[])
+    }
+
+    /// Returns a message with its variables substituted from `args`.
+    ///
+    /// The message is looked up in the selected language first and in
+    /// English second. The message identifier itself is returned if the
+    /// message does not exist in either of these.
+    pub fn format(&self, id: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        self.bundles[self.language]
+            .format(id, args)
+            .or_else(|| self.bundles[FALLBACK].format(id, args))
+            .unwrap_or_else(|| id.to_owned())
+    }
+}
+
+fn source(language: Language) -> &'static str {
+    match language {
+        Language::English => include_str!("../lang/en.ftl"),
+        Language::Czech => include_str!("../lang/cs.ftl"),
+    }
+}
+
+fn load(language: Language) -> Bundle {
+    Bundle::parse(source(language))
+        .unwrap_or_else(|error| panic!("Invalid translations of {language:?}: {error}"))
+}
+
+fn setup(conf: Res<Configuration>, mut localize: ResMut<Localize>) {
+    localize.set_language(conf.localization().language());
+}
+
+fn conf_failed(
+    mut events: EventReader<ConfigLoadFailedEvent>,
+    localize: Res<Localize>,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    events.clear();
+    toasts.send(ToastEvent::new(localize.get("toast-conf-loading-failed")));
+}
+
+#[cfg(test)]
+mod tests {
+    use enum_map::Enum;
+
+    use super::*;
+
+    #[test]
+    fn test_translations() {
+        let fallback = load(FALLBACK);
+        for index in 0..Language::LENGTH {
+            let language = Language::from_usize(index);
+            let bundle = load(language);
+            for id in bundle.ids() {
+                assert!(
+                    fallback.format(id, &[]).is_some(),
+                    "{language:?} has extra message {id}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallback() {
+        let mut localize = Localize::new(Language::Czech);
+        assert_eq!(localize.get("settings-on"), "Zapnuto");
+        assert_eq!(localize.get("unknown-message"), "unknown-message");
+        localize.set_language(Language::English);
+        assert_eq!(localize.get("settings-on"), "On");
+    }
+}
@@ -0,0 +1,118 @@
+//! This module implements names of game concepts (e.g. actions or objects)
+//! shown to the user.
+
+use de_conf::Action;
+use de_core::{
+    alert::AlertKind,
+    objects::{ActiveObjectType, BuildingType, InactiveObjectType, ObjectType, UnitType},
+};
+
+use crate::Localize;
+
+impl Localize {
+    pub fn action_name(&self, action: Action) -> String {
+        let id = match action {
+            Action::CameraLeft => "action-camera-left",
+            Action::CameraRight => "action-camera-right",
+            Action::CameraUp => "action-camera-up",
+            Action::CameraDown => "action-camera-down",
+            Action::CameraPivot => "action-camera-pivot",
+            Action::CameraFollow => "action-camera-follow",
+            Action::JumpToAlert => "action-jump-to-alert",
+            Action::Select => "action-select",
+            Action::Command => "action-command",
+            Action::AttackMove => "action-attack-move",
+            Action::Patrol => "action-patrol",
+            Action::Focus => "action-focus",
+            Action::PlaceBase => "action-place-base",
+            Action::PlacePowerHub => "action-place-power-hub",
+            Action::StanceAggressive => "action-stance-aggressive",
+            Action::StanceDefensive => "action-stance-defensive",
+            Action::StanceHoldFire => "action-stance-hold-fire",
+            Action::StanceHoldPosition => "action-stance-hold-position",
+            Action::FormationLine => "action-formation-line",
+            Action::FormationWedge => "action-formation-wedge",
+            Action::FormationBox => "action-formation-box",
+            Action::Menu => "action-menu",
+            Action::SelectAll => "action-select-all",
+            Action::SelectAllVisible => "action-select-visible",
+            Action::CycleSubgroup => "action-cycle-subgroup",
+            Action::Pause => "action-pause",
+            Action::Group0
+            | Action::Group1
+            | Action::Group2
+            | Action::Group3
+            | Action::Group4
+            | Action::Group5
+            | Action::Group6
+            | Action::Group7
+            | Action::Group8
+            | Action::Group9 => "action-group",
+            Action::Bookmark0 | Action::Bookmark1 | Action::Bookmark2 | Action::Bookmark3 => {
+                "action-bookmark"
+            }
+            Action::Card0
+            | Action::Card1
+            | Action::Card2
+            | Action::Card3
+            | Action::Card4
+            | Action::Card5
+            | Action::Card6
+            | Action::Card7 => "action-card",
+        };
+
+        // Control groups are numbered from 0 (as on the keyboard), bookmarks
+        // and card slots from 1.
+        let number = action
+            .group()
+            .or_else(|| action.bookmark().map(|bookmark| bookmark + 1))
+            .or_else(|| action.card_slot().map(|slot| slot + 1));
+        match number {
+            Some(number) => self.format(id, &[("number", &number)]),
+            None => self.get(id),
+        }
+    }
+
+    pub fn object_name(&self, object_type: ObjectType) -> String {
+        let id = match object_type {
+            ObjectType::Active(ActiveObjectType::Building(BuildingType::Base)) => "object-base",
+            ObjectType::Active(ActiveObjectType::Building(BuildingType::PowerHub)) => {
+                "object-power-hub"
+            }
+            ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)) => "object-attacker",
+            ObjectType::Inactive(InactiveObjectType::Tree) => "object-tree",
+        };
+        self.get(id)
+    }
+
+    pub fn alert(&self, kind: AlertKind) -> String {
+        match kind {
+            AlertKind::UnderAttack => self.get("alert-under-attack"),
+            AlertKind::BuildingComplete => self.get("alert-building-complete"),
+            AlertKind::PlayerLeft(player) => {
+                self.format("alert-player-left", &[("player", &player.to_num())])
+            }
+            AlertKind::LowPower => self.get("alert-low-power"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_conf::Language;
+    use enum_map::Enum;
+
+    use super::*;
+
+    #[test]
+    fn test_action_names() {
+        let localize = Localize::new(Language::English);
+        for index in 0..Action::LENGTH {
+            let action = Action::from_usize(index);
+            assert!(!localize.action_name(action).starts_with("action-"));
+        }
+        assert_eq!(localize.action_name(Action::Group0), "Group 0");
+        assert_eq!(localize.action_name(Action::Bookmark0), "Bookmark 1");
+        assert_eq!(localize.action_name(Action::Card7), "Command card 8");
+    }
+}
@@ -17,7 +17,7 @@ use crate::{
 /// Content of the map.
 ///
 /// This object is potentially large.
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MapContent {
     objects: Vec<Object>,
 }
@@ -47,6 +47,15 @@ impl MapContent {
         self.objects.push(object);
     }
 
+    /// Removes an object from the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub(crate) fn remove_object(&mut self, index: usize) -> Object {
+        self.objects.remove(index)
+    }
+
     pub(crate) fn validate(&self, metadata: &MapMetadata) -> Result<(), MapContentValidationError> {
         #[derive(Default)]
         struct Counter {
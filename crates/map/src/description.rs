@@ -0,0 +1,234 @@
+use de_core::player::Player;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::meta::MapMetadata;
+
+pub const MAX_AUTHOR_LEN: usize = 32;
+pub const MAX_DESCRIPTION_LEN: usize = 1024;
+/// Maximum size of a PNG encoded map thumbnail in bytes.
+pub const MAX_THUMBNAIL_SIZE: usize = 512 * 1024;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Human oriented information about a map. It has no effect on the game
+/// play. Maps stored in format version 1 have an empty description.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct MapDescription {
+    author: String,
+    description: String,
+    recommended_players: Option<Player>,
+}
+
+impl MapDescription {
+    /// Creates a new map description.
+    ///
+    /// # Arguments
+    ///
+    /// * `author` - name of the author of the map. It may be empty.
+    ///
+    /// * `description` - free form description of the map. It may be empty.
+    ///
+    /// * `recommended_players` - number of players the map is best played
+    ///   with. It must not be greater than the maximum number of players of
+    ///   the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if author or description is too long.
+    pub fn new(author: String, description: String, recommended_players: Option<Player>) -> Self {
+        let map_description = Self {
+            author,
+            description,
+            recommended_players,
+        };
+        map_description.validate_texts().unwrap();
+        map_description
+    }
+
+    pub fn author(&self) -> &str {
+        self.author.as_str()
+    }
+
+    pub fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    pub fn recommended_players(&self) -> Option<Player> {
+        self.recommended_players
+    }
+
+    pub(crate) fn validate(
+        &self,
+        metadata: &MapMetadata,
+    ) -> Result<(), MapDescriptionValidationError> {
+        self.validate_texts()?;
+
+        if let Some(players) = self.recommended_players {
+            if players < Player::Player2 || players > metadata.max_player() {
+                return Err(MapDescriptionValidationError::RecommendedPlayers {
+                    players,
+                    max_player: metadata.max_player(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_texts(&self) -> Result<(), MapDescriptionValidationError> {
+        if self.author.len() > MAX_AUTHOR_LEN {
+            return Err(MapDescriptionValidationError::Author(format!(
+                "author name too long: {} > {}",
+                self.author.len(),
+                MAX_AUTHOR_LEN
+            )));
+        }
+        if self.description.len() > MAX_DESCRIPTION_LEN {
+            return Err(MapDescriptionValidationError::Description(format!(
+                "description too long: {} > {}",
+                self.description.len(),
+                MAX_DESCRIPTION_LEN
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MapDescriptionValidationError {
+    #[error("invalid map author: {0}")]
+    Author(String),
+    #[error("invalid map description: {0}")]
+    Description(String),
+    #[error("recommended number of players {players} is not between 2 and {max_player}")]
+    RecommendedPlayers { players: Player, max_player: Player },
+}
+
+/// PNG encoded preview image of a map.
+#[derive(Clone, PartialEq)]
+pub struct Thumbnail(Vec<u8>);
+
+impl Thumbnail {
+    /// # Panics
+    ///
+    /// Panics if the data is not PNG encoded or if it is too large.
+    pub fn new(png: Vec<u8>) -> Self {
+        let thumbnail = Self(png);
+        thumbnail.validate().unwrap();
+        thumbnail
+    }
+
+    pub(crate) fn from_bytes(png: Vec<u8>) -> Self {
+        Self(png)
+    }
+
+    /// Returns PNG encoded image.
+    pub fn png(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ThumbnailValidationError> {
+        if self.0.len() > MAX_THUMBNAIL_SIZE {
+            return Err(ThumbnailValidationError::TooLarge(self.0.len()));
+        }
+        if !self.0.starts_with(&PNG_SIGNATURE) {
+            return Err(ThumbnailValidationError::NotPng);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ThumbnailValidationError {
+    #[error("thumbnail too large: {0} > {MAX_THUMBNAIL_SIZE} bytes")]
+    TooLarge(usize),
+    #[error("thumbnail is not a PNG image")]
+    NotPng,
+}
+
+/// Information needed to present a map to the players, for example during
+/// map selection. See [`crate::io::load_preview`].
+pub struct MapPreview {
+    metadata: MapMetadata,
+    description: MapDescription,
+    thumbnail: Option<Thumbnail>,
+}
+
+impl MapPreview {
+    pub(crate) fn new(
+        metadata: MapMetadata,
+        description: MapDescription,
+        thumbnail: Option<Thumbnail>,
+    ) -> Self {
+        Self {
+            metadata,
+            description,
+            thumbnail,
+        }
+    }
+
+    pub fn metadata(&self) -> &MapMetadata {
+        &self.metadata
+    }
+
+    pub fn description(&self) -> &MapDescription {
+        &self.description
+    }
+
+    pub fn thumbnail(&self) -> Option<&Thumbnail> {
+        self.thumbnail.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+    use crate::size::MapBounds;
+
+    #[test]
+    fn test_validate() {
+        let metadata = MapMetadata::new(
+            "Test".into(),
+            MapBounds::new(Vec2::new(1000., 1000.)),
+            Player::Player3,
+        );
+
+        assert!(MapDescription::default().validate(&metadata).is_ok());
+        assert!(
+            MapDescription::new("Me".into(), "A map.".into(), Some(Player::Player3))
+                .validate(&metadata)
+                .is_ok()
+        );
+        assert!(matches!(
+            MapDescription::new(String::new(), String::new(), Some(Player::Player4))
+                .validate(&metadata),
+            Err(MapDescriptionValidationError::RecommendedPlayers { .. })
+        ));
+        assert!(matches!(
+            MapDescription::new(String::new(), String::new(), Some(Player::Player1))
+                .validate(&metadata),
+            Err(MapDescriptionValidationError::RecommendedPlayers { .. })
+        ));
+
+        let author = MapDescription {
+            author: "a".repeat(MAX_AUTHOR_LEN + 1),
+            description: String::new(),
+            recommended_players: None,
+        };
+        assert!(matches!(
+            author.validate(&metadata),
+            Err(MapDescriptionValidationError::Author(_))
+        ));
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&[0; 16]);
+        assert!(Thumbnail::from_bytes(png).validate().is_ok());
+        assert!(matches!(
+            Thumbnail::from_bytes(vec![0; 32]).validate(),
+            Err(ThumbnailValidationError::NotPng)
+        ));
+    }
+}
@@ -0,0 +1,438 @@
+//! Procedural generation of random skirmish maps.
+//!
+//! Maps are generated from a seed so that all players of a multiplayer game
+//! generate an identical map from the same seed. The generation uses only
+//! basic floating point arithmetic (no trigonometric or other functions whose
+//! results differ between platforms).
+//!
+//! Generated maps are rotationally symmetric around the map center: 2 player
+//! maps are symmetric under rotation by 180°, larger maps under rotation by
+//! 90°. Each player starts with a base in a corner of the map.
+
+use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, TAU};
+
+use de_core::{
+    objects::{ActiveObjectType, BuildingType, InactiveObjectType},
+    player::Player,
+};
+use fastrand::Rng;
+use glam::Vec2;
+
+use crate::{
+    content::{ActiveObject, InactiveObject, InnerObject, Object},
+    description::MapDescription,
+    heightmap::{Heightmap, MAX_TERRAIN_HEIGHT},
+    map::Map,
+    meta::MapMetadata,
+    size::MapBounds,
+    zones::{TerrainZone, ZoneKind, ZONE_BOUNDARY_MARGIN},
+};
+
+const MAP_NAME: &str = "Random map";
+/// Smallest and largest generated map size in multiples of [`SIZE_STEP`].
+const MIN_SIZE_STEPS: u32 = 8;
+const MAX_SIZE_STEPS: u32 = 16;
+const SIZE_STEP: f32 = 100.;
+/// Distance of the bases from the map boundaries along both axes.
+const SPAWN_INSET: f32 = 150.;
+/// No obstacles or hills are generated closer than this to a base.
+const SPAWN_CLEARANCE: f32 = 100.;
+const MIN_ZONE_RADIUS: f32 = 16.;
+const MAX_ZONE_RADIUS: f32 = 48.;
+/// Minimum gap between two terrain zones so that units can pass between
+/// them.
+const ZONE_GAP: f32 = 48.;
+const GROVE_RADIUS: f32 = 30.;
+/// Minimum distance between two trees.
+const TREE_SPACING: f32 = 3.;
+/// Minimum distance between a tree and a terrain zone.
+const TREE_ZONE_GAP: f32 = 4.;
+const MIN_HILL_RADIUS: f32 = 80.;
+const MAX_HILL_RADIUS: f32 = 240.;
+const MAX_HILL_HEIGHT: f32 = 12.;
+/// Number of heightmap samples along each side of the map. It is odd so that
+/// the sampling grid is symmetric around the map center.
+const HEIGHTMAP_RESOLUTION: usize = 65;
+/// Maximum number of attempts to place a single feature.
+const MAX_ATTEMPTS: usize = 32;
+
+/// Regular octagon with unit circumradius, vertices in counter-clockwise
+/// order.
+const OCTAGON: [Vec2; 8] = [
+    Vec2::new(1., 0.),
+    Vec2::new(FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    Vec2::new(0., 1.),
+    Vec2::new(-FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+    Vec2::new(-1., 0.),
+    Vec2::new(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    Vec2::new(0., -1.),
+    Vec2::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+/// Generates a random map. The same seed and maximum number of players
+/// always produce the same map (with the same hash).
+///
+/// # Panics
+///
+/// Panics if `max_player` is smaller than [`Player::Player2`].
+pub fn generate(seed: u64, max_player: Player) -> Map {
+    assert!(max_player >= Player::Player2);
+
+    let rng = Rng::with_seed(seed);
+    let symmetry = if max_player == Player::Player2 { 2 } else { 4 };
+    let size = SIZE_STEP * rng.u32(MIN_SIZE_STEPS..=MAX_SIZE_STEPS) as f32;
+    let bounds = MapBounds::new(Vec2::splat(size));
+
+    let mut generator = Generator {
+        rng,
+        bounds,
+        symmetry,
+        spawns: Vec::new(),
+        zones: Vec::new(),
+        trees: Vec::new(),
+        hills: Vec::new(),
+    };
+    generator.generate_spawns(max_player);
+    generator.generate_zones();
+    generator.generate_trees();
+    generator.generate_hills();
+
+    let mut map = Map::empty(MapMetadata::new(MAP_NAME.to_owned(), bounds, max_player));
+    generator.fill(&mut map);
+    map.set_description(MapDescription::new(
+        String::new(),
+        format!("Randomly generated from seed {seed}."),
+        Some(max_player),
+    ));
+    map
+}
+
+struct Generator {
+    rng: Rng,
+    bounds: MapBounds,
+    /// Order of the rotational symmetry of the map.
+    symmetry: usize,
+    spawns: Vec<(Player, Vec2, f32)>,
+    zones: Vec<(ZoneKind, Circle)>,
+    trees: Vec<(Vec2, f32)>,
+    hills: Vec<(Circle, f32)>,
+}
+
+#[derive(Clone, Copy)]
+struct Circle {
+    center: Vec2,
+    radius: f32,
+}
+
+impl Circle {
+    /// Returns true if the gap between the two circles is smaller than
+    /// `gap`.
+    fn near(&self, other: &Self, gap: f32) -> bool {
+        let distance = self.radius + other.radius + gap;
+        self.center.distance_squared(other.center) < distance * distance
+    }
+}
+
+impl Generator {
+    fn generate_spawns(&mut self, max_player: Player) {
+        let corner = self.bounds.min() + Vec2::splat(SPAWN_INSET);
+        for num in 1..=max_player.to_num() {
+            let player = Player::try_from(num).unwrap();
+            let rotation = usize::from(num - 1) * (4 / self.symmetry);
+            self.spawns
+                .push((player, rotate(corner, rotation), heading(0., rotation)));
+        }
+    }
+
+    fn generate_zones(&mut self) {
+        let count = self.rng.usize(0..=6 / (self.symmetry / 2));
+        for _ in 0..count {
+            let kind = match self.rng.u8(0..4) {
+                0 => ZoneKind::Crater,
+                1 => ZoneKind::Cliff,
+                _ => ZoneKind::Water,
+            };
+            let radius = self.random_between(MIN_ZONE_RADIUS, MAX_ZONE_RADIUS);
+            let Some(copies) = self.place(radius + ZONE_BOUNDARY_MARGIN, |generator, circle| {
+                let circle = Circle {
+                    center: circle.center,
+                    radius,
+                };
+                generator.clear_of_spawns(&circle, SPAWN_CLEARANCE)
+                    && generator
+                        .zones
+                        .iter()
+                        .all(|(_, zone)| !zone.near(&circle, ZONE_GAP))
+            }) else {
+                continue;
+            };
+
+            for center in copies {
+                self.zones.push((kind, Circle { center, radius }));
+            }
+        }
+    }
+
+    fn generate_trees(&mut self) {
+        let groves = self.rng.usize(2..=5);
+        for _ in 0..groves {
+            let Some(centers) = self.place(GROVE_RADIUS, |generator, circle| {
+                generator.clear_of_spawns(circle, SPAWN_CLEARANCE)
+            }) else {
+                continue;
+            };
+            let center = centers[0];
+
+            let count = self.rng.usize(4..=12);
+            for _ in 0..count {
+                let offset = Vec2::new(self.rng.f32() - 0.5, self.rng.f32() - 0.5);
+                let position = center + 2. * GROVE_RADIUS * offset;
+                let tree = Circle {
+                    center: position,
+                    radius: 0.,
+                };
+
+                let copies = self.copies(position);
+                let valid = copies.iter().all(|&copy| {
+                    let tree = Circle {
+                        center: copy,
+                        radius: 0.,
+                    };
+                    self.within(&tree, 1.)
+                        && self.clear_of_spawns(&tree, SPAWN_CLEARANCE)
+                        && self
+                            .zones
+                            .iter()
+                            .all(|(_, zone)| !zone.near(&tree, TREE_ZONE_GAP))
+                        && self.trees.iter().all(|&(other, _)| {
+                            other.distance_squared(copy) >= TREE_SPACING * TREE_SPACING
+                        })
+                }) && copies_apart(&copies, &tree, TREE_SPACING);
+                if !valid {
+                    continue;
+                }
+
+                let angle = TAU * self.rng.f32();
+                for (rotation, copy) in copies.into_iter().enumerate() {
+                    let rotation = rotation * (4 / self.symmetry);
+                    self.trees.push((copy, heading(angle, rotation)));
+                }
+            }
+        }
+    }
+
+    fn generate_hills(&mut self) {
+        let count = self.rng.usize(2..=5);
+        for _ in 0..count {
+            let radius = self.random_between(MIN_HILL_RADIUS, MAX_HILL_RADIUS);
+            let height = self.random_between(0.2 * MAX_HILL_HEIGHT, MAX_HILL_HEIGHT);
+            // Hills may extend beyond the map boundaries.
+            let Some(copies) = self.place(0., |generator, circle| {
+                let circle = Circle {
+                    center: circle.center,
+                    radius,
+                };
+                generator.clear_of_spawns(&circle, SPAWN_CLEARANCE)
+            }) else {
+                continue;
+            };
+
+            for center in copies {
+                self.hills.push((Circle { center, radius }, height));
+            }
+        }
+    }
+
+    /// Finds a random position for a circular feature such that the feature
+    /// and all its symmetric copies fulfill `valid`, are within the map
+    /// bounds and do not overlap with each other.
+    ///
+    /// It returns positions of all copies or None if no such position was
+    /// found.
+    fn place<F>(&self, radius: f32, valid: F) -> Option<Vec<Vec2>>
+    where
+        F: Fn(&Self, &Circle) -> bool,
+    {
+        for _ in 0..MAX_ATTEMPTS {
+            let position = self
+                .bounds
+                .rel_to_abs(Vec2::new(self.rng.f32(), self.rng.f32()));
+            let circle = Circle {
+                center: position,
+                radius,
+            };
+
+            let copies = self.copies(position);
+            let accepted = copies.iter().all(|&center| {
+                let copy = Circle { center, radius };
+                self.within(&copy, 0.) && valid(self, &copy)
+            }) && copies_apart(&copies, &circle, 0.);
+            if accepted {
+                return Some(copies);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the point and all its symmetric copies.
+    fn copies(&self, point: Vec2) -> Vec<Vec2> {
+        (0..self.symmetry)
+            .map(|i| rotate(point, i * (4 / self.symmetry)))
+            .collect()
+    }
+
+    /// Returns true if the whole circle is inside the map bounds, at least
+    /// `margin` meters from the boundaries.
+    fn within(&self, circle: &Circle, margin: f32) -> bool {
+        let extent = Vec2::splat(circle.radius + margin);
+        (circle.center - extent).cmpge(self.bounds.min()).all()
+            && (circle.center + extent).cmple(self.bounds.max()).all()
+    }
+
+    fn clear_of_spawns(&self, circle: &Circle, clearance: f32) -> bool {
+        self.spawns
+            .iter()
+            .all(|&(_, center, _)| !circle.near(&Circle { center, radius: 0. }, clearance))
+    }
+
+    fn random_between(&self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.rng.f32()
+    }
+
+    fn heightmap(&self) -> Heightmap {
+        let last = (HEIGHTMAP_RESOLUTION - 1) as f32;
+        let mut heights = Vec::with_capacity(HEIGHTMAP_RESOLUTION * HEIGHTMAP_RESOLUTION);
+        for row in 0..HEIGHTMAP_RESOLUTION {
+            for column in 0..HEIGHTMAP_RESOLUTION {
+                let point = self
+                    .bounds
+                    .rel_to_abs(Vec2::new(column as f32 / last, row as f32 / last));
+                let height: f32 = self
+                    .hills
+                    .iter()
+                    .map(|&(hill, height)| {
+                        let fraction =
+                            point.distance_squared(hill.center) / (hill.radius * hill.radius);
+                        if fraction < 1. {
+                            let falloff = 1. - fraction;
+                            height * falloff * falloff
+                        } else {
+                            0.
+                        }
+                    })
+                    .sum();
+                heights.push(height.min(MAX_TERRAIN_HEIGHT));
+            }
+        }
+
+        Heightmap::new(HEIGHTMAP_RESOLUTION, HEIGHTMAP_RESOLUTION, heights)
+    }
+
+    fn fill(&self, map: &mut Map) {
+        for &(kind, zone) in &self.zones {
+            map.insert_zone(TerrainZone::new(
+                kind,
+                OCTAGON
+                    .iter()
+                    .map(|&vertex| zone.center + zone.radius * vertex)
+                    .collect(),
+            ));
+        }
+
+        for &(player, position, heading) in &self.spawns {
+            let object = Object::new(
+                map.new_placement(position, heading),
+                InnerObject::Active(ActiveObject::new(
+                    ActiveObjectType::Building(BuildingType::Base),
+                    player,
+                )),
+            );
+            map.insert_object(object);
+        }
+
+        for &(position, heading) in &self.trees {
+            let object = Object::new(
+                map.new_placement(position, heading),
+                InnerObject::Inactive(InactiveObject::new(InactiveObjectType::Tree)),
+            );
+            map.insert_object(object);
+        }
+
+        if !self.hills.is_empty() {
+            map.set_heightmap(self.heightmap());
+        }
+    }
+}
+
+/// Returns true if no two copies of a circle are closer than `gap`.
+fn copies_apart(copies: &[Vec2], circle: &Circle, gap: f32) -> bool {
+    copies.iter().enumerate().all(|(i, &a)| {
+        copies[i + 1..].iter().all(|&b| {
+            !Circle {
+                center: a,
+                radius: circle.radius,
+            }
+            .near(
+                &Circle {
+                    center: b,
+                    radius: circle.radius,
+                },
+                gap,
+            )
+        })
+    })
+}
+
+/// Rotates a point around the map center by `quarters` × 90°
+/// counter-clockwise.
+fn rotate(point: Vec2, quarters: usize) -> Vec2 {
+    match quarters % 4 {
+        0 => point,
+        1 => Vec2::new(-point.y, point.x),
+        2 => -point,
+        _ => Vec2::new(point.y, -point.x),
+    }
+}
+
+/// Returns heading rotated by `quarters` × 90° counter-clockwise.
+fn heading(heading: f32, quarters: usize) -> f32 {
+    (heading + FRAC_PI_2 * quarters as f32) % TAU
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint;
+
+    #[test]
+    fn test_generate() {
+        for max_player in [Player::Player2, Player::Player3, Player::Player4] {
+            for seed in 0..32 {
+                let map = generate(seed, max_player);
+                map.validate().unwrap();
+                assert_eq!(lint::validate(&map), vec![], "seed {seed}, {max_player}");
+                assert_eq!(map.metadata().max_player(), max_player);
+            }
+        }
+
+        assert!(
+            generate(7, Player::Player4).compute_hash()
+                == generate(7, Player::Player4).compute_hash()
+        );
+        assert!(
+            generate(7, Player::Player4).compute_hash()
+                != generate(8, Player::Player4).compute_hash()
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        let point = Vec2::new(1., 2.);
+        assert_eq!(rotate(point, 0), point);
+        assert_eq!(rotate(point, 1), Vec2::new(-2., 1.));
+        assert_eq!(rotate(point, 2), Vec2::new(-1., -2.));
+        assert_eq!(rotate(point, 3), Vec2::new(2., -1.));
+        assert_eq!(rotate(point, 4), point);
+    }
+}
@@ -11,7 +11,7 @@ use thiserror::Error;
 
 use crate::io::MAP_FILE_SUFFIX;
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct MapHash([u8; 32]);
 
 impl MapHash {
@@ -19,6 +19,17 @@ impl MapHash {
         Self(hash)
     }
 
+    /// Constructs the map hash from its raw bytes, for example as received
+    /// over the network.
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self::new(bytes)
+    }
+
+    /// Returns raw bytes of the hash.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
     /// Constructs the map hash from a hexadecimal string.
     pub(crate) fn from_hex(hex: &str) -> Result<Self, HexError> {
         if hex.len() != 64 {
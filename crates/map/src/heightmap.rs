@@ -0,0 +1,184 @@
+use bevy::prelude::Resource;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{hash::MapHasher, size::MapBounds};
+
+/// Maximum terrain elevation above mean sea level in meters.
+pub const MAX_TERRAIN_HEIGHT: f32 = 50.;
+/// Maximum number of heightmap samples along a side of the map.
+pub const MAX_HEIGHTMAP_RESOLUTION: usize = 1025;
+
+/// Terrain elevation sampled on a regular grid spanning the whole map. Maps
+/// without a heightmap are flat.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Heightmap {
+    columns: usize,
+    rows: usize,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Creates a new heightmap.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - number of samples along x axis.
+    ///
+    /// * `rows` - number of samples along y axis.
+    ///
+    /// * `heights` - elevation of the samples in meters, row by row. The first
+    ///   sample corresponds to the south-west corner of the map and the last
+    ///   sample corresponds to the north-east corner of the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heightmap is invalid.
+    pub fn new(columns: usize, rows: usize, heights: Vec<f32>) -> Self {
+        let heightmap = Self {
+            columns,
+            rows,
+            heights,
+        };
+        heightmap.validate().unwrap();
+        heightmap
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Elevation of all samples, see [`Self::new`].
+    pub fn heights(&self) -> &[f32] {
+        self.heights.as_slice()
+    }
+
+    /// Returns bilinearly interpolated elevation of the terrain at a point.
+    /// Points out of the map are clamped to the map bounds.
+    pub fn height(&self, bounds: MapBounds, point: Vec2) -> f32 {
+        let cell = Vec2::new((self.columns - 1) as f32, (self.rows - 1) as f32);
+        let relative = ((point - bounds.min()) / bounds.size()).clamp(Vec2::ZERO, Vec2::ONE);
+        let position = relative * cell;
+
+        let column = (position.x.floor() as usize).min(self.columns - 2);
+        let row = (position.y.floor() as usize).min(self.rows - 2);
+        let fraction = position - Vec2::new(column as f32, row as f32);
+
+        let sample = |column: usize, row: usize| self.heights[row * self.columns + column];
+        let south = sample(column, row) * (1. - fraction.x) + sample(column + 1, row) * fraction.x;
+        let north =
+            sample(column, row + 1) * (1. - fraction.x) + sample(column + 1, row + 1) * fraction.x;
+        south * (1. - fraction.y) + north * fraction.y
+    }
+
+    pub(crate) fn update_hash(&self, hasher: &mut MapHasher) {
+        hasher.update_usize(self.columns);
+        hasher.update_usize(self.rows);
+        for &height in &self.heights {
+            hasher.update_f32(height);
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), HeightmapValidationError> {
+        for size in [self.columns, self.rows] {
+            if !(2..=MAX_HEIGHTMAP_RESOLUTION).contains(&size) {
+                return Err(HeightmapValidationError::Resolution(size));
+            }
+        }
+
+        let expected = self.columns * self.rows;
+        if self.heights.len() != expected {
+            return Err(HeightmapValidationError::SampleCount {
+                expected,
+                got: self.heights.len(),
+            });
+        }
+
+        for (index, &height) in self.heights.iter().enumerate() {
+            if !height.is_finite() || !(0. ..=MAX_TERRAIN_HEIGHT).contains(&height) {
+                return Err(HeightmapValidationError::Height { index, height });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum HeightmapValidationError {
+    #[error("heightmap resolution has to be between 2 and {MAX_HEIGHTMAP_RESOLUTION}, got {0}")]
+    Resolution(usize),
+    #[error("heightmap has to have {expected} samples, got {got}")]
+    SampleCount { expected: usize, got: usize },
+    #[error("heights[{index}] = {height} is not between 0 and {MAX_TERRAIN_HEIGHT}")]
+    Height { index: usize, height: f32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_height() {
+        let bounds = MapBounds::new(Vec2::new(100., 200.));
+        let heightmap = Heightmap::new(3, 2, vec![0., 2., 4., 10., 10., 10.]);
+
+        assert_eq!(heightmap.height(bounds, Vec2::new(-50., -100.)), 0.);
+        assert_eq!(heightmap.height(bounds, Vec2::new(50., -100.)), 4.);
+        assert_eq!(heightmap.height(bounds, Vec2::new(0., -100.)), 2.);
+        assert_eq!(heightmap.height(bounds, Vec2::new(-25., -100.)), 1.);
+        assert_eq!(heightmap.height(bounds, Vec2::new(-50., 0.)), 5.);
+        assert_eq!(heightmap.height(bounds, Vec2::new(50., 100.)), 10.);
+        assert_eq!(heightmap.height(bounds, Vec2::new(1000., 1000.)), 10.);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert!(Heightmap {
+            columns: 2,
+            rows: 2,
+            heights: vec![0.; 4],
+        }
+        .validate()
+        .is_ok());
+
+        assert_eq!(
+            Heightmap {
+                columns: 1,
+                rows: 2,
+                heights: vec![0.; 2],
+            }
+            .validate()
+            .unwrap_err()
+            .to_string(),
+            "heightmap resolution has to be between 2 and 1025, got 1"
+        );
+        assert_eq!(
+            Heightmap {
+                columns: 2,
+                rows: 2,
+                heights: vec![0.; 3],
+            }
+            .validate()
+            .unwrap_err()
+            .to_string(),
+            "heightmap has to have 4 samples, got 3"
+        );
+        assert_eq!(
+            Heightmap {
+                columns: 2,
+                rows: 2,
+                heights: vec![0., 1., -1., 0.],
+            }
+            .validate()
+            .unwrap_err()
+            .to_string(),
+            "heights[2] = -1 is not between 0 and 50"
+        );
+    }
+}
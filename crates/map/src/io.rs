@@ -7,10 +7,11 @@ use async_std::{
     stream::StreamExt,
 };
 use async_tar::{Archive, Builder, Entry, EntryType, Header};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
+    description::{MapDescription, MapPreview, Thumbnail},
     map::{Map, MapValidationError},
     meta::MapMetadata,
 };
@@ -35,8 +36,27 @@ macro_rules! storing_io_error {
 
 /// Maps are normally named with this suffix.
 pub const MAP_FILE_SUFFIX: &str = ".dem.tar";
+/// Version of the map file format written by [`store_map`]. Maps of all
+/// versions up to (and including) this one can be loaded.
+///
+/// Version 1 maps have no manifest, description nor thumbnail.
+pub const MAP_FORMAT_VERSION: u32 = 2;
+/// Entry with map format version and map checksum. It is present since map
+/// format version 2 and it is the first entry of the archive.
+const MANIFEST_JSON_ENTRY: &str = "manifest.json";
 const METADATA_JSON_ENTRY: &str = "metadata.json";
 const CONTENT_JSON_ENTRY: &str = "content.json";
+/// Optional entry with a scripted scenario of the map.
+const SCENARIO_JSON_ENTRY: &str = "scenario.json";
+/// Optional entry with terrain elevation of the map.
+const HEIGHTMAP_JSON_ENTRY: &str = "heightmap.json";
+/// Optional entry with water, cliffs and other special terrain of the map.
+const ZONES_JSON_ENTRY: &str = "zones.json";
+/// Optional entry with author, description and other information about the
+/// map.
+const DESCRIPTION_JSON_ENTRY: &str = "description.json";
+/// Optional entry with PNG encoded preview image of the map.
+const THUMBNAIL_PNG_ENTRY: &str = "thumbnail.png";
 
 type LoadingResult<T> = Result<T, MapLoadingError>;
 type StoringResult = Result<(), MapStoringError>;
@@ -74,6 +94,12 @@ pub async fn load_map<P: AsRef<Path>>(path: P) -> LoadingResult<Map> {
 
     let mut map_meta = None;
     let mut map_content = None;
+    let mut map_scenario = None;
+    let mut map_heightmap = None;
+    let mut map_zones = None;
+    let mut map_description = None;
+    let mut map_thumbnail = None;
+    let mut manifest = None;
 
     while let Some(entry) = entries.next().await {
         let mut entry = loading_io_error!(entry);
@@ -84,36 +110,138 @@ pub async fn load_map<P: AsRef<Path>>(path: P) -> LoadingResult<Map> {
             )));
         };
 
-        if path == METADATA_JSON_ENTRY {
+        if path == MANIFEST_JSON_ENTRY {
+            manifest = Some(deserialize_manifest(&mut entry).await?);
+        } else if path == METADATA_JSON_ENTRY {
             map_meta = deserialize_entry(&mut entry).await?;
         } else if path == CONTENT_JSON_ENTRY {
             map_content = deserialize_entry(&mut entry).await?;
+        } else if path == SCENARIO_JSON_ENTRY {
+            map_scenario = Some(deserialize_entry(&mut entry).await?);
+        } else if path == HEIGHTMAP_JSON_ENTRY {
+            map_heightmap = Some(deserialize_entry(&mut entry).await?);
+        } else if path == ZONES_JSON_ENTRY {
+            map_zones = Some(deserialize_entry(&mut entry).await?);
+        } else if path == DESCRIPTION_JSON_ENTRY {
+            map_description = Some(deserialize_entry(&mut entry).await?);
+        } else if path == THUMBNAIL_PNG_ENTRY {
+            map_thumbnail = Some(Thumbnail::from_bytes(read_entry(&mut entry).await?));
         }
     }
 
     let map_meta = unwrap(METADATA_JSON_ENTRY, map_meta)?;
     let map_content = unwrap(CONTENT_JSON_ENTRY, map_content)?;
-    let map = Map::new(map_meta, map_content);
+    let map = Map::new(
+        map_meta,
+        map_content,
+        map_scenario,
+        map_heightmap,
+        map_zones.unwrap_or_default(),
+        map_description.unwrap_or_default(),
+        map_thumbnail,
+    );
 
     if let Err(error) = map.validate() {
         return Err(MapLoadingError::Validation { source: error });
     }
 
+    if let Some(manifest) = manifest {
+        let checksum = map.compute_hash().to_hex();
+        if manifest.checksum != checksum {
+            return Err(MapLoadingError::Checksum {
+                expected: manifest.checksum,
+                actual: checksum,
+            });
+        }
+    }
+
     Ok(map)
 }
 
+/// Load map metadata, description and thumbnail from a map file. Content of
+/// the map is not loaded.
+///
+/// Maps stored in format version 1 have empty description and no thumbnail.
+pub async fn load_preview<P: AsRef<Path>>(path: P) -> LoadingResult<MapPreview> {
+    let mut file = loading_io_error!(File::open(&path).await);
+    let archive = Archive::new(&mut file);
+    let mut entries = loading_io_error!(archive.entries());
+
+    let mut map_meta = None;
+    let mut map_description = None;
+    let mut map_thumbnail = None;
+
+    while let Some(entry) = entries.next().await {
+        let mut entry = loading_io_error!(entry);
+        let path = loading_io_error!(entry.path());
+        let Some(path) = path.to_str() else {
+            return Err(MapLoadingError::ArchiveContent(String::from(
+                "The map archive contains an entry with non-UTF-8 path.",
+            )));
+        };
+
+        if path == MANIFEST_JSON_ENTRY {
+            deserialize_manifest(&mut entry).await?;
+        } else if path == METADATA_JSON_ENTRY {
+            map_meta = Some(deserialize_entry(&mut entry).await?);
+        } else if path == DESCRIPTION_JSON_ENTRY {
+            map_description = Some(deserialize_entry(&mut entry).await?);
+        } else if path == THUMBNAIL_PNG_ENTRY {
+            map_thumbnail = Some(Thumbnail::from_bytes(read_entry(&mut entry).await?));
+        }
+    }
+
+    let map_meta: MapMetadata = unwrap(METADATA_JSON_ENTRY, map_meta)?;
+    let map_description: MapDescription = map_description.unwrap_or_default();
+
+    if let Err(error) = map_meta.validate() {
+        return Err(MapLoadingError::Validation {
+            source: MapValidationError::Metadata { source: error },
+        });
+    }
+    if let Err(error) = map_description.validate(&map_meta) {
+        return Err(MapLoadingError::Validation {
+            source: MapValidationError::Description { source: error },
+        });
+    }
+    if let Some(ref thumbnail) = map_thumbnail {
+        if let Err(error) = thumbnail.validate() {
+            return Err(MapLoadingError::Validation {
+                source: MapValidationError::Thumbnail { source: error },
+            });
+        }
+    }
+
+    Ok(MapPreview::new(map_meta, map_description, map_thumbnail))
+}
+
+/// Deserializes map manifest and checks that the map format version is
+/// supported.
+async fn deserialize_manifest(entry: &mut Entry<Archive<&mut File>>) -> LoadingResult<Manifest> {
+    let manifest: Manifest = deserialize_entry(entry).await?;
+    if manifest.version < 2 || manifest.version > MAP_FORMAT_VERSION {
+        return Err(MapLoadingError::UnsupportedVersion(manifest.version));
+    }
+    Ok(manifest)
+}
+
 async fn deserialize_entry<T: DeserializeOwned>(
     entry: &mut Entry<Archive<&mut File>>,
 ) -> LoadingResult<T> {
-    let entry_size = loading_io_error!(entry.header().entry_size());
-    let mut buf: Vec<u8> = Vec::with_capacity(entry_size.try_into().unwrap());
-    loading_io_error!(entry.read_to_end(&mut buf).await);
+    let buf = read_entry(entry).await?;
     match serde_json::from_slice(buf.as_slice()) {
         Ok(map_inner) => Ok(map_inner),
         Err(error) => Err(MapLoadingError::JsonParsing { source: error }),
     }
 }
 
+async fn read_entry(entry: &mut Entry<Archive<&mut File>>) -> LoadingResult<Vec<u8>> {
+    let entry_size = loading_io_error!(entry.header().entry_size());
+    let mut buf: Vec<u8> = Vec::with_capacity(entry_size.try_into().unwrap());
+    loading_io_error!(entry.read_to_end(&mut buf).await);
+    Ok(buf)
+}
+
 fn unwrap<T>(entry_name: &str, wrapped: Option<T>) -> LoadingResult<T> {
     match wrapped {
         Some(wrapped) => Ok(wrapped),
@@ -133,9 +261,22 @@ pub enum MapLoadingError {
     JsonParsing { source: serde_json::Error },
     #[error(transparent)]
     Validation { source: MapValidationError },
+    #[error("unsupported map format version {0}")]
+    UnsupportedVersion(u32),
+    #[error("map checksum mismatch: expected {expected}, got {actual}")]
+    Checksum { expected: String, actual: String },
 }
 
-/// Writes a map to a TAR file. Overwrites the file if it already exists.
+/// Map format version and checksum of the map, see [`MANIFEST_JSON_ENTRY`].
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Hexadecimal hash of the map, see [`Map::compute_hash`].
+    checksum: String,
+}
+
+/// Writes a map to a TAR file in the latest map format version, see
+/// [`MAP_FORMAT_VERSION`]. Overwrites the file if it already exists.
 pub async fn store_map<P: AsRef<Path>>(map: &Map, path: P) -> StoringResult {
     let file = storing_io_error!(
         OpenOptions::new()
@@ -148,8 +289,28 @@ pub async fn store_map<P: AsRef<Path>>(map: &Map, path: P) -> StoringResult {
 
     let mut archive = Builder::new(file);
 
+    let manifest = Manifest {
+        version: MAP_FORMAT_VERSION,
+        checksum: map.compute_hash().to_hex(),
+    };
+    serialize_entry(&mut archive, MANIFEST_JSON_ENTRY, &manifest).await?;
     serialize_entry(&mut archive, METADATA_JSON_ENTRY, map.metadata()).await?;
     serialize_entry(&mut archive, CONTENT_JSON_ENTRY, map.content()).await?;
+    if let Some(scenario) = map.scenario() {
+        serialize_entry(&mut archive, SCENARIO_JSON_ENTRY, scenario).await?;
+    }
+    if let Some(heightmap) = map.heightmap() {
+        serialize_entry(&mut archive, HEIGHTMAP_JSON_ENTRY, heightmap).await?;
+    }
+    if !map.zones().is_empty() {
+        serialize_entry(&mut archive, ZONES_JSON_ENTRY, map.zones()).await?;
+    }
+    if map.description() != &MapDescription::default() {
+        serialize_entry(&mut archive, DESCRIPTION_JSON_ENTRY, map.description()).await?;
+    }
+    if let Some(thumbnail) = map.thumbnail() {
+        append_entry(&mut archive, THUMBNAIL_PNG_ENTRY, thumbnail.png()).await?;
+    }
 
     Ok(())
 }
@@ -167,14 +328,20 @@ where
         Ok(data) => data,
         Err(error) => return Err(MapStoringError::JsonSerialization { source: error }),
     };
+    append_entry(archive, entry_name, data.as_slice()).await
+}
 
+async fn append_entry<W>(archive: &mut Builder<W>, entry_name: &str, data: &[u8]) -> StoringResult
+where
+    W: Write + Unpin + Send + Sync,
+{
     let mut header = Header::new_gnu();
     header.set_entry_type(EntryType::Regular);
     header.set_mode(0x400);
     header.set_size(data.len().try_into().unwrap());
     storing_io_error!(
         archive
-            .append_data(&mut header, Path::new(entry_name), data.as_slice())
+            .append_data(&mut header, Path::new(entry_name), data)
             .await
     );
 
@@ -205,11 +372,54 @@ mod test {
     use super::*;
     use crate::{
         content::{ActiveObject, InnerObject, Object},
+        description::{MapDescription, Thumbnail},
+        heightmap::Heightmap,
         map::Map,
         meta::MapMetadata,
+        scenario::{Action, Condition, Scenario, Trigger},
         size::MapBounds,
+        zones::{TerrainZone, ZoneKind},
     };
 
+    const PNG: [u8; 12] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 1, 2, 3, 4];
+
+    fn test_map() -> Map {
+        let bounds = MapBounds::new(Vec2::new(1000., 2000.));
+        let mut map = Map::empty(MapMetadata::new("Test Map".into(), bounds, Player::Player2));
+        for (position, player) in [
+            (Vec2::new(-400., -900.), Player::Player1),
+            (Vec2::new(400., 900.), Player::Player2),
+        ] {
+            map.insert_object(Object::new(
+                map.new_placement(position, 0.),
+                InnerObject::Active(ActiveObject::new(
+                    ActiveObjectType::Building(BuildingType::Base),
+                    player,
+                )),
+            ));
+        }
+        map
+    }
+
+    /// Stores a map archive with the given manifest. The manifest is omitted
+    /// (i.e. map format version 1 is used) if it is None.
+    async fn store_raw(map: &Map, manifest: Option<Manifest>, path: &Path) {
+        let file = File::create(path).await.unwrap();
+        let mut archive = async_tar::Builder::new(file);
+        if let Some(manifest) = manifest {
+            serialize_entry(&mut archive, MANIFEST_JSON_ENTRY, &manifest)
+                .await
+                .unwrap();
+        }
+        serialize_entry(&mut archive, METADATA_JSON_ENTRY, map.metadata())
+            .await
+            .unwrap();
+        serialize_entry(&mut archive, CONTENT_JSON_ENTRY, map.content())
+            .await
+            .unwrap();
+        archive.into_inner().await.unwrap();
+    }
+
     #[test]
     fn test_store_load() {
         let bounds = MapBounds::new(Vec2::new(1000., 2000.));
@@ -232,17 +442,93 @@ mod test {
             ));
         }
 
+        let scenario = Scenario::new(vec![Trigger::new(
+            Condition::Elapsed(10.),
+            vec![Action::ShowObjective("Destroy all enemy bases.".into())],
+        )]);
+        map.set_scenario(scenario.clone());
+        let heightmap = Heightmap::new(2, 3, vec![0., 1., 2., 3., 4., 5.]);
+        map.set_heightmap(heightmap.clone());
+        map.insert_zone(TerrainZone::new(
+            ZoneKind::Water,
+            vec![
+                Vec2::new(-100., -100.),
+                Vec2::new(100., -100.),
+                Vec2::new(0., 100.),
+            ],
+        ));
+
+        let description = MapDescription::new(
+            "Tester".into(),
+            "Four bases and a lake.".into(),
+            Some(Player::Player2),
+        );
+        map.set_description(description.clone());
+        map.set_thumbnail(Some(Thumbnail::new(PNG.to_vec())));
+
         let tmp_dir = Builder::new().prefix("de_map_").tempdir().unwrap();
         let mut tmp_dir_path = PathBuf::from(tmp_dir.path());
         tmp_dir_path.push("test-map.dem.tar");
 
         task::block_on(store_map(&map, tmp_dir_path.as_path())).unwrap();
         let loaded_map = task::block_on(load_map(tmp_dir_path.as_path())).unwrap();
+        let preview = task::block_on(load_preview(tmp_dir_path.as_path())).unwrap();
 
         assert_eq!(
             loaded_map.metadata().bounds().aabb(),
             Aabb::new(Point::new(-500., -1000.), Point::new(500., 1000.))
         );
+        assert_eq!(loaded_map.scenario(), Some(&scenario));
+        assert_eq!(loaded_map.heightmap(), Some(&heightmap));
+        assert_eq!(loaded_map.zones(), map.zones());
+        assert_eq!(loaded_map.description(), &description);
+        assert_eq!(loaded_map.thumbnail().unwrap().png(), &PNG);
+        assert_eq!(loaded_map.compute_hash(), map.compute_hash());
+
+        assert_eq!(preview.metadata().name(), "Test Map");
+        assert_eq!(preview.description(), &description);
+        assert_eq!(preview.thumbnail().unwrap().png(), &PNG);
+    }
+
+    #[test]
+    fn test_load_versions() {
+        let map = test_map();
+        let tmp_dir = Builder::new().prefix("de_map_").tempdir().unwrap();
+        let path = tmp_dir.path().join("test-map.dem.tar");
+        let path = Path::new(path.as_os_str());
+
+        task::block_on(store_raw(&map, None, path));
+        let loaded_map = task::block_on(load_map(path)).unwrap();
+        assert_eq!(loaded_map.compute_hash(), map.compute_hash());
+        assert_eq!(loaded_map.description(), &MapDescription::default());
+        assert!(loaded_map.thumbnail().is_none());
+        let preview = task::block_on(load_preview(path)).unwrap();
+        assert_eq!(preview.metadata().name(), "Test Map");
+        assert!(preview.thumbnail().is_none());
+
+        let manifest = Manifest {
+            version: MAP_FORMAT_VERSION + 1,
+            checksum: map.compute_hash().to_hex(),
+        };
+        task::block_on(store_raw(&map, Some(manifest), path));
+        assert!(matches!(
+            task::block_on(load_map(path)),
+            Err(MapLoadingError::UnsupportedVersion(3))
+        ));
+        assert!(matches!(
+            task::block_on(load_preview(path)),
+            Err(MapLoadingError::UnsupportedVersion(3))
+        ));
+
+        let manifest = Manifest {
+            version: MAP_FORMAT_VERSION,
+            checksum: "0".repeat(64),
+        };
+        task::block_on(store_raw(&map, Some(manifest), path));
+        assert!(matches!(
+            task::block_on(load_map(path)),
+            Err(MapLoadingError::Checksum { .. })
+        ));
     }
 
     #[test]
@@ -1,7 +1,13 @@
 pub mod content;
+pub mod description;
+pub mod generator;
 pub mod hash;
+pub mod heightmap;
 pub mod io;
+pub mod lint;
 pub mod map;
 pub mod meta;
 pub mod placement;
+pub mod scenario;
 pub mod size;
+pub mod zones;
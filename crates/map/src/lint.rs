@@ -0,0 +1,418 @@
+//! Detection of map problems which do not make the map invalid but which
+//! likely break the game play, for example a player without a base or a base
+//! enclosed by water.
+//!
+//! Unlike [`crate::map::Map::validate`], issues found here are meant to be
+//! reported as warnings to the map author or in the game log.
+
+use std::{collections::VecDeque, fmt};
+
+use ahash::AHashMap;
+use de_core::{
+    objects::{ActiveObjectType, BuildingType, InactiveObjectType, ObjectType, UnitType},
+    player::Player,
+};
+use glam::Vec2;
+
+use crate::{content::InnerObject, map::Map, size::MapBounds, zones::TerrainZones};
+
+/// Size (in meters) of the cells of the grid used to check reachability of
+/// spawn areas.
+const REACHABILITY_CELL_SIZE: f32 = 8.;
+/// Number of points on the footprint boundary of an object checked against
+/// impassable terrain.
+const FOOTPRINT_SAMPLES: usize = 8;
+
+/// A problem found on a map, see [`validate`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapIssue {
+    /// The object extends beyond the map boundaries.
+    OutOfBounds { object: usize },
+    /// The object is (partly) placed on water, cliffs or other impassable
+    /// terrain zone.
+    OffTerrain { object: usize, zone: usize },
+    /// Footprints of the two objects overlap.
+    Overlap { first: usize, second: usize },
+    /// The player has no base.
+    MissingBase { player: Player },
+    /// Ground units cannot travel from the base of `player` to the base of
+    /// `other`.
+    UnreachableSpawn { player: Player, other: Player },
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { object } => {
+                write!(f, "objects[{object}] extends beyond the map boundaries")
+            }
+            Self::OffTerrain { object, zone } => {
+                write!(f, "objects[{object}] is placed on impassable zones[{zone}]")
+            }
+            Self::Overlap { first, second } => {
+                write!(f, "objects[{first}] and objects[{second}] overlap")
+            }
+            Self::MissingBase { player } => write!(f, "{player} has no base"),
+            Self::UnreachableSpawn { player, other } => {
+                write!(f, "base of {player} is not reachable from base of {other}")
+            }
+        }
+    }
+}
+
+/// Checks the map for problems which are not severe enough to make it
+/// invalid. Issues are returned in a deterministic order.
+pub fn validate(map: &Map) -> Vec<MapIssue> {
+    let bounds = map.metadata().bounds();
+    let zones = map.zones();
+    let objects: Vec<(Vec2, Footprint)> = map
+        .content()
+        .objects()
+        .iter()
+        .map(|object| {
+            let object_type = match object.inner() {
+                InnerObject::Active(active) => ObjectType::Active(active.object_type()),
+                InnerObject::Inactive(inactive) => ObjectType::Inactive(inactive.object_type()),
+            };
+            (object.placement().position(), Footprint::of(object_type))
+        })
+        .collect();
+
+    let mut issues = Vec::new();
+
+    for (index, &(position, footprint)) in objects.iter().enumerate() {
+        if !footprint.within(bounds, position) {
+            issues.push(MapIssue::OutOfBounds { object: index });
+        }
+        if let Some(zone) = footprint.impassable_zone(zones, position) {
+            issues.push(MapIssue::OffTerrain {
+                object: index,
+                zone,
+            });
+        }
+    }
+
+    issues.extend(overlaps(objects.as_slice()));
+
+    let mut spawns: AHashMap<Player, Vec2> = AHashMap::new();
+    for object in map.content().objects() {
+        if let InnerObject::Active(active) = object.inner() {
+            if active.object_type() == ActiveObjectType::Building(BuildingType::Base) {
+                spawns
+                    .entry(active.player())
+                    .or_insert_with(|| object.placement().position());
+            }
+        }
+    }
+
+    let players: Vec<(Player, Vec2)> = (1..=map.metadata().max_player().to_num())
+        .map(|num| Player::try_from(num).unwrap())
+        .filter_map(|player| match spawns.get(&player) {
+            Some(&position) => Some((player, position)),
+            None => {
+                issues.push(MapIssue::MissingBase { player });
+                None
+            }
+        })
+        .collect();
+
+    if !zones.is_empty() {
+        issues.extend(unreachable_spawns(bounds, zones, players.as_slice()));
+    }
+
+    issues
+}
+
+/// Approximate circular footprint of an object. The circle is inscribed in
+/// the actual footprint, therefore all issues found with it are real.
+#[derive(Clone, Copy)]
+struct Footprint {
+    inner: f32,
+}
+
+impl Footprint {
+    /// Returns approximate footprint of an object. The values are derived
+    /// from object footprints defined in `assets/objects`.
+    fn of(object_type: ObjectType) -> Self {
+        let inner = match object_type {
+            ObjectType::Active(ActiveObjectType::Building(BuildingType::Base)) => 18.56,
+            ObjectType::Active(ActiveObjectType::Building(BuildingType::PowerHub)) => 0.64,
+            ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)) => 0.85,
+            ObjectType::Inactive(InactiveObjectType::Tree) => 0.75,
+        };
+        Self { inner }
+    }
+
+    /// Returns true if the whole footprint is within the map bounds.
+    fn within(&self, bounds: MapBounds, position: Vec2) -> bool {
+        let inner = Vec2::splat(self.inner);
+        (position - inner).cmpge(bounds.min()).all() && (position + inner).cmple(bounds.max()).all()
+    }
+
+    /// Returns the first impassable zone overlapping with the footprint.
+    fn impassable_zone(&self, zones: &TerrainZones, position: Vec2) -> Option<usize> {
+        if let Some(zone) = zones.impassable_at(position) {
+            return Some(zone);
+        }
+
+        (0..FOOTPRINT_SAMPLES).find_map(|i| {
+            let angle = std::f32::consts::TAU * (i as f32) / (FOOTPRINT_SAMPLES as f32);
+            zones.impassable_at(position + self.inner * Vec2::from_angle(angle))
+        })
+    }
+}
+
+/// Returns issues for all pairs of objects whose footprints surely overlap.
+fn overlaps(objects: &[(Vec2, Footprint)]) -> Vec<MapIssue> {
+    let max_radius = objects
+        .iter()
+        .map(|(_, footprint)| footprint.inner)
+        .fold(0_f32, f32::max);
+
+    // Sweep along the x axis so that only nearby objects are compared.
+    let mut order: Vec<usize> = (0..objects.len()).collect();
+    order.sort_by(|&a, &b| objects[a].0.x.total_cmp(&objects[b].0.x));
+
+    let mut pairs = Vec::new();
+    for (i, &first) in order.iter().enumerate() {
+        let (first_position, first_footprint) = objects[first];
+        for &second in &order[i + 1..] {
+            let (second_position, second_footprint) = objects[second];
+            if second_position.x - first_position.x > first_footprint.inner + max_radius {
+                break;
+            }
+
+            let min_distance = first_footprint.inner + second_footprint.inner;
+            if first_position.distance_squared(second_position) < min_distance * min_distance {
+                pairs.push((first.min(second), first.max(second)));
+            }
+        }
+    }
+
+    pairs.sort_unstable();
+    pairs
+        .into_iter()
+        .map(|(first, second)| MapIssue::Overlap { first, second })
+        .collect()
+}
+
+/// Returns issues for all players whose base cannot be reached from the base
+/// of the first player (in the order of `spawns`). Impassable terrain zones
+/// are considered as the only obstacles.
+fn unreachable_spawns(
+    bounds: MapBounds,
+    zones: &TerrainZones,
+    spawns: &[(Player, Vec2)],
+) -> Vec<MapIssue> {
+    let Some(&(first, first_position)) = spawns.first() else {
+        return Vec::new();
+    };
+
+    let grid = ReachabilityGrid::new(bounds, zones);
+    let reachable = grid.flood(first_position);
+
+    spawns[1..]
+        .iter()
+        .filter(|&&(_, position)| !reachable[grid.index(position)])
+        .map(|&(player, _)| MapIssue::UnreachableSpawn {
+            player,
+            other: first,
+        })
+        .collect()
+}
+
+/// Coarse grid of passable parts of the map.
+struct ReachabilityGrid {
+    bounds: MapBounds,
+    columns: usize,
+    rows: usize,
+    passable: Vec<bool>,
+}
+
+impl ReachabilityGrid {
+    fn new(bounds: MapBounds, zones: &TerrainZones) -> Self {
+        let size = bounds.size();
+        let columns = (size.x / REACHABILITY_CELL_SIZE).ceil().max(1.) as usize;
+        let rows = (size.y / REACHABILITY_CELL_SIZE).ceil().max(1.) as usize;
+
+        let mut passable = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let center = bounds.min()
+                    + REACHABILITY_CELL_SIZE * Vec2::new(column as f32 + 0.5, row as f32 + 0.5);
+                passable.push(zones.impassable_at(center).is_none());
+            }
+        }
+
+        Self {
+            bounds,
+            columns,
+            rows,
+            passable,
+        }
+    }
+
+    fn index(&self, point: Vec2) -> usize {
+        let cell = ((point - self.bounds.min()) / REACHABILITY_CELL_SIZE).floor();
+        let column = (cell.x.max(0.) as usize).min(self.columns - 1);
+        let row = (cell.y.max(0.) as usize).min(self.rows - 1);
+        row * self.columns + column
+    }
+
+    /// Returns a mask of all cells reachable from `start`. The start cell is
+    /// always reachable.
+    fn flood(&self, start: Vec2) -> Vec<bool> {
+        let mut reachable = vec![false; self.passable.len()];
+        let start = self.index(start);
+        reachable[start] = true;
+
+        let mut queue = VecDeque::from([start]);
+        while let Some(index) = queue.pop_front() {
+            let (row, column) = (index / self.columns, index % self.columns);
+
+            let mut neighbours = Vec::with_capacity(4);
+            if column > 0 {
+                neighbours.push(index - 1);
+            }
+            if column + 1 < self.columns {
+                neighbours.push(index + 1);
+            }
+            if row > 0 {
+                neighbours.push(index - self.columns);
+            }
+            if row + 1 < self.rows {
+                neighbours.push(index + self.columns);
+            }
+
+            for neighbour in neighbours {
+                if self.passable[neighbour] && !reachable[neighbour] {
+                    reachable[neighbour] = true;
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+
+        reachable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        content::{ActiveObject, InactiveObject, Object},
+        meta::MapMetadata,
+        zones::{TerrainZone, ZoneKind},
+    };
+
+    fn insert_active(map: &mut Map, object_type: ActiveObjectType, player: Player, position: Vec2) {
+        map.insert_object(Object::new(
+            map.new_placement(position, 0.),
+            InnerObject::Active(ActiveObject::new(object_type, player)),
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let base = ActiveObjectType::Building(BuildingType::Base);
+        let mut map = Map::empty(MapMetadata::new(
+            "Test".into(),
+            MapBounds::new(Vec2::new(400., 400.)),
+            Player::Player3,
+        ));
+
+        insert_active(&mut map, base, Player::Player1, Vec2::new(-150., -150.));
+        insert_active(&mut map, base, Player::Player2, Vec2::new(150., 150.));
+        assert_eq!(
+            validate(&map),
+            vec![MapIssue::MissingBase {
+                player: Player::Player3
+            }]
+        );
+
+        insert_active(&mut map, base, Player::Player3, Vec2::new(190., -150.));
+        map.insert_object(Object::new(
+            map.new_placement(Vec2::new(0., 0.), 0.),
+            InnerObject::Inactive(InactiveObject::new(InactiveObjectType::Tree)),
+        ));
+        map.insert_object(Object::new(
+            map.new_placement(Vec2::new(0.5, 0.5), 0.),
+            InnerObject::Inactive(InactiveObject::new(InactiveObjectType::Tree)),
+        ));
+        insert_active(
+            &mut map,
+            ActiveObjectType::Unit(UnitType::Attacker),
+            Player::Player1,
+            Vec2::new(-150., -135.),
+        );
+        assert_eq!(
+            validate(&map),
+            vec![
+                MapIssue::OutOfBounds { object: 2 },
+                MapIssue::Overlap {
+                    first: 0,
+                    second: 5
+                },
+                MapIssue::Overlap {
+                    first: 3,
+                    second: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reachability() {
+        let base = ActiveObjectType::Building(BuildingType::Base);
+        let mut map = Map::empty(MapMetadata::new(
+            "Test".into(),
+            MapBounds::new(Vec2::new(400., 400.)),
+            Player::Player3,
+        ));
+        insert_active(&mut map, base, Player::Player1, Vec2::new(-150., -150.));
+        insert_active(&mut map, base, Player::Player2, Vec2::new(120., 120.));
+        insert_active(&mut map, base, Player::Player3, Vec2::new(150., -150.));
+
+        // A lake in the middle of the map does not block the players.
+        map.insert_zone(TerrainZone::new(
+            ZoneKind::Water,
+            vec![
+                Vec2::new(-50., -150.),
+                Vec2::new(50., -150.),
+                Vec2::new(50., 150.),
+                Vec2::new(-50., 150.),
+            ],
+        ));
+        assert_eq!(validate(&map), vec![]);
+
+        map.insert_object(Object::new(
+            map.new_placement(Vec2::new(-50.5, 0.), 0.),
+            InnerObject::Inactive(InactiveObject::new(InactiveObjectType::Tree)),
+        ));
+        assert_eq!(
+            validate(&map),
+            vec![MapIssue::OffTerrain { object: 3, zone: 0 }]
+        );
+        map.remove_object(3);
+
+        // Cliffs enclosing the base of the second player.
+        for (min, max) in [
+            (Vec2::new(60., 60.), Vec2::new(180., 80.)),
+            (Vec2::new(60., 160.), Vec2::new(180., 180.)),
+            (Vec2::new(60., 60.), Vec2::new(80., 180.)),
+            (Vec2::new(160., 60.), Vec2::new(180., 180.)),
+        ] {
+            map.insert_zone(TerrainZone::new(
+                ZoneKind::Cliff,
+                vec![min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)],
+            ));
+        }
+        assert_eq!(
+            validate(&map),
+            vec![MapIssue::UnreachableSpawn {
+                player: Player::Player2,
+                other: Player::Player1
+            }]
+        );
+    }
+}
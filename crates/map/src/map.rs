@@ -2,25 +2,61 @@ use glam::Vec2;
 use thiserror::Error;
 
 use crate::{
-    content::{MapContent, MapContentValidationError, Object},
+    content::{InnerObject, MapContent, MapContentValidationError, Object},
+    description::{
+        MapDescription, MapDescriptionValidationError, Thumbnail, ThumbnailValidationError,
+    },
     hash::{MapHash, MapHasher},
+    heightmap::{Heightmap, HeightmapValidationError},
     meta::{MapMetadata, MapMetadataValidationError},
     placement::Placement,
+    scenario::{Scenario, ScenarioValidationError},
+    zones::{TerrainZone, TerrainZones, TerrainZonesValidationError},
 };
 
+#[derive(Clone)]
 pub struct Map {
     metadata: MapMetadata,
     content: MapContent,
+    scenario: Option<Scenario>,
+    heightmap: Option<Heightmap>,
+    zones: TerrainZones,
+    description: MapDescription,
+    thumbnail: Option<Thumbnail>,
 }
 
 impl Map {
     /// Creates a new empty map (i.e. with no objects place on it).
     pub fn empty(metadata: MapMetadata) -> Self {
-        Self::new(metadata, MapContent::empty())
+        Self::new(
+            metadata,
+            MapContent::empty(),
+            None,
+            None,
+            TerrainZones::default(),
+            MapDescription::default(),
+            None,
+        )
     }
 
-    pub(crate) fn new(metadata: MapMetadata, content: MapContent) -> Self {
-        Self { metadata, content }
+    pub(crate) fn new(
+        metadata: MapMetadata,
+        content: MapContent,
+        scenario: Option<Scenario>,
+        heightmap: Option<Heightmap>,
+        zones: TerrainZones,
+        description: MapDescription,
+        thumbnail: Option<Thumbnail>,
+    ) -> Self {
+        Self {
+            metadata,
+            content,
+            scenario,
+            heightmap,
+            zones,
+            description,
+            thumbnail,
+        }
     }
 
     /// Compute deterministic hash of the map.
@@ -28,6 +64,10 @@ impl Map {
         let mut hasher = MapHasher::new();
         self.metadata.update_hash(&mut hasher);
         self.content.update_hash(&mut hasher);
+        if let Some(ref heightmap) = self.heightmap {
+            heightmap.update_hash(&mut hasher);
+        }
+        self.zones.update_hash(&mut hasher);
         hasher.finalize()
     }
 
@@ -35,10 +75,107 @@ impl Map {
         &self.metadata
     }
 
+    /// Replaces metadata of the map, e.g. to resize the map or to change the
+    /// maximum number of players.
+    ///
+    /// The original metadata is kept and an error is returned if the map
+    /// would become invalid with the new metadata, for example when some
+    /// objects are out of the new map bounds.
+    pub fn set_metadata(&mut self, metadata: MapMetadata) -> Result<(), MapValidationError> {
+        let original = std::mem::replace(&mut self.metadata, metadata);
+        let result = self.validate();
+        if result.is_err() {
+            self.metadata = original;
+        }
+        result
+    }
+
     pub fn content(&self) -> &MapContent {
         &self.content
     }
 
+    /// Scripted scenario of the map. Plain skirmish maps have no scenario.
+    pub fn scenario(&self) -> Option<&Scenario> {
+        self.scenario.as_ref()
+    }
+
+    /// Attaches a scenario to the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the scenario is invalid.
+    pub fn set_scenario(&mut self, scenario: Scenario) {
+        scenario.validate(&self.metadata).unwrap();
+        self.scenario = Some(scenario);
+    }
+
+    /// Elevation of the terrain. Maps without a heightmap are flat.
+    pub fn heightmap(&self) -> Option<&Heightmap> {
+        self.heightmap.as_ref()
+    }
+
+    /// Sets elevation of the terrain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the heightmap is invalid.
+    pub fn set_heightmap(&mut self, heightmap: Heightmap) {
+        heightmap.validate().unwrap();
+        self.heightmap = Some(heightmap);
+    }
+
+    /// Water, cliffs and other special terrain types on the map.
+    pub fn zones(&self) -> &TerrainZones {
+        &self.zones
+    }
+
+    /// Inserts a terrain zone to the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the zone is too close to the map boundaries or if the map
+    /// already has the maximum number of zones.
+    pub fn insert_zone(&mut self, zone: TerrainZone) {
+        self.zones.insert(zone);
+        self.zones.validate(self.metadata.bounds()).unwrap();
+    }
+
+    /// Removes a terrain zone from the map and returns it. Indices of all
+    /// successive zones are decremented.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_zone(&mut self, index: usize) -> TerrainZone {
+        self.zones.remove(index)
+    }
+
+    /// Author, description and other information about the map which does
+    /// not affect the game play.
+    pub fn description(&self) -> &MapDescription {
+        &self.description
+    }
+
+    /// Replaces the description of the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the description is not valid with the map metadata, e.g.
+    /// the recommended number of players is too large.
+    pub fn set_description(&mut self, description: MapDescription) {
+        description.validate(&self.metadata).unwrap();
+        self.description = description;
+    }
+
+    /// Preview image of the map.
+    pub fn thumbnail(&self) -> Option<&Thumbnail> {
+        self.thumbnail.as_ref()
+    }
+
+    pub fn set_thumbnail(&mut self, thumbnail: Option<Thumbnail>) {
+        self.thumbnail = thumbnail;
+    }
+
     /// Insert an object to the map.
     ///
     /// # Panics
@@ -52,6 +189,16 @@ impl Map {
         self.content.insert_object(object);
     }
 
+    /// Removes an object from the map and returns it. Indices of all
+    /// successive objects are decremented.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_object(&mut self, index: usize) -> Object {
+        self.content.remove_object(index)
+    }
+
     /// Creates a new placement on the map.
     ///
     /// # Arguments
@@ -72,13 +219,49 @@ impl Map {
         placement
     }
 
-    pub(crate) fn validate(&self) -> Result<(), MapValidationError> {
+    /// Validates the whole map. Maps are validated before they are stored
+    /// and after they are loaded.
+    pub fn validate(&self) -> Result<(), MapValidationError> {
         if let Err(error) = self.metadata.validate() {
             return Err(MapValidationError::Metadata { source: error });
         }
         if let Err(error) = self.content.validate(&self.metadata) {
             return Err(MapValidationError::Content { source: error });
         }
+        if let Some(ref scenario) = self.scenario {
+            if let Err(error) = scenario.validate(&self.metadata) {
+                return Err(MapValidationError::Scenario { source: error });
+            }
+        }
+        if let Some(ref heightmap) = self.heightmap {
+            if let Err(error) = heightmap.validate() {
+                return Err(MapValidationError::Heightmap { source: error });
+            }
+        }
+        if let Err(error) = self.zones.validate(self.metadata.bounds()) {
+            return Err(MapValidationError::Zones { source: error });
+        }
+        if let Err(error) = self.description.validate(&self.metadata) {
+            return Err(MapValidationError::Description { source: error });
+        }
+        if let Some(ref thumbnail) = self.thumbnail {
+            if let Err(error) = thumbnail.validate() {
+                return Err(MapValidationError::Thumbnail { source: error });
+            }
+        }
+
+        for (index, object) in self.content.objects().iter().enumerate() {
+            if !matches!(object.inner(), InnerObject::Active(_)) {
+                continue;
+            }
+            if let Some(zone) = self.zones.impassable_at(object.placement().position()) {
+                return Err(MapValidationError::ImpassablePlacement {
+                    object: index,
+                    zone,
+                });
+            }
+        }
+
         Ok(())
     }
 }
@@ -89,6 +272,20 @@ pub enum MapValidationError {
     Metadata { source: MapMetadataValidationError },
     #[error("invalid map content")]
     Content { source: MapContentValidationError },
+    #[error("invalid map scenario")]
+    Scenario { source: ScenarioValidationError },
+    #[error("invalid map heightmap")]
+    Heightmap { source: HeightmapValidationError },
+    #[error("invalid map terrain zones")]
+    Zones { source: TerrainZonesValidationError },
+    #[error("invalid map description")]
+    Description {
+        source: MapDescriptionValidationError,
+    },
+    #[error("invalid map thumbnail")]
+    Thumbnail { source: ThumbnailValidationError },
+    #[error("objects[{object}] is placed on impassable zones[{zone}]")]
+    ImpassablePlacement { object: usize, zone: usize },
 }
 
 #[cfg(test)]
@@ -96,7 +293,7 @@ mod test {
     use std::error::Error;
 
     use de_core::{
-        objects::{ActiveObjectType, InactiveObjectType, UnitType},
+        objects::{ActiveObjectType, BuildingType, InactiveObjectType, UnitType},
         player::Player,
     };
     use glam::Vec2;
@@ -106,6 +303,7 @@ mod test {
         content::{ActiveObject, InactiveObject, InnerObject},
         placement::Placement,
         size::MapBounds,
+        zones::ZoneKind,
     };
 
     #[test]
@@ -157,6 +355,11 @@ mod test {
                 Player::Player4,
             ),
             content,
+            None,
+            None,
+            TerrainZones::default(),
+            MapDescription::default(),
+            None,
         );
 
         let result = map.validate();
@@ -180,6 +383,110 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_map_impassable_placement() {
+        let mut map = Map::empty(MapMetadata::new(
+            "Test Map".into(),
+            MapBounds::new(Vec2::new(1000., 1000.)),
+            Player::Player2,
+        ));
+        map.insert_zone(TerrainZone::new(
+            ZoneKind::Crater,
+            vec![
+                Vec2::new(0., 0.),
+                Vec2::new(50., 0.),
+                Vec2::new(50., 50.),
+                Vec2::new(0., 50.),
+            ],
+        ));
+        map.insert_object(Object::new(
+            map.new_placement(Vec2::new(20., 25.), 0.),
+            InnerObject::Active(ActiveObject::new(
+                ActiveObjectType::Unit(UnitType::Attacker),
+                Player::Player1,
+            )),
+        ));
+        map.validate().unwrap();
+
+        map.insert_zone(TerrainZone::new(
+            ZoneKind::Water,
+            vec![
+                Vec2::new(10., 10.),
+                Vec2::new(30., 10.),
+                Vec2::new(20., 40.),
+            ],
+        ));
+        assert_eq!(
+            map.validate().unwrap_err().to_string(),
+            "objects[0] is placed on impassable zones[1]"
+        );
+    }
+
+    #[test]
+    fn test_map_editing() {
+        let mut map = Map::empty(MapMetadata::new(
+            "Test Map".into(),
+            MapBounds::new(Vec2::new(1000., 1000.)),
+            Player::Player3,
+        ));
+        for (position, player) in [
+            (Vec2::new(-400., 0.), Player::Player1),
+            (Vec2::new(400., 0.), Player::Player3),
+        ] {
+            map.insert_object(Object::new(
+                map.new_placement(position, 0.),
+                InnerObject::Active(ActiveObject::new(
+                    ActiveObjectType::Building(BuildingType::Base),
+                    player,
+                )),
+            ));
+        }
+
+        assert!(map
+            .set_metadata(MapMetadata::new(
+                "Test Map".into(),
+                MapBounds::new(Vec2::new(500., 500.)),
+                Player::Player3,
+            ))
+            .is_err());
+        assert_eq!(
+            map.set_metadata(MapMetadata::new(
+                "Test Map".into(),
+                MapBounds::new(Vec2::new(1000., 1000.)),
+                Player::Player2,
+            ))
+            .unwrap_err()
+            .to_string(),
+            "invalid map content"
+        );
+        assert_eq!(map.metadata().max_player(), Player::Player3);
+
+        let removed = map.remove_object(1);
+        assert_eq!(removed.placement().position(), Vec2::new(400., 0.));
+        assert_eq!(map.content().objects().len(), 1);
+
+        map.set_metadata(MapMetadata::new(
+            "Smaller Map".into(),
+            MapBounds::new(Vec2::new(900., 800.)),
+            Player::Player2,
+        ))
+        .unwrap();
+        assert_eq!(map.metadata().name(), "Smaller Map");
+        assert_eq!(map.metadata().max_player(), Player::Player2);
+
+        map.insert_zone(TerrainZone::new(
+            ZoneKind::Water,
+            vec![
+                Vec2::new(10., 10.),
+                Vec2::new(30., 10.),
+                Vec2::new(20., 40.),
+            ],
+        ));
+        assert_eq!(map.remove_zone(0).kind(), ZoneKind::Water);
+        assert!(map.zones().is_empty());
+        map.validate().unwrap();
+    }
+
     #[test]
     fn test_map_hash() {
         let mut map = Map::empty(MapMetadata::new(
@@ -1,4 +1,4 @@
-use de_core::player::Player;
+use de_core::{daytime::HOURS_PER_DAY, player::Player};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -16,6 +16,8 @@ pub struct MapMetadata {
     name: String,
     bounds: MapBounds,
     max_player: Player,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_time: Option<f32>,
 }
 
 impl MapMetadata {
@@ -39,16 +41,34 @@ impl MapMetadata {
             name,
             bounds,
             max_player,
+            start_time: None,
         };
         map.validate().unwrap();
         map
     }
 
+    /// Returns the metadata with time of day (in hours) at which games on
+    /// the map start.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the time is not between 0 (inclusive) and
+    /// [`HOURS_PER_DAY`] (exclusive).
+    pub fn with_start_time(mut self, hour: f32) -> Self {
+        self.start_time = Some(hour);
+        self.validate().unwrap();
+        self
+    }
+
     pub(crate) fn update_hash(&self, hasher: &mut MapHasher) {
         hasher.update_str(&self.name);
         hasher.update_vec2(self.bounds.min());
         hasher.update_vec2(self.bounds.max());
         hasher.update_u8(self.max_player.to_num());
+        // Not hashed when not set so that hashes of older maps are kept.
+        if let Some(start_time) = self.start_time {
+            hasher.update_f32(start_time);
+        }
     }
 
     pub fn name(&self) -> &str {
@@ -63,6 +83,12 @@ impl MapMetadata {
         self.max_player
     }
 
+    /// Time of day (in hours) at which games start. The game default is used
+    /// when it is None.
+    pub fn start_time(&self) -> Option<f32> {
+        self.start_time
+    }
+
     pub(crate) fn validate(&self) -> Result<(), MapMetadataValidationError> {
         if self.name.is_empty() {
             return Err(MapMetadataValidationError::MapName(
@@ -85,6 +111,12 @@ impl MapMetadata {
             return Err(MapMetadataValidationError::MaxPlayers(self.max_player));
         }
 
+        if let Some(start_time) = self.start_time {
+            if !(0. ..HOURS_PER_DAY).contains(&start_time) {
+                return Err(MapMetadataValidationError::StartTime(start_time));
+            }
+        }
+
         Ok(())
     }
 }
@@ -97,4 +129,33 @@ pub enum MapMetadataValidationError {
     MapBounds { source: MapBoundsValidationError },
     #[error("map has to have at least 2 players, got {0}")]
     MaxPlayers(Player),
+    #[error("map start time has to be between 0 and {HOURS_PER_DAY} hours, got {0}")]
+    StartTime(f32),
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+
+    #[test]
+    fn test_start_time() {
+        let metadata = MapMetadata::new(
+            "Test".into(),
+            MapBounds::new(Vec2::new(1000., 1000.)),
+            Player::Player2,
+        );
+        assert_eq!(metadata.start_time(), None);
+
+        let metadata = metadata.with_start_time(18.5);
+        assert_eq!(metadata.start_time(), Some(18.5));
+
+        let mut metadata = metadata;
+        metadata.start_time = Some(24.);
+        assert!(matches!(
+            metadata.validate(),
+            Err(MapMetadataValidationError::StartTime(_))
+        ));
+    }
 }
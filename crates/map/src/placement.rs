@@ -28,6 +28,11 @@ impl Placement {
         self.position
     }
 
+    /// Counter clockwise rotation in radians of the object around y axis.
+    pub fn heading(&self) -> f32 {
+        self.heading
+    }
+
     /// Produces world to object transform which can be used to position the
     /// object on the map.
     pub fn to_transform(self) -> Transform {
@@ -0,0 +1,268 @@
+use bevy::prelude::Resource;
+use de_core::{objects::UnitType, player::Player};
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::meta::MapMetadata;
+
+/// Maximum number of units spawned by a single action.
+pub const MAX_WAVE_SIZE: u32 = 50;
+
+/// Scripted scenario (a mission) attached to a map.
+///
+/// A scenario is a list of triggers. Each trigger fires at most once, the
+/// first time its condition is satisfied, and executes its actions in order.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Scenario {
+    triggers: Vec<Trigger>,
+}
+
+impl Scenario {
+    pub fn new(triggers: Vec<Trigger>) -> Self {
+        Self { triggers }
+    }
+
+    pub fn triggers(&self) -> &[Trigger] {
+        self.triggers.as_slice()
+    }
+
+    pub(crate) fn validate(&self, metadata: &MapMetadata) -> Result<(), ScenarioValidationError> {
+        for (index, trigger) in self.triggers.iter().enumerate() {
+            if let Err(error) = trigger.validate(metadata, self.triggers.len()) {
+                return Err(ScenarioValidationError::Trigger {
+                    index,
+                    source: error,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ScenarioValidationError {
+    #[error("invalid trigger at index {index}")]
+    Trigger {
+        index: usize,
+        source: TriggerValidationError,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Trigger {
+    condition: Condition,
+    actions: Vec<Action>,
+}
+
+impl Trigger {
+    pub fn new(condition: Condition, actions: Vec<Action>) -> Self {
+        Self { condition, actions }
+    }
+
+    pub fn condition(&self) -> &Condition {
+        &self.condition
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        self.actions.as_slice()
+    }
+
+    fn validate(
+        &self,
+        metadata: &MapMetadata,
+        num_triggers: usize,
+    ) -> Result<(), TriggerValidationError> {
+        self.condition.validate(metadata, num_triggers)?;
+        for action in &self.actions {
+            action.validate(metadata)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Condition {
+    /// Satisfied once the given number of seconds elapsed since the start
+    /// of the game.
+    Elapsed(f32),
+    /// Satisfied once all buildings of all enemies of the local player are
+    /// destroyed.
+    EnemyBuildingsDestroyed,
+    /// Satisfied once all buildings of the player are destroyed.
+    BuildingsDestroyed(Player),
+    /// Satisfied once all units of the player are destroyed.
+    UnitsDestroyed(Player),
+    /// Satisfied once a different trigger (given by its index) has fired.
+    Fired(usize),
+    /// Satisfied once all of the conditions are satisfied.
+    All(Vec<Condition>),
+}
+
+impl Condition {
+    fn validate(
+        &self,
+        metadata: &MapMetadata,
+        num_triggers: usize,
+    ) -> Result<(), TriggerValidationError> {
+        match self {
+            Self::Elapsed(secs) => {
+                if !secs.is_finite() || *secs < 0. {
+                    return Err(TriggerValidationError::Time(*secs));
+                }
+            }
+            Self::EnemyBuildingsDestroyed => (),
+            Self::Fired(index) => {
+                if *index >= num_triggers {
+                    return Err(TriggerValidationError::TriggerIndex(*index));
+                }
+            }
+            Self::BuildingsDestroyed(player) | Self::UnitsDestroyed(player) => {
+                validate_player(*player, metadata)?;
+            }
+            Self::All(conditions) => {
+                for condition in conditions {
+                    condition.validate(metadata, num_triggers)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Spawns a wave of units of a player around a position. The units are
+    /// sent to the target position if it is given.
+    SpawnWave {
+        player: Player,
+        unit: UnitType,
+        count: u32,
+        position: Vec2,
+        target: Option<Vec2>,
+    },
+    /// Displays an objective (or other message) to the local player.
+    ShowObjective(String),
+    /// Ends the game with a victory (true) or a defeat (false) of the local
+    /// player.
+    EndGame(bool),
+}
+
+impl Action {
+    fn validate(&self, metadata: &MapMetadata) -> Result<(), TriggerValidationError> {
+        match self {
+            Self::SpawnWave {
+                player,
+                count,
+                position,
+                target,
+                ..
+            } => {
+                validate_player(*player, metadata)?;
+                if *count == 0 || *count > MAX_WAVE_SIZE {
+                    return Err(TriggerValidationError::WaveSize(*count));
+                }
+                for point in [Some(*position), *target].into_iter().flatten() {
+                    if !metadata.bounds().contains(point) {
+                        return Err(TriggerValidationError::OutOfMapBounds(point));
+                    }
+                }
+            }
+            Self::ShowObjective(text) => {
+                if text.is_empty() {
+                    return Err(TriggerValidationError::EmptyObjective);
+                }
+            }
+            Self::EndGame(_) => (),
+        }
+        Ok(())
+    }
+}
+
+fn validate_player(player: Player, metadata: &MapMetadata) -> Result<(), TriggerValidationError> {
+    if player > metadata.max_player() {
+        return Err(TriggerValidationError::MaxPlayer {
+            max_player: metadata.max_player(),
+            player,
+        });
+    }
+    Ok(())
+}
+
+#[derive(Error, Debug)]
+pub enum TriggerValidationError {
+    #[error("invalid time {0}")]
+    Time(f32),
+    #[error("player {player} is larger than maximum number of players {max_player}")]
+    MaxPlayer { max_player: Player, player: Player },
+    #[error("wave size {0} is not between 1 and {MAX_WAVE_SIZE}")]
+    WaveSize(u32),
+    #[error("position {0:?} is out of map bounds")]
+    OutOfMapBounds(Vec2),
+    #[error("there is no trigger at index {0}")]
+    TriggerIndex(usize),
+    #[error("objective text is empty")]
+    EmptyObjective,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::size::MapBounds;
+
+    #[test]
+    fn test_validation() {
+        let metadata = MapMetadata::new(
+            "Test".into(),
+            MapBounds::new(Vec2::splat(100.)),
+            Player::Player2,
+        );
+
+        let wave = |player, count, position| Action::SpawnWave {
+            player,
+            unit: UnitType::Attacker,
+            count,
+            position,
+            target: None,
+        };
+
+        let valid = Scenario::new(vec![
+            Trigger::new(
+                Condition::Elapsed(30.),
+                vec![wave(Player::Player2, 5, Vec2::new(10., -10.))],
+            ),
+            Trigger::new(
+                Condition::All(vec![
+                    Condition::Fired(0),
+                    Condition::EnemyBuildingsDestroyed,
+                ]),
+                vec![
+                    Action::ShowObjective("Well done!".into()),
+                    Action::EndGame(true),
+                ],
+            ),
+        ]);
+        valid.validate(&metadata).unwrap();
+
+        let invalid = [
+            Trigger::new(Condition::Elapsed(-1.), vec![]),
+            Trigger::new(Condition::UnitsDestroyed(Player::Player3), vec![]),
+            Trigger::new(
+                Condition::Elapsed(1.),
+                vec![wave(Player::Player1, 0, Vec2::ZERO)],
+            ),
+            Trigger::new(
+                Condition::Elapsed(1.),
+                vec![wave(Player::Player1, 1, Vec2::new(60., 0.))],
+            ),
+            Trigger::new(
+                Condition::All(vec![Condition::BuildingsDestroyed(Player::Player4)]),
+                vec![],
+            ),
+            Trigger::new(Condition::Fired(1), vec![]),
+        ];
+        for trigger in invalid {
+            assert!(Scenario::new(vec![trigger]).validate(&metadata).is_err());
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use bevy::prelude::Resource;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{hash::MapHasher, size::MapBounds};
+
+/// Maximum number of terrain zones on a map.
+pub const MAX_ZONES: usize = 31;
+/// Maximum number of vertices of a single terrain zone polygon.
+pub const MAX_ZONE_VERTICES: usize = 8;
+/// Minimum distance between any terrain zone and the map boundaries.
+pub const ZONE_BOUNDARY_MARGIN: f32 = 8.;
+
+/// Special terrain types covering parts of the map. The rest of the map is
+/// ordinary land.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TerrainZones {
+    zones: Vec<TerrainZone>,
+}
+
+impl TerrainZones {
+    /// Returns a slice of all terrain zones of the map.
+    pub fn zones(&self) -> &[TerrainZone] {
+        self.zones.as_slice()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    /// Returns the first zone impassable for ground units which contains
+    /// `point`.
+    pub fn impassable_at(&self, point: Vec2) -> Option<usize> {
+        self.zones
+            .iter()
+            .position(|zone| zone.kind().impassable() && zone.contains(point))
+    }
+
+    /// Inserts a zone to the map.
+    ///
+    /// This method does no validation which is why it is only `pub(crate)`.
+    pub(crate) fn insert(&mut self, zone: TerrainZone) {
+        self.zones.push(zone);
+    }
+
+    /// Removes a zone from the map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub(crate) fn remove(&mut self, index: usize) -> TerrainZone {
+        self.zones.remove(index)
+    }
+
+    pub(crate) fn update_hash(&self, hasher: &mut MapHasher) {
+        for zone in &self.zones {
+            zone.update_hash(hasher);
+        }
+    }
+
+    pub(crate) fn validate(&self, bounds: MapBounds) -> Result<(), TerrainZonesValidationError> {
+        if self.zones.len() > MAX_ZONES {
+            return Err(TerrainZonesValidationError::MaxZones {
+                max: MAX_ZONES,
+                number: self.zones.len(),
+            });
+        }
+
+        for (index, zone) in self.zones.iter().enumerate() {
+            if let Err(source) = zone.validate(bounds) {
+                return Err(TerrainZonesValidationError::Zone { index, source });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TerrainZonesValidationError {
+    #[error("maximum number of terrain zones is {max}, got {number}")]
+    MaxZones { max: usize, number: usize },
+    #[error("invalid zones[{index}]")]
+    Zone {
+        index: usize,
+        source: TerrainZoneValidationError,
+    },
+}
+
+/// A convex area of the map with a special terrain type.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TerrainZone {
+    kind: ZoneKind,
+    vertices: Vec<Vec2>,
+}
+
+impl TerrainZone {
+    /// Creates a new terrain zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - terrain type of the zone.
+    ///
+    /// * `vertices` - counter-clockwise vertices of a convex polygon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the polygon is not convex, is not counter-clockwise or has
+    /// too few or too many vertices.
+    pub fn new(kind: ZoneKind, vertices: Vec<Vec2>) -> Self {
+        let zone = Self { kind, vertices };
+        zone.validate_polygon().unwrap();
+        zone
+    }
+
+    pub fn kind(&self) -> ZoneKind {
+        self.kind
+    }
+
+    /// Counter-clockwise vertices of the convex polygon of the zone.
+    pub fn vertices(&self) -> &[Vec2] {
+        self.vertices.as_slice()
+    }
+
+    /// Returns true if the point lies inside or on the boundary of the zone.
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.edges().all(|(a, b)| (b - a).perp_dot(point - a) >= 0.)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(&a, &b)| (a, b))
+    }
+
+    fn update_hash(&self, hasher: &mut MapHasher) {
+        hasher.update_u8(self.kind as u8);
+        hasher.update_usize(self.vertices.len());
+        for &vertex in &self.vertices {
+            hasher.update_vec2(vertex);
+        }
+    }
+
+    fn validate(&self, bounds: MapBounds) -> Result<(), TerrainZoneValidationError> {
+        self.validate_polygon()?;
+
+        let margin = Vec2::splat(ZONE_BOUNDARY_MARGIN);
+        for &vertex in &self.vertices {
+            if vertex.cmplt(bounds.min() + margin).any()
+                || vertex.cmpgt(bounds.max() - margin).any()
+            {
+                return Err(TerrainZoneValidationError::CloseToBoundary(vertex));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_polygon(&self) -> Result<(), TerrainZoneValidationError> {
+        if !(3..=MAX_ZONE_VERTICES).contains(&self.vertices.len()) {
+            return Err(TerrainZoneValidationError::VertexCount(self.vertices.len()));
+        }
+
+        for &vertex in &self.vertices {
+            if !vertex.is_finite() {
+                return Err(TerrainZoneValidationError::NotFinite(vertex));
+            }
+        }
+
+        let edges: Vec<(Vec2, Vec2)> = self.edges().collect();
+        for (index, &(a, b)) in edges.iter().enumerate() {
+            let (_, c) = edges[(index + 1) % edges.len()];
+            if (b - a).perp_dot(c - b) <= 0. {
+                return Err(TerrainZoneValidationError::NotConvex);
+            }
+        }
+
+        // Convex turns at all vertices do not rule out a polygon winding
+        // around multiple times.
+        let angle: f32 = edges
+            .iter()
+            .zip(edges.iter().cycle().skip(1))
+            .map(|(&(a, b), &(_, c))| (b - a).angle_between(c - b))
+            .sum();
+        if angle > std::f32::consts::TAU + 0.01 {
+            return Err(TerrainZoneValidationError::NotConvex);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TerrainZoneValidationError {
+    #[error("zone has to have between 3 and {MAX_ZONE_VERTICES} vertices, got {0}")]
+    VertexCount(usize),
+    #[error("vertex ({}, {}) is not finite", .0.x, .0.y)]
+    NotFinite(Vec2),
+    #[error("vertices do not form a counter-clockwise convex polygon")]
+    NotConvex,
+    #[error(
+        "vertex ({}, {}) is closer than {ZONE_BOUNDARY_MARGIN} meters to the map boundaries",
+        .0.x, .0.y
+    )]
+    CloseToBoundary(Vec2),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ZoneKind {
+    Water,
+    Cliff,
+    Crater,
+}
+
+impl ZoneKind {
+    /// Returns true if ground units cannot enter zones of this kind.
+    pub fn impassable(self) -> bool {
+        match self {
+            Self::Water | Self::Cliff => true,
+            Self::Crater => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(kind: ZoneKind, center: Vec2, half_size: f32) -> TerrainZone {
+        TerrainZone::new(
+            kind,
+            vec![
+                center + Vec2::new(-half_size, -half_size),
+                center + Vec2::new(half_size, -half_size),
+                center + Vec2::new(half_size, half_size),
+                center + Vec2::new(-half_size, half_size),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_contains() {
+        let zone = TerrainZone::new(
+            ZoneKind::Water,
+            vec![Vec2::new(0., 0.), Vec2::new(4., 0.), Vec2::new(0., 4.)],
+        );
+        assert!(zone.contains(Vec2::new(1., 1.)));
+        assert!(zone.contains(Vec2::new(2., 2.)));
+        assert!(zone.contains(Vec2::new(0., 0.)));
+        assert!(!zone.contains(Vec2::new(3., 3.)));
+        assert!(!zone.contains(Vec2::new(-1., 1.)));
+    }
+
+    #[test]
+    fn test_impassable_at() {
+        let mut zones = TerrainZones::default();
+        zones.insert(square(ZoneKind::Crater, Vec2::ZERO, 10.));
+        zones.insert(square(ZoneKind::Water, Vec2::new(5., 0.), 10.));
+
+        assert_eq!(zones.impassable_at(Vec2::new(-8., 0.)), None);
+        assert_eq!(zones.impassable_at(Vec2::new(0., 0.)), Some(1));
+        assert_eq!(zones.impassable_at(Vec2::new(50., 0.)), None);
+    }
+
+    #[test]
+    fn test_validation() {
+        let bounds = MapBounds::new(Vec2::splat(100.));
+        assert!(square(ZoneKind::Cliff, Vec2::ZERO, 20.)
+            .validate(bounds)
+            .is_ok());
+
+        assert_eq!(
+            square(ZoneKind::Cliff, Vec2::new(40., 0.), 5.)
+                .validate(bounds)
+                .unwrap_err()
+                .to_string(),
+            "vertex (45, -5) is closer than 8 meters to the map boundaries"
+        );
+        assert_eq!(
+            TerrainZone {
+                kind: ZoneKind::Water,
+                vertices: vec![Vec2::ZERO, Vec2::X],
+            }
+            .validate(bounds)
+            .unwrap_err()
+            .to_string(),
+            "zone has to have between 3 and 8 vertices, got 2"
+        );
+        assert_eq!(
+            TerrainZone {
+                kind: ZoneKind::Water,
+                vertices: vec![Vec2::ZERO, Vec2::Y, Vec2::X],
+            }
+            .validate(bounds)
+            .unwrap_err()
+            .to_string(),
+            "vertices do not form a counter-clockwise convex polygon"
+        );
+        assert_eq!(
+            TerrainZone {
+                kind: ZoneKind::Water,
+                vertices: vec![Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::new(0.5, -1.)],
+            }
+            .validate(bounds)
+            .unwrap_err()
+            .to_string(),
+            "vertices do not form a counter-clockwise convex polygon"
+        );
+    }
+}
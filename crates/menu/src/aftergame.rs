@@ -1,36 +1,344 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
-use de_core::gresult::GameResult;
-use de_gui::{GuiCommands, LabelCommands, OuterStyle};
+use de_core::{
+    gresult::GameResult,
+    player::Player,
+    summary::{MatchSummary, PlayerSummary},
+};
+use de_gui::{BodyTextCommands, ButtonCommands, GuiCommands, LabelCommands, OuterStyle};
+use de_loc::Localize;
 
 use crate::{menu::Menu, MenuState};
 
+/// Colors of players in graphs of the match summary.
+const PLAYER_COLORS: [Color; 4] = [
+    Color::rgb(0.1, 0.1, 0.9),
+    Color::rgb(0.9, 0.1, 0.1),
+    Color::rgb(0.1, 0.8, 0.1),
+    Color::rgb(0.9, 0.8, 0.1),
+];
+const POINT_SIZE: f32 = 6.;
+
 pub(crate) struct AfterGamePlugin;
 
 impl Plugin for AfterGamePlugin {
     fn build(&self, app: &mut App) {
         app.add_system(setup.in_schedule(OnEnter(MenuState::AfterGame)))
-            .add_system(cleanup.in_schedule(OnEnter(MenuState::AfterGame)));
+            .add_system(cleanup.in_schedule(OnEnter(MenuState::AfterGame)))
+            .add_system(remove_summary.in_schedule(OnExit(MenuState::AfterGame)))
+            .add_system(
+                metric_system
+                    .run_if(in_state(MenuState::AfterGame))
+                    .run_if(resource_exists::<MatchSummary>()),
+            );
+    }
+}
+
+/// Node holding the graph of the currently selected metric.
+#[derive(Component)]
+struct Graph;
+
+/// A statistic of the match summary presented in a graph over time.
+#[derive(Component, Clone, Copy)]
+enum Metric {
+    UnitsBuilt,
+    UnitsLost,
+    UnitsKilled,
+    DamageDealt,
+    ResourcesGathered,
+    Apm,
+}
+
+impl Metric {
+    const ALL: [Self; 6] = [
+        Self::UnitsBuilt,
+        Self::UnitsLost,
+        Self::UnitsKilled,
+        Self::DamageDealt,
+        Self::ResourcesGathered,
+        Self::Apm,
+    ];
+
+    fn caption(self, localize: &Localize) -> String {
+        match self {
+            Self::UnitsBuilt => localize.get("after-game-units-built"),
+            Self::UnitsLost => localize.get("after-game-units-lost"),
+            Self::UnitsKilled => localize.get("after-game-units-killed"),
+            Self::DamageDealt => localize.get("after-game-damage-dealt"),
+            Self::ResourcesGathered => localize.get("after-game-resources-gathered"),
+            Self::Apm => localize.get("after-game-apm"),
+        }
+    }
+
+    /// Returns values of the metric of a player at all sample times of the
+    /// summary.
+    fn series(self, times: &[Duration], player: &PlayerSummary) -> Vec<f32> {
+        let history = player.history().iter();
+        match self {
+            Self::UnitsBuilt => history.map(|stats| stats.units_built() as f32).collect(),
+            Self::UnitsLost => history.map(|stats| stats.units_lost() as f32).collect(),
+            Self::UnitsKilled => history.map(|stats| stats.units_killed() as f32).collect(),
+            Self::DamageDealt => history.map(|stats| stats.damage_dealt()).collect(),
+            Self::ResourcesGathered => history.map(|stats| stats.resources_gathered()).collect(),
+            Self::Apm => {
+                let actions: Vec<u32> = history.map(|stats| stats.actions()).collect();
+                per_minute(times, actions.as_slice())
+            }
+        }
     }
 }
 
+/// Converts cumulative counts to rates per minute over the preceding sample
+/// intervals. The rate at the first sample is 0.
+fn per_minute(times: &[Duration], counts: &[u32]) -> Vec<f32> {
+    let mut rates = Vec::with_capacity(counts.len());
+    for (i, &count) in counts.iter().enumerate() {
+        let rate = match i.checked_sub(1) {
+            Some(prev) => {
+                let minutes = (times[i] - times[prev]).as_secs_f32() / 60.;
+                if minutes > 0. {
+                    count.saturating_sub(counts[prev]) as f32 / minutes
+                } else {
+                    0.
+                }
+            }
+            None => 0.,
+        };
+        rates.push(rate);
+    }
+    rates
+}
+
+fn player_color(player: Player) -> Color {
+    PLAYER_COLORS[usize::from(player.to_num() - 1)]
+}
+
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<GameResult>();
 }
 
-fn setup(mut commands: GuiCommands, menu: Res<Menu>, result: Res<GameResult>) {
+fn remove_summary(mut commands: Commands) {
+    commands.remove_resource::<MatchSummary>();
+}
+
+fn setup(
+    mut commands: GuiCommands,
+    menu: Res<Menu>,
+    result: Res<GameResult>,
+    summary: Option<Res<MatchSummary>>,
+    localize: Res<Localize>,
+) {
     let text = match result.as_ref() {
         GameResult::Finished(result) => {
             if result.won() {
-                "You have won!".to_owned()
+                localize.get("after-game-won")
             } else {
-                "You have lost!".to_owned()
+                localize.get("after-game-lost")
             }
         }
         GameResult::Error(message) => {
             error!("Game finished with an error: {message}");
-            format!("Error: {message}")
+            localize.format("after-game-error", &[("message", message)])
         }
     };
     let text_id = commands.spawn_label(OuterStyle::default(), text).id();
-    commands.entity(menu.root_node()).add_child(text_id);
+
+    // Statistics are meaningless if the game has not finished normally.
+    let summary = match (result.as_ref(), summary) {
+        (GameResult::Finished(_), Some(summary)) => summary,
+        _ => {
+            commands.entity(menu.root_node()).add_child(text_id);
+            return;
+        }
+    };
+
+    let column_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                size: Size::new(Val::Percent(80.), Val::Percent(90.)),
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::FlexStart,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(menu.root_node()).add_child(column_node);
+    commands.entity(column_node).add_child(text_id);
+
+    for player in summary.players() {
+        player_row(&mut commands, column_node, &summary, player, &localize);
+    }
+
+    let buttons_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                margin: UiRect::vertical(Val::Percent(1.)),
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(column_node).add_child(buttons_node);
+    for metric in Metric::ALL {
+        let button = commands
+            .spawn_button(
+                OuterStyle {
+                    size: Size::new(Val::Percent(16.), Val::Percent(100.)),
+                    ..default()
+                },
+                metric.caption(localize.as_ref()),
+            )
+            .insert(metric)
+            .id();
+        commands.entity(buttons_node).add_child(button);
+    }
+
+    let graph_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(50.)),
+                ..default()
+            },
+            background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+            ..default()
+        })
+        .insert(Graph)
+        .id();
+    commands.entity(column_node).add_child(graph_node);
+    plot(&mut commands, graph_node, &summary, Metric::UnitsBuilt);
+}
+
+fn player_row(
+    commands: &mut GuiCommands,
+    parent: Entity,
+    summary: &MatchSummary,
+    player: &PlayerSummary,
+    localize: &Localize,
+) {
+    let row_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                size: Size::new(Val::Percent(100.), Val::Percent(4.)),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(parent).add_child(row_node);
+
+    let color_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(2. * POINT_SIZE), Val::Px(2. * POINT_SIZE)),
+                margin: UiRect::right(Val::Px(2. * POINT_SIZE)),
+                ..default()
+            },
+            background_color: player_color(player.player()).into(),
+            ..default()
+        })
+        .id();
+    commands.entity(row_node).add_child(color_node);
+
+    let stats = player.totals();
+    let text = localize.format(
+        "after-game-stats",
+        &[
+            ("player", &player.player().to_num()),
+            ("built", &stats.units_built()),
+            ("lost", &stats.units_lost()),
+            ("killed", &stats.units_killed()),
+            ("damage", &format!("{:.0}", stats.damage_dealt())),
+            ("resources", &format!("{:.0}", stats.resources_gathered())),
+            ("apm", &format!("{:.0}", stats.apm(summary.duration()))),
+        ],
+    );
+    let text_node = commands.spawn_body_text(OuterStyle::default(), text).id();
+    commands.entity(row_node).add_child(text_node);
+}
+
+/// Spawns points of a graph of the metric of all players as children of
+/// `parent`.
+fn plot(commands: &mut GuiCommands, parent: Entity, summary: &MatchSummary, metric: Metric) {
+    let times = summary.times();
+    let total = times.last().copied().unwrap_or_default().as_secs_f32();
+
+    let series: Vec<(Player, Vec<f32>)> = summary
+        .players()
+        .map(|player| (player.player(), metric.series(times, player)))
+        .collect();
+    let max = series
+        .iter()
+        .flat_map(|(_, values)| values.iter().copied())
+        .fold(0., f32::max);
+    let max = if max > 0. { max } else { 1. };
+
+    for (player, values) in series {
+        for (time, value) in times.iter().zip(values) {
+            let x = if total > 0. {
+                time.as_secs_f32() / total
+            } else {
+                0.
+            };
+
+            let point = commands
+                .spawn(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Percent(100. * x),
+                            bottom: Val::Percent(100. * value / max),
+                            ..default()
+                        },
+                        size: Size::new(Val::Px(POINT_SIZE), Val::Px(POINT_SIZE)),
+                        ..default()
+                    },
+                    background_color: player_color(player).into(),
+                    ..default()
+                })
+                .id();
+            commands.entity(parent).add_child(point);
+        }
+    }
+}
+
+fn metric_system(
+    mut commands: GuiCommands,
+    summary: Res<MatchSummary>,
+    graphs: Query<Entity, With<Graph>>,
+    interactions: Query<(&Interaction, &Metric), Changed<Interaction>>,
+) {
+    for (&interaction, &metric) in interactions.iter() {
+        if let Interaction::Clicked = interaction {
+            for graph in graphs.iter() {
+                commands.entity(graph).despawn_descendants();
+                plot(&mut commands, graph, summary.as_ref(), metric);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_minute() {
+        let times = [
+            Duration::ZERO,
+            Duration::from_secs(10),
+            Duration::from_secs(20),
+            Duration::from_secs(25),
+        ];
+        assert_eq!(per_minute(&times, &[0, 5, 5, 10]), vec![0., 30., 0., 60.]);
+        assert!(per_minute(&[], &[]).is_empty());
+    }
 }
@@ -0,0 +1,224 @@
+use bevy::{
+    ecs::system::SystemParam,
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput, ButtonState},
+    prelude::*,
+};
+use de_conf::{is_modifier, Action, Binding, BindingError, InputButton, KeyBindings, Modifiers};
+use de_gui::{ButtonCommands, ButtonOps, GuiCommands, OuterStyle, ToastEvent};
+use de_loc::Localize;
+
+use crate::{menu::Menu, MenuState};
+
+/// Number of columns of the action buttons.
+const COLUMNS: usize = 3;
+
+pub(crate) struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(MenuState::Controls)))
+            .add_system(cleanup.in_schedule(OnExit(MenuState::Controls)))
+            .add_system(
+                capture_system
+                    .run_if(in_state(MenuState::Controls))
+                    .before(button_system),
+            )
+            .add_system(button_system.run_if(in_state(MenuState::Controls)));
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+struct ActionButton(Action);
+
+#[derive(SystemParam)]
+struct ActionButtons<'w, 's> {
+    localize: Res<'w, Localize>,
+    ops: ButtonOps<'w, 's>,
+    buttons: Query<'w, 's, (Entity, &'static ActionButton)>,
+}
+
+impl<'w, 's> ActionButtons<'w, 's> {
+    fn set_caption(&mut self, action: Action, bindings: &KeyBindings) {
+        let text = caption(self.localize.as_ref(), action, bindings);
+        self.set_text(action, text);
+    }
+
+    fn set_waiting(&mut self, action: Action) {
+        let text = self.localize.format(
+            "controls-waiting",
+            &[("action", &self.localize.action_name(action))],
+        );
+        self.set_text(action, text);
+    }
+
+    fn set_text(&mut self, action: Action, text: String) {
+        for (entity, &ActionButton(button_action)) in self.buttons.iter() {
+            if button_action == action {
+                self.ops.set_text(entity, text.clone()).unwrap();
+            }
+        }
+    }
+}
+
+/// Action which is waiting for a key press (or a mouse button press) to be
+/// bound to.
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+fn caption(localize: &Localize, action: Action, bindings: &KeyBindings) -> String {
+    localize.format(
+        "controls-binding",
+        &[
+            ("action", &localize.action_name(action)),
+            ("binding", &bindings.get(action)),
+        ],
+    )
+}
+
+fn binding_error(localize: &Localize, error: &BindingError) -> String {
+    match *error {
+        BindingError::Conflict { first, second, .. } => localize.format(
+            "controls-error-conflict",
+            &[
+                ("first", &localize.action_name(first)),
+                ("second", &localize.action_name(second)),
+            ],
+        ),
+        BindingError::ModifierKey(action) => localize.format(
+            "controls-error-modifier",
+            &[("action", &localize.action_name(action))],
+        ),
+        BindingError::ReservedCtrl(action) => localize.format(
+            "controls-error-ctrl",
+            &[("action", &localize.action_name(action))],
+        ),
+    }
+}
+
+fn setup(
+    mut commands: GuiCommands,
+    menu: Res<Menu>,
+    bindings: Res<KeyBindings>,
+    localize: Res<Localize>,
+) {
+    commands.init_resource::<Rebinding>();
+
+    let row_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                size: Size::new(Val::Percent(90.), Val::Percent(100.)),
+                margin: UiRect::all(Val::Auto),
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(menu.root_node()).add_child(row_node);
+
+    let actions: Vec<Action> = bindings.iter().map(|(action, _)| action).collect();
+    for column in actions.chunks(actions.len().div_ceil(COLUMNS)) {
+        let column_node = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    size: Size::new(Val::Percent(30.), Val::Percent(100.)),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            })
+            .id();
+        commands.entity(row_node).add_child(column_node);
+
+        for &action in column {
+            let caption = caption(localize.as_ref(), action, bindings.as_ref());
+            button(&mut commands, column_node, action, caption);
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Rebinding>();
+}
+
+fn button(commands: &mut GuiCommands, parent: Entity, action: Action, caption: String) {
+    let button = commands
+        .spawn_button(
+            OuterStyle {
+                size: Size::new(Val::Percent(100.), Val::Percent(6.)),
+                margin: UiRect::new(
+                    Val::Percent(0.),
+                    Val::Percent(0.),
+                    Val::Percent(1.),
+                    Val::Percent(1.),
+                ),
+            },
+            caption,
+        )
+        .insert(ActionButton(action))
+        .id();
+    commands.entity(parent).add_child(button);
+}
+
+fn button_system(
+    mut rebinding: ResMut<Rebinding>,
+    bindings: Res<KeyBindings>,
+    mut buttons: ActionButtons,
+    interactions: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+) {
+    for (&interaction, &ActionButton(action)) in interactions.iter() {
+        if let Interaction::Clicked = interaction {
+            // Only a single action is rebound at a time.
+            if let Some(previous) = rebinding.0.replace(action) {
+                buttons.set_caption(previous, bindings.as_ref());
+            }
+            buttons.set_waiting(action);
+        }
+    }
+}
+
+/// Binds the action waiting for a binding to the first pressed key or mouse
+/// button.
+///
+/// Input events are consumed even when no action waits for a binding so that
+/// the mouse click which started the rebinding is not used as the new
+/// binding.
+fn capture_system(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<KeyBindings>,
+    keys: Res<Input<KeyCode>>,
+    mut key_events: EventReader<KeyboardInput>,
+    mut mouse_events: EventReader<MouseButtonInput>,
+    mut buttons: ActionButtons,
+    mut toasts: EventWriter<ToastEvent>,
+) {
+    let key_buttons = key_events
+        .iter()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .filter_map(|event| event.key_code)
+        .filter(|&key| !is_modifier(key))
+        .map(InputButton::Key);
+    let mouse_buttons = mouse_events
+        .iter()
+        .filter(|event| event.state == ButtonState::Pressed)
+        .map(|event| InputButton::Mouse(event.button));
+    let Some(button) = key_buttons.chain(mouse_buttons).last() else {
+        return;
+    };
+    let Some(action) = rebinding.0.take() else {
+        return;
+    };
+
+    let binding = Binding::new(button, Modifiers::pressed(keys.as_ref()));
+    if let Err(error) = bindings.rebind(action, binding) {
+        let error = binding_error(buttons.localize.as_ref(), &error);
+        toasts.send(ToastEvent::new(buttons.localize.format(
+            "controls-error",
+            &[("binding", &binding), ("error", &error)],
+        )));
+    }
+    buttons.set_caption(action, bindings.as_ref());
+}
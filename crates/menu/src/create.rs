@@ -1,13 +1,16 @@
 use std::net::SocketAddr;
 
 use bevy::prelude::*;
+use de_core::player::Player;
 use de_gui::{
     ButtonCommands, ButtonOps, GuiCommands, LabelCommands, OuterStyle, TextBoxCommands,
     TextBoxQuery, ToastEvent,
 };
 use de_lobby_client::CreateGameRequest;
 use de_lobby_model::{GameConfig, GameMap, GameSetup, Validatable};
-use de_map::hash::MapHash;
+use de_loc::Localize;
+use de_map::{generator, hash::MapHash};
+use de_net::PasswordHash;
 
 use crate::{
     mapselection::{MapSelectedEvent, SelectMapEvent},
@@ -61,30 +64,71 @@ enum ButtonAction {
 struct Inputs {
     name: Entity,
     max_players: Entity,
+    password: Entity,
     map: Entity,
 }
 
+/// Password of the most recently created or joined game. When a game is
+/// created, other players must provide the same password to join it.
+///
+/// Starting of multiplayer games from the menu is not yet implemented. Once
+/// it is, the hash is passed to `NetGameConf::with_password_hash`.
 #[derive(Resource)]
-struct SelectedMap(GameMap);
+pub struct GamePassword(Option<PasswordHash>);
+
+impl GamePassword {
+    /// An empty password means that the game is not password protected.
+    pub(crate) fn new(password: &str) -> Self {
+        Self((!password.is_empty()).then(|| PasswordHash::new(password)))
+    }
+
+    /// Hash of the password or None if the game is not password protected.
+    pub fn hash(&self) -> Option<PasswordHash> {
+        self.0
+    }
+}
+
+#[derive(Resource)]
+struct SelectedMap {
+    map: GameMap,
+    /// Seed of a randomly generated map, see [`MapSelectedEvent::seed`].
+    seed: Option<u64>,
+}
 
 struct CreateGameEvent;
 
-fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
+fn setup(mut commands: GuiCommands, menu: Res<Menu>, localize: Res<Localize>) {
     let column_id = column(&mut commands, menu.root_node());
 
     let name_row_id = row(&mut commands, column_id);
 
-    let name_id = text_input(&mut commands, name_row_id, "Name");
+    let name_id = text_input(
+        &mut commands,
+        name_row_id,
+        &localize.get("create-game-name"),
+    );
 
     let max_players_row_id = row(&mut commands, column_id);
-    let max_players_id = text_input(&mut commands, max_players_row_id, "Max Players");
+    let max_players_id = text_input(
+        &mut commands,
+        max_players_row_id,
+        &localize.get("create-game-max-players"),
+    );
+
+    let password_row_id = row(&mut commands, column_id);
+    let password_id = secret_input(
+        &mut commands,
+        password_row_id,
+        &localize.get("create-game-password"),
+    );
 
     let map_row_id = row(&mut commands, column_id);
-    let map_id = map_button(&mut commands, map_row_id);
+    let map_id = map_button(&mut commands, map_row_id, &localize.get("create-game-map"));
 
     commands.insert_resource(Inputs {
         name: name_id,
         max_players: max_players_id,
+        password: password_id,
         map: map_id,
     });
 
@@ -95,7 +139,7 @@ fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
                 size: Size::new(Val::Percent(100.), Val::Percent(100.)),
                 ..default()
             },
-            "Create Game",
+            localize.get("create-game-create"),
         )
         .insert(ButtonAction::Create)
         .id();
@@ -144,6 +188,19 @@ fn row(commands: &mut GuiCommands, parent_id: Entity) -> Entity {
 }
 
 fn text_input(commands: &mut GuiCommands, parent_id: Entity, caption: &str) -> Entity {
+    spawn_text_box(commands, parent_id, caption, false)
+}
+
+fn secret_input(commands: &mut GuiCommands, parent_id: Entity, caption: &str) -> Entity {
+    spawn_text_box(commands, parent_id, caption, true)
+}
+
+fn spawn_text_box(
+    commands: &mut GuiCommands,
+    parent_id: Entity,
+    caption: &str,
+    secret: bool,
+) -> Entity {
     spawn_caption(commands, parent_id, caption);
 
     let input_id = commands
@@ -152,15 +209,15 @@ fn text_input(commands: &mut GuiCommands, parent_id: Entity, caption: &str) -> E
                 size: Size::new(Val::Percent(65.), Val::Percent(100.)),
                 ..default()
             },
-            false,
+            secret,
         )
         .id();
     commands.entity(parent_id).add_child(input_id);
     input_id
 }
 
-fn map_button(commands: &mut GuiCommands, parent_id: Entity) -> Entity {
-    spawn_caption(commands, parent_id, "Map");
+fn map_button(commands: &mut GuiCommands, parent_id: Entity, caption: &str) -> Entity {
+    spawn_caption(commands, parent_id, caption);
 
     let input_id = commands
         .spawn_button(
@@ -215,36 +272,44 @@ fn map_selected_system(
     intpus: Res<Inputs>,
     mut buttons: ButtonOps,
     mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
 ) {
     let Some(event) = map_selected_events.iter().last() else {
         return;
     };
-    let hash = match MapHash::try_from(event.path()) {
-        Ok(hash) => hash,
-        Err(error) => {
-            toasts.send(ToastEvent::new(format!("Map error: {error}")));
-            return;
-        }
+    let hash = match event.seed() {
+        Some(seed) => generator::generate(seed, event.metadata().max_player()).compute_hash(),
+        None => match MapHash::try_from(event.path()) {
+            Ok(hash) => hash,
+            Err(error) => {
+                toasts.send(ToastEvent::new(
+                    localize.format("toast-map-error", &[("error", &error)]),
+                ));
+                return;
+            }
+        },
     };
 
     buttons
         .set_text(intpus.map, event.metadata().name().to_owned())
         .unwrap();
-    commands.insert_resource(SelectedMap(GameMap::new(
-        hash.to_hex(),
-        event.metadata().name().to_owned(),
-    )));
+    commands.insert_resource(SelectedMap {
+        map: GameMap::new(hash.to_hex(), event.metadata().name().to_owned()),
+        seed: event.seed(),
+    });
 }
 
 fn create_game_system(
+    mut commands: Commands,
     inputs: Res<Inputs>,
     texts: TextBoxQuery,
     selected_map: Option<Res<SelectedMap>>,
     mut toasts: EventWriter<ToastEvent>,
     mut sender: Sender<CreateGameRequest>,
+    localize: Res<Localize>,
 ) {
     let Some(selected_map) = selected_map else {
-        toasts.send(ToastEvent::new("No map selected."));
+        toasts.send(ToastEvent::new(localize.get("toast-no-map-selected")));
         return;
     };
 
@@ -252,19 +317,44 @@ fn create_game_system(
     let max_players: u8 = match texts.text(inputs.max_players).unwrap().parse() {
         Ok(value) => value,
         Err(error) => {
-            toasts.send(ToastEvent::new(format!("Invalid max players: {error}")));
+            toasts.send(ToastEvent::new(
+                localize.format("toast-invalid-max-players", &[("error", &error)]),
+            ));
             return;
         }
     };
 
+    // Generated maps differ with the number of players.
+    let map = match selected_map.seed {
+        Some(seed) => match Player::try_from(max_players) {
+            Ok(max_player) if max_player >= Player::Player2 => {
+                let map = generator::generate(seed, max_player);
+                GameMap::new(
+                    map.compute_hash().to_hex(),
+                    map.metadata().name().to_owned(),
+                )
+            }
+            _ => {
+                toasts.send(ToastEvent::new(
+                    localize.format("toast-invalid-max-players", &[("error", &max_players)]),
+                ));
+                return;
+            }
+        },
+        None => selected_map.map.clone(),
+    };
+
     let game_server: SocketAddr = "127.0.0.1:8082".parse().unwrap();
-    let game_config = GameConfig::new(name, max_players, selected_map.0.clone());
+    let game_config = GameConfig::new(name, max_players, map);
     let game_setup = GameSetup::new(game_server, game_config);
     if let Err(error) = game_setup.validate() {
         toasts.send(ToastEvent::new(format!("{error}")));
         return;
     }
 
+    let password = texts.text(inputs.password).unwrap();
+    commands.insert_resource(GamePassword::new(&password));
+
     sender.send(CreateGameRequest::new(game_setup));
 }
 
@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use de_core::state::AppState;
+use de_editor::EditorConfig;
+use de_gui::{ButtonCommands, GuiCommands, OuterStyle};
+use de_loc::Localize;
+
+use crate::{
+    mapselection::{MapSelectedEvent, SelectMapEvent},
+    menu::Menu,
+    MenuState,
+};
+
+pub(crate) struct EditorMenuPlugin;
+
+impl Plugin for EditorMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(MenuState::MapEditor)))
+            .add_system(button_system.run_if(in_state(MenuState::MapEditor)))
+            .add_system(map_selected_system.run_if(in_state(MenuState::MapEditor)));
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+enum ButtonAction {
+    NewMap,
+    OpenMap,
+}
+
+fn setup(mut commands: GuiCommands, menu: Res<Menu>, localize: Res<Localize>) {
+    let column_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                size: Size::new(Val::Percent(25.), Val::Percent(100.)),
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(menu.root_node()).add_child(column_node);
+
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::NewMap,
+        &localize.get("editor-menu-new-map"),
+    );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::OpenMap,
+        &localize.get("editor-menu-open-map"),
+    );
+}
+
+fn button(commands: &mut GuiCommands, parent: Entity, action: ButtonAction, caption: &str) {
+    let button = commands
+        .spawn_button(
+            OuterStyle {
+                size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                margin: UiRect::new(
+                    Val::Percent(0.),
+                    Val::Percent(0.),
+                    Val::Percent(2.),
+                    Val::Percent(2.),
+                ),
+            },
+            caption,
+        )
+        .insert(action)
+        .id();
+    commands.entity(parent).add_child(button);
+}
+
+fn button_system(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &ButtonAction), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut map_events: EventWriter<SelectMapEvent>,
+) {
+    for (&interaction, &action) in interactions.iter() {
+        if let Interaction::Clicked = interaction {
+            match action {
+                ButtonAction::NewMap => {
+                    commands.insert_resource(EditorConfig::new_map());
+                    next_state.set(AppState::InEditor);
+                }
+                ButtonAction::OpenMap => map_events.send(SelectMapEvent),
+            }
+        }
+    }
+}
+
+fn map_selected_system(
+    mut commands: Commands,
+    mut events: EventReader<MapSelectedEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(event) = events.iter().last() else {
+        return;
+    };
+    commands.insert_resource(match event.seed() {
+        Some(seed) => EditorConfig::generated(seed),
+        None => EditorConfig::open(event.path()),
+    });
+    next_state.set(AppState::InEditor);
+}
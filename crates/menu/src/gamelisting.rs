@@ -1,13 +1,29 @@
-use std::time::Duration;
+use std::{io, net::SocketAddr, time::Duration};
 
-use bevy::{prelude::*, time::Stopwatch};
-use de_gui::{ButtonCommands, GuiCommands, LabelCommands, OuterStyle, ToastEvent, ToastSet};
-use de_lobby_client::{ListGamesRequest, RequestEvent, ResponseEvent};
-use de_lobby_model::GamePartial;
+use async_std::future::timeout;
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task},
+    time::Stopwatch,
+};
+use de_conf::Configuration;
+use de_gui::{
+    ButtonCommands, GuiCommands, LabelCommands, OuterStyle, TextBoxCommands, TextBoxQuery,
+    ToastEvent, ToastSet,
+};
+use de_loc::Localize;
+use de_net::{
+    startup, DecodeError, EncodeError, FromServer, GameListing, NetConf, OutPackage, Peers, Socket,
+    ToServer,
+};
+use futures_lite::future;
+use thiserror::Error;
 
-use crate::{menu::Menu, MenuState};
+use crate::{create::GamePassword, menu::Menu, MenuState};
 
 const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// Maximum time to wait for a complete game listing from DE Connector.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub(crate) struct GameListingPlugin;
 
@@ -28,16 +44,30 @@ impl Plugin for GameListingPlugin {
 #[derive(Resource)]
 struct GamesTable(Entity);
 
+/// Pending query of open games.
+#[derive(Resource)]
+struct ListingTask(Task<Result<Listing, ListingError>>);
+
+/// Open games as listed by DE Connector.
+struct Listing {
+    /// Message of the day of the server.
+    motd: Option<String>,
+    games: Vec<GameListing>,
+}
+
 #[derive(Component)]
 enum ButtonAction {
     Create,
-    Join,
+    /// Join a game. The password is entered to the text box if the game is
+    /// password protected.
+    Join(Option<Entity>),
 }
 
 fn setup(
     mut commands: GuiCommands,
     menu: Res<Menu>,
-    mut requests: EventWriter<RequestEvent<ListGamesRequest>>,
+    conf: Res<Configuration>,
+    localize: Res<Localize>,
 ) {
     let column_id = commands
         .spawn(NodeBundle {
@@ -54,24 +84,25 @@ fn setup(
         .id();
     commands.entity(menu.root_node()).add_child(column_id);
 
-    create_game_button(&mut commands, column_id);
+    create_game_button(&mut commands, column_id, localize.as_ref());
     let table_id = table(&mut commands, column_id);
     commands.insert_resource(GamesTable(table_id));
-    requests.send(RequestEvent::new("list-games", ListGamesRequest));
+    commands.insert_resource(spawn_query(conf.multiplayer().connector()));
 }
 
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<GamesTable>();
+    commands.remove_resource::<ListingTask>();
 }
 
-fn create_game_button(commands: &mut GuiCommands, parent_node: Entity) {
+fn create_game_button(commands: &mut GuiCommands, parent_node: Entity, localize: &Localize) {
     let button_id = commands
         .spawn_button(
             OuterStyle {
                 size: Size::new(Val::Percent(100.), Val::Percent(8.)),
                 margin: UiRect::bottom(Val::Percent(1.)),
             },
-            "Create Game",
+            localize.get("game-listing-create"),
         )
         .insert(ButtonAction::Create)
         .id();
@@ -96,7 +127,19 @@ fn table(commands: &mut GuiCommands, parent_node: Entity) -> Entity {
     table_id
 }
 
-fn row(commands: &mut GuiCommands, game: &GamePartial) -> Entity {
+fn motd_row(commands: &mut GuiCommands, motd: &str) -> Entity {
+    commands
+        .spawn_label(
+            OuterStyle {
+                size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                margin: UiRect::vertical(Val::Percent(0.5)),
+            },
+            motd,
+        )
+        .id()
+}
+
+fn row(commands: &mut GuiCommands, localize: &Localize, game: &GameListing) -> Entity {
     let row_id = commands
         .spawn(NodeBundle {
             style: Style {
@@ -114,29 +157,53 @@ fn row(commands: &mut GuiCommands, game: &GamePartial) -> Entity {
     let name_id = commands
         .spawn_label(
             OuterStyle {
-                size: Size::new(Val::Percent(80.), Val::Percent(100.)),
+                size: Size::new(
+                    Val::Percent(if game.protected() { 55. } else { 80. }),
+                    Val::Percent(100.),
+                ),
                 margin: UiRect::right(Val::Percent(2.)),
             },
-            format!(
-                "{} ({}/{})",
-                game.config().name(),
-                game.num_players(),
-                game.config().max_players()
+            localize.format(
+                if game.protected() {
+                    "game-listing-protected-game"
+                } else {
+                    "game-listing-game"
+                },
+                &[
+                    ("name", &game.name()),
+                    ("map", &game.map()),
+                    ("players", &game.num_players()),
+                    ("max-players", &game.max_players()),
+                ],
             ),
         )
         .id();
     commands.entity(row_id).add_child(name_id);
 
-    if game.num_players() < game.config().max_players() {
+    if game.num_players() < game.max_players() {
+        let password_id = game.protected().then(|| {
+            let password_id = commands
+                .spawn_text_box(
+                    OuterStyle {
+                        size: Size::new(Val::Percent(23.), Val::Percent(100.)),
+                        margin: UiRect::right(Val::Percent(2.)),
+                    },
+                    true,
+                )
+                .id();
+            commands.entity(row_id).add_child(password_id);
+            password_id
+        });
+
         let button_id = commands
             .spawn_button(
                 OuterStyle {
                     size: Size::new(Val::Percent(18.), Val::Percent(100.)),
                     ..default()
                 },
-                "Join",
+                localize.get("game-listing-join"),
             )
-            .insert(ButtonAction::Join)
+            .insert(ButtonAction::Join(password_id))
             .id();
         commands.entity(row_id).add_child(button_id);
     }
@@ -145,50 +212,147 @@ fn row(commands: &mut GuiCommands, game: &GamePartial) -> Entity {
 }
 
 fn refresh_system(
+    mut commands: Commands,
+    conf: Res<Configuration>,
     time: Res<Time>,
     mut stopwatch: Local<Stopwatch>,
-    mut requests: EventWriter<RequestEvent<ListGamesRequest>>,
+    task: Option<Res<ListingTask>>,
 ) {
     stopwatch.tick(time.delta());
-    if stopwatch.elapsed() >= REFRESH_INTERVAL {
+    if task.is_none() && stopwatch.elapsed() >= REFRESH_INTERVAL {
         stopwatch.reset();
-        requests.send(RequestEvent::new("list-games", ListGamesRequest));
+        commands.insert_resource(spawn_query(conf.multiplayer().connector()));
     }
 }
 
 fn list_games_system(
     mut commands: GuiCommands,
     table: Res<GamesTable>,
-    mut events: EventReader<ResponseEvent<ListGamesRequest>>,
+    task: Option<ResMut<ListingTask>>,
     mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
 ) {
-    let Some(event) = events.iter().last() else {
+    let Some(mut task) = task else { return };
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
         return;
     };
+    commands.remove_resource::<ListingTask>();
     commands.entity(table.0).despawn_descendants();
 
-    match event.result() {
-        Ok(games) => {
-            for game in games.games() {
-                let row_id = row(&mut commands, game);
+    match result {
+        Ok(listing) => {
+            if let Some(motd) = listing.motd {
+                let row_id = motd_row(&mut commands, &motd);
+                commands.entity(table.0).add_child(row_id);
+            }
+            for game in listing.games.iter() {
+                let row_id = row(&mut commands, localize.as_ref(), game);
                 commands.entity(table.0).add_child(row_id);
             }
         }
-        Err(error) => toasts.send(ToastEvent::new(error)),
+        Err(ListingError::Timeout) => {
+            toasts.send(ToastEvent::new(localize.get("toast-game-listing-timeout")))
+        }
+        Err(error) => toasts.send(ToastEvent::new(
+            localize.format("toast-game-listing-failed", &[("error", &error)]),
+        )),
     }
 }
 
+fn spawn_query(server: SocketAddr) -> ListingTask {
+    ListingTask(IoTaskPool::get().spawn(query_games(server)))
+}
+
+/// Retrieves the list of currently open games from DE Connector.
+async fn query_games(server: SocketAddr) -> Result<Listing, ListingError> {
+    let socket = Socket::bind(None)
+        .await
+        .map_err(|source| ListingError::Network { source })?;
+    let pool = IoTaskPool::get();
+    let (outputs, inputs, _, _) = startup(|t| pool.spawn(t).detach(), socket, NetConf::default());
+
+    let request = OutPackage::encode_single(&ToServer::ListGames, true, Peers::Server, server)
+        .map_err(|source| ListingError::Encode { source })?;
+    outputs
+        .send(request)
+        .await
+        .map_err(|_| ListingError::Closed)?;
+
+    let mut motd = None;
+    let mut games = Vec::new();
+    let mut total = None;
+    let result = timeout(QUERY_TIMEOUT, async {
+        while total.map_or(true, |total| games.len() < total) {
+            let Ok(package) = inputs.recv().await else {
+                return Err(ListingError::Closed);
+            };
+            if package.source() != server {
+                continue;
+            }
+
+            for message in package.decode::<FromServer>() {
+                match message {
+                    Ok(FromServer::Game(game)) => games.push(game),
+                    Ok(FromServer::GamesEnd(count)) => total = Some(usize::from(count)),
+                    Ok(FromServer::Motd(text)) => motd = Some(text),
+                    Ok(_) => (),
+                    Err(source) => return Err(ListingError::Decode { source }),
+                }
+            }
+        }
+
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {
+            games.sort_unstable_by_key(|game| game.port());
+            Ok(Listing { motd, games })
+        }
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err(ListingError::Timeout),
+    }
+}
+
+#[derive(Error, Debug)]
+enum ListingError {
+    #[error("failed to open network: {source}")]
+    Network { source: io::Error },
+    #[error("failed to encode game listing request: {source}")]
+    Encode { source: EncodeError },
+    #[error("network unexpectedly closed")]
+    Closed,
+    #[error("invalid data received: {source}")]
+    Decode { source: DecodeError },
+    #[error("game listing timed out")]
+    Timeout,
+}
+
 fn button_system(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<MenuState>>,
     interactions: Query<(&Interaction, &ButtonAction), Changed<Interaction>>,
+    texts: TextBoxQuery,
     mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
 ) {
     for (&interaction, action) in interactions.iter() {
         if let Interaction::Clicked = interaction {
             match action {
                 ButtonAction::Create => next_state.set(MenuState::GameCreation),
-                ButtonAction::Join => {
-                    toasts.send(ToastEvent::new("Not yet implemented (issue #301)."))
+                ButtonAction::Join(password_id) => {
+                    let password = password_id
+                        .and_then(|password_id| texts.text(password_id))
+                        .unwrap_or_default();
+                    if password_id.is_some() && password.is_empty() {
+                        toasts.send(ToastEvent::new(localize.get("toast-password-required")));
+                        continue;
+                    }
+                    commands.insert_resource(GamePassword::new(&password));
+
+                    // Joining of games is not yet implemented.
+                    toasts.send(ToastEvent::new(localize.get("toast-not-implemented")));
                 }
             }
         }
@@ -1,25 +1,35 @@
 use aftergame::AfterGamePlugin;
 use bevy::{app::PluginGroupBuilder, prelude::*};
+use controls::ControlsPlugin;
 use create::CreateGamePlugin;
+pub use create::GamePassword;
 use de_core::{
     gresult::GameResult,
     state::AppState,
     transition::{DeStateTransition, StateWithSet},
 };
+use editor::EditorMenuPlugin;
 use gamelisting::GameListingPlugin;
+use loadgame::LoadGamePlugin;
 use mainmenu::MainMenuPlugin;
 use mapselection::MapSelectionPlugin;
 use menu::MenuPlugin;
+pub use settings::SettingsButton;
+use settings::SettingsPlugin;
 use signin::SignInPlugin;
 use singleplayer::SinglePlayerPlugin;
 
 mod aftergame;
+mod controls;
 mod create;
+mod editor;
 mod gamelisting;
+mod loadgame;
 mod mainmenu;
 mod mapselection;
 mod menu;
 mod requests;
+mod settings;
 mod signin;
 mod singleplayer;
 
@@ -35,8 +45,12 @@ impl PluginGroup for MenuPluginGroup {
             .add(SignInPlugin)
             .add(GameListingPlugin)
             .add(SinglePlayerPlugin)
+            .add(LoadGamePlugin)
             .add(CreateGamePlugin)
             .add(AfterGamePlugin)
+            .add(EditorMenuPlugin)
+            .add(SettingsPlugin)
+            .add(ControlsPlugin)
     }
 }
 
@@ -57,11 +71,15 @@ pub(crate) enum MenuState {
     None,
     MainMenu,
     SinglePlayerGame,
+    LoadGame,
     SignIn,
     GameListing,
     GameCreation,
     MultiPlayerGame,
     AfterGame,
+    MapEditor,
+    Settings,
+    Controls,
 }
 
 impl StateWithSet for MenuState {
@@ -0,0 +1,176 @@
+use async_std::path::PathBuf;
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task},
+};
+use chrono::{DateTime, Local};
+use de_ai::{AiConf, Difficulty};
+use de_core::{player::Player, state::AppState};
+use de_gui::{ButtonCommands, GuiCommands, LabelCommands, OuterStyle, ToastEvent};
+use de_loc::Localize;
+use de_persistence::{list_saves, load_save, LoadError, LoadedGame, SaveEntry, SaveFile};
+use futures_lite::future;
+
+use crate::{menu::Menu, MenuState};
+
+pub(crate) struct LoadGamePlugin;
+
+impl Plugin for LoadGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(MenuState::LoadGame)))
+            .add_system(cleanup.in_schedule(OnExit(MenuState::LoadGame)))
+            .add_system(list_saves_system.run_if(in_state(MenuState::LoadGame)))
+            .add_system(button_system.run_if(in_state(MenuState::LoadGame)))
+            .add_system(start_game_system.run_if(in_state(MenuState::LoadGame)));
+    }
+}
+
+#[derive(Resource)]
+struct SavesColumn(Entity);
+
+/// Pending listing of available saved games.
+#[derive(Resource)]
+struct ListingTask(Task<Result<Vec<SaveEntry>, LoadError>>);
+
+/// Pending loading of the selected saved game.
+#[derive(Resource)]
+struct LoadingTask(Task<Result<SaveFile, LoadError>>);
+
+#[derive(Component)]
+struct SaveButton(PathBuf);
+
+fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
+    let column_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                size: Size::new(Val::Percent(25.), Val::Percent(100.)),
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(menu.root_node()).add_child(column_node);
+
+    commands.insert_resource(SavesColumn(column_node));
+    commands.insert_resource(ListingTask(IoTaskPool::get().spawn(list_saves())));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<SavesColumn>();
+    commands.remove_resource::<ListingTask>();
+    commands.remove_resource::<LoadingTask>();
+}
+
+fn list_saves_system(
+    mut commands: GuiCommands,
+    column: Res<SavesColumn>,
+    task: Option<ResMut<ListingTask>>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    let Some(mut task) = task else { return };
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<ListingTask>();
+
+    let saves = match result {
+        Ok(saves) => saves,
+        Err(err) => {
+            toasts.send(ToastEvent::new(
+                localize.format("toast-save-listing-failed", &[("error", &err)]),
+            ));
+            return;
+        }
+    };
+
+    if saves.is_empty() {
+        let label = commands
+            .spawn_label(
+                OuterStyle {
+                    size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                    ..default()
+                },
+                localize.get("load-game-no-saves"),
+            )
+            .id();
+        commands.entity(column.0).add_child(label);
+    }
+
+    for save in saves {
+        let modified: DateTime<Local> = save.modified().into();
+        let button = commands
+            .spawn_button(
+                OuterStyle {
+                    size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                    margin: UiRect::new(
+                        Val::Percent(0.),
+                        Val::Percent(0.),
+                        Val::Percent(2.),
+                        Val::Percent(2.),
+                    ),
+                },
+                modified.format("%Y-%m-%d %H:%M:%S").to_string(),
+            )
+            .insert(SaveButton(save.path().to_owned()))
+            .id();
+        commands.entity(column.0).add_child(button);
+    }
+}
+
+fn button_system(
+    mut commands: Commands,
+    interactions: Query<(&Interaction, &SaveButton), Changed<Interaction>>,
+    task: Option<Res<LoadingTask>>,
+) {
+    if task.is_some() {
+        return;
+    }
+
+    for (&interaction, button) in interactions.iter() {
+        if let Interaction::Clicked = interaction {
+            let path = button.0.clone();
+            info!("Loading saved game from {path:?}.");
+            let task = IoTaskPool::get().spawn(async move { load_save(&path).await });
+            commands.insert_resource(LoadingTask(task));
+            break;
+        }
+    }
+}
+
+fn start_game_system(
+    mut commands: Commands,
+    task: Option<ResMut<LoadingTask>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    let Some(mut task) = task else { return };
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<LoadingTask>();
+
+    match result {
+        Ok(save) => {
+            let config = save.game_config();
+            // Difficulty is not part of saved games.
+            let ai_players = (1..=config.max_player().to_num())
+                .map(|num| Player::try_from(num).unwrap())
+                .filter(|&player| !config.locals().is_playable(player));
+            commands.insert_resource(AiConf::new(Difficulty::default(), ai_players));
+            commands.insert_resource(config);
+            commands.insert_resource(LoadedGame::new(save));
+            next_state.set(AppState::InGame);
+        }
+        Err(err) => {
+            toasts.send(ToastEvent::new(
+                localize.format("toast-game-loading-failed", &[("error", &err)]),
+            ));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::{app::AppExit, prelude::*};
 use de_gui::{ButtonCommands, GuiCommands, OuterStyle};
+use de_loc::Localize;
 
 use crate::{menu::Menu, MenuState};
 
@@ -18,7 +19,7 @@ enum ButtonAction {
     Quit,
 }
 
-fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
+fn setup(mut commands: GuiCommands, menu: Res<Menu>, localize: Res<Localize>) {
     let column_node = commands
         .spawn(NodeBundle {
             style: Style {
@@ -38,15 +39,38 @@ fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
         &mut commands,
         column_node,
         ButtonAction::SwithState(MenuState::SinglePlayerGame),
-        "Singleplayer",
+        &localize.get("main-menu-singleplayer"),
     );
     button(
         &mut commands,
         column_node,
         ButtonAction::SwithState(MenuState::SignIn),
-        "Multiplayer",
+        &localize.get("main-menu-multiplayer"),
+    );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::SwithState(MenuState::MapEditor),
+        &localize.get("main-menu-map-editor"),
+    );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::SwithState(MenuState::Settings),
+        &localize.get("main-menu-settings"),
+    );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::SwithState(MenuState::Controls),
+        &localize.get("main-menu-controls"),
+    );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::Quit,
+        &localize.get("main-menu-quit"),
     );
-    button(&mut commands, column_node, ButtonAction::Quit, "Quit Game");
 }
 
 fn button(commands: &mut GuiCommands, parent: Entity, action: ButtonAction, caption: &str) {
@@ -1,14 +1,24 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use async_std::{fs, io, stream::StreamExt};
 use bevy::{
     prelude::*,
+    render::texture::{CompressedImageFormats, ImageType},
     tasks::{IoTaskPool, Task},
 };
-use de_core::{assets::asset_path, log_full_error, state::AppState};
-use de_gui::{ButtonCommands, GuiCommands, OuterStyle};
+use de_core::{assets::asset_path, log_full_error, player::Player, state::AppState};
+use de_gui::{
+    BodyTextCommands, BodyTextOps, ButtonCommands, GuiCommands, OuterStyle, TextBoxCommands,
+    TextBoxQuery, ToastEvent,
+};
+use de_loc::Localize;
 use de_map::{
-    io::{load_metadata, MapLoadingError, MAP_FILE_SUFFIX},
+    description::{MapPreview, Thumbnail},
+    generator,
+    io::{load_preview, MapLoadingError, MAP_FILE_SUFFIX},
     meta::MapMetadata,
 };
 use futures_lite::future;
@@ -25,6 +35,16 @@ impl Plugin for MapSelectionPlugin {
             .add_system(cleanup.in_schedule(OnExit(MapState::On)))
             .add_system(init_buttons.run_if(in_state(MapState::On)))
             .add_system(button_system.run_if(in_state(MapState::On)))
+            .add_system(
+                random_button_system
+                    .run_if(in_state(MapState::On))
+                    .run_if(resource_exists::<SeedInput>()),
+            )
+            .add_system(
+                preview_system
+                    .run_if(in_state(MapState::On))
+                    .run_if(resource_exists::<PreviewNodes>()),
+            )
             .add_system(
                 select_map_system
                     .run_if(in_state(AppState::InMenu))
@@ -47,19 +67,39 @@ pub(crate) struct SelectMapEvent;
 /// switched to a next state.
 pub(crate) struct MapSelectedEvent {
     path: PathBuf,
+    seed: Option<u64>,
     metadata: MapMetadata,
 }
 
 impl MapSelectedEvent {
     fn new(path: PathBuf, metadata: MapMetadata) -> Self {
-        Self { path, metadata }
+        Self {
+            path,
+            seed: None,
+            metadata,
+        }
+    }
+
+    fn generated(seed: u64, metadata: MapMetadata) -> Self {
+        Self {
+            path: PathBuf::new(),
+            seed: Some(seed),
+            metadata,
+        }
     }
 
-    /// Path to the map on the local file system.
+    /// Path to the map on the local file system. It is empty if a randomly
+    /// generated map was selected.
     pub(crate) fn path(&self) -> &Path {
         self.path.as_path()
     }
 
+    /// Seed of the randomly generated map or None if a map file was
+    /// selected. See [`generator::generate`].
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
     /// Selected map metadata.
     pub(crate) fn metadata(&self) -> &MapMetadata {
         &self.metadata
@@ -69,23 +109,81 @@ impl MapSelectedEvent {
 #[derive(Resource)]
 struct PopUpNode(Entity);
 
+/// Thumbnail and details of the hovered map are displayed in these nodes.
+#[derive(Resource)]
+struct PreviewNodes {
+    image: Entity,
+    text: Entity,
+}
+
+/// Text box with the seed of a randomly generated map.
+#[derive(Resource)]
+struct SeedInput(Entity);
+
+/// Button selecting a randomly generated map.
+#[derive(Component)]
+struct RandomMapButton;
+
 #[derive(Resource)]
-struct LoadingTask(Task<Result<Vec<MapEntry>, LoadingError>>);
+struct LoadingTask(Task<Result<Vec<(PathBuf, MapPreview)>, LoadingError>>);
 
 #[derive(Component)]
-struct MapEntry(PathBuf, MapMetadata);
+struct MapEntry {
+    path: PathBuf,
+    preview: MapPreview,
+    thumbnail: Option<Handle<Image>>,
+}
 
 impl MapEntry {
-    fn new(path: PathBuf, meta: MapMetadata) -> Self {
-        Self(path, meta)
+    fn new(path: PathBuf, preview: MapPreview, thumbnail: Option<Handle<Image>>) -> Self {
+        Self {
+            path,
+            preview,
+            thumbnail,
+        }
     }
 
     fn path(&self) -> &Path {
-        self.0.as_path()
+        self.path.as_path()
     }
 
     fn metadata(&self) -> &MapMetadata {
-        &self.1
+        self.preview.metadata()
+    }
+
+    /// Returns multi-line human readable details of the map.
+    fn details(&self, localize: &Localize) -> String {
+        let metadata = self.preview.metadata();
+        let description = self.preview.description();
+        let size = metadata.bounds().size();
+
+        let mut details = metadata.name().to_owned();
+        if !description.author().is_empty() {
+            details.push('\n');
+            details.push_str(
+                &localize.format("map-details-author", &[("author", &description.author())]),
+            );
+        }
+        details.push_str("\n\n");
+        details.push_str(&localize.format(
+            "map-details-players",
+            &[("players", &metadata.max_player().to_num())],
+        ));
+        if let Some(players) = description.recommended_players() {
+            details.push(' ');
+            details.push_str(
+                &localize.format("map-details-recommended", &[("players", &players.to_num())]),
+            );
+        }
+        details.push('\n');
+        details.push_str(&localize.format(
+            "map-details-size",
+            &[("width", &size.x), ("height", &size.y)],
+        ));
+        if !description.description().is_empty() {
+            details.push_str(&format!("\n\n{}", description.description()));
+        }
+        details
     }
 }
 
@@ -119,8 +217,10 @@ fn setup(mut commands: Commands) {
 
 fn init_buttons(
     mut commands: GuiCommands,
+    mut images: ResMut<Assets<Image>>,
     node: Res<PopUpNode>,
     task: Option<ResMut<LoadingTask>>,
+    localize: Res<Localize>,
 ) {
     let Some(mut task) = task else { return };
     let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
@@ -153,14 +253,106 @@ fn init_buttons(
 
     commands.entity(node.0).add_child(column_node);
 
-    for map in map_entries {
-        let button = map_button(&mut commands, map);
+    let seed_input = commands
+        .spawn_text_box(
+            OuterStyle {
+                size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                margin: UiRect::vertical(Val::Percent(2.)),
+            },
+            false,
+        )
+        .id();
+    commands.entity(column_node).add_child(seed_input);
+    commands.insert_resource(SeedInput(seed_input));
+    let random_button = commands
+        .spawn_button(
+            OuterStyle {
+                size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                margin: UiRect::new(
+                    Val::Percent(0.),
+                    Val::Percent(0.),
+                    Val::Percent(2.),
+                    Val::Percent(6.),
+                ),
+            },
+            localize.get("map-selection-random"),
+        )
+        .insert(RandomMapButton)
+        .id();
+    commands.entity(column_node).add_child(random_button);
+
+    for (path, preview) in map_entries {
+        let thumbnail = preview
+            .thumbnail()
+            .and_then(|thumbnail| decode_thumbnail(path.as_path(), thumbnail))
+            .map(|image| images.add(image));
+        let button = map_button(&mut commands, MapEntry::new(path, preview, thumbnail));
         commands.entity(column_node).add_child(button);
     }
+
+    let preview_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                size: Size::new(Val::Percent(35.), Val::Percent(100.)),
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(node.0).add_child(preview_node);
+
+    let image = commands
+        .spawn(ImageBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Auto),
+                aspect_ratio: Some(1.),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .id();
+    commands.entity(preview_node).add_child(image);
+
+    let text = commands
+        .spawn_body_text(
+            OuterStyle {
+                size: Size::new(Val::Percent(100.), Val::Percent(40.)),
+                margin: UiRect::top(Val::Percent(4.)),
+            },
+            localize.get("map-selection-hint"),
+        )
+        .id();
+    commands.entity(preview_node).add_child(text);
+
+    commands.insert_resource(PreviewNodes { image, text });
+}
+
+/// Decodes a PNG map thumbnail. None is returned (and a warning is logged)
+/// if the thumbnail cannot be decoded.
+fn decode_thumbnail(path: &Path, thumbnail: &Thumbnail) -> Option<Image> {
+    match Image::from_buffer(
+        thumbnail.png(),
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+    ) {
+        Ok(image) => Some(image),
+        Err(error) => {
+            warn!("Invalid thumbnail of map {path:?}: {error}");
+            None
+        }
+    }
 }
 
 fn cleanup(mut commands: Commands, node: Res<PopUpNode>) {
     commands.remove_resource::<LoadingTask>();
+    commands.remove_resource::<PreviewNodes>();
+    commands.remove_resource::<SeedInput>();
     commands.entity(node.0).despawn_recursive();
 }
 
@@ -180,7 +372,73 @@ fn button_system(
     }
 }
 
-async fn load_available_maps() -> Result<Vec<MapEntry>, LoadingError> {
+/// Selects a map generated from the seed in the seed text box. A random seed
+/// is used if the text box is empty.
+fn random_button_system(
+    mut next_state: ResMut<NextState<MapState>>,
+    interactions: Query<&Interaction, (Changed<Interaction>, With<RandomMapButton>)>,
+    seed_input: Res<SeedInput>,
+    texts: TextBoxQuery,
+    mut events: EventWriter<MapSelectedEvent>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    if !interactions
+        .iter()
+        .any(|&interaction| interaction == Interaction::Clicked)
+    {
+        return;
+    }
+
+    let text = texts.text(seed_input.0).unwrap();
+    let seed = if text.is_empty() {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64)
+    } else {
+        match text.parse() {
+            Ok(seed) => seed,
+            Err(error) => {
+                toasts.send(ToastEvent::new(
+                    localize.format("toast-invalid-seed", &[("error", &error)]),
+                ));
+                return;
+            }
+        }
+    };
+
+    info!("Generating a random map from seed {seed}.");
+    let map = generator::generate(seed, Player::Player4);
+    next_state.set(MapState::Off);
+    events.send(MapSelectedEvent::generated(seed, map.metadata().clone()));
+}
+
+fn preview_system(
+    nodes: Res<PreviewNodes>,
+    interactions: Query<(&Interaction, &MapEntry), Changed<Interaction>>,
+    mut images: Query<(&mut UiImage, &mut Visibility)>,
+    localize: Res<Localize>,
+    mut text: BodyTextOps,
+) {
+    for (&interaction, map) in interactions.iter() {
+        if interaction != Interaction::Hovered {
+            continue;
+        }
+
+        let (mut image, mut visibility) = images.get_mut(nodes.image).unwrap();
+        match map.thumbnail {
+            Some(ref thumbnail) => {
+                *image = UiImage::new(thumbnail.clone());
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+        text.set_text(nodes.text, map.details(localize.as_ref()))
+            .unwrap();
+    }
+}
+
+async fn load_available_maps() -> Result<Vec<(PathBuf, MapPreview)>, LoadingError> {
     let maps_dir = asset_path("maps");
 
     let mut map_entries = Vec::new();
@@ -206,14 +464,14 @@ async fn load_available_maps() -> Result<Vec<MapEntry>, LoadingError> {
             continue;
         }
 
-        let metadata = match load_metadata(path.as_path()).await {
-            Ok(meta) => meta,
+        let preview = match load_preview(path.as_path()).await {
+            Ok(preview) => preview,
             Err(err) => return Err(LoadingError::Map { source: err }),
         };
-        map_entries.push(MapEntry::new(path.into(), metadata));
+        map_entries.push((path.into(), preview));
     }
 
-    map_entries.sort_by(|a, b| a.metadata().name().cmp(b.metadata().name()));
+    map_entries.sort_by(|(_, a), (_, b)| a.metadata().name().cmp(b.metadata().name()));
     Ok(map_entries)
 }
 
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use de_conf::{GraphicsSettings, ShadowQuality, RESOLUTION_SCALES};
+use de_gui::{ButtonCommands, ButtonOps, GuiCommands, OuterStyle};
+use de_loc::Localize;
+
+use crate::{menu::Menu, MenuState};
+
+pub(crate) struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(MenuState::Settings)))
+            .add_system(button_system.run_if(resource_exists::<GraphicsSettings>()));
+    }
+}
+
+/// Buttons with this component change a setting to its next value when
+/// clicked. The buttons are handled both in the settings menu and outside of
+/// it, e.g. in the in-game menu.
+#[derive(Component, Clone, Copy)]
+pub enum SettingsButton {
+    Shadows,
+    Msaa,
+    ResolutionScale,
+    Vsync,
+    Language,
+}
+
+impl SettingsButton {
+    /// Buttons of all graphics settings.
+    pub const GRAPHICS: [Self; 4] = [
+        Self::Shadows,
+        Self::Msaa,
+        Self::ResolutionScale,
+        Self::Vsync,
+    ];
+
+    pub fn caption(self, settings: &GraphicsSettings, localize: &Localize) -> String {
+        match self {
+            Self::Shadows => {
+                let quality = match settings.shadows() {
+                    ShadowQuality::Off => localize.get("settings-off"),
+                    ShadowQuality::Low => localize.get("settings-low"),
+                    ShadowQuality::Medium => localize.get("settings-medium"),
+                    ShadowQuality::High => localize.get("settings-high"),
+                };
+                localize.format("settings-shadows", &[("value", &quality)])
+            }
+            Self::Msaa => localize.format(
+                "settings-msaa",
+                &[("value", &on_off(settings.msaa(), localize))],
+            ),
+            Self::ResolutionScale => localize.format(
+                "settings-scale",
+                &[(
+                    "value",
+                    &format!("{:.0}", 100. * settings.resolution_scale()),
+                )],
+            ),
+            Self::Vsync => localize.format(
+                "settings-vsync",
+                &[("value", &on_off(settings.vsync(), localize))],
+            ),
+            Self::Language => {
+                localize.format("settings-language", &[("value", &localize.language())])
+            }
+        }
+    }
+
+    /// Changes the setting to its next value.
+    fn apply(self, settings: &mut GraphicsSettings, localize: &mut Localize) {
+        match self {
+            Self::Shadows => settings.set_shadows(settings.shadows().next()),
+            Self::Msaa => settings.set_msaa(!settings.msaa()),
+            Self::ResolutionScale => {
+                settings.set_resolution_scale(next_scale(settings.resolution_scale()))
+            }
+            Self::Vsync => settings.set_vsync(!settings.vsync()),
+            Self::Language => localize.set_language(localize.language().next()),
+        }
+    }
+}
+
+fn on_off(value: bool, localize: &Localize) -> String {
+    if value {
+        localize.get("settings-on")
+    } else {
+        localize.get("settings-off")
+    }
+}
+
+/// Returns the smallest offered resolution scale larger than the given
+/// scale, wrapping around to the smallest offered scale.
+fn next_scale(scale: f32) -> f32 {
+    RESOLUTION_SCALES
+        .iter()
+        .copied()
+        .find(|&candidate| candidate > scale)
+        .unwrap_or(RESOLUTION_SCALES[0])
+}
+
+fn setup(
+    mut commands: GuiCommands,
+    menu: Res<Menu>,
+    settings: Res<GraphicsSettings>,
+    localize: Res<Localize>,
+) {
+    let column_node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                size: Size::new(Val::Percent(25.), Val::Percent(100.)),
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .id();
+    commands.entity(menu.root_node()).add_child(column_node);
+
+    for action in SettingsButton::GRAPHICS
+        .into_iter()
+        .chain([SettingsButton::Language])
+    {
+        let caption = action.caption(settings.as_ref(), localize.as_ref());
+        button(&mut commands, column_node, action, caption);
+    }
+}
+
+fn button(commands: &mut GuiCommands, parent: Entity, action: SettingsButton, caption: String) {
+    let button = commands
+        .spawn_button(
+            OuterStyle {
+                size: Size::new(Val::Percent(100.), Val::Percent(8.)),
+                margin: UiRect::new(
+                    Val::Percent(0.),
+                    Val::Percent(0.),
+                    Val::Percent(2.),
+                    Val::Percent(2.),
+                ),
+            },
+            caption,
+        )
+        .insert(action)
+        .id();
+    commands.entity(parent).add_child(button);
+}
+
+fn button_system(
+    mut settings: ResMut<GraphicsSettings>,
+    mut localize: ResMut<Localize>,
+    mut buttons: ButtonOps,
+    interactions: Query<(&Interaction, &SettingsButton), Changed<Interaction>>,
+    actions: Query<(Entity, &SettingsButton)>,
+) {
+    for (&interaction, &action) in interactions.iter() {
+        if let Interaction::Clicked = interaction {
+            action.apply(settings.as_mut(), localize.as_mut());
+
+            // Change of the language affects captions of all buttons.
+            for (entity, &action) in actions.iter() {
+                buttons
+                    .set_text(entity, action.caption(settings.as_ref(), localize.as_ref()))
+                    .unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_scale() {
+        assert_eq!(next_scale(1.), 1.25);
+        assert_eq!(next_scale(1.1), 1.25);
+        assert_eq!(next_scale(2.), 0.5);
+        assert_eq!(next_scale(0.1), 0.5);
+    }
+}
@@ -5,6 +5,7 @@ use de_gui::{
 };
 use de_lobby_client::{Authentication, LobbyRequest, SignInRequest, SignUpRequest};
 use de_lobby_model::{User, UserWithPassword, UsernameAndPassword};
+use de_loc::Localize;
 
 use crate::{
     menu::Menu,
@@ -51,19 +52,34 @@ enum Action {
     SignUp,
 }
 
-fn setup(mut commands: GuiCommands, menu: Res<Menu>, mut focus: EventWriter<SetFocusEvent>) {
+fn setup(
+    mut commands: GuiCommands,
+    menu: Res<Menu>,
+    localize: Res<Localize>,
+    mut focus: EventWriter<SetFocusEvent>,
+) {
     let column = root_column(&mut commands);
     commands.entity(menu.root_node()).add_child(column);
 
     let username_row = row(&mut commands, column);
-    let input_text_box = input(&mut commands, username_row, "Username:", false);
+    let input_text_box = input(
+        &mut commands,
+        username_row,
+        &localize.get("sign-in-username"),
+        false,
+    );
     focus.send(SetFocusEvent::some(input_text_box));
 
     let password_row = row(&mut commands, column);
-    let password_text_box = input(&mut commands, password_row, "Password:", true);
+    let password_text_box = input(
+        &mut commands,
+        password_row,
+        &localize.get("sign-in-password"),
+        true,
+    );
 
     let buttons_row = row(&mut commands, column);
-    buttons(&mut commands, buttons_row);
+    buttons(&mut commands, buttons_row, localize.as_ref());
 
     commands.insert_resource(Inputs {
         username: input_text_box,
@@ -139,15 +155,15 @@ fn input(commands: &mut GuiCommands, parent: Entity, caption: &str, secret: bool
     input
 }
 
-fn buttons(commands: &mut GuiCommands, parent: Entity) {
-    button(commands, parent, Action::SignIn);
-    button(commands, parent, Action::SignUp);
+fn buttons(commands: &mut GuiCommands, parent: Entity, localize: &Localize) {
+    button(commands, parent, localize, Action::SignIn);
+    button(commands, parent, localize, Action::SignUp);
 }
 
-fn button(commands: &mut GuiCommands, parent: Entity, action: Action) {
+fn button(commands: &mut GuiCommands, parent: Entity, localize: &Localize, action: Action) {
     let caption = match action {
-        Action::SignIn => "Sign In",
-        Action::SignUp => "Sign Up",
+        Action::SignIn => localize.get("sign-in-sign-in"),
+        Action::SignUp => localize.get("sign-in-sign-up"),
     };
 
     let id = commands
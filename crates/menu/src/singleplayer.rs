@@ -1,11 +1,13 @@
 use async_std::path::PathBuf;
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_ai::{AiConf, Difficulty};
 use de_core::{
     gconfig::{GameConfig, LocalPlayers},
-    player::Player,
+    player::{Player, PlayerRange},
     state::AppState,
 };
-use de_gui::{ButtonCommands, GuiCommands, OuterStyle, ToastEvent};
+use de_gui::{ButtonCommands, ButtonOps, GuiCommands, OuterStyle, ToastEvent};
+use de_loc::Localize;
 
 use crate::{
     mapselection::{MapSelectedEvent, SelectMapEvent},
@@ -20,21 +22,28 @@ impl Plugin for SinglePlayerPlugin {
         app.add_system(setup.in_schedule(OnEnter(MenuState::SinglePlayerGame)))
             .add_system(cleanup.in_schedule(OnExit(MenuState::SinglePlayerGame)))
             .add_system(button_system.run_if(in_state(MenuState::SinglePlayerGame)))
+            .add_system(difficulty_system.run_if(in_state(MenuState::SinglePlayerGame)))
             .add_system(map_selected_system.run_if(in_state(MenuState::SinglePlayerGame)));
     }
 }
 
-#[derive(Resource)]
-struct SelectedMap(Option<PathBuf>);
+#[derive(Resource, Default)]
+struct GameSetup {
+    map: Option<PathBuf>,
+    map_seed: Option<u64>,
+    difficulty: Difficulty,
+}
 
 #[derive(Component, Clone, Copy)]
 enum ButtonAction {
     StartGame,
     SelectMap,
+    Difficulty,
+    LoadGame,
 }
 
-fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
-    commands.insert_resource(SelectedMap(None));
+fn setup(mut commands: GuiCommands, menu: Res<Menu>, localize: Res<Localize>) {
+    commands.init_resource::<GameSetup>();
 
     let column_node = commands
         .spawn(NodeBundle {
@@ -55,13 +64,25 @@ fn setup(mut commands: GuiCommands, menu: Res<Menu>) {
         &mut commands,
         column_node,
         ButtonAction::StartGame,
-        "Start Game",
+        &localize.get("singleplayer-start-game"),
     );
     button(
         &mut commands,
         column_node,
         ButtonAction::SelectMap,
-        "Select Map",
+        &localize.get("singleplayer-select-map"),
+    );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::Difficulty,
+        &difficulty_caption(localize.as_ref(), Difficulty::default()),
+    );
+    button(
+        &mut commands,
+        column_node,
+        ButtonAction::LoadGame,
+        &localize.get("singleplayer-load-game"),
     );
 }
 
@@ -84,43 +105,89 @@ fn button(commands: &mut GuiCommands, parent: Entity, action: ButtonAction, capt
     commands.entity(parent).add_child(button);
 }
 
+fn difficulty_caption(localize: &Localize, difficulty: Difficulty) -> String {
+    let difficulty = match difficulty {
+        Difficulty::Easy => localize.get("difficulty-easy"),
+        Difficulty::Normal => localize.get("difficulty-normal"),
+        Difficulty::Hard => localize.get("difficulty-hard"),
+    };
+    localize.format("singleplayer-difficulty", &[("difficulty", &difficulty)])
+}
+
 fn cleanup(mut commands: Commands) {
-    commands.remove_resource::<SelectedMap>();
+    commands.remove_resource::<GameSetup>();
+}
+
+#[derive(SystemParam)]
+struct NextStates<'w> {
+    app: ResMut<'w, NextState<AppState>>,
+    menu: ResMut<'w, NextState<MenuState>>,
 }
 
 fn button_system(
     mut commands: Commands,
     interactions: Query<(&Interaction, &ButtonAction), Changed<Interaction>>,
-    mut next_state: ResMut<NextState<AppState>>,
-    map: Res<SelectedMap>,
+    mut next_states: NextStates,
+    setup: Res<GameSetup>,
     mut map_events: EventWriter<SelectMapEvent>,
     mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
 ) {
     for (&interaction, &action) in interactions.iter() {
         if let Interaction::Clicked = interaction {
             match action {
-                ButtonAction::StartGame => match map.0.as_ref() {
+                ButtonAction::StartGame => match setup.map.as_ref() {
                     Some(path) => {
-                        commands.insert_resource(GameConfig::new(
+                        let config = GameConfig::new(
                             path,
                             Player::Player4,
                             LocalPlayers::new(Player::Player1),
+                        );
+                        commands.insert_resource(match setup.map_seed {
+                            Some(seed) => config.with_map_seed(seed),
+                            None => config,
+                        });
+                        commands.insert_resource(AiConf::new(
+                            setup.difficulty,
+                            PlayerRange::new(Player::Player2, Player::Player4),
                         ));
-                        next_state.set(AppState::InGame);
+                        next_states.app.set(AppState::InGame);
                     }
                     None => {
-                        toasts.send(ToastEvent::new("No map selected."));
+                        toasts.send(ToastEvent::new(localize.get("toast-no-map-selected")));
                     }
                 },
                 ButtonAction::SelectMap => map_events.send(SelectMapEvent),
+                ButtonAction::LoadGame => next_states.menu.set(MenuState::LoadGame),
+                ButtonAction::Difficulty => (),
             };
         }
     }
 }
 
-fn map_selected_system(mut events: EventReader<MapSelectedEvent>, mut map: ResMut<SelectedMap>) {
+fn difficulty_system(
+    mut setup: ResMut<GameSetup>,
+    localize: Res<Localize>,
+    mut buttons: ButtonOps,
+    interactions: Query<(Entity, &Interaction, &ButtonAction), Changed<Interaction>>,
+) {
+    for (entity, &interaction, &action) in interactions.iter() {
+        if let (Interaction::Clicked, ButtonAction::Difficulty) = (interaction, action) {
+            setup.difficulty = setup.difficulty.next();
+            buttons
+                .set_text(
+                    entity,
+                    difficulty_caption(localize.as_ref(), setup.difficulty),
+                )
+                .unwrap();
+        }
+    }
+}
+
+fn map_selected_system(mut events: EventReader<MapSelectedEvent>, mut setup: ResMut<GameSetup>) {
     let Some(event) = events.iter().last() else {
         return;
     };
-    map.0 = Some(event.path().into());
+    setup.map = Some(event.path().into());
+    setup.map_seed = event.seed();
 }
@@ -2,17 +2,25 @@ use bevy::prelude::*;
 use de_core::{
     baseset::GameSet,
     gamestate::GameState,
-    objects::{MovableSolid, ObjectType},
+    objects::{MovableSolid, ObjectType, StaticSolid},
+    projection::ToFlat,
     state::AppState,
 };
+use de_index::SpatialQuery;
 use de_objects::SolidObjects;
+use de_terrain::TerrainCollider;
+use parry3d::{bounding_volume::Aabb, math::Point};
 
 use crate::{
-    movement::DesiredVelocity,
+    movement::{DesiredVelocity, MovementSet},
     repulsion::{RepulsionLables, RepulsionVelocity},
     G_ACCELERATION, MAX_V_ACCELERATION, MAX_V_SPEED,
 };
 
+/// Flying objects climb over buildings which they would reach within this
+/// many seconds at their current horizontal velocity.
+const CLEARANCE_LOOKAHEAD: f32 = 3.;
+
 pub(crate) struct AltitudePlugin;
 
 impl Plugin for AltitudePlugin {
@@ -28,6 +36,12 @@ impl Plugin for AltitudePlugin {
                 .run_if(in_state(GameState::Playing))
                 .in_set(AltitudeSet::Update)
                 .after(RepulsionLables::Apply),
+        )
+        .add_system(
+            conform
+                .in_base_set(GameSet::Movement)
+                .run_if(in_state(GameState::Playing))
+                .after(MovementSet::UpdateTransform),
         );
     }
 }
@@ -59,8 +73,12 @@ fn setup_entities(
     }
 }
 
+/// Flying objects fly over buildings, thus their height is measured from the
+/// top of the buildings below (or ahead of) them if there are any.
 fn update(
     solids: SolidObjects,
+    terrain: TerrainCollider,
+    buildings: SpatialQuery<(&ObjectType, &Transform), With<StaticSolid>>,
     mut objects: Query<(
         &ObjectType,
         &mut DesiredVelocity<RepulsionVelocity>,
@@ -70,13 +88,32 @@ fn update(
 ) {
     objects.par_iter_mut().for_each_mut(
         |(&object_type, mut horizontal, mut climbing, transform)| {
-            let Some(flight) = solids.get(object_type).flight() else {
+            let solid = solids.get(object_type);
+            let Some(flight) = solid.flight() else {
                 return;
             };
-            let height = transform.translation.y;
 
+            let position = transform.translation.to_flat();
+            let ahead = position + CLEARANCE_LOOKAHEAD * horizontal.velocity();
+            let radius = Vec2::splat(solid.ichnography().radius());
+            let mins = position.min(ahead) - radius;
+            let maxs = position.max(ahead) + radius;
+            let region = Aabb::new(
+                Point::new(mins.x, f32::NEG_INFINITY, -maxs.y),
+                Point::new(maxs.x, f32::INFINITY, -mins.y),
+            );
+            let floor = buildings
+                .query_aabb(&region, None)
+                .map(|(&building_type, building_transform)| {
+                    building_transform.translation.y
+                        + solids.get(building_type).collider().aabb().maxs.y
+                })
+                .fold(terrain.elevation(position), f32::max);
+            let height = transform.translation.y - floor;
+
+            // Flying objects stay in the air even while stationary.
             let desired_height = if horizontal.stationary() {
-                0.
+                flight.min_height()
             } else {
                 if height < flight.min_height() {
                     horizontal.stop();
@@ -101,3 +138,24 @@ fn update(
         },
     );
 }
+
+/// Keeps ground units on the surface of the terrain.
+fn conform(
+    solids: SolidObjects,
+    terrain: TerrainCollider,
+    mut objects: Query<(&ObjectType, &mut Transform), With<MovableSolid>>,
+) {
+    objects
+        .par_iter_mut()
+        .for_each_mut(|(&object_type, mut transform)| {
+            if solids.get(object_type).flight().is_some() {
+                return;
+            }
+
+            let elevation = terrain.elevation(transform.translation.to_flat());
+            // Avoid change detection when possible.
+            if transform.translation.y != elevation {
+                transform.translation.y = elevation;
+            }
+        });
+}
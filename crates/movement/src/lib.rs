@@ -6,6 +6,7 @@ mod movement;
 mod obstacles;
 mod pathing;
 mod repulsion;
+mod steering;
 
 use std::f32::consts::PI;
 
@@ -16,6 +17,7 @@ use movement::MovementPlugin;
 use obstacles::ObstaclesPlugin;
 use pathing::PathingPlugin;
 use repulsion::RepulsionPlugin;
+use steering::SteeringPlugin;
 
 /// Maximum object horizontal speed in meters per second.
 const MAX_H_SPEED: f32 = 10.;
@@ -40,6 +42,7 @@ impl PluginGroup for MovementPluginGroup {
             .add(MovementPlugin)
             .add(PathingPlugin)
             .add(ObstaclesPlugin)
+            .add(SteeringPlugin)
             .add(RepulsionPlugin)
             .add(KinematicsPlugin)
             .add(AltitudePlugin)
@@ -88,6 +88,11 @@ impl ObjectVelocity {
         self.heading = heading;
     }
 
+    /// Returns velocity during the current update.
+    pub(crate) fn current(&self) -> Vec3 {
+        self.current
+    }
+
     /// Returns mean velocity over the last frame duration.
     fn frame(&self) -> Vec3 {
         self.current.lerp(self.previous, 0.5)
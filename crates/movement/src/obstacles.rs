@@ -1,8 +1,8 @@
-use bevy::prelude::*;
+use bevy::{ecs::query::ReadOnlyWorldQuery, prelude::*};
 use de_core::{
     baseset::GameSet,
     gamestate::GameState,
-    objects::{MovableSolid, ObjectType, StaticSolid},
+    objects::{Flying, MovableSolid, ObjectType, StaticSolid},
     projection::ToFlat,
 };
 use de_index::SpatialQuery;
@@ -29,14 +29,22 @@ impl Plugin for ObstaclesPlugin {
                 .in_base_set(GameSet::PreMovement)
                 .run_if(in_state(GameState::Playing)),
         )
+        // Flying objects ignore ground obstacles, they avoid only each
+        // other.
         .add_system(
-            update_nearby::<StaticObstacles, StaticSolid>
+            update_nearby::<StaticObstacles, Without<Flying>, With<StaticSolid>>
                 .in_base_set(GameSet::Movement)
                 .run_if(in_state(GameState::Playing))
                 .in_set(ObstaclesLables::UpdateNearby),
         )
         .add_system(
-            update_nearby::<MovableObstacles, MovableSolid>
+            update_nearby::<MovableObstacles, Without<Flying>, GroundMovable>
+                .in_base_set(GameSet::Movement)
+                .run_if(in_state(GameState::Playing))
+                .in_set(ObstaclesLables::UpdateNearby),
+        )
+        .add_system(
+            update_nearby::<MovableObstacles, With<Flying>, AirMovable>
                 .in_base_set(GameSet::Movement)
                 .run_if(in_state(GameState::Playing))
                 .in_set(ObstaclesLables::UpdateNearby),
@@ -53,6 +61,9 @@ pub(crate) struct StaticObstacles;
 
 pub(crate) struct MovableObstacles;
 
+type GroundMovable = (With<MovableSolid>, Without<Flying>);
+type AirMovable = (With<MovableSolid>, With<Flying>);
+
 type Uninitialized<'w, 's> = Query<
     'w,
     's,
@@ -78,11 +89,17 @@ fn update_discs(mut objects: Query<(&Transform, &mut Disc), Changed<Transform>>)
     }
 }
 
-fn update_nearby<M: Send + Sync + 'static, T: Component>(
+/// Caches obstacles (entities matching filter `O`) near objects matching
+/// filter `F`.
+fn update_nearby<M, F, O>(
     time: Res<Time>,
-    mut objects: Query<(Entity, &Transform, &mut DecayingCache<M>)>,
-    space: SpatialQuery<Entity, With<T>>,
-) {
+    mut objects: Query<(Entity, &Transform, &mut DecayingCache<M>), F>,
+    space: SpatialQuery<Entity, O>,
+) where
+    M: Send + Sync + 'static,
+    F: ReadOnlyWorldQuery + Send + Sync + 'static,
+    O: ReadOnlyWorldQuery + Send + Sync + 'static,
+{
     objects
         .par_iter_mut()
         .for_each_mut(|(entity, transform, mut cache)| {
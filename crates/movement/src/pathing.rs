@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use de_core::{baseset::GameSet, gamestate::GameState, projection::ToFlat, state::AppState};
-use de_pathing::ScheduledPath;
+use de_pathing::{PathTarget, ScheduledPath};
 
 use crate::{
     movement::{add_desired_velocity, DesiredVelocity},
@@ -61,17 +61,19 @@ fn follow_path(
     mut objects: Query<(
         &Transform,
         &mut ScheduledPath,
+        Option<&PathTarget>,
         &mut DesiredVelocity<PathVelocity>,
     )>,
 ) {
     objects
         .par_iter_mut()
-        .for_each_mut(|(transform, mut path, mut movement)| {
+        .for_each_mut(|(transform, mut path, target, mut movement)| {
             let location = transform.translation.to_flat();
             let remaining = path.destination().distance(location);
             let advancement = path.advance(location, MAX_H_SPEED * 0.5);
             let direction = (advancement - location).normalize();
-            let desired_speed = MAX_H_SPEED.min((2. * remaining * MAX_H_ACCELERATION).sqrt());
+            let max_speed = MAX_H_SPEED * target.map_or(1., |target| target.speed());
+            let desired_speed = max_speed.min((2. * remaining * MAX_H_ACCELERATION).sqrt());
             movement.update(desired_speed * direction);
         });
 }
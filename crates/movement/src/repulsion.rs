@@ -16,6 +16,7 @@ use crate::{
     movement::{add_desired_velocity, DesiredVelocity},
     obstacles::{MovableObstacles, ObstaclesLables, StaticObstacles},
     pathing::{PathVelocity, PathingSet},
+    steering::{SteeringSet, SteeringVelocity},
     MAX_H_ACCELERATION, MAX_H_SPEED,
 };
 
@@ -66,6 +67,7 @@ impl Plugin for RepulsionPlugin {
                 .in_base_set(GameSet::Movement)
                 .run_if(in_state(GameState::Playing))
                 .in_set(RepulsionLables::Apply)
+                .after(SteeringSet::Steer)
                 .after(RepulsionLables::RepelStatic)
                 .after(RepulsionLables::RepelMovable)
                 .after(RepulsionLables::RepelBounds),
@@ -270,13 +272,13 @@ fn repel_bounds(
 fn apply(
     mut objects: Query<(
         &mut Repulsion,
-        &DesiredVelocity<PathVelocity>,
+        &DesiredVelocity<SteeringVelocity>,
         &mut DesiredVelocity<RepulsionVelocity>,
     )>,
 ) {
     objects.par_iter_mut().for_each_mut(
-        |(mut repulsion, path_velocity, mut repulsion_velocity)| {
-            let velocity = repulsion.apply(path_velocity.velocity());
+        |(mut repulsion, steering_velocity, mut repulsion_velocity)| {
+            let velocity = repulsion.apply(steering_velocity.velocity());
             repulsion_velocity.update(velocity.clamp_length_max(MAX_H_SPEED));
             repulsion.clear();
         },
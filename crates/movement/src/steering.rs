@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, gamestate::GameState, projection::ToFlat, state::AppState};
+
+use crate::{
+    cache::DecayingCache,
+    disc::Disc,
+    movement::{add_desired_velocity, DesiredVelocity, ObjectVelocity},
+    obstacles::{MovableObstacles, ObstaclesLables},
+    pathing::{PathVelocity, PathingSet},
+};
+
+/// Collisions predicted further in the future than this (in seconds) are
+/// ignored.
+const TIME_HORIZON: f32 = 2.;
+/// Objects steer so that they pass each other at least this far apart.
+const MIN_CLEARANCE: f32 = 0.5;
+
+pub(crate) struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            add_desired_velocity::<SteeringVelocity>
+                .in_base_set(GameSet::PreMovement)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_system(
+            steer
+                .in_base_set(GameSet::Movement)
+                .run_if(in_state(GameState::Playing))
+                .in_set(SteeringSet::Steer)
+                .after(ObstaclesLables::UpdateNearby)
+                .after(PathingSet::FollowPath),
+        );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(crate) enum SteeringSet {
+    Steer,
+}
+
+pub(crate) struct SteeringVelocity;
+
+/// A nearby movable object as seen by the steering object.
+struct Neighbour {
+    disc: Disc,
+    velocity: Vec2,
+}
+
+type Steering<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Disc,
+        &'static DesiredVelocity<PathVelocity>,
+        &'static DecayingCache<MovableObstacles>,
+        &'static mut DesiredVelocity<SteeringVelocity>,
+    ),
+>;
+
+/// Adjusts path following velocity so that objects sidestep each other
+/// before they collide. This is a simplified reciprocal velocity obstacle
+/// avoidance: each object of a pair of moving objects takes half of the
+/// responsibility for avoiding the collision.
+fn steer(mut objects: Steering, obstacles: Query<(&Disc, &ObjectVelocity)>) {
+    objects.par_iter_mut().for_each_mut(
+        |(disc, path_velocity, movable_obstacles, mut steering_velocity)| {
+            if path_velocity.stationary() {
+                steering_velocity.stop();
+                return;
+            }
+
+            let neighbours = movable_obstacles.entities().iter().filter_map(|&entity| {
+                obstacles
+                    .get(entity)
+                    .ok()
+                    .map(|(&disc, velocity)| Neighbour {
+                        disc,
+                        velocity: velocity.current().to_flat(),
+                    })
+            });
+            steering_velocity.update(avoid(*disc, path_velocity.velocity(), neighbours));
+        },
+    );
+}
+
+/// Returns `preferred` velocity of an object corrected so that the object
+/// avoids collisions with its neighbours. The returned velocity is never
+/// faster than the preferred one.
+fn avoid<I>(disc: Disc, preferred: Vec2, neighbours: I) -> Vec2
+where
+    I: IntoIterator<Item = Neighbour>,
+{
+    let mut correction = Vec2::ZERO;
+
+    for neighbour in neighbours {
+        let offset = neighbour.disc.center() - disc.center();
+        let relative = preferred - neighbour.velocity;
+        let relative_squared = relative.length_squared();
+        if relative_squared <= f32::EPSILON {
+            continue;
+        }
+
+        // Time of the closest approach of the objects.
+        let time = offset.dot(relative) / relative_squared;
+        if time <= 0. || time > TIME_HORIZON {
+            continue;
+        }
+
+        let clearance = disc.radius() + neighbour.disc.radius() + MIN_CLEARANCE;
+        // Position of the object relative to the neighbour at the time of the
+        // closest approach.
+        let miss = relative * time - offset;
+        let distance = miss.length();
+        if distance >= clearance {
+            continue;
+        }
+
+        // Objects heading straight at each other both turn to their right.
+        let direction = if distance <= f32::EPSILON {
+            offset.perp().normalize()
+        } else {
+            -miss / distance
+        };
+        let share = if neighbour.velocity == Vec2::ZERO {
+            1.
+        } else {
+            0.5
+        };
+        correction -= direction * share * (clearance - distance) / time;
+    }
+
+    (preferred + correction).clamp_length_max(preferred.length())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_avoid() {
+        let disc = Disc::new(Vec2::ZERO, 1.);
+        let preferred = Vec2::new(5., 0.);
+
+        assert_eq!(avoid(disc, preferred, []), preferred);
+
+        // The neighbour is behind.
+        let behind = Neighbour {
+            disc: Disc::new(Vec2::new(-3., 0.), 1.),
+            velocity: Vec2::ZERO,
+        };
+        assert_eq!(avoid(disc, preferred, [behind]), preferred);
+
+        // The neighbour is too far away to matter yet.
+        let far = Neighbour {
+            disc: Disc::new(Vec2::new(30., 0.), 1.),
+            velocity: Vec2::ZERO,
+        };
+        assert_eq!(avoid(disc, preferred, [far]), preferred);
+
+        // The neighbour is passed with enough clearance.
+        let aside = Neighbour {
+            disc: Disc::new(Vec2::new(5., 3.), 1.),
+            velocity: Vec2::ZERO,
+        };
+        assert_eq!(avoid(disc, preferred, [aside]), preferred);
+
+        // A head-on collision, the object turns to its right.
+        let head_on = Neighbour {
+            disc: Disc::new(Vec2::new(5., 0.), 1.),
+            velocity: Vec2::new(-5., 0.),
+        };
+        let velocity = avoid(disc, preferred, [head_on]);
+        assert!(velocity.y < 0.);
+        assert!(velocity.length() <= preferred.length() + 1e-4);
+
+        // The neighbour is slightly to the left, the object sidesteps to the
+        // right.
+        let left = Neighbour {
+            disc: Disc::new(Vec2::new(5., 0.5), 1.),
+            velocity: Vec2::ZERO,
+        };
+        let velocity = avoid(disc, preferred, [left]);
+        assert!(velocity.y < 0.);
+    }
+}
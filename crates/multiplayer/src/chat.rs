@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, events::ResendEventPlugin, gamestate::GameState, player::Player};
+use de_net::{ChatChannel, FromGame, ToGame, MAX_CHAT_LEN};
+
+use crate::{
+    game::Players,
+    messages::{FromGameServerEvent, MessagesSet, ToGameServerEvent},
+    netstate::NetState,
+};
+
+/// Maximum number of messages kept in [`ChatLog`].
+const MAX_LOG_LEN: usize = 100;
+
+pub(crate) struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SendChatEvent>()
+            .add_event::<ChatReceivedEvent>()
+            // Messages received while the game is loading are delivered
+            // once it starts.
+            .add_plugin(ResendEventPlugin::<ChatReceivedEvent>::default())
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                send.in_base_set(GameSet::PostUpdate)
+                    .run_if(on_event::<SendChatEvent>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .in_set(ChatSet::Receive)
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                log_received
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<ChatLog>())
+                    .run_if(not(in_state(GameState::Loading)))
+                    .run_if(on_event::<ChatReceivedEvent>())
+                    .after(ChatSet::Receive),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+enum ChatSet {
+    Receive,
+}
+
+/// Send this event to send a chat message to other players in the game.
+pub struct SendChatEvent {
+    channel: ChatChannel,
+    text: String,
+}
+
+impl SendChatEvent {
+    /// # Panics
+    ///
+    /// Panics if `text` is longer than [`MAX_CHAT_LEN`] bytes.
+    pub fn new(channel: ChatChannel, text: String) -> Self {
+        assert!(text.len() <= MAX_CHAT_LEN);
+        Self { channel, text }
+    }
+}
+
+/// This event is sent when a chat message from another player is received.
+pub struct ChatReceivedEvent(ChatEntry);
+
+impl ChatReceivedEvent {
+    pub fn entry(&self) -> &ChatEntry {
+        &self.0
+    }
+}
+
+/// A single chat message.
+#[derive(Clone)]
+pub struct ChatEntry {
+    from: Player,
+    channel: ChatChannel,
+    text: String,
+}
+
+impl ChatEntry {
+    /// Player who sent the message.
+    pub fn from(&self) -> Player {
+        self.from
+    }
+
+    pub fn channel(&self) -> ChatChannel {
+        self.channel
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.as_str()
+    }
+}
+
+/// Latest chat messages sent or received during the current multiplayer
+/// game, oldest first.
+///
+/// This resource exists once the local client joins a multiplayer game.
+#[derive(Resource, Default)]
+pub struct ChatLog {
+    entries: VecDeque<ChatEntry>,
+}
+
+impl ChatLog {
+    pub fn entries(&self) -> impl Iterator<Item = &ChatEntry> {
+        self.entries.iter()
+    }
+
+    fn push(&mut self, entry: ChatEntry) {
+        if self.entries.len() >= MAX_LOG_LEN {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+fn setup(mut commands: Commands) {
+    // The log is kept when rejoining the game.
+    commands.init_resource::<ChatLog>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ChatLog>();
+}
+
+fn send(
+    players: Option<Res<Players>>,
+    log: Option<ResMut<ChatLog>>,
+    mut inputs: EventReader<SendChatEvent>,
+    mut outputs: EventWriter<ToGameServerEvent<true>>,
+) {
+    let local = players.and_then(|players| players.local());
+    let mut log = log.zip(local);
+
+    for event in inputs.iter() {
+        outputs.send(
+            ToGame::Chat {
+                channel: event.channel,
+                text: event.text.clone(),
+            }
+            .into(),
+        );
+
+        if let Some((log, local)) = log.as_mut() {
+            log.push(ChatEntry {
+                from: *local,
+                channel: event.channel,
+                text: event.text.clone(),
+            });
+        }
+    }
+}
+
+fn receive(
+    mut inputs: EventReader<FromGameServerEvent>,
+    mut outputs: EventWriter<ChatReceivedEvent>,
+) {
+    for event in inputs.iter() {
+        let FromGame::Chat {
+            from,
+            channel,
+            ref text,
+        } = *event.message()
+        else {
+            continue;
+        };
+
+        match Player::try_from(from) {
+            Ok(from) => outputs.send(ChatReceivedEvent(ChatEntry {
+                from,
+                channel,
+                text: text.clone(),
+            })),
+            Err(err) => warn!("Chat message from an invalid player: {err:?}"),
+        }
+    }
+}
+
+fn log_received(mut log: ResMut<ChatLog>, mut events: EventReader<ChatReceivedEvent>) {
+    for event in events.iter() {
+        log.push(event.entry().clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log() {
+        let mut log = ChatLog::default();
+        for i in 0..(MAX_LOG_LEN + 2) {
+            log.push(ChatEntry {
+                from: Player::Player1,
+                channel: ChatChannel::All,
+                text: i.to_string(),
+            });
+        }
+
+        let texts: Vec<&str> = log.entries().map(|entry| entry.text()).collect();
+        assert_eq!(texts.len(), MAX_LOG_LEN);
+        assert_eq!(texts[0], "2");
+        assert_eq!(texts[MAX_LOG_LEN - 1], (MAX_LOG_LEN + 1).to_string());
+    }
+}
@@ -0,0 +1,285 @@
+//! Synchronization of game time among players.
+//!
+//! The game server keeps the authoritative game clock: time elapsed since
+//! the game was opened. Each player periodically asks the server for its
+//! clock and estimates the offset between the local and the server clock
+//! from the response and the round trip time. Samples with the lowest round
+//! trip time are the most accurate, thus the estimate is based on them.
+//!
+//! Small differences between the estimated and the applied offset are
+//! corrected gradually so that game time never jumps noticeably.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use de_core::baseset::GameSet;
+use de_net::{FromGame, ToGame};
+
+use crate::{
+    messages::{FromGameServerEvent, MessagesSet, ToGameServerEvent},
+    netstate::NetState,
+};
+
+/// Time between two consecutive clock synchronization requests.
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of latest clock samples the estimate is based on.
+const MAX_SAMPLES: usize = 16;
+/// Maximum number of unanswered clock synchronization requests.
+const MAX_PENDING: usize = 8;
+/// Maximum rate of gradual clock correction in seconds per second.
+const MAX_SLEW: f64 = 0.05;
+/// Differences larger than this (in seconds) are corrected immediately.
+const STEP_THRESHOLD: f64 = 0.5;
+
+pub(crate) struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                request
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(resource_exists::<ClockSync>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<ClockSync>())
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .in_set(ClockSet::Receive)
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                adjust
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<ClockSync>())
+                    .after(ClockSet::Receive),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+enum ClockSet {
+    Receive,
+}
+
+/// Estimate of game time shared by all players of a multiplayer game. Game
+/// time is the time elapsed on the game server since the game was opened.
+///
+/// This resource exists once the local client joins a multiplayer game.
+#[derive(Resource)]
+pub struct GameClock {
+    base: Instant,
+    /// Game time minus local time (since `base`) in seconds.
+    offset: Option<f64>,
+}
+
+impl GameClock {
+    /// Returns current game time or None if it is not yet known.
+    pub fn now(&self) -> Option<Duration> {
+        self.at(Instant::now())
+    }
+
+    /// Returns game time at a given local time instant or None if it is not
+    /// yet known.
+    pub fn at(&self, instant: Instant) -> Option<Duration> {
+        let offset = self.offset?;
+        let local = instant.saturating_duration_since(self.base).as_secs_f64();
+        Some(Duration::from_secs_f64((local + offset).max(0.)))
+    }
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self {
+            base: Instant::now(),
+            offset: None,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct ClockSync {
+    next_id: u32,
+    last_request: Option<Instant>,
+    pending: VecDeque<(u32, Instant)>,
+    samples: VecDeque<ClockSample>,
+}
+
+struct ClockSample {
+    rtt: Duration,
+    offset: f64,
+}
+
+impl ClockSync {
+    /// Returns ID of a new clock synchronization request or None if it is
+    /// not yet time to send one.
+    fn request(&mut self, now: Instant) -> Option<u32> {
+        if self
+            .last_request
+            .map_or(false, |last| now - last < SYNC_INTERVAL)
+        {
+            return None;
+        }
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        self.last_request = Some(now);
+        if self.pending.len() >= MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back((id, now));
+        Some(id)
+    }
+
+    /// Processes a response to a clock synchronization request.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - local time from which the offset is measured.
+    ///
+    /// * `id` - ID of the request.
+    ///
+    /// * `server_time` - game time reported by the server.
+    ///
+    /// * `received` - time of reception of the response.
+    fn receive(&mut self, base: Instant, id: u32, server_time: Duration, received: Instant) {
+        let Some(index) = self.pending.iter().position(|&(other, _)| other == id) else {
+            return;
+        };
+        let (_, sent) = self.pending.remove(index).unwrap();
+
+        let rtt = received.saturating_duration_since(sent);
+        // The server most likely measured its time half way through the
+        // round trip.
+        let local = received
+            .saturating_duration_since(base)
+            .saturating_sub(rtt / 2)
+            .as_secs_f64();
+
+        if self.samples.len() >= MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ClockSample {
+            rtt,
+            offset: server_time.as_secs_f64() - local,
+        });
+    }
+
+    /// Returns the estimated offset of game time relative to the local time
+    /// or None if no sample was received yet.
+    fn estimate(&self) -> Option<f64> {
+        self.samples
+            .iter()
+            .min_by_key(|sample| sample.rtt)
+            .map(|sample| sample.offset)
+    }
+}
+
+/// Returns the offset to be applied after `elapsed` seconds given the
+/// currently applied offset and the target (estimated) offset.
+fn slew(current: Option<f64>, target: f64, elapsed: f64) -> f64 {
+    let Some(current) = current else {
+        return target;
+    };
+
+    let diff = target - current;
+    if diff.abs() > STEP_THRESHOLD {
+        return target;
+    }
+
+    let max = MAX_SLEW * elapsed;
+    current + diff.clamp(-max, max)
+}
+
+fn setup(mut commands: Commands) {
+    // The clock is kept when rejoining the game.
+    commands.init_resource::<GameClock>();
+    commands.init_resource::<ClockSync>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<GameClock>();
+    commands.remove_resource::<ClockSync>();
+}
+
+fn request(mut sync: ResMut<ClockSync>, mut server: EventWriter<ToGameServerEvent<false>>) {
+    if let Some(id) = sync.request(Instant::now()) {
+        server.send(ToGame::SyncClock(id).into());
+    }
+}
+
+fn receive(
+    clock: Res<GameClock>,
+    mut sync: ResMut<ClockSync>,
+    mut inputs: EventReader<FromGameServerEvent>,
+) {
+    for event in inputs.iter() {
+        if let FromGame::Clock { id, time } = *event.message() {
+            sync.receive(clock.base, id, Duration::from_micros(time), event.time());
+        }
+    }
+}
+
+fn adjust(time: Res<Time>, sync: Res<ClockSync>, mut clock: ResMut<GameClock>) {
+    if let Some(target) = sync.estimate() {
+        clock.offset = Some(slew(clock.offset, target, time.delta_seconds_f64()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync() {
+        let base = Instant::now();
+        let mut sync = ClockSync::default();
+        assert_eq!(sync.estimate(), None);
+
+        let first = sync.request(base).unwrap();
+        assert_eq!(sync.request(base + Duration::from_millis(500)), None);
+        let second = sync.request(base + SYNC_INTERVAL).unwrap();
+        assert_ne!(first, second);
+
+        // Slow round trip.
+        sync.receive(
+            base,
+            first,
+            Duration::from_secs(11),
+            base + Duration::from_millis(1200),
+        );
+        // Redelivered response is ignored.
+        sync.receive(
+            base,
+            first,
+            Duration::from_secs(20),
+            base + Duration::from_millis(1200),
+        );
+        assert!((sync.estimate().unwrap() - 10.4).abs() < 1e-6);
+
+        // Fast round trip.
+        sync.receive(
+            base,
+            second,
+            Duration::from_millis(11050),
+            base + Duration::from_millis(1100),
+        );
+        assert!((sync.estimate().unwrap() - 10.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_slew() {
+        assert_eq!(slew(None, 5., 0.1), 5.);
+        assert_eq!(slew(Some(1.), 5., 0.1), 5.);
+        assert!((slew(Some(1.), 1.1, 0.1) - 1.005).abs() < 1e-9);
+        assert!((slew(Some(1.), 0.9, 0.1) - 0.995).abs() < 1e-9);
+        assert!((slew(Some(1.), 1.001, 0.1) - 1.001).abs() < 1e-9);
+    }
+}
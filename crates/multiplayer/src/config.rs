@@ -1,26 +1,151 @@
 use std::net::IpAddr;
 
 use de_core::player::Player;
+use de_map::hash::MapHash;
+use de_net::{
+    DropPolicy, GameSlots, PasswordHash, ResendPolicy, MAX_AUTH_LEN, MAX_GAME_NAME_LEN,
+    MAX_MAP_NAME_LEN,
+};
 
 pub struct NetGameConf {
     max_players: Player,
+    slots: GameSlots,
     server_host: IpAddr,
     server_port: ServerPort,
+    resend_policy: ResendPolicy,
+    send_rates: SendRates,
+    drop_policy: DropPolicy,
+    spectator: bool,
+    game_name: String,
+    map_name: String,
+    map: Option<MapHash>,
+    auth: Option<String>,
+    password: Option<PasswordHash>,
 }
 
 impl NetGameConf {
     pub fn new(max_players: Player, server_host: IpAddr, server_port: ServerPort) -> Self {
         Self {
             max_players,
+            slots: GameSlots::default(),
             server_host,
             server_port,
+            resend_policy: ResendPolicy::default(),
+            send_rates: SendRates::default(),
+            drop_policy: DropPolicy::default(),
+            spectator: false,
+            game_name: String::new(),
+            map_name: String::new(),
+            map: None,
+            auth: None,
+            password: None,
         }
     }
 
+    /// Sets team and AI slots of the game. This is used only when a new game
+    /// is opened. By default, the game is free for all without AI players.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slots are not valid for the maximum number of players,
+    /// see [`GameSlots::is_valid`].
+    pub fn with_slots(mut self, slots: GameSlots) -> Self {
+        assert!(slots.is_valid(self.max_players.to_num()));
+        self.slots = slots;
+        self
+    }
+
+    /// Sets policy of re-sending of reliable messages.
+    pub fn with_resend_policy(mut self, resend_policy: ResendPolicy) -> Self {
+        self.resend_policy = resend_policy;
+        self
+    }
+
+    /// Sets rates of periodic state updates sent to other players. Lower
+    /// rates save bandwidth on slow connections.
+    pub fn with_send_rates(mut self, send_rates: SendRates) -> Self {
+        self.send_rates = send_rates;
+        self
+    }
+
+    /// Sets handling of objects of players who leave the game after it
+    /// started. Objects are destroyed by default.
+    ///
+    /// Only the policy of the remaining player with the lowest ID is
+    /// applied, see [`de_net::ToPlayers::PlayerDropped`].
+    pub fn with_drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// Joins the game as a spectator. Spectators receive the state of the
+    /// game but cannot command any units.
+    ///
+    /// Only an existing game may be spectated, i.e. the server port must be
+    /// [`ServerPort::Game`].
+    pub fn with_spectator(mut self) -> Self {
+        self.spectator = true;
+        self
+    }
+
+    /// Sets name of the game and name of its map shown in game listings of
+    /// the server. This is used only when a new game is opened.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the names is longer than
+    /// [`MAX_GAME_NAME_LEN`] or [`MAX_MAP_NAME_LEN`]
+    /// respectively.
+    pub fn with_listing(mut self, game_name: String, map_name: String) -> Self {
+        assert!(game_name.len() <= MAX_GAME_NAME_LEN);
+        assert!(map_name.len() <= MAX_MAP_NAME_LEN);
+        self.game_name = game_name;
+        self.map_name = map_name;
+        self
+    }
+
+    /// Sets hash of the map of the game. If the map is not available locally
+    /// after the game is joined, it is downloaded from another player, see
+    /// [`crate::GameReadyEvent`].
+    pub fn with_map(mut self, hash: MapHash) -> Self {
+        self.map = Some(hash);
+        self
+    }
+
+    /// Sets the authentication token issued by DE Lobby. DE Connector
+    /// verifies the token when a game is opened or joined, if it has
+    /// authentication enabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the token is longer than [`MAX_AUTH_LEN`].
+    pub fn with_auth(mut self, token: String) -> Self {
+        assert!(token.len() <= MAX_AUTH_LEN);
+        self.auth = Some(token);
+        self
+    }
+
+    /// Sets the game password. When a new game is opened, other players
+    /// must provide the same password to join it. When an existing game is
+    /// joined, the password must match the one the game was opened with.
+    pub fn with_password(self, password: &str) -> Self {
+        self.with_password_hash(PasswordHash::new(password))
+    }
+
+    /// Sets the already hashed game password, see [`Self::with_password`].
+    pub fn with_password_hash(mut self, password: PasswordHash) -> Self {
+        self.password = Some(password);
+        self
+    }
+
     pub(crate) fn max_players(&self) -> Player {
         self.max_players
     }
 
+    pub(crate) fn slots(&self) -> GameSlots {
+        self.slots
+    }
+
     /// Address of DE Connector server.
     pub(crate) fn server_host(&self) -> IpAddr {
         self.server_host
@@ -29,6 +154,84 @@ impl NetGameConf {
     pub(crate) fn server_port(&self) -> ServerPort {
         self.server_port
     }
+
+    pub(crate) fn resend_policy(&self) -> ResendPolicy {
+        self.resend_policy
+    }
+
+    pub(crate) fn send_rates(&self) -> SendRates {
+        self.send_rates
+    }
+
+    pub(crate) fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    pub(crate) fn spectator(&self) -> bool {
+        self.spectator
+    }
+
+    pub(crate) fn game_name(&self) -> &str {
+        self.game_name.as_str()
+    }
+
+    pub(crate) fn map_name(&self) -> &str {
+        self.map_name.as_str()
+    }
+
+    pub(crate) fn map(&self) -> Option<MapHash> {
+        self.map
+    }
+
+    pub(crate) fn auth(&self) -> Option<&str> {
+        self.auth.as_deref()
+    }
+
+    pub(crate) fn password(&self) -> Option<PasswordHash> {
+        self.password
+    }
+}
+
+/// Rates (in updates per second) of periodic state updates sent to other
+/// players. Event-like messages, e.g. chat messages or player commands, are
+/// always sent immediately.
+#[derive(Clone, Copy, Debug)]
+pub struct SendRates {
+    positions: f32,
+    health: f32,
+}
+
+impl SendRates {
+    /// # Arguments
+    ///
+    /// * `positions` - rate of updates of positions and rotations of
+    ///   replicated objects.
+    ///
+    /// * `health` - rate of updates of health of replicated objects. Health
+    ///   is never sent more often than positions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the rates is not a positive finite number.
+    pub fn new(positions: f32, health: f32) -> Self {
+        assert!(positions.is_finite() && positions > 0.);
+        assert!(health.is_finite() && health > 0.);
+        Self { positions, health }
+    }
+
+    pub fn positions(&self) -> f32 {
+        self.positions
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+}
+
+impl Default for SendRates {
+    fn default() -> Self {
+        Self::new(10., 10.)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -0,0 +1,282 @@
+//! Detection of diverging (desynchronized) simulations.
+//!
+//! Buildings and units are simulated only on the computer of their owner and
+//! replicated to other players (see [`super::replication`]). Every
+//! [`CHECKSUM_INTERVAL`] lockstep ticks, each player hashes the replicated
+//! state of its objects and sends the hash to all other players. The
+//! complete state of the same tick is replicated to each player, who hashes
+//! its replicas of the objects and compares the hash with the one received
+//! from the owner. Hashes are thus compared per player: a mismatch means
+//! that replicas of objects of that player diverged from their owner.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, player::Player};
+use de_net::ToPlayers;
+
+use super::{replication::ReplicationSet, Players};
+use crate::{
+    messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent},
+    netstate::NetState,
+};
+
+/// Number of lockstep ticks between two consecutive state hashes.
+pub(super) const CHECKSUM_INTERVAL: u32 = 10;
+/// Hashes older than this number of ticks (relative to the latest hash) are
+/// forgotten.
+const CHECKSUM_HISTORY: u32 = 20 * CHECKSUM_INTERVAL;
+
+pub(super) struct ChecksumPlugin;
+
+impl Plugin for ChecksumPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DesyncDetectedEvent>()
+            .add_event::<StateHashedEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Checksums>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                compare
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Checksums>())
+                    .run_if(on_event::<StateHashedEvent>())
+                    .after(ReplicationSet::Send)
+                    .before(MessagesSet::SendMessages),
+            );
+    }
+}
+
+/// This event is sent when local replicas of objects of another player
+/// differ from the state of the objects hashed by the player at the same
+/// tick.
+pub struct DesyncDetectedEvent {
+    tick: u32,
+    player: Player,
+    hash: u64,
+    expected: u64,
+}
+
+impl DesyncDetectedEvent {
+    /// The lockstep tick right after which the states were hashed.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// The player whose objects differ from their local replicas.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// The hash received from the other player.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Hash of the local replicas.
+    pub fn expected(&self) -> u64 {
+        self.expected
+    }
+}
+
+/// This event is sent when the replicated state of objects of a player is
+/// hashed, either by the owner (the local player) before it is replicated or
+/// by a player who received it.
+pub(super) struct StateHashedEvent {
+    tick: u32,
+    owner: Player,
+    hash: u64,
+}
+
+impl StateHashedEvent {
+    pub(super) fn new(tick: u32, owner: Player, hash: u64) -> Self {
+        Self { tick, owner, hash }
+    }
+}
+
+#[derive(Resource, Default)]
+struct Checksums {
+    /// Hashes of local replicas of objects of other players keyed by the
+    /// tick and the owner.
+    local: BTreeMap<(u32, Player), u64>,
+    /// Hashes received from the owners of the objects.
+    remote: BTreeMap<(u32, Player), u64>,
+}
+
+impl Checksums {
+    /// Stores hash of local replicas of objects of a player. It returns the
+    /// hash received from the player if it is known and does not match.
+    fn insert_local(&mut self, tick: u32, player: Player, hash: u64) -> Option<u64> {
+        self.forget(tick);
+        match self.remote.remove(&(tick, player)) {
+            Some(remote) => (remote != hash).then_some(remote),
+            None => {
+                self.local.insert((tick, player), hash);
+                None
+            }
+        }
+    }
+
+    /// Stores hash of objects of another player received from the player. It
+    /// returns the local hash of the replicas at the same tick if it is
+    /// known and does not match.
+    fn insert_remote(&mut self, tick: u32, player: Player, hash: u64) -> Option<u64> {
+        self.forget(tick);
+        match self.local.remove(&(tick, player)) {
+            Some(local) => (local != hash).then_some(local),
+            None => {
+                self.remote.insert((tick, player), hash);
+                None
+            }
+        }
+    }
+
+    fn forget(&mut self, tick: u32) {
+        let min_tick = tick.saturating_sub(CHECKSUM_HISTORY);
+        self.local.retain(|&(other, _), _| other >= min_tick);
+        self.remote.retain(|&(other, _), _| other >= min_tick);
+    }
+}
+
+/// 64-bit FNV-1a hash. Unlike the hashers from std, it is guaranteed to
+/// give the same results on all platforms and compiler versions.
+pub(super) struct Fnv(u64);
+
+impl Fnv {
+    pub(super) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(super) fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(super) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Checksums>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Checksums>();
+}
+
+fn compare(
+    players: Res<Players>,
+    mut checksums: ResMut<Checksums>,
+    mut hashes: EventReader<StateHashedEvent>,
+    mut outputs: EventWriter<ToPlayersEvent<true>>,
+    mut desyncs: EventWriter<DesyncDetectedEvent>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in hashes.iter() {
+        if event.owner == local {
+            outputs.send(
+                ToPlayers::Checksum {
+                    player: local.to_num(),
+                    tick: event.tick,
+                    hash: event.hash,
+                }
+                .into(),
+            );
+        } else if let Some(remote) = checksums.insert_local(event.tick, event.owner, event.hash) {
+            warn!(
+                "Replicas of objects of {} diverged at tick {}.",
+                event.owner, event.tick
+            );
+            desyncs.send(DesyncDetectedEvent {
+                tick: event.tick,
+                player: event.owner,
+                hash: remote,
+                expected: event.hash,
+            });
+        }
+    }
+}
+
+fn receive(
+    mut checksums: ResMut<Checksums>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut desyncs: EventWriter<DesyncDetectedEvent>,
+) {
+    for event in inputs.iter() {
+        let ToPlayers::Checksum { player, tick, hash } = *event.message() else {
+            continue;
+        };
+
+        let player = match Player::try_from(player) {
+            Ok(player) => player,
+            Err(err) => {
+                warn!("Checksum from an invalid player: {err:?}");
+                continue;
+            }
+        };
+
+        if let Some(expected) = checksums.insert_remote(tick, player, hash) {
+            warn!("Replicas of objects of {player} diverged at tick {tick}.");
+            desyncs.send(DesyncDetectedEvent {
+                tick,
+                player,
+                hash,
+                expected,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        let mut checksums = Checksums::default();
+
+        assert_eq!(checksums.insert_remote(10, Player::Player2, 7), None);
+        assert_eq!(checksums.insert_remote(10, Player::Player3, 8), None);
+        assert_eq!(checksums.insert_local(10, Player::Player2, 7), None);
+        assert_eq!(checksums.insert_local(10, Player::Player3, 9), Some(8));
+
+        // Hashes are compared per player.
+        assert_eq!(checksums.insert_local(20, Player::Player2, 1), None);
+        assert_eq!(checksums.insert_local(20, Player::Player3, 2), None);
+        assert_eq!(checksums.insert_remote(20, Player::Player3, 2), None);
+        assert_eq!(checksums.insert_remote(20, Player::Player2, 2), Some(1));
+
+        // Old hashes are forgotten.
+        assert_eq!(checksums.insert_local(30, Player::Player2, 1), None);
+        assert_eq!(
+            checksums.insert_remote(30 + CHECKSUM_HISTORY + 1, Player::Player2, 3),
+            None
+        );
+        assert_eq!(checksums.insert_remote(30, Player::Player2, 2), None);
+    }
+
+    #[test]
+    fn test_fnv() {
+        let mut first = Fnv::new();
+        first.write(&[1, 2, 3]);
+        let mut second = Fnv::new();
+        second.write(&[1, 2]);
+        second.write(&[3]);
+        assert_eq!(first.finish(), second.finish());
+
+        second.write(&[0]);
+        assert_ne!(first.finish(), second.finish());
+    }
+}
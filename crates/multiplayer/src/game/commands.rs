@@ -0,0 +1,147 @@
+//! Execution of scheduled commands of the local player.
+//!
+//! Commands of all kinds (e.g. construction or movement) of a player are
+//! concatenated into a single byte sequence per lockstep tick. Buildings and
+//! units are simulated only on the computer of their owner, thus commands of
+//! other players are ignored. The only exception are terrain deformations,
+//! which are executed for all players in the order of the players.
+
+use bevy::prelude::*;
+use de_behaviour::{ExecuteMovementEvent, MovementCommand};
+use de_construction::{ConstructionCommand, ExecuteConstructionEvent};
+use de_core::baseset::GameSet;
+use de_terrain::{ExecuteDeformationEvent, TerrainDeformation};
+use de_transport::{ExecuteTransportEvent, TransportCommand};
+
+use super::{lockstep::LockstepTickEvent, Players};
+
+pub(super) struct CommandsPlugin;
+
+impl Plugin for CommandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            execute
+                .in_base_set(GameSet::PreUpdate)
+                .run_if(resource_exists::<Players>())
+                .run_if(on_event::<LockstepTickEvent>()),
+        );
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Construction(ConstructionCommand),
+    Movement(MovementCommand),
+    Transport(TransportCommand),
+    Deformation(TerrainDeformation),
+}
+
+impl Command {
+    /// Decodes a command of any kind from the beginning of `bytes`. It
+    /// returns the command and the number of consumed bytes or None if the
+    /// bytes do not start with a valid command.
+    ///
+    /// Encoded commands of different kinds are distinguished by their first
+    /// byte.
+    fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        ConstructionCommand::decode(bytes)
+            .map(|(command, len)| (Self::Construction(command), len))
+            .or_else(|| {
+                MovementCommand::decode(bytes).map(|(command, len)| (Self::Movement(command), len))
+            })
+            .or_else(|| {
+                TransportCommand::decode(bytes)
+                    .map(|(command, len)| (Self::Transport(command), len))
+            })
+            .or_else(|| {
+                TerrainDeformation::decode(bytes)
+                    .map(|(deformation, len)| (Self::Deformation(deformation), len))
+            })
+    }
+}
+
+fn execute(
+    players: Res<Players>,
+    mut ticks: EventReader<LockstepTickEvent>,
+    mut construction_events: EventWriter<ExecuteConstructionEvent>,
+    mut movement_events: EventWriter<ExecuteMovementEvent>,
+    mut transport_events: EventWriter<ExecuteTransportEvent>,
+    mut deformation_events: EventWriter<ExecuteDeformationEvent>,
+) {
+    let local = players.local();
+
+    for tick in ticks.iter() {
+        for (player, bytes) in tick.commands() {
+            let is_local = local == Some(*player);
+            let mut bytes = bytes.as_slice();
+
+            while !bytes.is_empty() {
+                let Some((command, len)) = Command::decode(bytes) else {
+                    if is_local {
+                        warn!("Invalid commands in tick {}.", tick.tick());
+                    }
+                    break;
+                };
+                match command {
+                    Command::Construction(command) if is_local => {
+                        construction_events.send(ExecuteConstructionEvent::new(command))
+                    }
+                    Command::Movement(command) if is_local => {
+                        movement_events.send(ExecuteMovementEvent::new(command))
+                    }
+                    Command::Transport(command) if is_local => {
+                        transport_events.send(ExecuteTransportEvent::new(command))
+                    }
+                    Command::Deformation(deformation) => {
+                        deformation_events.send(ExecuteDeformationEvent::new(deformation))
+                    }
+                    _ => (),
+                }
+                bytes = &bytes[len..];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::objects::UnitType;
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let construction = ConstructionCommand::Enqueue {
+            factory: Entity::from_raw(7),
+            unit: UnitType::Attacker,
+        };
+        let movement =
+            MovementCommand::new(Entity::from_raw(8), vec![Vec2::new(1., 2.)]).with_patrol();
+        let deformation = TerrainDeformation::crater(Vec2::new(-3., 4.), 5., 1.);
+        let transport = TransportCommand::Load {
+            unit: Entity::from_raw(9),
+            carrier: Entity::from_raw(10),
+        };
+
+        let mut buf = Vec::new();
+        movement.encode(&mut buf);
+        construction.encode(&mut buf);
+        deformation.encode(&mut buf);
+        transport.encode(&mut buf);
+        movement.encode(&mut buf);
+
+        let mut bytes = buf.as_slice();
+        for expected in [
+            Command::Movement(movement.clone()),
+            Command::Construction(construction),
+            Command::Deformation(deformation),
+            Command::Transport(transport),
+            Command::Movement(movement),
+        ] {
+            let (command, len) = Command::decode(bytes).unwrap();
+            assert_eq!(command, expected);
+            bytes = &bytes[len..];
+        }
+        assert!(bytes.is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+//! Scheduling of construction & manufacturing commands of the local player.
+//!
+//! The commands are scheduled with the lockstep simulation and executed once
+//! the tick they were stamped with is simulated, see [`super::commands`].
+
+use bevy::prelude::*;
+use de_construction::{
+    CancelProductionEvent, ChangeDeliveryLocationEvent, ConstructBuildingEvent,
+    ConstructionCommand, EnqueueAssemblyEvent, ScheduledConstruction,
+};
+use de_core::baseset::GameSet;
+use de_net::MAX_COMMANDS_LEN;
+
+use super::lockstep::{Lockstep, ScheduleCommandsEvent};
+use crate::netstate::NetState;
+
+pub(super) struct ConstructionPlugin;
+
+impl Plugin for ConstructionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.run_if(resource_added::<Lockstep>()))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                schedule
+                    .in_base_set(GameSet::Update)
+                    .run_if(resource_exists::<Lockstep>()),
+            );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(ScheduledConstruction);
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ScheduledConstruction>();
+}
+
+fn schedule(
+    mut enqueue_events: EventReader<EnqueueAssemblyEvent>,
+    mut cancel_events: EventReader<CancelProductionEvent>,
+    mut construct_events: EventReader<ConstructBuildingEvent>,
+    mut location_events: EventReader<ChangeDeliveryLocationEvent>,
+    mut out_events: EventWriter<ScheduleCommandsEvent>,
+) {
+    let commands = enqueue_events
+        .iter()
+        .map(|event| ConstructionCommand::Enqueue {
+            factory: event.factory(),
+            unit: event.unit(),
+        })
+        .chain(
+            cancel_events
+                .iter()
+                .map(|event| ConstructionCommand::Cancel {
+                    factory: event.factory(),
+                }),
+        )
+        .chain(
+            construct_events
+                .iter()
+                .map(|event| ConstructionCommand::Construct {
+                    player: event.player(),
+                    building: event.building(),
+                    position: event.position(),
+                    heading: event.heading(),
+                }),
+        )
+        .chain(
+            location_events
+                .iter()
+                .map(|event| ConstructionCommand::Deliver {
+                    factory: event.factory(),
+                    target: event.target(),
+                }),
+        );
+
+    let mut buf = Vec::new();
+    for command in commands {
+        if buf.len() + ConstructionCommand::MAX_ENCODED_LEN > MAX_COMMANDS_LEN {
+            out_events.send(ScheduleCommandsEvent::new(std::mem::take(&mut buf)));
+        }
+        command.encode(&mut buf);
+    }
+    if !buf.is_empty() {
+        out_events.send(ScheduleCommandsEvent::new(buf));
+    }
+}
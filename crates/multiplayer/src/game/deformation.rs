@@ -0,0 +1,51 @@
+//! Scheduling of terrain deformations caused by the local player.
+//!
+//! Unlike other commands, the deformations are executed on the computers of
+//! all players once the tick they were stamped with is simulated so that the
+//! terrain stays the same everywhere, see [`super::commands`].
+
+use bevy::prelude::*;
+use de_core::baseset::GameSet;
+use de_net::MAX_COMMANDS_LEN;
+use de_terrain::{DeformTerrainEvent, ScheduledDeformation, TerrainDeformation};
+
+use super::lockstep::{Lockstep, ScheduleCommandsEvent};
+use crate::netstate::NetState;
+
+pub(super) struct DeformationPlugin;
+
+impl Plugin for DeformationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.run_if(resource_added::<Lockstep>()))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                schedule
+                    .in_base_set(GameSet::Update)
+                    .run_if(resource_exists::<Lockstep>()),
+            );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(ScheduledDeformation);
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ScheduledDeformation>();
+}
+
+fn schedule(
+    mut events: EventReader<DeformTerrainEvent>,
+    mut out_events: EventWriter<ScheduleCommandsEvent>,
+) {
+    let mut buf = Vec::new();
+    for event in events.iter() {
+        if buf.len() + TerrainDeformation::MAX_ENCODED_LEN > MAX_COMMANDS_LEN {
+            out_events.send(ScheduleCommandsEvent::new(std::mem::take(&mut buf)));
+        }
+        event.deformation().encode(&mut buf);
+    }
+    if !buf.is_empty() {
+        out_events.send(ScheduleCommandsEvent::new(buf));
+    }
+}
@@ -0,0 +1,185 @@
+//! Forming of alliances during a multiplayer game.
+//!
+//! A proposal of an alliance is sent reliably to all players. Every player
+//! keeps track of all proposals and the alliance is formed once both players
+//! propose it to each other, i.e. a proposal to a player who has already
+//! proposed is an acceptance.
+
+use ahash::AHashSet;
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, diplomacy::Diplomacy, player::Player};
+use de_net::ToPlayers;
+
+use super::Players;
+use crate::{
+    messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent},
+    netstate::NetState,
+};
+
+pub(super) struct DiplomacyPlugin;
+
+impl Plugin for DiplomacyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ProposeAllianceEvent>()
+            .add_event::<AllianceProposedEvent>()
+            .add_event::<AllianceFormedEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                propose
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Proposals>())
+                    .run_if(resource_exists::<Diplomacy>())
+                    .run_if(on_event::<ProposeAllianceEvent>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Proposals>())
+                    .run_if(resource_exists::<Diplomacy>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            );
+    }
+}
+
+/// Send this event to propose an alliance to a player or to accept their
+/// proposal.
+pub struct ProposeAllianceEvent(Player);
+
+impl ProposeAllianceEvent {
+    pub fn new(target: Player) -> Self {
+        Self(target)
+    }
+}
+
+/// This event is sent when a player proposes an alliance to another player
+/// and the proposal is not yet accepted.
+pub struct AllianceProposedEvent {
+    player: Player,
+    target: Player,
+}
+
+impl AllianceProposedEvent {
+    /// The proposing player.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    pub fn target(&self) -> Player {
+        self.target
+    }
+}
+
+/// This event is sent when two players become allies, see [`Diplomacy`].
+pub struct AllianceFormedEvent(Player, Player);
+
+impl AllianceFormedEvent {
+    pub fn players(&self) -> (Player, Player) {
+        (self.0, self.1)
+    }
+}
+
+/// Alliance proposals made during the current multiplayer game.
+#[derive(Resource, Default)]
+struct Proposals(AHashSet<(Player, Player)>);
+
+impl Proposals {
+    /// Registers a proposal of `player` to ally with `target`. It returns
+    /// true if `target` has already proposed the alliance to `player`.
+    fn propose(&mut self, player: Player, target: Player) -> bool {
+        self.0.insert((player, target));
+        self.0.contains(&(target, player))
+    }
+}
+
+fn setup(mut commands: Commands) {
+    // Proposals are kept when rejoining the game.
+    commands.init_resource::<Proposals>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Proposals>();
+}
+
+fn propose(
+    players: Res<Players>,
+    mut proposals: ResMut<Proposals>,
+    mut diplomacy: ResMut<Diplomacy>,
+    mut events: EventReader<ProposeAllianceEvent>,
+    mut outputs: EventWriter<ToPlayersEvent<true>>,
+    mut formed: EventWriter<AllianceFormedEvent>,
+) {
+    let Some(local) = players.local().filter(|_| players.is_controlling()) else {
+        warn!("Cannot propose an alliance, no player is controlled.");
+        return;
+    };
+
+    for event in events.iter() {
+        let target = event.0;
+        if diplomacy.are_allies(local, target) {
+            continue;
+        }
+
+        info!("Proposing an alliance to {target}.");
+        outputs.send(
+            ToPlayers::ProposeAlliance {
+                player: local.to_num(),
+                target: target.to_num(),
+            }
+            .into(),
+        );
+
+        if proposals.propose(local, target) {
+            info!("Allied with {target}.");
+            diplomacy.ally(local, target);
+            formed.send(AllianceFormedEvent(local, target));
+        }
+    }
+}
+
+fn receive(
+    mut proposals: ResMut<Proposals>,
+    mut diplomacy: ResMut<Diplomacy>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut proposed: EventWriter<AllianceProposedEvent>,
+    mut formed: EventWriter<AllianceFormedEvent>,
+) {
+    for event in inputs.iter() {
+        let ToPlayers::ProposeAlliance { player, target } = *event.message() else {
+            continue;
+        };
+        let (Ok(player), Ok(target)) = (Player::try_from(player), Player::try_from(target)) else {
+            warn!("Alliance proposal of an invalid player received.");
+            continue;
+        };
+        if player == target || diplomacy.are_allies(player, target) {
+            continue;
+        }
+
+        if proposals.propose(player, target) {
+            info!("{player} and {target} formed an alliance.");
+            diplomacy.ally(player, target);
+            formed.send(AllianceFormedEvent(player, target));
+        } else {
+            proposed.send(AllianceProposedEvent { player, target });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proposals() {
+        let mut proposals = Proposals::default();
+        assert!(!proposals.propose(Player::Player1, Player::Player2));
+        // Redelivered proposal.
+        assert!(!proposals.propose(Player::Player1, Player::Player2));
+        assert!(!proposals.propose(Player::Player3, Player::Player1));
+        assert!(proposals.propose(Player::Player2, Player::Player1));
+        assert!(proposals.propose(Player::Player1, Player::Player3));
+    }
+}
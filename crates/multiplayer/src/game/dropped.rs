@@ -0,0 +1,345 @@
+//! Handling of objects of players who left an already started game.
+//!
+//! Once a player leaves, the remaining player with the lowest ID decides,
+//! based on its [`DropPolicy`], what happens to objects of the player and
+//! informs all other players. This way, all players handle the objects in
+//! the same way even if their policies differ. Decisions of other players
+//! are ignored.
+//!
+//! With [`DropPolicy::AiTakeover`], the objects are handed over to the AI of
+//! the deciding player (see [`AiTakeoverEvent`]), who simulates and
+//! replicates them from then on.
+
+use ahash::AHashSet;
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_ai::AiTakeoverEvent;
+use de_core::{baseset::GameSet, objects::Active, player::Player};
+use de_net::{DropPolicy, ToPlayers};
+use de_objects::Health;
+
+use super::{
+    lockstep::{Lockstep, LockstepSet},
+    DefeatedPlayers, PlayerLeftEvent, Players,
+};
+use crate::{
+    lifecycle::NetGameConfRes,
+    messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent},
+    netstate::NetState,
+};
+
+pub(super) struct DroppedPlugin;
+
+impl Plugin for DroppedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PlayerDroppedEvent>()
+            .add_event::<AiTakeoverEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                decide
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Dropped>())
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(on_event::<PlayerLeftEvent>())
+                    .in_set(DroppedSet::Decide)
+                    .after(LockstepSet::TrackPlayers),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Dropped>())
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .in_set(DroppedSet::Receive)
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                apply
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(on_event::<PlayerDroppedEvent>())
+                    .after(DroppedSet::Decide)
+                    .after(DroppedSet::Receive),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(super) enum DroppedSet {
+    Decide,
+    Receive,
+}
+
+/// This event is sent when objects of a player who left the game are
+/// handled. With [`DropPolicy::AiTakeover`], an AI controller of the
+/// [`Self::host`] player takes over the objects of [`Self::player`].
+pub struct PlayerDroppedEvent {
+    player: Player,
+    host: Player,
+    policy: DropPolicy,
+}
+
+impl PlayerDroppedEvent {
+    /// The player who left the game.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// The player who decided on the handling of the objects.
+    pub fn host(&self) -> Player {
+        self.host
+    }
+
+    pub fn policy(&self) -> DropPolicy {
+        self.policy
+    }
+}
+
+/// Players whose objects were already handled.
+#[derive(Resource, Default)]
+struct Dropped(AHashSet<Player>);
+
+impl Dropped {
+    /// Marks a player as handled. It returns false if the player was
+    /// already handled.
+    fn insert(&mut self, player: Player) -> bool {
+        self.0.insert(player)
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Dropped>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Dropped>();
+}
+
+/// Returns the player deciding on the handling of objects of a player who
+/// left the game: the remaining player with the lowest ID.
+///
+/// # Arguments
+///
+/// * `players` - players participating in the game. The dropped player is
+///   ignored, thus it may be included if not yet removed.
+///
+/// * `dropped` - the player who left the game.
+fn decider(players: impl IntoIterator<Item = Player>, dropped: Player) -> Option<Player> {
+    players
+        .into_iter()
+        .filter(|&player| player != dropped)
+        .min()
+}
+
+#[derive(SystemParam)]
+struct DropOutputs<'w> {
+    players: EventWriter<'w, ToPlayersEvent<true>>,
+    drops: EventWriter<'w, PlayerDroppedEvent>,
+}
+
+fn decide(
+    conf: Res<NetGameConfRes>,
+    players: Res<Players>,
+    lockstep: Res<Lockstep>,
+    defeated: Res<DefeatedPlayers>,
+    mut dropped: ResMut<Dropped>,
+    mut events: EventReader<PlayerLeftEvent>,
+    mut outputs: DropOutputs,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    let policy = conf.drop_policy();
+    for event in events.iter() {
+        let player = event.player();
+        if decider(lockstep.players(), player) != Some(local) {
+            continue;
+        }
+
+        // Objects of defeated players are already destroyed.
+        if defeated.contains(player) || !dropped.insert(player) {
+            continue;
+        }
+
+        info!("Handling objects of dropped {player} with {policy:?}.");
+        outputs.players.send(
+            ToPlayers::PlayerDropped {
+                player: local.to_num(),
+                dropped: player.to_num(),
+                policy,
+            }
+            .into(),
+        );
+        outputs.drops.send(PlayerDroppedEvent {
+            player,
+            host: local,
+            policy,
+        });
+    }
+}
+
+fn receive(
+    lockstep: Res<Lockstep>,
+    mut dropped: ResMut<Dropped>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut drops: EventWriter<PlayerDroppedEvent>,
+) {
+    for event in inputs.iter() {
+        let ToPlayers::PlayerDropped {
+            player,
+            dropped: dropped_player,
+            policy,
+        } = *event.message()
+        else {
+            continue;
+        };
+
+        let event = match validate(lockstep.players(), player, dropped_player, policy) {
+            Ok(event) => event,
+            Err(err) => {
+                warn!("Invalid dropped player message received: {err}");
+                continue;
+            }
+        };
+
+        if dropped.insert(event.player) {
+            info!(
+                "{} handles objects of dropped {} with {policy:?}.",
+                event.host, event.player
+            );
+            drops.send(event);
+        }
+    }
+}
+
+/// Validates a decision of player `host` on objects of player `dropped`
+/// received from another player.
+fn validate(
+    players: impl IntoIterator<Item = Player>,
+    host: u8,
+    dropped: u8,
+    policy: DropPolicy,
+) -> Result<PlayerDroppedEvent, String> {
+    let host = Player::try_from(host).map_err(|err| format!("invalid player: {err:?}"))?;
+    let player = Player::try_from(dropped).map_err(|err| format!("invalid player: {err:?}"))?;
+
+    // The sender (host) was verified by the game server.
+    if decider(players, player) != Some(host) {
+        return Err(format!(
+            "{host} is not allowed to decide on objects of {player}"
+        ));
+    }
+
+    Ok(PlayerDroppedEvent {
+        player,
+        host,
+        policy,
+    })
+}
+
+fn apply(
+    players: Res<Players>,
+    mut events: EventReader<PlayerDroppedEvent>,
+    mut objects: Query<(&Player, &mut Health), With<Active>>,
+    mut takeovers: EventWriter<AiTakeoverEvent>,
+) {
+    for event in events.iter() {
+        match event.policy() {
+            DropPolicy::Destroy => {
+                for (&owner, mut health) in objects.iter_mut() {
+                    if owner == event.player() {
+                        health.hit(f32::INFINITY);
+                    }
+                }
+            }
+            DropPolicy::AiTakeover => {
+                if players.local() == Some(event.host()) {
+                    takeovers.send(AiTakeoverEvent::new(event.player()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::player::Teams;
+    use de_net::Capabilities;
+
+    use super::*;
+
+    #[test]
+    fn test_decider() {
+        assert_eq!(decider([], Player::Player1), None);
+        assert_eq!(decider([Player::Player1], Player::Player1), None);
+        assert_eq!(
+            decider(
+                [Player::Player3, Player::Player2, Player::Player4],
+                Player::Player1
+            ),
+            Some(Player::Player2)
+        );
+        // The dropped player may not yet be removed from the players.
+        assert_eq!(
+            decider(
+                [Player::Player1, Player::Player3, Player::Player2],
+                Player::Player1
+            ),
+            Some(Player::Player2)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let players = [Player::Player1, Player::Player2, Player::Player3];
+
+        let event = validate(players, 2, 1, DropPolicy::AiTakeover).unwrap();
+        assert_eq!(event.player(), Player::Player1);
+        assert_eq!(event.host(), Player::Player2);
+        assert_eq!(event.policy(), DropPolicy::AiTakeover);
+
+        // Player 3 is not the decider.
+        assert!(validate(players, 3, 1, DropPolicy::Destroy).is_err());
+        // The dropped player cannot decide on itself.
+        assert!(validate(players, 1, 1, DropPolicy::Destroy).is_err());
+        assert!(validate(players, 0, 1, DropPolicy::Destroy).is_err());
+        assert!(validate(players, 2, 9, DropPolicy::Destroy).is_err());
+    }
+
+    #[test]
+    fn test_takeover() {
+        let mut app = App::new();
+        app.add_event::<PlayerDroppedEvent>()
+            .add_event::<AiTakeoverEvent>()
+            .insert_resource(Players {
+                local: Some(Player::Player2),
+                token: None,
+                capabilities: Capabilities::empty(),
+                spectator: false,
+                teams: Teams::default(),
+            })
+            .add_system(apply);
+
+        let mut drops = app.world.resource_mut::<Events<PlayerDroppedEvent>>();
+        for (player, host) in [
+            (Player::Player1, Player::Player2),
+            (Player::Player4, Player::Player3),
+        ] {
+            drops.send(PlayerDroppedEvent {
+                player,
+                host,
+                policy: DropPolicy::AiTakeover,
+            });
+        }
+        app.update();
+
+        let takeovers = app.world.resource::<Events<AiTakeoverEvent>>();
+        let players: Vec<Player> = takeovers
+            .get_reader()
+            .iter(takeovers)
+            .map(|event| event.player())
+            .collect();
+        // Objects are taken over only by the AI of the deciding player.
+        assert_eq!(players, vec![Player::Player1]);
+    }
+}
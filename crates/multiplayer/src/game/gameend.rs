@@ -0,0 +1,80 @@
+//! Synchronization of the game end.
+//!
+//! Each client detects the game end on its own. The first client to do so
+//! sends the end, including the winner, reliably to all players so that all
+//! clients finish the game with the same result.
+
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet,
+    player::Player,
+    victory::{GameEndedEvent, VictorySet},
+};
+use de_net::ToPlayers;
+
+use super::Players;
+use crate::messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent};
+
+pub(super) struct GameEndPlugin;
+
+impl Plugin for GameEndPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            send.in_base_set(GameSet::PostUpdate)
+                .run_if(resource_exists::<Players>())
+                .run_if(on_event::<GameEndedEvent>())
+                .after(VictorySet::Detect)
+                .before(MessagesSet::SendMessages),
+        )
+        .add_system(
+            receive
+                .in_base_set(GameSet::PreMovement)
+                .run_if(resource_exists::<Players>())
+                .run_if(on_event::<FromPlayersEvent>())
+                .after(MessagesSet::RecvMessages),
+        );
+    }
+}
+
+fn send(
+    players: Res<Players>,
+    mut events: EventReader<GameEndedEvent>,
+    mut outputs: EventWriter<ToPlayersEvent<true>>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    // Game ends received from other players are not sent back.
+    let Some(event) = events.iter().find(|event| !event.is_received()) else {
+        return;
+    };
+
+    info!("Sending game end to other players.");
+    outputs.send(
+        ToPlayers::GameEnded {
+            player: local.to_num(),
+            winner: event.winner().map(Player::to_num),
+        }
+        .into(),
+    );
+}
+
+fn receive(mut inputs: EventReader<FromPlayersEvent>, mut outputs: EventWriter<GameEndedEvent>) {
+    for event in inputs.iter() {
+        let ToPlayers::GameEnded { player, winner } = *event.message() else {
+            continue;
+        };
+
+        let winner = match winner.map(Player::try_from).transpose() {
+            Ok(winner) => winner,
+            Err(err) => {
+                warn!("Game end with an invalid winner received from player {player}: {err:?}");
+                continue;
+            }
+        };
+
+        info!("Game end received from player {player}.");
+        outputs.send(GameEndedEvent::received(winner));
+    }
+}
@@ -0,0 +1,175 @@
+//! Smoothing of transforms of remotely simulated objects.
+//!
+//! Transforms of remote objects arrive at network rate, i.e. much less
+//! often than frames are rendered. Objects with [`InterpolationBuffer`] are
+//! rendered slightly in the past ([`INTERPOLATION_DELAY`]) so that their
+//! transforms can be interpolated between two received updates. When
+//! updates stop arriving, the objects keep moving along their last known
+//! velocity for at most [`MAX_EXTRAPOLATION`].
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, state::AppState};
+
+/// Remote objects are rendered this much in the past.
+const INTERPOLATION_DELAY: Duration = Duration::from_millis(150);
+/// Maximum time for which objects are moved past their latest received
+/// transform.
+const MAX_EXTRAPOLATION: Duration = Duration::from_millis(250);
+/// Maximum number of buffered transform updates of a single object.
+const MAX_SAMPLES: usize = 16;
+
+pub(super) struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            interpolate
+                .in_base_set(GameSet::Movement)
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+/// Buffer of transforms of a remotely simulated object. Transform of an
+/// entity with this component is driven solely by the buffered updates.
+#[derive(Component, Default)]
+pub struct InterpolationBuffer {
+    samples: VecDeque<Sample>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Sample {
+    time: Duration,
+    translation: Vec3,
+    rotation: Quat,
+}
+
+impl InterpolationBuffer {
+    /// Stores a received transform of the object.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - time of the update since app startup (as given by
+    ///   [`Time::elapsed`]). Updates older than the latest stored update
+    ///   are ignored.
+    ///
+    /// * `translation` - position of the object.
+    ///
+    /// * `rotation` - rotation of the object.
+    pub fn push(&mut self, time: Duration, translation: Vec3, rotation: Quat) {
+        if self.samples.back().map_or(false, |last| last.time >= time) {
+            return;
+        }
+
+        if self.samples.len() >= MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            time,
+            translation,
+            rotation,
+        });
+    }
+
+    /// Forgets updates which are no longer needed for interpolation at
+    /// `time` or later.
+    fn prune(&mut self, time: Duration) {
+        while self.samples.len() > 2 && self.samples[1].time <= time {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns interpolated or extrapolated translation and rotation of the
+    /// object at `time` or None if no update was received yet.
+    fn sample(&self, time: Duration) -> Option<(Vec3, Quat)> {
+        let last = self.samples.back()?;
+
+        if time >= last.time {
+            let Some(previous) = self.samples.iter().nth_back(1) else {
+                return Some((last.translation, last.rotation));
+            };
+
+            let velocity = (last.translation - previous.translation)
+                / (last.time - previous.time).as_secs_f32();
+            let ahead = (time - last.time).min(MAX_EXTRAPOLATION).as_secs_f32();
+            return Some((last.translation + ahead * velocity, last.rotation));
+        }
+
+        let next_index = self.samples.partition_point(|sample| sample.time <= time);
+        if next_index == 0 {
+            let first = self.samples.front().unwrap();
+            return Some((first.translation, first.rotation));
+        }
+
+        let previous = self.samples[next_index - 1];
+        let next = self.samples[next_index];
+        let fraction =
+            (time - previous.time).as_secs_f32() / (next.time - previous.time).as_secs_f32();
+        Some((
+            previous.translation.lerp(next.translation, fraction),
+            previous.rotation.slerp(next.rotation, fraction),
+        ))
+    }
+}
+
+fn interpolate(time: Res<Time>, mut objects: Query<(&mut InterpolationBuffer, &mut Transform)>) {
+    let Some(render_time) = time.elapsed().checked_sub(INTERPOLATION_DELAY) else {
+        return;
+    };
+
+    for (mut buffer, mut transform) in objects.iter_mut() {
+        buffer.prune(render_time);
+        if let Some((translation, rotation)) = buffer.sample(render_time) {
+            transform.translation = translation;
+            transform.rotation = rotation;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut buffer = InterpolationBuffer::default();
+        assert_eq!(buffer.sample(ms(0)), None);
+
+        buffer.push(ms(100), Vec3::ZERO, Quat::IDENTITY);
+        assert_eq!(buffer.sample(ms(50)), Some((Vec3::ZERO, Quat::IDENTITY)));
+        assert_eq!(buffer.sample(ms(150)), Some((Vec3::ZERO, Quat::IDENTITY)));
+
+        buffer.push(ms(200), Vec3::new(2., 0., -4.), Quat::IDENTITY);
+        // Out of order updates are ignored.
+        buffer.push(ms(150), Vec3::ONE, Quat::IDENTITY);
+
+        let (translation, _) = buffer.sample(ms(150)).unwrap();
+        assert!(translation.abs_diff_eq(Vec3::new(1., 0., -2.), 1e-5));
+
+        // Extrapolation is limited.
+        let (translation, _) = buffer.sample(ms(300)).unwrap();
+        assert!(translation.abs_diff_eq(Vec3::new(4., 0., -8.), 1e-5));
+        let (translation, _) = buffer.sample(ms(1000)).unwrap();
+        assert!(translation.abs_diff_eq(Vec3::new(7., 0., -14.), 1e-5));
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut buffer = InterpolationBuffer::default();
+        for i in 0..20 {
+            buffer.push(ms(i * 100), Vec3::splat(i as f32), Quat::IDENTITY);
+        }
+        assert_eq!(buffer.samples.len(), MAX_SAMPLES);
+
+        buffer.prune(ms(1850));
+        assert_eq!(buffer.samples.len(), 2);
+        let (translation, _) = buffer.sample(ms(1850)).unwrap();
+        assert!(translation.abs_diff_eq(Vec3::splat(18.5), 1e-5));
+    }
+}
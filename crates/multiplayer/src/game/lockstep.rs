@@ -0,0 +1,550 @@
+//! Deterministic lockstep simulation.
+//!
+//! The simulation is split into ticks of a fixed duration. Commands issued
+//! by the local player are stamped with a tick [`INPUT_DELAY`] ticks in the
+//! future and sent to all other players. A tick is simulated only once inputs
+//! of all players for the tick have arrived, thus all players simulate the
+//! same ticks with the same commands.
+//!
+//! Player packages are delivered reliably but possibly out of order. Inputs
+//! are therefore buffered and ordered by their ticks.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    time::Duration,
+};
+
+use ahash::{AHashMap, AHashSet};
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, player::Player};
+use de_net::{FromGame, ToPlayers, MAX_COMMANDS_LEN};
+
+use super::Players;
+use crate::{
+    lifecycle::FatalErrorEvent,
+    messages::{FromGameServerEvent, FromPlayersEvent, MessagesSet, ToPlayersEvent},
+    netstate::NetState,
+};
+
+/// Duration of a single simulation tick.
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+/// Number of ticks between the tick at which commands are issued and the
+/// tick at which they are executed. This gives the commands time to reach
+/// all other players.
+const INPUT_DELAY: u32 = 3;
+/// Maximum number of ticks inputs of other players may be ahead of the
+/// local simulation.
+const MAX_AHEAD: u32 = 64;
+
+pub(super) struct LockstepPlugin;
+
+impl Plugin for LockstepPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ScheduleCommandsEvent>()
+            .add_event::<LockstepTickEvent>()
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                track_players
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .in_set(LockstepSet::TrackPlayers)
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .in_set(LockstepSet::Receive)
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                advance
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Lockstep>())
+                    .after(LockstepSet::TrackPlayers)
+                    .after(LockstepSet::Receive),
+            )
+            .add_system(
+                schedule
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Lockstep>())
+                    .before(MessagesSet::SendMessages),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(super) enum LockstepSet {
+    TrackPlayers,
+    Receive,
+}
+
+/// Send this event to schedule commands of the local player for execution
+/// in a future simulation tick. See [`LockstepTickEvent`].
+///
+/// The commands are opaque to the multiplayer functionality. Commands of
+/// multiple events scheduled for the same tick are concatenated, thus they
+/// must be self-delimiting.
+pub struct ScheduleCommandsEvent(Vec<u8>);
+
+impl ScheduleCommandsEvent {
+    /// # Panics
+    ///
+    /// Panics if `commands` is longer than [`MAX_COMMANDS_LEN`] bytes.
+    pub fn new(commands: Vec<u8>) -> Self {
+        assert!(commands.len() <= MAX_COMMANDS_LEN);
+        Self(commands)
+    }
+}
+
+/// This event is sent when the simulation may advance by a single tick. It
+/// is sent only once commands of all players for the tick are known.
+pub struct LockstepTickEvent {
+    tick: u32,
+    commands: Vec<(Player, Vec<u8>)>,
+}
+
+impl LockstepTickEvent {
+    /// Number of the tick. Ticks are numbered from 0 and the events are sent
+    /// in order.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Commands of all players scheduled for the tick sorted by the player.
+    /// Players without any commands are omitted.
+    pub fn commands(&self) -> &[(Player, Vec<u8>)] {
+        self.commands.as_slice()
+    }
+}
+
+#[derive(Resource)]
+struct LockstepTimer(Timer);
+
+#[derive(Resource)]
+pub(super) struct Lockstep {
+    local: Player,
+    players: AHashSet<Player>,
+    /// Players who left the simulation.
+    left: AHashSet<Player>,
+    /// Next tick to be simulated.
+    next_tick: u32,
+    /// Next tick to be stamped on local commands.
+    next_scheduled: u32,
+    /// Local commands not yet scheduled.
+    pending: VecDeque<Vec<u8>>,
+    inputs: BTreeMap<u32, AHashMap<Player, Vec<u8>>>,
+    /// The simulation is halted right before this tick.
+    pause: Option<u32>,
+}
+
+impl Lockstep {
+    /// # Arguments
+    ///
+    /// * `local` - the player controlled from this computer.
+    ///
+    /// * `others` - all other players participating in the simulation.
+    fn new(local: Player, others: impl IntoIterator<Item = Player>) -> Self {
+        let mut players: AHashSet<Player> = others.into_iter().collect();
+        players.insert(local);
+
+        Self {
+            local,
+            players,
+            left: AHashSet::new(),
+            next_tick: 0,
+            next_scheduled: INPUT_DELAY,
+            pending: VecDeque::new(),
+            inputs: BTreeMap::new(),
+            pause: None,
+        }
+    }
+
+    /// Returns the local player.
+    pub(super) fn local(&self) -> Player {
+        self.local
+    }
+
+    /// Returns all players participating in the simulation, including the
+    /// local player.
+    pub(super) fn players(&self) -> impl Iterator<Item = Player> + '_ {
+        self.players.iter().copied()
+    }
+
+    /// Returns the next tick to be simulated.
+    pub(super) fn next_tick(&self) -> u32 {
+        self.next_tick
+    }
+
+    /// Returns the next tick to be stamped on local commands. No player has
+    /// simulated this tick yet.
+    pub(super) fn next_scheduled(&self) -> u32 {
+        self.next_scheduled
+    }
+
+    /// Halts the simulation right before a given tick (or right before the
+    /// next tick if the given tick was already simulated).
+    pub(super) fn pause_at(&mut self, tick: u32) {
+        self.pause = Some(tick.max(self.next_tick));
+    }
+
+    /// Continues a halted simulation.
+    pub(super) fn resume(&mut self) {
+        self.pause = None;
+    }
+
+    /// Returns true if the simulation reached the pause tick. No local
+    /// commands are scheduled while halted.
+    pub(super) fn is_halted(&self) -> bool {
+        self.pause.map_or(false, |pause| self.next_tick >= pause)
+    }
+
+    /// Queues local commands to be scheduled with [`Self::schedule`].
+    fn push(&mut self, commands: Vec<u8>) {
+        self.pending.push_back(commands);
+    }
+
+    /// Stamps (a size limited part of) queued local commands with the next
+    /// tick. It returns the tick and the commands which must be sent to all
+    /// other players.
+    fn schedule(&mut self) -> (u32, Vec<u8>) {
+        let mut commands = Vec::new();
+        while let Some(next) = self.pending.front() {
+            if commands.len() + next.len() > MAX_COMMANDS_LEN {
+                break;
+            }
+            commands.extend(self.pending.pop_front().unwrap());
+        }
+
+        let tick = self.next_scheduled;
+        self.next_scheduled += 1;
+        self.inputs
+            .entry(tick)
+            .or_default()
+            .insert(self.local, commands.clone());
+        (tick, commands)
+    }
+
+    /// Stores commands of another player. Redelivered commands are ignored.
+    fn receive(&mut self, player: Player, tick: u32, commands: Vec<u8>) -> Result<(), InputError> {
+        if self.left.contains(&player) {
+            return Err(InputError::Left);
+        }
+        if player == self.local || !self.players.contains(&player) {
+            return Err(InputError::UnknownPlayer);
+        }
+        if tick < self.next_tick {
+            return Err(InputError::Late);
+        }
+        if tick >= self.next_tick + MAX_AHEAD {
+            return Err(InputError::TooEarly);
+        }
+
+        self.inputs
+            .entry(tick)
+            .or_default()
+            .entry(player)
+            .or_insert(commands);
+        Ok(())
+    }
+
+    /// Stops waiting for inputs of a player, e.g. after the player left the
+    /// game.
+    fn remove(&mut self, player: Player) {
+        if self.players.remove(&player) {
+            self.left.insert(player);
+        }
+    }
+
+    /// Returns all commands of the next tick or None if the tick may not be
+    /// simulated yet.
+    ///
+    /// The first [`INPUT_DELAY`] ticks have no commands. The simulation is
+    /// never ahead of the locally scheduled ticks by more than
+    /// [`INPUT_DELAY`], thus it is paced by [`Self::schedule`].
+    fn advance(&mut self) -> Option<LockstepTickEvent> {
+        if self.is_halted() || self.next_tick + INPUT_DELAY > self.next_scheduled {
+            return None;
+        }
+
+        let tick = self.next_tick;
+        let commands = if tick < INPUT_DELAY {
+            Vec::new()
+        } else {
+            let inputs = self.inputs.get(&tick)?;
+            if !self
+                .players
+                .iter()
+                .all(|player| inputs.contains_key(player))
+            {
+                return None;
+            }
+
+            let mut commands: Vec<(Player, Vec<u8>)> = self
+                .inputs
+                .remove(&tick)
+                .unwrap()
+                .into_iter()
+                .filter(|(player, commands)| self.players.contains(player) && !commands.is_empty())
+                .collect();
+            commands.sort_unstable_by_key(|&(player, _)| player);
+            commands
+        };
+
+        self.next_tick += 1;
+        Some(LockstepTickEvent { tick, commands })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum InputError {
+    /// The commands were sent by an unknown player.
+    UnknownPlayer,
+    /// The commands were sent by a player who already left the simulation.
+    /// They might have been sent before the player left.
+    Left,
+    /// The commands are for an already simulated tick.
+    Late,
+    /// The commands are for a tick too far in the future.
+    TooEarly,
+}
+
+impl InputError {
+    /// Returns true if the error is a protocol violation which makes it
+    /// impossible to continue the simulation. Other errors may be caused by
+    /// delayed delivery of otherwise valid commands.
+    fn is_fatal(&self) -> bool {
+        match self {
+            Self::UnknownPlayer | Self::TooEarly => true,
+            Self::Left | Self::Late => false,
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Lockstep>();
+    commands.remove_resource::<LockstepTimer>();
+}
+
+/// Starts the lockstep simulation once the game starts and keeps track of
+/// the players participating in it.
+fn track_players(
+    mut commands: Commands,
+    players: Res<Players>,
+    mut lockstep: Option<ResMut<Lockstep>>,
+    mut lobby: Local<Vec<Player>>,
+    mut inputs: EventReader<FromGameServerEvent>,
+) {
+    for event in inputs.iter() {
+        match event.message() {
+            FromGame::LobbyState(state) => {
+                lobby.clear();
+                lobby.extend(
+                    state
+                        .players()
+                        .iter()
+                        .filter_map(|player| Player::try_from(player.id()).ok()),
+                );
+            }
+            FromGame::GameStarted => {
+                // Spectators do not participate in the simulation.
+                let Some(local) = players.local().filter(|_| players.is_controlling()) else {
+                    continue;
+                };
+
+                info!("Starting lockstep simulation with {} players.", lobby.len());
+                commands.insert_resource(Lockstep::new(local, lobby.drain(..)));
+                commands.insert_resource(LockstepTimer(Timer::new(
+                    TICK_INTERVAL,
+                    TimerMode::Repeating,
+                )));
+            }
+            FromGame::PeerLeft(id) | FromGame::PlayerKicked(id) => {
+                if let (Some(lockstep), Ok(player)) = (lockstep.as_mut(), Player::try_from(*id)) {
+                    lockstep.remove(player);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn receive(
+    mut lockstep: ResMut<Lockstep>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    for event in inputs.iter() {
+        let ToPlayers::Commands {
+            player,
+            tick,
+            ref commands,
+        } = *event.message()
+        else {
+            continue;
+        };
+
+        let player = match Player::try_from(player) {
+            Ok(player) => player,
+            Err(err) => {
+                warn!("Commands from an invalid player: {err:?}");
+                continue;
+            }
+        };
+
+        match lockstep.receive(player, tick, commands.clone()) {
+            Ok(()) => (),
+            Err(err) if !err.is_fatal() => {
+                warn!("Commands of {player} for tick {tick} dropped: {err:?}");
+            }
+            Err(err) => fatals.send(
+                FatalErrorEvent::new("mp-error-invalid-commands")
+                    .with_arg("player", player)
+                    .with_arg("tick", tick)
+                    .with_arg("error", format!("{err:?}")),
+            ),
+        }
+    }
+}
+
+fn advance(mut lockstep: ResMut<Lockstep>, mut ticks: EventWriter<LockstepTickEvent>) {
+    while let Some(event) = lockstep.advance() {
+        ticks.send(event);
+    }
+}
+
+fn schedule(
+    time: Res<Time>,
+    mut timer: ResMut<LockstepTimer>,
+    mut lockstep: ResMut<Lockstep>,
+    mut inputs: EventReader<ScheduleCommandsEvent>,
+    mut outputs: EventWriter<ToPlayersEvent<true>>,
+) {
+    for event in inputs.iter() {
+        lockstep.push(event.0.clone());
+    }
+
+    if lockstep.is_halted() {
+        return;
+    }
+
+    timer.0.tick(time.delta());
+    for _ in 0..timer.0.times_finished_this_tick() {
+        let (tick, commands) = lockstep.schedule();
+        outputs.send(
+            ToPlayers::Commands {
+                player: lockstep.local.to_num(),
+                tick,
+                commands,
+            }
+            .into(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Tick = (u32, Vec<(Player, Vec<u8>)>);
+
+    fn advance(lockstep: &mut Lockstep) -> Option<Tick> {
+        lockstep
+            .advance()
+            .map(|event| (event.tick(), event.commands().to_vec()))
+    }
+
+    #[test]
+    fn test_lockstep() {
+        let mut lockstep = Lockstep::new(Player::Player1, [Player::Player2]);
+
+        // The first ticks have no commands but are still paced by the local
+        // schedule.
+        assert_eq!(advance(&mut lockstep), Some((0, vec![])));
+        assert_eq!(advance(&mut lockstep), None);
+
+        lockstep.push(vec![1, 2]);
+        lockstep.push(vec![3]);
+        assert_eq!(lockstep.schedule(), (3, vec![1, 2, 3]));
+        assert_eq!(advance(&mut lockstep), Some((1, vec![])));
+        assert_eq!(advance(&mut lockstep), None);
+        assert_eq!(lockstep.schedule(), (4, vec![]));
+        assert_eq!(lockstep.schedule(), (5, vec![]));
+        assert_eq!(advance(&mut lockstep), Some((2, vec![])));
+
+        // Waiting for the other player.
+        assert_eq!(advance(&mut lockstep), None);
+        lockstep.receive(Player::Player2, 4, vec![8]).unwrap();
+        assert_eq!(advance(&mut lockstep), None);
+        lockstep.receive(Player::Player2, 3, vec![7]).unwrap();
+        // Redelivered commands are ignored.
+        lockstep.receive(Player::Player2, 3, vec![9]).unwrap();
+
+        assert_eq!(
+            advance(&mut lockstep),
+            Some((
+                3,
+                vec![(Player::Player1, vec![1, 2, 3]), (Player::Player2, vec![7])]
+            ))
+        );
+        assert_eq!(advance(&mut lockstep), None);
+        lockstep.schedule();
+        assert_eq!(
+            advance(&mut lockstep),
+            Some((4, vec![(Player::Player2, vec![8])]))
+        );
+
+        assert_eq!(
+            lockstep.receive(Player::Player2, 0, vec![]),
+            Err(InputError::Late)
+        );
+        assert_eq!(
+            lockstep.receive(Player::Player3, 5, vec![]),
+            Err(InputError::UnknownPlayer)
+        );
+        assert_eq!(
+            lockstep.receive(Player::Player2, 5 + MAX_AHEAD, vec![]),
+            Err(InputError::TooEarly)
+        );
+
+        // Left players are no longer awaited.
+        lockstep.schedule();
+        assert_eq!(advance(&mut lockstep), None);
+        lockstep.remove(Player::Player2);
+        assert_eq!(advance(&mut lockstep), Some((5, vec![])));
+
+        // Commands sent before the player left are not fatal.
+        let err = lockstep.receive(Player::Player2, 6, vec![]).unwrap_err();
+        assert_eq!(err, InputError::Left);
+        assert!(!err.is_fatal());
+        assert!(!InputError::Late.is_fatal());
+        assert!(InputError::UnknownPlayer.is_fatal());
+        assert!(InputError::TooEarly.is_fatal());
+    }
+
+    #[test]
+    fn test_schedule_limit() {
+        let mut lockstep = Lockstep::new(Player::Player1, []);
+        lockstep.push(vec![1; MAX_COMMANDS_LEN - 1]);
+        lockstep.push(vec![2; 2]);
+
+        assert_eq!(lockstep.schedule(), (3, vec![1; MAX_COMMANDS_LEN - 1]));
+        assert_eq!(lockstep.schedule(), (4, vec![2; 2]));
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut lockstep = Lockstep::new(Player::Player1, []);
+        lockstep.pause_at(1);
+
+        assert_eq!(advance(&mut lockstep), Some((0, vec![])));
+        assert!(lockstep.is_halted());
+        lockstep.schedule();
+        assert_eq!(advance(&mut lockstep), None);
+
+        lockstep.resume();
+        assert!(!lockstep.is_halted());
+        assert_eq!(advance(&mut lockstep), Some((1, vec![])));
+    }
+}
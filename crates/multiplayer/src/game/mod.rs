@@ -0,0 +1,533 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_core::{
+    baseset::GameSet,
+    player::{Player, Team, Teams},
+};
+use de_net::{
+    Capabilities, FromGame, FromServer, GameOpenError, JoinError, ToGame, ToServer, Token,
+    PROTOCOL_VERSION,
+};
+
+use self::{
+    checksum::ChecksumPlugin, commands::CommandsPlugin, construction::ConstructionPlugin,
+    deformation::DeformationPlugin, diplomacy::DiplomacyPlugin, dropped::DroppedPlugin,
+    gameend::GameEndPlugin, interpolation::InterpolationPlugin, lockstep::LockstepPlugin,
+    orders::OrdersPlugin, pause::PausePlugin, pings::PingsPlugin, replay::ReplayPlugin,
+    replication::ReplicationPlugin, snapshot::SnapshotPlugin, surrender::SurrenderPlugin,
+    transport::TransportPlugin,
+};
+pub use self::{
+    checksum::DesyncDetectedEvent,
+    diplomacy::{AllianceFormedEvent, AllianceProposedEvent, ProposeAllianceEvent},
+    dropped::PlayerDroppedEvent,
+    interpolation::InterpolationBuffer,
+    lockstep::{LockstepTickEvent, ScheduleCommandsEvent},
+    pause::{GamePausedEvent, GameResumedEvent, PauseRequestEvent},
+    replay::{Replay, ReplayError, ReplayHeader, ReplayTick},
+    replication::Replicated,
+    snapshot::ResyncedEvent,
+    surrender::{DefeatedPlayers, PlayerSurrenderedEvent, SurrenderEvent},
+};
+use crate::{
+    lifecycle::{FatalErrorEvent, NetGameConfRes},
+    messages::{
+        FromGameServerEvent, FromMainServerEvent, MessagesSet, Ports, ToGameServerEvent,
+        ToMainServerEvent,
+    },
+    netstate::NetState,
+    network::NetEncryption,
+    ServerPort,
+};
+
+mod checksum;
+mod commands;
+mod construction;
+mod deformation;
+mod diplomacy;
+mod dropped;
+mod gameend;
+mod interpolation;
+mod lockstep;
+mod orders;
+mod pause;
+mod pings;
+mod replay;
+mod replication;
+mod snapshot;
+mod surrender;
+mod transport;
+
+/// For how long does the client try to rejoin the game after the connection
+/// was lost. This must be shorter than the grace period of the server.
+const REJOIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(LockstepPlugin)
+            .add_plugin(ChecksumPlugin)
+            .add_plugin(SnapshotPlugin)
+            .add_plugin(InterpolationPlugin)
+            .add_plugin(ReplicationPlugin)
+            .add_plugin(PausePlugin)
+            .add_plugin(SurrenderPlugin)
+            .add_plugin(DroppedPlugin)
+            .add_plugin(ReplayPlugin)
+            .add_plugin(DiplomacyPlugin)
+            .add_plugin(PingsPlugin)
+            .add_plugin(GameEndPlugin)
+            .add_plugin(ConstructionPlugin)
+            .add_plugin(OrdersPlugin)
+            .add_plugin(TransportPlugin)
+            .add_plugin(DeformationPlugin)
+            .add_plugin(CommandsPlugin)
+            .add_event::<PlayerLeftEvent>()
+            .add_event::<ResyncRequestedEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Connected)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(open_or_join.in_schedule(OnEnter(NetState::Connected)))
+            .add_system(
+                process_from_server
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(on_event::<FromMainServerEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                process_from_game
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                start_encryption
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(start_rejoin.in_schedule(OnEnter(NetState::Rejoining)))
+            .add_system(stop_rejoin.in_schedule(OnExit(NetState::Rejoining)))
+            .add_system(
+                rejoin_timeout
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(in_state(NetState::Rejoining)),
+            )
+            .add_system(leave.in_schedule(OnEnter(NetState::ShuttingDown)));
+    }
+}
+
+/// This event is sent when another player leaves the game.
+pub struct PlayerLeftEvent(Player);
+
+impl PlayerLeftEvent {
+    pub fn player(&self) -> Player {
+        self.0
+    }
+}
+
+/// This event is sent when another player (e.g. after reconnecting to the
+/// game) requests the full game state. The state should be sent to the
+/// player.
+pub struct ResyncRequestedEvent(Player);
+
+impl ResyncRequestedEvent {
+    pub fn player(&self) -> Player {
+        self.0
+    }
+}
+
+/// Time after which rejoin attempts are abandoned.
+#[derive(Resource)]
+struct RejoinDeadline(Instant);
+
+/// Role of the local client in a joined multiplayer game.
+#[derive(Resource)]
+pub struct Players {
+    local: Option<Player>,
+    token: Option<Token>,
+    capabilities: Capabilities,
+    spectator: bool,
+    teams: Teams,
+}
+
+impl Players {
+    /// The player controlled from this computer or None if not (yet) joined
+    /// or joined as a spectator.
+    pub fn local(&self) -> Option<Player> {
+        self.local
+    }
+
+    /// Returns true if the local client may command units of the local
+    /// player. Spectators render the game but do not control anything.
+    pub fn is_controlling(&self) -> bool {
+        !self.spectator && self.local.is_some()
+    }
+
+    /// Token issued by the game server to the local player or None if the
+    /// player has not yet joined.
+    pub(crate) fn token(&self) -> Option<Token> {
+        self.token
+    }
+
+    /// Optional protocol features supported by both the local client and the
+    /// game server. It is empty if not yet joined.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Assignment of players, including AI players, to teams as last
+    /// announced in the game lobby.
+    pub fn teams(&self) -> &Teams {
+        &self.teams
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(Players {
+        local: None,
+        token: None,
+        capabilities: Capabilities::empty(),
+        spectator: false,
+        teams: Teams::default(),
+    });
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Players>();
+}
+
+fn open_or_join(
+    conf: Res<NetGameConfRes>,
+    mut main_server: EventWriter<ToMainServerEvent>,
+    mut game_server: EventWriter<ToGameServerEvent<true>>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    match conf.server_port() {
+        ServerPort::Main(_) if conf.spectator() => {
+            fatals.send(FatalErrorEvent::new("mp-error-spectator-open"));
+        }
+        ServerPort::Game(_) if conf.spectator() => {
+            info!("Sending a spectate-game request.");
+            game_server.send(ToGame::Spectate(conf.password()).into());
+        }
+        ServerPort::Main(_) => {
+            info!("Sending a open-game request.");
+            main_server.send(
+                ToServer::OpenGame {
+                    max_players: conf.max_players().to_num(),
+                    slots: conf.slots(),
+                    name: conf.game_name().to_owned(),
+                    map: conf.map_name().to_owned(),
+                    auth: conf.auth().map(String::from),
+                    password: conf.password(),
+                    capabilities: Capabilities::SUPPORTED,
+                }
+                .into(),
+            );
+        }
+        ServerPort::Game(_) => {
+            info!("Sending a join-game request.");
+            game_server.send(
+                ToGame::Join {
+                    version: PROTOCOL_VERSION,
+                    capabilities: Capabilities::SUPPORTED,
+                    auth: conf.auth().map(String::from),
+                    password: conf.password(),
+                }
+                .into(),
+            );
+        }
+    }
+}
+
+fn process_from_server(
+    mut ports: ResMut<Ports>,
+    mut events: EventReader<FromMainServerEvent>,
+    mut outputs: EventWriter<ToGameServerEvent<true>>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    for event in events.iter() {
+        match event.message() {
+            FromServer::Pong(id) => {
+                info!("Pong {} received from server.", *id);
+            }
+            FromServer::GameOpened { port } => match ports.init_game_port(*port) {
+                Ok(_) => {
+                    info!("Game on port {} opened.", *port);
+                    // Send something to open NAT.
+                    outputs.send(ToGame::Ping(u32::MAX).into());
+                }
+                Err(err) => {
+                    fatals.send(
+                        FatalErrorEvent::new("mp-error-invalid-game-opened")
+                            .with_arg("error", format!("{err:?}")),
+                    );
+                }
+            },
+            FromServer::GameOpenError(err) => match err {
+                GameOpenError::DifferentGame => {
+                    fatals.send(FatalErrorEvent::new("mp-error-open-different-game"));
+                }
+                GameOpenError::InvalidListing => {
+                    fatals.send(FatalErrorEvent::new("mp-error-open-invalid-listing"));
+                }
+                GameOpenError::TooManyPlayers => {
+                    fatals.send(FatalErrorEvent::new("mp-error-open-too-many-players"));
+                }
+                GameOpenError::Unavailable => {
+                    fatals.send(FatalErrorEvent::new("mp-error-open-unavailable"));
+                }
+                GameOpenError::Unauthenticated => {
+                    fatals.send(FatalErrorEvent::new("mp-error-open-unauthenticated"));
+                }
+                GameOpenError::InvalidSlots => {
+                    fatals.send(FatalErrorEvent::new("mp-error-open-invalid-slots"));
+                }
+            },
+            FromServer::Game(_) | FromServer::GamesEnd(_) | FromServer::Motd(_) => {
+                trace!("Unexpected game listing received.");
+            }
+        }
+    }
+}
+
+/// Events informing about other players in the game.
+#[derive(SystemParam)]
+struct PeerEvents<'w> {
+    left: EventWriter<'w, PlayerLeftEvent>,
+    resyncs: EventWriter<'w, ResyncRequestedEvent>,
+}
+
+fn process_from_game(
+    mut players: ResMut<Players>,
+    mut inputs: EventReader<FromGameServerEvent>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+    mut peers: PeerEvents,
+    state: Res<State<NetState>>,
+    mut next_state: ResMut<NextState<NetState>>,
+) {
+    for event in inputs.iter() {
+        match event.message() {
+            FromGame::Pong(id) => {
+                trace!("Received Pong({id}).");
+            }
+            FromGame::NotJoined => {
+                fatals.send(FatalErrorEvent::new("mp-error-not-in-game"));
+            }
+            FromGame::Joined {
+                id,
+                token,
+                capabilities,
+            } => match Player::try_from(*id) {
+                Ok(player) if state.0 == NetState::Rejoining => {
+                    if players.local != Some(player) {
+                        fatals.send(
+                            FatalErrorEvent::new("mp-error-rejoined-different-player")
+                                .with_arg("player", player),
+                        );
+                        continue;
+                    }
+
+                    info!("Rejoined game as Player {player}.");
+                    players.token = Some(*token);
+                    // The game state is requested after the transition.
+                    next_state.set(NetState::Joined);
+                }
+                Ok(player) => {
+                    info!("Joined game as Player {player}.");
+                    players.local = Some(player);
+                    players.token = Some(*token);
+                    players.capabilities = Capabilities::SUPPORTED.intersection(*capabilities);
+                    next_state.set(NetState::Joined);
+                }
+                Err(err) => {
+                    fatals.send(
+                        FatalErrorEvent::new("mp-error-invalid-player")
+                            .with_arg("error", format!("{err:?}")),
+                    );
+                }
+            },
+            FromGame::JoinError(error) => match error {
+                JoinError::GameFull => {
+                    fatals.send(FatalErrorEvent::new("mp-error-join-full"));
+                }
+                JoinError::AlreadyJoined => {
+                    fatals.send(FatalErrorEvent::new("mp-error-join-already-joined"));
+                }
+                JoinError::DifferentGame => {
+                    fatals.send(FatalErrorEvent::new("mp-error-join-different-game"));
+                }
+                JoinError::GameStarted => {
+                    fatals.send(FatalErrorEvent::new("mp-error-join-started"));
+                }
+                JoinError::InvalidToken => {
+                    fatals.send(FatalErrorEvent::new("mp-error-join-invalid-token"));
+                }
+                JoinError::Banned => {
+                    fatals.send(FatalErrorEvent::new("mp-error-join-banned"));
+                }
+                JoinError::Unauthenticated => {
+                    fatals.send(FatalErrorEvent::new("mp-error-join-unauthenticated"));
+                }
+            },
+            FromGame::Left => {
+                if state.0 < NetState::ShuttingDown {
+                    fatals.send(FatalErrorEvent::new("mp-error-kicked"));
+                }
+            }
+            FromGame::PeerJoined(id) => {
+                info!("Peer {id} joined.");
+            }
+            FromGame::PeerLeft(id) => match Player::try_from(*id) {
+                Ok(player) => {
+                    info!("Peer {player} left.");
+                    peers.left.send(PlayerLeftEvent(player));
+                }
+                Err(err) => {
+                    fatals.send(
+                        FatalErrorEvent::new("mp-error-invalid-player-left")
+                            .with_arg("error", format!("{err:?}")),
+                    );
+                }
+            },
+            FromGame::LobbyState(lobby) => {
+                debug!("Lobby updated: {:?}", lobby.players());
+
+                let humans = lobby.players().iter().map(|p| (p.id(), p.team()));
+                let ai = lobby.ai_players().iter().map(|p| (p.id(), p.team()));
+                for (id, team) in humans.chain(ai) {
+                    if let Ok(player) = Player::try_from(id) {
+                        players.teams.set(player, Team::new(team));
+                    }
+                }
+            }
+            FromGame::GameStarted => {
+                info!("Game started.");
+            }
+            FromGame::Chat { .. } => {
+                // Handled by the messages module.
+            }
+            FromGame::Clock { .. } => {
+                // Handled by the clock module.
+            }
+            FromGame::PlayerSurrendered(_) => {
+                // Handled by the surrender module.
+            }
+            FromGame::Spectating => {
+                info!("Joined game as a spectator.");
+                players.spectator = true;
+                next_state.set(NetState::Joined);
+            }
+            FromGame::PlayerKicked(id) => match Player::try_from(*id) {
+                Ok(player) if players.local == Some(player) => {
+                    fatals.send(FatalErrorEvent::new("mp-error-kicked"));
+                }
+                Ok(player) => {
+                    info!("Peer {player} was kicked.");
+                    peers.left.send(PlayerLeftEvent(player));
+                }
+                Err(err) => {
+                    fatals.send(
+                        FatalErrorEvent::new("mp-error-invalid-player-kicked")
+                            .with_arg("error", format!("{err:?}")),
+                    );
+                }
+            },
+            FromGame::Kicked => {
+                fatals.send(FatalErrorEvent::new("mp-error-rate-limited"));
+            }
+            FromGame::VersionMismatch { server, client } => {
+                let id = if server > client {
+                    "mp-error-version-outdated-client"
+                } else {
+                    "mp-error-version-outdated-server"
+                };
+                fatals.send(
+                    FatalErrorEvent::new(id)
+                        .with_arg("client", client)
+                        .with_arg("server", server),
+                );
+            }
+            FromGame::WrongPassword => {
+                fatals.send(FatalErrorEvent::new("mp-error-wrong-password"));
+            }
+            FromGame::PeerDisconnected(id) => {
+                info!("Peer {id} got disconnected.");
+            }
+            FromGame::ResyncRequested(id) => match Player::try_from(*id) {
+                Ok(player) => {
+                    peers.resyncs.send(ResyncRequestedEvent(player));
+                }
+                Err(err) => {
+                    fatals.send(
+                        FatalErrorEvent::new("mp-error-invalid-state-request")
+                            .with_arg("error", format!("{err:?}")),
+                    );
+                }
+            },
+        }
+    }
+}
+
+/// Starts encryption of the connection to the game server once (re)joining
+/// the game negotiated [`Capabilities::ENCRYPTION`].
+fn start_encryption(
+    conf: Res<NetGameConfRes>,
+    ports: Res<Ports>,
+    encryption: Res<NetEncryption>,
+    mut inputs: EventReader<FromGameServerEvent>,
+) {
+    for event in inputs.iter() {
+        let FromGame::Joined {
+            token,
+            capabilities,
+            ..
+        } = event.message()
+        else {
+            continue;
+        };
+        if !Capabilities::SUPPORTED
+            .intersection(*capabilities)
+            .contains(Capabilities::ENCRYPTION)
+        {
+            continue;
+        }
+        let Some(port) = ports.game() else {
+            continue;
+        };
+
+        encryption.initiate(SocketAddr::new(conf.server_host(), port), *token);
+    }
+}
+
+fn start_rejoin(
+    mut commands: Commands,
+    players: Res<Players>,
+    mut server: EventWriter<ToGameServerEvent<true>>,
+) {
+    // Guaranteed by the transition to the rejoining state.
+    let token = players.token().unwrap();
+    commands.insert_resource(RejoinDeadline(Instant::now() + REJOIN_TIMEOUT));
+    server.send(ToGame::Rejoin(token).into());
+}
+
+fn stop_rejoin(mut commands: Commands) {
+    commands.remove_resource::<RejoinDeadline>();
+}
+
+fn rejoin_timeout(deadline: Res<RejoinDeadline>, mut fatals: EventWriter<FatalErrorEvent>) {
+    if Instant::now() >= deadline.0 {
+        fatals.send(FatalErrorEvent::new("mp-error-rejoin-timeout"));
+    }
+}
+
+fn leave(mut server: EventWriter<ToGameServerEvent<true>>) {
+    // Send this even if not yet joined because the join / open-game request
+    // might already be processed.
+    server.send(ToGame::Leave.into());
+}
@@ -0,0 +1,50 @@
+//! Scheduling of movement commands of the local player.
+//!
+//! The commands are scheduled with the lockstep simulation and executed once
+//! the tick they were stamped with is simulated, see [`super::commands`].
+
+use bevy::prelude::*;
+use de_behaviour::{EnqueueCommandEvent, MovementCommand, ScheduledMovement};
+use de_core::baseset::GameSet;
+use de_net::MAX_COMMANDS_LEN;
+
+use super::lockstep::{Lockstep, ScheduleCommandsEvent};
+use crate::netstate::NetState;
+
+pub(super) struct OrdersPlugin;
+
+impl Plugin for OrdersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.run_if(resource_added::<Lockstep>()))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                schedule
+                    .in_base_set(GameSet::Update)
+                    .run_if(resource_exists::<Lockstep>()),
+            );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(ScheduledMovement);
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ScheduledMovement>();
+}
+
+fn schedule(
+    mut events: EventReader<EnqueueCommandEvent>,
+    mut out_events: EventWriter<ScheduleCommandsEvent>,
+) {
+    let mut buf = Vec::new();
+    for event in events.iter() {
+        if buf.len() + MovementCommand::MAX_ENCODED_LEN > MAX_COMMANDS_LEN {
+            out_events.send(ScheduleCommandsEvent::new(std::mem::take(&mut buf)));
+        }
+        event.command().encode(&mut buf);
+    }
+    if !buf.is_empty() {
+        out_events.send(ScheduleCommandsEvent::new(buf));
+    }
+}
@@ -0,0 +1,366 @@
+//! Pausing of a multiplayer game.
+//!
+//! Any player may request a pause. The request contains the first lockstep
+//! tick not yet scheduled by the requesting player, thus not yet simulated by
+//! any player. All players halt the simulation right before that tick and
+//! acknowledge the request. Each player may pause the game only
+//! [`PAUSE_BUDGET`] times.
+
+use ahash::{AHashMap, AHashSet};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use de_core::{baseset::GameSet, player::Player};
+use de_net::ToPlayers;
+
+use super::{
+    lockstep::{Lockstep, LockstepSet},
+    Players,
+};
+use crate::{
+    messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent},
+    netstate::NetState,
+};
+
+/// Number of times each player may pause the game.
+const PAUSE_BUDGET: u8 = 3;
+
+pub(super) struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PauseRequestEvent>()
+            .add_event::<GamePausedEvent>()
+            .add_event::<GameResumedEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<PauseState>())
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .in_set(LockstepSet::Receive)
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                request
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<PauseState>())
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(on_event::<PauseRequestEvent>())
+                    .before(MessagesSet::SendMessages),
+            );
+    }
+}
+
+/// Send this event to pause or resume a multiplayer game.
+pub enum PauseRequestEvent {
+    Pause,
+    Resume,
+}
+
+/// This event is sent once a requested pause was acknowledged by all
+/// players.
+pub struct GamePausedEvent {
+    player: Player,
+    tick: u32,
+}
+
+impl GamePausedEvent {
+    /// The player who paused the game.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// The simulation is halted right before this lockstep tick.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+}
+
+/// This event is sent when a paused game is resumed.
+pub struct GameResumedEvent {
+    player: Player,
+}
+
+impl GameResumedEvent {
+    /// The player who resumed the game.
+    pub fn player(&self) -> Player {
+        self.player
+    }
+}
+
+#[derive(Resource, Default)]
+struct PauseState {
+    /// Number of pauses requested by each player.
+    used: AHashMap<Player, u8>,
+    active: Option<ActivePause>,
+}
+
+struct ActivePause {
+    requester: Player,
+    tick: u32,
+    acks: AHashSet<Player>,
+    confirmed: bool,
+}
+
+impl PauseState {
+    /// Registers a pause request of a player.
+    fn request(&mut self, player: Player, tick: u32) -> Result<(), PauseError> {
+        if self.active.is_some() {
+            return Err(PauseError::AlreadyPaused);
+        }
+
+        let used = self.used.entry(player).or_default();
+        if *used >= PAUSE_BUDGET {
+            return Err(PauseError::BudgetExhausted);
+        }
+        *used += 1;
+
+        self.active = Some(ActivePause {
+            requester: player,
+            tick,
+            acks: AHashSet::new(),
+            confirmed: false,
+        });
+        Ok(())
+    }
+
+    /// Registers an acknowledgement of the active pause. It returns the
+    /// active pause once (the first time) it is acknowledged by all
+    /// `players` (apart from the requester).
+    fn ack(
+        &mut self,
+        player: Player,
+        requester: Player,
+        tick: u32,
+        players: impl IntoIterator<Item = Player>,
+    ) -> Option<(Player, u32)> {
+        let active = self.active.as_mut()?;
+        if active.requester != requester || active.tick != tick {
+            return None;
+        }
+
+        active.acks.insert(player);
+        if active.confirmed
+            || !players
+                .into_iter()
+                .all(|other| other == requester || active.acks.contains(&other))
+        {
+            return None;
+        }
+
+        active.confirmed = true;
+        Some((active.requester, active.tick))
+    }
+
+    /// Ends the active pause. It returns false if the game is not paused.
+    fn resume(&mut self) -> bool {
+        self.active.take().is_some()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PauseError {
+    AlreadyPaused,
+    BudgetExhausted,
+}
+
+fn setup(mut commands: Commands) {
+    // Pauses are kept when rejoining the game.
+    commands.init_resource::<PauseState>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<PauseState>();
+}
+
+fn request(
+    mut state: ResMut<PauseState>,
+    mut lockstep: ResMut<Lockstep>,
+    players: Res<Players>,
+    mut requests: EventReader<PauseRequestEvent>,
+    mut events: PauseEvents,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in requests.iter() {
+        match event {
+            PauseRequestEvent::Pause => {
+                let tick = lockstep.next_scheduled();
+                if let Err(err) = state.request(local, tick) {
+                    info!("Cannot pause the game: {err:?}");
+                    continue;
+                }
+
+                info!("Pausing the game before tick {tick}.");
+                lockstep.pause_at(tick);
+                events.outputs.send(
+                    ToPlayers::Pause {
+                        player: local.to_num(),
+                        tick,
+                    }
+                    .into(),
+                );
+                // There might be no other players.
+                if let Some((player, tick)) = state.ack(local, local, tick, lockstep.players()) {
+                    events.paused.send(GamePausedEvent { player, tick });
+                }
+            }
+            PauseRequestEvent::Resume => {
+                if !state.resume() {
+                    continue;
+                }
+
+                info!("Resuming the game.");
+                lockstep.resume();
+                events.outputs.send(
+                    ToPlayers::Resume {
+                        player: local.to_num(),
+                    }
+                    .into(),
+                );
+                events.resumed.send(GameResumedEvent { player: local });
+            }
+        }
+    }
+}
+
+/// Events sent by the pause protocol.
+#[derive(SystemParam)]
+struct PauseEvents<'w> {
+    outputs: EventWriter<'w, ToPlayersEvent<true>>,
+    paused: EventWriter<'w, GamePausedEvent>,
+    resumed: EventWriter<'w, GameResumedEvent>,
+}
+
+fn receive(
+    mut state: ResMut<PauseState>,
+    mut lockstep: ResMut<Lockstep>,
+    players: Res<Players>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut events: PauseEvents,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in inputs.iter() {
+        match *event.message() {
+            ToPlayers::Pause { player, tick } => {
+                let Ok(player) = Player::try_from(player) else {
+                    warn!("Pause request of an invalid player received.");
+                    continue;
+                };
+
+                if let Err(err) = state.request(player, tick) {
+                    warn!("Ignoring pause request of {player}: {err:?}");
+                    continue;
+                }
+
+                info!("{player} paused the game before tick {tick}.");
+                lockstep.pause_at(tick);
+                events.outputs.send(
+                    ToPlayers::PauseAck {
+                        player: local.to_num(),
+                        requester: player.to_num(),
+                        tick,
+                    }
+                    .into(),
+                );
+                if let Some((player, tick)) = state.ack(local, player, tick, lockstep.players()) {
+                    events.paused.send(GamePausedEvent { player, tick });
+                }
+            }
+            ToPlayers::PauseAck {
+                player,
+                requester,
+                tick,
+            } => {
+                let (Ok(player), Ok(requester)) =
+                    (Player::try_from(player), Player::try_from(requester))
+                else {
+                    warn!("Pause acknowledgement of an invalid player received.");
+                    continue;
+                };
+
+                if let Some((player, tick)) = state.ack(player, requester, tick, lockstep.players())
+                {
+                    events.paused.send(GamePausedEvent { player, tick });
+                }
+            }
+            ToPlayers::Resume { player } => {
+                let Ok(player) = Player::try_from(player) else {
+                    warn!("Resume request of an invalid player received.");
+                    continue;
+                };
+
+                if state.resume() {
+                    info!("{player} resumed the game.");
+                    lockstep.resume();
+                    events.resumed.send(GameResumedEvent { player });
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause() {
+        let players = [Player::Player1, Player::Player2, Player::Player3];
+        let mut state = PauseState::default();
+
+        assert_eq!(state.request(Player::Player2, 10), Ok(()));
+        assert_eq!(
+            state.request(Player::Player1, 12),
+            Err(PauseError::AlreadyPaused)
+        );
+
+        assert_eq!(
+            state.ack(Player::Player1, Player::Player2, 10, players),
+            None
+        );
+        // Acknowledgement of a different pause.
+        assert_eq!(
+            state.ack(Player::Player3, Player::Player2, 11, players),
+            None
+        );
+        assert_eq!(
+            state.ack(Player::Player3, Player::Player2, 10, players),
+            Some((Player::Player2, 10))
+        );
+        // Redelivered acknowledgement.
+        assert_eq!(
+            state.ack(Player::Player3, Player::Player2, 10, players),
+            None
+        );
+
+        assert!(state.resume());
+        assert!(!state.resume());
+        assert_eq!(
+            state.ack(Player::Player1, Player::Player2, 10, players),
+            None
+        );
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut state = PauseState::default();
+        for _ in 0..PAUSE_BUDGET {
+            assert_eq!(state.request(Player::Player1, 1), Ok(()));
+            assert!(state.resume());
+        }
+
+        assert_eq!(
+            state.request(Player::Player1, 1),
+            Err(PauseError::BudgetExhausted)
+        );
+        assert_eq!(state.request(Player::Player2, 1), Ok(()));
+    }
+}
@@ -0,0 +1,88 @@
+//! Sharing of map pings with allied players.
+//!
+//! Pings of the local player are sent reliably to all players. Each player
+//! ignores pings of players who are not their allies.
+
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, diplomacy::Diplomacy, ping::MapPingEvent, player::Player};
+use de_net::ToPlayers;
+
+use super::Players;
+use crate::messages::{FromPlayersEvent, MessagesSet, ToPlayersEvent};
+
+pub(super) struct PingsPlugin;
+
+impl Plugin for PingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            send.in_base_set(GameSet::PostUpdate)
+                .run_if(resource_exists::<Players>())
+                .run_if(on_event::<MapPingEvent>())
+                .before(MessagesSet::SendMessages),
+        )
+        .add_system(
+            receive
+                .in_base_set(GameSet::PreMovement)
+                .run_if(resource_exists::<Players>())
+                .run_if(resource_exists::<Diplomacy>())
+                .run_if(on_event::<FromPlayersEvent>())
+                .after(MessagesSet::RecvMessages),
+        );
+    }
+}
+
+fn send(
+    players: Res<Players>,
+    mut events: EventReader<MapPingEvent>,
+    mut outputs: EventWriter<ToPlayersEvent<true>>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in events.iter() {
+        // Pings received from other players are sent by this module as well.
+        if event.player() != local {
+            continue;
+        }
+
+        let position = event.position();
+        outputs.send(
+            ToPlayers::MapPing {
+                player: local.to_num(),
+                x: position.x,
+                y: position.y,
+            }
+            .into(),
+        );
+    }
+}
+
+fn receive(
+    players: Res<Players>,
+    diplomacy: Res<Diplomacy>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut pings: EventWriter<MapPingEvent>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in inputs.iter() {
+        let ToPlayers::MapPing { player, x, y } = *event.message() else {
+            continue;
+        };
+        let Ok(player) = Player::try_from(player) else {
+            warn!("Map ping of an invalid player received.");
+            continue;
+        };
+        if !x.is_finite() || !y.is_finite() {
+            warn!("Map ping with invalid position received from {player}.");
+            continue;
+        }
+
+        if player != local && diplomacy.are_allies(local, player) {
+            pings.send(MapPingEvent::new(player, Vec2::new(x, y)));
+        }
+    }
+}
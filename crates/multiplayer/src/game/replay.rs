@@ -0,0 +1,290 @@
+//! Recording of multiplayer games.
+//!
+//! Lockstep inputs of all players are recorded together with the map and
+//! the player roster. The recording is stored to a `.dereplay` file in
+//! [`replays_dir`] once the game is left.
+//!
+//! The file starts with [`MAGIC`] and a format version, followed by a
+//! bincode encoded [`ReplayHeader`] and a sequence of encoded ticks. Ticks
+//! without any commands are omitted.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_std::fs;
+use bevy::{prelude::*, tasks::IoTaskPool};
+use bincode::{
+    config::{Configuration, LittleEndian, Varint},
+    decode_from_slice, encode_into_std_write,
+    error::DecodeError,
+    Decode, Encode,
+};
+use de_core::{baseset::GameSet, fs::replays_dir, gconfig::GameConfig, player::Player};
+use thiserror::Error;
+
+use super::{lockstep::Lockstep, LockstepTickEvent, Players};
+use crate::netstate::NetState;
+
+const MAGIC: &[u8; 8] = b"DEREPLAY";
+const FORMAT_VERSION: u16 = 1;
+const BINCODE_CONF: Configuration<LittleEndian, Varint> = bincode::config::standard();
+
+pub(super) struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(store.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                start
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(resource_exists::<GameConfig>())
+                    .run_if(not(resource_exists::<ReplayRecorder>())),
+            )
+            .add_system(
+                record
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<ReplayRecorder>())
+                    .run_if(on_event::<LockstepTickEvent>()),
+            );
+    }
+}
+
+/// A recorded multiplayer game.
+pub struct Replay {
+    header: ReplayHeader,
+    ticks: Vec<ReplayTick>,
+}
+
+impl Replay {
+    /// Decodes a replay from the content of a `.dereplay` file.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let Some(bytes) = bytes.strip_prefix(MAGIC.as_slice()) else {
+            return Err(ReplayError::NotReplay);
+        };
+
+        let (version, mut offset): (u16, usize) = decode_from_slice(bytes, BINCODE_CONF)?;
+        if version != FORMAT_VERSION {
+            return Err(ReplayError::Version(version));
+        }
+
+        let (header, len) = decode_from_slice(&bytes[offset..], BINCODE_CONF)?;
+        offset += len;
+
+        let mut ticks = Vec::new();
+        while offset < bytes.len() {
+            let (tick, len) = decode_from_slice(&bytes[offset..], BINCODE_CONF)?;
+            offset += len;
+            ticks.push(tick);
+        }
+
+        Ok(Self { header, ticks })
+    }
+
+    pub fn header(&self) -> &ReplayHeader {
+        &self.header
+    }
+
+    /// Recorded ticks with at least one command, in simulation order.
+    pub fn ticks(&self) -> &[ReplayTick] {
+        self.ticks.as_slice()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ReplayError {
+    #[error("not a replay file")]
+    NotReplay,
+    #[error("unsupported replay format version {0}")]
+    Version(u16),
+    #[error("invalid replay data: {0}")]
+    Decode(#[from] DecodeError),
+}
+
+/// Map and players of a recorded game.
+#[derive(Encode, Decode, Debug, PartialEq, Eq)]
+pub struct ReplayHeader {
+    map: String,
+    max_players: u8,
+    recorded_by: u8,
+    players: Vec<u8>,
+}
+
+impl ReplayHeader {
+    /// File name of the map of the game.
+    pub fn map(&self) -> &str {
+        self.map.as_str()
+    }
+
+    pub fn max_players(&self) -> u8 {
+        self.max_players
+    }
+
+    /// ID of the player who recorded the game.
+    pub fn recorded_by(&self) -> u8 {
+        self.recorded_by
+    }
+
+    /// IDs of all players participating in the lockstep simulation when
+    /// the recording started.
+    pub fn players(&self) -> &[u8] {
+        self.players.as_slice()
+    }
+}
+
+/// Commands of all players executed at a single lockstep tick.
+#[derive(Encode, Decode, Debug, PartialEq, Eq)]
+pub struct ReplayTick {
+    tick: u32,
+    commands: Vec<(u8, Vec<u8>)>,
+}
+
+impl ReplayTick {
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+
+    /// Commands of individual players (by ID). See
+    /// [`LockstepTickEvent::commands`].
+    pub fn commands(&self) -> &[(u8, Vec<u8>)] {
+        self.commands.as_slice()
+    }
+}
+
+#[derive(Resource)]
+struct ReplayRecorder {
+    data: Vec<u8>,
+}
+
+impl ReplayRecorder {
+    fn new(header: &ReplayHeader) -> Self {
+        let mut data = MAGIC.to_vec();
+        encode_into_std_write(FORMAT_VERSION, &mut data, BINCODE_CONF).unwrap();
+        encode_into_std_write(header, &mut data, BINCODE_CONF).unwrap();
+        Self { data }
+    }
+
+    fn record(&mut self, tick: u32, commands: &[(Player, Vec<u8>)]) {
+        let commands: Vec<(u8, Vec<u8>)> = commands
+            .iter()
+            .filter(|(_, commands)| !commands.is_empty())
+            .map(|(player, commands)| (player.to_num(), commands.clone()))
+            .collect();
+        if commands.is_empty() {
+            return;
+        }
+
+        encode_into_std_write(ReplayTick { tick, commands }, &mut self.data, BINCODE_CONF).unwrap();
+    }
+}
+
+fn start(
+    mut commands: Commands,
+    conf: Res<GameConfig>,
+    players: Res<Players>,
+    lockstep: Res<Lockstep>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    let mut roster: Vec<u8> = lockstep.players().map(|player| player.to_num()).collect();
+    roster.sort_unstable();
+
+    let header = ReplayHeader {
+        map: conf
+            .map_path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        max_players: conf.players().last().map_or(0, |player| player.to_num()),
+        recorded_by: local.to_num(),
+        players: roster,
+    };
+    info!("Recording replay of a game on map {:?}.", header.map);
+    commands.insert_resource(ReplayRecorder::new(&header));
+}
+
+fn record(mut recorder: ResMut<ReplayRecorder>, mut events: EventReader<LockstepTickEvent>) {
+    for event in events.iter() {
+        recorder.record(event.tick(), event.commands());
+    }
+}
+
+fn store(mut commands: Commands, recorder: Option<Res<ReplayRecorder>>) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    commands.remove_resource::<ReplayRecorder>();
+
+    let data = recorder.data.clone();
+    IoTaskPool::get()
+        .spawn(async move {
+            if let Err(err) = store_replay(data).await {
+                error!("Failed to store a replay: {err}");
+            }
+        })
+        .detach();
+}
+
+async fn store_replay(data: Vec<u8>) -> anyhow::Result<()> {
+    let dir = replays_dir()?;
+    fs::create_dir_all(&dir).await?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = dir.join(format!("{timestamp}.dereplay"));
+    fs::write(&path, data).await?;
+    info!("Replay stored to {path:?}.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let header = ReplayHeader {
+            map: "test.dem".into(),
+            max_players: 4,
+            recorded_by: 2,
+            players: vec![1, 2, 3],
+        };
+        let mut recorder = ReplayRecorder::new(&header);
+        recorder.record(
+            7,
+            &[
+                (Player::Player1, vec![1, 2, 3]),
+                (Player::Player2, vec![]),
+                (Player::Player3, vec![4]),
+            ],
+        );
+        recorder.record(8, &[(Player::Player1, vec![]), (Player::Player2, vec![])]);
+        recorder.record(9, &[(Player::Player2, vec![5, 6])]);
+
+        let replay = Replay::decode(&recorder.data).unwrap();
+        assert_eq!(replay.header(), &header);
+        assert_eq!(
+            replay.ticks(),
+            &[
+                ReplayTick {
+                    tick: 7,
+                    commands: vec![(1, vec![1, 2, 3]), (3, vec![4])],
+                },
+                ReplayTick {
+                    tick: 9,
+                    commands: vec![(2, vec![5, 6])],
+                },
+            ]
+        );
+
+        assert!(matches!(
+            Replay::decode(b"DEMAP123"),
+            Err(ReplayError::NotReplay)
+        ));
+        let truncated = &recorder.data[..recorder.data.len() - 1];
+        assert!(matches!(
+            Replay::decode(truncated),
+            Err(ReplayError::Decode(_))
+        ));
+    }
+}
@@ -0,0 +1,977 @@
+//! Replication of objects owned by the local player to other players.
+//!
+//! The state of all replicated objects is periodically sent to each other
+//! player. To save bandwidth, only changes relative to the latest state
+//! acknowledged by the player are sent. Each changed object is encoded as a
+//! bit-mask of changed fields followed by quantized differences of the
+//! changed fields.
+//!
+//! Replicated objects of other players are spawned locally and their
+//! transforms are smoothed with [`InterpolationBuffer`].
+//!
+//! Every [`CHECKSUM_INTERVAL`] lockstep ticks, the complete state of objects
+//! of the local player is sent (if it fits) and hashed for the detection of
+//! diverging replicas, see [`super::checksum`].
+//!
+//! Objects of a player who left the game and whose objects were taken over
+//! by the AI of another player (see [`DropPolicy::AiTakeover`]) are
+//! replicated by the taking over player. Replicated state of such objects is
+//! accepted only from that player.
+
+use std::collections::BTreeMap;
+
+use ahash::{AHashMap, AHashSet};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bincode::{
+    config::{Configuration, Limit, LittleEndian, Varint},
+    decode_from_slice, encode_into_std_write,
+    error::DecodeError,
+    Decode, Encode,
+};
+use de_core::{
+    baseset::GameSet,
+    objects::{Active, ActiveObjectType, ObjectType},
+    player::Player,
+};
+use de_net::{DropPolicy, ToPlayers, MAX_REPLICATION_LEN};
+use de_objects::{Health, InitialHealths};
+use de_spawner::SpawnBundle;
+use enum_map::Enum;
+
+use super::{
+    checksum::{Fnv, StateHashedEvent, CHECKSUM_INTERVAL},
+    dropped::{DroppedSet, PlayerDroppedEvent},
+    interpolation::InterpolationBuffer,
+    lockstep::{Lockstep, LockstepTickEvent},
+    Players,
+};
+use crate::{
+    messages::{FromPlayersEvent, MessageCategory, MessagesSet, SendSchedule, ToPlayersEvent},
+    netstate::NetState,
+};
+
+/// Number of latest sent and received states remembered for each player.
+const HISTORY: u32 = 32;
+const BINCODE_CONF: Configuration<LittleEndian, Varint, Limit<MAX_REPLICATION_LEN>> =
+    bincode::config::standard().with_limit::<MAX_REPLICATION_LEN>();
+
+const TYPE_BIT: u8 = 1;
+const TRANSLATION_BIT: u8 = 1 << 1;
+const ROTATION_BIT: u8 = 1 << 2;
+const HEALTH_BIT: u8 = 1 << 3;
+const REMOVED_BIT: u8 = 1 << 4;
+
+pub(super) struct ReplicationPlugin;
+
+impl Plugin for ReplicationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Replication>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                take_over
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Replication>())
+                    .run_if(on_event::<PlayerDroppedEvent>())
+                    .after(DroppedSet::Decide)
+                    .after(DroppedSet::Receive),
+            )
+            .add_system(
+                mark_adopted
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Replication>()),
+            )
+            .add_system(
+                assign_ids
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Replication>()),
+            )
+            .add_system(
+                send.in_base_set(GameSet::PostUpdate)
+                    .in_set(ReplicationSet::Send)
+                    .run_if(resource_exists::<Replication>())
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(resource_exists::<SendSchedule>())
+                    .after(MessagesSet::UpdateSchedule)
+                    .before(MessagesSet::SendMessages),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub(super) enum ReplicationSet {
+    Send,
+}
+
+/// Insert this component to objects owned by the local player (or by a
+/// player taken over by the local player) whose state should be sent to
+/// other players.
+#[derive(Component, Default)]
+pub struct Replicated;
+
+/// Identifier of a replicated object unique among objects of a single
+/// player.
+#[derive(Component, Clone, Copy)]
+struct NetId(u32);
+
+#[derive(Resource, Default)]
+struct Replication {
+    /// Next free object ID of each player whose objects are replicated by
+    /// the local player.
+    next_ids: AHashMap<Player, u32>,
+    sequence: u32,
+    /// Keyed by the owner of the replicated objects and the target player.
+    senders: AHashMap<(Player, Player), PeerSender>,
+    /// Keyed by the owner of the replicated objects.
+    receivers: AHashMap<Player, PeerReceiver>,
+    /// Players who left the game and whose objects were taken over by the
+    /// local player.
+    adopted: AHashSet<Player>,
+    /// Players who left the game mapped to the players who took over (and
+    /// replicate) their objects.
+    replicators: AHashMap<Player, Player>,
+    /// Locally spawned replicas of objects of other players.
+    replicas: AHashMap<(Player, u32), Entity>,
+    /// Quantized health of replicated objects (keyed by the owner and the
+    /// object ID) as last sampled. Health is sampled less often than other
+    /// state, see [`crate::SendRates`].
+    healths: AHashMap<(Player, u32), u16>,
+}
+
+impl Replication {
+    /// Returns the player allowed to replicate objects of `owner`.
+    fn replicator(&self, owner: Player) -> Player {
+        self.replicators.get(&owner).copied().unwrap_or(owner)
+    }
+}
+
+/// Quantized state of a single replicated object.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ObjectState {
+    object_type: u8,
+    /// Position in centimeters.
+    translation: [i32; 3],
+    rotation: [i16; 4],
+    health: u16,
+}
+
+impl ObjectState {
+    fn new(object_type: ActiveObjectType, transform: &Transform, health: f32) -> Self {
+        Self {
+            // There is only a handful of object types.
+            object_type: object_type.into_usize() as u8,
+            translation: transform
+                .translation
+                .to_array()
+                .map(|coordinate| (coordinate * 100.).round() as i32),
+            rotation: transform
+                .rotation
+                .to_array()
+                .map(|component| (component * f32::from(i16::MAX)).round() as i16),
+            health: (health * f32::from(u16::MAX)).round() as u16,
+        }
+    }
+
+    fn object_type(&self) -> Option<ActiveObjectType> {
+        let index = usize::from(self.object_type);
+        (index < ActiveObjectType::LENGTH).then(|| ActiveObjectType::from_usize(index))
+    }
+
+    fn translation(&self) -> Vec3 {
+        Vec3::from_array(self.translation.map(|coordinate| coordinate as f32 / 100.))
+    }
+
+    fn rotation(&self) -> Quat {
+        let rotation = Quat::from_array(
+            self.rotation
+                .map(|component| f32::from(component) / f32::from(i16::MAX)),
+        );
+        if rotation.length_squared() > 0.5 {
+            rotation.normalize()
+        } else {
+            Quat::IDENTITY
+        }
+    }
+
+    fn health(&self) -> f32 {
+        f32::from(self.health) / f32::from(u16::MAX)
+    }
+}
+
+/// Quantized states of all replicated objects of a player.
+type State = BTreeMap<u32, ObjectState>;
+
+/// Hashes quantized states of objects, see [`ToPlayers::Checksum`].
+fn hash_state(state: &State) -> u64 {
+    let mut hasher = Fnv::new();
+    for (id, object) in state {
+        hasher.write(&id.to_le_bytes());
+        hasher.write(&[object.object_type]);
+        for coordinate in object.translation {
+            hasher.write(&coordinate.to_le_bytes());
+        }
+        for component in object.rotation {
+            hasher.write(&component.to_le_bytes());
+        }
+        hasher.write(&object.health.to_le_bytes());
+    }
+    hasher.finish()
+}
+
+/// Replication state of a single other player from the perspective of the
+/// owner of the replicated objects.
+#[derive(Default)]
+struct PeerSender {
+    acked: Option<u32>,
+    /// States as reconstructed by the other player.
+    sent: BTreeMap<u32, State>,
+    /// Changes are encoded starting with this object so that all changes
+    /// are eventually sent even if they do not fit into a single message.
+    next_id: u32,
+}
+
+impl PeerSender {
+    fn ack(&mut self, sequence: u32) {
+        if self.sent.contains_key(&sequence) && self.acked.map_or(true, |acked| acked < sequence) {
+            self.acked = Some(sequence);
+        }
+    }
+
+    /// Encodes (a size limited part of) changes of the latest acknowledged
+    /// state to `state`. It returns the baseline sequence number, the
+    /// encoded changes and whether all changes were encoded.
+    fn send(&mut self, sequence: u32, state: &State) -> (Option<u32>, Vec<u8>, bool) {
+        let empty = State::new();
+        let baseline = self
+            .acked
+            .and_then(|acked| self.sent.get(&acked))
+            .unwrap_or(&empty);
+
+        let delta = encode_delta(baseline, state, self.next_id);
+        self.next_id = delta.next_id;
+
+        let acked = self.acked;
+        self.sent.insert(sequence, delta.state);
+        self.sent.retain(|&other, _| {
+            other + HISTORY > sequence || acked.map_or(false, |acked| acked == other)
+        });
+
+        (self.acked, delta.data, delta.complete)
+    }
+}
+
+/// Replication state of objects owned by another player.
+#[derive(Default)]
+struct PeerReceiver {
+    latest: Option<u32>,
+    received: BTreeMap<u32, State>,
+}
+
+impl PeerReceiver {
+    /// Decodes and stores received state changes. It returns true if the
+    /// resulting state is the latest received state.
+    fn receive(
+        &mut self,
+        sequence: u32,
+        baseline: Option<u32>,
+        data: &[u8],
+    ) -> Result<bool, ReplicationError> {
+        let empty = State::new();
+        let baseline = match baseline {
+            Some(baseline) => self
+                .received
+                .get(&baseline)
+                .ok_or(ReplicationError::UnknownBaseline)?,
+            None => &empty,
+        };
+
+        let state = decode_delta(baseline, data)?;
+        self.received.insert(sequence, state);
+
+        let is_latest = self.latest.map_or(true, |latest| latest < sequence);
+        if is_latest {
+            self.latest = Some(sequence);
+            self.received.retain(|&other, _| other + HISTORY > sequence);
+        }
+        Ok(is_latest)
+    }
+
+    fn latest(&self) -> Option<&State> {
+        self.latest.and_then(|latest| self.received.get(&latest))
+    }
+}
+
+#[derive(Debug)]
+enum ReplicationError {
+    /// The changes are relative to a state which is not (or no longer)
+    /// known.
+    UnknownBaseline,
+    Invalid(String),
+}
+
+impl From<DecodeError> for ReplicationError {
+    fn from(error: DecodeError) -> Self {
+        Self::Invalid(error.to_string())
+    }
+}
+
+struct Delta {
+    data: Vec<u8>,
+    /// State reconstructed from the baseline and the encoded changes.
+    state: State,
+    /// First object to be encoded next time.
+    next_id: u32,
+    /// True if all changes were encoded.
+    complete: bool,
+}
+
+/// Encodes changes from `baseline` to `target` of as many objects as fit
+/// into [`MAX_REPLICATION_LEN`] bytes. Objects with ID `first` and higher are
+/// encoded first.
+fn encode_delta(baseline: &State, target: &State, first: u32) -> Delta {
+    let removed = baseline
+        .keys()
+        .filter(|id| !target.contains_key(id))
+        .map(|&id| (id, None));
+    let changed = target
+        .iter()
+        .filter(|&(id, state)| baseline.get(id) != Some(state))
+        .map(|(&id, state)| (id, Some(state)));
+    let mut changes: Vec<(u32, Option<&ObjectState>)> = removed.chain(changed).collect();
+    changes.sort_unstable_by_key(|&(id, _)| (id < first, id));
+
+    let mut data = Vec::new();
+    let mut state = baseline.clone();
+    let mut next_id = 0;
+    let mut complete = true;
+    let mut buffer = Vec::new();
+
+    for (id, new) in changes {
+        buffer.clear();
+        encode_object(&mut buffer, id, baseline.get(&id), new);
+        if data.len() + buffer.len() > MAX_REPLICATION_LEN {
+            next_id = id;
+            complete = false;
+            break;
+        }
+
+        data.extend_from_slice(&buffer);
+        match new {
+            Some(&new) => state.insert(id, new),
+            None => state.remove(&id),
+        };
+    }
+
+    Delta {
+        data,
+        state,
+        next_id,
+        complete,
+    }
+}
+
+fn encode_object(
+    buffer: &mut Vec<u8>,
+    id: u32,
+    old: Option<&ObjectState>,
+    new: Option<&ObjectState>,
+) {
+    let Some(new) = new else {
+        write(buffer, id);
+        write(buffer, REMOVED_BIT);
+        return;
+    };
+    let mut mask = 0;
+    if old.map_or(true, |old| old.object_type != new.object_type) {
+        mask |= TYPE_BIT;
+    }
+    let old = old.copied().unwrap_or_default();
+    if old.translation != new.translation {
+        mask |= TRANSLATION_BIT;
+    }
+    if old.rotation != new.rotation {
+        mask |= ROTATION_BIT;
+    }
+    if old.health != new.health {
+        mask |= HEALTH_BIT;
+    }
+
+    write(buffer, id);
+    write(buffer, mask);
+    if mask & TYPE_BIT != 0 {
+        write(buffer, new.object_type);
+    }
+    if mask & TRANSLATION_BIT != 0 {
+        for i in 0..3 {
+            write(buffer, new.translation[i].wrapping_sub(old.translation[i]));
+        }
+    }
+    if mask & ROTATION_BIT != 0 {
+        for i in 0..4 {
+            write(buffer, new.rotation[i].wrapping_sub(old.rotation[i]));
+        }
+    }
+    if mask & HEALTH_BIT != 0 {
+        write(buffer, new.health.wrapping_sub(old.health) as i16);
+    }
+}
+
+fn decode_delta(baseline: &State, mut data: &[u8]) -> Result<State, ReplicationError> {
+    let mut state = baseline.clone();
+
+    while !data.is_empty() {
+        let id: u32 = read(&mut data)?;
+        let mask: u8 = read(&mut data)?;
+
+        if mask & REMOVED_BIT != 0 {
+            if state.remove(&id).is_none() {
+                return Err(ReplicationError::Invalid(format!(
+                    "Removal of unknown object {id}."
+                )));
+            }
+            continue;
+        }
+
+        let mut object = match state.get(&id) {
+            Some(&object) => object,
+            None if mask & TYPE_BIT != 0 => ObjectState::default(),
+            None => {
+                return Err(ReplicationError::Invalid(format!(
+                    "Changes of unknown object {id}."
+                )))
+            }
+        };
+
+        if mask & TYPE_BIT != 0 {
+            object.object_type = read(&mut data)?;
+        }
+        if mask & TRANSLATION_BIT != 0 {
+            for coordinate in object.translation.iter_mut() {
+                *coordinate = coordinate.wrapping_add(read(&mut data)?);
+            }
+        }
+        if mask & ROTATION_BIT != 0 {
+            for component in object.rotation.iter_mut() {
+                *component = component.wrapping_add(read(&mut data)?);
+            }
+        }
+        if mask & HEALTH_BIT != 0 {
+            let diff: i16 = read(&mut data)?;
+            object.health = object.health.wrapping_add(diff as u16);
+        }
+
+        state.insert(id, object);
+    }
+
+    Ok(state)
+}
+
+fn write<E: Encode>(buffer: &mut Vec<u8>, value: E) {
+    encode_into_std_write(value, buffer, BINCODE_CONF).unwrap();
+}
+
+fn read<D: Decode>(data: &mut &[u8]) -> Result<D, DecodeError> {
+    let (value, len) = decode_from_slice(data, BINCODE_CONF)?;
+    *data = &data[len..];
+    Ok(value)
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Replication>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Replication>();
+}
+
+type NewObjects<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static Player),
+    (
+        Added<Active>,
+        Without<Replicated>,
+        Without<InterpolationBuffer>,
+    ),
+>;
+
+/// Marks newly spawned objects of players taken over by the local player as
+/// replicated.
+fn mark_adopted(mut commands: Commands, replication: Res<Replication>, new: NewObjects) {
+    for (entity, player) in new.iter() {
+        if replication.adopted.contains(player) {
+            commands.entity(entity).insert(Replicated);
+        }
+    }
+}
+
+type Unidentified<'w, 's> =
+    Query<'w, 's, (Entity, &'static Player), (With<Replicated>, Without<NetId>)>;
+
+fn assign_ids(mut commands: Commands, mut replication: ResMut<Replication>, new: Unidentified) {
+    for (entity, &player) in new.iter() {
+        let next_id = replication.next_ids.entry(player).or_default();
+        let id = *next_id;
+        *next_id += 1;
+        commands.entity(entity).insert(NetId(id));
+    }
+}
+
+/// Hands replication of objects of players who left the game over to the
+/// players whose AI took the objects over.
+fn take_over(
+    mut commands: Commands,
+    players: Res<Players>,
+    mut replication: ResMut<Replication>,
+    mut events: EventReader<PlayerDroppedEvent>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+    let replication = replication.as_mut();
+
+    for event in events.iter() {
+        if !matches!(event.policy(), DropPolicy::AiTakeover) {
+            continue;
+        }
+
+        let owner = event.player();
+        // State sent by the new replicator is not relative to the states
+        // received from the owner.
+        replication.receivers.remove(&owner);
+
+        if event.host() != local {
+            replication.replicators.insert(owner, event.host());
+            continue;
+        }
+
+        replication.adopted.insert(owner);
+        let next_id = replication.next_ids.entry(owner).or_default();
+        replication.replicas.retain(|&(player, id), &mut entity| {
+            if player != owner {
+                return true;
+            }
+            // The replica keeps its ID so that other players keep their
+            // replicas of the object.
+            if let Some(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands
+                    .remove::<InterpolationBuffer>()
+                    .insert((Replicated, NetId(id)));
+            }
+            *next_id = (*next_id).max(id + 1);
+            false
+        });
+    }
+}
+
+fn send(
+    schedule: Res<SendSchedule>,
+    lockstep: Res<Lockstep>,
+    mut replication: ResMut<Replication>,
+    objects: Query<(&NetId, &ObjectType, &Player, &Transform, &Health), With<Replicated>>,
+    mut ticks: EventReader<LockstepTickEvent>,
+    mut outputs: EventWriter<ToPlayersEvent<false>>,
+    mut hashes: EventWriter<StateHashedEvent>,
+) {
+    if schedule.is_due(MessageCategory::Health) {
+        // Health is re-sampled with the next state update.
+        replication.healths.clear();
+    }
+    let checksum_tick = ticks
+        .iter()
+        .map(|event| event.tick())
+        .filter(|tick| tick % CHECKSUM_INTERVAL == 0)
+        .last();
+    if checksum_tick.is_none() && !schedule.is_due(MessageCategory::Positions) {
+        return;
+    }
+    let local = lockstep.local();
+
+    let replication = replication.as_mut();
+    let owners: Vec<Player> = std::iter::once(local)
+        .chain(replication.adopted.iter().copied())
+        .collect();
+
+    let healths = &mut replication.healths;
+    let states: Vec<(Player, State)> = owners
+        .into_iter()
+        .map(|owner| {
+            let state: State = objects
+                .iter()
+                .filter(|&(_, _, &player, _, _)| player == owner)
+                .filter_map(
+                    |(id, &object_type, _, transform, health)| match object_type {
+                        ObjectType::Active(active_type) => {
+                            let mut state =
+                                ObjectState::new(active_type, transform, health.fraction());
+                            state.health = *healths.entry((owner, id.0)).or_insert(state.health);
+                            Some((id.0, state))
+                        }
+                        ObjectType::Inactive(_) => None,
+                    },
+                )
+                .collect();
+            (owner, state)
+        })
+        .collect();
+    healths.retain(|(player, id), _| {
+        states
+            .iter()
+            .any(|(owner, state)| owner == player && state.contains_key(id))
+    });
+
+    let sequence = replication.sequence;
+    replication.sequence += 1;
+
+    let peers: Vec<Player> = lockstep
+        .players()
+        .filter(|&player| player != local)
+        .collect();
+    replication
+        .senders
+        .retain(|(_, target), _| peers.contains(target));
+
+    for (owner, state) in states {
+        // Only the owner hashes its objects, see ToPlayers::Checksum.
+        let checksum_tick = checksum_tick.filter(|_| owner == local);
+        if let Some(tick) = checksum_tick {
+            hashes.send(StateHashedEvent::new(tick, owner, hash_state(&state)));
+        }
+
+        for &target in &peers {
+            let (baseline, data, complete) = replication
+                .senders
+                .entry((owner, target))
+                .or_default()
+                .send(sequence, &state);
+            outputs.send(
+                ToPlayers::Replication {
+                    player: local.to_num(),
+                    owner: owner.to_num(),
+                    target: target.to_num(),
+                    sequence,
+                    baseline,
+                    tick: checksum_tick.filter(|_| complete),
+                    data,
+                }
+                .into(),
+            );
+        }
+    }
+}
+
+fn receive(
+    players: Res<Players>,
+    mut replication: ResMut<Replication>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut replicas: Replicas,
+    mut outputs: EventWriter<ToPlayersEvent<false>>,
+    mut hashes: EventWriter<StateHashedEvent>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in inputs.iter() {
+        match *event.message() {
+            ToPlayers::ReplicationAck {
+                player,
+                owner,
+                target,
+                sequence,
+            } if target == local.to_num() => {
+                let (Ok(player), Ok(owner)) = (Player::try_from(player), Player::try_from(owner))
+                else {
+                    continue;
+                };
+                if let Some(sender) = replication.senders.get_mut(&(owner, player)) {
+                    sender.ack(sequence);
+                }
+            }
+            ToPlayers::Replication {
+                player,
+                owner,
+                target,
+                sequence,
+                baseline,
+                tick,
+                ref data,
+            } if target == local.to_num() => {
+                let (sender, owner) = match (Player::try_from(player), Player::try_from(owner)) {
+                    (Ok(sender), Ok(owner)) if owner != local => (sender, owner),
+                    _ => {
+                        warn!("Replicated state of an invalid player received.");
+                        continue;
+                    }
+                };
+                if replication.replicator(owner) != sender {
+                    warn!("{sender} is not allowed to replicate objects of {owner}.");
+                    continue;
+                }
+
+                let receiver = replication.receivers.entry(owner).or_default();
+                match receiver.receive(sequence, baseline, data) {
+                    Ok(is_latest) => {
+                        outputs.send(
+                            ToPlayers::ReplicationAck {
+                                player: local.to_num(),
+                                owner: owner.to_num(),
+                                target: player,
+                                sequence,
+                            }
+                            .into(),
+                        );
+
+                        if !is_latest {
+                            continue;
+                        }
+                    }
+                    Err(ReplicationError::UnknownBaseline) => {
+                        trace!("Replicated state of {owner} with an unknown baseline received.");
+                        continue;
+                    }
+                    Err(ReplicationError::Invalid(err)) => {
+                        warn!("Invalid replicated state of {owner} received: {err}");
+                        continue;
+                    }
+                }
+
+                let state = receiver.latest().unwrap().clone();
+                let replicated = replicas.apply(&mut replication.replicas, owner, &state);
+                if let Some(tick) = tick {
+                    hashes.send(StateHashedEvent::new(tick, owner, hash_state(&replicated)));
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Local replicas of objects of other players.
+#[derive(SystemParam)]
+struct Replicas<'w, 's> {
+    commands: Commands<'w, 's>,
+    time: Res<'w, Time>,
+    healths: Res<'w, InitialHealths>,
+    replicas: Query<'w, 's, (&'static mut InterpolationBuffer, &'static mut Health)>,
+}
+
+impl<'w, 's> Replicas<'w, 's> {
+    /// Updates, spawns or destroys local replicas of objects of a player
+    /// according to the latest received state. It returns the part of the
+    /// state whose replicas exist.
+    fn apply(
+        &mut self,
+        entities: &mut AHashMap<(Player, u32), Entity>,
+        owner: Player,
+        state: &State,
+    ) -> State {
+        entities.retain(|&(player, id), &mut entity| {
+            if player != owner || state.contains_key(&id) {
+                return true;
+            }
+            if let Ok((_, mut health)) = self.replicas.get_mut(entity) {
+                // Destroyed rather than despawned so that all book-keeping of
+                // destroyed objects takes place.
+                health.hit(f32::INFINITY);
+            }
+            false
+        });
+
+        let time = self.time.elapsed();
+        let mut replicated = State::new();
+        for (&id, object) in state {
+            let Some(object_type) = object.object_type() else {
+                warn!("Replicated object of an invalid type received.");
+                continue;
+            };
+
+            if let Some(&entity) = entities.get(&(owner, id)) {
+                if let Ok((mut buffer, mut health)) = self.replicas.get_mut(entity) {
+                    buffer.push(time, object.translation(), object.rotation());
+                    if health.fraction() != object.health() {
+                        health.set_fraction(object.health());
+                    }
+                    replicated.insert(id, *object);
+                }
+                continue;
+            }
+
+            let transform =
+                Transform::from_translation(object.translation()).with_rotation(object.rotation());
+            let mut health = self.healths.health(object_type).clone();
+            health.set_fraction(object.health());
+            let mut buffer = InterpolationBuffer::default();
+            buffer.push(time, transform.translation, transform.rotation);
+
+            let entity = self
+                .commands
+                .spawn((
+                    SpawnBundle::new(ObjectType::Active(object_type), transform),
+                    owner,
+                    health,
+                    buffer,
+                ))
+                .id();
+            entities.insert((owner, id), entity);
+            replicated.insert(id, *object);
+        }
+
+        replicated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::objects::{BuildingType, UnitType};
+
+    use super::*;
+
+    fn object(x: f32, health: f32) -> ObjectState {
+        ObjectState::new(
+            ActiveObjectType::Unit(UnitType::Attacker),
+            &Transform::from_xyz(x, 0., -1.),
+            health,
+        )
+    }
+
+    #[test]
+    fn test_object_state() {
+        let transform =
+            Transform::from_xyz(1.234, 0., -10.5).with_rotation(Quat::from_rotation_y(2.));
+        let state = ObjectState::new(
+            ActiveObjectType::Building(BuildingType::PowerHub),
+            &transform,
+            0.25,
+        );
+
+        assert_eq!(
+            state.object_type(),
+            Some(ActiveObjectType::Building(BuildingType::PowerHub))
+        );
+        assert!(state.translation().abs_diff_eq(transform.translation, 0.01));
+        assert!(state.rotation().abs_diff_eq(transform.rotation, 0.001));
+        assert!((state.health() - 0.25).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_delta() {
+        let mut first = State::new();
+        first.insert(1, object(1., 1.));
+        first.insert(2, object(2., 1.));
+        first.insert(3, object(3., 1.));
+
+        let delta = encode_delta(&State::new(), &first, 0);
+        assert_eq!(delta.state, first);
+        assert_eq!(delta.next_id, 0);
+        assert!(delta.complete);
+        assert_eq!(decode_delta(&State::new(), &delta.data).unwrap(), first);
+
+        let mut second = first.clone();
+        second.remove(&1);
+        second.insert(2, object(2.5, 1.));
+        second.insert(3, object(3., 0.5));
+        second.insert(7, object(7., 1.));
+
+        let delta = encode_delta(&first, &second, 0);
+        assert_eq!(delta.state, second);
+        assert_eq!(decode_delta(&first, &delta.data).unwrap(), second);
+        // Only changed fields are sent.
+        assert!(delta.data.len() < encode_delta(&State::new(), &second, 0).data.len());
+
+        assert!(decode_delta(&State::new(), &delta.data).is_err());
+        assert!(decode_delta(&first, &delta.data[..delta.data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_delta_limit() {
+        let target: State = (0..100).map(|id| (id, object(id as f32, 1.))).collect();
+
+        let first = encode_delta(&State::new(), &target, 0);
+        assert!(first.data.len() <= MAX_REPLICATION_LEN);
+        assert!(first.next_id > 0);
+        assert!(!first.complete);
+        assert!(first.state.len() < target.len());
+
+        let second = encode_delta(&first.state, &target, first.next_id);
+        assert!(second.state.contains_key(&first.next_id));
+        assert_eq!(
+            decode_delta(&first.state, &second.data).unwrap(),
+            second.state
+        );
+    }
+
+    #[test]
+    fn test_hash_state() {
+        let mut owned = State::new();
+        owned.insert(1, object(1., 1.));
+        owned.insert(2, object(2., 0.5));
+
+        // Replicas reconstructed from a complete delta match the owner.
+        let delta = encode_delta(&State::new(), &owned, 0);
+        let replicas = decode_delta(&State::new(), &delta.data).unwrap();
+        assert_eq!(hash_state(&replicas), hash_state(&owned));
+
+        let mut moved = replicas.clone();
+        moved.insert(1, object(1.5, 1.));
+        assert_ne!(hash_state(&moved), hash_state(&owned));
+
+        let mut damaged = replicas.clone();
+        damaged.insert(2, object(2., 0.25));
+        assert_ne!(hash_state(&damaged), hash_state(&owned));
+
+        let mut missing = replicas;
+        missing.remove(&2);
+        assert_ne!(hash_state(&missing), hash_state(&owned));
+    }
+
+    #[test]
+    fn test_replicator() {
+        let mut replication = Replication::default();
+        assert_eq!(replication.replicator(Player::Player2), Player::Player2);
+
+        replication
+            .replicators
+            .insert(Player::Player2, Player::Player1);
+        assert_eq!(replication.replicator(Player::Player2), Player::Player1);
+        assert_eq!(replication.replicator(Player::Player3), Player::Player3);
+    }
+
+    #[test]
+    fn test_peers() {
+        let mut state = State::new();
+        state.insert(1, object(1., 1.));
+
+        let mut sender = PeerSender::default();
+        let mut receiver = PeerReceiver::default();
+
+        let (baseline, data, complete) = sender.send(0, &state);
+        assert!(complete);
+        assert_eq!(baseline, None);
+        assert!(receiver.receive(0, baseline, &data).unwrap());
+        sender.ack(0);
+
+        state.insert(1, object(2., 1.));
+        let (first_baseline, first_data, _) = sender.send(1, &state);
+        assert_eq!(first_baseline, Some(0));
+        state.insert(2, object(3., 1.));
+        let (second_baseline, second_data, _) = sender.send(2, &state);
+        assert_eq!(second_baseline, Some(0));
+
+        // Out of order delivery.
+        assert!(receiver.receive(2, second_baseline, &second_data).unwrap());
+        assert!(!receiver.receive(1, first_baseline, &first_data).unwrap());
+        assert_eq!(receiver.latest(), Some(&state));
+
+        assert!(matches!(
+            receiver.receive(3, Some(10), &[]),
+            Err(ReplicationError::UnknownBaseline)
+        ));
+    }
+}
@@ -0,0 +1,573 @@
+//! Full game state snapshots used to resynchronize players.
+//!
+//! A player requests the game state after rejoining the game or after its
+//! simulation diverged from the simulation of another player. A single peer
+//! (the one with the lowest ID apart from the requesting player) serializes
+//! its replicas of all active objects of the requesting player, splits the
+//! snapshot into fragments and sends them to the requesting player.
+//!
+//! The requesting player accepts snapshot fragments only from that peer and
+//! only while its request is pending. It reassembles the fragments, destroys
+//! all its own active objects and spawns the objects from the snapshot
+//! instead. Objects of other players are replicated by their owners (see
+//! [`super::replication`]) and are left intact.
+
+use bevy::prelude::*;
+use bincode::{
+    config::{Configuration, Limit, LittleEndian, Varint},
+    decode_from_slice, encode_to_vec, Decode, Encode,
+};
+use de_core::{
+    baseset::GameSet,
+    objects::{Active, ActiveObjectType, ObjectType},
+    player::Player,
+};
+use de_net::{ToGame, ToPlayers, MAX_SNAPSHOT_FRAGMENT_LEN};
+use de_objects::{Health, InitialHealths};
+use de_spawner::SpawnBundle;
+use enum_map::Enum;
+
+use super::{lockstep::Lockstep, DesyncDetectedEvent, Players, ResyncRequestedEvent};
+use crate::{
+    messages::{FromPlayersEvent, MessagesSet, ToGameServerEvent, ToPlayersEvent},
+    netstate::NetState,
+};
+
+/// Maximum number of fragments of a single snapshot.
+const MAX_FRAGMENTS: u16 = 4096;
+const MAX_SNAPSHOT_LEN: usize = MAX_FRAGMENTS as usize * MAX_SNAPSHOT_FRAGMENT_LEN;
+const BINCODE_CONF: Configuration<LittleEndian, Varint, Limit<MAX_SNAPSHOT_LEN>> =
+    bincode::config::standard().with_limit::<MAX_SNAPSHOT_LEN>();
+
+pub(super) struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResyncedEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                request_after_rejoin
+                    .in_schedule(OnExit(NetState::Rejoining))
+                    // The state is already updated, thus this holds only
+                    // after a successful rejoin.
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(resource_exists::<Resync>()),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<Resync>())
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                request
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Resync>())
+                    .run_if(on_event::<DesyncDetectedEvent>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                send.in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Lockstep>())
+                    .run_if(on_event::<ResyncRequestedEvent>())
+                    .before(MessagesSet::SendMessages),
+            );
+    }
+}
+
+/// This event is sent after all active objects of the local player were
+/// replaced by objects from a game state snapshot received from another
+/// player.
+pub struct ResyncedEvent {
+    tick: u32,
+}
+
+impl ResyncedEvent {
+    /// The snapshot captured the state right before this lockstep tick.
+    pub fn tick(&self) -> u32 {
+        self.tick
+    }
+}
+
+#[derive(Resource, Default)]
+struct Resync {
+    /// True if a snapshot was requested and it was not yet received.
+    /// Snapshots are accepted only while this is true.
+    requested: bool,
+    incoming: Option<Fragments>,
+}
+
+/// Fragments of a partially received snapshot.
+struct Fragments {
+    tick: u32,
+    fragments: Vec<Option<Vec<u8>>>,
+    missing: usize,
+}
+
+impl Fragments {
+    fn new(tick: u32, count: u16) -> Self {
+        Self {
+            tick,
+            fragments: vec![None; usize::from(count)],
+            missing: usize::from(count),
+        }
+    }
+
+    /// Stores a fragment. Redelivered fragments are ignored.
+    fn insert(&mut self, index: u16, data: Vec<u8>) {
+        let fragment = &mut self.fragments[usize::from(index)];
+        if fragment.is_none() {
+            *fragment = Some(data);
+            self.missing -= 1;
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.missing == 0
+    }
+
+    /// Concatenates all fragments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if not all fragments were received.
+    fn join(self) -> Vec<u8> {
+        self.fragments
+            .into_iter()
+            .flat_map(|fragment| fragment.unwrap())
+            .collect()
+    }
+}
+
+impl Resync {
+    /// Stores a received snapshot fragment. A fragment of a snapshot of a
+    /// different tick than the currently assembled snapshot starts a new
+    /// snapshot.
+    ///
+    /// It returns the tick and the data of the snapshot once all of its
+    /// fragments are received.
+    fn insert(
+        &mut self,
+        tick: u32,
+        index: u16,
+        count: u16,
+        data: Vec<u8>,
+    ) -> Result<Option<(u32, Vec<u8>)>, FragmentError> {
+        if !self.requested {
+            return Err(FragmentError::NotRequested);
+        }
+        if count == 0 || count > MAX_FRAGMENTS {
+            return Err(FragmentError::InvalidCount);
+        }
+        if index >= count {
+            return Err(FragmentError::InvalidIndex);
+        }
+        if data.len() > MAX_SNAPSHOT_FRAGMENT_LEN {
+            return Err(FragmentError::TooLong);
+        }
+
+        let incoming = match self.incoming {
+            Some(ref mut incoming)
+                if incoming.tick == tick && incoming.fragments.len() == usize::from(count) =>
+            {
+                incoming
+            }
+            _ => self.incoming.insert(Fragments::new(tick, count)),
+        };
+
+        incoming.insert(index, data);
+        if !incoming.is_complete() {
+            return Ok(None);
+        }
+
+        self.requested = false;
+        let incoming = self.incoming.take().unwrap();
+        Ok(Some((incoming.tick, incoming.join())))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum FragmentError {
+    NotRequested,
+    InvalidCount,
+    InvalidIndex,
+    TooLong,
+}
+
+/// Serialized state of a single active object.
+#[derive(Encode, Decode, Debug, PartialEq)]
+struct ObjectSnapshot {
+    object_type: u8,
+    player: u8,
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    health: f32,
+}
+
+impl ObjectSnapshot {
+    fn new(
+        object_type: ActiveObjectType,
+        player: Player,
+        transform: &Transform,
+        health: f32,
+    ) -> Self {
+        Self {
+            // There is only a handful of object types.
+            object_type: object_type.into_usize() as u8,
+            player: player.to_num(),
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            health,
+        }
+    }
+
+    fn object_type(&self) -> Result<ActiveObjectType, String> {
+        let index = usize::from(self.object_type);
+        if index >= ActiveObjectType::LENGTH {
+            return Err(format!("Invalid object type {index}."));
+        }
+        Ok(ActiveObjectType::from_usize(index))
+    }
+
+    fn player(&self) -> Result<Player, String> {
+        Player::try_from(self.player).map_err(|err| format!("Invalid player: {err:?}"))
+    }
+
+    fn transform(&self) -> Result<Transform, String> {
+        let translation = Vec3::from_array(self.translation);
+        let rotation = Quat::from_array(self.rotation);
+        if !translation.is_finite() || !rotation.is_normalized() {
+            return Err("Invalid object transform.".to_owned());
+        }
+        Ok(Transform {
+            translation,
+            rotation,
+            ..Default::default()
+        })
+    }
+
+    fn health(&self) -> Result<f32, String> {
+        if !(0. ..=1.).contains(&self.health) {
+            return Err(format!("Invalid health fraction {}.", self.health));
+        }
+        Ok(self.health)
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.init_resource::<Resync>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<Resync>();
+}
+
+/// Returns the player who sends the game state to `target`.
+fn responder(lockstep: &Lockstep, target: Player) -> Option<Player> {
+    lockstep.players().filter(|&player| player != target).min()
+}
+
+/// Requests the game state after the local player rejoined the game.
+fn request_after_rejoin(
+    mut resync: ResMut<Resync>,
+    mut server: EventWriter<ToGameServerEvent<true>>,
+) {
+    info!("Requesting game state after a rejoin.");
+    resync.requested = true;
+    resync.incoming = None;
+    server.send(ToGame::RequestResync.into());
+}
+
+/// Requests the game state if the local simulation diverged from the
+/// simulation of a player with a lower ID.
+fn request(
+    players: Res<Players>,
+    mut resync: ResMut<Resync>,
+    mut desyncs: EventReader<DesyncDetectedEvent>,
+    mut server: EventWriter<ToGameServerEvent<true>>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    // Players with lower IDs are considered authoritative, otherwise both
+    // players would replace their state with the state of the other player.
+    if desyncs.iter().any(|event| event.player() < local) && !resync.requested {
+        info!("Requesting game state after a desync.");
+        resync.requested = true;
+        server.send(ToGame::RequestResync.into());
+    }
+}
+
+/// Sends the state of objects of players who requested it. Only the player
+/// with the lowest ID (apart from the requesting player) responds.
+fn send(
+    players: Res<Players>,
+    lockstep: Res<Lockstep>,
+    mut requests: EventReader<ResyncRequestedEvent>,
+    objects: Query<(&ObjectType, &Player, &Transform, &Health), With<Active>>,
+    mut outputs: EventWriter<ToPlayersEvent<true>>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in requests.iter() {
+        let target = event.player();
+        if responder(lockstep.as_ref(), target) != Some(local) {
+            continue;
+        }
+
+        let snapshot: Vec<ObjectSnapshot> = objects
+            .iter()
+            .filter(|(_, &player, _, _)| player == target)
+            .filter_map(
+                |(&object_type, &player, transform, health)| match object_type {
+                    ObjectType::Active(active_type) => Some(ObjectSnapshot::new(
+                        active_type,
+                        player,
+                        transform,
+                        health.fraction(),
+                    )),
+                    ObjectType::Inactive(_) => None,
+                },
+            )
+            .collect();
+        let data = encode_to_vec(snapshot, BINCODE_CONF).unwrap();
+        if data.len() > MAX_SNAPSHOT_LEN {
+            warn!("Game state is too large to be sent.");
+            continue;
+        }
+
+        let count = data
+            .chunks(MAX_SNAPSHOT_FRAGMENT_LEN)
+            .len()
+            .max(1)
+            .try_into()
+            .unwrap();
+        let tick = lockstep.next_tick();
+        info!("Sending game state to {target} in {count} fragments.");
+
+        for index in 0..count {
+            let start = usize::from(index) * MAX_SNAPSHOT_FRAGMENT_LEN;
+            let end = (start + MAX_SNAPSHOT_FRAGMENT_LEN).min(data.len());
+            outputs.send(
+                ToPlayers::Snapshot {
+                    player: local.to_num(),
+                    target: target.to_num(),
+                    tick,
+                    index,
+                    count,
+                    data: data[start..end].to_vec(),
+                }
+                .into(),
+            );
+        }
+    }
+}
+
+fn receive(
+    mut commands: Commands,
+    lockstep: Res<Lockstep>,
+    mut resync: ResMut<Resync>,
+    healths: Res<InitialHealths>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut objects: Query<(&Player, &mut Health), With<Active>>,
+    mut outputs: EventWriter<ResyncedEvent>,
+) {
+    let local = lockstep.local();
+
+    for event in inputs.iter() {
+        let ToPlayers::Snapshot {
+            player,
+            target,
+            tick,
+            index,
+            count,
+            ref data,
+        } = *event.message()
+        else {
+            continue;
+        };
+        if target != local.to_num() {
+            continue;
+        }
+        // The sender was verified by the game server.
+        if responder(lockstep.as_ref(), local).map(|player| player.to_num()) != Some(player) {
+            warn!("Game state fragment received from Player {player}, who was not asked.");
+            continue;
+        }
+
+        let (tick, data) = match resync.insert(tick, index, count, data.clone()) {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => continue,
+            Err(err) => {
+                warn!("Invalid game state fragment received: {err:?}");
+                continue;
+            }
+        };
+
+        let snapshot: Vec<ObjectSnapshot> = match decode_from_slice(&data, BINCODE_CONF) {
+            Ok((snapshot, _)) => snapshot,
+            Err(err) => {
+                warn!("Invalid game state received: {err:?}");
+                continue;
+            }
+        };
+
+        let mut bundles = Vec::with_capacity(snapshot.len());
+        for object in &snapshot {
+            match decode_object(object, local, healths.as_ref()) {
+                Ok(bundle) => bundles.push(bundle),
+                Err(err) => {
+                    warn!("Invalid object in a game state received: {err}");
+                    bundles.clear();
+                    break;
+                }
+            }
+        }
+        if bundles.len() != snapshot.len() {
+            continue;
+        }
+
+        info!(
+            "Replacing local objects with a snapshot of {} objects.",
+            bundles.len()
+        );
+        // Objects are destroyed (rather than despawned) so that all
+        // book-keeping of destroyed objects takes place.
+        for (_, mut health) in objects.iter_mut().filter(|(&owner, _)| owner == local) {
+            health.hit(f32::INFINITY);
+        }
+        for bundle in bundles {
+            commands.spawn(bundle);
+        }
+        outputs.send(ResyncedEvent { tick });
+    }
+}
+
+/// Decodes an object of the player `local` from a snapshot.
+fn decode_object(
+    object: &ObjectSnapshot,
+    local: Player,
+    healths: &InitialHealths,
+) -> Result<(SpawnBundle, Player, Health), String> {
+    let player = object.player()?;
+    if player != local {
+        return Err(format!("Object of another player {player}."));
+    }
+
+    let object_type = object.object_type()?;
+    let mut health = healths.health(object_type).clone();
+    health.set_fraction(object.health()?);
+
+    Ok((
+        SpawnBundle::new(ObjectType::Active(object_type), object.transform()?),
+        player,
+        health,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::objects::{BuildingType, UnitType};
+
+    use super::*;
+
+    #[test]
+    fn test_resync() {
+        let mut resync = Resync::default();
+        assert_eq!(
+            resync.insert(1, 0, 1, vec![1]),
+            Err(FragmentError::NotRequested)
+        );
+
+        resync.requested = true;
+
+        assert_eq!(
+            resync.insert(1, 0, 0, vec![]),
+            Err(FragmentError::InvalidCount)
+        );
+        assert_eq!(
+            resync.insert(1, 2, 2, vec![]),
+            Err(FragmentError::InvalidIndex)
+        );
+        assert_eq!(
+            resync.insert(1, 0, 2, vec![0; MAX_SNAPSHOT_FRAGMENT_LEN + 1]),
+            Err(FragmentError::TooLong)
+        );
+
+        assert_eq!(resync.insert(1, 1, 2, vec![3, 4]), Ok(None));
+        assert_eq!(resync.insert(1, 1, 2, vec![3, 4]), Ok(None));
+        // A newer snapshot replaces the incomplete one.
+        assert_eq!(resync.insert(2, 1, 2, vec![5]), Ok(None));
+        assert!(resync.requested);
+        assert_eq!(
+            resync.insert(2, 0, 2, vec![1, 2]),
+            Ok(Some((2, vec![1, 2, 5])))
+        );
+        assert!(!resync.requested);
+        assert!(resync.incoming.is_none());
+
+        // Only a single snapshot is accepted per request.
+        assert_eq!(
+            resync.insert(2, 0, 1, vec![1]),
+            Err(FragmentError::NotRequested)
+        );
+    }
+
+    #[test]
+    fn test_object_snapshot() {
+        let transform = Transform {
+            translation: Vec3::new(1., 2., -3.),
+            rotation: Quat::from_rotation_y(1.),
+            ..Default::default()
+        };
+        let snapshot = vec![
+            ObjectSnapshot::new(
+                ActiveObjectType::Unit(UnitType::Attacker),
+                Player::Player2,
+                &transform,
+                0.5,
+            ),
+            ObjectSnapshot::new(
+                ActiveObjectType::Building(BuildingType::PowerHub),
+                Player::Player1,
+                &Transform::IDENTITY,
+                1.,
+            ),
+        ];
+
+        let data = encode_to_vec(&snapshot, BINCODE_CONF).unwrap();
+        let (decoded, _): (Vec<ObjectSnapshot>, _) =
+            decode_from_slice(&data, BINCODE_CONF).unwrap();
+        assert_eq!(decoded, snapshot);
+
+        assert_eq!(
+            decoded[0].object_type().unwrap(),
+            ActiveObjectType::Unit(UnitType::Attacker)
+        );
+        assert_eq!(
+            decoded[1].object_type().unwrap(),
+            ActiveObjectType::Building(BuildingType::PowerHub)
+        );
+        assert_eq!(decoded[0].player().unwrap(), Player::Player2);
+        assert_eq!(decoded[0].transform().unwrap(), transform);
+        assert_eq!(decoded[0].health().unwrap(), 0.5);
+
+        let invalid = ObjectSnapshot {
+            object_type: u8::MAX,
+            player: 0,
+            translation: [f32::NAN, 0., 0.],
+            rotation: [0., 0., 0., 1.],
+            health: 2.,
+        };
+        assert!(invalid.object_type().is_err());
+        assert!(invalid.player().is_err());
+        assert!(invalid.transform().is_err());
+        assert!(invalid.health().is_err());
+    }
+}
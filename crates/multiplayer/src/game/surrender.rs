@@ -0,0 +1,151 @@
+//! Giving up of a multiplayer game.
+//!
+//! A surrender is sent to the game server which marks the player as defeated
+//! and relays the surrender to all clients, including the surrendering one.
+//! All clients then destroy all objects of the defeated player.
+
+use ahash::AHashSet;
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, objects::Active, player::Player};
+use de_net::{FromGame, ToGame};
+use de_objects::Health;
+
+use super::Players;
+use crate::{
+    messages::{FromGameServerEvent, MessagesSet, ToGameServerEvent},
+    netstate::NetState,
+};
+
+pub(super) struct SurrenderPlugin;
+
+impl Plugin for SurrenderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SurrenderEvent>()
+            .add_event::<PlayerSurrenderedEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                send.in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Players>())
+                    .run_if(on_event::<SurrenderEvent>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<DefeatedPlayers>())
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .after(MessagesSet::RecvMessages),
+            );
+    }
+}
+
+/// Send this event to give up the multiplayer game by the local player.
+pub struct SurrenderEvent;
+
+/// This event is sent when a player (including the local player) gives up
+/// the game. All objects of the player are destroyed at that point.
+pub struct PlayerSurrenderedEvent(Player);
+
+impl PlayerSurrenderedEvent {
+    pub fn player(&self) -> Player {
+        self.0
+    }
+}
+
+/// Players who gave up the current multiplayer game.
+///
+/// This resource exists once the local client joins a multiplayer game.
+#[derive(Resource, Default)]
+pub struct DefeatedPlayers(AHashSet<Player>);
+
+impl DefeatedPlayers {
+    pub fn contains(&self, player: Player) -> bool {
+        self.0.contains(&player)
+    }
+
+    /// Marks a player as defeated. It returns false if the player was
+    /// already defeated.
+    fn insert(&mut self, player: Player) -> bool {
+        self.0.insert(player)
+    }
+}
+
+fn setup(mut commands: Commands) {
+    // Defeated players are kept when rejoining the game.
+    commands.init_resource::<DefeatedPlayers>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<DefeatedPlayers>();
+}
+
+fn send(
+    players: Res<Players>,
+    mut events: EventReader<SurrenderEvent>,
+    mut server: EventWriter<ToGameServerEvent<true>>,
+) {
+    // Multiple requests are merged into one.
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    if !players.is_controlling() {
+        warn!("Cannot surrender the game, no player is controlled.");
+        return;
+    }
+
+    info!("Surrendering the game.");
+    server.send(ToGame::Surrender.into());
+}
+
+fn receive(
+    mut defeated: ResMut<DefeatedPlayers>,
+    mut inputs: EventReader<FromGameServerEvent>,
+    mut objects: Query<(&Player, &mut Health), With<Active>>,
+    mut outputs: EventWriter<PlayerSurrenderedEvent>,
+) {
+    for event in inputs.iter() {
+        let FromGame::PlayerSurrendered(id) = *event.message() else {
+            continue;
+        };
+
+        let player = match Player::try_from(id) {
+            Ok(player) => player,
+            Err(err) => {
+                warn!("Invalid player surrendered the game: {err:?}");
+                continue;
+            }
+        };
+
+        if !defeated.insert(player) {
+            continue;
+        }
+
+        info!("{player} surrendered the game.");
+        // Objects are destroyed (rather than despawned) so that all
+        // the usual destruction handling takes place.
+        for (&owner, mut health) in objects.iter_mut() {
+            if owner == player {
+                health.hit(f32::INFINITY);
+            }
+        }
+        outputs.send(PlayerSurrenderedEvent(player));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defeated() {
+        let mut defeated = DefeatedPlayers::default();
+        assert!(!defeated.contains(Player::Player2));
+
+        assert!(defeated.insert(Player::Player2));
+        assert!(!defeated.insert(Player::Player2));
+        assert!(defeated.contains(Player::Player2));
+        assert!(!defeated.contains(Player::Player1));
+    }
+}
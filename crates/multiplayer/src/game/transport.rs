@@ -0,0 +1,62 @@
+//! Scheduling of transport commands (boarding and unloading of carriers) of
+//! the local player.
+//!
+//! The commands are scheduled with the lockstep simulation and executed once
+//! the tick they were stamped with is simulated, see [`super::commands`].
+
+use bevy::prelude::*;
+use de_core::baseset::GameSet;
+use de_net::MAX_COMMANDS_LEN;
+use de_transport::{LoadEvent, ScheduledTransport, TransportCommand, UnloadEvent};
+
+use super::lockstep::{Lockstep, ScheduleCommandsEvent};
+use crate::netstate::NetState;
+
+pub(super) struct TransportPlugin;
+
+impl Plugin for TransportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.run_if(resource_added::<Lockstep>()))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                schedule
+                    .in_base_set(GameSet::Update)
+                    .run_if(resource_exists::<Lockstep>()),
+            );
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(ScheduledTransport);
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ScheduledTransport>();
+}
+
+fn schedule(
+    mut load_events: EventReader<LoadEvent>,
+    mut unload_events: EventReader<UnloadEvent>,
+    mut out_events: EventWriter<ScheduleCommandsEvent>,
+) {
+    let commands = load_events
+        .iter()
+        .map(|event| TransportCommand::Load {
+            unit: event.unit(),
+            carrier: event.carrier(),
+        })
+        .chain(unload_events.iter().map(|event| TransportCommand::Unload {
+            carrier: event.carrier(),
+        }));
+
+    let mut buf = Vec::new();
+    for command in commands {
+        if buf.len() + TransportCommand::MAX_ENCODED_LEN > MAX_COMMANDS_LEN {
+            out_events.send(ScheduleCommandsEvent::new(std::mem::take(&mut buf)));
+        }
+        command.encode(&mut buf);
+    }
+    if !buf.is_empty() {
+        out_events.send(ScheduleCommandsEvent::new(buf));
+    }
+}
@@ -8,21 +8,42 @@
 //! down via [`ShutdownMultiplayerEvent`].
 
 use bevy::{app::PluginGroupBuilder, prelude::*};
+use chat::ChatPlugin;
+use clock::ClockPlugin;
+pub use de_net::{
+    ChatChannel, DropPolicy, GiveUp, PasswordHash, ResendPolicy, MAX_CHAT_LEN, MAX_COMMANDS_LEN,
+};
 use game::GamePlugin;
 use lifecycle::LifecyclePlugin;
+use maps::MapsPlugin;
 use messages::MessagesPlugin;
 use stats::StatsPlugin;
 
 pub use crate::{
-    config::{NetGameConf, ServerPort},
+    chat::{ChatEntry, ChatLog, ChatReceivedEvent, SendChatEvent},
+    clock::GameClock,
+    config::{NetGameConf, SendRates, ServerPort},
+    game::{
+        AllianceFormedEvent, AllianceProposedEvent, DefeatedPlayers, DesyncDetectedEvent,
+        GamePausedEvent, GameResumedEvent, InterpolationBuffer, LockstepTickEvent,
+        PauseRequestEvent, PlayerDroppedEvent, PlayerLeftEvent, PlayerSurrenderedEvent, Players,
+        ProposeAllianceEvent, Replay, ReplayError, ReplayHeader, ReplayTick, Replicated,
+        ResyncRequestedEvent, ResyncedEvent, ScheduleCommandsEvent, SurrenderEvent,
+    },
     lifecycle::{ShutdownMultiplayerEvent, StartMultiplayerEvent},
+    maps::GameReadyEvent,
     netstate::NetState,
+    network::DeliveryFailedEvent,
+    stats::{NetStatsEvent, PeerStatsEvent},
 };
 use crate::{netstate::NetStatePlugin, network::NetworkPlugin};
 
+mod chat;
+mod clock;
 mod config;
 mod game;
 mod lifecycle;
+mod maps;
 mod messages;
 mod netstate;
 mod network;
@@ -39,5 +60,8 @@ impl PluginGroup for MultiplayerPluginGroup {
             .add(MessagesPlugin)
             .add(GamePlugin)
             .add(StatsPlugin)
+            .add(ClockPlugin)
+            .add(ChatPlugin)
+            .add(MapsPlugin)
     }
 }
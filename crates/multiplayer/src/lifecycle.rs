@@ -1,10 +1,18 @@
-use std::ops::Deref;
+use std::{fmt, ops::Deref};
 
 use bevy::prelude::*;
-use de_core::{gresult::GameResult, state::AppState};
+use de_core::{baseset::GameSet, gresult::GameResult, state::AppState};
 use de_gui::ToastEvent;
+use de_loc::Localize;
+use de_net::GiveUp;
 
-use crate::{config::NetGameConf, NetState};
+use crate::{
+    config::NetGameConf,
+    game::Players,
+    messages::Ports,
+    network::{DeliveryFailedEvent, NetworkSet, PeerDisconnectedEvent},
+    NetState,
+};
 
 pub(super) struct LifecyclePlugin;
 
@@ -40,6 +48,23 @@ impl Plugin for LifecyclePlugin {
                 errors
                     .run_if(not(in_state(NetState::None)))
                     .run_if(on_event::<FatalErrorEvent>()),
+            )
+            .add_system(
+                disconnected
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Ports>())
+                    .run_if(not(in_state(NetState::ShuttingDown)))
+                    .run_if(on_event::<PeerDisconnectedEvent>())
+                    .after(NetworkSet::RecvErrors),
+            )
+            .add_system(
+                delivery_failed
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<NetGameConfRes>())
+                    .run_if(resource_exists::<Ports>())
+                    .run_if(not(in_state(NetState::ShuttingDown)))
+                    .run_if(on_event::<DeliveryFailedEvent>())
+                    .after(NetworkSet::RecvErrors),
             );
     }
 }
@@ -65,11 +90,37 @@ pub struct ShutdownMultiplayerEvent;
 /// which prevents further continuation of multiplayer game.
 ///
 /// An error will be displayed to the user and multiplayer will shut down.
-pub(crate) struct FatalErrorEvent(String);
+pub(crate) struct FatalErrorEvent {
+    /// Identifier of the localized error message.
+    id: &'static str,
+    args: Vec<(&'static str, String)>,
+}
 
 impl FatalErrorEvent {
-    pub(crate) fn new(message: impl ToString) -> Self {
-        Self(message.to_string())
+    /// # Arguments
+    ///
+    /// * `id` - identifier of the localized error message, see
+    ///   [`Localize`].
+    pub(crate) fn new(id: &'static str) -> Self {
+        Self {
+            id,
+            args: Vec::new(),
+        }
+    }
+
+    /// Sets a variable of the localized error message.
+    pub(crate) fn with_arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    fn message(&self, localize: &Localize) -> String {
+        let args: Vec<(&str, &dyn fmt::Display)> = self
+            .args
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn fmt::Display))
+            .collect();
+        localize.format(self.id, &args)
     }
 }
 
@@ -114,20 +165,82 @@ fn errors(
     mut events: EventReader<FatalErrorEvent>,
     mut toasts: EventWriter<ToastEvent>,
     mut shutdowns: EventWriter<ShutdownMultiplayerEvent>,
+    localize: Res<Localize>,
 ) {
     let Some(event) = events.iter().next() else {
         return;
     };
 
-    error!("Fatal multiplayer error: {}", event.0);
-    toasts.send(ToastEvent::new(&event.0));
+    let message = event.message(localize.as_ref());
+    error!("Fatal multiplayer error: {message}");
+    toasts.send(ToastEvent::new(&message));
     shutdowns.send(ShutdownMultiplayerEvent);
 
-    commands.insert_resource(GameResult::error(&event.0));
+    commands.insert_resource(GameResult::error(&message));
 
     events.clear();
 }
 
+fn disconnected(
+    ports: Res<Ports>,
+    state: Res<State<NetState>>,
+    players: Option<Res<Players>>,
+    mut next_state: ResMut<NextState<NetState>>,
+    mut events: EventReader<PeerDisconnectedEvent>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    for event in events.iter() {
+        // Until the game port is known, the client communicates only with the
+        // main server.
+        let port = event.addr().port();
+        if ports.game().map_or(true, |game| game == port) {
+            if can_rejoin(state.0, players.as_deref()) {
+                warn!("Connection to the game server was lost, rejoining...");
+                next_state.set(NetState::Rejoining);
+            } else {
+                fatals.send(FatalErrorEvent::new("mp-error-connection-lost"));
+            }
+        }
+    }
+}
+
+fn delivery_failed(
+    conf: Res<NetGameConfRes>,
+    ports: Res<Ports>,
+    state: Res<State<NetState>>,
+    players: Option<Res<Players>>,
+    mut next_state: ResMut<NextState<NetState>>,
+    mut events: EventReader<DeliveryFailedEvent>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    for event in events.iter() {
+        match conf.resend_policy().give_up() {
+            GiveUp::DropPackage => {
+                warn!("A message to {:?} could not be delivered.", event.addr());
+            }
+            GiveUp::DropPeer => {
+                if ports.game() == Some(event.addr().port())
+                    && can_rejoin(state.0, players.as_deref())
+                {
+                    warn!("Connection error with the game server, rejoining...");
+                    next_state.set(NetState::Rejoining);
+                } else {
+                    fatals.send(
+                        FatalErrorEvent::new("mp-error-connection")
+                            .with_arg("address", format!("{:?}", event.addr())),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Returns true if connection to the game server may be restored via
+/// [`de_net::ToGame::Rejoin`].
+fn can_rejoin(state: NetState, players: Option<&Players>) -> bool {
+    state == NetState::Joined && players.map_or(false, |players| players.token().is_some())
+}
+
 fn game_left(mut shutdowns: EventWriter<ShutdownMultiplayerEvent>) {
     shutdowns.send(ShutdownMultiplayerEvent);
 }
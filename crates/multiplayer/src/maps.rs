@@ -0,0 +1,563 @@
+//! Transfer of map files between players.
+//!
+//! A player who joins a game whose map is not available locally requests the
+//! map from other players. A single peer (the one with the lowest ID in the
+//! game lobby) splits the map file into fragments and sends them to the
+//! requesting player. The requesting player reassembles the fragments,
+//! verifies the hash of the map and stores it to the map cache directory.
+
+use std::{
+    path::PathBuf as SyncPathBuf,
+    time::{Duration, Instant},
+};
+
+use async_std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task},
+};
+use de_core::{
+    assets::asset_path,
+    baseset::GameSet,
+    fs::{maps_cache_dir, DirError},
+    player::Player,
+};
+use de_map::{
+    hash::MapHash,
+    io::{load_map, MapLoadingError},
+};
+use de_net::{FromGame, ToPlayers, MAX_MAP_FRAGMENT_LEN};
+use futures_lite::future;
+use thiserror::Error;
+
+use crate::{
+    game::Players,
+    lifecycle::{FatalErrorEvent, NetGameConfRes},
+    messages::{FromGameServerEvent, FromPlayersEvent, MessagesSet, ToPlayersEvent},
+    netstate::NetState,
+};
+
+/// Maximum size of a transferred map file in bytes. It is limited by the
+/// maximum number of fragments.
+const MAX_MAP_FILE_LEN: usize = u16::MAX as usize * MAX_MAP_FRAGMENT_LEN;
+/// Map download is abandoned if no fragment is received for this long.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub(crate) struct MapsPlugin;
+
+impl Plugin for MapsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameReadyEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+            .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                track_game
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<GameMap>())
+                    .run_if(on_event::<FromGameServerEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                locate
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<GameMap>()),
+            )
+            .add_system(
+                receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<GameMap>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                verify
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<GameMap>()),
+            )
+            .add_system(
+                accept_requests
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(resource_exists::<GameMap>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                send.in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<Uploads>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                announce
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<GameMap>()),
+            );
+    }
+}
+
+/// This event is sent once the joined game has started and its map is
+/// available locally. The map is configured with
+/// [`crate::NetGameConf::with_map`].
+///
+/// The game should not proceed to loading before this event is received.
+pub struct GameReadyEvent {
+    map_path: SyncPathBuf,
+}
+
+impl GameReadyEvent {
+    /// Path to the map file of the game. The map was either found among
+    /// the bundled maps or downloaded from another player.
+    pub fn map_path(&self) -> &std::path::Path {
+        self.map_path.as_path()
+    }
+}
+
+/// Map of the joined game and the state of its local availability.
+#[derive(Resource)]
+struct GameMap {
+    hash: MapHash,
+    status: MapStatus,
+    /// Players in the game lobby.
+    lobby: Vec<Player>,
+    started: bool,
+    announced: bool,
+}
+
+enum MapStatus {
+    Locating(Task<Option<PathBuf>>),
+    Downloading {
+        incoming: Option<Fragments>,
+        deadline: Instant,
+    },
+    Verifying(Task<Result<PathBuf, DownloadError>>),
+    Ready(PathBuf),
+    Failed,
+}
+
+/// Map files being prepared for sending to players who requested them.
+#[derive(Resource, Default)]
+struct Uploads(Vec<(Player, Task<Option<Vec<u8>>>)>);
+
+/// Fragments of a partially received map file.
+struct Fragments {
+    fragments: Vec<Option<Vec<u8>>>,
+    missing: usize,
+}
+
+impl Fragments {
+    fn new(count: u16) -> Self {
+        Self {
+            fragments: vec![None; usize::from(count)],
+            missing: usize::from(count),
+        }
+    }
+
+    /// Stores a received fragment. Redelivered fragments are ignored.
+    ///
+    /// It returns the whole file once all of its fragments are received.
+    fn insert(
+        &mut self,
+        index: u16,
+        count: u16,
+        data: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, FragmentError> {
+        if usize::from(count) != self.fragments.len() {
+            return Err(FragmentError::InvalidCount);
+        }
+        if index >= count {
+            return Err(FragmentError::InvalidIndex);
+        }
+        if data.len() > MAX_MAP_FRAGMENT_LEN {
+            return Err(FragmentError::TooLong);
+        }
+
+        let fragment = &mut self.fragments[usize::from(index)];
+        if fragment.is_none() {
+            *fragment = Some(data);
+            self.missing -= 1;
+        }
+
+        if self.missing > 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            self.fragments
+                .iter_mut()
+                .flat_map(|fragment| fragment.take().unwrap())
+                .collect(),
+        ))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum FragmentError {
+    InvalidCount,
+    InvalidIndex,
+    TooLong,
+}
+
+#[derive(Error, Debug)]
+enum DownloadError {
+    #[error(transparent)]
+    Dir(#[from] DirError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid map received")]
+    Loading(#[from] MapLoadingError),
+    #[error("received map has a different hash {actual:?}")]
+    HashMismatch { actual: MapHash },
+}
+
+fn setup(mut commands: Commands, conf: Res<NetGameConfRes>, map: Option<Res<GameMap>>) {
+    // The map is kept after a rejoin.
+    if map.is_some() {
+        return;
+    }
+    let Some(hash) = conf.map() else {
+        return;
+    };
+
+    info!("Looking for map {}.", hash.to_hex());
+    commands.insert_resource(GameMap {
+        hash,
+        status: MapStatus::Locating(IoTaskPool::get().spawn(find_map(hash))),
+        lobby: Vec::new(),
+        started: false,
+        announced: false,
+    });
+    commands.init_resource::<Uploads>();
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<GameMap>();
+    commands.remove_resource::<Uploads>();
+}
+
+fn track_game(mut map: ResMut<GameMap>, mut inputs: EventReader<FromGameServerEvent>) {
+    for event in inputs.iter() {
+        match event.message() {
+            FromGame::LobbyState(state) => {
+                map.lobby.clear();
+                map.lobby.extend(
+                    state
+                        .players()
+                        .iter()
+                        .filter_map(|player| Player::try_from(player.id()).ok()),
+                );
+            }
+            FromGame::GameStarted => {
+                map.started = true;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Requests the map from other players if it is not available locally.
+fn locate(
+    players: Res<Players>,
+    mut map: ResMut<GameMap>,
+    mut outputs: EventWriter<ToPlayersEvent<true>>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    let MapStatus::Locating(ref mut task) = map.status else {
+        return;
+    };
+    let Some(result) = future::block_on(future::poll_once(task)) else {
+        return;
+    };
+
+    if let Some(path) = result {
+        info!("Map found at {}.", path.display());
+        map.status = MapStatus::Ready(path);
+        return;
+    }
+
+    // Spectators cannot send messages to other players.
+    let Some(local) = players.local().filter(|_| players.is_controlling()) else {
+        fatals.send(FatalErrorEvent::new("mp-error-map-unavailable"));
+        map.status = MapStatus::Failed;
+        return;
+    };
+
+    info!("Map not found, requesting it from other players.");
+    outputs.send(
+        ToPlayers::MapRequest {
+            player: local.to_num(),
+            hash: map.hash.to_bytes(),
+        }
+        .into(),
+    );
+    map.status = MapStatus::Downloading {
+        incoming: None,
+        deadline: Instant::now() + DOWNLOAD_TIMEOUT,
+    };
+}
+
+fn receive(
+    players: Res<Players>,
+    mut map: ResMut<GameMap>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
+    let hash = map.hash;
+    let MapStatus::Downloading {
+        ref mut incoming,
+        ref mut deadline,
+    } = map.status
+    else {
+        return;
+    };
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    let mut data = None;
+    for event in inputs.iter() {
+        let ToPlayers::MapFragment {
+            target,
+            index,
+            count,
+            data: ref fragment,
+        } = *event.message()
+        else {
+            continue;
+        };
+        if target != local.to_num() {
+            continue;
+        }
+        if count == 0 {
+            warn!(
+                "Invalid map fragment received: {:?}",
+                FragmentError::InvalidCount
+            );
+            continue;
+        }
+
+        *deadline = Instant::now() + DOWNLOAD_TIMEOUT;
+        let incoming = incoming.get_or_insert_with(|| Fragments::new(count));
+        match incoming.insert(index, count, fragment.clone()) {
+            Ok(Some(file)) => {
+                data = Some(file);
+                break;
+            }
+            Ok(None) => (),
+            Err(err) => warn!("Invalid map fragment received: {err:?}"),
+        }
+    }
+
+    if let Some(data) = data {
+        info!("Map received, verifying it.");
+        map.status = MapStatus::Verifying(IoTaskPool::get().spawn(store_download(hash, data)));
+    } else if Instant::now() > *deadline {
+        fatals.send(FatalErrorEvent::new("mp-error-map-download"));
+        map.status = MapStatus::Failed;
+    }
+}
+
+fn verify(mut map: ResMut<GameMap>, mut fatals: EventWriter<FatalErrorEvent>) {
+    let MapStatus::Verifying(ref mut task) = map.status else {
+        return;
+    };
+    let Some(result) = future::block_on(future::poll_once(task)) else {
+        return;
+    };
+
+    match result {
+        Ok(path) => {
+            info!("Map stored to {}.", path.display());
+            map.status = MapStatus::Ready(path);
+        }
+        Err(err) => {
+            fatals.send(FatalErrorEvent::new("mp-error-map-invalid").with_arg("error", err));
+            map.status = MapStatus::Failed;
+        }
+    }
+}
+
+/// Prepares the map for sending to players who requested it. Only the
+/// player with the lowest ID in the lobby (apart from the requesting player)
+/// responds.
+fn accept_requests(
+    players: Res<Players>,
+    map: Res<GameMap>,
+    mut uploads: ResMut<Uploads>,
+    mut inputs: EventReader<FromPlayersEvent>,
+) {
+    let Some(local) = players.local() else {
+        return;
+    };
+
+    for event in inputs.iter() {
+        let ToPlayers::MapRequest { player, hash } = *event.message() else {
+            continue;
+        };
+        let Ok(target) = Player::try_from(player) else {
+            continue;
+        };
+
+        let responder = map.lobby.iter().copied().filter(|&p| p != target).min();
+        if responder != Some(local) {
+            continue;
+        }
+
+        let hash = MapHash::from_bytes(hash);
+        if hash != map.hash {
+            warn!(
+                "Map {} requested by {target} is not the map of the game.",
+                hash.to_hex()
+            );
+            continue;
+        }
+
+        uploads
+            .0
+            .push((target, IoTaskPool::get().spawn(read_map(hash))));
+    }
+}
+
+fn send(mut uploads: ResMut<Uploads>, mut outputs: EventWriter<ToPlayersEvent<true>>) {
+    uploads.0.retain_mut(|(target, task)| {
+        let Some(result) = future::block_on(future::poll_once(task)) else {
+            return true;
+        };
+        let Some(data) = result else {
+            warn!("Map requested by {target} is not available.");
+            return false;
+        };
+        if data.len() > MAX_MAP_FILE_LEN {
+            warn!("Map is too large to be sent.");
+            return false;
+        }
+
+        let count = data
+            .chunks(MAX_MAP_FRAGMENT_LEN)
+            .len()
+            .max(1)
+            .try_into()
+            .unwrap();
+        info!("Sending map to {target} in {count} fragments.");
+
+        for index in 0..count {
+            let start = usize::from(index) * MAX_MAP_FRAGMENT_LEN;
+            let end = (start + MAX_MAP_FRAGMENT_LEN).min(data.len());
+            outputs.send(
+                ToPlayers::MapFragment {
+                    target: target.to_num(),
+                    index,
+                    count,
+                    data: data[start..end].to_vec(),
+                }
+                .into(),
+            );
+        }
+
+        false
+    });
+}
+
+fn announce(mut map: ResMut<GameMap>, mut events: EventWriter<GameReadyEvent>) {
+    if !map.started || map.announced {
+        return;
+    }
+    let MapStatus::Ready(ref path) = map.status else {
+        return;
+    };
+
+    events.send(GameReadyEvent {
+        map_path: path.clone().into(),
+    });
+    map.announced = true;
+}
+
+/// Returns path to the map with the given hash among bundled maps or among
+/// previously downloaded maps.
+async fn find_map(hash: MapHash) -> Option<PathBuf> {
+    let bundled = hash.construct_path(asset_path("maps"));
+    if bundled.is_file().await {
+        return Some(bundled);
+    }
+
+    match maps_cache_dir() {
+        Ok(dir) => {
+            let cached = hash.construct_path(dir);
+            cached.is_file().await.then_some(cached)
+        }
+        Err(err) => {
+            warn!("Maps cache directory is not available: {err}");
+            None
+        }
+    }
+}
+
+async fn read_map(hash: MapHash) -> Option<Vec<u8>> {
+    let path = find_map(hash).await?;
+    match fs::read(&path).await {
+        Ok(data) => Some(data),
+        Err(err) => {
+            warn!("Failed to read map {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Verifies a downloaded map file and stores it to the maps cache
+/// directory.
+async fn store_download(hash: MapHash, data: Vec<u8>) -> Result<PathBuf, DownloadError> {
+    let dir = maps_cache_dir()?;
+    fs::create_dir_all(&dir).await?;
+
+    let target = hash.construct_path(&dir);
+    let partial = dir.join(format!("{}.part", hash.to_hex()));
+    fs::write(&partial, data).await?;
+
+    if let Err(err) = verify_file(&partial, hash).await {
+        fs::remove_file(&partial).await?;
+        return Err(err);
+    }
+
+    fs::rename(&partial, &target).await?;
+    Ok(target)
+}
+
+async fn verify_file(path: &Path, hash: MapHash) -> Result<(), DownloadError> {
+    let actual = load_map(path).await?.compute_hash();
+    if actual != hash {
+        return Err(DownloadError::HashMismatch { actual });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragments() {
+        let mut fragments = Fragments::new(3);
+
+        assert_eq!(fragments.insert(1, 3, vec![3, 4]), Ok(None));
+        assert_eq!(
+            fragments.insert(3, 3, vec![5]),
+            Err(FragmentError::InvalidIndex)
+        );
+        assert_eq!(
+            fragments.insert(0, 2, vec![1]),
+            Err(FragmentError::InvalidCount)
+        );
+        assert_eq!(
+            fragments.insert(0, 3, vec![0; MAX_MAP_FRAGMENT_LEN + 1]),
+            Err(FragmentError::TooLong)
+        );
+        assert_eq!(fragments.insert(0, 3, vec![1, 2]), Ok(None));
+        // Redelivered fragment.
+        assert_eq!(fragments.insert(1, 3, vec![3, 4]), Ok(None));
+        assert_eq!(
+            fragments.insert(2, 3, vec![5]),
+            Ok(Some(vec![1, 2, 3, 4, 5]))
+        );
+    }
+}
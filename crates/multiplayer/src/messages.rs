@@ -1,16 +1,29 @@
-use std::{net::SocketAddr, time::Instant};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 use bevy::prelude::*;
 use de_core::baseset::GameSet;
-use de_net::{FromGame, FromServer, InPackage, PackageBuilder, Peers, ToGame, ToServer};
+use de_net::{
+    BincodeCodec, Capabilities, FromGame, FromServer, InPackage, PackageBuilder, Peers, ToGame,
+    ToPlayers, ToServer,
+};
+use enum_map::{enum_map, Enum, EnumMap};
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     config::ServerPort,
+    game::Players,
     lifecycle::{FatalErrorEvent, NetGameConfRes},
     netstate::NetState,
     network::{NetworkSet, PackageReceivedEvent, SendPackageEvent},
 };
 
+/// Codec of all messages exchanged with the servers and with other players.
+/// All players of a game must use the same codec.
+const CODEC: BincodeCodec = BincodeCodec;
+
 pub(crate) struct MessagesPlugin;
 
 impl Plugin for MessagesPlugin {
@@ -18,10 +31,20 @@ impl Plugin for MessagesPlugin {
         app.add_event::<ToMainServerEvent>()
             .add_event::<ToGameServerEvent<true>>()
             .add_event::<ToGameServerEvent<false>>()
+            .add_event::<ToPlayersEvent<true>>()
+            .add_event::<ToPlayersEvent<false>>()
             .add_event::<FromMainServerEvent>()
             .add_event::<FromGameServerEvent>()
+            .add_event::<FromPlayersEvent>()
             .add_system(setup.in_schedule(OnEnter(NetState::Connecting)))
             .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
+            .add_system(
+                tick_schedule
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(resource_exists::<SendSchedule>())
+                    .in_set(MessagesSet::UpdateSchedule)
+                    .before(MessagesSet::SendMessages),
+            )
             .add_system(
                 message_sender::<ToMainServerEvent>
                     .in_base_set(GameSet::PostUpdate)
@@ -43,6 +66,20 @@ impl Plugin for MessagesPlugin {
                     .in_set(MessagesSet::SendMessages)
                     .before(NetworkSet::SendPackages),
             )
+            .add_system(
+                message_sender::<ToPlayersEvent<true>>
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(on_event::<ToPlayersEvent<true>>())
+                    .in_set(MessagesSet::SendMessages)
+                    .before(NetworkSet::SendPackages),
+            )
+            .add_system(
+                message_sender::<ToPlayersEvent<false>>
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(on_event::<ToPlayersEvent<false>>())
+                    .in_set(MessagesSet::SendMessages)
+                    .before(NetworkSet::SendPackages),
+            )
             .add_system(
                 recv_messages
                     .in_base_set(GameSet::PreMovement)
@@ -55,6 +92,8 @@ impl Plugin for MessagesPlugin {
 
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
 pub(crate) enum MessagesSet {
+    /// Periodic message categories become due. See [`SendSchedule`].
+    UpdateSchedule,
     SendMessages,
     RecvMessages,
 }
@@ -63,8 +102,9 @@ trait ToMessage
 where
     Self: Send + Sync + 'static,
 {
-    type Message: bincode::Encode;
+    type Message: Serialize;
     const PORT_TYPE: PortType;
+    const PEERS: Peers;
     const RELIABLE: bool;
 
     fn message(&self) -> &Self::Message;
@@ -81,6 +121,7 @@ impl From<ToServer> for ToMainServerEvent {
 impl ToMessage for ToMainServerEvent {
     type Message = ToServer;
     const PORT_TYPE: PortType = PortType::Main;
+    const PEERS: Peers = Peers::Server;
     const RELIABLE: bool = true;
 
     fn message(&self) -> &Self::Message {
@@ -99,6 +140,27 @@ impl<const R: bool> From<ToGame> for ToGameServerEvent<R> {
 impl<const R: bool> ToMessage for ToGameServerEvent<R> {
     type Message = ToGame;
     const PORT_TYPE: PortType = PortType::Game;
+    const PEERS: Peers = Peers::Server;
+    const RELIABLE: bool = R;
+
+    fn message(&self) -> &Self::Message {
+        &self.0
+    }
+}
+
+/// A message to all other players in the game.
+pub(crate) struct ToPlayersEvent<const R: bool>(ToPlayers);
+
+impl<const R: bool> From<ToPlayers> for ToPlayersEvent<R> {
+    fn from(message: ToPlayers) -> Self {
+        Self(message)
+    }
+}
+
+impl<const R: bool> ToMessage for ToPlayersEvent<R> {
+    type Message = ToPlayers;
+    const PORT_TYPE: PortType = PortType::Game;
+    const PEERS: Peers = Peers::Players;
     const RELIABLE: bool = R;
 
     fn message(&self) -> &Self::Message {
@@ -154,6 +216,30 @@ impl InMessageEvent for FromGameServerEvent {
     }
 }
 
+/// A message from another player relayed by the game server.
+pub(crate) struct FromPlayersEvent {
+    time: Instant,
+    message: ToPlayers,
+}
+
+impl FromPlayersEvent {
+    pub(crate) fn time(&self) -> Instant {
+        self.time
+    }
+
+    pub(crate) fn message(&self) -> &ToPlayers {
+        &self.message
+    }
+}
+
+impl InMessageEvent for FromPlayersEvent {
+    type M = ToPlayers;
+
+    fn from_message(time: Instant, message: Self::M) -> Self {
+        Self { time, message }
+    }
+}
+
 /// Already known ports of the main and game server.
 #[derive(Resource)]
 pub(crate) enum Ports {
@@ -203,7 +289,7 @@ impl Ports {
     }
 
     /// Returns port of the game server if known.
-    fn game(&self) -> Option<u16> {
+    pub(crate) fn game(&self) -> Option<u16> {
         match self {
             Self::Game(port) => Some(*port),
             Self::Both { game, .. } => Some(*game),
@@ -226,6 +312,39 @@ impl From<ServerPort> for Ports {
     }
 }
 
+/// Category of periodically sent messages.
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MessageCategory {
+    Positions,
+    Health,
+}
+
+/// Schedule of periodically sent messages based on configured
+/// [`crate::SendRates`].
+#[derive(Resource)]
+pub(crate) struct SendSchedule(EnumMap<MessageCategory, Timer>);
+
+impl SendSchedule {
+    fn new(positions: Duration, health: Duration) -> Self {
+        Self(enum_map! {
+            MessageCategory::Positions => Timer::new(positions, TimerMode::Repeating),
+            MessageCategory::Health => Timer::new(health, TimerMode::Repeating),
+        })
+    }
+
+    /// Returns true if messages of the category should be sent during this
+    /// update.
+    pub(crate) fn is_due(&self, category: MessageCategory) -> bool {
+        self.0[category].just_finished()
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        for timer in self.0.values_mut() {
+            timer.tick(delta);
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 enum PortType {
     Main,
@@ -235,15 +354,27 @@ enum PortType {
 fn setup(mut commands: Commands, conf: Res<NetGameConfRes>) {
     let ports: Ports = conf.server_port().into();
     commands.insert_resource(ports);
+
+    let rates = conf.send_rates();
+    commands.insert_resource(SendSchedule::new(
+        Duration::from_secs_f32(rates.positions().recip()),
+        Duration::from_secs_f32(rates.health().recip()),
+    ));
 }
 
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<Ports>();
+    commands.remove_resource::<SendSchedule>();
+}
+
+fn tick_schedule(time: Res<Time>, mut schedule: ResMut<SendSchedule>) {
+    schedule.tick(time.delta());
 }
 
 fn message_sender<E>(
     conf: Res<NetGameConfRes>,
     ports: Res<Ports>,
+    players: Option<Res<Players>>,
     mut inputs: EventReader<E>,
     mut outputs: EventWriter<SendPackageEvent>,
 ) where
@@ -254,7 +385,17 @@ fn message_sender<E>(
         return;
     };
     let addr = SocketAddr::new(conf.server_host(), port);
-    let mut builder = PackageBuilder::new(E::RELIABLE, Peers::Server, addr);
+    let mut builder = PackageBuilder::with_codec(CODEC, E::RELIABLE, E::PEERS, addr);
+    if let PortType::Game = E::PORT_TYPE {
+        if let Some(players) = players {
+            if let Some(token) = players.token() {
+                builder = builder.with_token(token);
+            }
+            if players.capabilities().contains(Capabilities::COMPRESSION) {
+                builder = builder.with_compression();
+            }
+        }
+    }
 
     for event in inputs.iter() {
         builder.push(event.message()).unwrap();
@@ -269,12 +410,15 @@ fn recv_messages(
     mut packages: EventReader<PackageReceivedEvent>,
     mut main_server: EventWriter<FromMainServerEvent>,
     mut game_server: EventWriter<FromGameServerEvent>,
+    mut players: EventWriter<FromPlayersEvent>,
     mut fatals: EventWriter<FatalErrorEvent>,
 ) {
     for event in packages.iter() {
         let package = event.package();
         if ports.is_main(package.source().port()) {
             decode_and_send::<FromServer, _>(package, &mut main_server, &mut fatals);
+        } else if let Peers::Players = package.peers() {
+            decode_and_send::<ToPlayers, _>(package, &mut players, &mut fatals);
         } else {
             decode_and_send::<FromGame, _>(package, &mut game_server, &mut fatals);
         }
@@ -286,19 +430,19 @@ fn decode_and_send<P, E>(
     events: &mut EventWriter<E>,
     fatals: &mut EventWriter<FatalErrorEvent>,
 ) where
-    P: bincode::Decode,
+    P: DeserializeOwned,
     E: InMessageEvent<M = P>,
 {
-    for message in package.decode::<P>() {
+    for message in package.decode_with::<P, _>(CODEC) {
         match message {
             Ok(message) => {
                 events.send(E::from_message(package.time(), message));
             }
             Err(err) => {
-                fatals.send(FatalErrorEvent::new(format!(
-                    "Invalid data received: {:?}",
-                    err
-                )));
+                fatals.send(
+                    FatalErrorEvent::new("mp-error-invalid-data")
+                        .with_arg("error", format!("{err:?}")),
+                );
                 break;
             }
         }
@@ -324,4 +468,20 @@ mod tests {
         ports.init_game_port(4).unwrap();
         assert!(ports.init_game_port(5).is_err());
     }
+
+    #[test]
+    fn test_schedule() {
+        let mut schedule = SendSchedule::new(Duration::from_millis(50), Duration::from_millis(200));
+        assert!(!schedule.is_due(MessageCategory::Positions));
+
+        let mut health = 0;
+        for _ in 0..8 {
+            schedule.tick(Duration::from_millis(50));
+            assert!(schedule.is_due(MessageCategory::Positions));
+            if schedule.is_due(MessageCategory::Health) {
+                health += 1;
+            }
+        }
+        assert_eq!(health, 2);
+    }
 }
@@ -23,6 +23,9 @@ pub enum NetState {
     Connected,
     /// Client has joined a game.
     Joined,
+    /// Connection to the game server was lost and the client is trying to
+    /// rejoin the game.
+    Rejoining,
     /// Multiplayer is being actively shut down.
     ShuttingDown,
 }
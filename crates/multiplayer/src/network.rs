@@ -1,4 +1,4 @@
-use std::ops::Deref;
+use std::{net::SocketAddr, ops::Deref};
 
 use async_std::channel::{TryRecvError, TrySendError};
 use bevy::{
@@ -7,12 +7,16 @@ use bevy::{
 };
 use de_core::baseset::GameSet;
 use de_net::{
-    startup, ConnErrorReceiver, InPackage, OutPackage, PackageReceiver, PackageSender, Socket,
+    startup, ConnErrorKind, ConnErrorReceiver, ConnStatsReceiver, Encryption, InPackage, NetConf,
+    OutPackage, PackageReceiver, PackageSender, Socket, Token,
 };
 use futures_lite::future;
 use iyes_progress::prelude::*;
 
-use crate::{lifecycle::FatalErrorEvent, netstate::NetState};
+use crate::{
+    lifecycle::{FatalErrorEvent, NetGameConfRes},
+    netstate::NetState,
+};
 
 const MAX_RECV_PER_UPDATE: usize = 100;
 
@@ -22,6 +26,8 @@ impl Plugin for NetworkPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SendPackageEvent>()
             .add_event::<PackageReceivedEvent>()
+            .add_event::<PeerDisconnectedEvent>()
+            .add_event::<DeliveryFailedEvent>()
             .add_system(setup.in_schedule(OnEnter(NetState::Connecting)))
             .add_system(cleanup.in_schedule(OnEnter(NetState::None)))
             .add_system(
@@ -80,8 +86,36 @@ impl PackageReceivedEvent {
     }
 }
 
+/// This event is sent when a peer closed the connection or when nothing has
+/// been received from it for too long.
+pub(crate) struct PeerDisconnectedEvent(SocketAddr);
+
+impl PeerDisconnectedEvent {
+    pub(crate) fn addr(&self) -> SocketAddr {
+        self.0
+    }
+}
+
+/// This event is sent when a reliable message could not be delivered to a
+/// peer despite all re-send attempts. See [`crate::ResendPolicy`].
+pub struct DeliveryFailedEvent(SocketAddr);
+
+impl DeliveryFailedEvent {
+    /// Address of the peer to which the message was not delivered.
+    pub fn addr(&self) -> SocketAddr {
+        self.0
+    }
+}
+
 #[derive(Resource)]
-struct NetworkStartup(Task<(PackageSender, PackageReceiver, ConnErrorReceiver)>);
+struct NetworkStartup(
+    Task<(
+        PackageSender,
+        PackageReceiver,
+        ConnErrorReceiver,
+        ConnStatsReceiver,
+    )>,
+);
 
 #[derive(Resource)]
 struct Sender(PackageSender);
@@ -116,11 +150,44 @@ impl Deref for Errors {
     }
 }
 
-fn setup(mut commands: Commands) {
+/// Receiver of periodic connection statistics reports.
+#[derive(Resource)]
+pub(crate) struct StatsReceiver(ConnStatsReceiver);
+
+impl Deref for StatsReceiver {
+    type Target = ConnStatsReceiver;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Encryption sessions of the network stack.
+#[derive(Resource)]
+pub(crate) struct NetEncryption(Encryption);
+
+impl NetEncryption {
+    /// Starts encryption of the connection to a peer, see
+    /// [`Encryption::initiate`].
+    pub(crate) fn initiate(&self, addr: SocketAddr, token: Token) {
+        let encryption = self.0.clone();
+        IoTaskPool::get()
+            .spawn(async move { encryption.initiate(addr, token).await })
+            .detach();
+    }
+}
+
+fn setup(mut commands: Commands, conf: Res<NetGameConfRes>) {
+    let encryption = Encryption::new();
+    let net_conf = NetConf::default()
+        .with_resend_policy(conf.resend_policy())
+        .with_encryption(encryption.clone());
+    commands.insert_resource(NetEncryption(encryption));
+
     let pool = IoTaskPool::get();
-    let task = pool.spawn(async {
+    let task = pool.spawn(async move {
         let socket = Socket::bind(None).await.unwrap();
-        startup(|t| pool.spawn(t).detach(), socket)
+        startup(|t| pool.spawn(t).detach(), socket, net_conf)
     });
     commands.insert_resource(NetworkStartup(task));
 }
@@ -130,10 +197,13 @@ fn cleanup(mut commands: Commands) {
     commands.remove_resource::<Sender>();
     commands.remove_resource::<Receiver>();
     commands.remove_resource::<Errors>();
+    commands.remove_resource::<StatsReceiver>();
+    commands.remove_resource::<NetEncryption>();
 }
 
 fn wait_for_network(mut commands: Commands, mut task: ResMut<NetworkStartup>) -> Progress {
-    let Some((sender, receiver, errors)) = future::block_on(future::poll_once(&mut task.0)) else {
+    let Some((sender, receiver, errors, stats)) = future::block_on(future::poll_once(&mut task.0))
+    else {
         return false.into();
     };
 
@@ -143,6 +213,7 @@ fn wait_for_network(mut commands: Commands, mut task: ResMut<NetworkStartup>) ->
     commands.insert_resource(Sender(sender));
     commands.insert_resource(Receiver(receiver));
     commands.insert_resource(Errors(errors));
+    commands.insert_resource(StatsReceiver(stats));
 
     true.into()
 }
@@ -156,12 +227,10 @@ fn send_packages(
         if let Err(err) = sender.try_send(event.0) {
             match err {
                 TrySendError::Full(_) => {
-                    fatals.send(FatalErrorEvent::new("Network stack is not keeping up."));
+                    fatals.send(FatalErrorEvent::new("mp-error-network-overloaded"));
                 }
                 TrySendError::Closed(_) => {
-                    fatals.send(FatalErrorEvent::new(
-                        "Network output channel is unexpectedly closed.",
-                    ));
+                    fatals.send(FatalErrorEvent::new("mp-error-network-output-closed"));
                 }
             }
         }
@@ -178,9 +247,7 @@ fn recv_packages(
             Ok(package) => events.send(PackageReceivedEvent(package)),
             Err(TryRecvError::Empty) => return,
             Err(TryRecvError::Closed) => {
-                fatals.send(FatalErrorEvent::new(
-                    "Network message receiver is unexpectedly closed.",
-                ));
+                fatals.send(FatalErrorEvent::new("mp-error-network-receiver-closed"));
             }
         }
     }
@@ -188,20 +255,25 @@ fn recv_packages(
     warn!("More than {MAX_RECV_PER_UPDATE} messages received since the last update.");
 }
 
-fn recv_errors(receiver: Res<Errors>, mut fatals: EventWriter<FatalErrorEvent>) {
+fn recv_errors(
+    receiver: Res<Errors>,
+    mut disconnections: EventWriter<PeerDisconnectedEvent>,
+    mut failures: EventWriter<DeliveryFailedEvent>,
+    mut fatals: EventWriter<FatalErrorEvent>,
+) {
     loop {
         match receiver.try_recv() {
-            Ok(error) => {
-                fatals.send(FatalErrorEvent::new(format!(
-                    "Connection error with {:?}.",
-                    error.target()
-                )));
-            }
+            Ok(error) => match error.kind() {
+                ConnErrorKind::Undelivered => {
+                    failures.send(DeliveryFailedEvent(error.target()));
+                }
+                ConnErrorKind::PeerDisconnected | ConnErrorKind::PeerLeft => {
+                    disconnections.send(PeerDisconnectedEvent(error.target()));
+                }
+            },
             Err(TryRecvError::Empty) => return,
             Err(TryRecvError::Closed) => {
-                fatals.send(FatalErrorEvent::new(
-                    "Network connection errors receiver is unexpectedly closed.",
-                ));
+                fatals.send(FatalErrorEvent::new("mp-error-network-errors-closed"));
             }
         }
     }
@@ -3,14 +3,26 @@ use std::{
     time::{Duration, Instant},
 };
 
+use ahash::{AHashMap, AHashSet};
+use async_std::channel::TryRecvError;
 use bevy::prelude::*;
-use de_core::baseset::GameSet;
-use de_net::{FromGame, ToGame};
+use de_core::{
+    baseset::GameSet,
+    player::Player,
+    summary::{MatchSummary, PlayerStats},
+    victory::{GameEndedEvent, VictorySet},
+};
+use de_net::{ConnectionStats, FromGame, ToGame, ToPlayers};
 use tracing::{debug, info, trace};
 
 use crate::{
-    messages::{FromGameServerEvent, MessagesSet, ToGameServerEvent},
+    game::{PlayerLeftEvent, Players},
+    messages::{
+        FromGameServerEvent, FromPlayersEvent, MessagesSet, Ports, ToGameServerEvent,
+        ToPlayersEvent,
+    },
     netstate::NetState,
+    network::{NetworkSet, StatsReceiver},
 };
 
 const RELIABLE_PING_INTERVAL: Duration = Duration::from_secs(10);
@@ -19,6 +31,16 @@ const UNRELIABLE_PING_INTERVAL: Duration = Duration::from_secs(1);
 const UNRELIABLE_HISTORY: usize = 100;
 const STATS_INTERVAL: Duration = Duration::from_secs(10);
 const STATS_OFFSET: Duration = Duration::from_secs(10);
+/// Interval of pings sent to other players and of [`PeerStatsEvent`].
+const PEER_PING_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of latest pings to other players the loss estimate is based on.
+const PEER_HISTORY: usize = 10;
+/// Pings to other players not responded within this time are considered
+/// lost.
+const PEER_PONG_TIMEOUT: Duration = Duration::from_secs(2);
+/// Interval of sending of match statistics of the local player to other
+/// players, see [`MatchSummary`].
+const MATCH_STATS_INTERVAL: Duration = Duration::from_secs(2);
 
 pub(crate) struct StatsPlugin;
 
@@ -55,8 +77,49 @@ impl Plugin for StatsPlugin {
         Self::build_spec::<false>(app);
         Self::build_spec::<true>(app);
 
-        app.add_system(setup.in_schedule(OnEnter(NetState::Joined)))
+        app.add_event::<NetStatsEvent>()
+            .add_event::<PeerStatsEvent>()
+            .add_system(setup.in_schedule(OnEnter(NetState::Joined)))
             .add_system(cleanup.in_schedule(OnExit(NetState::Joined)))
+            .add_system(
+                peer_ping
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(resource_exists::<Players>())
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                peer_receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(resource_exists::<Players>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
+            .add_system(
+                peer_left
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(on_event::<PlayerLeftEvent>()),
+            )
+            .add_system(
+                match_stats_send
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(resource_exists::<Players>())
+                    .run_if(resource_exists::<MatchSummary>())
+                    .after(VictorySet::Detect)
+                    .before(MessagesSet::SendMessages),
+            )
+            .add_system(
+                match_stats_receive
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(resource_exists::<Players>())
+                    .run_if(resource_exists::<MatchSummary>())
+                    .run_if(on_event::<FromPlayersEvent>())
+                    .after(MessagesSet::RecvMessages),
+            )
             .add_system(
                 stats_tick
                     .in_base_set(GameSet::PreMovement)
@@ -69,10 +132,101 @@ impl Plugin for StatsPlugin {
                     .run_if(in_state(NetState::Joined))
                     .after(StatsSet::StatsTick)
                     .after(StatsSet::Unresolved),
+            )
+            .add_system(
+                conn_stats
+                    .in_base_set(GameSet::PreMovement)
+                    .run_if(in_state(NetState::Joined))
+                    .run_if(resource_exists::<StatsReceiver>())
+                    .after(NetworkSet::RecvPackages),
             );
     }
 }
 
+/// This event is sent periodically during a multiplayer game with up-to-date
+/// statistics of the connection to the game server.
+pub struct NetStatsEvent {
+    rtt: Option<Duration>,
+    jitter: Option<Duration>,
+    loss: Option<f32>,
+    bandwidth_out: f32,
+    bandwidth_in: f32,
+}
+
+impl NetStatsEvent {
+    /// Smoothed round trip time to the game server or None if it has not been
+    /// measured yet.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Round trip time variation or None if it has not been measured yet.
+    pub fn jitter(&self) -> Option<Duration> {
+        self.jitter
+    }
+
+    /// Estimated ratio (between 0 and 1) of lost packages or None if it has
+    /// not been measured yet.
+    pub fn loss(&self) -> Option<f32> {
+        self.loss
+    }
+
+    /// Number of bytes per second sent to the game server.
+    pub fn bandwidth_out(&self) -> f32 {
+        self.bandwidth_out
+    }
+
+    /// Number of bytes per second received from the game server.
+    pub fn bandwidth_in(&self) -> f32 {
+        self.bandwidth_in
+    }
+}
+
+impl From<ConnectionStats> for NetStatsEvent {
+    fn from(stats: ConnectionStats) -> Self {
+        Self {
+            rtt: stats.rtt(),
+            jitter: stats.jitter(),
+            loss: stats.loss(),
+            bandwidth_out: stats.bandwidth_out(),
+            bandwidth_in: stats.bandwidth_in(),
+        }
+    }
+}
+
+/// This event is sent every second during a multiplayer game for each other
+/// player with up-to-date statistics of the connection to the player. The
+/// statistics include the game server relaying all messages.
+pub struct PeerStatsEvent {
+    player: Player,
+    rtt: Option<Duration>,
+    loss: Option<f32>,
+    silence: Duration,
+}
+
+impl PeerStatsEvent {
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Smoothed round trip time to the player or None if it has not been
+    /// measured yet.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Ratio (between 0 and 1) of recent pings not responded by the player
+    /// or None if it has not been measured yet.
+    pub fn loss(&self) -> Option<f32> {
+        self.loss
+    }
+
+    /// Time elapsed since the last message was received from the player.
+    pub fn last_heard(&self) -> Duration {
+        self.silence
+    }
+}
+
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
 enum StatsSet {
     Pong,
@@ -86,6 +240,9 @@ struct PingTimer<const R: bool>(Timer);
 #[derive(Resource)]
 struct StatsTimer(Timer);
 
+#[derive(Resource)]
+struct MatchStatsTimer(Timer);
+
 #[derive(Resource)]
 struct Counter(u32);
 
@@ -192,14 +349,130 @@ impl<const R: bool> PingTracker<R> {
     }
 }
 
+/// Tracker of connection quality to other players.
+#[derive(Resource)]
+struct PeerTracker {
+    timer: Timer,
+    next_id: u32,
+    pings: VecDeque<PeerPing>,
+    peers: AHashMap<Player, PeerRecord>,
+}
+
+struct PeerPing {
+    id: u32,
+    time: Instant,
+    responded: AHashSet<Player>,
+}
+
+struct PeerRecord {
+    first_heard: Instant,
+    last_heard: Instant,
+    rtt: Option<Duration>,
+}
+
+impl PeerTracker {
+    fn new() -> Self {
+        Self {
+            timer: Timer::new(PEER_PING_INTERVAL, TimerMode::Repeating),
+            next_id: 0,
+            pings: VecDeque::new(),
+            peers: AHashMap::new(),
+        }
+    }
+
+    /// Registers reception of a message from a player at `time`.
+    fn heard(&mut self, player: Player, time: Instant) {
+        let record = self.peers.entry(player).or_insert(PeerRecord {
+            first_heard: time,
+            last_heard: time,
+            rtt: None,
+        });
+        record.last_heard = record.last_heard.max(time);
+    }
+
+    /// Registers a new ping sent at `time` and returns its ID.
+    fn ping(&mut self, time: Instant) -> u32 {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        if self.pings.len() >= PEER_HISTORY {
+            self.pings.pop_front();
+        }
+        self.pings.push_back(PeerPing {
+            id,
+            time,
+            responded: AHashSet::new(),
+        });
+        id
+    }
+
+    /// Registers a response of a player to the ping `id` received at `time`.
+    fn pong(&mut self, player: Player, id: u32, time: Instant) {
+        let Some(ping) = self.pings.iter_mut().find(|ping| ping.id == id) else {
+            return;
+        };
+        if !ping.responded.insert(player) {
+            return;
+        }
+        let Some(record) = self.peers.get_mut(&player) else {
+            return;
+        };
+
+        let sample = time.saturating_duration_since(ping.time);
+        record.rtt = Some(match record.rtt {
+            Some(rtt) => (rtt * 7 + sample) / 8,
+            None => sample,
+        });
+    }
+
+    fn remove(&mut self, player: Player) {
+        self.peers.remove(&player);
+    }
+
+    /// Returns up-to-date statistics of all players heard from.
+    fn stats(&self, now: Instant) -> impl Iterator<Item = PeerStatsEvent> + '_ {
+        self.peers.iter().map(move |(&player, record)| {
+            let mut sample_size = 0;
+            let mut responded = 0;
+            for ping in self.pings.iter().filter(|ping| {
+                ping.time >= record.first_heard
+                    && now.saturating_duration_since(ping.time) >= PEER_PONG_TIMEOUT
+            }) {
+                sample_size += 1;
+                if ping.responded.contains(&player) {
+                    responded += 1;
+                }
+            }
+
+            PeerStatsEvent {
+                player,
+                rtt: record.rtt,
+                loss: if sample_size == 0 {
+                    None
+                } else {
+                    Some(1. - responded as f32 / sample_size as f32)
+                },
+                silence: now.saturating_duration_since(record.last_heard),
+            }
+        })
+    }
+}
+
 fn setup(mut commands: Commands) {
     commands.insert_resource(Counter::new());
     commands.insert_resource(StatsTimer(Timer::new(STATS_INTERVAL, TimerMode::Repeating)));
+    commands.insert_resource(PeerTracker::new());
+    commands.insert_resource(MatchStatsTimer(Timer::new(
+        MATCH_STATS_INTERVAL,
+        TimerMode::Repeating,
+    )));
 }
 
 fn cleanup(mut commands: Commands) {
     commands.remove_resource::<Counter>();
     commands.remove_resource::<StatsTimer>();
+    commands.remove_resource::<PeerTracker>();
+    commands.remove_resource::<MatchStatsTimer>();
 }
 
 fn setup_spec<const R: bool>(mut commands: Commands) {
@@ -311,6 +584,185 @@ fn delivery_rate(timer: ResMut<StatsTimer>, tracker: Res<PingTracker<false>>) {
     }
 }
 
+fn conn_stats(
+    ports: Res<Ports>,
+    receiver: Res<StatsReceiver>,
+    mut events: EventWriter<NetStatsEvent>,
+) {
+    let Some(game_port) = ports.game() else {
+        return;
+    };
+
+    loop {
+        match receiver.try_recv() {
+            Ok(stats) => {
+                if stats.target().port() == game_port {
+                    events.send(stats.into());
+                }
+            }
+            Err(TryRecvError::Empty | TryRecvError::Closed) => return,
+        }
+    }
+}
+
+fn peer_ping(
+    time: Res<Time>,
+    players: Res<Players>,
+    mut tracker: ResMut<PeerTracker>,
+    mut messages: EventWriter<ToPlayersEvent<false>>,
+    mut events: EventWriter<PeerStatsEvent>,
+) {
+    tracker.timer.tick(time.delta());
+    if !tracker.timer.just_finished() {
+        return;
+    }
+
+    let now = Instant::now();
+    events.send_batch(tracker.stats(now));
+
+    // Spectators may not send messages to players.
+    let Some(local) = players.local().filter(|_| players.is_controlling()) else {
+        return;
+    };
+    let id = tracker.ping(now);
+    trace!("Sending Ping({id}) to other players.");
+    messages.send(
+        ToPlayers::Ping {
+            player: local.to_num(),
+            id,
+        }
+        .into(),
+    );
+}
+
+fn peer_receive(
+    players: Res<Players>,
+    mut tracker: ResMut<PeerTracker>,
+    mut inputs: EventReader<FromPlayersEvent>,
+    mut messages: EventWriter<ToPlayersEvent<false>>,
+) {
+    let local = players.local().filter(|_| players.is_controlling());
+
+    for event in inputs.iter() {
+        let Some(Ok(sender)) = event.message().sender().map(Player::try_from) else {
+            continue;
+        };
+        tracker.heard(sender, event.time());
+
+        match *event.message() {
+            ToPlayers::Ping { id, .. } => {
+                if let Some(local) = local {
+                    messages.send(
+                        ToPlayers::Pong {
+                            player: local.to_num(),
+                            target: sender.to_num(),
+                            id,
+                        }
+                        .into(),
+                    );
+                }
+            }
+            ToPlayers::Pong { target, id, .. }
+                if local.map_or(false, |local| local.to_num() == target) =>
+            {
+                tracker.pong(sender, id, event.time());
+            }
+            _ => (),
+        }
+    }
+}
+
+fn peer_left(mut tracker: ResMut<PeerTracker>, mut events: EventReader<PlayerLeftEvent>) {
+    for event in events.iter() {
+        tracker.remove(event.player());
+    }
+}
+
+fn match_stats_send(
+    time: Res<Time>,
+    players: Res<Players>,
+    summary: Res<MatchSummary>,
+    mut timer: ResMut<MatchStatsTimer>,
+    mut ended: EventReader<GameEndedEvent>,
+    mut messages: EventWriter<ToPlayersEvent<true>>,
+) {
+    timer.0.tick(time.delta());
+    // The final statistics are sent right away so that other players can
+    // present them after the game.
+    let ended = ended.iter().count() > 0;
+    if !timer.0.just_finished() && !ended {
+        return;
+    }
+
+    // Spectators may not send messages to players.
+    let Some(local) = players.local().filter(|_| players.is_controlling()) else {
+        return;
+    };
+    let Some(stats) = summary.player(local).map(|summary| summary.totals()) else {
+        return;
+    };
+
+    trace!("Sending match statistics to other players.");
+    messages.send(
+        ToPlayers::MatchStats {
+            player: local.to_num(),
+            units_built: stats.units_built(),
+            units_lost: stats.units_lost(),
+            units_killed: stats.units_killed(),
+            damage_dealt: stats.damage_dealt(),
+            resources_gathered: stats.resources_gathered(),
+            actions: stats.actions(),
+        }
+        .into(),
+    );
+}
+
+fn match_stats_receive(
+    players: Res<Players>,
+    mut summary: ResMut<MatchSummary>,
+    mut messages: EventReader<FromPlayersEvent>,
+) {
+    for event in messages.iter() {
+        let ToPlayers::MatchStats {
+            player,
+            units_built,
+            units_lost,
+            units_killed,
+            damage_dealt,
+            resources_gathered,
+            actions,
+        } = *event.message()
+        else {
+            continue;
+        };
+
+        let Ok(player) = Player::try_from(player) else {
+            warn!("Match statistics of an invalid player received.");
+            continue;
+        };
+        // Statistics of the local player are accumulated locally.
+        if players.local() == Some(player) {
+            continue;
+        }
+        if !damage_dealt.is_finite() || !resources_gathered.is_finite() {
+            warn!("Invalid match statistics received from {player}.");
+            continue;
+        }
+
+        summary.replace(
+            player,
+            PlayerStats::new(
+                units_built,
+                units_lost,
+                units_killed,
+                damage_dealt,
+                resources_gathered,
+                actions,
+            ),
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -336,4 +788,41 @@ mod tests {
         tracker.trim(2, &mut ids);
         assert_eq!(ids, vec![0, 3]);
     }
+
+    #[test]
+    fn test_peer_tracker() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut tracker = PeerTracker::new();
+
+        tracker.heard(Player::Player2, ms(0));
+        tracker.heard(Player::Player3, ms(0));
+        let first = tracker.ping(ms(10));
+        let second = tracker.ping(ms(1010));
+
+        tracker.heard(Player::Player2, ms(110));
+        tracker.pong(Player::Player2, first, ms(110));
+        // Redelivered response.
+        tracker.pong(Player::Player2, first, ms(500));
+        tracker.heard(Player::Player2, ms(1090));
+        tracker.pong(Player::Player2, second, ms(1090));
+        tracker.heard(Player::Player3, ms(1500));
+        tracker.pong(Player::Player3, second, ms(1500));
+
+        let mut stats: Vec<PeerStatsEvent> = tracker.stats(ms(3010)).collect();
+        stats.sort_by_key(|stats| stats.player());
+        assert_eq!(stats.len(), 2);
+
+        assert_eq!(stats[0].player(), Player::Player2);
+        assert_eq!(stats[0].rtt(), Some(Duration::from_micros(97500)));
+        assert_eq!(stats[0].loss(), Some(0.));
+        assert_eq!(stats[0].last_heard(), Duration::from_millis(1920));
+
+        assert_eq!(stats[1].player(), Player::Player3);
+        assert_eq!(stats[1].rtt(), Some(Duration::from_millis(490)));
+        assert_eq!(stats[1].loss(), Some(0.5));
+
+        tracker.remove(Player::Player3);
+        assert_eq!(tracker.stats(ms(3010)).count(), 1);
+    }
 }
@@ -0,0 +1,169 @@
+//! Coalescing of multiple datagrams into a single batch datagram.
+//!
+//! Payload of a batch datagram is a sequence of entries. Each entry starts
+//! with a 2-byte big-endian length followed by a complete datagram (header and
+//! payload) of that length. Batches are never nested.
+
+use thiserror::Error;
+
+use crate::{
+    header::{DatagramHeader, HEADER_SIZE},
+    protocol::MAX_PACKAGE_SIZE,
+};
+
+/// Number of bytes preceding each entry of a batch.
+const LEN_SIZE: usize = 2;
+
+/// Builder of a payload of a single batch datagram.
+pub(crate) struct BatchBuilder {
+    payload: Vec<u8>,
+    entries: usize,
+}
+
+impl BatchBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            payload: Vec::new(),
+            entries: 0,
+        }
+    }
+
+    /// Returns true if a datagram with a payload of length `len` may be
+    /// ever pushed to a batch.
+    pub(crate) fn fits_empty(len: usize) -> bool {
+        LEN_SIZE + HEADER_SIZE + len <= MAX_PACKAGE_SIZE
+    }
+
+    /// Number of datagrams in the batch.
+    pub(crate) fn len(&self) -> usize {
+        self.entries
+    }
+
+    /// Appends a datagram to the batch.
+    ///
+    /// # Returns
+    ///
+    /// Returns false (and does nothing) if the batch would exceed
+    /// [`MAX_PACKAGE_SIZE`] with the datagram.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `header` is [`DatagramHeader::Batch`].
+    pub(crate) fn push(&mut self, header: DatagramHeader, data: &[u8]) -> bool {
+        assert!(header != DatagramHeader::Batch);
+
+        let len = HEADER_SIZE + data.len();
+        if self.payload.len() + LEN_SIZE + len > MAX_PACKAGE_SIZE {
+            return false;
+        }
+
+        // Guaranteed by the size limit above.
+        let len_bytes = u16::try_from(len).unwrap().to_be_bytes();
+        self.payload.extend_from_slice(&len_bytes);
+
+        let start = self.payload.len();
+        self.payload.resize(start + len, 0);
+        header.write(&mut self.payload[start..]);
+        self.payload[start + HEADER_SIZE..].copy_from_slice(data);
+
+        self.entries += 1;
+        true
+    }
+
+    /// Returns the builder emptied and the batch payload.
+    pub(crate) fn take(&mut self) -> Vec<u8> {
+        self.entries = 0;
+        std::mem::take(&mut self.payload)
+    }
+}
+
+/// Iterator over datagrams contained in a batch datagram payload.
+pub(crate) struct BatchReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BatchReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a> Iterator for BatchReader<'a> {
+    type Item = Result<(DatagramHeader, &'a [u8]), BatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        if self.data.len() < LEN_SIZE {
+            self.data = &[];
+            return Some(Err(BatchError::Truncated));
+        }
+
+        let len = u16::from_be_bytes([self.data[0], self.data[1]]) as usize;
+        let rest = &self.data[LEN_SIZE..];
+        if len < HEADER_SIZE || rest.len() < len {
+            self.data = &[];
+            return Some(Err(BatchError::Truncated));
+        }
+
+        let (entry, rest) = rest.split_at(len);
+        self.data = rest;
+
+        Some(match DatagramHeader::read(entry) {
+            Ok(DatagramHeader::Batch) => Err(BatchError::Nested),
+            Ok(header) => Ok((header, &entry[HEADER_SIZE..])),
+            Err(_) => Err(BatchError::InvalidHeader),
+        })
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub(crate) enum BatchError {
+    #[error("batch entry is truncated")]
+    Truncated,
+    #[error("batch entry has an invalid header")]
+    InvalidHeader,
+    #[error("batch entry is a batch")]
+    Nested,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::{PackageId, Peers};
+
+    #[test]
+    fn test_batch() {
+        let package =
+            DatagramHeader::new_package(true, Peers::Players, false, false, PackageId::zero());
+
+        let mut builder = BatchBuilder::new();
+        assert_eq!(builder.len(), 0);
+        assert!(builder.push(DatagramHeader::KeepAlive, &[]));
+        assert!(builder.push(package, &[1, 2, 3]));
+        assert!(!builder.push(package, &[0; MAX_PACKAGE_SIZE]));
+        assert_eq!(builder.len(), 2);
+
+        let payload = builder.take();
+        assert_eq!(builder.len(), 0);
+        assert_eq!(
+            payload,
+            [0, 4, 129, 0, 0, 0, 0, 7, 64, 0, 0, 0, 1, 2, 3].to_vec()
+        );
+
+        let mut reader = BatchReader::new(&payload);
+        assert_eq!(
+            reader.next().unwrap().unwrap(),
+            (DatagramHeader::KeepAlive, &[][..])
+        );
+        assert_eq!(reader.next().unwrap().unwrap(), (package, &[1, 2, 3][..]));
+        assert!(reader.next().is_none());
+
+        let mut reader = BatchReader::new(&[0, 4, 131, 0, 0, 0, 0, 9, 64]);
+        assert_eq!(reader.next().unwrap().unwrap_err(), BatchError::Nested);
+        assert_eq!(reader.next().unwrap().unwrap_err(), BatchError::Truncated);
+        assert!(reader.next().is_none());
+    }
+}
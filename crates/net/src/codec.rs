@@ -0,0 +1,157 @@
+use bincode::config::{BigEndian, Configuration, Limit, Varint};
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use crate::protocol::MAX_PACKAGE_SIZE;
+
+const BINCODE_CONF: Configuration<BigEndian, Varint, Limit<MAX_PACKAGE_SIZE>> =
+    bincode::config::standard()
+        .with_big_endian()
+        .with_variable_int_encoding()
+        .with_limit::<MAX_PACKAGE_SIZE>();
+
+/// Binary encoding of messages sent over the network. Messages are plain
+/// serde types, thus a message type works with any codec.
+///
+/// All peers of a connection must use the same codec. [`BincodeCodec`] is
+/// used by default.
+pub trait Codec {
+    /// Encodes a message into the beginning of a buffer and returns the
+    /// number of written bytes.
+    ///
+    /// [`EncodeError::BufferFull`] is returned if the message does not fit
+    /// into the buffer.
+    fn encode_into_slice<E: Serialize>(
+        &self,
+        message: &E,
+        buf: &mut [u8],
+    ) -> Result<usize, EncodeError>;
+
+    fn encode_to_vec<E: Serialize>(&self, message: &E) -> Result<Vec<u8>, EncodeError>;
+
+    /// Decodes a message from the beginning of the data and returns it
+    /// together with the number of read bytes.
+    fn decode_from_slice<D: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<(D, usize), DecodeError>;
+}
+
+/// Compact binary codec based on bincode with variable length integer
+/// encoding.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BincodeCodec;
+
+impl Codec for BincodeCodec {
+    fn encode_into_slice<E: Serialize>(
+        &self,
+        message: &E,
+        buf: &mut [u8],
+    ) -> Result<usize, EncodeError> {
+        bincode::serde::encode_into_slice(message, buf, BINCODE_CONF).map_err(EncodeError::from)
+    }
+
+    fn encode_to_vec<E: Serialize>(&self, message: &E) -> Result<Vec<u8>, EncodeError> {
+        bincode::serde::encode_to_vec(message, BINCODE_CONF).map_err(EncodeError::from)
+    }
+
+    fn decode_from_slice<D: DeserializeOwned>(
+        &self,
+        data: &[u8],
+    ) -> Result<(D, usize), DecodeError> {
+        bincode::serde::decode_from_slice(data, BINCODE_CONF)
+            .map_err(|err| DecodeError(err.to_string()))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum EncodeError {
+    #[error("the message does not fit into the buffer")]
+    BufferFull,
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<bincode::error::EncodeError> for EncodeError {
+    fn from(error: bincode::error::EncodeError) -> Self {
+        match error {
+            bincode::error::EncodeError::UnexpectedEnd => Self::BufferFull,
+            error => Self::Other(error.to_string()),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[error("message decoding failed: {0}")]
+pub struct DecodeError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatChannel, FromGame, ToGame, ToPlayers, PROTOCOL_VERSION};
+
+    fn roundtrip<M: Serialize + DeserializeOwned>(message: &M) -> M {
+        let data = BincodeCodec.encode_to_vec(message).unwrap();
+        let (decoded, len) = BincodeCodec.decode_from_slice(&data).unwrap();
+        assert_eq!(len, data.len());
+        decoded
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let ToGame::Chat { channel, text } = roundtrip(&ToGame::Chat {
+            channel: ChatChannel::Whisper(3),
+            text: "Hello!".to_owned(),
+        }) else {
+            panic!("Unexpected message.");
+        };
+        assert_eq!(channel, ChatChannel::Whisper(3));
+        assert_eq!(text, "Hello!");
+
+        let FromGame::VersionMismatch { server, client } = roundtrip(&FromGame::VersionMismatch {
+            server: PROTOCOL_VERSION,
+            client: 7,
+        }) else {
+            panic!("Unexpected message.");
+        };
+        assert_eq!(server, PROTOCOL_VERSION);
+        assert_eq!(client, 7);
+
+        let ToPlayers::Commands {
+            player,
+            tick,
+            commands,
+        } = roundtrip(&ToPlayers::Commands {
+            player: 2,
+            tick: 1000,
+            commands: vec![1, 2, 3],
+        })
+        else {
+            panic!("Unexpected message.");
+        };
+        assert_eq!(player, 2);
+        assert_eq!(tick, 1000);
+        assert_eq!(commands, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_bincode() {
+        // [1, 1, 0, 0, 0] -> ToGame::Join { version: 1, capabilities: 0, auth: None, password: None }
+        let data = BincodeCodec
+            .encode_to_vec(&ToGame::Join {
+                version: 1,
+                capabilities: Default::default(),
+                auth: None,
+                password: None,
+            })
+            .unwrap();
+        assert_eq!(data, vec![1, 1, 0, 0, 0]);
+
+        let mut buf = [0; 4];
+        assert!(matches!(
+            BincodeCodec.encode_into_slice(&ToGame::Ping(u32::MAX), &mut buf),
+            Err(EncodeError::BufferFull)
+        ));
+        assert!(BincodeCodec.decode_from_slice::<ToGame>(&[200]).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use thiserror::Error;
+
+use crate::protocol::MAX_PACKAGE_SIZE;
+
+/// Payloads shorter than this are never compressed because the gain would be
+/// negligible.
+const COMPRESSION_THRESHOLD: usize = 128;
+/// Zstandard compression level. The fastest level is used because packages
+/// are compressed on the fly.
+const COMPRESSION_LEVEL: i32 = 1;
+
+/// Compresses package payload with Zstandard.
+///
+/// None is returned if the payload is too short to be worth compressing or
+/// if compression would not reduce its size.
+pub(crate) fn compress(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < COMPRESSION_THRESHOLD {
+        return None;
+    }
+
+    zstd::bulk::compress(data, COMPRESSION_LEVEL)
+        .ok()
+        .filter(|compressed| compressed.len() < data.len())
+}
+
+/// Decompresses package payload.
+///
+/// Decompression fails if the decompressed payload would be longer than
+/// [`MAX_PACKAGE_SIZE`].
+pub(crate) fn decompress(data: &[u8]) -> Result<Vec<u8>, DecompressionError> {
+    Ok(zstd::bulk::decompress(data, MAX_PACKAGE_SIZE)?)
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum DecompressionError {
+    #[error("invalid or too long compressed payload: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression() {
+        assert!(compress(&[1, 2, 3]).is_none());
+
+        let data: Vec<u8> = (0..400).map(|i| (i % 7) as u8).collect();
+        let compressed = compress(&data).unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(decompress(&compressed).unwrap(), data);
+
+        let random: Vec<u8> = (0..200).map(|_| fastrand::u8(..)).collect();
+        assert!(compress(&random).is_none());
+
+        let long = vec![0; MAX_PACKAGE_SIZE + 1];
+        let compressed = zstd::bulk::compress(&long, COMPRESSION_LEVEL).unwrap();
+        assert!(decompress(&compressed).is_err());
+        assert!(decompress(&[1, 2, 3]).is_err());
+    }
+}
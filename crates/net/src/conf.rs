@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use crate::{connection::KEEP_ALIVE_INTERVAL, Encryption, NetMetrics, Tokens};
+
+/// Configuration of the networking stack.
+#[derive(Clone, Debug)]
+pub struct NetConf {
+    peer_timeout: Duration,
+    resend_policy: ResendPolicy,
+    bandwidth_limit: Option<u32>,
+    metrics: NetMetrics,
+    encryption: Encryption,
+    tokens: Tokens,
+}
+
+impl NetConf {
+    /// # Arguments
+    ///
+    /// * `peer_timeout` - a peer is considered disconnected once nothing is
+    ///   received from it for this long. See
+    ///   [`crate::ConnErrorKind::PeerDisconnected`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `peer_timeout` is not longer than keep-alive interval (one
+    /// second).
+    pub fn new(peer_timeout: Duration) -> Self {
+        assert!(peer_timeout > KEEP_ALIVE_INTERVAL);
+        Self {
+            peer_timeout,
+            resend_policy: ResendPolicy::default(),
+            bandwidth_limit: None,
+            metrics: NetMetrics::default(),
+            encryption: Encryption::default(),
+            tokens: Tokens::default(),
+        }
+    }
+
+    /// Sets policy of reliable package re-sending.
+    pub fn with_resend_policy(mut self, resend_policy: ResendPolicy) -> Self {
+        self.resend_policy = resend_policy;
+        self
+    }
+
+    /// Limits outgoing bandwidth to `bytes_per_second`. Datagrams are queued
+    /// and sent in the order of their priority (see [`crate::Priority`]) once
+    /// the limit is reached.
+    ///
+    /// By default, the bandwidth is not limited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_second` is zero.
+    pub fn with_bandwidth_limit(mut self, bytes_per_second: u32) -> Self {
+        assert!(bytes_per_second > 0);
+        self.bandwidth_limit = Some(bytes_per_second);
+        self
+    }
+
+    /// Traffic of the networking stack is counted into (a clone of)
+    /// `metrics`, thus the caller may observe it.
+    pub fn with_metrics(mut self, metrics: NetMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Datagrams are encrypted with sessions of (a clone of) `encryption`,
+    /// thus the caller may negotiate encryption with individual peers.
+    pub fn with_encryption(mut self, encryption: Encryption) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    /// Tokens of incoming packages are verified against (a clone of)
+    /// `tokens`, thus the caller may issue tokens to individual peers.
+    pub fn with_tokens(mut self, tokens: Tokens) -> Self {
+        self.tokens = tokens;
+        self
+    }
+
+    pub fn peer_timeout(&self) -> Duration {
+        self.peer_timeout
+    }
+
+    pub fn resend_policy(&self) -> ResendPolicy {
+        self.resend_policy
+    }
+
+    /// Maximum outgoing bandwidth in bytes per second or None if unlimited.
+    pub fn bandwidth_limit(&self) -> Option<u32> {
+        self.bandwidth_limit
+    }
+
+    pub fn metrics(&self) -> &NetMetrics {
+        &self.metrics
+    }
+
+    pub fn encryption(&self) -> &Encryption {
+        &self.encryption
+    }
+
+    pub fn tokens(&self) -> &Tokens {
+        &self.tokens
+    }
+}
+
+impl Default for NetConf {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10))
+    }
+}
+
+/// Policy of reliable package re-sending.
+///
+/// A reliable package is re-sent until it is confirmed by the receiver. The
+/// delay between successive attempts grows exponentially (with a small random
+/// jitter). Once the maximum number of attempts is reached, the package is
+/// given up and [`crate::ConnErrorKind::Undelivered`] is reported.
+#[derive(Clone, Copy, Debug)]
+pub struct ResendPolicy {
+    initial_timeout: Duration,
+    multiplier: f32,
+    max_attempts: u8,
+    give_up: GiveUp,
+}
+
+impl ResendPolicy {
+    /// # Arguments
+    ///
+    /// * `initial_timeout` - delay between the first delivery attempt and the
+    ///   first re-send.
+    ///
+    /// * `multiplier` - each subsequent delay is this many times longer than
+    ///   the previous one.
+    ///
+    /// * `max_attempts` - maximum number of re-sends of a single package.
+    ///
+    /// * `give_up` - what happens once a package could not be delivered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `initial_timeout` is zero or if `multiplier` is smaller than
+    /// one.
+    pub fn new(
+        initial_timeout: Duration,
+        multiplier: f32,
+        max_attempts: u8,
+        give_up: GiveUp,
+    ) -> Self {
+        assert!(!initial_timeout.is_zero());
+        assert!(multiplier >= 1.);
+        Self {
+            initial_timeout,
+            multiplier,
+            max_attempts,
+            give_up,
+        }
+    }
+
+    pub fn initial_timeout(&self) -> Duration {
+        self.initial_timeout
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        self.multiplier
+    }
+
+    pub fn max_attempts(&self) -> u8 {
+        self.max_attempts
+    }
+
+    pub fn give_up(&self) -> GiveUp {
+        self.give_up
+    }
+}
+
+impl Default for ResendPolicy {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(220), 2., 6, GiveUp::DropPeer)
+    }
+}
+
+/// Behavior of the networking stack after a reliable package could not be
+/// delivered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GiveUp {
+    /// Only the undelivered package is dropped. Re-sending of other pending
+    /// packages to the same peer continues.
+    DropPackage,
+    /// All pending packages to the peer are dropped.
+    DropPeer,
+}
@@ -0,0 +1,224 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use async_std::{
+    channel::{SendError, Sender},
+    sync::{Arc, Mutex},
+};
+
+use super::book::{Connection, ConnectionBook};
+use crate::{header::DatagramHeader, tasks::OutDatagram};
+
+/// A keep-alive datagram is sent to a peer if nothing was sent to it for this
+/// long.
+pub(crate) const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Bookkeeping of connection liveness.
+///
+/// Only peers with which user data were exchanged are tracked. Keep-alive
+/// datagrams from other peers are ignored.
+#[derive(Clone)]
+pub(crate) struct Liveness {
+    book: Arc<Mutex<ConnectionBook<Peer>>>,
+}
+
+impl Liveness {
+    pub(crate) fn new() -> Self {
+        Self {
+            book: Arc::new(Mutex::new(ConnectionBook::new())),
+        }
+    }
+
+    /// Registers a package sent to `addr`. The peer is tracked from now on.
+    pub(crate) async fn sent(&mut self, time: Instant, addr: SocketAddr) {
+        self.book
+            .lock()
+            .await
+            .update(time, addr, || Peer::new(time))
+            .last_sent = time;
+    }
+
+    /// Registers a package received from `addr`. The peer is tracked from now
+    /// on.
+    pub(crate) async fn received_package(&mut self, time: Instant, addr: SocketAddr) {
+        self.book
+            .lock()
+            .await
+            .update(time, addr, || Peer::new(time))
+            .last_received = time;
+    }
+
+    /// Registers a protocol control datagram received from `addr`. Nothing
+    /// happens if the peer is not tracked.
+    pub(crate) async fn received_control(&mut self, time: Instant, addr: SocketAddr) {
+        if let Some(peer) = self.book.lock().await.get_mut(addr) {
+            peer.last_received = time;
+        }
+    }
+
+    /// Registers a goodbye datagram received from `addr` and forgets the
+    /// peer.
+    ///
+    /// # Returns
+    ///
+    /// It returns true if the peer was tracked.
+    pub(crate) async fn received_goodbye(&mut self, addr: SocketAddr) -> bool {
+        self.book.lock().await.remove(addr).is_some()
+    }
+
+    /// Sends goodbye datagrams to all tracked peers and forgets them. This
+    /// should be called only once nothing more is going to be sent.
+    pub(crate) async fn goodbye(
+        &mut self,
+        datagrams: &mut Sender<OutDatagram>,
+    ) -> Result<(), SendError<OutDatagram>> {
+        let mut book = self.book.lock().await;
+        while let Some((addr, _)) = book.next() {
+            datagrams
+                .send(OutDatagram::new(DatagramHeader::Goodbye, Vec::new(), addr))
+                .await?;
+            book.remove_current();
+        }
+        Ok(())
+    }
+
+    /// Sends keep-alive datagrams to all tracked peers to which nothing was
+    /// sent recently and forgets all peers from which nothing was received
+    /// for longer than `timeout`.
+    pub(crate) async fn beat(
+        &mut self,
+        time: Instant,
+        timeout: Duration,
+        datagrams: &mut Sender<OutDatagram>,
+    ) -> Result<BeatResult, SendError<OutDatagram>> {
+        let mut result = BeatResult {
+            disconnected: Vec::new(),
+            next: time + KEEP_ALIVE_INTERVAL,
+        };
+
+        let mut book = self.book.lock().await;
+        while let Some((addr, peer)) = book.next() {
+            if time.saturating_duration_since(peer.last_received) > timeout {
+                book.remove_current();
+                result.disconnected.push(addr);
+                continue;
+            }
+
+            if time.saturating_duration_since(peer.last_sent) >= KEEP_ALIVE_INTERVAL {
+                datagrams
+                    .send(OutDatagram::new(
+                        DatagramHeader::KeepAlive,
+                        Vec::new(),
+                        addr,
+                    ))
+                    .await?;
+                peer.last_sent = time;
+            }
+
+            result.next = result
+                .next
+                .min(peer.last_sent + KEEP_ALIVE_INTERVAL)
+                .min(peer.last_received + timeout);
+        }
+
+        Ok(result)
+    }
+
+    pub(crate) async fn clean(&mut self, time: Instant) {
+        self.book.lock().await.clean(time);
+    }
+}
+
+pub(crate) struct BeatResult {
+    /// Peers from which nothing was received for too long.
+    pub(crate) disconnected: Vec<SocketAddr>,
+    /// Soonest possible time of the next keep-alive or timeout.
+    pub(crate) next: Instant,
+}
+
+struct Peer {
+    last_sent: Instant,
+    last_received: Instant,
+}
+
+impl Peer {
+    fn new(time: Instant) -> Self {
+        Self {
+            last_sent: time,
+            last_received: time,
+        }
+    }
+}
+
+impl Connection for Peer {
+    fn pending(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::{channel::bounded, task};
+
+    use super::*;
+
+    #[test]
+    fn test_liveness() {
+        task::block_on(task::spawn(async {
+            let timeout = Duration::from_secs(5);
+            let (mut sender, receiver) = bounded(16);
+            let mut liveness = Liveness::new();
+
+            let start = Instant::now();
+            let first: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+            let second: SocketAddr = "127.0.0.1:1002".parse().unwrap();
+            let third: SocketAddr = "127.0.0.1:1003".parse().unwrap();
+
+            liveness.sent(start, first).await;
+            liveness.received_package(start, second).await;
+            // Not tracked.
+            liveness.received_control(start, third).await;
+
+            let result = liveness
+                .beat(start + Duration::from_millis(500), timeout, &mut sender)
+                .await
+                .unwrap();
+            assert!(result.disconnected.is_empty());
+            assert_eq!(result.next, start + KEEP_ALIVE_INTERVAL);
+            assert!(receiver.is_empty());
+
+            let time = start + Duration::from_secs(2);
+            liveness.received_control(time, first).await;
+            let result = liveness.beat(time, timeout, &mut sender).await.unwrap();
+            assert!(result.disconnected.is_empty());
+            assert_eq!(result.next, time + KEEP_ALIVE_INTERVAL);
+            assert_eq!(receiver.len(), 2);
+
+            let result = liveness
+                .beat(start + Duration::from_secs(6), timeout, &mut sender)
+                .await
+                .unwrap();
+            assert_eq!(result.disconnected, vec![second]);
+
+            let result = liveness
+                .beat(start + Duration::from_secs(8), timeout, &mut sender)
+                .await
+                .unwrap();
+            assert_eq!(result.disconnected, vec![first]);
+
+            let time = start + Duration::from_secs(9);
+            liveness.sent(time, first).await;
+            liveness.sent(time, second).await;
+            assert!(liveness.received_goodbye(second).await);
+            assert!(!liveness.received_goodbye(third).await);
+            while receiver.try_recv().is_ok() {}
+            liveness.goodbye(&mut sender).await.unwrap();
+            assert_eq!(receiver.len(), 1);
+            let result = liveness.beat(time, timeout, &mut sender).await.unwrap();
+            assert!(result.disconnected.is_empty());
+            assert_eq!(receiver.len(), 1);
+        }));
+    }
+}
@@ -53,6 +53,27 @@ impl<T: Connection> ConnectionBook<T> {
         &mut record.value
     }
 
+    /// Returns mutable reference to the connection value object if the
+    /// connection exists. Contrary to [`Self::update`], last update time of
+    /// the connection is not modified.
+    pub(super) fn get_mut(&mut self, addr: SocketAddr) -> Option<&mut T> {
+        self.records.get_mut(&addr).map(|record| &mut record.value)
+    }
+
+    /// Removes the connection and returns its value object if the connection
+    /// existed.
+    pub(super) fn remove(&mut self, addr: SocketAddr) -> Option<T> {
+        let record = self.records.remove(&addr)?;
+        let index = self.addrs.iter().position(|&a| a == addr).unwrap();
+        self.addrs.swap_remove(index);
+        if index < self.next_index {
+            // Keep the "iterator" consistent, the last element was moved to
+            // the already yielded position.
+            self.next_index -= 1;
+        }
+        Some(record.value)
+    }
+
     /// Forget all connections which:
     ///
     /// - has not been actively used for longer than [`MAX_CONN_AGE`],
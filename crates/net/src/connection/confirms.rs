@@ -4,7 +4,6 @@ use std::{
     time::{Duration, Instant},
 };
 
-use ahash::AHashSet;
 use async_std::{
     channel::{SendError, Sender},
     sync::{Arc, Mutex},
@@ -18,12 +17,19 @@ use crate::{
     tasks::OutDatagram,
 };
 
-/// The buffer is flushed after it grows beyond this number of bytes.
-// Each ID is 3 bytes, thus this must be a multiple of 3.
-const MAX_BUFF_SIZE: usize = 96;
+/// The buffer is flushed after it grows beyond this number of IDs.
+const MAX_BUFF_IDS: usize = 128;
+/// Size of a single entry of a confirmation datagram: base ID and a bitfield
+/// of following IDs.
+const ENTRY_SIZE: usize = 7;
 /// The buffer is flushed after the oldest part is older than this.
 const MAX_BUFF_AGE: Duration = Duration::from_millis(100);
 const MAX_SKIPPED: usize = 1024;
+/// Number of most recent package IDs tracked for duplicate detection. It must
+/// be a power of two and at least [`MAX_SKIPPED`] + 1 so that no skipped
+/// package falls out of the window.
+const WINDOW_SIZE: usize = 2048;
+const WINDOW_WORDS: usize = WINDOW_SIZE / 64;
 
 #[derive(Clone)]
 pub(crate) struct Confirmations {
@@ -87,11 +93,7 @@ impl Confirmations {
                 if force || expiration <= time || id_receiver.buffer.full() {
                     while let Some(data) = id_receiver.buffer.flush(MAX_PACKAGE_SIZE) {
                         datagrams
-                            .send(OutDatagram::new(
-                                DatagramHeader::Confirmation,
-                                data.to_vec(),
-                                addr,
-                            ))
+                            .send(OutDatagram::new(DatagramHeader::Confirmation, data, addr))
                             .await?;
                     }
                 } else {
@@ -103,6 +105,22 @@ impl Confirmations {
         Ok(next)
     }
 
+    /// If a package with `id` from `addr` was already marked as received,
+    /// its confirmation is sent again and true is returned. Otherwise, the
+    /// package is not marked as received and false is returned.
+    pub(crate) async fn received_duplicate(
+        &mut self,
+        time: Instant,
+        addr: SocketAddr,
+        id: PackageId,
+    ) -> bool {
+        self.book
+            .lock()
+            .await
+            .get_mut(addr)
+            .map_or(false, |receiver| receiver.push_duplicate(time, id))
+    }
+
     pub(crate) async fn clean(&mut self, time: Instant) {
         self.book.lock().await.clean(time);
     }
@@ -131,6 +149,16 @@ impl IdReceiver {
         self.buffer.push(time, id);
         Ok(duplicate)
     }
+
+    /// Re-confirms a package if it was already received. It returns true in
+    /// such a case.
+    fn push_duplicate(&mut self, time: Instant, id: PackageId) -> bool {
+        let duplicate = self.duplicates.contains(id);
+        if duplicate {
+            self.buffer.push(time, id);
+        }
+        duplicate
+    }
 }
 
 impl Connection for IdReceiver {
@@ -139,47 +167,108 @@ impl Connection for IdReceiver {
     }
 }
 
+/// Sliding window of recently received package IDs.
+///
+/// The window spans [`WINDOW_SIZE`] IDs ending with the highest received ID.
+/// Packages older than the window are considered to be duplicates.
 struct Duplicates {
-    highest_id: Option<PackageId>,
-    holes: AHashSet<PackageId>,
+    /// Highest received package ID. Before any package is received, this
+    /// points just before zero so that the counting starts from zero.
+    highest_id: PackageId,
+    /// Bit for each ID in the window (indexed by ID modulo window size) set if
+    /// the package was received.
+    received: [u64; WINDOW_WORDS],
+    /// Number of not yet received packages within the window.
+    skipped: usize,
 }
 
 impl Duplicates {
     fn new() -> Self {
         Self {
-            highest_id: None,
-            holes: AHashSet::new(),
+            // Guaranteed to be a valid ID.
+            highest_id: PackageId::try_from(0xffffff).unwrap(),
+            // IDs before zero are never delivered.
+            received: [u64::MAX; WINDOW_WORDS],
+            skipped: 0,
         }
     }
 
     /// Registers package as delivered and returns true if it was already
     /// delivered in the past.
     fn process(&mut self, id: PackageId) -> Result<bool, PackageIdError> {
-        let range_start = match self.highest_id {
-            Some(highest) => match highest.ordering(id) {
-                Ordering::Less => highest.incremented(),
-                Ordering::Greater => {
-                    return Ok(!self.holes.remove(&id));
+        match self.highest_id.ordering(id) {
+            Ordering::Less => {
+                let distance = id.distance(self.highest_id) as usize;
+                if distance - 1 > MAX_SKIPPED {
+                    return Err(PackageIdError::TooManySkipped(distance - 1 + self.skipped));
+                }
+
+                // Slots of the new IDs are occupied by the oldest IDs which
+                // fall out of the window. Not yet received packages among
+                // them are forgotten.
+                let new_ids =
+                    || PackageIdRange::range(self.highest_id.incremented(), id.incremented());
+                let evicted = new_ids().filter(|&new| !self.get(new)).count();
+
+                let skipped = distance - 1 + self.skipped - evicted;
+                if skipped > MAX_SKIPPED {
+                    return Err(PackageIdError::TooManySkipped(skipped));
                 }
-                Ordering::Equal => {
+
+                for new in PackageIdRange::range(self.highest_id.incremented(), id) {
+                    self.set(new, false);
+                }
+                self.set(id, true);
+                self.highest_id = id;
+                self.skipped = skipped;
+
+                Ok(false)
+            }
+            Ordering::Greater => {
+                if self.highest_id.distance(id) as usize >= WINDOW_SIZE {
                     return Ok(true);
                 }
-            },
-            None => PackageId::zero(),
-        };
-
-        let range = PackageIdRange::range(range_start, id);
-        let skipped = range.size_hint().1.unwrap() + self.holes.len();
-        if skipped > MAX_SKIPPED {
-            return Err(PackageIdError::TooManySkipped(skipped));
+
+                let duplicate = self.get(id);
+                if !duplicate {
+                    self.set(id, true);
+                    self.skipped -= 1;
+                }
+                Ok(duplicate)
+            }
+            Ordering::Equal => Ok(true),
+        }
+    }
+
+    /// Returns true if the package was already delivered. Contrary to
+    /// [`Self::process`], the package is not registered.
+    fn contains(&self, id: PackageId) -> bool {
+        match self.highest_id.ordering(id) {
+            Ordering::Less => false,
+            Ordering::Greater => {
+                self.highest_id.distance(id) as usize >= WINDOW_SIZE || self.get(id)
+            }
+            Ordering::Equal => true,
         }
+    }
 
-        self.highest_id = Some(id);
-        for hole in range {
-            self.holes.insert(hole);
+    fn get(&self, id: PackageId) -> bool {
+        let (word, mask) = Self::position(id);
+        self.received[word] & mask != 0
+    }
+
+    fn set(&mut self, id: PackageId, received: bool) {
+        let (word, mask) = Self::position(id);
+        if received {
+            self.received[word] |= mask;
+        } else {
+            self.received[word] &= !mask;
         }
+    }
 
-        Ok(false)
+    fn position(id: PackageId) -> (usize, u64) {
+        let index = id.distance(PackageId::zero()) as usize % WINDOW_SIZE;
+        (index / 64, 1 << (index % 64))
     }
 }
 
@@ -190,38 +279,40 @@ pub(crate) enum PackageIdError {
 }
 
 /// Buffer with datagram confirmations.
+///
+/// Confirmations are encoded as a sequence of entries. Each entry consists of
+/// a 3-byte base package ID followed by a 4-byte bitfield. Bit `i` (counting
+/// from the least significant bit) of the bitfield is set if package with ID
+/// `base + i + 1` is confirmed as well.
 struct Buffer {
     oldest: Instant,
-    buffer: Vec<u8>,
-    flushed: usize,
+    ids: Vec<PackageId>,
 }
 
 impl Buffer {
     fn new() -> Self {
         Self {
             oldest: Instant::now(),
-            buffer: Vec::with_capacity(MAX_BUFF_SIZE),
-            flushed: 0,
+            ids: Vec::with_capacity(MAX_BUFF_IDS),
         }
     }
 
     fn is_empty(&self) -> bool {
-        self.buffer.is_empty()
+        self.ids.is_empty()
     }
 
     /// Pushes another datagram ID to the buffer.
     fn push(&mut self, time: Instant, id: PackageId) {
-        if self.buffer.is_empty() {
+        if self.ids.is_empty() {
             self.oldest = time;
         }
-        self.buffer.extend_from_slice(&id.to_bytes());
-        self.flushed = self.buffer.len();
+        self.ids.push(id);
     }
 
     /// Returns time when the buffer expires, i.e. time when it becomes
     /// necessary to flush the buffer and send the confirmations.
     fn expiration(&self) -> Option<Instant> {
-        if self.flushed == 0 {
+        if self.ids.is_empty() {
             None
         } else {
             Some(self.oldest + MAX_BUFF_AGE)
@@ -229,27 +320,101 @@ impl Buffer {
     }
 
     fn full(&self) -> bool {
-        self.buffer.len() >= MAX_BUFF_SIZE
+        self.ids.len() >= MAX_BUFF_IDS
     }
 
-    /// Return accumulated bytes from the buffer if it is not empty. The number
-    /// of returned bytes is always smaller than `max_size`. This method should
-    /// be called repeatedly until it returns None.
-    fn flush(&mut self, max_size: usize) -> Option<&[u8]> {
-        self.buffer.truncate(self.flushed);
+    /// Encodes and removes accumulated IDs from the buffer if it is not
+    /// empty. The number of returned bytes is always smaller or equal to
+    /// `max_size`. This method should be called repeatedly until it returns
+    /// None.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_size` is smaller than size of a single entry.
+    fn flush(&mut self, max_size: usize) -> Option<Vec<u8>> {
+        assert!(max_size >= ENTRY_SIZE);
 
-        if self.buffer.is_empty() {
-            None
-        } else {
-            // Make sure it is multiple of 4 (i.e. larges multiple of 4 smaller
-            // or equal than the original).
-            let size = self.buffer.len().min(max_size & (usize::MAX - 3));
-            self.flushed = self.buffer.len() - size;
-            Some(&self.buffer[self.flushed..])
+        if self.ids.is_empty() {
+            return None;
+        }
+
+        self.ids
+            .sort_unstable_by_key(|id| id.distance(PackageId::zero()));
+        self.ids.dedup();
+
+        let mut data = Vec::with_capacity(max_size);
+        let mut consumed = 0;
+
+        while consumed < self.ids.len() && data.len() + ENTRY_SIZE <= max_size {
+            let base = self.ids[consumed];
+            consumed += 1;
+
+            let mut bits = 0u32;
+            while let Some(&id) = self.ids.get(consumed) {
+                let distance = id.distance(base);
+                if distance > u32::BITS {
+                    break;
+                }
+                bits |= 1 << (distance - 1);
+                consumed += 1;
+            }
+
+            data.extend_from_slice(&base.to_bytes());
+            data.extend_from_slice(&bits.to_be_bytes());
+        }
+
+        self.ids.drain(..consumed);
+        Some(data)
+    }
+}
+
+/// Iterator over package IDs encoded in a confirmation datagram payload. See
+/// [`Buffer`].
+pub(super) struct ConfirmedIds<'a> {
+    data: &'a [u8],
+    base: PackageId,
+    bits: u32,
+}
+
+impl<'a> ConfirmedIds<'a> {
+    /// Creates a new iterator. Incomplete trailing entry is ignored.
+    pub(super) fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            base: PackageId::zero(),
+            bits: 0,
         }
     }
 }
 
+impl<'a> Iterator for ConfirmedIds<'a> {
+    type Item = PackageId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bits != 0 {
+            let offset = self.bits.trailing_zeros();
+            self.bits &= self.bits - 1;
+
+            let mut id = self.base;
+            for _ in 0..=offset {
+                id = id.incremented();
+            }
+            return Some(id);
+        }
+
+        if self.data.len() < ENTRY_SIZE {
+            return None;
+        }
+
+        let (entry, rest) = self.data.split_at(ENTRY_SIZE);
+        self.data = rest;
+
+        self.base = PackageId::from_bytes(&entry[0..3]);
+        self.bits = u32::from_be_bytes(entry[3..ENTRY_SIZE].try_into().unwrap());
+        Some(self.base)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,9 +436,15 @@ mod tests {
             .process(PackageId::from_bytes(&[0, 0, 0]))
             .unwrap());
 
+        assert!(!duplicates.contains(PackageId::from_bytes(&[0, 0, 5])));
         assert!(!duplicates
             .process(PackageId::from_bytes(&[0, 0, 5]))
             .unwrap());
+        assert!(duplicates.contains(PackageId::from_bytes(&[0, 0, 5])));
+        assert!(duplicates.contains(PackageId::from_bytes(&[0, 0, 1])));
+        // Checking does not register the package.
+        assert!(!duplicates.contains(PackageId::from_bytes(&[0, 0, 3])));
+        assert!(!duplicates.contains(PackageId::from_bytes(&[0, 0, 3])));
         assert!(!duplicates
             .process(PackageId::from_bytes(&[0, 0, 3]))
             .unwrap());
@@ -291,6 +462,27 @@ mod tests {
             duplicates.process(PackageId::from_bytes(&[50, 0, 6])),
             Err(PackageIdError::TooManySkipped(3276800))
         ));
+
+        for i in 7..1500 {
+            assert!(!duplicates.process(i.try_into().unwrap()).unwrap());
+        }
+        assert!(!duplicates
+            .process(PackageId::from_bytes(&[0, 0, 4]))
+            .unwrap());
+        assert!(duplicates
+            .process(PackageId::from_bytes(&[0, 0, 4]))
+            .unwrap());
+
+        // The hole falls out of the window.
+        assert!(!duplicates.process(1501.try_into().unwrap()).unwrap());
+        for i in 1502..3600 {
+            assert!(!duplicates.process(i.try_into().unwrap()).unwrap());
+        }
+        assert_eq!(duplicates.skipped, 0);
+        assert!(duplicates.process(1500.try_into().unwrap()).unwrap());
+        assert!(!duplicates.process(3601.try_into().unwrap()).unwrap());
+        assert!(!duplicates.process(3600.try_into().unwrap()).unwrap());
+        assert!(duplicates.process(3600.try_into().unwrap()).unwrap());
     }
 
     #[test]
@@ -298,7 +490,7 @@ mod tests {
         let now = Instant::now();
         let mut buf = Buffer::new();
 
-        assert!(buf.flush(13).is_none());
+        assert!(buf.flush(14).is_none());
         assert!(buf.expiration().is_none());
         assert!(!buf.full());
 
@@ -306,7 +498,7 @@ mod tests {
         assert!(buf.expiration().unwrap() > now);
         assert!(!buf.full());
 
-        assert_eq!(buf.flush(13).unwrap(), &[0, 4, 18]);
+        assert_eq!(buf.flush(13).unwrap(), [0, 4, 18, 0, 0, 0, 0]);
         assert!(buf.expiration().is_none());
         assert!(!buf.full());
 
@@ -318,40 +510,85 @@ mod tests {
         assert_eq!(buf.expiration(), Some(now + MAX_BUFF_AGE));
         assert!(!buf.full());
 
-        assert_eq!(buf.flush(13).unwrap(), &[0, 0, 43]);
-        assert!(buf.flush(13).is_none());
+        buf.push(now, 40.try_into().unwrap());
+        buf.push(now, 43.try_into().unwrap());
+        buf.push(now, 72.try_into().unwrap());
+        buf.push(now, 73.try_into().unwrap());
+        buf.push(now, 500.try_into().unwrap());
+
+        assert_eq!(
+            buf.flush(14).unwrap(),
+            [
+                0,
+                0,
+                40,
+                0b1000_0000,
+                0,
+                0,
+                0b0000_0100,
+                0,
+                0,
+                73,
+                0,
+                0,
+                0,
+                0
+            ]
+        );
+        assert_eq!(buf.flush(14).unwrap(), [0, 1, 244, 0, 0, 0, 0]);
+        assert!(buf.flush(14).is_none());
         assert!(!buf.full());
 
-        for i in 0..32 {
-            buf.push(now, (100 + i).try_into().unwrap());
-
-            if i < 31 {
-                assert!(!buf.full());
-            } else {
-                assert!(buf.full());
-            }
-        }
-
-        for i in 0..8 {
-            assert_eq!(
-                buf.flush(12 + (i as usize) % 3).unwrap(),
-                &[
-                    0,
-                    0,
-                    128 - i * 4,
-                    0,
-                    0,
-                    129 - i * 4,
-                    0,
-                    0,
-                    130 - i * 4,
-                    0,
-                    0,
-                    131 - i * 4
-                ]
-            );
+        for i in 0..MAX_BUFF_IDS {
+            assert!(!buf.full());
+            buf.push(now, (100 + i as u32).try_into().unwrap());
         }
+        assert!(buf.full());
+
+        let data = buf.flush(MAX_PACKAGE_SIZE).unwrap();
+        assert_eq!(data.len(), 4 * ENTRY_SIZE);
+        assert!(buf.flush(MAX_PACKAGE_SIZE).is_none());
+
+        let ids: Vec<PackageId> = ConfirmedIds::new(&data).collect();
+        assert_eq!(
+            ids,
+            (100..100 + MAX_BUFF_IDS as u32)
+                .map(|id| PackageId::try_from(id).unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
 
-        assert!(buf.flush(8).is_none());
+    #[test]
+    fn test_confirmed_ids() {
+        let ids: Vec<PackageId> = ConfirmedIds::new(&[
+            0,
+            0,
+            40,
+            0b1000_0000,
+            0,
+            0,
+            0b0000_0101,
+            255,
+            255,
+            255,
+            0,
+            0,
+            0,
+            1,
+            0,
+            1,
+        ])
+        .collect();
+        assert_eq!(
+            ids,
+            [
+                PackageId::from_bytes(&[0, 0, 40]),
+                PackageId::from_bytes(&[0, 0, 41]),
+                PackageId::from_bytes(&[0, 0, 43]),
+                PackageId::from_bytes(&[0, 0, 72]),
+                PackageId::from_bytes(&[255, 255, 255]),
+                PackageId::from_bytes(&[0, 0, 0]),
+            ]
+        );
     }
 }
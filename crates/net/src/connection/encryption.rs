@@ -0,0 +1,761 @@
+use std::{
+    fmt,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use async_std::sync::{Arc, Mutex};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN},
+    agreement::{agree_ephemeral, EphemeralPrivateKey, UnparsedPublicKey, X25519},
+    hkdf::{Salt, HKDF_SHA256},
+    rand::SystemRandom,
+};
+use tracing::warn;
+
+use super::book::{Connection, ConnectionBook};
+use crate::{
+    header::{DatagramHeader, HEADER_SIZE},
+    token::TOKEN_SIZE,
+    Token,
+};
+
+/// Length of a public key sent in a hello datagram.
+pub(crate) const PUBLIC_KEY_LEN: usize = 32;
+/// Length of the counter (used as AEAD nonce) included in each encrypted
+/// datagram.
+const COUNTER_LEN: usize = 8;
+/// Length of the AEAD authentication tag.
+const TAG_LEN: usize = 16;
+/// Number of bytes by which a datagram grows when it is encrypted.
+pub(crate) const ENCRYPTION_OVERHEAD: usize = HEADER_SIZE + COUNTER_LEN + TAG_LEN;
+/// Minimum time between two unanswered hello datagrams sent to a peer.
+const HELLO_INTERVAL: Duration = Duration::from_secs(1);
+/// A warning is logged once this many hello datagrams sent to a peer stay
+/// unanswered.
+const MAX_HELLO_ATTEMPTS: u8 = 5;
+/// Number of most recent counters tracked by [`ReplayWindow`].
+const REPLAY_WINDOW: u64 = 64;
+
+/// Per connection encryption of datagrams.
+///
+/// Encryption is used only with peers which negotiated it (see
+/// [`crate::Capabilities::ENCRYPTION`]), communication with other peers
+/// happens in plain text. One side of the connection initiates the handshake
+/// with [`Self::initiate`], the other side accepts it with [`Self::accept`].
+///
+/// Session keys are established with an X25519 key exchange of ephemeral
+/// keys carried by hello datagrams. The keys are derived from the exchanged
+/// secret and from the token issued to the player when joining a game, thus
+/// only the two parties of the join handshake may establish the session.
+/// Afterwards all datagrams to and from the peer are encrypted and
+/// authenticated with ChaCha20-Poly1305 and replayed datagrams are rejected.
+///
+/// The initiator never falls back to plain text: until the handshake is
+/// finished, all datagrams to the peer are withheld (reliable datagrams are
+/// re-sent later) and all plain text datagrams from the peer are rejected.
+/// The responder communicates in plain text until it receives the hello of
+/// the initiator because the initiator learns the token only from a plain
+/// text response. Once the session is established, plain text datagrams are
+/// rejected by both sides, thus the source address of a peer cannot be
+/// spoofed.
+///
+/// The struct is a cheaply clonable handle, all clones share the sessions.
+#[derive(Clone)]
+pub struct Encryption {
+    rng: SystemRandom,
+    book: Arc<Mutex<ConnectionBook<Session>>>,
+}
+
+impl Encryption {
+    pub fn new() -> Self {
+        Self {
+            rng: SystemRandom::new(),
+            book: Arc::new(Mutex::new(ConnectionBook::new())),
+        }
+    }
+
+    /// Starts an encryption handshake with a peer. Any previous session with
+    /// the peer is discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    ///
+    /// * `token` - token issued by the peer when joining a game. The peer
+    ///   must accept the handshake with the same token.
+    pub async fn initiate(&self, addr: SocketAddr, token: Token) {
+        self.negotiate(addr, Role::Initiator, token).await;
+    }
+
+    /// Enables encryption handshake initiated by a peer. Any previous session
+    /// with the peer is discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - address of the peer.
+    ///
+    /// * `token` - token issued to the peer when joining a game.
+    pub async fn accept(&self, addr: SocketAddr, token: Token) {
+        self.negotiate(addr, Role::Responder, token).await;
+    }
+
+    async fn negotiate(&self, addr: SocketAddr, role: Role, token: Token) {
+        let mut book = self.book.lock().await;
+        let session = book.update(Instant::now(), addr, Session::new);
+        *session = Session::new();
+        session.handshake = Some(Handshake { role, token });
+    }
+
+    /// Prepares a complete datagram for sending.
+    ///
+    /// # Arguments
+    ///
+    /// * `datagram` - complete (header included) plain text datagram.
+    ///
+    /// * `sealed` - the encrypted datagram is written to this buffer. The
+    ///   buffer must be at least [`ENCRYPTION_OVERHEAD`] bytes longer than
+    ///   `datagram`.
+    pub(crate) async fn outgoing(
+        &self,
+        time: Instant,
+        addr: SocketAddr,
+        datagram: &[u8],
+        sealed: &mut [u8],
+    ) -> Outgoing {
+        let mut book = self.book.lock().await;
+        let session = book.update(time, addr, Session::new);
+
+        if let Some(ref mut keys) = session.keys {
+            return Outgoing::Sealed(keys.seal(datagram, sealed));
+        }
+
+        match session.handshake {
+            None => Outgoing::Plain,
+            // The initiator has not yet learned the token or its hello has
+            // not yet arrived.
+            Some(Handshake {
+                role: Role::Responder,
+                ..
+            }) => Outgoing::Plain,
+            Some(Handshake {
+                role: Role::Initiator,
+                ..
+            }) => {
+                if session.last_hello.map_or(false, |last| {
+                    time.saturating_duration_since(last) < HELLO_INTERVAL
+                }) {
+                    return Outgoing::Withheld;
+                }
+
+                if session.hello_attempts == MAX_HELLO_ATTEMPTS {
+                    warn!(
+                        "Encryption handshake with {addr:?} is not answered, datagrams are \
+                        withheld until it is."
+                    );
+                }
+
+                match session.public_key(&self.rng) {
+                    Some(public_key) => {
+                        session.hello_attempts = session.hello_attempts.saturating_add(1);
+                        session.last_hello = Some(time);
+                        Outgoing::Hello(public_key)
+                    }
+                    None => Outgoing::Withheld,
+                }
+            }
+        }
+    }
+
+    /// Processes a received datagram.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - header of the received datagram.
+    ///
+    /// * `payload` - payload of the received datagram. Encrypted datagrams are
+    ///   decrypted in place.
+    pub(crate) async fn incoming(
+        &self,
+        time: Instant,
+        addr: SocketAddr,
+        header: DatagramHeader,
+        payload: &mut [u8],
+    ) -> Incoming {
+        let mut book = self.book.lock().await;
+
+        match header {
+            DatagramHeader::Hello => {
+                book.clean(time);
+                match book.get_mut(addr) {
+                    Some(session) if session.handshake.is_some() => {
+                        let incoming = session.hello(&self.rng, addr, payload);
+                        book.update(time, addr, Session::new);
+                        incoming
+                    }
+                    _ => {
+                        warn!(
+                            "Hello datagram received from {addr:?} which did not negotiate \
+                            encryption."
+                        );
+                        Incoming::Drop
+                    }
+                }
+            }
+            DatagramHeader::Encrypted => {
+                let Some(keys) = book.get_mut(addr).and_then(|session| session.keys.as_mut())
+                else {
+                    return Incoming::Drop;
+                };
+                match keys.open(payload) {
+                    Some(len) => {
+                        // Refresh last update time of the session.
+                        book.update(time, addr, Session::new);
+                        Incoming::Decrypted(COUNTER_LEN..COUNTER_LEN + len)
+                    }
+                    None => Incoming::Drop,
+                }
+            }
+            _ => {
+                let encrypted = book.get_mut(addr).map_or(false, |session| {
+                    session.keys.is_some()
+                        || session
+                            .handshake
+                            .map_or(false, |handshake| handshake.role == Role::Initiator)
+                });
+
+                if encrypted {
+                    Incoming::Drop
+                } else {
+                    Incoming::Plain
+                }
+            }
+        }
+    }
+
+    /// Forgets session with a peer which closed the connection.
+    pub(crate) async fn forget(&self, addr: SocketAddr) {
+        self.book.lock().await.remove(addr);
+    }
+}
+
+impl Default for Encryption {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encryption").finish_non_exhaustive()
+    }
+}
+
+pub(crate) enum Outgoing {
+    /// The datagram is to be sent as is.
+    Plain,
+    /// A hello datagram with this public key is to be sent instead of the
+    /// datagram, which must not be sent before the handshake is finished.
+    Hello([u8; PUBLIC_KEY_LEN]),
+    /// The datagram was encrypted, the encrypted datagram has this length.
+    Sealed(usize),
+    /// The datagram must not be sent before the handshake is finished.
+    Withheld,
+}
+
+pub(crate) enum Incoming {
+    /// The datagram was received in plain text and is to be processed as is.
+    Plain,
+    /// The datagram was decrypted. This range of the payload holds complete
+    /// (header included) decrypted datagram.
+    Decrypted(std::ops::Range<usize>),
+    /// A hello datagram with this public key is to be sent back to the
+    /// peer.
+    Reply([u8; PUBLIC_KEY_LEN]),
+    /// The datagram is invalid, not authentic, or of no further use.
+    Drop,
+}
+
+/// Encryption negotiated with a peer.
+#[derive(Clone, Copy)]
+struct Handshake {
+    role: Role,
+    /// The session keys are bound to this token.
+    token: Token,
+}
+
+/// Side of the handshake taken by this end of a connection.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Role {
+    /// Hello datagrams are sent to the peer until it replies.
+    Initiator,
+    /// Hello datagrams received from the peer are replied.
+    Responder,
+}
+
+struct Session {
+    /// None if encryption was not negotiated with the peer.
+    handshake: Option<Handshake>,
+    private_key: Option<EphemeralPrivateKey>,
+    public_key: Option<[u8; PUBLIC_KEY_LEN]>,
+    peer_key: Option<[u8; PUBLIC_KEY_LEN]>,
+    keys: Option<Keys>,
+    last_hello: Option<Instant>,
+    hello_attempts: u8,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            handshake: None,
+            private_key: None,
+            public_key: None,
+            peer_key: None,
+            keys: None,
+            last_hello: None,
+            hello_attempts: 0,
+        }
+    }
+
+    /// Returns own public key, a new key pair is generated if needed.
+    fn public_key(&mut self, rng: &SystemRandom) -> Option<[u8; PUBLIC_KEY_LEN]> {
+        if self.public_key.is_none() {
+            let private_key = EphemeralPrivateKey::generate(&X25519, rng).ok()?;
+            let public_key = private_key.compute_public_key().ok()?;
+            self.public_key = Some(public_key.as_ref().try_into().ok()?);
+            self.private_key = Some(private_key);
+        }
+        self.public_key
+    }
+
+    /// Processes a hello datagram from a peer with which encryption was
+    /// negotiated.
+    fn hello(&mut self, rng: &SystemRandom, addr: SocketAddr, payload: &[u8]) -> Incoming {
+        let handshake = self.handshake.unwrap();
+        let Ok(peer_key) = <[u8; PUBLIC_KEY_LEN]>::try_from(payload) else {
+            warn!("Invalid hello datagram received from {addr:?}.");
+            return Incoming::Drop;
+        };
+
+        if let Some(known) = self.peer_key {
+            if known != peer_key {
+                warn!("Hello datagram with an unexpected key received from {addr:?}.");
+                return Incoming::Drop;
+            }
+
+            // The peer has probably not received our reply.
+            return match self.public_key {
+                Some(public_key) => Incoming::Reply(public_key),
+                None => Incoming::Drop,
+            };
+        }
+
+        let Some(public_key) = self.public_key(rng) else {
+            return Incoming::Drop;
+        };
+        let private_key = self.private_key.take().unwrap();
+
+        let keys = agree_ephemeral(
+            private_key,
+            &UnparsedPublicKey::new(&X25519, peer_key),
+            (),
+            |secret| Keys::derive(secret, &public_key, &peer_key, handshake.token),
+        );
+        match keys {
+            Ok(keys) => {
+                self.peer_key = Some(peer_key);
+                self.keys = Some(keys);
+                if handshake.role == Role::Initiator {
+                    // This is a reply to our hello.
+                    Incoming::Drop
+                } else {
+                    Incoming::Reply(public_key)
+                }
+            }
+            Err(()) => {
+                warn!("Key agreement with {addr:?} failed.");
+                Incoming::Drop
+            }
+        }
+    }
+}
+
+impl Connection for Session {
+    fn pending(&self) -> bool {
+        false
+    }
+}
+
+struct Keys {
+    sealing: LessSafeKey,
+    opening: LessSafeKey,
+    counter: u64,
+    replay: ReplayWindow,
+}
+
+impl Keys {
+    /// Derives directional session keys from a shared secret and a join
+    /// token.
+    fn derive(
+        secret: &[u8],
+        own: &[u8; PUBLIC_KEY_LEN],
+        peer: &[u8; PUBLIC_KEY_LEN],
+        token: Token,
+    ) -> Result<Self, ()> {
+        // Both sides must use the same salt and agree on the key directions.
+        let (first, second) = if own < peer { (own, peer) } else { (peer, own) };
+        let mut salt = [0u8; 2 * PUBLIC_KEY_LEN + TOKEN_SIZE];
+        salt[..PUBLIC_KEY_LEN].copy_from_slice(first);
+        salt[PUBLIC_KEY_LEN..2 * PUBLIC_KEY_LEN].copy_from_slice(second);
+        salt[2 * PUBLIC_KEY_LEN..].copy_from_slice(&token.to_bytes());
+
+        let prk = Salt::new(HKDF_SHA256, &salt).extract(secret);
+        let key = |info: &[u8]| -> Result<LessSafeKey, ()> {
+            let info = [info];
+            let okm = prk.expand(&info, &CHACHA20_POLY1305).map_err(|_| ())?;
+            Ok(LessSafeKey::new(UnboundKey::from(okm)))
+        };
+
+        let first_key = key(b"de_net first")?;
+        let second_key = key(b"de_net second")?;
+        let (sealing, opening) = if own < peer {
+            (first_key, second_key)
+        } else {
+            (second_key, first_key)
+        };
+
+        Ok(Self {
+            sealing,
+            opening,
+            counter: 0,
+            replay: ReplayWindow::default(),
+        })
+    }
+
+    /// Encrypts a complete datagram and writes the resulting encrypted
+    /// datagram to `sealed`. Length of the encrypted datagram is returned.
+    fn seal(&mut self, datagram: &[u8], sealed: &mut [u8]) -> usize {
+        let counter = self.counter.to_be_bytes();
+        self.counter += 1;
+
+        let payload_start = HEADER_SIZE + COUNTER_LEN;
+        let tag_start = payload_start + datagram.len();
+        let len = tag_start + TAG_LEN;
+
+        DatagramHeader::Encrypted.write(sealed);
+        sealed[HEADER_SIZE..payload_start].copy_from_slice(&counter);
+        sealed[payload_start..tag_start].copy_from_slice(datagram);
+
+        let (aad, in_out) = sealed.split_at_mut(payload_start);
+        let tag = self
+            .sealing
+            .seal_in_place_separate_tag(
+                nonce(counter),
+                Aad::from(aad),
+                &mut in_out[..datagram.len()],
+            )
+            .unwrap();
+        sealed[tag_start..len].copy_from_slice(tag.as_ref());
+
+        len
+    }
+
+    /// Decrypts encrypted datagram payload in place and returns length of
+    /// the decrypted datagram (which starts right after the counter). None is
+    /// returned if the datagram is not authentic or if it was already
+    /// received.
+    fn open(&mut self, payload: &mut [u8]) -> Option<usize> {
+        if payload.len() < COUNTER_LEN + HEADER_SIZE + TAG_LEN {
+            return None;
+        }
+
+        let counter: [u8; COUNTER_LEN] = payload[..COUNTER_LEN].try_into().unwrap();
+        let number = u64::from_be_bytes(counter);
+        if !self.replay.check(number) {
+            return None;
+        }
+
+        let mut aad = [0u8; HEADER_SIZE + COUNTER_LEN];
+        DatagramHeader::Encrypted.write(&mut aad);
+        aad[HEADER_SIZE..].copy_from_slice(&counter);
+
+        let len = self
+            .opening
+            .open_in_place(
+                nonce(counter),
+                Aad::from(&aad[..]),
+                &mut payload[COUNTER_LEN..],
+            )
+            .ok()?
+            .len();
+        self.replay.update(number);
+        Some(len)
+    }
+}
+
+/// Sliding window of counters of received encrypted datagrams.
+///
+/// Datagrams may be reordered, thus a counter is accepted if it is newer
+/// than all accepted counters or if it falls within the window of
+/// [`REPLAY_WINDOW`] most recent counters and was not yet accepted.
+#[derive(Default)]
+struct ReplayWindow {
+    /// The newest accepted counter plus one or zero if nothing was accepted.
+    next: u64,
+    /// Bit `i` is set if counter `next - 1 - i` was accepted.
+    bitmap: u64,
+}
+
+impl ReplayWindow {
+    /// Returns true if a datagram with the counter may be accepted.
+    fn check(&self, counter: u64) -> bool {
+        if counter >= self.next {
+            return true;
+        }
+
+        let offset = self.next - 1 - counter;
+        offset < REPLAY_WINDOW && self.bitmap & (1 << offset) == 0
+    }
+
+    /// Marks a counter of an authentic datagram as accepted. The counter
+    /// must pass [`Self::check`].
+    fn update(&mut self, counter: u64) {
+        if counter >= self.next {
+            let shift = counter + 1 - self.next;
+            self.bitmap = if shift < REPLAY_WINDOW {
+                self.bitmap << shift
+            } else {
+                0
+            };
+            self.bitmap |= 1;
+            self.next = counter + 1;
+        } else {
+            self.bitmap |= 1 << (self.next - 1 - counter);
+        }
+    }
+}
+
+fn nonce(counter: [u8; COUNTER_LEN]) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[NONCE_LEN - COUNTER_LEN..].copy_from_slice(&counter);
+    Nonce::assume_unique_for_key(nonce)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+
+    use super::*;
+
+    #[test]
+    fn test_encryption() {
+        task::block_on(async {
+            let time = Instant::now();
+            let client_addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+            let server_addr: SocketAddr = "127.0.0.1:1002".parse().unwrap();
+            let client = Encryption::new();
+            let server = Encryption::new();
+
+            let datagram = [64, 0, 0, 1, 8, 9];
+            let mut sealed = [0u8; 64];
+
+            // Encryption was not negotiated yet.
+            assert!(matches!(
+                client
+                    .outgoing(time, server_addr, &datagram, &mut sealed)
+                    .await,
+                Outgoing::Plain
+            ));
+            let mut payload = [7u8; PUBLIC_KEY_LEN];
+            assert!(matches!(
+                server
+                    .incoming(time, client_addr, DatagramHeader::Hello, &mut payload)
+                    .await,
+                Incoming::Drop
+            ));
+
+            let token = Token::random();
+            client.initiate(server_addr, token).await;
+            server.accept(client_addr, token).await;
+
+            // The responder talks in plain text until the initiator starts
+            // the handshake.
+            assert!(matches!(
+                server
+                    .outgoing(time, client_addr, &datagram, &mut sealed)
+                    .await,
+                Outgoing::Plain
+            ));
+            // The initiator never falls back to plain text.
+            assert!(matches!(
+                client
+                    .incoming(time, server_addr, DatagramHeader::KeepAlive, &mut [])
+                    .await,
+                Incoming::Drop
+            ));
+
+            let Outgoing::Hello(client_key) = client
+                .outgoing(time, server_addr, &datagram, &mut sealed)
+                .await
+            else {
+                panic!("Hello expected.");
+            };
+            // Hello is not repeated too often and datagrams are withheld.
+            assert!(matches!(
+                client
+                    .outgoing(time, server_addr, &datagram, &mut sealed)
+                    .await,
+                Outgoing::Withheld
+            ));
+
+            let mut payload = client_key;
+            let Incoming::Reply(server_key) = server
+                .incoming(time, client_addr, DatagramHeader::Hello, &mut payload)
+                .await
+            else {
+                panic!("Reply expected.");
+            };
+
+            // The server has established the session, plain text is no
+            // longer accepted.
+            assert!(matches!(
+                server
+                    .incoming(time, client_addr, DatagramHeader::KeepAlive, &mut [])
+                    .await,
+                Incoming::Drop
+            ));
+
+            // The handshake was initiated by the client, the reply is not
+            // replied.
+            let mut payload = server_key;
+            assert!(matches!(
+                client
+                    .incoming(time, server_addr, DatagramHeader::Hello, &mut payload)
+                    .await,
+                Incoming::Drop
+            ));
+
+            let Outgoing::Sealed(len) = client
+                .outgoing(time, server_addr, &datagram, &mut sealed)
+                .await
+            else {
+                panic!("Sealed datagram expected.");
+            };
+            assert_eq!(len, datagram.len() + ENCRYPTION_OVERHEAD);
+            assert_eq!(
+                DatagramHeader::read(&sealed).unwrap(),
+                DatagramHeader::Encrypted
+            );
+
+            let mut tampered = sealed;
+            tampered[HEADER_SIZE + COUNTER_LEN] ^= 1;
+            assert!(matches!(
+                server
+                    .incoming(
+                        time,
+                        client_addr,
+                        DatagramHeader::Encrypted,
+                        &mut tampered[HEADER_SIZE..len]
+                    )
+                    .await,
+                Incoming::Drop
+            ));
+
+            let mut replayed = sealed;
+            let payload = &mut sealed[HEADER_SIZE..len];
+            let Incoming::Decrypted(range) = server
+                .incoming(time, client_addr, DatagramHeader::Encrypted, payload)
+                .await
+            else {
+                panic!("Decrypted datagram expected.");
+            };
+            assert_eq!(&payload[range], &datagram);
+
+            assert!(matches!(
+                server
+                    .incoming(
+                        time,
+                        client_addr,
+                        DatagramHeader::Encrypted,
+                        &mut replayed[HEADER_SIZE..len]
+                    )
+                    .await,
+                Incoming::Drop
+            ));
+        });
+    }
+
+    #[test]
+    fn test_token_mismatch() {
+        task::block_on(async {
+            let time = Instant::now();
+            let client_addr: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+            let server_addr: SocketAddr = "127.0.0.1:1002".parse().unwrap();
+            let client = Encryption::new();
+            let server = Encryption::new();
+
+            client.initiate(server_addr, Token::random()).await;
+            server.accept(client_addr, Token::random()).await;
+
+            let datagram = [64, 0, 0, 1, 8, 9];
+            let mut sealed = [0u8; 64];
+
+            let Outgoing::Hello(mut client_key) = client
+                .outgoing(time, server_addr, &datagram, &mut sealed)
+                .await
+            else {
+                panic!("Hello expected.");
+            };
+            let Incoming::Reply(mut server_key) = server
+                .incoming(time, client_addr, DatagramHeader::Hello, &mut client_key)
+                .await
+            else {
+                panic!("Reply expected.");
+            };
+            client
+                .incoming(time, server_addr, DatagramHeader::Hello, &mut server_key)
+                .await;
+
+            let Outgoing::Sealed(len) = client
+                .outgoing(time, server_addr, &datagram, &mut sealed)
+                .await
+            else {
+                panic!("Sealed datagram expected.");
+            };
+            assert!(matches!(
+                server
+                    .incoming(
+                        time,
+                        client_addr,
+                        DatagramHeader::Encrypted,
+                        &mut sealed[HEADER_SIZE..len]
+                    )
+                    .await,
+                Incoming::Drop
+            ));
+        });
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let mut window = ReplayWindow::default();
+
+        for counter in [0, 1, 5, 3] {
+            assert!(window.check(counter));
+            window.update(counter);
+        }
+        for counter in [0, 1, 3, 5] {
+            assert!(!window.check(counter));
+        }
+        assert!(window.check(2));
+        assert!(window.check(4));
+
+        window.update(100);
+        assert!(!window.check(2));
+        assert!(!window.check(100));
+        assert!(window.check(99));
+        assert!(window.check(37));
+        assert!(!window.check(36));
+        assert!(window.check(101));
+    }
+}
@@ -1,7 +1,15 @@
+pub(crate) use alive::{Liveness, KEEP_ALIVE_INTERVAL};
 pub(crate) use confirms::Confirmations;
+pub use encryption::Encryption;
+pub(crate) use encryption::{Incoming, Outgoing, ENCRYPTION_OVERHEAD, PUBLIC_KEY_LEN};
 pub(crate) use resend::Resends;
+pub use stats::ConnectionStats;
+pub(crate) use stats::{Delivery, Stats};
 
+mod alive;
 mod book;
 mod confirms;
 mod databuf;
+mod encryption;
 mod resend;
+mod stats;
@@ -13,25 +13,28 @@ use priority_queue::PriorityQueue;
 
 use super::{
     book::{Connection, ConnectionBook, MAX_CONN_AGE},
+    confirms::ConfirmedIds,
     databuf::DataBuf,
+    stats::{Delivery, Stats},
 };
 use crate::{
-    header::{DatagramHeader, PackageId, Peers},
+    header::{DatagramHeader, PackageHeader, PackageId},
     tasks::OutDatagram,
+    GiveUp, Priority, ResendPolicy,
 };
 
-const START_BACKOFF_MS: u64 = 220;
-const MAX_TRIES: u8 = 6;
-const MAX_BASE_RESEND_INTERVAL_MS: u64 = (MAX_CONN_AGE.as_millis() / 2) as u64;
+const MAX_BASE_RESEND_INTERVAL: Duration = Duration::from_secs(MAX_CONN_AGE.as_secs() / 2);
 
 #[derive(Clone)]
 pub(crate) struct Resends {
+    policy: ResendPolicy,
     book: Arc<Mutex<ConnectionBook<Queue>>>,
 }
 
 impl Resends {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(policy: ResendPolicy) -> Self {
         Self {
+            policy,
             book: Arc::new(Mutex::new(ConnectionBook::new())),
         }
     }
@@ -40,41 +43,59 @@ impl Resends {
         &mut self,
         time: Instant,
         addr: SocketAddr,
-        id: PackageId,
-        peers: Peers,
+        header: PackageHeader,
+        priority: Priority,
         data: &[u8],
     ) {
+        let policy = self.policy;
         let mut book = self.book.lock().await;
-        let queue = book.update(time, addr, Queue::new);
-        queue.push(id, peers, data, time);
+        let queue = book.update(time, addr, || Queue::new(policy));
+        queue.push(header, priority, data, time);
     }
 
     /// Processes data with package confirmations.
     ///
     /// The data encode IDs of delivered (and confirmed) packages so that they
     /// can be forgotten.
-    pub(crate) async fn confirmed(&mut self, time: Instant, addr: SocketAddr, data: &[u8]) {
+    ///
+    /// # Arguments
+    ///
+    /// * `rtts` - round trip times of confirmed packages which were delivered
+    ///   without any re-send are pushed to this Vec.
+    pub(crate) async fn confirmed(
+        &mut self,
+        time: Instant,
+        addr: SocketAddr,
+        data: &[u8],
+        rtts: &mut Vec<Duration>,
+    ) {
+        let policy = self.policy;
         let mut book = self.book.lock().await;
-        let queue = book.update(time, addr, Queue::new);
+        let queue = book.update(time, addr, || Queue::new(policy));
 
-        for i in 0..data.len() / 3 {
-            let offset = i * 3;
-            let id = PackageId::from_bytes(&data[offset..offset + 3]);
-            queue.resolve(id);
+        for id in ConfirmedIds::new(data) {
+            if let Some(rtt) = queue.resolve(id, time) {
+                rtts.push(rtt);
+            }
         }
     }
 
     /// Re-send all packages already due for re-sending.
+    ///
+    /// All re-sent packages are registered to `stats`. Packages which reached
+    /// the maximum number of attempts are given up in accordance with the
+    /// [`ResendPolicy`].
     pub(crate) async fn resend(
         &mut self,
         time: Instant,
         buf: &mut [u8],
         datagrams: &mut Sender<OutDatagram>,
+        stats: &mut Stats,
     ) -> Result<ResendResult, SendError<OutDatagram>> {
         let mut result = ResendResult {
             failures: Vec::new(),
             pending: 0,
-            next: time + Duration::from_millis(START_BACKOFF_MS),
+            next: time + self.policy.initial_timeout(),
         };
 
         let mut book = self.book.lock().await;
@@ -82,13 +103,21 @@ impl Resends {
         while let Some((addr, queue)) = book.next() {
             let failure = loop {
                 match queue.reschedule(buf, time) {
-                    RescheduleResult::Resend { len, id, peers } => {
+                    RescheduleResult::Resend {
+                        len,
+                        header,
+                        priority,
+                    } => {
+                        stats.sent(time, addr, Delivery::Resend, len).await;
                         datagrams
-                            .send(OutDatagram::new(
-                                DatagramHeader::new_package(true, peers, id),
-                                buf[..len].to_vec(),
-                                addr,
-                            ))
+                            .send(
+                                OutDatagram::new(
+                                    DatagramHeader::Package(header),
+                                    buf[..len].to_vec(),
+                                    addr,
+                                )
+                                .with_priority(priority),
+                            )
                             .await?;
                     }
                     RescheduleResult::Waiting(until) => {
@@ -100,14 +129,16 @@ impl Resends {
                     }
                     RescheduleResult::Failed => {
                         result.failures.push(addr);
-                        break true;
+                        match self.policy.give_up() {
+                            GiveUp::DropPackage => queue.drop_next(),
+                            GiveUp::DropPeer => break true,
+                        }
                     }
                 }
             };
 
             if failure {
                 book.remove_current();
-                result.failures.push(addr);
             } else {
                 result.pending += queue.len();
             }
@@ -122,7 +153,8 @@ impl Resends {
 }
 
 pub(crate) struct ResendResult {
-    /// Vec of failed connections.
+    /// Vec of connections with an undelivered package. A connection is
+    /// included once per each undelivered package.
     pub(crate) failures: Vec<SocketAddr>,
     /// Number of pending (not yet confirmed) datagrams.
     pub(crate) pending: usize,
@@ -133,14 +165,16 @@ pub(crate) struct ResendResult {
 /// This struct governs reliable package re-sending (until each package is
 /// confirmed).
 struct Queue {
+    policy: ResendPolicy,
     queue: PriorityQueue<PackageId, Timing>,
-    meta: AHashMap<PackageId, Peers>,
+    meta: AHashMap<PackageId, Meta>,
     data: DataBuf,
 }
 
 impl Queue {
-    fn new() -> Self {
+    fn new(policy: ResendPolicy) -> Self {
         Self {
+            policy,
             queue: PriorityQueue::new(),
             meta: AHashMap::new(),
             data: DataBuf::new(),
@@ -153,17 +187,41 @@ impl Queue {
     }
 
     /// Registers new package for re-sending until it is resolved.
-    fn push(&mut self, id: PackageId, peers: Peers, data: &[u8], now: Instant) {
-        self.queue.push(id, Timing::new(now));
-        self.meta.insert(id, peers);
+    fn push(&mut self, header: PackageHeader, priority: Priority, data: &[u8], now: Instant) {
+        let id = header.id();
+        self.queue.push(id, Timing::new(&self.policy, now));
+        self.meta.insert(
+            id,
+            Meta {
+                header,
+                priority,
+                sent: now,
+            },
+        );
         self.data.push(id, data);
     }
 
     /// Marks a package as delivered. No more re-sends will be scheduled and
     /// package data will be dropped.
-    fn resolve(&mut self, id: PackageId) {
-        let result = self.queue.remove(&id);
-        if result.is_some() {
+    ///
+    /// Time elapsed since the package was sent is returned if the package was
+    /// pending and has not been re-sent.
+    fn resolve(&mut self, id: PackageId, now: Instant) -> Option<Duration> {
+        let (_, timing) = self.queue.remove(&id)?;
+        let meta = self.meta.remove(&id).unwrap();
+        self.data.remove(id);
+
+        if timing.attempt == 0 {
+            Some(now.saturating_duration_since(meta.sent))
+        } else {
+            None
+        }
+    }
+
+    /// Forgets the package with the soonest scheduled re-send. This is used to
+    /// give up a package after [`RescheduleResult::Failed`].
+    fn drop_next(&mut self) {
+        if let Some((id, _)) = self.queue.pop() {
             self.meta.remove(&id);
             self.data.remove(id);
         }
@@ -190,12 +248,16 @@ impl Queue {
             Some((&id, timing)) => {
                 let until = timing.expiration();
                 if until <= now {
-                    match timing.another(now) {
+                    match timing.another(&self.policy, now) {
                         Some(backoff) => {
                             self.queue.change_priority(&id, backoff);
                             let len = self.data.get(id, buf).unwrap();
-                            let peers = *self.meta.get(&id).unwrap();
-                            RescheduleResult::Resend { len, id, peers }
+                            let meta = self.meta.get(&id).unwrap();
+                            RescheduleResult::Resend {
+                                len,
+                                header: meta.header,
+                                priority: meta.priority,
+                            }
                         }
                         None => RescheduleResult::Failed,
                     }
@@ -214,14 +276,21 @@ impl Connection for Queue {
     }
 }
 
+struct Meta {
+    header: PackageHeader,
+    priority: Priority,
+    /// Time of the first delivery attempt.
+    sent: Instant,
+}
+
 /// Rescheduling result.
 pub(crate) enum RescheduleResult {
     /// A datagram is scheduled for an immediate resend.
     Resend {
         /// Length of the datagram data (written to a buffer) in bytes.
         len: usize,
-        id: PackageId,
-        peers: Peers,
+        header: PackageHeader,
+        priority: Priority,
     },
     /// No datagram is currently scheduled for an immediate resent. This
     /// variant holds soonest possible time of a next resend.
@@ -241,10 +310,10 @@ struct Timing {
 }
 
 impl Timing {
-    fn new(now: Instant) -> Self {
+    fn new(policy: &ResendPolicy, now: Instant) -> Self {
         Self {
             attempt: 0,
-            expiration: Self::schedule(0, now),
+            expiration: Self::schedule(policy, 0, now),
         }
     }
 
@@ -252,29 +321,35 @@ impl Timing {
         self.expiration
     }
 
-    fn another(&self, now: Instant) -> Option<Self> {
-        if self.attempt == MAX_TRIES {
+    fn another(&self, policy: &ResendPolicy, now: Instant) -> Option<Self> {
+        if self.attempt >= policy.max_attempts() {
             None
         } else {
             let attempt = self.attempt + 1;
             Some(Self {
                 attempt,
-                expiration: Self::schedule(attempt, now),
+                expiration: Self::schedule(policy, attempt, now),
             })
         }
     }
 
-    fn schedule(attempt: u8, now: Instant) -> Instant {
-        let millis = Self::jitter(Self::backoff(attempt));
-        now + Duration::from_millis(millis)
+    fn schedule(policy: &ResendPolicy, attempt: u8, now: Instant) -> Instant {
+        now + Self::jitter(Self::backoff(policy, attempt))
     }
 
-    fn backoff(attempt: u8) -> u64 {
-        MAX_BASE_RESEND_INTERVAL_MS.min(START_BACKOFF_MS * 2u64.pow(attempt as u32))
+    fn backoff(policy: &ResendPolicy, attempt: u8) -> Duration {
+        let factor = policy.multiplier().powi(attempt as i32);
+        let base = policy.initial_timeout().as_secs_f32() * factor;
+        if base >= MAX_BASE_RESEND_INTERVAL.as_secs_f32() {
+            MAX_BASE_RESEND_INTERVAL
+        } else {
+            Duration::from_secs_f32(base)
+        }
     }
 
-    fn jitter(millis: u64) -> u64 {
-        millis + fastrand::u64(0..millis / 2)
+    fn jitter(backoff: Duration) -> Duration {
+        let millis = backoff.as_millis() as u64;
+        backoff + Duration::from_millis(fastrand::u64(0..=millis / 2))
     }
 }
 
@@ -297,3 +372,64 @@ impl PartialEq for Timing {
         self.expiration == other.expiration && self.attempt == other.attempt
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::Peers;
+
+    #[test]
+    fn test_backoff() {
+        let policy = ResendPolicy::new(Duration::from_millis(100), 3., 2, GiveUp::DropPeer);
+
+        assert_eq!(Timing::backoff(&policy, 0).as_millis(), 100);
+        assert_eq!(Timing::backoff(&policy, 2).as_millis(), 900);
+        assert_eq!(Timing::backoff(&policy, 100), MAX_BASE_RESEND_INTERVAL);
+
+        let now = Instant::now();
+        let timing = Timing::new(&policy, now);
+        assert!(timing.expiration() >= now + Duration::from_millis(99));
+        assert!(timing.expiration() <= now + Duration::from_millis(151));
+
+        let timing = timing.another(&policy, now).unwrap();
+        assert!(timing.expiration() >= now + Duration::from_millis(299));
+        let timing = timing.another(&policy, now).unwrap();
+        assert!(timing.another(&policy, now).is_none());
+    }
+
+    #[test]
+    fn test_give_up() {
+        let policy = ResendPolicy::new(Duration::from_millis(100), 2., 0, GiveUp::DropPackage);
+        let now = Instant::now();
+        let later = now + Duration::from_secs(1);
+        let mut buf = [0u8; 16];
+
+        let mut queue = Queue::new(policy);
+        let header = |id: u32| {
+            let id = id.try_into().unwrap();
+            match DatagramHeader::new_package(true, Peers::Server, false, false, id) {
+                DatagramHeader::Package(header) => header,
+                _ => unreachable!(),
+            }
+        };
+        queue.push(header(1), Priority::Command, &[1, 2], now);
+        queue.push(header(2), Priority::Chat, &[3], now);
+        assert_eq!(queue.len(), 2);
+
+        assert!(matches!(
+            queue.reschedule(&mut buf, later),
+            RescheduleResult::Failed
+        ));
+        queue.drop_next();
+        assert_eq!(queue.len(), 1);
+        assert!(matches!(
+            queue.reschedule(&mut buf, later),
+            RescheduleResult::Failed
+        ));
+        queue.drop_next();
+        assert!(matches!(
+            queue.reschedule(&mut buf, later),
+            RescheduleResult::Empty
+        ));
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use async_std::{
+    channel::Sender,
+    sync::{Arc, Mutex},
+};
+
+use super::book::{Connection, ConnectionBook};
+use crate::NetMetrics;
+
+/// Statistics are reported with (approximately) this period.
+pub(crate) const STATS_INTERVAL: Duration = Duration::from_secs(1);
+/// Weight of a new sample in exponentially weighted moving averages.
+const SAMPLE_WEIGHT: f32 = 0.125;
+/// Weight of a new sample in RTT variation (jitter) estimation.
+const VARIATION_WEIGHT: f32 = 0.25;
+
+#[derive(Clone)]
+pub(crate) struct Stats {
+    book: Arc<Mutex<ConnectionBook<Record>>>,
+    metrics: NetMetrics,
+}
+
+impl Stats {
+    /// # Arguments
+    ///
+    /// * `metrics` - re-sent packages are counted into these metrics.
+    pub(crate) fn new(metrics: NetMetrics) -> Self {
+        Self {
+            book: Arc::new(Mutex::new(ConnectionBook::new())),
+            metrics,
+        }
+    }
+
+    /// Registers a round trip time sample, i.e. time between sending of a
+    /// package and reception of its confirmation.
+    ///
+    /// Only packages confirmed without being re-sent should be sampled, since
+    /// it is not possible to tell which copy of the package was confirmed
+    /// otherwise.
+    pub(crate) async fn rtt(&mut self, time: Instant, addr: SocketAddr, rtt: Duration) {
+        self.book
+            .lock()
+            .await
+            .update(time, addr, Record::new)
+            .rtt(rtt);
+    }
+
+    /// Registers a package (to be) sent to `addr`.
+    ///
+    /// # Arguments
+    ///
+    /// * `delivery` - the way the package is sent.
+    ///
+    /// * `bytes` - size of the package payload.
+    pub(crate) async fn sent(
+        &mut self,
+        time: Instant,
+        addr: SocketAddr,
+        delivery: Delivery,
+        bytes: usize,
+    ) {
+        if matches!(delivery, Delivery::Resend) {
+            self.metrics.record_retransmission();
+        }
+
+        self.book
+            .lock()
+            .await
+            .update(time, addr, Record::new)
+            .sent(delivery, bytes);
+    }
+
+    /// Registers a package received from `addr`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - size of the package payload.
+    pub(crate) async fn received(&mut self, time: Instant, addr: SocketAddr, bytes: usize) {
+        self.book
+            .lock()
+            .await
+            .update(time, addr, Record::new)
+            .received(bytes);
+    }
+
+    /// Sends statistics of all connections whose reporting period has
+    /// elapsed.
+    ///
+    /// Reports which do not fit into the (bounded) `reports` channel are
+    /// dropped so that a slow (or no) consumer never blocks the networking
+    /// stack.
+    ///
+    /// # Returns
+    ///
+    /// It returns the soonest time of a next report.
+    pub(crate) async fn report(
+        &mut self,
+        time: Instant,
+        reports: &Sender<ConnectionStats>,
+    ) -> Instant {
+        let mut next = time + STATS_INTERVAL;
+        let mut book = self.book.lock().await;
+
+        while let Some((addr, record)) = book.next() {
+            let due = record.period_start + STATS_INTERVAL;
+            if due <= time {
+                let _ = reports.try_send(record.flush(time, addr));
+            } else {
+                next = next.min(due);
+            }
+        }
+
+        next
+    }
+
+    pub(crate) async fn clean(&mut self, time: Instant) {
+        self.book.lock().await.clean(time);
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Delivery {
+    Unreliable,
+    Reliable,
+    /// Re-delivery of a reliable package whose delivery has not been
+    /// confirmed in time.
+    Resend,
+}
+
+/// Accumulated statistics of a single connection.
+struct Record {
+    period_start: Instant,
+    srtt: Option<f32>,
+    rttvar: f32,
+    loss: Option<f32>,
+    sent: u32,
+    resent: u32,
+    bytes_out: usize,
+    bytes_in: usize,
+}
+
+impl Record {
+    fn new() -> Self {
+        Self {
+            period_start: Instant::now(),
+            srtt: None,
+            rttvar: 0.,
+            loss: None,
+            sent: 0,
+            resent: 0,
+            bytes_out: 0,
+            bytes_in: 0,
+        }
+    }
+
+    fn rtt(&mut self, rtt: Duration) {
+        let sample = rtt.as_secs_f32();
+        match self.srtt {
+            Some(srtt) => {
+                self.rttvar += VARIATION_WEIGHT * ((srtt - sample).abs() - self.rttvar);
+                self.srtt = Some(srtt + SAMPLE_WEIGHT * (sample - srtt));
+            }
+            None => {
+                self.rttvar = sample / 2.;
+                self.srtt = Some(sample);
+            }
+        }
+    }
+
+    fn sent(&mut self, delivery: Delivery, bytes: usize) {
+        match delivery {
+            Delivery::Unreliable => (),
+            Delivery::Reliable => self.sent += 1,
+            Delivery::Resend => self.resent += 1,
+        }
+        self.bytes_out += bytes;
+    }
+
+    fn received(&mut self, bytes: usize) {
+        self.bytes_in += bytes;
+    }
+
+    /// Finishes current reporting period and returns statistics of the
+    /// connection.
+    fn flush(&mut self, time: Instant, addr: SocketAddr) -> ConnectionStats {
+        let total = self.sent + self.resent;
+        if total > 0 {
+            let sample = self.resent as f32 / total as f32;
+            self.loss = Some(match self.loss {
+                Some(loss) => loss + SAMPLE_WEIGHT * (sample - loss),
+                None => sample,
+            });
+        }
+
+        let period = (time - self.period_start).as_secs_f32().max(f32::EPSILON);
+        let stats = ConnectionStats {
+            target: addr,
+            rtt: self.srtt.map(Duration::from_secs_f32),
+            jitter: self.srtt.map(|_| Duration::from_secs_f32(self.rttvar)),
+            loss: self.loss,
+            bandwidth_out: self.bytes_out as f32 / period,
+            bandwidth_in: self.bytes_in as f32 / period,
+        };
+
+        self.period_start = time;
+        self.sent = 0;
+        self.resent = 0;
+        self.bytes_out = 0;
+        self.bytes_in = 0;
+
+        stats
+    }
+}
+
+impl Connection for Record {
+    fn pending(&self) -> bool {
+        false
+    }
+}
+
+/// Network statistics of a connection with a single peer.
+#[derive(Clone, Debug)]
+pub struct ConnectionStats {
+    target: SocketAddr,
+    rtt: Option<Duration>,
+    jitter: Option<Duration>,
+    loss: Option<f32>,
+    bandwidth_out: f32,
+    bandwidth_in: f32,
+}
+
+impl ConnectionStats {
+    /// Address of the peer.
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+
+    /// Smoothed round trip time or None if it has not been measured yet.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Round trip time variation or None if it has not been measured yet.
+    pub fn jitter(&self) -> Option<Duration> {
+        self.jitter
+    }
+
+    /// Smoothed ratio (between 0 and 1) of reliable packages which had to be
+    /// re-sent, or None if no reliable package has been sent yet.
+    pub fn loss(&self) -> Option<f32> {
+        self.loss
+    }
+
+    /// Average number of payload bytes per second sent to the peer during
+    /// the last reporting period.
+    pub fn bandwidth_out(&self) -> f32 {
+        self.bandwidth_out
+    }
+
+    /// Average number of payload bytes per second received from the peer
+    /// during the last reporting period.
+    pub fn bandwidth_in(&self) -> f32 {
+        self.bandwidth_in
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let addr: SocketAddr = "127.0.0.1:1111".parse().unwrap();
+        let mut record = Record::new();
+        let start = record.period_start;
+
+        let stats = record.flush(start + Duration::from_secs(1), addr);
+        assert!(stats.rtt().is_none());
+        assert!(stats.jitter().is_none());
+        assert!(stats.loss().is_none());
+        assert_eq!(stats.bandwidth_out(), 0.);
+
+        record.rtt(Duration::from_millis(100));
+        record.rtt(Duration::from_millis(100));
+        record.sent(Delivery::Reliable, 300);
+        record.sent(Delivery::Reliable, 300);
+        record.sent(Delivery::Reliable, 300);
+        record.sent(Delivery::Resend, 300);
+        record.received(500);
+
+        let stats = record.flush(start + Duration::from_secs(3), addr);
+        assert_eq!(stats.target(), addr);
+        assert_eq!(stats.rtt().unwrap().as_millis(), 100);
+        assert!(stats.jitter().unwrap() < Duration::from_millis(50));
+        assert_eq!(stats.loss().unwrap(), 0.25);
+        assert_eq!(stats.bandwidth_out(), 600.);
+        assert_eq!(stats.bandwidth_in(), 250.);
+
+        record.sent(Delivery::Unreliable, 5);
+        record.sent(Delivery::Reliable, 5);
+        let stats = record.flush(start + Duration::from_secs(4), addr);
+        assert!(stats.loss().unwrap() < 0.25);
+        assert_eq!(stats.bandwidth_out(), 10.);
+        assert_eq!(stats.bandwidth_in(), 0.);
+    }
+}
@@ -0,0 +1,174 @@
+//! Simulation of an unreliable network. This is intended for testing only.
+
+use std::{sync::Mutex, time::Duration};
+
+/// Extra delay of reordered datagrams on top of the maximum regular delay.
+const REORDER_DELAY: Duration = Duration::from_millis(10);
+
+/// Configuration of artificial network faults injected into outgoing
+/// datagrams of a [`crate::Socket`]. See [`crate::Socket::with_faults`].
+///
+/// All random decisions are driven by a seeded pseudo random number generator,
+/// thus a given sequence of sent datagrams is always affected the same way.
+#[derive(Clone, Copy, Debug)]
+pub struct Faults {
+    seed: u64,
+    latency: Duration,
+    jitter: Duration,
+    loss: f32,
+    duplication: f32,
+    reordering: f32,
+}
+
+impl Faults {
+    /// Creates a new configuration with no faults.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - seed of the pseudo random number generator.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            loss: 0.,
+            duplication: 0.,
+            reordering: 0.,
+        }
+    }
+
+    /// Each datagram is delayed by `latency` plus a random duration between
+    /// zero and `jitter`.
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    /// Each datagram is dropped with probability `loss`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `loss` is not between 0 and 1.
+    pub fn with_loss(mut self, loss: f32) -> Self {
+        assert!((0. ..=1.).contains(&loss));
+        self.loss = loss;
+        self
+    }
+
+    /// Each (not dropped) datagram is sent twice with probability
+    /// `duplication`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `duplication` is not between 0 and 1.
+    pub fn with_duplication(mut self, duplication: f32) -> Self {
+        assert!((0. ..=1.).contains(&duplication));
+        self.duplication = duplication;
+        self
+    }
+
+    /// Each (not dropped) datagram is held back long enough to be overtaken
+    /// by subsequent datagrams with probability `reordering`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `reordering` is not between 0 and 1.
+    pub fn with_reordering(mut self, reordering: f32) -> Self {
+        assert!((0. ..=1.).contains(&reordering));
+        self.reordering = reordering;
+        self
+    }
+}
+
+pub(crate) struct FaultInjector {
+    faults: Faults,
+    rng: Mutex<fastrand::Rng>,
+}
+
+impl FaultInjector {
+    pub(crate) fn new(faults: Faults) -> Self {
+        Self {
+            faults,
+            rng: Mutex::new(fastrand::Rng::with_seed(faults.seed)),
+        }
+    }
+
+    /// Decides the fate of a single outgoing datagram. It returns delay of
+    /// each copy of the datagram to be sent. The returned Vec is empty if the
+    /// datagram is lost.
+    pub(crate) fn plan(&self) -> Vec<Duration> {
+        let rng = self.rng.lock().unwrap();
+
+        if rng.f32() < self.faults.loss {
+            return Vec::new();
+        }
+
+        let copies = if rng.f32() < self.faults.duplication {
+            2
+        } else {
+            1
+        };
+
+        let mut delays = Vec::with_capacity(copies);
+        for _ in 0..copies {
+            let mut delay = self.faults.latency + self.jitter(&rng);
+            if rng.f32() < self.faults.reordering {
+                delay += self.faults.latency + self.faults.jitter + REORDER_DELAY;
+            }
+            delays.push(delay);
+        }
+        delays
+    }
+
+    fn jitter(&self, rng: &fastrand::Rng) -> Duration {
+        let max = self.faults.jitter.as_micros() as u64;
+        Duration::from_micros(rng.u64(0..=max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let injector = FaultInjector::new(Faults::new(7));
+        for _ in 0..10 {
+            assert_eq!(injector.plan(), vec![Duration::ZERO]);
+        }
+
+        let injector = FaultInjector::new(Faults::new(7).with_loss(1.));
+        assert!(injector.plan().is_empty());
+
+        let injector = FaultInjector::new(
+            Faults::new(7)
+                .with_duplication(1.)
+                .with_latency(Duration::from_millis(20), Duration::from_millis(5)),
+        );
+        let delays = injector.plan();
+        assert_eq!(delays.len(), 2);
+        for delay in delays {
+            assert!(delay >= Duration::from_millis(20));
+            assert!(delay <= Duration::from_millis(25));
+        }
+
+        let injector = FaultInjector::new(
+            Faults::new(7)
+                .with_reordering(1.)
+                .with_latency(Duration::from_millis(20), Duration::ZERO),
+        );
+        assert_eq!(injector.plan(), vec![Duration::from_millis(50)]);
+
+        let faults = Faults::new(42)
+            .with_loss(0.3)
+            .with_duplication(0.3)
+            .with_reordering(0.3)
+            .with_latency(Duration::from_millis(20), Duration::from_millis(10));
+        let first = FaultInjector::new(faults);
+        let second = FaultInjector::new(faults);
+        for _ in 0..100 {
+            assert_eq!(first.plan(), second.plan());
+        }
+    }
+}
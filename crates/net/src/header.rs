@@ -7,23 +7,62 @@ pub(crate) const HEADER_SIZE: usize = 4;
 
 /// This bit is set in protocol control datagrams.
 const CONTROL_BIT: u8 = 0b1000_0000;
+/// Header mask of delivery confirmation control datagrams.
+const CONFIRMATION_MASK: u8 = CONTROL_BIT;
+/// Header mask of keep-alive control datagrams.
+const KEEP_ALIVE_MASK: u8 = CONTROL_BIT | 0b0000_0001;
+/// Header mask of connection closing control datagrams.
+const GOODBYE_MASK: u8 = CONTROL_BIT | 0b0000_0010;
+/// Header mask of datagrams composed of multiple coalesced datagrams.
+const BATCH_MASK: u8 = CONTROL_BIT | 0b0000_0011;
+/// Header mask of encryption handshake control datagrams.
+const HELLO_MASK: u8 = CONTROL_BIT | 0b0000_0100;
+/// Header mask of encrypted datagrams.
+const ENCRYPTED_MASK: u8 = CONTROL_BIT | 0b0000_0101;
 /// This bit is set on datagrams which must be delivered reliably.
 const RELIABLE_BIT: u8 = 0b0100_0000;
 /// This bit is set on datagrams which are sent to the server instead of other
 /// players.
 const SERVER_PEER_BIT: u8 = 0b0010_0000;
+/// This bit is set on datagrams whose payload is compressed.
+const COMPRESSED_BIT: u8 = 0b0001_0000;
+/// This bit is set on datagrams whose payload starts with a
+/// [`crate::Token`].
+const TOKEN_BIT: u8 = 0b0000_1000;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub(crate) enum DatagramHeader {
     Confirmation,
+    /// Control datagram without any payload which is sent solely to inform
+    /// the peer that the connection is still alive.
+    KeepAlive,
+    /// Control datagram without any payload which is sent to inform the peer
+    /// that the connection is being closed.
+    Goodbye,
+    /// Datagram whose payload is a sequence of other (complete) datagrams
+    /// sent to the same target. See [`crate::batch`].
+    Batch,
+    /// Encryption handshake datagram whose payload is a public key of the
+    /// sender. See [`crate::connection::Encryption`].
+    Hello,
+    /// Datagram whose payload is another (complete) encrypted datagram.
+    Encrypted,
     Package(PackageHeader),
 }
 
 impl DatagramHeader {
-    pub(crate) fn new_package(reliable: bool, peers: Peers, id: PackageId) -> Self {
+    pub(crate) fn new_package(
+        reliable: bool,
+        peers: Peers,
+        compressed: bool,
+        has_token: bool,
+        id: PackageId,
+    ) -> Self {
         Self::Package(PackageHeader {
             reliable,
             peers,
+            compressed,
+            has_token,
             id,
         })
     }
@@ -36,7 +75,12 @@ impl DatagramHeader {
     pub(crate) fn write(&self, buf: &mut [u8]) {
         assert!(buf.len() >= HEADER_SIZE);
         let (mask, id) = match self {
-            Self::Confirmation => (CONTROL_BIT, [0, 0, 0]),
+            Self::Confirmation => (CONFIRMATION_MASK, [0, 0, 0]),
+            Self::KeepAlive => (KEEP_ALIVE_MASK, [0, 0, 0]),
+            Self::Goodbye => (GOODBYE_MASK, [0, 0, 0]),
+            Self::Batch => (BATCH_MASK, [0, 0, 0]),
+            Self::Hello => (HELLO_MASK, [0, 0, 0]),
+            Self::Encrypted => (ENCRYPTED_MASK, [0, 0, 0]),
             Self::Package(package_header) => {
                 let mut mask = 0;
                 if package_header.reliable {
@@ -45,6 +89,12 @@ impl DatagramHeader {
                 if matches!(package_header.peers, Peers::Server) {
                     mask |= SERVER_PEER_BIT;
                 }
+                if package_header.compressed {
+                    mask |= COMPRESSED_BIT;
+                }
+                if package_header.has_token {
+                    mask |= TOKEN_BIT;
+                }
                 (mask, package_header.id.to_bytes())
             }
         };
@@ -65,10 +115,14 @@ impl DatagramHeader {
         let mask = data[0];
 
         if mask & CONTROL_BIT > 0 {
-            if mask == CONTROL_BIT {
-                Ok(Self::Confirmation)
-            } else {
-                Err(HeaderError::Invalid)
+            match mask {
+                CONFIRMATION_MASK => Ok(Self::Confirmation),
+                KEEP_ALIVE_MASK => Ok(Self::KeepAlive),
+                GOODBYE_MASK => Ok(Self::Goodbye),
+                BATCH_MASK => Ok(Self::Batch),
+                HELLO_MASK => Ok(Self::Hello),
+                ENCRYPTED_MASK => Ok(Self::Encrypted),
+                _ => Err(HeaderError::Invalid),
             }
         } else {
             let reliable = mask & RELIABLE_BIT > 0;
@@ -80,6 +134,8 @@ impl DatagramHeader {
             Ok(Self::Package(PackageHeader {
                 reliable,
                 peers,
+                compressed: mask & COMPRESSED_BIT > 0,
+                has_token: mask & TOKEN_BIT > 0,
                 id: PackageId::from_bytes(&data[1..HEADER_SIZE]),
             }))
         }
@@ -90,11 +146,16 @@ impl fmt::Display for DatagramHeader {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Confirmation => write!(f, "Confirmation"),
+            Self::KeepAlive => write!(f, "KeepAlive"),
+            Self::Goodbye => write!(f, "Goodbye"),
+            Self::Batch => write!(f, "Batch"),
+            Self::Hello => write!(f, "Hello"),
+            Self::Encrypted => write!(f, "Encrypted"),
             Self::Package(header) => {
                 write!(
                     f,
-                    "Package {{ reliable: {}, peers: {}, id: {} }}",
-                    header.reliable, header.peers, header.id
+                    "Package {{ reliable: {}, peers: {}, compressed: {}, token: {}, id: {} }}",
+                    header.reliable, header.peers, header.compressed, header.has_token, header.id
                 )
             }
         }
@@ -106,6 +167,11 @@ pub(crate) struct PackageHeader {
     /// True if the package is delivered reliably.
     reliable: bool,
     peers: Peers,
+    /// True if the package payload is compressed.
+    compressed: bool,
+    /// True if the datagram payload starts with a token (which is not part of
+    /// the possibly compressed package payload).
+    has_token: bool,
     id: PackageId,
 }
 
@@ -118,6 +184,14 @@ impl PackageHeader {
         self.peers
     }
 
+    pub(crate) fn compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub(crate) fn has_token(&self) -> bool {
+        self.has_token
+    }
+
     pub(crate) fn id(&self) -> PackageId {
         self.id
     }
@@ -190,6 +264,12 @@ impl PackageId {
         }
     }
 
+    /// Returns number of increments needed to get from `other` to `self`
+    /// (with wrapping around maximum value).
+    pub(crate) fn distance(self, other: PackageId) -> u32 {
+        self.0.wrapping_sub(other.0) & Self::MAX
+    }
+
     /// # Panics
     ///
     /// If not exactly 3 bytes are passed.
@@ -290,16 +370,37 @@ mod tests {
     fn test_write_header() {
         let mut buf = [0u8; 256];
 
-        DatagramHeader::new_package(false, Peers::Server, PackageId::zero()).write(&mut buf);
+        DatagramHeader::new_package(false, Peers::Server, false, false, PackageId::zero())
+            .write(&mut buf);
         assert_eq![&buf[0..4], &[0b0010_0000, 0, 0, 0]];
         assert_eq![&buf[4..], &[0; 252]];
-        DatagramHeader::new_package(true, Peers::Server, 256.try_into().unwrap()).write(&mut buf);
+        DatagramHeader::new_package(true, Peers::Server, false, false, 256.try_into().unwrap())
+            .write(&mut buf);
         assert_eq![&buf[0..4], &[0b0110_0000, 0, 1, 0]];
         assert_eq![&buf[4..], &[0; 252]];
 
-        DatagramHeader::new_package(true, Peers::Players, 1033.try_into().unwrap()).write(&mut buf);
+        DatagramHeader::new_package(true, Peers::Players, false, false, 1033.try_into().unwrap())
+            .write(&mut buf);
         assert_eq![&buf[0..4], &[0b0100_0000, 0, 4, 9]];
         assert_eq![&buf[4..], &[0; 252]];
+
+        DatagramHeader::new_package(false, Peers::Players, true, false, 2.try_into().unwrap())
+            .write(&mut buf);
+        assert_eq![&buf[0..4], &[0b0001_0000, 0, 0, 2]];
+        assert_eq![&buf[4..], &[0; 252]];
+
+        DatagramHeader::new_package(false, Peers::Players, false, true, 3.try_into().unwrap())
+            .write(&mut buf);
+        assert_eq![&buf[0..4], &[0b0000_1000, 0, 0, 3]];
+        assert_eq![&buf[4..], &[0; 252]];
+
+        DatagramHeader::KeepAlive.write(&mut buf);
+        assert_eq![&buf[0..4], &[0b1000_0001, 0, 0, 0]];
+        assert_eq![&buf[4..], &[0; 252]];
+
+        DatagramHeader::Goodbye.write(&mut buf);
+        assert_eq![&buf[0..4], &[0b1000_0010, 0, 0, 0]];
+        assert_eq![&buf[4..], &[0; 252]];
     }
 
     #[test]
@@ -309,20 +410,62 @@ mod tests {
         buf[0..4].copy_from_slice(&[64, 0, 0, 0]);
         assert_eq!(
             DatagramHeader::read(&buf).unwrap(),
-            DatagramHeader::new_package(true, Peers::Players, 0.try_into().unwrap())
+            DatagramHeader::new_package(true, Peers::Players, false, false, 0.try_into().unwrap())
         );
 
         buf[0..4].copy_from_slice(&[64, 1, 0, 3]);
         assert_eq!(
             DatagramHeader::read(&buf).unwrap(),
-            DatagramHeader::new_package(true, Peers::Players, 65539.try_into().unwrap())
+            DatagramHeader::new_package(
+                true,
+                Peers::Players,
+                false,
+                false,
+                65539.try_into().unwrap()
+            )
         );
 
         buf[0..4].copy_from_slice(&[32, 0, 0, 2]);
         assert_eq!(
             DatagramHeader::read(&buf).unwrap(),
-            DatagramHeader::new_package(false, Peers::Server, 2.try_into().unwrap())
+            DatagramHeader::new_package(false, Peers::Server, false, false, 2.try_into().unwrap())
+        );
+
+        buf[0..4].copy_from_slice(&[112, 0, 0, 1]);
+        assert_eq!(
+            DatagramHeader::read(&buf).unwrap(),
+            DatagramHeader::new_package(true, Peers::Server, true, false, 1.try_into().unwrap())
         );
+
+        buf[0..4].copy_from_slice(&[8, 0, 0, 1]);
+        assert_eq!(
+            DatagramHeader::read(&buf).unwrap(),
+            DatagramHeader::new_package(false, Peers::Players, false, true, 1.try_into().unwrap())
+        );
+
+        buf[0..4].copy_from_slice(&[129, 0, 0, 0]);
+        assert_eq!(
+            DatagramHeader::read(&buf).unwrap(),
+            DatagramHeader::KeepAlive
+        );
+
+        buf[0..4].copy_from_slice(&[130, 0, 0, 0]);
+        assert_eq!(DatagramHeader::read(&buf).unwrap(), DatagramHeader::Goodbye);
+
+        buf[0..4].copy_from_slice(&[131, 0, 0, 0]);
+        assert_eq!(DatagramHeader::read(&buf).unwrap(), DatagramHeader::Batch);
+
+        buf[0..4].copy_from_slice(&[132, 0, 0, 0]);
+        assert_eq!(DatagramHeader::read(&buf).unwrap(), DatagramHeader::Hello);
+
+        buf[0..4].copy_from_slice(&[133, 0, 0, 0]);
+        assert_eq!(
+            DatagramHeader::read(&buf).unwrap(),
+            DatagramHeader::Encrypted
+        );
+
+        buf[0..4].copy_from_slice(&[134, 0, 0, 0]);
+        assert!(DatagramHeader::read(&buf).is_err());
     }
 
     #[test]
@@ -359,6 +502,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_distance() {
+        let id = PackageId::from_bytes(&[0, 1, 2]);
+        assert_eq!(id.distance(id), 0);
+        assert_eq!(id.distance(PackageId::from_bytes(&[0, 0, 255])), 3);
+        assert_eq!(PackageId::zero().distance(0xffffff.try_into().unwrap()), 1);
+        assert_eq!(
+            PackageId::zero().distance(PackageId::zero().incremented()),
+            0xffffff
+        );
+    }
+
     #[test]
     fn test_iter() {
         let mut counter = PackageIdRange::counter();
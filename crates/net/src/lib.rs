@@ -1,15 +1,39 @@
+pub use codec::{BincodeCodec, Codec, DecodeError, EncodeError};
+pub use conf::{GiveUp, NetConf, ResendPolicy};
+pub use connection::Encryption;
+#[cfg(feature = "testing")]
+pub use faults::Faults;
 pub use header::Peers;
-pub use messages::{FromGame, FromServer, GameOpenError, JoinError, ToGame, ToServer};
+pub use messages::{
+    AiSlot, Capabilities, ChatChannel, DropPolicy, FromGame, FromServer, GameListing,
+    GameOpenError, GameSlots, JoinError, LobbyPlayer, LobbyState, ToGame, ToPlayers, ToServer,
+    MAX_AUTH_LEN, MAX_CHAT_LEN, MAX_COMMANDS_LEN, MAX_GAME_NAME_LEN, MAX_MAP_FRAGMENT_LEN,
+    MAX_MAP_NAME_LEN, MAX_MOTD_LEN, MAX_REPLICATION_LEN, MAX_SNAPSHOT_FRAGMENT_LEN,
+    PROTOCOL_VERSION,
+};
+pub use metrics::NetMetrics;
+pub use password::PasswordHash;
 pub use protocol::{Targets, MAX_PACKAGE_SIZE};
 pub use socket::{RecvError, SendError, Socket, MAX_DATAGRAM_SIZE};
 pub use tasks::{
-    startup, ConnErrorReceiver, ConnectionError, InPackage, MessageDecoder, OutPackage,
-    PackageBuilder, PackageReceiver, PackageSender,
+    startup, ConnErrorKind, ConnErrorReceiver, ConnStatsReceiver, ConnectionError, ConnectionStats,
+    InPackage, MessageDecoder, OutPackage, PackageBuilder, PackageReceiver, PackageSender,
+    Priority,
 };
+pub use token::{Token, Tokens};
 
+mod batch;
+mod codec;
+mod compression;
+mod conf;
 mod connection;
+#[cfg(feature = "testing")]
+mod faults;
 mod header;
 mod messages;
+mod metrics;
+mod password;
 mod protocol;
 mod socket;
 mod tasks;
+mod token;
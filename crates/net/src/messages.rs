@@ -1,19 +1,62 @@
-use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::{PasswordHash, Token};
+
+/// Version of the protocol spoken between clients and game servers. It is
+/// increased with each incompatible change of the messages. See
+/// [`ToGame::Join`].
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// Maximum length of a game name in bytes. See [`ToServer::OpenGame`].
+pub const MAX_GAME_NAME_LEN: usize = 32;
+/// Maximum length of a map name in bytes. See [`ToServer::OpenGame`].
+pub const MAX_MAP_NAME_LEN: usize = 32;
 
 /// Message to be sent from a player/client to a main server (outside of a
 /// game).
-#[derive(Encode, Decode)]
+#[derive(Serialize, Deserialize)]
 pub enum ToServer {
     /// Prompts the server to respond [`FromServer::Pong`] with the same ping ID.
     Ping(u32),
     /// This message opens a new game on the server. The server responds with
     /// [`FromServer::GameOpened`].
-    OpenGame { max_players: u8 },
+    ///
+    /// The name of the game and the name of its map are used only in game
+    /// listings, see [`ToServer::ListGames`].
+    ///
+    /// `auth` is an authentication token (at most [`MAX_AUTH_LEN`] bytes
+    /// long) issued by DE Lobby. It is required by servers with
+    /// authentication enabled.
+    ///
+    /// If `password` is set, players must present the same password hash
+    /// when joining the game, see [`ToGame::Join`].
+    ///
+    /// `slots` configures teams and AI players of the game. The server
+    /// responds with [`GameOpenError::InvalidSlots`] if the configuration is
+    /// not valid for `max_players`, see [`GameSlots::is_valid`].
+    ///
+    /// `capabilities` are optional protocol features supported by the
+    /// client. They are negotiated in the same way as in [`ToGame::Join`]
+    /// because the client automatically joins the game.
+    OpenGame {
+        max_players: u8,
+        slots: GameSlots,
+        name: String,
+        map: String,
+        auth: Option<String>,
+        password: Option<PasswordHash>,
+        capabilities: Capabilities,
+    },
+    /// Prompts the server to list all currently open games. The server
+    /// responds with zero or more [`FromServer::Game`] followed by
+    /// [`FromServer::GamesEnd`]. The listing is preceded by
+    /// [`FromServer::Motd`] if the server has a message of the day.
+    ListGames,
 }
 
 /// Message to be sent from a main server to a player/client (outside of a
 /// game).
-#[derive(Encode, Decode)]
+#[derive(Serialize, Deserialize)]
 pub enum FromServer {
     /// Response to [`ToServer::Ping`].
     Pong(u32),
@@ -23,26 +66,250 @@ pub enum FromServer {
         port: u16,
     },
     GameOpenError(GameOpenError),
+    /// A single open game. See [`ToServer::ListGames`].
+    Game(GameListing),
+    /// Terminates a listing of open games. The number is the total number of
+    /// listed games, i.e. the number of [`FromServer::Game`] messages sent
+    /// as part of the listing.
+    ///
+    /// Note that the messages might be delivered out of order.
+    GamesEnd(u16),
+    /// Message of the day of the server, at most [`MAX_MOTD_LEN`] bytes long.
+    /// See [`ToServer::ListGames`].
+    Motd(String),
 }
 
-#[derive(Encode, Decode)]
+#[derive(Serialize, Deserialize)]
 pub enum GameOpenError {
     /// The player opening the game has already joined a different game.
     DifferentGame,
+    /// The name of the game or of the map is too long.
+    InvalidListing,
+    /// The requested maximum number of players exceeds the limit of the
+    /// server.
+    TooManyPlayers,
+    /// The server cannot host any more games at the moment.
+    Unavailable,
+    /// The server requires authentication and the authentication token is
+    /// missing or invalid.
+    Unauthenticated,
+    /// The slot configuration is not valid for the requested maximum number
+    /// of players.
+    InvalidSlots,
+}
+
+/// Configuration of player slots of a game.
+///
+/// Slots, i.e. player IDs, from 1 to `max_players` (inclusive) are split
+/// into human slots followed by AI slots. All AI slots are always occupied.
+///
+/// In a team game, each slot initially belongs to a team (see
+/// [`Self::team`]) and each team has `max_players / teams` slots.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GameSlots {
+    teams: u8,
+    ai_players: u8,
+}
+
+impl GameSlots {
+    /// # Arguments
+    ///
+    /// * `teams` - number of teams. Zero means free for all, i.e. each player
+    ///   is initially alone in a team.
+    ///
+    /// * `ai_players` - number of slots occupied by AI players.
+    pub fn new(teams: u8, ai_players: u8) -> Self {
+        Self { teams, ai_players }
+    }
+
+    /// Number of teams or 0 in a free for all game.
+    pub fn teams(&self) -> u8 {
+        self.teams
+    }
+
+    pub fn ai_players(&self) -> u8 {
+        self.ai_players
+    }
+
+    /// Returns true if the configuration is valid for a game with
+    /// `max_players`. There must be at least one human slot and, in a team
+    /// game, at least two teams with the same number of slots each.
+    pub fn is_valid(&self, max_players: u8) -> bool {
+        self.ai_players < max_players
+            && (self.teams == 0 || (self.teams > 1 && max_players % self.teams == 0))
+    }
+
+    /// Number of slots available to human players.
+    pub fn human_players(&self, max_players: u8) -> u8 {
+        max_players.saturating_sub(self.ai_players)
+    }
+
+    /// Maximum number of players in a single team or None in a free for all
+    /// game.
+    pub fn team_size(&self, max_players: u8) -> Option<u8> {
+        if self.teams == 0 {
+            None
+        } else {
+            Some(max_players / self.teams)
+        }
+    }
+
+    /// Initial team of the player (human or AI) with ID `id`. It returns
+    /// None for the invalid ID 0.
+    pub fn team(&self, id: u8) -> Option<u8> {
+        if id == 0 {
+            None
+        } else if self.teams == 0 {
+            Some(id)
+        } else {
+            Some((id - 1) % self.teams + 1)
+        }
+    }
+}
+
+/// Information about an open game.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GameListing {
+    port: u16,
+    name: String,
+    map: String,
+    num_players: u8,
+    max_players: u8,
+    protected: bool,
+}
+
+impl GameListing {
+    pub fn new(
+        port: u16,
+        name: String,
+        map: String,
+        num_players: u8,
+        max_players: u8,
+        protected: bool,
+    ) -> Self {
+        Self {
+            port,
+            name,
+            map,
+            num_players,
+            max_players,
+            protected,
+        }
+    }
+
+    /// Port of the game server.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Name of the map of the game.
+    pub fn map(&self) -> &str {
+        self.map.as_str()
+    }
+
+    /// Number of players currently occupying a slot in the game.
+    pub fn num_players(&self) -> u8 {
+        self.num_players
+    }
+
+    pub fn max_players(&self) -> u8 {
+        self.max_players
+    }
+
+    /// True if a password is required to join the game.
+    pub fn protected(&self) -> bool {
+        self.protected
+    }
 }
 
 /// Message to be sent from a player/client to a game server (inside of a
 /// game).
-#[derive(Encode, Decode)]
+#[derive(Serialize, Deserialize)]
 pub enum ToGame {
     /// Prompts the server to respond [`FromGame::Pong`] with the same ping ID.
     Ping(u32),
     /// Connect the player to the game.
-    Join,
+    ///
+    /// `version` must be equal to [`PROTOCOL_VERSION`] of the server,
+    /// otherwise the server responds with [`FromGame::VersionMismatch`].
+    /// `capabilities` are optional protocol features supported by the
+    /// client. The server responds with features supported by both sides in
+    /// [`FromGame::Joined`] and only these are used afterwards.
+    ///
+    /// The optional authentication token is the same as in
+    /// [`ToServer::OpenGame`]. The password hash must match the one the game
+    /// was opened with, otherwise the server responds with
+    /// [`FromGame::WrongPassword`].
+    Join {
+        version: u16,
+        capabilities: Capabilities,
+        auth: Option<String>,
+        password: Option<PasswordHash>,
+    },
     /// Disconnect the player from the game.
     ///
     /// The game is automatically closed once all players disconnect.
     Leave,
+    /// Enter the lobby of the game. Only players connected to the game may
+    /// enter its lobby and only before the game starts.
+    ///
+    /// All players in the lobby are informed about its changes with
+    /// [`FromGame::LobbyState`].
+    JoinLobby,
+    /// Exit the lobby of the game. The player stays connected to the game.
+    LeaveLobby,
+    /// Mark the player as (not) ready to start the game. The game starts once
+    /// all players in the lobby are ready.
+    SetReady(bool),
+    /// Start the game regardless of readiness of the players in the lobby.
+    /// Only the host (the creator of the game) may start the game.
+    StartGame,
+    /// Join a team. Players are initially in a team given by their slot, see
+    /// [`GameSlots::team`]. In a team game, only teams from 1 to the number
+    /// of teams (inclusive) with a free slot may be joined. Teams may be
+    /// changed only before the game starts.
+    SetTeam(u8),
+    /// Send a chat message to other players. The server relays the message
+    /// as [`FromGame::Chat`] to all players within the channel.
+    ///
+    /// Messages longer than [`MAX_CHAT_LEN`] bytes are dropped.
+    Chat { channel: ChatChannel, text: String },
+    /// Connect the client to the game as a spectator. Unlike
+    /// [`ToGame::Join`], this is possible even after the game has started.
+    ///
+    /// Spectators receive all player packages sent within the game but their
+    /// own player packages are dropped. Spectators disconnect from the game
+    /// with [`ToGame::Leave`].
+    ///
+    /// Password protected games require the password as in
+    /// [`ToGame::Join`].
+    Spectate(Option<PasswordHash>),
+    /// Re-connect the player to the game after the connection was
+    /// unexpectedly lost. The token is the last one received in
+    /// [`FromGame::Joined`].
+    ///
+    /// The player keeps their slot (and ID) only for a limited grace period
+    /// after the disconnection.
+    Rejoin(Token),
+    /// Ask the other players to send the full game state to this player. The
+    /// server relays this as [`FromGame::ResyncRequested`]. This is intended
+    /// to be sent after a successful [`ToGame::Rejoin`].
+    RequestResync,
+    /// Remove the player with the given ID from the game and prevent them
+    /// from joining it again. Only the host (the creator of the game) may
+    /// kick other players.
+    Kick(u8),
+    /// Prompts the server to respond [`FromGame::Clock`] with the same ID.
+    /// This is used to synchronize game time among the players.
+    SyncClock(u32),
+    /// Give up the already started game. The player is marked as defeated
+    /// and all clients are informed with [`FromGame::PlayerSurrendered`].
+    /// The player stays connected to the game.
+    Surrender,
 }
 
 /// Message to be sent from a game server to a player/client (inside of a
@@ -51,7 +318,7 @@ pub enum ToGame {
 /// # Notes
 ///
 /// * Players are numbered from 1 to `max_players` (inclusive).
-#[derive(Encode, Decode)]
+#[derive(Serialize, Deserialize)]
 pub enum FromGame {
     /// Response to [`ToGame::Ping`].
     Pong(u32),
@@ -59,8 +326,15 @@ pub enum FromGame {
     /// messages (to any peer) due to the player not being part of the game.
     NotJoined,
     /// Informs the player that they were just connected to the game under the
-    /// ID.
-    Joined(u8),
+    /// ID. The token must be included in all further packages sent by the
+    /// player to the game server. `capabilities` are optional protocol
+    /// features supported by both the server and the player, see
+    /// [`ToGame::Join`].
+    Joined {
+        id: u8,
+        token: Token,
+        capabilities: Capabilities,
+    },
     /// Informs the player that they were not connected to the game due to an
     /// error.
     JoinError(JoinError),
@@ -73,13 +347,475 @@ pub enum FromGame {
     /// Informs the player that another player with the given ID just
     /// disconnected from the same game.
     PeerLeft(u8),
+    /// Current state of the game lobby. This is sent to all players in the
+    /// lobby after each change of the lobby.
+    LobbyState(LobbyState),
+    /// Informs the player that the game has just started. The lobby is closed
+    /// at this point.
+    GameStarted,
+    /// A chat message sent by a player with the given ID. See
+    /// [`ToGame::Chat`].
+    Chat {
+        from: u8,
+        channel: ChatChannel,
+        text: String,
+    },
+    /// Informs the client that they were just connected to the game as a
+    /// spectator.
+    Spectating,
+    /// Informs the player that connection to another player with the given
+    /// ID was lost. The player may rejoin the game (see [`ToGame::Rejoin`]),
+    /// otherwise [`FromGame::PeerLeft`] follows.
+    PeerDisconnected(u8),
+    /// Another player with the given ID requests the full game state. See
+    /// [`ToGame::RequestResync`].
+    ResyncRequested(u8),
+    /// Informs the player that a player with the given ID was kicked out of
+    /// the game by the host or by the server, see [`FromGame::Kicked`].
+    PlayerKicked(u8),
+    /// Informs the client that it was disconnected from the game by the
+    /// server because it sent too many packages.
+    Kicked,
+    /// Informs the client that it was not connected to the game because the
+    /// game is password protected and the password is missing or does not
+    /// match. See [`ToGame::Join`].
+    WrongPassword,
+    /// Response to [`ToGame::SyncClock`] with the same ID. `time` is the
+    /// number of microseconds elapsed on the server since the game was
+    /// opened.
+    Clock { id: u32, time: u64 },
+    /// Informs the client that a player with the given ID gave up the game,
+    /// see [`ToGame::Surrender`]. All objects of the player are to be
+    /// destroyed.
+    PlayerSurrendered(u8),
+    /// Informs the client that it was not connected to the game because it
+    /// uses an incompatible protocol version. See [`ToGame::Join`].
+    VersionMismatch { server: u16, client: u16 },
 }
 
-#[derive(Encode, Decode)]
+/// Message to be sent from a player to all other players in the game.
+///
+/// The game server relays player packages only if the sender of each message
+/// (see [`ToPlayers::sender`]) is the player who sent the package, thus the
+/// sender of a received message can be trusted.
+#[derive(Serialize, Deserialize)]
+pub enum ToPlayers {
+    /// Commands of the player with ID `player` to be executed at simulation
+    /// tick `tick` of a lockstep simulation. Each player sends exactly one
+    /// such message for each tick, with no commands if the player did
+    /// nothing. The game server rejects commands with `player` different from
+    /// the sending player.
+    ///
+    /// The commands are opaque to the network layer and are at most
+    /// [`MAX_COMMANDS_LEN`] bytes long.
+    Commands {
+        player: u8,
+        tick: u32,
+        commands: Vec<u8>,
+    },
+    /// Hash of the state (types, positions, rotations and healths) of
+    /// objects owned by the player with ID `player` as replicated right after
+    /// simulation tick `tick`. Other players compare it with the hash of
+    /// their replicas of the objects received with [`ToPlayers::Replication`]
+    /// of the same tick to detect diverging simulations.
+    Checksum { player: u8, tick: u32, hash: u64 },
+    /// A fragment of the state of objects of the player with ID `target`
+    /// sent by the player with ID `player` in response to
+    /// [`FromGame::ResyncRequested`]. All other players ignore the message.
+    ///
+    /// The state, as it was right before simulation tick `tick`, is split
+    /// into `count` fragments of at most [`MAX_SNAPSHOT_FRAGMENT_LEN`] bytes
+    /// and this is the fragment number `index`.
+    Snapshot {
+        player: u8,
+        target: u8,
+        tick: u32,
+        index: u16,
+        count: u16,
+        data: Vec<u8>,
+    },
+    /// Changes of replicated objects owned by the player with ID `owner`
+    /// sent by the player with ID `player` to the player with ID `target`.
+    /// All other players ignore the message. This is sent unreliably.
+    ///
+    /// Objects are replicated by their owner unless the owner left the game
+    /// and its objects were taken over by the player who decided on them,
+    /// see [`DropPolicy::AiTakeover`].
+    ///
+    /// The changes (at most [`MAX_REPLICATION_LEN`] bytes) are relative to
+    /// the state with sequence number `baseline` or to an empty state if
+    /// `baseline` is None. The resulting state has sequence number
+    /// `sequence` and is acknowledged with [`ToPlayers::ReplicationAck`].
+    ///
+    /// If `tick` is not None, the resulting state is the complete state of
+    /// the objects right after simulation tick `tick`, see
+    /// [`ToPlayers::Checksum`].
+    Replication {
+        player: u8,
+        owner: u8,
+        target: u8,
+        sequence: u32,
+        baseline: Option<u32>,
+        tick: Option<u32>,
+        data: Vec<u8>,
+    },
+    /// Acknowledges reception of replicated state with sequence number
+    /// `sequence` of objects owned by the player with ID `owner` sent by the
+    /// player with ID `target`. The acknowledging player has ID `player`.
+    /// See [`ToPlayers::Replication`].
+    ReplicationAck {
+        player: u8,
+        owner: u8,
+        target: u8,
+        sequence: u32,
+    },
+    /// Request of the player with ID `player` to pause the game. The
+    /// simulation halts right before simulation tick `tick`. All other
+    /// players acknowledge the request with [`ToPlayers::PauseAck`].
+    Pause { player: u8, tick: u32 },
+    /// Acknowledges a pause request of the player with ID `requester`. The
+    /// acknowledging player has ID `player`. See [`ToPlayers::Pause`].
+    PauseAck {
+        player: u8,
+        requester: u8,
+        tick: u32,
+    },
+    /// Request of the player with ID `player` to resume the paused game.
+    Resume { player: u8 },
+    /// Decision of the player with ID `player` on how to handle objects of
+    /// the player with ID `dropped` who left the game. The decision is made
+    /// by the remaining player with the lowest ID so that all players handle
+    /// the objects in the same way.
+    PlayerDropped {
+        player: u8,
+        dropped: u8,
+        policy: DropPolicy,
+    },
+    /// Prompts all other players to respond with [`ToPlayers::Pong`] with
+    /// the same ping ID. This is sent unreliably by the player with ID
+    /// `player` to measure connection quality to the other players.
+    Ping { player: u8, id: u32 },
+    /// Response of the player with ID `player` to [`ToPlayers::Ping`] of the
+    /// player with ID `target`. All other players ignore the message.
+    Pong { player: u8, target: u8, id: u32 },
+    /// Proposal of the player with ID `player` to form an alliance with the
+    /// player with ID `target`. The alliance is formed once both players
+    /// propose it to each other.
+    ProposeAlliance { player: u8, target: u8 },
+    /// Ping marker placed by the player with ID `player` on the map at 2D
+    /// flat position (`x`, `y`). Players who are not allied with the sender
+    /// ignore the message.
+    MapPing { player: u8, x: f32, y: f32 },
+    /// The player with ID `player` detected end of the game. `winner` is
+    /// the ID of the winning player or None in the case of a draw. The game
+    /// ends for all players once the first such message is received.
+    GameEnded { player: u8, winner: Option<u8> },
+    /// Statistics of the player with ID `player` accumulated since the start
+    /// of the game. Objects of a player are simulated only on the computer
+    /// of the player, thus other players rely on these messages. Each such
+    /// message supersedes all previous ones from the same player.
+    MatchStats {
+        player: u8,
+        units_built: u32,
+        units_lost: u32,
+        units_killed: u32,
+        damage_dealt: f32,
+        resources_gathered: f32,
+        actions: u32,
+    },
+    /// The player with ID `player` joined a game whose map with hash `hash`
+    /// is not available locally. The player with the lowest ID among the
+    /// other players in the lobby responds with [`ToPlayers::MapFragment`].
+    MapRequest { player: u8, hash: [u8; 32] },
+    /// A fragment of a map file sent to the player with ID `target` in
+    /// response to [`ToPlayers::MapRequest`]. All other players ignore the
+    /// message.
+    ///
+    /// The map file is split into `count` fragments of at most
+    /// [`MAX_MAP_FRAGMENT_LEN`] bytes and this is the fragment number
+    /// `index`.
+    MapFragment {
+        target: u8,
+        index: u16,
+        count: u16,
+        data: Vec<u8>,
+    },
+}
+
+impl ToPlayers {
+    /// Returns ID of the player who sent the message or None if the message
+    /// does not contain it.
+    pub fn sender(&self) -> Option<u8> {
+        match *self {
+            Self::Commands { player, .. }
+            | Self::Checksum { player, .. }
+            | Self::Replication { player, .. }
+            | Self::ReplicationAck { player, .. }
+            | Self::Pause { player, .. }
+            | Self::PauseAck { player, .. }
+            | Self::Resume { player }
+            | Self::PlayerDropped { player, .. }
+            | Self::Snapshot { player, .. }
+            | Self::Ping { player, .. }
+            | Self::Pong { player, .. }
+            | Self::ProposeAlliance { player, .. }
+            | Self::MapPing { player, .. }
+            | Self::GameEnded { player, .. }
+            | Self::MatchStats { player, .. }
+            | Self::MapRequest { player, .. } => Some(player),
+            Self::MapFragment { .. } => None,
+        }
+    }
+}
+
+/// Maximum length of encoded commands of a single player for a single tick
+/// in bytes. See [`ToPlayers::Commands`].
+pub const MAX_COMMANDS_LEN: usize = 384;
+
+/// Maximum length of a single game state snapshot fragment in bytes. See
+/// [`ToPlayers::Snapshot`].
+pub const MAX_SNAPSHOT_FRAGMENT_LEN: usize = 384;
+
+/// Maximum length of a single map file fragment in bytes. See
+/// [`ToPlayers::MapFragment`].
+pub const MAX_MAP_FRAGMENT_LEN: usize = 384;
+
+/// Maximum length of replicated state changes in bytes. See
+/// [`ToPlayers::Replication`].
+pub const MAX_REPLICATION_LEN: usize = 384;
+
+/// Maximum length of an authentication token in bytes.
+pub const MAX_AUTH_LEN: usize = 384;
+
+/// Maximum length of a message of the day in bytes.
+pub const MAX_MOTD_LEN: usize = 256;
+
+/// Maximum length of a chat message text in bytes.
+pub const MAX_CHAT_LEN: usize = 256;
+
+/// Handling of objects of a player who left an already started game.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DropPolicy {
+    /// All objects of the player are destroyed.
+    #[default]
+    Destroy,
+    /// The objects are handed over to an AI controller of the player who
+    /// made the decision, see [`ToPlayers::PlayerDropped`].
+    AiTakeover,
+}
+
+/// A set of optional protocol features, e.g. compression or encryption,
+/// negotiated when joining a game. See [`ToGame::Join`].
+///
+/// Unknown features are kept so that they can be relayed and intersected
+/// with the features known to a different version of the game.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// Package payloads may be compressed, see
+    /// [`crate::OutPackage::with_compression`].
+    pub const COMPRESSION: Self = Self(1);
+    /// Datagrams are encrypted once the player joins the game, see
+    /// [`crate::Encryption`].
+    pub const ENCRYPTION: Self = Self(1 << 1);
+    /// Features supported by this version of the game.
+    pub const SUPPORTED: Self = Self(Self::COMPRESSION.0 | Self::ENCRYPTION.0);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns true if all features of `other` are in this set.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns features present in both sets, i.e. features which can be
+    /// used when both sides of a connection support the sets.
+    pub fn intersection(&self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+/// Recipients of a chat message.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChatChannel {
+    /// All other players in the game.
+    All,
+    /// Other players in the same team as the sender.
+    Team,
+    /// A single player with the given ID.
+    Whisper(u8),
+}
+
+/// Players waiting in a game lobby and slot assignments of the game.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LobbyState {
+    players: Vec<LobbyPlayer>,
+    ai_players: Vec<AiSlot>,
+}
+
+impl LobbyState {
+    /// # Panics
+    ///
+    /// Panics if the players or the AI players are not sorted by their ID.
+    pub fn new(players: Vec<LobbyPlayer>, ai_players: Vec<AiSlot>) -> Self {
+        assert!(players.windows(2).all(|w| w[0].id < w[1].id));
+        assert!(ai_players.windows(2).all(|w| w[0].id < w[1].id));
+        Self {
+            players,
+            ai_players,
+        }
+    }
+
+    /// Players in the lobby sorted by their ID.
+    pub fn players(&self) -> &[LobbyPlayer] {
+        self.players.as_slice()
+    }
+
+    /// AI players of the game sorted by their ID.
+    pub fn ai_players(&self) -> &[AiSlot] {
+        self.ai_players.as_slice()
+    }
+
+    /// Returns true if there is at least one player in the lobby and all
+    /// players in the lobby are ready.
+    pub fn all_ready(&self) -> bool {
+        !self.players.is_empty() && self.players.iter().all(|p| p.ready)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LobbyPlayer {
+    id: u8,
+    team: u8,
+    name: Option<String>,
+    ready: bool,
+}
+
+impl LobbyPlayer {
+    pub fn new(id: u8, team: u8, name: Option<String>, ready: bool) -> Self {
+        Self {
+            id,
+            team,
+            name,
+            ready,
+        }
+    }
+
+    /// ID of the player in the game.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn team(&self) -> u8 {
+        self.team
+    }
+
+    /// User name of the player verified by the server. It is None if the
+    /// server does not authenticate players.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// True if the player is ready to start the game.
+    pub fn ready(&self) -> bool {
+        self.ready
+    }
+}
+
+/// A slot occupied by an AI player.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AiSlot {
+    id: u8,
+    team: u8,
+}
+
+impl AiSlot {
+    pub fn new(id: u8, team: u8) -> Self {
+        Self { id, team }
+    }
+
+    /// ID of the AI player in the game.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn team(&self) -> u8 {
+        self.team
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub enum JoinError {
     GameFull,
     /// The player has already joined the game.
     AlreadyJoined,
     /// The player already participates on a different game.
     DifferentGame,
+    /// The game has already started and does not accept new players.
+    GameStarted,
+    /// The rejoin token does not belong to any disconnected player of the
+    /// game, or the grace period has already passed.
+    InvalidToken,
+    /// The player was kicked out of the game by the host.
+    Banned,
+    /// The server requires authentication and the authentication token is
+    /// missing or invalid.
+    Unauthenticated,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let client = Capabilities::from_bits(0b0110);
+        let server = Capabilities::from_bits(0b1100);
+
+        let negotiated = client.intersection(server);
+        assert_eq!(negotiated.bits(), 0b0100);
+        assert!(client.contains(negotiated));
+        assert!(server.contains(negotiated));
+        assert!(!negotiated.contains(client));
+        assert!(client.contains(Capabilities::empty()));
+
+        assert!(Capabilities::SUPPORTED.contains(Capabilities::COMPRESSION));
+        assert!(Capabilities::SUPPORTED.contains(Capabilities::ENCRYPTION));
+        assert!(!Capabilities::COMPRESSION.contains(Capabilities::ENCRYPTION));
+    }
+
+    #[test]
+    fn test_slots() {
+        let ffa = GameSlots::new(0, 2);
+        assert!(ffa.is_valid(3));
+        assert!(!ffa.is_valid(2));
+        assert_eq!(ffa.human_players(4), 2);
+        assert_eq!(ffa.team_size(4), None);
+        assert_eq!(ffa.team(3), Some(3));
+        assert_eq!(ffa.team(0), None);
+
+        let teams = GameSlots::new(2, 0);
+        assert!(teams.is_valid(4));
+        assert!(!teams.is_valid(3));
+        assert!(!GameSlots::new(1, 0).is_valid(4));
+        assert_eq!(teams.team_size(4), Some(2));
+        assert_eq!(
+            (1..=4)
+                .map(|id| teams.team(id).unwrap())
+                .collect::<Vec<_>>(),
+            vec![1, 2, 1, 2]
+        );
+        assert_eq!(teams.team(0), None);
+    }
 }
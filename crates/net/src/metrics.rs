@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use async_std::sync::Arc;
+
+/// Cumulative counters of network traffic of a single networking stack. See
+/// [`crate::NetConf::with_metrics`].
+///
+/// The counters are shared by all clones of the value and they are updated
+/// by the tasks of the networking stack, thus they may be read at any time
+/// from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct NetMetrics(Arc<Counters>);
+
+impl NetMetrics {
+    /// Number of sent UDP datagrams, including re-sent and system datagrams.
+    pub fn datagrams_sent(&self) -> u64 {
+        self.0.datagrams_sent.load(Ordering::Relaxed)
+    }
+
+    /// Number of received UDP datagrams.
+    pub fn datagrams_received(&self) -> u64 {
+        self.0.datagrams_received.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes sent in UDP datagrams.
+    pub fn bytes_sent(&self) -> u64 {
+        self.0.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Number of bytes received in UDP datagrams.
+    pub fn bytes_received(&self) -> u64 {
+        self.0.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Number of re-sent reliable packages.
+    pub fn retransmissions(&self) -> u64 {
+        self.0.retransmissions.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_sent(&self, bytes: usize) {
+        self.0.datagrams_sent.fetch_add(1, Ordering::Relaxed);
+        self.0.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_received(&self, bytes: usize) {
+        self.0.datagrams_received.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_retransmission(&self) {
+        self.0.retransmissions.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    datagrams_sent: AtomicU64,
+    datagrams_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    retransmissions: AtomicU64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let metrics = NetMetrics::default();
+        let clone = metrics.clone();
+
+        clone.record_sent(100);
+        clone.record_sent(20);
+        clone.record_received(7);
+        clone.record_retransmission();
+
+        assert_eq!(metrics.datagrams_sent(), 2);
+        assert_eq!(metrics.bytes_sent(), 120);
+        assert_eq!(metrics.datagrams_received(), 1);
+        assert_eq!(metrics.bytes_received(), 7);
+        assert_eq!(metrics.retransmissions(), 1);
+    }
+}
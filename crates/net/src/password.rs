@@ -0,0 +1,45 @@
+use std::fmt;
+
+use ring::digest::{digest, SHA256};
+use serde::{Deserialize, Serialize};
+
+/// Domain separation prefix of hashed game passwords.
+const PREFIX: &[u8] = b"de-game-password:";
+
+/// Hash of a game password. Clients send only hashes of passwords so that
+/// the passwords themselves are never sent over the network in plain text.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordHash([u8; 32]);
+
+impl PasswordHash {
+    /// Hashes a (plain text) game password.
+    pub fn new(password: &str) -> Self {
+        let mut data = Vec::with_capacity(PREFIX.len() + password.len());
+        data.extend_from_slice(PREFIX);
+        data.extend_from_slice(password.as_bytes());
+
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(digest(&SHA256, &data).as_ref());
+        Self(bytes)
+    }
+}
+
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Do not leak the hash to logs.
+        write!(f, "PasswordHash(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(PasswordHash::new("secret"), PasswordHash::new("secret"));
+        assert_ne!(PasswordHash::new("secret"), PasswordHash::new("Secret"));
+        assert_ne!(PasswordHash::new(""), PasswordHash::new(" "));
+        assert_eq!(format!("{:?}", PasswordHash::new("x")), "PasswordHash(..)");
+    }
+}
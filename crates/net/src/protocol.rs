@@ -1,36 +1,43 @@
-use std::{borrow::Cow, net::SocketAddr};
+use std::{borrow::Cow, net::SocketAddr, time::Instant};
 
 use async_std::sync::Arc;
-use futures::future::try_join_all;
 use thiserror::Error;
 use tracing::{error, trace};
 
 use crate::{
+    connection::{Encryption, Incoming, Outgoing, ENCRYPTION_OVERHEAD, PUBLIC_KEY_LEN},
     header::{DatagramHeader, HeaderError, HEADER_SIZE},
-    socket, SendError, Socket, MAX_DATAGRAM_SIZE,
+    socket,
+    token::TOKEN_SIZE,
+    NetMetrics, SendError, Socket, MAX_DATAGRAM_SIZE,
 };
 
 /// Maximum number of bytes of a single package payload.
-pub const MAX_PACKAGE_SIZE: usize = MAX_DATAGRAM_SIZE - HEADER_SIZE;
+pub const MAX_PACKAGE_SIZE: usize =
+    MAX_DATAGRAM_SIZE - HEADER_SIZE - TOKEN_SIZE - ENCRYPTION_OVERHEAD;
 
 /// A thin layer over a UDP socket translating between UDP datagrams and
 /// header-payload pairs.
+///
+/// Datagrams are transparently encrypted, see [`Encryption`].
 #[derive(Clone)]
 pub(crate) struct ProtocolSocket {
     socket: Arc<Socket>,
+    encryption: Encryption,
+    metrics: NetMetrics,
 }
 
 impl ProtocolSocket {
-    pub(crate) fn new(socket: Socket) -> Self {
+    pub(crate) fn new(socket: Socket, metrics: NetMetrics, encryption: Encryption) -> Self {
         Self {
             socket: Arc::new(socket),
+            encryption,
+            metrics,
         }
     }
 
     /// Send data to a list of targets.
     ///
-    /// The sending is done in parallel.
-    ///
     /// # Arguments
     ///
     /// * `buf` - binary data buffer used during datagram construction.
@@ -58,20 +65,60 @@ impl ProtocolSocket {
         trace!("Going to send datagram {}", header);
         header.write(buf);
 
-        match targets.into() {
-            Targets::Single(target) => {
-                self.socket.send(target, buf).await?;
-            }
-            Targets::Many(targets) => {
-                try_join_all(targets.iter().map(|&target| self.socket.send(target, buf))).await?;
+        let time = Instant::now();
+        let mut sealed = [0u8; MAX_DATAGRAM_SIZE];
+        for target in &targets.into() {
+            match self
+                .encryption
+                .outgoing(time, target, buf, &mut sealed)
+                .await
+            {
+                Outgoing::Plain => {
+                    self.send_raw(target, buf).await?;
+                }
+                Outgoing::Hello(public_key) => {
+                    trace!("Datagram to {target} withheld, sending hello instead");
+                    self.send_hello(target, public_key).await?;
+                }
+                Outgoing::Sealed(len) => {
+                    self.send_raw(target, &sealed[..len]).await?;
+                }
+                Outgoing::Withheld => {
+                    trace!("Datagram to {target} withheld until encryption handshake finishes");
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Forgets encryption session with a peer which closed the connection.
+    pub(crate) async fn forget(&self, addr: SocketAddr) {
+        self.encryption.forget(addr).await;
+    }
+
+    async fn send_hello(
+        &self,
+        target: SocketAddr,
+        public_key: [u8; PUBLIC_KEY_LEN],
+    ) -> Result<(), SendError> {
+        let mut hello = [0u8; HEADER_SIZE + PUBLIC_KEY_LEN];
+        DatagramHeader::Hello.write(&mut hello);
+        hello[HEADER_SIZE..].copy_from_slice(&public_key);
+        self.send_raw(target, &hello).await
+    }
+
+    async fn send_raw(&self, target: SocketAddr, data: &[u8]) -> Result<(), SendError> {
+        self.socket.send(target, data).await?;
+        self.metrics.record_sent(data.len());
+        Ok(())
+    }
+
     /// Receive a single datagram.
     ///
+    /// Encryption handshake datagrams are handled internally and encrypted
+    /// datagrams are decrypted.
+    ///
     /// # Arguments
     ///
     /// * `buf` - the data is written to this buffer. The buffer must be at
@@ -89,12 +136,43 @@ impl ProtocolSocket {
         &self,
         buf: &'a mut [u8],
     ) -> Result<(SocketAddr, DatagramHeader, &'a [u8]), MsgRecvError> {
-        let (stop, source) = self.socket.recv(buf).await.map_err(MsgRecvError::from)?;
+        loop {
+            let (stop, source) = self.socket.recv(buf).await.map_err(MsgRecvError::from)?;
+            self.metrics.record_received(stop);
+
+            let header = DatagramHeader::read(&buf[0..stop]).map_err(MsgRecvError::from)?;
+            trace!("Received datagram with ID {header}");
+
+            let incoming = self
+                .encryption
+                .incoming(Instant::now(), source, header, &mut buf[HEADER_SIZE..stop])
+                .await;
+
+            match incoming {
+                Incoming::Plain => return Ok((source, header, &buf[HEADER_SIZE..stop])),
+                Incoming::Decrypted(range) => {
+                    let start = HEADER_SIZE + range.start;
+                    let stop = HEADER_SIZE + range.end;
 
-        let header = DatagramHeader::read(&buf[0..stop]).map_err(MsgRecvError::from)?;
-        trace!("Received datagram with ID {header}");
+                    let header = DatagramHeader::read(&buf[start..stop])?;
+                    trace!("Decrypted datagram with ID {header}");
 
-        Ok((source, header, &buf[HEADER_SIZE..stop]))
+                    if matches!(header, DatagramHeader::Hello | DatagramHeader::Encrypted) {
+                        return Err(MsgRecvError::InvalidHeader(HeaderError::Invalid));
+                    }
+
+                    return Ok((source, header, &buf[start + HEADER_SIZE..stop]));
+                }
+                Incoming::Reply(public_key) => {
+                    if let Err(err) = self.send_hello(source, public_key).await {
+                        error!("Hello reply to {source:?} could not be sent: {err:?}");
+                    }
+                }
+                Incoming::Drop => {
+                    trace!("Dropped datagram from {source:?}.");
+                }
+            }
+        }
     }
 }
 
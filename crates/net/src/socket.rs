@@ -1,10 +1,20 @@
 use std::{
     io,
-    net::{IpAddr, Ipv4Addr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
 };
 
-use async_std::net::{SocketAddr, UdpSocket};
+#[cfg(feature = "testing")]
+use async_std::task;
+use async_std::{
+    net::{SocketAddr, UdpSocket},
+    sync::Arc,
+};
+use socket2::{Domain, Protocol, Type};
 use thiserror::Error;
+use tracing::warn;
+
+#[cfg(feature = "testing")]
+use crate::faults::{FaultInjector, Faults};
 
 /// Maximum size of a UDP datagram which might be sent by this crate.
 ///
@@ -16,32 +26,84 @@ pub const MAX_DATAGRAM_SIZE: usize = 508;
 
 /// This struct represents a low level network socket. The socket is based on
 /// UDP and thus provides unreliable and unordered means of data delivery.
+///
+/// The socket is dual-stack (i.e. it communicates over both IPv4 and IPv6)
+/// whenever the system supports it. IPv4 addresses are always represented as
+/// [`SocketAddr::V4`] (never as IPv4-mapped IPv6 addresses).
 pub struct Socket {
-    socket: UdpSocket,
+    socket: Arc<UdpSocket>,
     port: u16,
+    dual_stack: bool,
+    #[cfg(feature = "testing")]
+    faults: Option<FaultInjector>,
 }
 
 impl Socket {
-    /// Creates / binds a new IPv4 based connection (socket).
+    /// Creates / binds a new dual-stack connection (socket). It falls back to
+    /// an IPv4 only socket if IPv6 is not available.
     ///
     /// # Arguments
     ///
     /// * `port` - if None, system assigned port is used.
     pub async fn bind(port: Option<u16>) -> io::Result<Self> {
-        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port.unwrap_or(0));
-        let socket = UdpSocket::bind(addr).await?;
+        let (socket, dual_stack) = match Self::bind_dual_stack(port.unwrap_or(0)) {
+            Ok(socket) => (socket, true),
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => return Err(err),
+            Err(err) => {
+                warn!("Could not bind a dual-stack socket, falling back to IPv4: {err:?}");
+                let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port.unwrap_or(0));
+                (UdpSocket::bind(addr).await?, false)
+            }
+        };
+
+        Self::from_udp(socket, port, dual_stack)
+    }
+
+    /// Creates / binds a new connection (socket) to a specific local IP
+    /// address. Unlike [`Self::bind`], the socket communicates only over the
+    /// IP version of `ip`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ip` - local address to bind the socket to.
+    ///
+    /// * `port` - if None, system assigned port is used.
+    pub async fn bind_to(ip: IpAddr, port: Option<u16>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(SocketAddr::new(ip, port.unwrap_or(0))).await?;
+        Self::from_udp(socket, port, false)
+    }
 
+    fn from_udp(socket: UdpSocket, port: Option<u16>, dual_stack: bool) -> io::Result<Self> {
         let obtained_port = socket.local_addr().map(|addr| addr.port())?;
         if let Some(desired_port) = port {
             assert_eq!(obtained_port, desired_port);
         }
 
         Ok(Self {
-            socket,
+            socket: Arc::new(socket),
             port: obtained_port,
+            dual_stack,
+            #[cfg(feature = "testing")]
+            faults: None,
         })
     }
 
+    /// Injects artificial faults (latency, loss and so on) into all datagrams
+    /// subsequently sent via the socket.
+    #[cfg(feature = "testing")]
+    pub fn with_faults(mut self, faults: Faults) -> Self {
+        self.faults = Some(FaultInjector::new(faults));
+        self
+    }
+
+    fn bind_dual_stack(port: u16) -> io::Result<UdpSocket> {
+        let socket = socket2::Socket::new(Domain::IPV6, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_only_v6(false)?;
+        let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port);
+        socket.bind(&addr.into())?;
+        Ok(std::net::UdpSocket::from(socket).into())
+    }
+
     pub fn port(&self) -> u16 {
         self.port
     }
@@ -60,7 +122,7 @@ impl Socket {
         self.socket
             .recv_from(buf)
             .await
-            .map(|(len, source)| (len.min(MAX_DATAGRAM_SIZE), source))
+            .map(|(len, source)| (len.min(MAX_DATAGRAM_SIZE), to_canonical(source)))
             .map_err(RecvError::from)
     }
 
@@ -79,6 +141,34 @@ impl Socket {
             );
         }
 
+        let target = if self.dual_stack {
+            to_mapped(target)
+        } else {
+            target
+        };
+
+        #[cfg(feature = "testing")]
+        if let Some(faults) = self.faults.as_ref() {
+            for delay in faults.plan() {
+                if delay.is_zero() {
+                    self.send_inner(target, data).await?;
+                } else {
+                    let socket = Arc::clone(&self.socket);
+                    let data = data.to_vec();
+                    task::spawn(async move {
+                        task::sleep(delay).await;
+                        // Delayed datagrams are sent on a best-effort basis.
+                        let _ = socket.send_to(&data, target).await;
+                    });
+                }
+            }
+            return Ok(());
+        }
+
+        self.send_inner(target, data).await
+    }
+
+    async fn send_inner(&self, target: SocketAddr, data: &[u8]) -> Result<(), SendError> {
         let n = self
             .socket
             .send_to(data, target)
@@ -93,6 +183,26 @@ impl Socket {
     }
 }
 
+/// Converts an IPv4 address to an IPv4-mapped IPv6 address, which is needed
+/// for sending over a dual-stack socket.
+fn to_mapped(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(v4) => SocketAddr::new(IpAddr::V6(v4.ip().to_ipv6_mapped()), v4.port()),
+        SocketAddr::V6(_) => addr,
+    }
+}
+
+/// Converts an IPv4-mapped IPv6 address to an IPv4 address.
+fn to_canonical(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(v6) => match v6.ip().to_ipv4_mapped() {
+            Some(v4) => SocketAddr::new(IpAddr::V4(v4), v6.port()),
+            None => addr,
+        },
+        SocketAddr::V4(_) => addr,
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum RecvError {
     #[error("an IO error occurred")]
@@ -106,3 +216,42 @@ pub enum SendError {
     #[error("only {0} of {1} bytes sent")]
     PartialSend(usize, usize),
 }
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+
+    use super::*;
+
+    #[test]
+    fn test_address_mapping() {
+        let v4: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+        let mapped: SocketAddr = "[::ffff:127.0.0.1]:1000".parse().unwrap();
+        let v6: SocketAddr = "[::1]:1000".parse().unwrap();
+
+        assert_eq!(to_mapped(v4), mapped);
+        assert_eq!(to_mapped(v6), v6);
+        assert_eq!(to_canonical(mapped), v4);
+        assert_eq!(to_canonical(v4), v4);
+        assert_eq!(to_canonical(v6), v6);
+    }
+
+    #[test]
+    fn test_ipv4() {
+        task::block_on(async {
+            let first = Socket::bind(None).await.unwrap();
+            let second = Socket::bind(None).await.unwrap();
+
+            let target = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), second.port());
+            first.send(target, &[1, 2, 3]).await.unwrap();
+
+            let mut buf = [0u8; MAX_DATAGRAM_SIZE];
+            let (len, source) = second.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], &[1, 2, 3]);
+            assert_eq!(
+                source,
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), first.port())
+            );
+        });
+    }
+}
@@ -1,27 +1,25 @@
 use std::{marker::PhantomData, mem, net::SocketAddr, ops::Deref, time::Instant};
 
 use async_std::channel::{Receiver, Sender};
-use bincode::{
-    config::{BigEndian, Configuration, Limit, Varint},
-    decode_from_slice, encode_into_slice, encode_to_vec,
-    error::{DecodeError, EncodeError},
-};
+use serde::{de::DeserializeOwned, Serialize};
 
+pub use crate::connection::ConnectionStats;
 use crate::{
+    codec::{BincodeCodec, Codec, DecodeError, EncodeError},
+    compression::compress,
     header::Peers,
     protocol::{Targets, MAX_PACKAGE_SIZE},
+    token::Token,
 };
 
-const BINCODE_CONF: Configuration<BigEndian, Varint, Limit<MAX_PACKAGE_SIZE>> =
-    bincode::config::standard()
-        .with_big_endian()
-        .with_variable_int_encoding()
-        .with_limit::<MAX_PACKAGE_SIZE>();
-
 /// It cumulatively builds output packages from individual messages.
-pub struct PackageBuilder {
+pub struct PackageBuilder<C: Codec = BincodeCodec> {
+    codec: C,
     reliable: bool,
     peers: Peers,
+    token: Option<Token>,
+    priority: Priority,
+    compression: bool,
     targets: Targets<'static>,
     buffer: Vec<u8>,
     used: usize,
@@ -30,12 +28,27 @@ pub struct PackageBuilder {
 
 impl PackageBuilder {
     pub fn new<T>(reliable: bool, peers: Peers, targets: T) -> Self
+    where
+        T: Into<Targets<'static>>,
+    {
+        Self::with_codec(BincodeCodec, reliable, peers, targets)
+    }
+}
+
+impl<C: Codec> PackageBuilder<C> {
+    /// Creates a builder which encodes the messages with the given codec.
+    /// See [`Self::new`] for the default codec.
+    pub fn with_codec<T>(codec: C, reliable: bool, peers: Peers, targets: T) -> Self
     where
         T: Into<Targets<'static>>,
     {
         Self {
+            codec,
             reliable,
             peers,
+            token: None,
+            priority: Priority::default(),
+            compression: false,
             targets: targets.into(),
             buffer: vec![0; MAX_PACKAGE_SIZE],
             used: 0,
@@ -43,18 +56,48 @@ impl PackageBuilder {
         }
     }
 
+    /// Includes the token in all built packages. See
+    /// [`OutPackage::with_token`].
+    pub fn with_token(mut self, token: Token) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Sets priority of all built packages. See [`OutPackage::with_priority`].
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Compresses all built packages if it is worth it. See
+    /// [`OutPackage::with_compression`].
+    pub fn with_compression(mut self) -> Self {
+        self.compression = true;
+        self
+    }
+
+    fn finish(&self, data: Vec<u8>) -> OutPackage {
+        let package = OutPackage::new(data, self.reliable, self.peers, self.targets.clone())
+            .with_token_opt(self.token)
+            .with_priority(self.priority);
+        if self.compression {
+            package.with_compression()
+        } else {
+            package
+        }
+    }
+
     /// Build output packages from all pushed messages.
     ///
     /// The messages are distributed among the packages in a sequential order.
     /// Each package is filled with as many messages as it can accommodate.
     pub fn build(mut self) -> Vec<OutPackage> {
-        let mut packages = self.packages;
+        let mut packages = mem::take(&mut self.packages);
 
         if self.used > 0 {
-            self.buffer.truncate(self.used);
-            let package =
-                OutPackage::new(self.buffer, self.reliable, self.peers, self.targets.clone());
-            packages.push(package);
+            let mut data = mem::take(&mut self.buffer);
+            data.truncate(self.used);
+            packages.push(self.finish(data));
         }
 
         packages
@@ -64,17 +107,16 @@ impl PackageBuilder {
     /// the resulting packages.
     pub fn push<E>(&mut self, message: &E) -> Result<(), EncodeError>
     where
-        E: bincode::Encode,
+        E: Serialize,
     {
         match self.push_inner(message) {
-            Err(EncodeError::UnexpectedEnd) => {
+            Err(EncodeError::BufferFull) => {
                 let mut data = vec![0; MAX_PACKAGE_SIZE];
                 mem::swap(&mut data, &mut self.buffer);
                 data.truncate(self.used);
                 self.used = 0;
 
-                let package =
-                    OutPackage::new(data, self.reliable, self.peers, self.targets.clone());
+                let package = self.finish(data);
                 self.packages.push(package);
 
                 self.push_inner(message)
@@ -86,9 +128,11 @@ impl PackageBuilder {
 
     fn push_inner<E>(&mut self, message: &E) -> Result<(), EncodeError>
     where
-        E: bincode::Encode,
+        E: Serialize,
     {
-        let len = encode_into_slice(message, &mut self.buffer[self.used..], BINCODE_CONF)?;
+        let len = self
+            .codec
+            .encode_into_slice(message, &mut self.buffer[self.used..])?;
         self.used += len;
         Ok(())
     }
@@ -99,11 +143,15 @@ pub struct OutPackage {
     pub(super) data: Vec<u8>,
     reliable: bool,
     peers: Peers,
+    compressed: bool,
+    token: Option<Token>,
+    priority: Priority,
     pub(super) targets: Targets<'static>,
 }
 
 impl OutPackage {
-    /// Creates a package from a single message.
+    /// Creates a package from a single message encoded with the default
+    /// codec ([`BincodeCodec`]).
     ///
     /// See also [`Self::new`].
     pub fn encode_single<E, T>(
@@ -113,10 +161,26 @@ impl OutPackage {
         targets: T,
     ) -> Result<Self, EncodeError>
     where
-        E: bincode::Encode,
+        E: Serialize,
         T: Into<Targets<'static>>,
     {
-        let data = encode_to_vec(message, BINCODE_CONF)?;
+        Self::encode_single_with(&BincodeCodec, message, reliable, peers, targets)
+    }
+
+    /// Creates a package from a single message encoded with the given codec.
+    pub fn encode_single_with<C, E, T>(
+        codec: &C,
+        message: &E,
+        reliable: bool,
+        peers: Peers,
+        targets: T,
+    ) -> Result<Self, EncodeError>
+    where
+        C: Codec,
+        E: Serialize,
+        T: Into<Targets<'static>>,
+    {
+        let data = codec.encode_to_vec(message)?;
         Ok(Self::new(data, reliable, peers, targets))
     }
 
@@ -136,14 +200,48 @@ impl OutPackage {
         T: Into<Targets<'static>>,
     {
         assert!(data.len() < MAX_PACKAGE_SIZE);
+
         Self {
             data,
             reliable,
             peers,
+            compressed: false,
+            token: None,
+            priority: Priority::default(),
             targets: targets.into(),
         }
     }
 
+    /// Includes the token in the package. The token is delivered alongside
+    /// the package payload, see [`InPackage::token`].
+    pub fn with_token(self, token: Token) -> Self {
+        self.with_token_opt(Some(token))
+    }
+
+    fn with_token_opt(mut self, token: Option<Token>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Sets priority of the package (by default [`Priority::Command`]).
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Compresses the package payload if it is worth it. Only packages to
+    /// peers which negotiated [`crate::Capabilities::COMPRESSION`] should be
+    /// compressed.
+    pub fn with_compression(mut self) -> Self {
+        if !self.compressed {
+            if let Some(compressed) = compress(&self.data) {
+                self.data = compressed;
+                self.compressed = true;
+            }
+        }
+        self
+    }
+
     pub(super) fn reliable(&self) -> bool {
         self.reliable
     }
@@ -151,6 +249,36 @@ impl OutPackage {
     pub(super) fn peers(&self) -> Peers {
         self.peers
     }
+
+    pub(super) fn compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub(super) fn token(&self) -> Option<Token> {
+        self.token
+    }
+
+    pub(super) fn priority(&self) -> Priority {
+        self.priority
+    }
+}
+
+/// Priority of an outgoing package.
+///
+/// Packages with higher priority are sent before packages with lower priority
+/// whenever more packages are waiting to be sent, for example due to limited
+/// outgoing bandwidth (see [`crate::NetConf::with_bandwidth_limit`]).
+/// Protocol control datagrams (for example delivery confirmations) always take
+/// precedence over all packages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Time-critical packages, for example player commands.
+    #[default]
+    Command,
+    /// Game state synchronization.
+    StateSync,
+    /// Packages which may wait, for example chat messages.
+    Chat,
 }
 
 /// A received message / datagram.
@@ -158,6 +286,7 @@ pub struct InPackage {
     data: Vec<u8>,
     reliable: bool,
     peers: Peers,
+    token: Option<Token>,
     source: SocketAddr,
     time: Instant,
 }
@@ -167,6 +296,7 @@ impl InPackage {
         data: Vec<u8>,
         reliable: bool,
         peers: Peers,
+        token: Option<Token>,
         source: SocketAddr,
         time: Instant,
     ) -> Self {
@@ -174,6 +304,7 @@ impl InPackage {
             data,
             reliable,
             peers,
+            token,
             source,
             time,
         }
@@ -183,12 +314,24 @@ impl InPackage {
         self.data
     }
 
-    /// Interpret the data as a sequence of encoded messages.
+    /// Interpret the data as a sequence of messages encoded with the default
+    /// codec ([`BincodeCodec`]).
     pub fn decode<E>(&self) -> MessageDecoder<E>
     where
-        E: bincode::Decode,
+        E: DeserializeOwned,
+    {
+        self.decode_with(BincodeCodec)
+    }
+
+    /// Interpret the data as a sequence of messages encoded with the given
+    /// codec.
+    pub fn decode_with<E, C>(&self, codec: C) -> MessageDecoder<E, C>
+    where
+        E: DeserializeOwned,
+        C: Codec,
     {
         MessageDecoder {
+            codec,
             data: self.data.as_slice(),
             offset: 0,
             _marker: PhantomData,
@@ -208,6 +351,11 @@ impl InPackage {
         self.peers
     }
 
+    /// Token included by the sender or None if no token was included.
+    pub fn token(&self) -> Option<Token> {
+        self.token
+    }
+
     /// Package arrival time.
     pub fn time(&self) -> Instant {
         self.time
@@ -215,18 +363,21 @@ impl InPackage {
 }
 
 /// An iterator which decodes binary input data item by item.
-pub struct MessageDecoder<'a, E>
+pub struct MessageDecoder<'a, E, C = BincodeCodec>
 where
-    E: bincode::Decode,
+    E: DeserializeOwned,
+    C: Codec,
 {
+    codec: C,
     data: &'a [u8],
     offset: usize,
     _marker: PhantomData<E>,
 }
 
-impl<'a, E> Iterator for MessageDecoder<'a, E>
+impl<'a, E, C> Iterator for MessageDecoder<'a, E, C>
 where
-    E: bincode::Decode,
+    E: DeserializeOwned,
+    C: Codec,
 {
     type Item = Result<E, DecodeError>;
 
@@ -235,7 +386,7 @@ where
             return None;
         }
 
-        match decode_from_slice(&self.data[self.offset..], BINCODE_CONF) {
+        match self.codec.decode_from_slice(&self.data[self.offset..]) {
             Ok((item, len)) => {
                 self.offset += len;
                 Some(Ok(item))
@@ -245,25 +396,42 @@ where
     }
 }
 
-/// This error indicates failure to deliver a package to the target.
+/// This error indicates a failure of connection with the target.
 pub struct ConnectionError {
     target: SocketAddr,
+    kind: ConnErrorKind,
 }
 
 impl ConnectionError {
-    pub(super) fn new(target: SocketAddr) -> Self {
-        Self { target }
+    pub(super) fn new(target: SocketAddr, kind: ConnErrorKind) -> Self {
+        Self { target, kind }
     }
 
     pub fn target(&self) -> SocketAddr {
         self.target
     }
+
+    pub fn kind(&self) -> ConnErrorKind {
+        self.kind
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnErrorKind {
+    /// A reliably sent package could not be delivered to the target.
+    Undelivered,
+    /// Nothing has been received from the target for longer than the
+    /// configured timeout. See [`crate::NetConf`].
+    PeerDisconnected,
+    /// The target gracefully closed the connection.
+    PeerLeft,
 }
 
 /// Channel into networking stack tasks, used for data sending.
 ///
 /// The data-sending components of the networking stack are halted when this
-/// channel is closed (dropped).
+/// channel is closed (dropped). Before that, all peers with which packages
+/// were exchanged are informed that the connection is being closed.
 pub struct PackageSender(pub(crate) Sender<OutPackage>);
 
 impl Deref for PackageSender {
@@ -310,15 +478,34 @@ impl Deref for ConnErrorReceiver {
     }
 }
 
+/// Channel into networking stack tasks, used for receiving of periodic
+/// connection statistics reports.
+///
+/// A report for each active connection is delivered approximately once every
+/// second. Reports which are not received in time are dropped, thus the
+/// non-receiving of reports does not block the networking stack.
+///
+/// If the statistics are not needed, this channel can be safely dropped.
+pub struct ConnStatsReceiver(pub(crate) Receiver<ConnectionStats>);
+
+impl Deref for ConnStatsReceiver {
+    type Target = Receiver<ConnectionStats>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use bincode::Decode;
+    use serde::Deserialize;
 
     use super::*;
+    use crate::compression::decompress;
 
     #[test]
     fn test_out_message_builder() {
-        #[derive(bincode::Encode)]
+        #[derive(Serialize)]
         struct TestData {
             values: [u64; 16], // up to 128 bytes
         }
@@ -327,7 +514,8 @@ mod tests {
             true,
             Peers::Players,
             "127.0.0.1:1111".parse::<SocketAddr>().unwrap(),
-        );
+        )
+        .with_compression();
 
         for i in 0..10 {
             builder
@@ -340,23 +528,30 @@ mod tests {
 
         let packages = builder.build();
         assert_eq!(packages.len(), 4);
+        // The repetitive data are compressed.
+        assert!(packages.iter().all(|package| package.compressed()));
+        let lens: Vec<usize> = packages
+            .iter()
+            .map(|package| decompress(&package.data).unwrap().len())
+            .collect();
+
         // 3 items + something extra for the encoding
-        assert!(packages[0].data.len() >= 128 * 3);
+        assert!(lens[0] >= 128 * 3);
         // less then 4 items
-        assert!(packages[0].data.len() < 128 * 4);
+        assert!(lens[0] < 128 * 4);
 
-        assert!(packages[1].data.len() >= 128 * 3);
-        assert!(packages[1].data.len() < 128 * 4);
-        assert!(packages[2].data.len() >= 128 * 3);
-        assert!(packages[2].data.len() < 128 * 4);
+        assert!(lens[1] >= 128 * 3);
+        assert!(lens[1] < 128 * 4);
+        assert!(lens[2] >= 128 * 3);
+        assert!(lens[2] < 128 * 4);
         // last one contains only one leftover item
-        assert!(packages[3].data.len() >= 128);
-        assert!(packages[3].data.len() < 128 * 2);
+        assert!(lens[3] >= 128);
+        assert!(lens[3] < 128 * 2);
     }
 
     #[test]
     fn test_decoding() {
-        #[derive(Decode, Debug, Eq, PartialEq)]
+        #[derive(Deserialize, Debug, Eq, PartialEq)]
         enum Message {
             One(u16),
             Two([u32; 2]),
@@ -367,6 +562,7 @@ mod tests {
             data: vec![1, 3, 4, 0, 251, 5, 6],
             reliable: false,
             peers: Peers::Players,
+            token: None,
             source: "127.0.0.1:1111".parse().unwrap(),
             time: Instant::now(),
         };
@@ -3,20 +3,23 @@ use std::time::Instant;
 use async_std::{channel::Sender, task};
 use tracing::{error, info};
 
-use super::{cancellation::CancellationRecv, dsender::OutDatagram};
-use crate::connection::Confirmations;
+use super::{cancellation::CancellationRecv, communicator::ConnectionStats, dsender::OutDatagram};
+use crate::connection::{Confirmations, Stats};
 
-/// Scheduler of datagram confirmations.
+/// Scheduler of datagram confirmations and of connection statistics reports.
 pub(super) async fn run(
     port: u16,
     cancellation: CancellationRecv,
     mut datagrams: Sender<OutDatagram>,
+    reports: Sender<ConnectionStats>,
     mut confirms: Confirmations,
+    mut stats: Stats,
 ) {
     info!("Starting confirmer on port {port}...");
 
     loop {
         confirms.clean(Instant::now()).await;
+        stats.clean(Instant::now()).await;
 
         let Ok(next) = confirms
             .send_confirms(Instant::now(), cancellation.cancelled(), &mut datagrams)
@@ -30,6 +33,8 @@ pub(super) async fn run(
             break;
         }
 
+        let next = next.min(stats.report(Instant::now(), &reports).await);
+
         let now = Instant::now();
         if next > now {
             task::sleep(next - now).await;
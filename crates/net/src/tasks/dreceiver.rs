@@ -1,9 +1,15 @@
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
 use async_std::{channel::Sender, future::timeout};
 use tracing::{error, info, warn};
 
+use super::communicator::{ConnErrorKind, ConnectionError};
 use crate::{
+    batch::BatchReader,
+    connection::Liveness,
     header::{DatagramHeader, PackageHeader},
     protocol::{MsgRecvError, ProtocolSocket},
     MAX_DATAGRAM_SIZE,
@@ -28,13 +34,23 @@ pub(super) async fn run(
     port: u16,
     system_datagrams: Sender<InSystemDatagram>,
     package_datagrams: Sender<InPackageDatagram>,
+    errors: Sender<ConnectionError>,
     socket: ProtocolSocket,
+    liveness: Liveness,
 ) {
     info!("Starting datagram receiver on port {port}...");
     let mut buffer = [0u8; MAX_DATAGRAM_SIZE];
+    let mut handler = Handler {
+        port,
+        socket: socket.clone(),
+        system_datagrams,
+        package_datagrams,
+        errors,
+        liveness,
+    };
 
     loop {
-        if package_datagrams.is_closed() && system_datagrams.is_closed() {
+        if handler.package_datagrams.is_closed() && handler.system_datagrams.is_closed() {
             break;
         }
 
@@ -54,19 +70,81 @@ pub(super) async fn run(
             }
         };
 
-        // Closed channel(s) are handled at the top part of the loop,
+        let time = Instant::now();
+        if header == DatagramHeader::Batch {
+            for entry in BatchReader::new(data) {
+                match entry {
+                    Ok((header, data)) => handler.handle(time, addr, header, data).await,
+                    Err(err) => {
+                        warn!("Invalid batch datagram received on port {port}: {err:?}");
+                        break;
+                    }
+                }
+            }
+        } else {
+            handler.handle(time, addr, header, data).await;
+        }
+    }
+
+    info!("Datagram receiver on port {port} finished.");
+}
+
+struct Handler {
+    port: u16,
+    socket: ProtocolSocket,
+    system_datagrams: Sender<InSystemDatagram>,
+    package_datagrams: Sender<InPackageDatagram>,
+    errors: Sender<ConnectionError>,
+    liveness: Liveness,
+}
+
+impl Handler {
+    /// Handles a single (non-batch) datagram.
+    async fn handle(
+        &mut self,
+        time: Instant,
+        addr: SocketAddr,
+        header: DatagramHeader,
+        data: &[u8],
+    ) {
+        // Closed channel(s) are handled at the top part of the main loop,
         // therefore errors from .send() are not treated below.
         match header {
             DatagramHeader::Confirmation => {
-                let _ = system_datagrams
+                self.liveness.received_control(time, addr).await;
+                let _ = self
+                    .system_datagrams
                     .send(InSystemDatagram {
                         source: addr,
                         data: data.to_vec(),
                     })
                     .await;
             }
+            DatagramHeader::KeepAlive => {
+                self.liveness.received_control(time, addr).await;
+            }
+            DatagramHeader::Goodbye => {
+                self.socket.forget(addr).await;
+                if self.liveness.received_goodbye(addr).await {
+                    info!("Peer {addr:?} on port {} closed the connection.", self.port);
+                    if !self.errors.is_closed() {
+                        let _ = self
+                            .errors
+                            .send(ConnectionError::new(addr, ConnErrorKind::PeerLeft))
+                            .await;
+                    }
+                }
+            }
+            DatagramHeader::Batch | DatagramHeader::Hello | DatagramHeader::Encrypted => {
+                warn!(
+                    "Unexpected nested {header} datagram received on port {} from {addr:?}.",
+                    self.port
+                );
+            }
             DatagramHeader::Package(package_header) => {
-                let _ = package_datagrams
+                self.liveness.received_package(time, addr).await;
+                let _ = self
+                    .package_datagrams
                     .send(InPackageDatagram {
                         source: addr,
                         header: package_header,
@@ -74,8 +152,6 @@ pub(super) async fn run(
                     })
                     .await;
             }
-        };
+        }
     }
-
-    info!("Datagram receiver on port {port} finished.");
 }
@@ -1,16 +1,35 @@
-use async_std::channel::Receiver;
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use ahash::AHashMap;
+use async_std::{channel::Receiver, task};
 use tracing::{error, info};
 
 use crate::{
-    header::DatagramHeader,
+    batch::{BatchBuilder, BatchReader},
+    header::{DatagramHeader, HEADER_SIZE},
     protocol::{ProtocolSocket, Targets},
-    MAX_DATAGRAM_SIZE,
+    Priority, SendError, MAX_DATAGRAM_SIZE,
 };
 
+/// Maximum number of queued datagrams which are coalesced at once.
+const MAX_COALESCED: usize = 64;
+/// No more datagrams are taken from the input channel while this many
+/// datagrams are waiting to be sent.
+const MAX_QUEUED: usize = 1024;
+/// Maximum burst of outgoing data as a fraction of bandwidth limit (i.e.
+/// duration of the burst).
+const MAX_BURST: Duration = Duration::from_millis(100);
+
 pub(crate) struct OutDatagram {
     header: DatagramHeader,
     data: Vec<u8>,
     targets: Targets<'static>,
+    /// None for protocol control datagrams.
+    priority: Option<Priority>,
 }
 
 impl OutDatagram {
@@ -23,27 +42,86 @@ impl OutDatagram {
             header,
             data,
             targets: targets.into(),
+            priority: None,
         }
     }
+
+    /// Sets priority of a package datagram. Datagrams without a priority are
+    /// sent before all other datagrams.
+    pub(crate) fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Number of bytes sent over the network.
+    fn cost(&self) -> usize {
+        (HEADER_SIZE + self.data.len()) * (&self.targets).into_iter().count()
+    }
 }
 
-pub(super) async fn run(port: u16, datagrams: Receiver<OutDatagram>, socket: ProtocolSocket) {
+/// Handler of output datagrams.
+///
+/// Datagrams are queued and sent in the order of their priority. Protocol
+/// control datagrams are sent first, then packages in the order of
+/// [`Priority`]. If `bandwidth_limit` (in bytes per second) is set, datagrams
+/// wait in the queues until the bandwidth budget allows to send them.
+///
+/// Datagrams which are already queued when a datagram is about to be sent are
+/// coalesced, i.e. small datagrams destined to the same target are merged into
+/// a single batch datagram. Datagrams are never delayed for the purpose of
+/// coalescing.
+pub(super) async fn run(
+    port: u16,
+    datagrams: Receiver<OutDatagram>,
+    socket: ProtocolSocket,
+    bandwidth_limit: Option<u32>,
+) {
     info!("Starting datagram sender on port {port}...");
     let mut buffer = [0u8; MAX_DATAGRAM_SIZE];
+    let mut queues = Queues::new();
+    let mut budget = bandwidth_limit.map(|limit| Budget::new(limit, Instant::now()));
+    let mut pending = Vec::with_capacity(MAX_COALESCED);
+    let mut batches = AHashMap::new();
 
     loop {
-        let Ok(datagram) = datagrams.recv().await else {
-            break;
-        };
-        if let Err(err) = socket
-            .send(
-                &mut buffer,
-                datagram.header,
-                &datagram.data,
-                datagram.targets,
-            )
-            .await
-        {
+        if queues.is_empty() {
+            let Ok(datagram) = datagrams.recv().await else {
+                break;
+            };
+            queues.push(datagram);
+        }
+        while queues.len() < MAX_QUEUED {
+            let Ok(datagram) = datagrams.try_recv() else {
+                break;
+            };
+            queues.push(datagram);
+        }
+
+        if let Some(budget) = budget.as_mut() {
+            budget.refill(Instant::now());
+        }
+
+        while pending.len() < MAX_COALESCED {
+            let Some(datagram) = queues.pop() else {
+                break;
+            };
+            if let Some(budget) = budget.as_mut() {
+                if !budget.consume(datagram.cost()) {
+                    queues.push_front(datagram);
+                    break;
+                }
+            }
+            pending.push(datagram);
+        }
+
+        if pending.is_empty() {
+            if let Some(budget) = budget.as_ref() {
+                task::sleep(budget.wait()).await;
+            }
+            continue;
+        }
+
+        if let Err(err) = send(&socket, &mut buffer, &mut pending, &mut batches).await {
             error!("Error while sending a datagram: {err:?}");
             break;
         }
@@ -51,3 +129,243 @@ pub(super) async fn run(port: u16, datagrams: Receiver<OutDatagram>, socket: Pro
 
     info!("Datagram sender on port {port} finished.");
 }
+
+/// FIFO queues of datagrams, one for each priority.
+struct Queues {
+    /// Protocol control datagrams are at index 0, packages follow in the
+    /// order of their priority.
+    queues: [VecDeque<OutDatagram>; 4],
+    len: usize,
+}
+
+impl Queues {
+    fn new() -> Self {
+        Self {
+            queues: Default::default(),
+            len: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Pushes a datagram to the back of its queue.
+    fn push(&mut self, datagram: OutDatagram) {
+        self.len += 1;
+        self.queues[Self::index(datagram.priority)].push_back(datagram);
+    }
+
+    /// Returns a datagram to the front of its queue.
+    fn push_front(&mut self, datagram: OutDatagram) {
+        self.len += 1;
+        self.queues[Self::index(datagram.priority)].push_front(datagram);
+    }
+
+    /// Removes and returns the oldest datagram with the highest priority.
+    fn pop(&mut self) -> Option<OutDatagram> {
+        let datagram = self.queues.iter_mut().find_map(|queue| queue.pop_front());
+        if datagram.is_some() {
+            self.len -= 1;
+        }
+        datagram
+    }
+
+    fn index(priority: Option<Priority>) -> usize {
+        match priority {
+            None => 0,
+            Some(Priority::Command) => 1,
+            Some(Priority::StateSync) => 2,
+            Some(Priority::Chat) => 3,
+        }
+    }
+}
+
+/// Token bucket limiting outgoing bandwidth.
+///
+/// A datagram may be sent whenever the budget is positive, even if the
+/// datagram is larger than the budget. Thus arbitrarily large datagrams are
+/// eventually sent and the budget is in debt afterwards.
+struct Budget {
+    /// Bytes per second.
+    rate: f64,
+    /// Maximum budget in bytes.
+    capacity: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl Budget {
+    fn new(rate: u32, now: Instant) -> Self {
+        let rate = rate as f64;
+        let capacity = (rate * MAX_BURST.as_secs_f64()).max(MAX_DATAGRAM_SIZE as f64);
+        Self {
+            rate,
+            capacity,
+            available: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = self.capacity.min(self.available + elapsed * self.rate);
+        self.updated = now;
+    }
+
+    /// Consumes `bytes` from the budget and returns true if the budget is
+    /// positive. Otherwise, it returns false and keeps the budget intact.
+    fn consume(&mut self, bytes: usize) -> bool {
+        if self.available > 0. {
+            self.available -= bytes as f64;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns time needed for the budget to become positive.
+    fn wait(&self) -> Duration {
+        // Make sure that the budget is positive (not zero) after the wait.
+        let missing = 1. - self.available.min(0.);
+        Duration::from_secs_f64(missing / self.rate)
+    }
+}
+
+/// Sends all `pending` datagrams (and empties the vector), possibly coalesced
+/// into batches.
+async fn send(
+    socket: &ProtocolSocket,
+    buf: &mut [u8],
+    pending: &mut Vec<OutDatagram>,
+    batches: &mut AHashMap<SocketAddr, BatchBuilder>,
+) -> Result<(), SendError> {
+    if pending.len() == 1 {
+        let datagram = pending.pop().unwrap();
+        return socket
+            .send(buf, datagram.header, &datagram.data, datagram.targets)
+            .await;
+    }
+
+    for datagram in pending.drain(..) {
+        if !BatchBuilder::fits_empty(datagram.data.len()) {
+            // Preserve ordering of datagrams to each target.
+            for target in &datagram.targets {
+                if let Some(batch) = batches.get_mut(&target) {
+                    flush(socket, buf, target, batch).await?;
+                }
+            }
+            socket
+                .send(buf, datagram.header, &datagram.data, datagram.targets)
+                .await?;
+            continue;
+        }
+
+        for target in &datagram.targets {
+            let batch = batches.entry(target).or_insert_with(BatchBuilder::new);
+            if !batch.push(datagram.header, &datagram.data) {
+                flush(socket, buf, target, batch).await?;
+                let pushed = batch.push(datagram.header, &datagram.data);
+                debug_assert!(pushed);
+            }
+        }
+    }
+
+    for (&target, batch) in batches.iter_mut() {
+        flush(socket, buf, target, batch).await?;
+    }
+    batches.clear();
+
+    Ok(())
+}
+
+/// Sends all datagrams from a batch and empties the batch. A batch with a
+/// single datagram is sent as an ordinary datagram.
+async fn flush(
+    socket: &ProtocolSocket,
+    buf: &mut [u8],
+    target: SocketAddr,
+    batch: &mut BatchBuilder,
+) -> Result<(), SendError> {
+    let len = batch.len();
+    let payload = batch.take();
+
+    match len {
+        0 => Ok(()),
+        1 => {
+            let (header, data) = BatchReader::new(&payload).next().unwrap().unwrap();
+            socket.send(buf, header, data, target).await
+        }
+        _ => {
+            socket
+                .send(buf, DatagramHeader::Batch, &payload, target)
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queues() {
+        let datagram = |id: u8, priority: Option<Priority>| {
+            let datagram = OutDatagram::new(
+                DatagramHeader::KeepAlive,
+                vec![id],
+                "127.0.0.1:1000".parse::<SocketAddr>().unwrap(),
+            );
+            match priority {
+                Some(priority) => datagram.with_priority(priority),
+                None => datagram,
+            }
+        };
+
+        let mut queues = Queues::new();
+        assert!(queues.is_empty());
+        assert!(queues.pop().is_none());
+
+        queues.push(datagram(1, Some(Priority::Chat)));
+        queues.push(datagram(2, Some(Priority::Command)));
+        queues.push(datagram(3, Some(Priority::StateSync)));
+        queues.push(datagram(4, None));
+        queues.push(datagram(5, Some(Priority::Command)));
+        assert_eq!(queues.len(), 5);
+
+        let first = queues.pop().unwrap();
+        assert_eq!(first.data, [4]);
+        queues.push_front(first);
+
+        let order: Vec<u8> = std::iter::from_fn(|| queues.pop())
+            .map(|datagram| datagram.data[0])
+            .collect();
+        assert_eq!(order, [4, 2, 5, 3, 1]);
+        assert!(queues.is_empty());
+    }
+
+    #[test]
+    fn test_budget() {
+        let now = Instant::now();
+        let mut budget = Budget::new(10_000, now);
+
+        // Initial burst of 100 ms.
+        assert!(budget.consume(600));
+        assert!(budget.consume(600));
+        assert!(!budget.consume(1));
+        assert_eq!(budget.wait().as_millis(), 20);
+
+        budget.refill(now + Duration::from_millis(10));
+        assert!(!budget.consume(1));
+        budget.refill(now + Duration::from_millis(30));
+        assert!(budget.consume(1));
+
+        budget.refill(now + Duration::from_secs(10));
+        assert!(budget.consume(1000));
+        assert!(!budget.consume(1));
+    }
+}
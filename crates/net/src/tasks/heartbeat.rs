@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+use async_std::{channel::Sender, task};
+use tracing::{error, info, warn};
+
+use super::{
+    cancellation::CancellationRecv,
+    communicator::{ConnErrorKind, ConnectionError},
+    dsender::OutDatagram,
+};
+use crate::{connection::Liveness, NetConf};
+
+/// Sender of keep-alive datagrams and detector of disconnected peers.
+pub(super) async fn run(
+    port: u16,
+    conf: NetConf,
+    cancellation: CancellationRecv,
+    mut datagrams: Sender<OutDatagram>,
+    errors: Sender<ConnectionError>,
+    mut liveness: Liveness,
+) {
+    info!("Starting heartbeat on port {port}...");
+
+    loop {
+        if cancellation.cancelled() {
+            break;
+        }
+
+        liveness.clean(Instant::now()).await;
+
+        let Ok(result) = liveness
+            .beat(Instant::now(), conf.peer_timeout(), &mut datagrams)
+            .await
+        else {
+            error!("Datagram sender channel on port {port} is unexpectedly closed.");
+            break;
+        };
+
+        for target in result.disconnected {
+            warn!("Peer {target:?} on port {port} timed out.");
+
+            if errors.is_closed() {
+                continue;
+            }
+            let error = ConnectionError::new(target, ConnErrorKind::PeerDisconnected);
+            if errors.send(error).await.is_err() {
+                break;
+            }
+        }
+
+        let now = Instant::now();
+        if result.next > now {
+            task::sleep(result.next - now).await;
+        }
+    }
+
+    info!("Heartbeat on port {port} finished.");
+}
@@ -16,10 +16,16 @@
 //! |   dsender   | <-----+ |   resender  |
 //! |             |         |             | * * * *
 //! +-------------+         +-------------+       *
-//!           ^                                   *
-//!           |             +-------------+       *
-//!           |             |             |       *
-//!           +-----------+ |  confirmer  | < *   *
+//!        ^  ^                                   *
+//!        |  |             +-------------+       *
+//!        |  |             |             |       *
+//!        |  +-----------+ |  confirmer  | < *   *
+//!        |                |             |   *   *
+//!        |                +-------------+   *   *
+//!        |                                  *   *
+//!        |                +-------------+   *   *
+//!        |                |             |   *   *
+//!        +--------------+ |  heartbeat  | < *   *
 //!                         |             |   *   *
 //!                         +-------------+   *   *
 //!                                           *   *
@@ -38,6 +44,13 @@
 //!
 //! `dsender` and `dreceiver` are responsible for sending and receiving UDP
 //! datagrams. Both are terminated soon after all their channels are closed.
+//! `dsender` coalesces small queued datagrams destined to the same target into
+//! batch datagrams, `dreceiver` splits them back. Both transparently encrypt
+//! and decrypt datagrams once an encryption handshake with the peer is
+//! finished. `dsender` sends queued datagrams in the order of their priority
+//! and optionally limits the outgoing bandwidth.
+//! `dreceiver` reports peers which gracefully closed the connection via
+//! [`ConnErrorReceiver`].
 //!
 //! `resender` is responsible for redelivery of reliably sent datagrams whose
 //! confirmation was not received within a time limit. If all attempts fail,
@@ -47,28 +60,38 @@
 //! These include delivery confirmations.
 //!
 //! `confirmer` is responsible for sending of datagram delivery confirmations.
+//! It also periodically reports connection statistics (gathered by
+//! `usender`, `ureceiver`, `resender`, and `sreceiver`) via
+//! [`ConnStatsReceiver`].
+//!
+//! `heartbeat` is responsible for sending of keep-alive datagrams and for
+//! detection of disconnected peers. Peer disconnections are reported via
+//! [`ConnErrorReceiver`].
 //!
-//! `resender`, `sreceiver`, and `confirmer` are terminated soon after their
-//! cancellation token is canceled.
+//! `resender`, `sreceiver`, `confirmer`, and `heartbeat` are terminated soon
+//! after their cancellation token is canceled.
 //!
 //! `usender` and `ureceiver` are responsible for sending and reception of user
 //! data. The user communicates with these via [`PackageSender`] and
-//! [`PackageReceiver`] respectively.
+//! [`PackageReceiver`] respectively. Once [`PackageSender`] is closed,
+//! `usender` sends goodbye datagrams to all peers with which packages were
+//! exchanged.
 
 use async_std::channel::bounded;
 pub use communicator::{
-    ConnErrorReceiver, ConnectionError, InPackage, MessageDecoder, OutPackage, PackageBuilder,
-    PackageReceiver, PackageSender,
+    ConnErrorKind, ConnErrorReceiver, ConnStatsReceiver, ConnectionError, ConnectionStats,
+    InPackage, MessageDecoder, OutPackage, PackageBuilder, PackageReceiver, PackageSender,
+    Priority,
 };
 pub(crate) use dsender::OutDatagram;
 use futures::future::BoxFuture;
 use tracing::info;
 
 use crate::{
-    connection::{Confirmations, Resends},
+    connection::{Confirmations, Liveness, Resends, Stats},
     protocol::ProtocolSocket,
     tasks::cancellation::cancellation,
-    Socket,
+    NetConf, Socket,
 };
 
 mod cancellation;
@@ -76,6 +99,7 @@ mod communicator;
 mod confirmer;
 mod dreceiver;
 mod dsender;
+mod heartbeat;
 mod resender;
 mod sreceiver;
 mod ureceiver;
@@ -84,80 +108,115 @@ mod usender;
 const CHANNEL_CAPACITY: usize = 1024;
 
 /// Setups and starts communication stack tasks and returns communication
-/// channels for data sending, data retrieval, and error retrieval.
+/// channels for data sending, data retrieval, error retrieval, and
+/// connection statistics retrieval.
 ///
 /// All tasks in the network stack keep running until the returned channels are
-/// closed. Once the [`PackageSender`], [`PackageReceiver`], and
-/// [`ConnErrorReceiver`] are all dropped, the networking stack will terminate
-/// completely.
+/// closed. Once the [`PackageSender`], [`PackageReceiver`],
+/// [`ConnErrorReceiver`], and [`ConnStatsReceiver`] are all dropped, the
+/// networking stack will terminate completely.
 ///
 /// # Arguments
 ///
 /// * `spawn` - async task spawner.
 ///
 /// * `socket` - network communication will happen over this socket.
-pub fn startup<S>(spawn: S, socket: Socket) -> (PackageSender, PackageReceiver, ConnErrorReceiver)
+///
+/// * `conf` - configuration of the networking stack.
+pub fn startup<S>(
+    spawn: S,
+    socket: Socket,
+    conf: NetConf,
+) -> (
+    PackageSender,
+    PackageReceiver,
+    ConnErrorReceiver,
+    ConnStatsReceiver,
+)
 where
     S: Fn(BoxFuture<'static, ()>),
 {
     let port = socket.port();
     info!("Starting up network stack on port {port}...");
 
-    let protocol_socket = ProtocolSocket::new(socket);
+    let protocol_socket =
+        ProtocolSocket::new(socket, conf.metrics().clone(), conf.encryption().clone());
 
     let (out_datagrams_sender, out_datagrams_receiver) = bounded(16);
     spawn(Box::pin(dsender::run(
         port,
         out_datagrams_receiver,
         protocol_socket.clone(),
+        conf.bandwidth_limit(),
     )));
 
+    let liveness = Liveness::new();
+    let (errors_sender, errors_receiver) = bounded(CHANNEL_CAPACITY);
     let (in_system_datagrams_sender, in_system_datagrams_receiver) = bounded(16);
     let (in_user_datagrams_sender, in_user_datagrams_receiver) = bounded(16);
     spawn(Box::pin(dreceiver::run(
         port,
         in_system_datagrams_sender,
         in_user_datagrams_sender,
+        errors_sender.clone(),
         protocol_socket,
+        liveness.clone(),
     )));
 
-    let resends = Resends::new();
+    let resends = Resends::new(conf.resend_policy());
+    let stats = Stats::new(conf.metrics().clone());
     let (sreceiver_cancellation_sender, sreceiver_cancellation_receiver) = cancellation();
     spawn(Box::pin(sreceiver::run(
         port,
         sreceiver_cancellation_receiver,
         in_system_datagrams_receiver,
         resends.clone(),
+        stats.clone(),
     )));
 
     let (inputs_sender, inputs_receiver) = bounded(CHANNEL_CAPACITY);
     let (confirmer_cancellation_sender, confirmer_cancellation_receiver) = cancellation();
+    let (heartbeat_cancellation_sender, heartbeat_cancellation_receiver) = cancellation();
     let confirms = Confirmations::new();
     spawn(Box::pin(ureceiver::run(
         port,
-        confirmer_cancellation_sender,
+        [confirmer_cancellation_sender, heartbeat_cancellation_sender],
         in_user_datagrams_receiver,
         inputs_sender,
         confirms.clone(),
+        conf.tokens().clone(),
+        stats.clone(),
     )));
 
     let (outputs_sender, outputs_receiver) = bounded(CHANNEL_CAPACITY);
-    let (errors_sender, errors_receiver) = bounded(CHANNEL_CAPACITY);
     let (resender_cancellation_sender, resender_cancellation_receiver) = cancellation();
     spawn(Box::pin(resender::run(
         port,
         resender_cancellation_receiver,
         sreceiver_cancellation_sender,
         out_datagrams_sender.clone(),
-        errors_sender,
+        errors_sender.clone(),
         resends.clone(),
+        stats.clone(),
+    )));
+
+    spawn(Box::pin(heartbeat::run(
+        port,
+        conf,
+        heartbeat_cancellation_receiver,
+        out_datagrams_sender.clone(),
+        errors_sender,
+        liveness.clone(),
     )));
 
+    let (stats_sender, stats_receiver) = bounded(CHANNEL_CAPACITY);
     spawn(Box::pin(confirmer::run(
         port,
         confirmer_cancellation_receiver,
         out_datagrams_sender.clone(),
+        stats_sender,
         confirms,
+        stats.clone(),
     )));
     spawn(Box::pin(usender::run(
         port,
@@ -165,11 +224,14 @@ where
         out_datagrams_sender,
         outputs_receiver,
         resends,
+        stats,
+        liveness,
     )));
 
     (
         PackageSender(outputs_sender),
         PackageReceiver(inputs_receiver),
         ConnErrorReceiver(errors_receiver),
+        ConnStatsReceiver(stats_receiver),
     )
 }
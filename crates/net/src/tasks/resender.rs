@@ -5,10 +5,13 @@ use tracing::{error, info};
 
 use super::{
     cancellation::{CancellationRecv, CancellationSender},
-    communicator::ConnectionError,
+    communicator::{ConnErrorKind, ConnectionError},
     dsender::OutDatagram,
 };
-use crate::{connection::Resends, MAX_DATAGRAM_SIZE};
+use crate::{
+    connection::{Resends, Stats},
+    MAX_DATAGRAM_SIZE,
+};
 
 const CANCELLATION_DEADLINE: Duration = Duration::from_secs(5);
 
@@ -20,6 +23,7 @@ pub(super) async fn run(
     mut datagrams: Sender<OutDatagram>,
     errors: Sender<ConnectionError>,
     mut resends: Resends,
+    mut stats: Stats,
 ) {
     info!("Starting resender on port {port}...");
 
@@ -34,7 +38,7 @@ pub(super) async fn run(
         resends.clean(Instant::now()).await;
 
         let Ok(resend_result) = resends
-            .resend(Instant::now(), &mut buf, &mut datagrams)
+            .resend(Instant::now(), &mut buf, &mut datagrams, &mut stats)
             .await
         else {
             error!("Datagram sender channel on port {port} is unexpectedly closed.");
@@ -43,7 +47,9 @@ pub(super) async fn run(
 
         if !errors.is_closed() {
             'failures: for target in resend_result.failures {
-                let result = errors.send(ConnectionError::new(target)).await;
+                let result = errors
+                    .send(ConnectionError::new(target, ConnErrorKind::Undelivered))
+                    .await;
                 if result.is_err() {
                     if cancellation_recv.cancelled() {
                         break 'main;
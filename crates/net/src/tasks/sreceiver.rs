@@ -4,7 +4,7 @@ use async_std::{channel::Receiver, future::timeout};
 use tracing::{error, info};
 
 use super::{cancellation::CancellationRecv, dreceiver::InSystemDatagram};
-use crate::connection::Resends;
+use crate::connection::{Resends, Stats};
 
 /// Handler of protocol control datagrams.
 ///
@@ -14,9 +14,12 @@ pub(super) async fn run(
     cancellation: CancellationRecv,
     datagrams: Receiver<InSystemDatagram>,
     mut resends: Resends,
+    mut stats: Stats,
 ) {
     info!("Starting protocol control datagram receiver on port {port}...");
 
+    let mut rtts = Vec::new();
+
     loop {
         if cancellation.cancelled() {
             break;
@@ -31,9 +34,14 @@ pub(super) async fn run(
             break;
         };
 
+        let time = Instant::now();
+        rtts.clear();
         resends
-            .confirmed(Instant::now(), datagram.source, &datagram.data)
+            .confirmed(time, datagram.source, &datagram.data, &mut rtts)
             .await;
+        for &rtt in &rtts {
+            stats.rtt(time, datagram.source, rtt).await;
+        }
     }
 
     info!("Protocol control datagram receiver on port {port} finished.");
@@ -7,19 +7,32 @@ use async_std::{
 use tracing::{error, info, trace, warn};
 
 use super::{cancellation::CancellationSender, dreceiver::InPackageDatagram};
-use crate::{connection::Confirmations, InPackage};
+use crate::{
+    compression::decompress,
+    connection::{Confirmations, Stats},
+    token::{Token, Tokens, TOKEN_SIZE},
+    InPackage,
+};
 
 /// Handler of user datagrams, i.e. datagrams with user data targeted to
 /// higher-level users of the network protocol.
 ///
+/// Packages are confirmed (and marked as received) only once their token is
+/// verified and their payload is decompressed. Thus packages spoofed by
+/// somebody else or packages which cannot be processed are re-sent by their
+/// sender rather than silently lost.
+///
 /// The handler runs a loop which finishes when `datagrams` or `packages`
-/// channel is closed.
+/// channel is closed. Receivers of `_cancellations` (of the confirmer and the
+/// heartbeat tasks) are cancelled once the loop finishes.
 pub(super) async fn run(
     port: u16,
-    _cancellation: CancellationSender,
+    _cancellations: [CancellationSender; 2],
     datagrams: Receiver<InPackageDatagram>,
     packages: Sender<InPackage>,
     mut confirms: Confirmations,
+    tokens: Tokens,
+    mut stats: Stats,
 ) {
     info!("Starting package receiver on port {port}...");
 
@@ -47,6 +60,58 @@ pub(super) async fn run(
         };
 
         let time = Instant::now();
+        stats
+            .received(time, datagram.source, datagram.data.len())
+            .await;
+
+        let (token, data) = if datagram.header.has_token() {
+            if datagram.data.len() < TOKEN_SIZE {
+                warn!(
+                    "Package with a truncated token received from {:?}.",
+                    datagram.source
+                );
+                continue;
+            }
+            let token = Token::from_bytes(datagram.data[..TOKEN_SIZE].try_into().unwrap());
+            (Some(token), datagram.data[TOKEN_SIZE..].to_vec())
+        } else {
+            (None, datagram.data)
+        };
+
+        if !tokens.verify(datagram.source, token).await {
+            // Re-delivery of an already received package (sent before the
+            // token was issued) is confirmed again so that the sender stops
+            // re-sending it.
+            if datagram.header.reliable()
+                && confirms
+                    .received_duplicate(time, datagram.source, datagram.header.id())
+                    .await
+            {
+                continue;
+            }
+
+            warn!(
+                "Package with an invalid token received from {:?}.",
+                datagram.source
+            );
+            continue;
+        }
+
+        let data = if datagram.header.compressed() {
+            match decompress(&data) {
+                Ok(data) => data,
+                Err(err) => {
+                    warn!(
+                        "Invalid compressed package received from {:?}: {err:?}",
+                        datagram.source
+                    );
+                    continue;
+                }
+            }
+        } else {
+            data
+        };
+
         if datagram.header.reliable() {
             match confirms
                 .received(time, datagram.source, datagram.header.id())
@@ -69,9 +134,10 @@ pub(super) async fn run(
 
         let result = packages
             .send(InPackage::new(
-                datagram.data,
+                data,
                 datagram.header.reliable(),
                 datagram.header.peers(),
+                token,
                 datagram.source,
                 time,
             ))
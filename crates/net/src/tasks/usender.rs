@@ -1,12 +1,13 @@
-use std::time::Instant;
+use std::{mem, time::Instant};
 
 use async_std::channel::{Receiver, Sender};
 use tracing::{error, info};
 
 use super::{cancellation::CancellationSender, dsender::OutDatagram};
 use crate::{
-    connection::Resends,
+    connection::{Delivery, Liveness, Resends, Stats},
     header::{DatagramHeader, PackageIdRange},
+    token::TOKEN_SIZE,
     OutPackage,
 };
 
@@ -14,9 +15,11 @@ use crate::{
 pub(super) async fn run(
     port: u16,
     _cancellation: CancellationSender,
-    datagrams: Sender<OutDatagram>,
+    mut datagrams: Sender<OutDatagram>,
     packages: Receiver<OutPackage>,
     mut resends: Resends,
+    mut stats: Stats,
+    mut liveness: Liveness,
 ) {
     info!("Starting package sender on port {port}...");
 
@@ -24,7 +27,7 @@ pub(super) async fn run(
     let mut counter_unreliable = PackageIdRange::counter();
 
     loop {
-        let Ok(package) = packages.recv().await else {
+        let Ok(mut package) = packages.recv().await else {
             break;
         };
 
@@ -34,35 +37,60 @@ pub(super) async fn run(
             counter_unreliable.next().unwrap()
         };
 
-        let header = DatagramHeader::new_package(package.reliable(), package.peers(), package_id);
+        let header = DatagramHeader::new_package(
+            package.reliable(),
+            package.peers(),
+            package.compressed(),
+            package.token().is_some(),
+            package_id,
+        );
+
+        let data = match package.token() {
+            Some(token) => {
+                let mut data = Vec::with_capacity(TOKEN_SIZE + package.data.len());
+                data.extend_from_slice(&token.to_bytes());
+                data.extend_from_slice(&package.data);
+                data
+            }
+            None => mem::take(&mut package.data),
+        };
+
+        let time = Instant::now();
+        let delivery = if package.reliable() {
+            Delivery::Reliable
+        } else {
+            Delivery::Unreliable
+        };
+        for target in &package.targets {
+            stats.sent(time, target, delivery, data.len()).await;
+            liveness.sent(time, target).await;
+        }
 
         if let DatagramHeader::Package(package_header) = header {
             if package_header.reliable() {
-                let time = Instant::now();
                 for target in &package.targets {
                     resends
-                        .sent(
-                            time,
-                            target,
-                            package_header.id(),
-                            package_header.peers(),
-                            &package.data,
-                        )
+                        .sent(time, target, package_header, package.priority(), &data)
                         .await;
                 }
             }
         }
 
+        let priority = package.priority();
         let closed = datagrams
-            .send(OutDatagram::new(header, package.data, package.targets))
+            .send(OutDatagram::new(header, data, package.targets).with_priority(priority))
             .await
             .is_err();
 
         if closed {
-            error!("Datagram sender channel on port {port} is unexpectedly closed. ");
+            error!("Datagram sender channel on port {port} is unexpectedly closed.");
             break;
         }
     }
 
+    if !datagrams.is_closed() && liveness.goodbye(&mut datagrams).await.is_err() {
+        error!("Datagram sender channel on port {port} is unexpectedly closed.");
+    }
+
     info!("Package sender on port {port} finished.");
 }
@@ -0,0 +1,113 @@
+use std::{fmt, net::SocketAddr};
+
+use ahash::AHashMap;
+use async_std::sync::{Arc, RwLock};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+/// Number of bytes of an encoded token.
+pub(crate) const TOKEN_SIZE: usize = 8;
+
+/// Random secret issued by a server to a client. The client includes it in
+/// packages so that the server can verify that the packages are not sent by
+/// somebody else with a spoofed source address.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Token(u64);
+
+impl Token {
+    /// Generates a new cryptographically secure random token.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system random number generator fails.
+    pub fn random() -> Self {
+        let mut bytes = [0u8; TOKEN_SIZE];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .expect("Random token generation failed.");
+        Self::from_bytes(bytes)
+    }
+
+    pub(crate) fn from_bytes(bytes: [u8; TOKEN_SIZE]) -> Self {
+        Self(u64::from_be_bytes(bytes))
+    }
+
+    pub(crate) fn to_bytes(self) -> [u8; TOKEN_SIZE] {
+        self.0.to_be_bytes()
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Do not leak the secret to logs.
+        write!(f, "Token(..)")
+    }
+}
+
+/// Tokens issued to individual peers. Packages from a peer with an issued
+/// token are dropped by the networking stack, before they are confirmed or
+/// marked as received, unless they carry the token. Packages from other
+/// peers are not checked. See [`crate::NetConf::with_tokens`].
+///
+/// The struct is a cheaply clonable handle, all clones share the tokens.
+#[derive(Clone, Default)]
+pub struct Tokens(Arc<RwLock<AHashMap<SocketAddr, Token>>>);
+
+impl Tokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// From now on, packages from `addr` must carry `token`.
+    pub async fn insert(&self, addr: SocketAddr, token: Token) {
+        self.0.write().await.insert(addr, token);
+    }
+
+    /// Stops checking tokens of packages from `addr`.
+    pub async fn remove(&self, addr: SocketAddr) {
+        self.0.write().await.remove(&addr);
+    }
+
+    /// Returns true if a package from `addr` carrying `token` may be
+    /// accepted.
+    pub(crate) async fn verify(&self, addr: SocketAddr, token: Option<Token>) -> bool {
+        match self.0.read().await.get(&addr) {
+            Some(&expected) => token == Some(expected),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Debug for Tokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tokens").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_std::task;
+
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        task::block_on(async {
+            let first: SocketAddr = "127.0.0.1:1000".parse().unwrap();
+            let second: SocketAddr = "127.0.0.1:1001".parse().unwrap();
+            let token = Token::random();
+
+            let tokens = Tokens::new();
+            assert!(tokens.verify(first, None).await);
+
+            tokens.clone().insert(first, token).await;
+            assert!(tokens.verify(first, Some(token)).await);
+            assert!(!tokens.verify(first, None).await);
+            assert!(!tokens.verify(first, Some(Token::random())).await);
+            assert!(tokens.verify(second, None).await);
+
+            tokens.remove(first).await;
+            assert!(tokens.verify(first, None).await);
+        });
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::{bail, Context};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    ecs::system::SystemParam,
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashMap},
+};
+use de_core::state::AppState;
+use enum_map::{Enum, EnumMap};
+use iyes_progress::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const MATRIX_EXTENSION: [&str; 1] = ["matrix.json"];
+const MATRIX_PATH: &str = "objects/damage.matrix.json";
+
+pub(crate) struct ArmorPlugin;
+
+impl Plugin for ArmorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<DamageMatrix>()
+            .add_asset_loader(DamageMatrixLoader)
+            .add_system(setup.in_schedule(OnEnter(AppState::AppLoading)))
+            .add_system(
+                check_status
+                    .track_progress()
+                    .run_if(in_state(AppState::AppLoading)),
+            );
+    }
+}
+
+/// Type of damage inflicted by a weapon.
+#[derive(Enum, Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    Kinetic,
+    Energy,
+    Explosive,
+}
+
+/// Armor class of an object. The effectiveness of each damage type against
+/// each armor class is given by [`DamageMatrix`].
+#[derive(Enum, Component, Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ArmorClass {
+    Unarmored,
+    Light,
+    Heavy,
+    Structure,
+}
+
+/// Multipliers of damage for all combinations of damage types and armor
+/// classes.
+#[derive(TypeUuid)]
+#[uuid = "2c1c5fd4-8e0e-4a8f-9d86-58d0ec1e2a77"]
+pub struct DamageMatrix(EnumMap<DamageType, EnumMap<ArmorClass, f32>>);
+
+impl DamageMatrix {
+    /// Returns multiplier of damage of a given type inflicted to an object
+    /// with a given armor class.
+    pub fn effectiveness(&self, damage_type: DamageType, armor: ArmorClass) -> f32 {
+        self.0[damage_type][armor]
+    }
+}
+
+impl TryFrom<DamageMatrixSerde> for DamageMatrix {
+    type Error = anyhow::Error;
+
+    fn try_from(matrix_serde: DamageMatrixSerde) -> Result<Self, Self::Error> {
+        let mut matrix: EnumMap<DamageType, EnumMap<ArmorClass, f32>> = EnumMap::default();
+        for (damage_type, row) in matrix.iter_mut() {
+            let row_serde = matrix_serde
+                .get(&damage_type)
+                .with_context(|| format!("Missing damage type {damage_type:?}"))?;
+
+            for (armor, effectiveness) in row.iter_mut() {
+                let value = *row_serde.get(&armor).with_context(|| {
+                    format!("Missing armor class {armor:?} of damage type {damage_type:?}")
+                })?;
+                if !value.is_finite() || value < 0. {
+                    bail!(
+                        "Effectiveness of {damage_type:?} against {armor:?} must be a \
+                         non-negative finite number, got: {value}"
+                    );
+                }
+                *effectiveness = value;
+            }
+        }
+
+        Ok(Self(matrix))
+    }
+}
+
+type DamageMatrixSerde = HashMap<DamageType, HashMap<ArmorClass, f32>>;
+
+struct DamageMatrixLoader;
+
+impl AssetLoader for DamageMatrixLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, anyhow::Result<()>> {
+        Box::pin(async move {
+            let matrix_serde: DamageMatrixSerde =
+                serde_json::from_slice(bytes).context("Failed to parse damage matrix JSON")?;
+            let matrix = DamageMatrix::try_from(matrix_serde)?;
+            load_context.set_default_asset(LoadedAsset::new(matrix));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        MATRIX_EXTENSION.as_slice()
+    }
+}
+
+#[derive(Resource)]
+struct DamageMatrixHandle(Handle<DamageMatrix>);
+
+#[derive(SystemParam)]
+pub struct DamageMatrices<'w> {
+    handle: Res<'w, DamageMatrixHandle>,
+    assets: Res<'w, Assets<DamageMatrix>>,
+}
+
+impl<'w> DamageMatrices<'w> {
+    pub fn get(&self) -> &DamageMatrix {
+        self.assets.get(&self.handle.0).unwrap()
+    }
+}
+
+fn setup(mut commands: Commands, server: Res<AssetServer>) {
+    commands.insert_resource(DamageMatrixHandle(server.load(MATRIX_PATH)));
+}
+
+fn check_status(server: Res<AssetServer>, handle: Res<DamageMatrixHandle>) -> Progress {
+    match server.get_load_state(&handle.0) {
+        LoadState::Failed => panic!("Damage matrix loading failed"),
+        LoadState::Unloaded => panic!("Damage matrix is unexpectedly unloaded"),
+        LoadState::NotLoaded | LoadState::Loading => false.into(),
+        LoadState::Loaded => true.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix() {
+        let json = r#"{
+            "Kinetic": {"Unarmored": 1.0, "Light": 0.8, "Heavy": 0.5, "Structure": 0.3},
+            "Energy": {"Unarmored": 1.0, "Light": 1.0, "Heavy": 0.8, "Structure": 0.6},
+            "Explosive": {"Unarmored": 1.2, "Light": 1.0, "Heavy": 1.0, "Structure": 1.5}
+        }"#;
+        let matrix_serde: DamageMatrixSerde = serde_json::from_str(json).unwrap();
+        let matrix = DamageMatrix::try_from(matrix_serde).unwrap();
+        assert_eq!(
+            matrix.effectiveness(DamageType::Kinetic, ArmorClass::Heavy),
+            0.5
+        );
+        assert_eq!(
+            matrix.effectiveness(DamageType::Explosive, ArmorClass::Structure),
+            1.5
+        );
+
+        let incomplete = r#"{
+            "Kinetic": {"Unarmored": 1.0, "Light": 0.8, "Heavy": 0.5, "Structure": 0.3}
+        }"#;
+        let matrix_serde: DamageMatrixSerde = serde_json::from_str(incomplete).unwrap();
+        assert!(DamageMatrix::try_from(matrix_serde).is_err());
+
+        let negative = r#"{
+            "Kinetic": {"Unarmored": 1.0, "Light": 0.8, "Heavy": 0.5, "Structure": 0.3},
+            "Energy": {"Unarmored": 1.0, "Light": 1.0, "Heavy": 0.8, "Structure": 0.6},
+            "Explosive": {"Unarmored": 1.2, "Light": -1.0, "Heavy": 1.0, "Structure": 1.5}
+        }"#;
+        let matrix_serde: DamageMatrixSerde = serde_json::from_str(negative).unwrap();
+        assert!(DamageMatrix::try_from(matrix_serde).is_err());
+    }
+}
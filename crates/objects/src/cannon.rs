@@ -1,14 +1,20 @@
 use std::{cmp::Ordering, time::Duration};
 
+use anyhow::ensure;
 use bevy::prelude::Component;
 use glam::Vec3;
 use serde::{Deserialize, Serialize};
 
+use crate::{armor::DamageType, carrier::Carrier};
+
 #[derive(Component, Clone)]
 pub struct LaserCannon {
     muzzle: Vec3,
     range: f32,
     damage: f32,
+    damage_type: DamageType,
+    ballistics: Option<Ballistics>,
+    anti_air: bool,
     charge: LaserCharge,
 }
 
@@ -29,6 +35,47 @@ impl LaserCannon {
         self.damage
     }
 
+    /// Type of the inflicted damage. The damage is multiplied by the
+    /// effectiveness of the damage type against armor of the hit object.
+    pub fn damage_type(&self) -> DamageType {
+        self.damage_type
+    }
+
+    /// Returns None for hitscan cannons (lasers). Otherwise, it returns
+    /// parameters of projectiles fired by the cannon.
+    pub fn ballistics(&self) -> Option<&Ballistics> {
+        self.ballistics.as_ref()
+    }
+
+    /// Returns true if the cannon can hit flying objects. Other cannons only
+    /// hit objects on the ground.
+    pub fn anti_air(&self) -> bool {
+        self.anti_air
+    }
+
+    /// Returns a cannon firing on behalf of `count` units carried by a
+    /// garrisoned object, each armed with a cannon like this one. The
+    /// returned cannon is not charged.
+    ///
+    /// # Arguments
+    ///
+    /// * `muzzle` - position of the cannon muzzle relative to the carrier.
+    ///
+    /// * `count` - number of armed carried units.
+    ///
+    /// * `carrier` - the carrier info whose bonuses are applied.
+    pub fn garrisoned(&self, muzzle: Vec3, count: usize, carrier: &Carrier) -> Self {
+        Self {
+            muzzle,
+            range: self.range * carrier.range_multiplier(),
+            damage: self.damage * count as f32 * carrier.damage_multiplier(),
+            damage_type: self.damage_type,
+            ballistics: self.ballistics.clone(),
+            anti_air: self.anti_air,
+            charge: LaserCharge::new(self.charge.charge_time, self.charge.discharge_time),
+        }
+    }
+
     pub fn charge(&self) -> &LaserCharge {
         &self.charge
     }
@@ -151,6 +198,9 @@ impl TryFrom<LaserCannonSerde> for LaserCannon {
             muzzle: Vec3::from_slice(info.muzzle.as_slice()),
             range: info.range,
             damage: info.damage,
+            damage_type: info.damage_type,
+            ballistics: info.ballistics.map(Ballistics::try_from).transpose()?,
+            anti_air: info.anti_air,
             charge: LaserCharge::new(
                 Duration::from_secs_f32(info.charge_time_sec),
                 Duration::from_secs_f32(info.discharge_time_sec),
@@ -164,15 +214,96 @@ pub(crate) struct LaserCannonSerde {
     muzzle: [f32; 3],
     range: f32,
     damage: f32,
+    damage_type: DamageType,
+    ballistics: Option<BallisticsSerde>,
+    anti_air: bool,
     charge_time_sec: f32,
     discharge_time_sec: f32,
 }
 
+/// Parameters of projectiles fired by a cannon.
+#[derive(Clone)]
+pub struct Ballistics {
+    speed: f32,
+    blast_radius: f32,
+}
+
+impl Ballistics {
+    /// Speed of the projectile (in meters per second) along the line between
+    /// the muzzle and the target. The projectile is launched slightly upwards
+    /// to compensate for gravity.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Objects within this distance from the point of impact are damaged.
+    /// The damage linearly decreases with the distance.
+    pub fn blast_radius(&self) -> f32 {
+        self.blast_radius
+    }
+}
+
+impl TryFrom<BallisticsSerde> for Ballistics {
+    type Error = anyhow::Error;
+
+    fn try_from(info: BallisticsSerde) -> Result<Self, Self::Error> {
+        ensure!(
+            info.speed.is_finite() && info.speed > 0.,
+            "Projectile speed must be a positive finite number, got: {}",
+            info.speed
+        );
+        ensure!(
+            info.blast_radius.is_finite() && info.blast_radius >= 0.,
+            "Blast radius must be a non-negative finite number, got: {}",
+            info.blast_radius
+        );
+
+        Ok(Self {
+            speed: info.speed,
+            blast_radius: info.blast_radius,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BallisticsSerde {
+    speed: f32,
+    blast_radius: f32,
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp::Ordering;
 
     use super::*;
+    use crate::carrier::CarrierSerde;
+
+    #[test]
+    fn test_garrisoned() {
+        let cannon = LaserCannon::try_from(LaserCannonSerde {
+            muzzle: [0., 1., 0.],
+            range: 40.,
+            damage: 2.,
+            damage_type: DamageType::Kinetic,
+            ballistics: None,
+            anti_air: true,
+            charge_time_sec: 1.,
+            discharge_time_sec: 2.,
+        })
+        .unwrap();
+        let carrier_serde: CarrierSerde = serde_json::from_str(
+            r#"{"capacity": 4, "range_multiplier": 1.25, "damage_multiplier": 1.5}"#,
+        )
+        .unwrap();
+        let carrier = Carrier::try_from(carrier_serde).unwrap();
+
+        let garrisoned = cannon.garrisoned(Vec3::new(0., 10., 0.), 3, &carrier);
+        assert_eq!(garrisoned.muzzle(), Vec3::new(0., 10., 0.));
+        assert_eq!(garrisoned.range(), 50.);
+        assert_eq!(garrisoned.damage(), 9.);
+        assert!(garrisoned.anti_air());
+        assert!(!garrisoned.charge().charged());
+    }
 
     #[test]
     fn test_charge() {
@@ -0,0 +1,60 @@
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+
+/// Info about an object which can carry other units, e.g. a building which
+/// can be garrisoned.
+pub struct Carrier {
+    capacity: usize,
+    range_multiplier: f32,
+    damage_multiplier: f32,
+}
+
+impl Carrier {
+    /// Maximum number of simultaneously carried units.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Range of cannons of carried units is multiplied by this while they
+    /// fire from a garrisoned building.
+    pub fn range_multiplier(&self) -> f32 {
+        self.range_multiplier
+    }
+
+    /// Damage of cannons of carried units is multiplied by this while they
+    /// fire from a garrisoned building.
+    pub fn damage_multiplier(&self) -> f32 {
+        self.damage_multiplier
+    }
+}
+
+impl TryFrom<CarrierSerde> for Carrier {
+    type Error = anyhow::Error;
+
+    fn try_from(info: CarrierSerde) -> Result<Self, Self::Error> {
+        ensure!(info.capacity > 0, "Carrier capacity must be positive.");
+        ensure!(
+            info.range_multiplier.is_finite() && info.range_multiplier > 0.,
+            "Carrier range multiplier must be a positive finite number, got: {}",
+            info.range_multiplier
+        );
+        ensure!(
+            info.damage_multiplier.is_finite() && info.damage_multiplier > 0.,
+            "Carrier damage multiplier must be a positive finite number, got: {}",
+            info.damage_multiplier
+        );
+
+        Ok(Self {
+            capacity: info.capacity,
+            range_multiplier: info.range_multiplier,
+            damage_multiplier: info.damage_multiplier,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CarrierSerde {
+    capacity: usize,
+    range_multiplier: f32,
+    damage_multiplier: f32,
+}
@@ -6,12 +6,14 @@ pub struct Flight {
 }
 
 impl Flight {
-    /// Returns minimum flight height (above terrain) of the object.
+    /// Returns minimum flight height (above terrain or buildings) of the
+    /// object. Flying objects hover at this height while stationary.
     pub fn min_height(&self) -> f32 {
         self.min_height
     }
 
-    /// Returns maximum flight height (above terrain) of the object.
+    /// Returns maximum flight height (above terrain or buildings) of the
+    /// object.
     pub fn max_height(&self) -> f32 {
         self.max_height
     }
@@ -66,6 +66,29 @@ impl Health {
         self.health / self.max
     }
 
+    /// Sets current health to a given fraction of maximum health.
+    ///
+    /// # Panics
+    ///
+    /// This method might panic if `fraction` is not a number between 0 and 1
+    /// (inclusive).
+    pub fn set_fraction(&mut self, fraction: f32) {
+        debug_assert!((0. ..=1.).contains(&fraction));
+        self.health = self.max * fraction;
+    }
+
+    /// Multiplies both maximum and current health by a given factor.
+    ///
+    /// # Panics
+    ///
+    /// This method might panic if `factor` is not a positive finite number.
+    pub fn scale_max(&mut self, factor: f32) {
+        debug_assert!(factor.is_finite());
+        debug_assert!(factor > 0.);
+        self.max *= factor;
+        self.health *= factor;
+    }
+
     /// This method decreases health.
     ///
     /// # Arguments
@@ -1,8 +1,11 @@
 //! This crate implements functionality around map object handling, mostly
 //! object asset caching and pre-loading.
 
+use armor::ArmorPlugin;
+pub use armor::{ArmorClass, DamageMatrices, DamageMatrix, DamageType};
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
-pub use cannon::LaserCannon;
+pub use cannon::{Ballistics, LaserCannon};
+pub use carrier::Carrier;
 pub use collection::AssetCollection;
 pub use collider::ObjectCollider;
 pub use flight::Flight;
@@ -11,10 +14,14 @@ pub use health::{Health, InitialHealths};
 pub use ichnography::{Ichnography, EXCLUSION_OFFSET};
 use scenes::ScenesPlugin;
 pub use scenes::{SceneType, Scenes};
+pub use shield::Shield;
 use solids::SolidsPlugin;
 pub use solids::{SolidObject, SolidObjects};
+pub use stealth::{Cloak, Detector};
 
+mod armor;
 mod cannon;
+mod carrier;
 mod collection;
 mod collider;
 mod factory;
@@ -23,7 +30,9 @@ mod health;
 mod ichnography;
 mod names;
 mod scenes;
+mod shield;
 mod solids;
+mod stealth;
 
 pub struct ObjectsPluginGroup;
 
@@ -33,5 +42,6 @@ impl PluginGroup for ObjectsPluginGroup {
             .add(ScenesPlugin)
             .add(SolidsPlugin)
             .add(HealthPlugin)
+            .add(ArmorPlugin)
     }
 }
@@ -25,6 +25,17 @@ impl Plugin for ScenesPlugin {
 #[derive(Resource)]
 pub struct Scenes(AHashMap<SceneType, Handle<Scene>>);
 
+impl Scenes {
+    /// Returns type of the scene with the given handle or None if the handle
+    /// is not part of the collection.
+    pub fn scene_type(&self, handle: &Handle<Scene>) -> Option<SceneType> {
+        self.0
+            .iter()
+            .find(|(_, scene)| *scene == handle)
+            .map(|(&scene_type, _)| scene_type)
+    }
+}
+
 impl AssetCollection for Scenes {
     type Key = SceneType;
     type Asset = Scene;
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use anyhow::ensure;
+use bevy::prelude::Component;
+use serde::{Deserialize, Serialize};
+
+/// Energy shield protecting an object. Damage is absorbed by the shield
+/// before it reaches the object hull (health).
+#[derive(Component, Clone)]
+pub struct Shield {
+    capacity: f32,
+    energy: f32,
+    delay: Duration,
+    rate: f32,
+    idle: Duration,
+}
+
+impl Shield {
+    /// Maximum shield energy.
+    pub fn capacity(&self) -> f32 {
+        self.capacity
+    }
+
+    /// Current shield energy.
+    pub fn energy(&self) -> f32 {
+        self.energy
+    }
+
+    /// Returns the ratio between current and maximum shield energy.
+    pub fn fraction(&self) -> f32 {
+        self.energy / self.capacity
+    }
+
+    /// Absorbs as much of the damage as possible and returns the remaining
+    /// damage which must be applied to the hull.
+    ///
+    /// Shield regeneration is postponed by every hit.
+    pub fn absorb(&mut self, damage: f32) -> f32 {
+        debug_assert!(damage >= 0.);
+        self.idle = Duration::ZERO;
+        let absorbed = damage.min(self.energy);
+        self.energy -= absorbed;
+        damage - absorbed
+    }
+
+    /// Regenerates the shield. The shield starts regenerating once the
+    /// regeneration delay elapsed since the last hit.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - time elapsed since the last call of this method.
+    pub fn regenerate(&mut self, delta: Duration) {
+        let before = self.idle;
+        self.idle += delta;
+        let regenerating = self.idle.saturating_sub(before.max(self.delay));
+        self.energy = self
+            .capacity
+            .min(self.energy + self.rate * regenerating.as_secs_f32());
+    }
+}
+
+impl TryFrom<ShieldSerde> for Shield {
+    type Error = anyhow::Error;
+
+    fn try_from(info: ShieldSerde) -> Result<Self, Self::Error> {
+        ensure!(
+            info.capacity.is_finite() && info.capacity > 0.,
+            "Shield capacity must be a positive finite number, got: {}",
+            info.capacity
+        );
+        ensure!(
+            info.regeneration_delay_sec.is_finite() && info.regeneration_delay_sec >= 0.,
+            "Shield regeneration delay must be a non-negative finite number, got: {}",
+            info.regeneration_delay_sec
+        );
+        ensure!(
+            info.regeneration_rate.is_finite() && info.regeneration_rate >= 0.,
+            "Shield regeneration rate must be a non-negative finite number, got: {}",
+            info.regeneration_rate
+        );
+
+        Ok(Self {
+            capacity: info.capacity,
+            energy: info.capacity,
+            delay: Duration::from_secs_f32(info.regeneration_delay_sec),
+            rate: info.regeneration_rate,
+            idle: Duration::ZERO,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ShieldSerde {
+    capacity: f32,
+    regeneration_delay_sec: f32,
+    regeneration_rate: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shield() {
+        let mut shield = Shield::try_from(ShieldSerde {
+            capacity: 10.,
+            regeneration_delay_sec: 2.,
+            regeneration_rate: 1.,
+        })
+        .unwrap();
+
+        assert_eq!(shield.absorb(4.), 0.);
+        assert_eq!(shield.energy(), 6.);
+        assert_eq!(shield.absorb(8.), 2.);
+        assert_eq!(shield.energy(), 0.);
+
+        shield.regenerate(Duration::from_millis(1500));
+        assert_eq!(shield.energy(), 0.);
+        shield.regenerate(Duration::from_secs(1));
+        assert_eq!(shield.energy(), 0.5);
+        shield.regenerate(Duration::from_secs(3));
+        assert_eq!(shield.energy(), 3.5);
+
+        shield.absorb(1.);
+        shield.regenerate(Duration::from_secs(1));
+        assert_eq!(shield.energy(), 2.5);
+        shield.regenerate(Duration::from_secs(100));
+        assert_eq!(shield.energy(), 10.);
+        assert_eq!(shield.fraction(), 1.);
+
+        assert!(Shield::try_from(ShieldSerde {
+            capacity: 0.,
+            regeneration_delay_sec: 2.,
+            regeneration_rate: 1.,
+        })
+        .is_err());
+    }
+}
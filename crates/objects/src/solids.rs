@@ -12,12 +12,16 @@ use iyes_progress::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    armor::ArmorClass,
     cannon::{LaserCannon, LaserCannonSerde},
+    carrier::{Carrier, CarrierSerde},
     collection::AssetCollectionLoader,
     collider::{ColliderSerde, ObjectCollider},
     factory::{Factory, FactorySerde},
     flight::{Flight, FlightSerde},
     ichnography::{FootprintSerde, Ichnography},
+    shield::{Shield, ShieldSerde},
+    stealth::{Cloak, CloakSerde, Detector, DetectorSerde},
     AssetCollection,
 };
 
@@ -69,12 +73,29 @@ impl AssetCollectionLoader for Solids {
 pub struct SolidObject {
     ichnography: Ichnography,
     collider: ObjectCollider,
+    armor: Option<ArmorClass>,
+    shield: Option<Shield>,
     cannon: Option<LaserCannon>,
     flight: Option<Flight>,
     factory: Option<Factory>,
+    carrier: Option<Carrier>,
+    cloak: Option<Cloak>,
+    detector: Option<Detector>,
 }
 
 impl SolidObject {
+    /// Armor class of the object. It is None for objects which cannot be
+    /// damaged.
+    pub fn armor(&self) -> Option<ArmorClass> {
+        self.armor
+    }
+
+    /// Returns None for objects without a shield. Otherwise, it returns a
+    /// fully charged shield of the object.
+    pub fn shield(&self) -> Option<&Shield> {
+        self.shield.as_ref()
+    }
+
     pub fn cannon(&self) -> Option<&LaserCannon> {
         self.cannon.as_ref()
     }
@@ -91,6 +112,23 @@ impl SolidObject {
         self.factory.as_ref()
     }
 
+    /// Returns None if other units cannot board the object, otherwise it
+    /// returns info about its carrying capabilities.
+    pub fn carrier(&self) -> Option<&Carrier> {
+        self.carrier.as_ref()
+    }
+
+    /// Returns None for objects which cannot cloak, otherwise it returns an
+    /// unrevealed cloak of the object.
+    pub fn cloak(&self) -> Option<&Cloak> {
+        self.cloak.as_ref()
+    }
+
+    /// Returns None if the object cannot detect cloaked objects.
+    pub fn detector(&self) -> Option<&Detector> {
+        self.detector.as_ref()
+    }
+
     pub fn ichnography(&self) -> &Ichnography {
         &self.ichnography
     }
@@ -107,9 +145,14 @@ impl TryFrom<SolidObjectSerde> for SolidObject {
         Ok(Self {
             ichnography: Ichnography::try_from(solid_serde.footprint)?,
             collider: ObjectCollider::try_from(solid_serde.shape)?,
+            armor: solid_serde.armor,
+            shield: solid_serde.shield.map(Shield::try_from).transpose()?,
             cannon: solid_serde.cannon.map(LaserCannon::try_from).transpose()?,
             flight: solid_serde.flight.map(Flight::try_from).transpose()?,
             factory: solid_serde.factory.map(Factory::try_from).transpose()?,
+            carrier: solid_serde.carrier.map(Carrier::try_from).transpose()?,
+            cloak: solid_serde.cloak.map(Cloak::try_from).transpose()?,
+            detector: solid_serde.detector.map(Detector::try_from).transpose()?,
         })
     }
 }
@@ -118,9 +161,14 @@ impl TryFrom<SolidObjectSerde> for SolidObject {
 struct SolidObjectSerde {
     footprint: FootprintSerde,
     shape: ColliderSerde,
+    armor: Option<ArmorClass>,
+    shield: Option<ShieldSerde>,
     cannon: Option<LaserCannonSerde>,
     flight: Option<FlightSerde>,
     factory: Option<FactorySerde>,
+    carrier: Option<CarrierSerde>,
+    cloak: Option<CloakSerde>,
+    detector: Option<DetectorSerde>,
 }
 
 struct SolidObjectLoader;
@@ -155,6 +203,16 @@ impl<'w> SolidObjects<'w> {
     pub fn get(&self, object_type: ObjectType) -> &SolidObject {
         self.assets.get(self.solids.get(object_type)).unwrap()
     }
+
+    /// Returns type of the object configured by the asset with the given
+    /// handle or None if the handle does not belong to any object type.
+    pub fn object_type(&self, handle: &Handle<SolidObject>) -> Option<ObjectType> {
+        self.solids
+            .0
+            .iter()
+            .find(|(_, solid)| *solid == handle)
+            .map(|(&object_type, _)| object_type)
+    }
 }
 
 fn setup(mut commands: Commands, server: Res<AssetServer>) {
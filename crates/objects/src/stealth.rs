@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use anyhow::ensure;
+use bevy::prelude::Component;
+use serde::{Deserialize, Serialize};
+
+/// Cloaking device of an object. Cloaked objects are invisible to enemies
+/// without a [`Detector`] in range. Each fire reveals the object to everybody
+/// for a while.
+#[derive(Component, Clone)]
+pub struct Cloak {
+    reveal_time: Duration,
+    revealed: Duration,
+}
+
+impl Cloak {
+    /// Returns true if the object fired recently and thus it is visible to
+    /// all players.
+    pub fn revealed(&self) -> bool {
+        !self.revealed.is_zero()
+    }
+
+    /// Reveals the object to all players for the configured time. This
+    /// should be called whenever the object fires.
+    pub fn reveal(&mut self) {
+        self.revealed = self.reveal_time;
+    }
+
+    /// Lets the object cloak again once the reveal time elapsed.
+    ///
+    /// # Arguments
+    ///
+    /// * `delta` - time elapsed since the last call of this method.
+    pub fn tick(&mut self, delta: Duration) {
+        self.revealed = self.revealed.saturating_sub(delta);
+    }
+}
+
+impl TryFrom<CloakSerde> for Cloak {
+    type Error = anyhow::Error;
+
+    fn try_from(info: CloakSerde) -> Result<Self, Self::Error> {
+        ensure!(
+            info.reveal_time_sec.is_finite() && info.reveal_time_sec >= 0.,
+            "Cloak reveal time must be a non-negative finite number, got: {}",
+            info.reveal_time_sec
+        );
+
+        Ok(Self {
+            reveal_time: Duration::from_secs_f32(info.reveal_time_sec),
+            revealed: Duration::ZERO,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct CloakSerde {
+    reveal_time_sec: f32,
+}
+
+/// Detector of cloaked objects, see [`Cloak`].
+#[derive(Component, Clone)]
+pub struct Detector {
+    range: f32,
+}
+
+impl Detector {
+    /// Cloaked enemies closer than this are visible to the owner of the
+    /// detector and to their allies.
+    pub fn range(&self) -> f32 {
+        self.range
+    }
+}
+
+impl TryFrom<DetectorSerde> for Detector {
+    type Error = anyhow::Error;
+
+    fn try_from(info: DetectorSerde) -> Result<Self, Self::Error> {
+        ensure!(
+            info.range.is_finite() && info.range > 0.,
+            "Detector range must be a positive finite number, got: {}",
+            info.range
+        );
+
+        Ok(Self { range: info.range })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DetectorSerde {
+    range: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cloak() {
+        let mut cloak = Cloak::try_from(CloakSerde {
+            reveal_time_sec: 2.,
+        })
+        .unwrap();
+        assert!(!cloak.revealed());
+
+        cloak.reveal();
+        assert!(cloak.revealed());
+        cloak.tick(Duration::from_millis(1500));
+        assert!(cloak.revealed());
+        cloak.tick(Duration::from_secs(1));
+        assert!(!cloak.revealed());
+
+        assert!(Cloak::try_from(CloakSerde {
+            reveal_time_sec: -1.,
+        })
+        .is_err());
+        assert!(Detector::try_from(DetectorSerde { range: 0. }).is_err());
+        assert_eq!(
+            Detector::try_from(DetectorSerde { range: 30. })
+                .unwrap()
+                .range(),
+            30.
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! This module contains additional cost of movement over difficult terrain
+//! (slopes, craters and similar) as seen by the path finder.
+
+use de_map::size::MapBounds;
+use glam::Vec2;
+use parry2d::{
+    bounding_volume::Aabb,
+    math::Point,
+    query::{Ray, RayCast},
+    shape::ConvexPolygon,
+};
+
+use crate::elevation::Elevation;
+
+/// Additional cost of movement over difficult terrain. It is added to the
+/// (horizontal) distance of paths.
+#[derive(Clone)]
+pub(crate) struct TerrainCost {
+    elevation: Option<Elevation>,
+    zones: Vec<CostlyZone>,
+}
+
+impl TerrainCost {
+    /// Creates a new terrain cost. None is returned if the terrain has no
+    /// difficult parts.
+    pub(crate) fn new(elevation: Option<Elevation>, zones: Vec<CostlyZone>) -> Option<Self> {
+        if elevation.is_none() && zones.is_empty() {
+            None
+        } else {
+            Some(Self { elevation, zones })
+        }
+    }
+
+    /// Returns a copy of a terrain cost with elevation re-sampled inside
+    /// given areas of the map. Terrain without elevation is considered flat.
+    ///
+    /// # Arguments
+    ///
+    /// * `cost` - the original terrain cost.
+    ///
+    /// * `bounds` - bounds of the map.
+    ///
+    /// * `areas` - flat bounding boxes of the areas to be re-sampled.
+    ///
+    /// * `height` - current elevation of the terrain at a point.
+    pub(crate) fn rebaked<F>(
+        cost: Option<&Self>,
+        bounds: MapBounds,
+        areas: &[Aabb],
+        height: F,
+    ) -> Self
+    where
+        F: Fn(Vec2) -> f32,
+    {
+        let mut cost = cost.cloned().unwrap_or(Self {
+            elevation: None,
+            zones: Vec::new(),
+        });
+        let elevation = cost
+            .elevation
+            .get_or_insert_with(|| Elevation::flat(bounds));
+        for area in areas {
+            elevation.rebake(area, &height);
+        }
+        cost
+    }
+
+    /// Returns additional cost (in meters) of a straight movement between two
+    /// points.
+    pub(crate) fn cost(&self, from: Point<f32>, to: Point<f32>) -> f32 {
+        let climb = self
+            .elevation
+            .as_ref()
+            .map_or(0., |elevation| elevation.cost(from, to));
+        let zones: f32 = self.zones.iter().map(|zone| zone.cost(from, to)).sum();
+        climb + zones
+    }
+}
+
+/// A convex area of the map whose traversal is more expensive than traversal
+/// of ordinary terrain.
+#[derive(Clone)]
+pub(crate) struct CostlyZone {
+    polygon: ConvexPolygon,
+    penalty: f32,
+}
+
+impl CostlyZone {
+    /// # Arguments
+    ///
+    /// * `polygon` - area of the zone.
+    ///
+    /// * `penalty` - additional cost of each meter travelled inside the zone.
+    pub(crate) fn new(polygon: ConvexPolygon, penalty: f32) -> Self {
+        debug_assert!(penalty.is_finite());
+        debug_assert!(penalty >= 0.);
+        Self { polygon, penalty }
+    }
+
+    fn cost(&self, from: Point<f32>, to: Point<f32>) -> f32 {
+        self.penalty * self.length_inside(from, to)
+    }
+
+    /// Returns length of the part of line segment between `from` and `to`
+    /// which lies inside the zone.
+    fn length_inside(&self, from: Point<f32>, to: Point<f32>) -> f32 {
+        let dir = to - from;
+        // The polygon is convex so the segment enters it at most once and
+        // leaves it at most once.
+        let Some(enter) = self.polygon.cast_local_ray(&Ray::new(from, dir), 1., true) else {
+            return 0.;
+        };
+        let Some(leave) = self.polygon.cast_local_ray(&Ray::new(to, -dir), 1., true) else {
+            return 0.;
+        };
+        (1. - enter - leave).max(0.) * dir.norm()
+    }
+}
+
+/// Returns cost of a straight movement between two points in addition to the
+/// distance. See [`TerrainCost::cost`].
+pub(crate) fn terrain_cost(cost: Option<&TerrainCost>, from: Point<f32>, to: Point<f32>) -> f32 {
+    cost.map_or(0., |cost| cost.cost(from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_cost() {
+        let zone = CostlyZone::new(
+            ConvexPolygon::from_convex_polyline(vec![
+                Point::new(0., 0.),
+                Point::new(10., 0.),
+                Point::new(10., 10.),
+                Point::new(0., 10.),
+            ])
+            .unwrap(),
+            2.,
+        );
+
+        assert_eq!(zone.cost(Point::new(-5., 20.), Point::new(15., 20.)), 0.);
+        assert_eq!(zone.cost(Point::new(2., 2.), Point::new(2., 6.)), 8.);
+        assert!((zone.cost(Point::new(-10., 5.), Point::new(20., 5.)) - 20.).abs() < 1e-4);
+        assert!((zone.cost(Point::new(5., 5.), Point::new(5., 20.)) - 10.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_terrain_cost() {
+        assert!(TerrainCost::new(None, Vec::new()).is_none());
+        assert_eq!(
+            terrain_cost(None, Point::new(-50., 0.), Point::new(50., 0.)),
+            0.
+        );
+
+        let cost = TerrainCost::new(
+            None,
+            vec![
+                CostlyZone::new(
+                    ConvexPolygon::from_convex_polyline(vec![
+                        Point::new(0., 0.),
+                        Point::new(10., 0.),
+                        Point::new(0., 10.),
+                    ])
+                    .unwrap(),
+                    1.,
+                ),
+                CostlyZone::new(
+                    ConvexPolygon::from_convex_polyline(vec![
+                        Point::new(0., 0.),
+                        Point::new(0., -10.),
+                        Point::new(10., 0.),
+                    ])
+                    .unwrap(),
+                    3.,
+                ),
+            ],
+        )
+        .unwrap();
+        assert!(
+            (terrain_cost(Some(&cost), Point::new(1., -20.), Point::new(1., 20.)) - 36.).abs()
+                < 1e-4
+        );
+    }
+}
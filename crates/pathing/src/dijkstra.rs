@@ -7,6 +7,7 @@ use bevy::utils::FloatOrd;
 use parry2d::{math::Point, na, query::PointQuery, shape::Segment};
 
 use crate::{
+    cost::{terrain_cost, TerrainCost},
     funnel::Funnel,
     geometry::{orient, which_side, Side},
     graph::VisibilityGraph,
@@ -18,22 +19,28 @@ use crate::{
 ///
 /// Source and target points must not lie inside or on the edge of the same
 /// triangle of the triangulation from which `graph` was created.
+///
+/// Paths over difficult terrain (e.g. steep slopes) are penalized if `cost` is
+/// given.
 pub(crate) fn find_path(
     graph: &VisibilityGraph,
     source: PointContext,
     target: PointContext,
     properties: PathQueryProps,
+    cost: Option<&TerrainCost>,
 ) -> Option<Path> {
     let mut open_set = OpenSet::new();
     let mut explored = AHashSet::new();
 
     let funnel = Funnel::new(source.point());
     for &edge_id in source.neighbours() {
+        let geometry = graph.geometry(edge_id);
         open_set.push(Step::from_segment(
             source.point(),
             &funnel,
-            graph.geometry(edge_id).segment(),
+            geometry.segment(),
             edge_id,
+            terrain_cost(cost, source.point(), geometry.midpoint()),
         ));
     }
 
@@ -90,6 +97,7 @@ pub(crate) fn find_path(
                 step.funnel(),
                 next_geom.segment(),
                 next_edge_id,
+                step.penalty() + terrain_cost(cost, geometry.midpoint(), next_geom.midpoint()),
             ));
         }
     }
@@ -154,6 +162,9 @@ impl OpenSet {
 /// line segments -- used in the edge/triangle graph traversal algorithm.
 struct Step {
     score: FloatOrd,
+    /// Accumulated terrain cost along the traversed edges. It is included in
+    /// `score`.
+    penalty: f32,
     /// From which side the edge was approached. This is the side from the
     /// perspective of the edge's line segment before orientation.
     side: Side,
@@ -165,23 +176,40 @@ struct Step {
 }
 
 impl Step {
-    fn from_segment(eye: Point<f32>, funnel: &Funnel, segment: Segment, edge_id: u32) -> Self {
+    fn from_segment(
+        eye: Point<f32>,
+        funnel: &Funnel,
+        segment: Segment,
+        edge_id: u32,
+        penalty: f32,
+    ) -> Self {
         let side = which_side(segment.a, segment.b, eye);
         let segment = orient(eye, segment);
         let funnel = funnel.extended(segment);
         let dist = segment.distance_to_local_point(&funnel.tail().point(), true);
-        Self::new(funnel.tail().length() + dist, side, funnel, edge_id)
+        Self::new(
+            funnel.tail().length() + dist + penalty,
+            penalty,
+            side,
+            funnel,
+            edge_id,
+        )
     }
 
-    fn new(score: f32, side: Side, funnel: Funnel, edge_id: u32) -> Self {
+    fn new(score: f32, penalty: f32, side: Side, funnel: Funnel, edge_id: u32) -> Self {
         Self {
             score: FloatOrd(score),
+            penalty,
             side,
             funnel,
             edge_id,
         }
     }
 
+    fn penalty(&self) -> f32 {
+        self.penalty
+    }
+
     fn side(&self) -> Side {
         self.side
     }
@@ -253,9 +281,27 @@ mod tests {
     #[test]
     fn test_open_set() {
         let mut set = OpenSet::new();
-        set.push(Step::new(2., Side::Left, Funnel::new(Point::origin()), 1));
-        set.push(Step::new(1.1, Side::Left, Funnel::new(Point::origin()), 2));
-        set.push(Step::new(4., Side::Left, Funnel::new(Point::origin()), 3));
+        set.push(Step::new(
+            2.,
+            0.,
+            Side::Left,
+            Funnel::new(Point::origin()),
+            1,
+        ));
+        set.push(Step::new(
+            1.1,
+            0.,
+            Side::Left,
+            Funnel::new(Point::origin()),
+            2,
+        ));
+        set.push(Step::new(
+            4.,
+            0.,
+            Side::Left,
+            Funnel::new(Point::origin()),
+            3,
+        ));
         assert_eq!(set.pop().unwrap().edge_id(), 2);
         assert_eq!(set.pop().unwrap().edge_id(), 1);
         assert_eq!(set.pop().unwrap().edge_id(), 3);
@@ -263,8 +309,8 @@ mod tests {
 
     #[test]
     fn test_step_ord() {
-        let step_a = Step::new(2., Side::Left, Funnel::new(Point::origin()), 1);
-        let step_b = Step::new(2.1, Side::Left, Funnel::new(Point::origin()), 2);
+        let step_a = Step::new(2., 0., Side::Left, Funnel::new(Point::origin()), 1);
+        let step_b = Step::new(2.1, 0., Side::Left, Funnel::new(Point::origin()), 2);
         assert!(step_b < step_a);
     }
 }
@@ -0,0 +1,180 @@
+//! This module contains terrain elevation as seen by the path finder.
+
+use de_map::{
+    heightmap::{Heightmap, MAX_HEIGHTMAP_RESOLUTION},
+    size::MapBounds,
+};
+use glam::Vec2;
+use parry2d::{bounding_volume::Aabb, math::Point};
+
+/// Each meter of climb or descent costs as much as this many meters of
+/// horizontal distance.
+const CLIMB_COST: f32 = 4.;
+/// Maximum number of elevation samples taken along a single line segment.
+const MAX_SAMPLES: usize = 64;
+/// Desired distance between neighbouring samples of elevation of initially
+/// flat terrain.
+const FLAT_SPACING: f32 = 4.;
+
+/// Terrain elevation used to penalize paths over steep slopes.
+#[derive(Clone)]
+pub(crate) struct Elevation {
+    bounds: MapBounds,
+    columns: usize,
+    rows: usize,
+    /// Elevation sampled on a regular grid, row by row from south to north.
+    heights: Vec<f32>,
+    /// Distance between neighbouring samples.
+    spacing: f32,
+}
+
+impl Elevation {
+    pub(crate) fn new(bounds: MapBounds, heightmap: &Heightmap) -> Self {
+        Self::from_samples(
+            bounds,
+            heightmap.columns(),
+            heightmap.rows(),
+            heightmap.heights().to_vec(),
+        )
+    }
+
+    /// Creates elevation of flat terrain sampled densely enough so that it
+    /// can be later re-baked after a deformation of the terrain.
+    pub(crate) fn flat(bounds: MapBounds) -> Self {
+        let samples = |size: f32| {
+            ((size / FLAT_SPACING).ceil() as usize).clamp(1, MAX_HEIGHTMAP_RESOLUTION - 1) + 1
+        };
+        let columns = samples(bounds.size().x);
+        let rows = samples(bounds.size().y);
+        Self::from_samples(bounds, columns, rows, vec![0.; columns * rows])
+    }
+
+    fn from_samples(bounds: MapBounds, columns: usize, rows: usize, heights: Vec<f32>) -> Self {
+        let cells = Vec2::new((columns - 1) as f32, (rows - 1) as f32);
+        let spacing = (bounds.size() / cells).min_element();
+        Self {
+            bounds,
+            columns,
+            rows,
+            heights,
+            spacing,
+        }
+    }
+
+    /// Re-samples the elevation inside an area of the map, e.g. after the
+    /// terrain was deformed.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - flat bounding box of the area to be re-sampled.
+    ///
+    /// * `height` - current elevation of the terrain at a point.
+    pub(crate) fn rebake<F>(&mut self, area: &Aabb, height: F)
+    where
+        F: Fn(Vec2) -> f32,
+    {
+        let cells = Vec2::new((self.columns - 1) as f32, (self.rows - 1) as f32);
+        let relative =
+            |point: Point<f32>| (Vec2::from(point) - self.bounds.min()) / self.bounds.size();
+        let start = (relative(area.mins) * cells).ceil().max(Vec2::ZERO);
+        let end = (relative(area.maxs) * cells).floor().min(cells);
+        if start.cmpgt(end).any() {
+            return;
+        }
+
+        for row in start.y as usize..=end.y as usize {
+            for column in start.x as usize..=end.x as usize {
+                let point = self.bounds.min()
+                    + Vec2::new(column as f32, row as f32) / cells * self.bounds.size();
+                self.heights[row * self.columns + column] = height(point);
+            }
+        }
+    }
+
+    /// Returns additional cost (in meters) of a straight movement between two
+    /// points. The cost is proportional to the total climb and descent along
+    /// the line segment.
+    pub(crate) fn cost(&self, from: Point<f32>, to: Point<f32>) -> f32 {
+        let from = Vec2::from(from);
+        let to = Vec2::from(to);
+
+        let samples = ((from.distance(to) / self.spacing).ceil() as usize).clamp(1, MAX_SAMPLES);
+        let mut previous = self.height(from);
+        let mut climb = 0.;
+        for i in 1..=samples {
+            let height = self.height(from.lerp(to, i as f32 / samples as f32));
+            climb += (height - previous).abs();
+            previous = height;
+        }
+
+        CLIMB_COST * climb
+    }
+
+    /// Returns bilinearly interpolated elevation at a point. Points out of
+    /// the map are clamped to the map bounds.
+    fn height(&self, point: Vec2) -> f32 {
+        let cells = Vec2::new((self.columns - 1) as f32, (self.rows - 1) as f32);
+        let relative =
+            ((point - self.bounds.min()) / self.bounds.size()).clamp(Vec2::ZERO, Vec2::ONE);
+        let position = relative * cells;
+
+        let column = (position.x.floor() as usize).min(self.columns - 2);
+        let row = (position.y.floor() as usize).min(self.rows - 2);
+        let fraction = position - Vec2::new(column as f32, row as f32);
+
+        let sample = |column: usize, row: usize| self.heights[row * self.columns + column];
+        let south = sample(column, row) * (1. - fraction.x) + sample(column + 1, row) * fraction.x;
+        let north =
+            sample(column, row + 1) * (1. - fraction.x) + sample(column + 1, row + 1) * fraction.x;
+        south * (1. - fraction.y) + north * fraction.y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost() {
+        // A ridge along y axis in the middle of the map.
+        let elevation = Elevation::new(
+            MapBounds::new(Vec2::new(100., 100.)),
+            &Heightmap::new(3, 2, vec![0., 10., 0., 0., 10., 0.]),
+        );
+
+        assert_eq!(
+            elevation.cost(Point::new(-40., -10.), Point::new(-40., 30.)),
+            0.
+        );
+        assert_eq!(
+            elevation.cost(Point::new(-50., 0.), Point::new(0., 0.)),
+            CLIMB_COST * 10.
+        );
+        assert!(
+            (elevation.cost(Point::new(-50., 0.), Point::new(50., 0.)) - CLIMB_COST * 20.).abs()
+                < 1e-3
+        );
+    }
+    #[test]
+    fn test_rebake() {
+        let mut elevation = Elevation::flat(MapBounds::new(Vec2::new(96., 80.)));
+        assert_eq!(elevation.columns, 25);
+        assert_eq!(elevation.rows, 21);
+        assert_eq!(
+            elevation.cost(Point::new(-48., 0.), Point::new(48., 0.)),
+            0.
+        );
+
+        elevation.rebake(
+            &Aabb::new(Point::new(-5., -5.), Point::new(5., 5.)),
+            |point| -(5. - point.length()).max(0.),
+        );
+        assert_eq!(elevation.height(Vec2::ZERO), -5.);
+        assert_eq!(elevation.height(Vec2::new(4., 0.)), -1.);
+        assert_eq!(elevation.height(Vec2::new(8., 0.)), 0.);
+        assert!(
+            (elevation.cost(Point::new(-48., 0.), Point::new(48., 0.)) - CLIMB_COST * 10.).abs()
+                < 1e-3
+        );
+    }
+}
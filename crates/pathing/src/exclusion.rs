@@ -1,6 +1,7 @@
 use bevy::prelude::Transform;
 use de_core::projection::ToFlat;
-use de_objects::Ichnography;
+use de_map::zones::TerrainZone;
+use de_objects::{Ichnography, EXCLUSION_OFFSET};
 use glam::EulerRot;
 use parry2d::{
     math::{Isometry, Point},
@@ -58,6 +59,16 @@ impl ExclusionArea {
         Self::new(ConvexPolygon::from_convex_polyline(vertices).unwrap())
     }
 
+    /// Creates a new exclusion area from an impassable terrain zone.
+    pub(crate) fn from_zone(zone: &TerrainZone) -> Self {
+        let vertices: Vec<Point<f32>> = zone.vertices().iter().map(|&v| v.into()).collect();
+        Self::new(
+            ConvexPolygon::from_convex_polyline(vertices)
+                .unwrap()
+                .offsetted(EXCLUSION_OFFSET),
+        )
+    }
+
     pub(crate) fn new(polygon: ConvexPolygon) -> Self {
         let aabb = polygon.local_aabb();
         Self {
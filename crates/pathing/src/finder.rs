@@ -1,8 +1,11 @@
 //! This module contains global map shortest path finder.
 
+use std::sync::Arc;
+
 use ahash::AHashMap;
 use bevy::prelude::{debug, info};
 use de_map::size::MapBounds;
+use glam::Vec2;
 use parry2d::{
     math::Point,
     na,
@@ -13,14 +16,20 @@ use rstar::{PointDistance, RTree, RTreeObject, AABB};
 use tinyvec::{ArrayVec, TinyVec};
 
 use crate::{
+    cost::TerrainCost,
     dijkstra::{find_path, PointContext},
     exclusion::ExclusionArea,
+    flow::FlowField,
     graph::VisibilityGraph,
     path::Path,
     utils::HashableSegment,
     PathTarget,
 };
 
+/// Minimum number of simultaneous path requests to a single triangle for
+/// which a flow field is used instead of individual path searches.
+pub(crate) const FLOW_FIELD_THRESHOLD: usize = 32;
+
 /// A struct used for path finding.
 pub struct PathFinder {
     /// Spatial index of triangles. It is used to find edges neighboring start
@@ -30,6 +39,9 @@ pub struct PathFinder {
     /// `triangles`. It is used to find way out of unreachable area.
     exclusions: RTree<GraphExclusion>,
     graph: VisibilityGraph,
+    /// Cost used to penalize paths over difficult terrain (e.g. steep
+    /// slopes). Easy terrain is assumed everywhere if it is None.
+    cost: Option<Arc<TerrainCost>>,
 }
 
 impl PathFinder {
@@ -128,9 +140,17 @@ impl PathFinder {
             triangles: RTree::bulk_load(indexed_triangles),
             exclusions: RTree::bulk_load(exclusions),
             graph,
+            cost: None,
         }
     }
 
+    /// Returns the path finder which prefers paths avoiding difficult
+    /// terrain.
+    pub(crate) fn with_cost(mut self, cost: Option<Arc<TerrainCost>>) -> Self {
+        self.cost = cost;
+        self
+    }
+
     /// Returns a shortest path between two points.
     ///
     /// Returns `None` if there is no path between the two points.
@@ -140,14 +160,7 @@ impl PathFinder {
 
         info!("Finding path from {:?} to {:?}", from, to);
 
-        let source_edges = {
-            let edges = self.locate_triangle_edges(from);
-            if edges.is_empty() {
-                self.locate_exclusion_edges(from)
-            } else {
-                edges
-            }
-        };
+        let source_edges = self.locate_edges(from);
         if source_edges.is_empty() {
             return None;
         }
@@ -175,7 +188,13 @@ impl PathFinder {
 
         let source = PointContext::new(from, source_edges);
         let target_context = PointContext::new(to, target_edges);
-        match find_path(&self.graph, source, target_context, target.properties()) {
+        match find_path(
+            &self.graph,
+            source,
+            target_context,
+            target.properties(),
+            self.cost.as_deref(),
+        ) {
             Some(path) => {
                 debug!(
                     "Path of length {} from {:?} to {:?} found",
@@ -192,6 +211,63 @@ impl PathFinder {
         }
     }
 
+    /// Returns shortest paths for multiple path requests. Paths are returned
+    /// in the same order as `requests`.
+    ///
+    /// Paths of groups of at least [`FLOW_FIELD_THRESHOLD`] requests whose
+    /// targets lie in the same triangle are found with a shared flow field.
+    /// See [`crate::flow`].
+    pub(crate) fn find_paths(&self, requests: &[(Vec2, PathTarget)]) -> Vec<Option<Path>> {
+        let mut groups: AHashMap<[u32; 3], Vec<usize>> = AHashMap::new();
+        for (index, (_, target)) in requests.iter().enumerate() {
+            let location = target.location();
+            if let Some(triangle) = self
+                .triangles
+                .locate_all_at_point(&[location.x, location.y])
+                .next()
+            {
+                groups.entry(triangle.edges).or_default().push(index);
+            }
+        }
+
+        let mut paths: Vec<Option<Option<Path>>> = (0..requests.len()).map(|_| None).collect();
+        for (targets, indices) in groups {
+            if indices.len() < FLOW_FIELD_THRESHOLD {
+                continue;
+            }
+
+            debug!("Finding {} paths with a shared flow field", indices.len());
+            let field = FlowField::new(&self.graph, targets, self.cost.as_deref());
+            for index in indices {
+                let (from, target) = requests[index];
+                let from: Point<f32> = from.into();
+                let source_edges = self.locate_edges(from);
+                if let Some(path) =
+                    field.path(&self.graph, from, &source_edges, target.location().into())
+                {
+                    paths[index] = Some(path.truncated(target.properties().distance()));
+                }
+            }
+        }
+
+        paths
+            .into_iter()
+            .zip(requests)
+            .map(|(path, &(from, target))| path.unwrap_or_else(|| self.find_path(from, target)))
+            .collect()
+    }
+
+    /// Returns edges neighbouring to `point`. Edges of the surrounding
+    /// exclusion area are returned if the point is not inside any triangle.
+    fn locate_edges(&self, point: Point<f32>) -> Vec<u32> {
+        let edges = self.locate_triangle_edges(point);
+        if edges.is_empty() {
+            self.locate_exclusion_edges(point)
+        } else {
+            edges
+        }
+    }
+
     fn locate_triangle_edges(&self, point: Point<f32>) -> Vec<u32> {
         self.triangles
             .locate_all_at_point(&[point.x, point.y])
@@ -297,14 +373,12 @@ impl PointDistance for GraphExclusion {
 
 #[cfg(test)]
 mod tests {
-    use glam::Vec2;
     use ntest::timeout;
 
     use super::*;
     use crate::PathQueryProps;
 
-    #[test]
-    fn test_finder() {
+    fn finder() -> PathFinder {
         let triangles = vec![
             Triangle::new(
                 Point::new(-18.6, -18.6),
@@ -347,7 +421,12 @@ mod tests {
                 Point::new(500., 1000.),
             ),
         ];
-        let finder = PathFinder::from_triangles(triangles, vec![]);
+        PathFinder::from_triangles(triangles, vec![])
+    }
+
+    #[test]
+    fn test_finder() {
+        let finder = finder();
 
         let first_path = finder
             .find_path(
@@ -436,6 +515,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_find_paths() {
+        let finder = finder();
+
+        let target = PathTarget::new(Vec2::new(450., 950.), PathQueryProps::exact(), false);
+        let mut requests = vec![(Vec2::new(-460., -950.), target); FLOW_FIELD_THRESHOLD];
+        requests.push((
+            Vec2::new(0.2, -950.),
+            PathTarget::new(Vec2::new(0., 950.), PathQueryProps::exact(), false),
+        ));
+
+        let paths = finder.find_paths(&requests);
+        assert_eq!(paths.len(), requests.len());
+        for (path, &(from, target)) in paths.iter().zip(&requests) {
+            let path = path.as_ref().unwrap();
+            let expected = finder.find_path(from, target).unwrap();
+            assert_eq!(path.waypoints().first(), expected.waypoints().first());
+            assert_eq!(path.waypoints().last(), expected.waypoints().last());
+            assert!(path.length() <= 1.01 * expected.length());
+        }
+    }
+
     #[test]
     #[timeout(100)]
     fn test_unreachable() {
@@ -0,0 +1,198 @@
+//! This module contains flow field based path finding. It is used when many
+//! objects travel to the same area of the map: a single graph traversal is
+//! shared by all of them instead of searching for each path separately.
+
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::utils::FloatOrd;
+use glam::Vec2;
+use parry2d::{math::Point, na};
+
+use crate::{
+    cost::{terrain_cost, TerrainCost},
+    funnel::Funnel,
+    geometry::orient,
+    graph::VisibilityGraph,
+    path::Path,
+};
+
+/// A flow field over the visibility graph. Each graph node (triangle edge)
+/// points to its neighbour on the shortest way to a target triangle.
+///
+/// Distances between graph nodes are measured between midpoints of the
+/// triangle edges, thus the resulting paths are slightly less optimal than
+/// paths found by [`crate::dijkstra::find_path`].
+pub(crate) struct FlowField {
+    /// Edges of the target triangle.
+    targets: [u32; 3],
+    /// Distance to the target triangle of each graph node.
+    distances: Vec<f32>,
+    /// Next graph node on the way to the target triangle. It is None for the
+    /// edges of the target triangle and for nodes from which the target
+    /// triangle is unreachable.
+    next: Vec<Option<u32>>,
+}
+
+impl FlowField {
+    /// Creates a new flow field leading to a triangle with edges `targets`.
+    /// Movement over difficult terrain (e.g. steep slopes) is penalized if
+    /// `cost` is given.
+    pub(crate) fn new(
+        graph: &VisibilityGraph,
+        targets: [u32; 3],
+        cost: Option<&TerrainCost>,
+    ) -> Self {
+        let mut distances = vec![f32::INFINITY; graph.len()];
+        let mut next = vec![None; graph.len()];
+        let mut open_set = BinaryHeap::new();
+
+        for edge_id in targets {
+            distances[index(edge_id)] = 0.;
+            open_set.push((Reverse(FloatOrd(0.)), edge_id));
+        }
+
+        while let Some((Reverse(FloatOrd(distance)), edge_id)) = open_set.pop() {
+            if distance > distances[index(edge_id)] {
+                continue;
+            }
+
+            let midpoint = graph.geometry(edge_id).midpoint();
+            for &neighbour_id in graph.neighbours(edge_id) {
+                let neighbour_midpoint = graph.geometry(neighbour_id).midpoint();
+                let neighbour_distance = distance
+                    + na::distance(&midpoint, &neighbour_midpoint)
+                    + terrain_cost(cost, neighbour_midpoint, midpoint);
+                if neighbour_distance < distances[index(neighbour_id)] {
+                    distances[index(neighbour_id)] = neighbour_distance;
+                    next[index(neighbour_id)] = Some(edge_id);
+                    open_set.push((Reverse(FloatOrd(neighbour_distance)), neighbour_id));
+                }
+            }
+        }
+
+        Self {
+            targets,
+            distances,
+            next,
+        }
+    }
+
+    /// Returns a path from `source` to `target` following the flow field.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - starting point of the path.
+    ///
+    /// * `source_edges` - edges neighbouring to `source`, see
+    ///   [`crate::dijkstra::PointContext::new`].
+    ///
+    /// * `target` - end point of the path. It must lie inside the target
+    ///   triangle of the flow field.
+    ///
+    /// Returns None if the target triangle is unreachable from `source`.
+    pub(crate) fn path(
+        &self,
+        graph: &VisibilityGraph,
+        source: Point<f32>,
+        source_edges: &[u32],
+        target: Point<f32>,
+    ) -> Option<Path> {
+        if source_edges
+            .iter()
+            .filter(|edge_id| self.targets.contains(edge_id))
+            .take(2)
+            .count()
+            >= 2
+        {
+            // Both points are in the target triangle.
+            return Some(Path::straight(Vec2::from(source), Vec2::from(target)));
+        }
+
+        let (_, start) = source_edges
+            .iter()
+            .copied()
+            .map(|edge_id| {
+                let distance = self.distances[index(edge_id)]
+                    + na::distance(&source, &graph.geometry(edge_id).midpoint());
+                (FloatOrd(distance), edge_id)
+            })
+            .filter(|(FloatOrd(distance), _)| distance.is_finite())
+            .min()?;
+
+        let mut edge_id = Some(start);
+        let mut funnel = Funnel::new(source);
+        let mut eye = source;
+        while let Some(current) = edge_id {
+            let geometry = graph.geometry(current);
+            funnel = funnel.extended(orient(eye, geometry.segment()));
+            eye = geometry.midpoint();
+            edge_id = self.next[index(current)];
+        }
+        Some(funnel.closed(target))
+    }
+}
+
+fn index(edge_id: u32) -> usize {
+    edge_id.try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use parry2d::shape::Segment;
+
+    use super::*;
+
+    #[test]
+    fn test_flow_field() {
+        // Two triangles sharing edge `c`:
+        //
+        //  (0, 2) ---- (2, 2)
+        //    |  \  B     |
+        //    | A  \      |
+        //  (0, 0) ---- (2, 0)
+        let mut graph = VisibilityGraph::new();
+        let a_0 = graph.new_node(Segment::new(Point::new(0., 0.), Point::new(2., 0.)));
+        let a_1 = graph.new_node(Segment::new(Point::new(0., 0.), Point::new(0., 2.)));
+        let c = graph.new_node(Segment::new(Point::new(2., 0.), Point::new(0., 2.)));
+        let b_0 = graph.new_node(Segment::new(Point::new(0., 2.), Point::new(2., 2.)));
+        let b_1 = graph.new_node(Segment::new(Point::new(2., 2.), Point::new(2., 0.)));
+        for [edge_id, neighbour_a, neighbour_b] in [
+            [a_0, a_1, c],
+            [a_1, c, a_0],
+            [c, a_0, a_1],
+            [c, b_0, b_1],
+            [b_0, b_1, c],
+            [b_1, c, b_0],
+        ] {
+            graph.add_neighbours(edge_id, neighbour_a, neighbour_b);
+        }
+
+        let field = FlowField::new(&graph, [c, b_0, b_1], None);
+        assert_eq!(field.next[index(c)], None);
+        assert_eq!(field.next[index(a_0)], Some(c));
+        assert_eq!(field.next[index(a_1)], Some(c));
+
+        let path = field
+            .path(
+                &graph,
+                Point::new(0.2, 0.5),
+                &[a_0, a_1, c],
+                Point::new(1.8, 1.5),
+            )
+            .unwrap();
+        assert_eq!(
+            path.waypoints(),
+            &[Vec2::new(1.8, 1.5), Vec2::new(0.2, 0.5)]
+        );
+
+        let path = field
+            .path(
+                &graph,
+                Point::new(1.5, 1.8),
+                &[c, b_0, b_1],
+                Point::new(1.8, 1.5),
+            )
+            .unwrap();
+        assert_eq!(path.waypoints().len(), 2);
+    }
+}
@@ -1,4 +1,4 @@
-use std::{ops::Deref, sync::Arc};
+use std::sync::Arc;
 
 use bevy::{
     prelude::*,
@@ -10,11 +10,19 @@ use de_core::{
     objects::{ObjectType, StaticSolid},
     state::AppState,
 };
-use de_map::size::MapBounds;
+use de_map::{heightmap::Heightmap, size::MapBounds, zones::TerrainZones};
 use de_objects::SolidObjects;
+use de_terrain::{TerrainCollider, TerrainDeformedEvent};
+use enum_map::{enum_map, EnumMap};
 use futures_lite::future;
+use parry2d::bounding_volume::Aabb;
 
-use crate::{exclusion::ExclusionArea, finder::PathFinder, triangulation::triangulate};
+use crate::{
+    cost::TerrainCost, elevation::Elevation, exclusion::ExclusionArea, finder::PathFinder,
+    triangulation::triangulate, zones::MovementClass,
+};
+
+type PathFinders = EnumMap<MovementClass, PathFinder>;
 
 /// This plugin registers systems which automatically update the path finder
 /// when static solid objects are added or removed from the world.
@@ -22,7 +30,11 @@ use crate::{exclusion::ExclusionArea, finder::PathFinder, triangulation::triangu
 /// # World Update
 ///
 /// * Each solid static object's ichnography (a convex polygon) is offset by
-///   some amount. See [`crate::exclusion`].
+///   some amount. See [`crate::exclusion`]. Flying objects ignore static
+///   objects, thus this step is skipped for them.
+///
+/// * Terrain zones impassable for a movement class (e.g. water for ground
+///   objects) are offset by the same amount. See [`crate::zones`].
 ///
 /// * Overlapping polygons from the previous steps are merged -- their convex
 ///   hull is used. These are called exclusion areas.
@@ -37,6 +49,16 @@ use crate::{exclusion::ExclusionArea, finder::PathFinder, triangulation::triangu
 /// * A visibility sub-graph is created. The each triangle edge is connected
 ///   with all neighboring triangle edges. See
 ///   [`crate::finder::PathFinder::from_triangles`].
+///
+/// * Terrain elevation of the map (if any) and passable but difficult terrain
+///   zones are attached to the path finder so that paths over steep slopes or
+///   craters are penalized. See [`crate::cost`].
+///
+/// All the steps above are done separately for each
+/// [`crate::zones::MovementClass`].
+///
+/// Terrain elevation is re-sampled inside areas of deformed terrain (e.g.
+/// craters after explosions) and the path finders are updated afterwards.
 pub struct FinderPlugin;
 
 impl Plugin for FinderPlugin {
@@ -57,12 +79,20 @@ impl Plugin for FinderPlugin {
                     .run_if(in_state(GameState::Playing))
                     .in_set(FinderSet::CheckUpdated),
             )
+            .add_system(
+                rebake
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<TerrainDeformedEvent>())
+                    .in_set(FinderSet::Rebake),
+            )
             .add_system(
                 update
                     .in_base_set(GameSet::PostUpdate)
                     .run_if(in_state(GameState::Playing))
                     .after(FinderSet::CheckUpdated)
-                    .after(FinderSet::CheckRemoved),
+                    .after(FinderSet::CheckRemoved)
+                    .after(FinderSet::Rebake),
             )
             .add_system(
                 check_update_result
@@ -78,6 +108,7 @@ pub(crate) enum FinderSet {
     UpdateFinder,
     CheckRemoved,
     CheckUpdated,
+    Rebake,
 }
 
 /// This event is sent whenever the path finder is updated.
@@ -87,30 +118,28 @@ pub(crate) enum FinderSet {
 pub(crate) struct PathFinderUpdated;
 
 #[derive(Clone, Resource)]
-pub(crate) struct FinderRes(Arc<PathFinder>);
+pub(crate) struct FinderRes(Arc<PathFinders>);
 
 impl FinderRes {
-    fn new(finder: PathFinder) -> Self {
-        Self(Arc::new(finder))
+    fn new(finders: PathFinders) -> Self {
+        Self(Arc::new(finders))
     }
 
-    fn update(&mut self, finder: PathFinder) {
-        self.0 = Arc::new(finder);
+    fn update(&mut self, finders: PathFinders) {
+        self.0 = Arc::new(finders);
     }
-}
-
-impl Deref for FinderRes {
-    type Target = PathFinder;
 
-    fn deref(&self) -> &Self::Target {
-        self.0.as_ref()
+    /// Returns the path finder for objects of a movement class.
+    pub(crate) fn get(&self, class: MovementClass) -> &PathFinder {
+        &self.0[class]
     }
 }
 
 #[derive(Resource)]
 struct UpdateFinderState {
     invalid: bool,
-    task: Option<Task<PathFinder>>,
+    task: Option<Task<PathFinders>>,
+    terrain: EnumMap<MovementClass, ClassTerrain>,
 }
 
 impl UpdateFinderState {
@@ -134,12 +163,26 @@ impl UpdateFinderState {
             })
             .collect();
 
+        let terrain = self.terrain.clone();
         let pool = AsyncComputeTaskPool::get();
-        self.task = Some(pool.spawn(async move { create_finder(bounds, exclusions) }));
+        self.task = Some(pool.spawn(async move {
+            enum_map! {
+                class => {
+                    let terrain: &ClassTerrain = &terrain[class];
+                    let mut exclusions = if class.avoids_solids() {
+                        exclusions.clone()
+                    } else {
+                        Vec::new()
+                    };
+                    exclusions.extend(terrain.exclusions.iter().cloned());
+                    create_finder(bounds, exclusions).with_cost(terrain.cost.clone())
+                }
+            }
+        }));
         self.invalid = false;
     }
 
-    fn check_result(&mut self) -> Option<PathFinder> {
+    fn check_result(&mut self) -> Option<PathFinders> {
         let finder = self
             .task
             .as_mut()
@@ -156,8 +199,51 @@ impl Default for UpdateFinderState {
         Self {
             invalid: true,
             task: None,
+            terrain: EnumMap::default(),
+        }
+    }
+}
+
+/// Terrain as seen by path finders of a single movement class.
+#[derive(Clone, Default)]
+struct ClassTerrain {
+    /// Exclusion areas of impassable terrain zones.
+    exclusions: Vec<ExclusionArea>,
+    cost: Option<Arc<TerrainCost>>,
+}
+
+impl ClassTerrain {
+    fn new(
+        class: MovementClass,
+        bounds: MapBounds,
+        heightmap: Option<&Heightmap>,
+        zones: &TerrainZones,
+    ) -> Self {
+        let elevation = match class {
+            MovementClass::Ground => heightmap.map(|heightmap| Elevation::new(bounds, heightmap)),
+            // Flying objects are not slowed down by slopes.
+            MovementClass::Air => None,
+        };
+
+        Self {
+            exclusions: class.exclusions(zones),
+            cost: TerrainCost::new(elevation, class.costly_zones(zones)).map(Arc::new),
         }
     }
+
+    /// Re-samples terrain elevation inside deformed areas of the map.
+    fn rebake<F>(&mut self, class: MovementClass, bounds: MapBounds, areas: &[Aabb], height: F)
+    where
+        F: Fn(Vec2) -> f32,
+    {
+        // Flying objects are not slowed down by slopes.
+        if class == MovementClass::Air {
+            return;
+        }
+
+        let cost = TerrainCost::rebaked(self.cost.as_deref(), bounds, areas, height);
+        self.cost = Some(Arc::new(cost));
+    }
 }
 
 type ChangedQuery<'world, 'state> =
@@ -167,8 +253,23 @@ fn setup_loading(mut commands: Commands) {
     commands.init_resource::<UpdateFinderState>();
 }
 
-fn setup_playing(mut commands: Commands, bounds: Res<MapBounds>) {
-    commands.insert_resource(FinderRes::new(PathFinder::new(bounds.as_ref())));
+fn setup_playing(
+    mut commands: Commands,
+    mut state: ResMut<UpdateFinderState>,
+    bounds: Res<MapBounds>,
+    heightmap: Option<Res<Heightmap>>,
+    zones: Option<Res<TerrainZones>>,
+) {
+    let zones = zones.as_deref().cloned().unwrap_or_default();
+    state.terrain = enum_map! {
+        class => ClassTerrain::new(class, *bounds, heightmap.as_deref(), &zones),
+    };
+
+    // Impassable zones are included once the finders are updated.
+    let finders = enum_map! {
+        class => PathFinder::new(bounds.as_ref()).with_cost(state.terrain[class].cost.clone()),
+    };
+    commands.insert_resource(FinderRes::new(finders));
 }
 
 fn cleanup(mut commands: Commands) {
@@ -191,6 +292,21 @@ fn check_updated(mut state: ResMut<UpdateFinderState>, changed: ChangedQuery) {
     }
 }
 
+fn rebake(
+    mut state: ResMut<UpdateFinderState>,
+    bounds: Res<MapBounds>,
+    mut events: EventReader<TerrainDeformedEvent>,
+    terrain: TerrainCollider,
+) {
+    let areas: Vec<Aabb> = events.iter().map(|event| *event.area()).collect();
+    for (class, class_terrain) in state.terrain.iter_mut() {
+        class_terrain.rebake(class, *bounds, areas.as_slice(), |point| {
+            terrain.elevation(point)
+        });
+    }
+    state.invalidate();
+}
+
 fn update(
     mut state: ResMut<UpdateFinderState>,
     bounds: Res<MapBounds>,
@@ -208,9 +324,9 @@ fn check_update_result(
     mut finder_res: ResMut<FinderRes>,
     mut pf_updated: EventWriter<PathFinderUpdated>,
 ) {
-    if let Some(finder) = state.check_result() {
-        info!("Inserting updated path finder");
-        finder_res.update(finder);
+    if let Some(finders) = state.check_result() {
+        info!("Inserting updated path finders");
+        finder_res.update(finders);
         pf_updated.send(PathFinderUpdated);
     }
 }
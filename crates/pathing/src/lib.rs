@@ -3,9 +3,12 @@
 //! game map.
 
 mod chain;
+mod cost;
 mod dijkstra;
+mod elevation;
 mod exclusion;
 mod finder;
+mod flow;
 mod fplugin;
 mod funnel;
 mod geometry;
@@ -15,6 +18,7 @@ mod pplugin;
 mod query;
 mod triangulation;
 mod utils;
+mod zones;
 
 use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
 pub use exclusion::ExclusionArea;
@@ -24,6 +28,7 @@ pub use path::ScheduledPath;
 use pplugin::PathingPlugin;
 pub use pplugin::UpdateEntityPath;
 pub use query::{PathQueryProps, PathTarget};
+pub use zones::MovementClass;
 
 pub struct PathingPluginGroup;
 
@@ -4,14 +4,22 @@ use bevy::{
     tasks::{AsyncComputeTaskPool, Task},
 };
 use de_core::{
-    baseset::GameSet, gamestate::GameState, objects::MovableSolid, projection::ToFlat,
+    baseset::GameSet,
+    events::ResendEventPlugin,
+    gamestate::GameState,
+    objects::{MovableSolid, ObjectType},
+    projection::ToFlat,
     state::AppState,
 };
+use de_objects::SolidObjects;
+use enum_map::EnumMap;
 use futures_lite::future;
 
 use crate::{
+    finder::FLOW_FIELD_THRESHOLD,
     fplugin::{FinderRes, FinderSet, PathFinderUpdated},
     path::{Path, ScheduledPath},
+    zones::MovementClass,
     PathQueryProps, PathTarget,
 };
 
@@ -29,11 +37,18 @@ const TARGET_TOLERANCE: f32 = 2.;
 ///   [`crate::dijkstra`]. Funnel algorithm is embedded into the algorithm so
 ///   path funneling can be gradually applied during the graph traversal. See
 ///   [`crate::funnel`].
+///
+/// * When many entities are sent to the same area of the map at once, a
+///   single flow field is shared by all of them instead. See [`crate::flow`].
+///
+/// * The path finder of the entity's movement class is used. See
+///   [`crate::zones::MovementClass`].
 pub struct PathingPlugin;
 
 impl Plugin for PathingPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<UpdateEntityPath>()
+            .add_plugin(ResendEventPlugin::<UpdateEntityPath>::default())
             .add_system(setup.in_schedule(OnEnter(AppState::InGame)))
             .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
             .add_system(
@@ -85,6 +100,8 @@ enum PathingSet {
 
 /// This event triggers computation of shortest path to a target and
 /// replacement / insertion of this path to the entity.
+///
+/// Events sent while the game is loading are handled once it is started.
 pub struct UpdateEntityPath {
     entity: Entity,
     target: PathTarget,
@@ -112,19 +129,61 @@ impl UpdateEntityPath {
 #[derive(Default, Resource)]
 struct UpdatePathsState {
     tasks: AHashMap<Entity, UpdatePathTask>,
+    batches: Vec<UpdateBatchTask>,
 }
 
 impl UpdatePathsState {
     fn contains(&self, entity: Entity) -> bool {
-        self.tasks.contains_key(&entity)
+        self.tasks.contains_key(&entity) || self.batches.iter().any(|batch| batch.contains(entity))
     }
 
-    fn spawn_new(&mut self, finder: FinderRes, entity: Entity, source: Vec2, target: PathTarget) {
+    fn spawn_new(
+        &mut self,
+        finder: FinderRes,
+        class: MovementClass,
+        entity: Entity,
+        source: Vec2,
+        target: PathTarget,
+    ) {
+        self.cancel_batched(entity);
         let pool = AsyncComputeTaskPool::get();
-        let task = pool.spawn(async move { finder.find_path(source, target) });
+        let task = pool.spawn(async move { finder.get(class).find_path(source, target) });
         self.tasks.insert(entity, UpdatePathTask::new(task));
     }
 
+    /// Spawns a single task finding paths of all `requests`. This is more
+    /// efficient than many individual tasks when many entities are sent to
+    /// the same area of the map, see [`PathFinder::find_paths`].
+    ///
+    /// [`PathFinder::find_paths`]: crate::finder::PathFinder::find_paths
+    fn spawn_batch(
+        &mut self,
+        finder: FinderRes,
+        class: MovementClass,
+        requests: Vec<(Entity, Vec2, PathTarget)>,
+    ) {
+        for &(entity, _, _) in &requests {
+            self.tasks.remove(&entity);
+            self.cancel_batched(entity);
+        }
+
+        let (entities, requests): (Vec<Entity>, Vec<(Vec2, PathTarget)>) = requests
+            .into_iter()
+            .map(|(entity, source, target)| (entity, (source, target)))
+            .unzip();
+        let pool = AsyncComputeTaskPool::get();
+        let task = pool.spawn(async move { finder.get(class).find_paths(&requests) });
+        self.batches.push(UpdateBatchTask::new(entities, task));
+    }
+
+    /// Makes sure that a result of an older batch does not override a newer
+    /// path of the entity.
+    fn cancel_batched(&mut self, entity: Entity) {
+        for batch in self.batches.iter_mut() {
+            batch.cancel(entity);
+        }
+    }
+
     fn check_results(&mut self) -> Vec<(Entity, Option<Path>)> {
         let mut results = Vec::new();
         self.tasks.retain(|&entity, task| match task.check() {
@@ -134,11 +193,57 @@ impl UpdatePathsState {
             }
             UpdatePathState::Processing => true,
         });
+        self.batches.retain_mut(|batch| match batch.check() {
+            Some(batch_results) => {
+                results.extend(batch_results);
+                false
+            }
+            None => true,
+        });
 
         results
     }
 }
 
+struct UpdateBatchTask {
+    /// Entities in the order of the requests. Cancelled entities are None.
+    entities: Vec<Option<Entity>>,
+    task: Task<Vec<Option<Path>>>,
+}
+
+impl UpdateBatchTask {
+    fn new(entities: Vec<Entity>, task: Task<Vec<Option<Path>>>) -> Self {
+        Self {
+            entities: entities.into_iter().map(Some).collect(),
+            task,
+        }
+    }
+
+    fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&Some(entity))
+    }
+
+    fn cancel(&mut self, entity: Entity) {
+        for slot in self.entities.iter_mut() {
+            if *slot == Some(entity) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Returns paths of all not cancelled entities once the task is finished.
+    fn check(&mut self) -> Option<Vec<(Entity, Option<Path>)>> {
+        let paths = future::block_on(future::poll_once(&mut self.task))?;
+        Some(
+            self.entities
+                .iter()
+                .zip(paths)
+                .filter_map(|(entity, path)| entity.map(|entity| (entity, path)))
+                .collect(),
+        )
+    }
+}
+
 struct UpdatePathTask(Task<Option<Path>>);
 
 impl UpdatePathTask {
@@ -169,10 +274,17 @@ fn cleanup(mut commands: Commands) {
 
 fn update_existing_paths(
     finder: Res<FinderRes>,
+    solids: SolidObjects,
     mut state: ResMut<UpdatePathsState>,
-    entities: Query<(Entity, &Transform, &PathTarget, Option<&ScheduledPath>)>,
+    entities: Query<(
+        Entity,
+        &Transform,
+        &ObjectType,
+        &PathTarget,
+        Option<&ScheduledPath>,
+    )>,
 ) {
-    for (entity, transform, target, path) in entities.iter() {
+    for (entity, transform, &object_type, target, path) in entities.iter() {
         let position = transform.translation.to_flat();
         if path.is_none() && !state.contains(entity) {
             let current_distance = position.distance(target.location());
@@ -191,7 +303,8 @@ fn update_existing_paths(
             target.permanent(),
         );
 
-        state.spawn_new(finder.clone(), entity, position, new_target);
+        let class = MovementClass::of(solids.get(object_type));
+        state.spawn_new(finder.clone(), class, entity, position, new_target);
     }
 }
 
@@ -199,18 +312,30 @@ fn update_requested_paths(
     mut commands: Commands,
     finder: Res<FinderRes>,
     mut state: ResMut<UpdatePathsState>,
+    solids: SolidObjects,
     mut events: EventReader<UpdateEntityPath>,
-    entities: Query<&Transform, With<MovableSolid>>,
+    entities: Query<(&Transform, &ObjectType), With<MovableSolid>>,
 ) {
+    let mut requests: EnumMap<MovementClass, Vec<_>> = EnumMap::default();
     for event in events.iter() {
-        if let Ok(transform) = entities.get(event.entity()) {
+        if let Ok((transform, &object_type)) = entities.get(event.entity()) {
             commands.entity(event.entity()).insert(event.target());
-            state.spawn_new(
-                finder.clone(),
+            let class = MovementClass::of(solids.get(object_type));
+            requests[class].push((
                 event.entity(),
                 transform.translation.to_flat(),
                 event.target(),
-            );
+            ));
+        }
+    }
+
+    for (class, requests) in requests {
+        if requests.len() >= FLOW_FIELD_THRESHOLD {
+            state.spawn_batch(finder.clone(), class, requests);
+        } else {
+            for (entity, source, target) in requests {
+                state.spawn_new(finder.clone(), class, entity, source, target);
+            }
         }
     }
 }
@@ -6,6 +6,7 @@ pub struct PathTarget {
     location: Vec2,
     properties: PathQueryProps,
     permanent: bool,
+    speed: f32,
 }
 
 impl PathTarget {
@@ -24,9 +25,26 @@ impl PathTarget {
             location,
             properties,
             permanent,
+            speed: 1.,
         }
     }
 
+    /// Limits speed of the entity while it follows the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `speed` - fraction of the maximum speed of the entity.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `speed` is not a number in the interval (0, 1].
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        debug_assert!(speed > 0.);
+        debug_assert!(speed <= 1.);
+        self.speed = speed;
+        self
+    }
+
     pub fn location(&self) -> Vec2 {
         self.location
     }
@@ -38,6 +56,11 @@ impl PathTarget {
     pub fn permanent(&self) -> bool {
         self.permanent
     }
+
+    /// Speed limit as a fraction of the maximum speed of the entity.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -0,0 +1,133 @@
+//! This module contains handling of special terrain zones (water, cliffs,
+//! craters and similar) by the path finder.
+
+use de_map::zones::{TerrainZones, ZoneKind};
+use de_objects::SolidObject;
+use enum_map::Enum;
+use parry2d::{math::Point, shape::ConvexPolygon};
+
+use crate::{cost::CostlyZone, exclusion::ExclusionArea};
+
+/// Each meter travelled through a crater costs as much as this many meters
+/// travelled over ordinary terrain.
+const CRATER_COST: f32 = 3.;
+
+/// Movement capabilities of an object. Separate path finder is used for each
+/// class since terrain zones affect them differently.
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovementClass {
+    /// Objects moving over the terrain surface. Water and cliffs are
+    /// impassable and craters slow them down.
+    Ground,
+    /// Flying objects unaffected by the terrain and by ground obstacles
+    /// (e.g. buildings).
+    Air,
+}
+
+impl MovementClass {
+    pub fn of(solid: &SolidObject) -> Self {
+        if solid.flight().is_some() {
+            Self::Air
+        } else {
+            Self::Ground
+        }
+    }
+
+    /// Returns true if static solid objects (e.g. buildings) are obstacles
+    /// for objects of this class.
+    pub(crate) fn avoids_solids(self) -> bool {
+        match self {
+            Self::Ground => true,
+            Self::Air => false,
+        }
+    }
+
+    /// Returns None if objects of this class cannot enter a zone of the given
+    /// kind. Otherwise, it returns cost of each meter travelled through the
+    /// zone relative to ordinary terrain.
+    fn zone_cost(self, kind: ZoneKind) -> Option<f32> {
+        match self {
+            Self::Ground => match kind {
+                ZoneKind::Water | ZoneKind::Cliff => None,
+                ZoneKind::Crater => Some(CRATER_COST),
+            },
+            Self::Air => Some(1.),
+        }
+    }
+
+    /// Returns exclusion areas of all zones impassable for this class.
+    pub(crate) fn exclusions(self, zones: &TerrainZones) -> Vec<ExclusionArea> {
+        zones
+            .zones()
+            .iter()
+            .filter(|zone| self.zone_cost(zone.kind()).is_none())
+            .map(ExclusionArea::from_zone)
+            .collect()
+    }
+
+    /// Returns all zones which are passable but expensive for this class.
+    pub(crate) fn costly_zones(self, zones: &TerrainZones) -> Vec<CostlyZone> {
+        zones
+            .zones()
+            .iter()
+            .filter_map(|zone| {
+                let cost = self.zone_cost(zone.kind())?;
+                if cost <= 1. {
+                    return None;
+                }
+
+                let vertices: Vec<Point<f32>> = zone.vertices().iter().map(|&v| v.into()).collect();
+                let polygon = ConvexPolygon::from_convex_polyline(vertices).unwrap();
+                Some(CostlyZone::new(polygon, cost - 1.))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::player::Player;
+    use de_map::{
+        map::Map,
+        meta::MapMetadata,
+        size::MapBounds,
+        zones::{TerrainZone, ZoneKind},
+    };
+    use glam::Vec2;
+
+    use super::*;
+
+    #[test]
+    fn test_zones() {
+        let mut map = Map::empty(MapMetadata::new(
+            "Test".into(),
+            MapBounds::new(Vec2::splat(200.)),
+            Player::Player2,
+        ));
+        for (kind, offset) in [
+            (ZoneKind::Water, Vec2::new(-50., 0.)),
+            (ZoneKind::Crater, Vec2::new(0., 0.)),
+            (ZoneKind::Cliff, Vec2::new(50., 0.)),
+        ] {
+            map.insert_zone(TerrainZone::new(
+                kind,
+                vec![
+                    offset + Vec2::new(-10., -10.),
+                    offset + Vec2::new(10., -10.),
+                    offset + Vec2::new(0., 10.),
+                ],
+            ));
+        }
+
+        let exclusions = MovementClass::Ground.exclusions(map.zones());
+        assert_eq!(exclusions.len(), 2);
+        assert!(exclusions[0].points()[0].x < -60.);
+        assert_eq!(MovementClass::Ground.costly_zones(map.zones()).len(), 1);
+
+        assert!(MovementClass::Air.exclusions(map.zones()).is_empty());
+        assert!(MovementClass::Air.costly_zones(map.zones()).is_empty());
+
+        assert!(MovementClass::Ground.avoids_solids());
+        assert!(!MovementClass::Air.avoids_solids());
+    }
+}
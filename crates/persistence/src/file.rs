@@ -0,0 +1,513 @@
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bincode::{
+    config::{Configuration, LittleEndian, Varint},
+    error::DecodeError,
+    serde::{decode_from_slice, encode_into_std_write},
+};
+use de_core::{
+    gconfig::{GameConfig, LocalPlayers},
+    objects::{ActiveObjectType, ObjectType, UnitType},
+    player::{Player, Teams},
+};
+use de_pathing::{PathQueryProps, PathTarget};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// File name suffix of saved games.
+pub const SAVE_FILE_SUFFIX: &str = ".desave";
+const MAGIC: &[u8; 8] = b"DESAVEGM";
+const FORMAT_VERSION: u16 = 4;
+const BINCODE_CONF: Configuration<LittleEndian, Varint> = bincode::config::standard();
+
+/// Full state of a saved single-player game.
+///
+/// The file starts with [`MAGIC`] and a format version, followed by the
+/// bincode encoded game. Saves of other format versions are rejected.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct SaveFile {
+    map_path: PathBuf,
+    /// Seed of a randomly generated map, see [`GameConfig::map_seed`].
+    map_seed: Option<u64>,
+    max_player: Player,
+    playable: Player,
+    teams: Teams,
+    objects: Vec<SavedObject>,
+}
+
+impl SaveFile {
+    pub(crate) fn new(config: &GameConfig, objects: Vec<SavedObject>) -> Self {
+        Self {
+            map_path: config.map_path().to_owned(),
+            map_seed: config.map_seed(),
+            max_player: config.max_player(),
+            playable: config.locals().playable(),
+            teams: config.teams().clone(),
+            objects,
+        }
+    }
+
+    /// Decodes and validates a game from the content of a save file.
+    pub fn decode(bytes: &[u8]) -> Result<Self, SaveFileError> {
+        let Some(bytes) = bytes.strip_prefix(MAGIC.as_slice()) else {
+            return Err(SaveFileError::NotSave);
+        };
+
+        let (version, offset): (u16, usize) = bincode::decode_from_slice(bytes, BINCODE_CONF)?;
+        if version != FORMAT_VERSION {
+            return Err(SaveFileError::Version(version));
+        }
+
+        let (save, len): (Self, usize) = decode_from_slice(&bytes[offset..], BINCODE_CONF)?;
+        if offset + len != bytes.len() {
+            return Err(SaveFileError::Invalid("trailing data".to_owned()));
+        }
+        save.validate()?;
+        Ok(save)
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        bincode::encode_into_std_write(FORMAT_VERSION, &mut data, BINCODE_CONF).unwrap();
+        encode_into_std_write(self, &mut data, BINCODE_CONF).unwrap();
+        data
+    }
+
+    fn validate(&self) -> Result<(), SaveFileError> {
+        if self.playable > self.max_player {
+            return Err(SaveFileError::Invalid(format!(
+                "playable player {} is larger than maximum number of players {}",
+                self.playable, self.max_player
+            )));
+        }
+        for (index, object) in self.objects.iter().enumerate() {
+            object.validate(self.max_player)?;
+            if object.attack.map_or(false, |enemy| {
+                enemy as usize == index || enemy as usize >= self.objects.len()
+            }) {
+                return Err(SaveFileError::Invalid(format!(
+                    "invalid attack order of object {index}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Path of the map of the game, see [`GameConfig::map_path`].
+    pub fn map_path(&self) -> &Path {
+        self.map_path.as_path()
+    }
+
+    /// Returns configuration of a game continuing the saved game.
+    pub fn game_config(&self) -> GameConfig {
+        let config = GameConfig::new(
+            self.map_path.clone(),
+            self.max_player,
+            LocalPlayers::new(self.playable),
+        )
+        .with_teams(self.teams.clone());
+        match self.map_seed {
+            Some(seed) => config.with_map_seed(seed),
+            None => config,
+        }
+    }
+
+    /// All objects (both active and inactive) of the game.
+    pub fn objects(&self) -> &[SavedObject] {
+        self.objects.as_slice()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SaveFileError {
+    #[error("not a saved game")]
+    NotSave,
+    #[error("unsupported save format version {0}")]
+    Version(u16),
+    #[error("invalid save data: {0}")]
+    Decode(#[from] DecodeError),
+    #[error("invalid saved game: {0}")]
+    Invalid(String),
+}
+
+/// A single object of a saved game.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct SavedObject {
+    object_type: ObjectType,
+    /// Owner of the object, None for inactive objects.
+    player: Option<Player>,
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    /// Fraction of remaining health, None for inactive objects.
+    health: Option<f32>,
+    /// Target of the current move order of a unit.
+    target: Option<SavedTarget>,
+    /// Energy stored in the battery of an active object in joules.
+    energy: Option<f64>,
+    /// Index of the object attacked by this object in [`SaveFile::objects`].
+    attack: Option<u32>,
+    /// Units in the assembly line of a factory in the order of their
+    /// delivery.
+    production: Vec<UnitType>,
+    /// Construction progress of a building which is not yet fully
+    /// constructed.
+    construction: Option<f32>,
+}
+
+impl SavedObject {
+    pub(crate) fn new(
+        object_type: ObjectType,
+        player: Option<Player>,
+        transform: &Transform,
+        health: Option<f32>,
+        target: Option<&PathTarget>,
+    ) -> Self {
+        Self {
+            object_type,
+            player,
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            health,
+            target: target.map(SavedTarget::new),
+            energy: None,
+            attack: None,
+            production: Vec::new(),
+            construction: None,
+        }
+    }
+
+    /// Sets energy stored in the battery of the object.
+    pub(crate) fn with_energy(mut self, energy: f64) -> Self {
+        self.energy = Some(energy);
+        self
+    }
+
+    /// Sets the attack order of the object.
+    ///
+    /// # Arguments
+    ///
+    /// * `enemy` - index of the attacked object in [`SaveFile::objects`].
+    pub(crate) fn with_attack(mut self, enemy: u32) -> Self {
+        self.attack = Some(enemy);
+        self
+    }
+
+    /// Sets units in the assembly line of the object.
+    pub(crate) fn with_production(mut self, production: Vec<UnitType>) -> Self {
+        self.production = production;
+        self
+    }
+
+    /// Sets construction progress of an unfinished building.
+    pub(crate) fn with_construction(mut self, progress: f32) -> Self {
+        self.construction = Some(progress);
+        self
+    }
+
+    fn validate(&self, max_player: Player) -> Result<(), SaveFileError> {
+        let active = matches!(self.object_type, ObjectType::Active(_));
+        if active != self.player.is_some() || active != self.health.is_some() {
+            return Err(SaveFileError::Invalid(format!(
+                "owner or health of {} object does not match its type",
+                self.object_type
+            )));
+        }
+        if self.player.map_or(false, |player| player > max_player) {
+            return Err(SaveFileError::Invalid(
+                "object of an invalid player".to_owned(),
+            ));
+        }
+        if !Vec3::from_array(self.translation).is_finite()
+            || !Quat::from_array(self.rotation).is_normalized()
+        {
+            return Err(SaveFileError::Invalid(
+                "invalid object transform".to_owned(),
+            ));
+        }
+        if let Some(health) = self.health {
+            if !(0. ..=1.).contains(&health) {
+                return Err(SaveFileError::Invalid(format!(
+                    "invalid health fraction {health}"
+                )));
+            }
+        }
+        if let Some(ref target) = self.target {
+            target.validate()?;
+        }
+        if self.energy.map_or(false, |energy| {
+            !active || !energy.is_finite() || energy < 0.
+        }) {
+            return Err(SaveFileError::Invalid("invalid battery energy".to_owned()));
+        }
+        if !active && (self.attack.is_some() || !self.production.is_empty()) {
+            return Err(SaveFileError::Invalid(format!(
+                "orders of {} object does not match its type",
+                self.object_type
+            )));
+        }
+        if let Some(progress) = self.construction {
+            if !matches!(
+                self.object_type,
+                ObjectType::Active(ActiveObjectType::Building(_))
+            ) || !(0. ..1.).contains(&progress)
+            {
+                return Err(SaveFileError::Invalid(format!(
+                    "invalid construction progress {progress}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn object_type(&self) -> ObjectType {
+        self.object_type
+    }
+
+    /// Owner of an active object.
+    pub fn player(&self) -> Option<Player> {
+        self.player
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform {
+            translation: Vec3::from_array(self.translation),
+            rotation: Quat::from_array(self.rotation),
+            ..Default::default()
+        }
+    }
+
+    /// Remaining health of an active object as a fraction of its maximum
+    /// health.
+    pub fn health(&self) -> Option<f32> {
+        self.health
+    }
+
+    /// Target of the move order the object was executing.
+    pub fn target(&self) -> Option<PathTarget> {
+        self.target.as_ref().map(SavedTarget::to_target)
+    }
+
+    /// Energy stored in the battery of an active object in joules.
+    pub fn energy(&self) -> Option<f64> {
+        self.energy
+    }
+
+    /// Index of the object attacked by this object in
+    /// [`SaveFile::objects`].
+    pub fn attack(&self) -> Option<usize> {
+        self.attack.map(|enemy| enemy as usize)
+    }
+
+    /// Units in the assembly line of a factory in the order of their
+    /// delivery.
+    pub fn production(&self) -> &[UnitType] {
+        self.production.as_slice()
+    }
+
+    /// Construction progress of a building which is not yet fully
+    /// constructed.
+    pub fn construction(&self) -> Option<f32> {
+        self.construction
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct SavedTarget {
+    location: [f32; 2],
+    distance: f32,
+    max_distance: f32,
+    permanent: bool,
+}
+
+impl SavedTarget {
+    fn new(target: &PathTarget) -> Self {
+        Self {
+            location: target.location().to_array(),
+            distance: target.properties().distance(),
+            max_distance: target.properties().max_distance(),
+            permanent: target.permanent(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), SaveFileError> {
+        if !Vec2::from_array(self.location).is_finite()
+            || !self.distance.is_finite()
+            || self.distance < 0.
+            || self.max_distance.is_nan()
+            || self.max_distance < self.distance
+        {
+            return Err(SaveFileError::Invalid("invalid move order".to_owned()));
+        }
+        Ok(())
+    }
+
+    fn to_target(&self) -> PathTarget {
+        PathTarget::new(
+            Vec2::from_array(self.location),
+            PathQueryProps::new(self.distance, self.max_distance),
+            self.permanent,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::objects::{BuildingType, InactiveObjectType};
+
+    use super::*;
+
+    #[test]
+    fn test_save_file() {
+        let config = GameConfig::new(
+            "maps/test.dem",
+            Player::Player2,
+            LocalPlayers::new(Player::Player1),
+        )
+        .with_map_seed(7);
+        let target = PathTarget::new(
+            Vec2::new(1., 2.),
+            PathQueryProps::new(3., f32::INFINITY),
+            true,
+        );
+        let save = SaveFile::new(
+            &config,
+            vec![
+                SavedObject::new(
+                    ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)),
+                    Some(Player::Player2),
+                    &Transform::from_xyz(1., 0., -3.),
+                    Some(0.5),
+                    Some(&target),
+                )
+                .with_energy(1000.)
+                .with_attack(2),
+                SavedObject::new(
+                    ObjectType::Active(ActiveObjectType::Building(BuildingType::Base)),
+                    Some(Player::Player1),
+                    &Transform::IDENTITY,
+                    Some(1.),
+                    None,
+                )
+                .with_production(vec![UnitType::Attacker, UnitType::Attacker]),
+                SavedObject::new(
+                    ObjectType::Active(ActiveObjectType::Building(BuildingType::PowerHub)),
+                    Some(Player::Player1),
+                    &Transform::from_xyz(10., 0., 10.),
+                    Some(1.),
+                    None,
+                )
+                .with_construction(0.25),
+                SavedObject::new(
+                    ObjectType::Inactive(InactiveObjectType::Tree),
+                    None,
+                    &Transform::IDENTITY,
+                    None,
+                    None,
+                ),
+            ],
+        );
+
+        let data = save.encode();
+        let decoded = SaveFile::decode(&data).unwrap();
+        assert_eq!(decoded, save);
+        assert_eq!(decoded.map_path(), Path::new("maps/test.dem"));
+        assert_eq!(decoded.game_config().locals().playable(), Player::Player1);
+        assert_eq!(decoded.game_config().map_seed(), Some(7));
+
+        let unit = &decoded.objects()[0];
+        assert_eq!(unit.player(), Some(Player::Player2));
+        assert_eq!(unit.transform().translation, Vec3::new(1., 0., -3.));
+        assert_eq!(unit.health(), Some(0.5));
+        let decoded_target = unit.target().unwrap();
+        assert_eq!(decoded_target.location(), Vec2::new(1., 2.));
+        assert_eq!(decoded_target.properties().max_distance(), f32::INFINITY);
+        assert!(decoded_target.permanent());
+        assert_eq!(unit.energy(), Some(1000.));
+        assert_eq!(unit.attack(), Some(2));
+        assert!(unit.production().is_empty());
+
+        let base = &decoded.objects()[1];
+        assert_eq!(base.energy(), None);
+        assert_eq!(base.attack(), None);
+        assert_eq!(base.production(), &[UnitType::Attacker, UnitType::Attacker]);
+        assert_eq!(base.construction(), None);
+
+        assert_eq!(decoded.objects()[2].construction(), Some(0.25));
+
+        assert!(matches!(
+            SaveFile::decode(b"DEREPLAY"),
+            Err(SaveFileError::NotSave)
+        ));
+        assert!(matches!(
+            SaveFile::decode(&data[..data.len() - 1]),
+            Err(SaveFileError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_validation() {
+        let config = GameConfig::new(
+            "test.dem",
+            Player::Player2,
+            LocalPlayers::new(Player::Player1),
+        );
+        let save = SaveFile::new(
+            &config,
+            vec![SavedObject::new(
+                ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)),
+                Some(Player::Player3),
+                &Transform::IDENTITY,
+                Some(1.),
+                None,
+            )],
+        );
+        assert!(matches!(
+            SaveFile::decode(&save.encode()),
+            Err(SaveFileError::Invalid(_))
+        ));
+
+        let save = SaveFile::new(
+            &config,
+            vec![SavedObject::new(
+                ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)),
+                Some(Player::Player1),
+                &Transform::IDENTITY,
+                Some(1.),
+                None,
+            )
+            .with_attack(1)],
+        );
+        assert!(matches!(
+            SaveFile::decode(&save.encode()),
+            Err(SaveFileError::Invalid(_))
+        ));
+
+        for (object_type, progress) in [
+            (
+                ObjectType::Active(ActiveObjectType::Building(BuildingType::Base)),
+                1.,
+            ),
+            (
+                ObjectType::Active(ActiveObjectType::Unit(UnitType::Attacker)),
+                0.5,
+            ),
+        ] {
+            let save = SaveFile::new(
+                &config,
+                vec![SavedObject::new(
+                    object_type,
+                    Some(Player::Player1),
+                    &Transform::IDENTITY,
+                    Some(1.),
+                    None,
+                )
+                .with_construction(progress)],
+            );
+            assert!(matches!(
+                SaveFile::decode(&save.encode()),
+                Err(SaveFileError::Invalid(_))
+            ));
+        }
+    }
+}
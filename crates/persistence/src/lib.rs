@@ -0,0 +1,29 @@
+//! Saving and loading of single-player games.
+//!
+//! The game is saved to a versioned file, see [`SaveFile`]. A saved game is
+//! continued by inserting [`LoadedGame`] before the game is started; the
+//! saved objects are spawned by `de_loader`.
+
+use bevy::{app::PluginGroupBuilder, prelude::PluginGroup};
+use load::LoadPlugin;
+use save::SavePlugin;
+
+pub use crate::{
+    file::{SaveFile, SaveFileError, SavedObject, SAVE_FILE_SUFFIX},
+    load::{list_saves, load_save, LoadError, LoadedGame, SaveEntry},
+    save::SaveGameEvent,
+};
+
+mod file;
+mod load;
+mod save;
+
+pub struct PersistencePluginGroup;
+
+impl PluginGroup for PersistencePluginGroup {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(SavePlugin)
+            .add(LoadPlugin)
+    }
+}
@@ -0,0 +1,112 @@
+use std::{cmp::Reverse, ops::Deref, time::SystemTime};
+
+use async_std::{
+    fs,
+    path::{Path, PathBuf},
+    stream::StreamExt,
+};
+use bevy::prelude::*;
+use de_core::{fs::saves_dir, state::AppState};
+use thiserror::Error;
+
+use crate::file::{SaveFile, SaveFileError, SAVE_FILE_SUFFIX};
+
+pub(crate) struct LoadPlugin;
+
+impl Plugin for LoadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(cleanup.in_schedule(OnExit(AppState::InGame)));
+    }
+}
+
+/// A saved game to be continued. Insert this resource together with
+/// [`SaveFile::game_config`] before [`AppState::InGame`] is entered and the
+/// saved objects are spawned instead of the objects of the map.
+///
+/// This resource is automatically removed when [`AppState::InGame`] is
+/// exited.
+#[derive(Resource)]
+pub struct LoadedGame(SaveFile);
+
+impl LoadedGame {
+    pub fn new(save: SaveFile) -> Self {
+        Self(save)
+    }
+}
+
+impl Deref for LoadedGame {
+    type Target = SaveFile;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// A save file available in [`saves_dir`].
+pub struct SaveEntry {
+    path: PathBuf,
+    modified: SystemTime,
+}
+
+impl SaveEntry {
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Time of the last modification of the file.
+    pub fn modified(&self) -> SystemTime {
+        self.modified
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LoadError {
+    #[error("saves directory cannot be established: {0}")]
+    Dir(#[from] de_core::fs::DirError),
+    #[error(transparent)]
+    Io(#[from] async_std::io::Error),
+    #[error(transparent)]
+    Save(#[from] SaveFileError),
+}
+
+/// Lists all saved games, the most recent first.
+pub async fn list_saves() -> Result<Vec<SaveEntry>, LoadError> {
+    let dir = saves_dir()?;
+    if !dir.is_dir().await {
+        return Ok(Vec::new());
+    }
+
+    let mut saves = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next().await {
+        let entry = entry?;
+        let path = entry.path();
+        let is_save = path.file_name().map_or(false, |name| {
+            name.to_string_lossy().ends_with(SAVE_FILE_SUFFIX)
+        });
+        if !is_save {
+            continue;
+        }
+
+        let metadata = entry.metadata().await?;
+        if metadata.is_file() {
+            saves.push(SaveEntry {
+                path,
+                modified: metadata.modified()?,
+            });
+        }
+    }
+
+    saves.sort_by_key(|save| Reverse(save.modified));
+    Ok(saves)
+}
+
+/// Reads and decodes a saved game.
+pub async fn load_save(path: &Path) -> Result<SaveFile, LoadError> {
+    let data = fs::read(path).await?;
+    Ok(SaveFile::decode(&data)?)
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<LoadedGame>();
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_std::fs;
+use bevy::{
+    prelude::*,
+    tasks::{IoTaskPool, Task},
+};
+use de_combat::Attacking;
+use de_construction::{AssemblyLine, UnderConstruction};
+use de_core::{
+    baseset::GameSet, fs::saves_dir, gamestate::GameState, gconfig::GameConfig,
+    objects::ObjectType, player::Player,
+};
+use de_energy::Battery;
+use de_gui::ToastEvent;
+use de_loc::Localize;
+use de_objects::Health;
+use de_pathing::PathTarget;
+use futures_lite::future;
+
+use crate::file::{SaveFile, SavedObject, SAVE_FILE_SUFFIX};
+
+pub(crate) struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveGameEvent>()
+            .add_system(
+                save.in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(on_event::<SaveGameEvent>()),
+            )
+            .add_system(check_task.run_if(resource_exists::<SaveTask>()));
+    }
+}
+
+/// Send this event to save the current game to a new file in
+/// [`saves_dir`]. Saving is meant for single-player games.
+pub struct SaveGameEvent;
+
+#[derive(Resource)]
+struct SaveTask(Task<anyhow::Result<()>>);
+
+type ObjectComponents<'a> = (
+    Entity,
+    &'a ObjectType,
+    Option<&'a Player>,
+    &'a Transform,
+    Option<&'a Health>,
+    Option<&'a PathTarget>,
+    (
+        Option<&'a Battery>,
+        Option<&'a Attacking>,
+        Option<&'a AssemblyLine>,
+        Option<&'a UnderConstruction>,
+    ),
+);
+
+fn save(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    task: Option<Res<SaveTask>>,
+    mut events: EventReader<SaveGameEvent>,
+    objects: Query<ObjectComponents>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    // Multiple events in a single frame lead to a single save.
+    events.clear();
+    if task.is_some() {
+        toasts.send(ToastEvent::new(localize.get("toast-game-saving")));
+        return;
+    }
+
+    // Attack orders refer to other objects by their index in the save.
+    let indices: HashMap<Entity, u32> = objects
+        .iter()
+        .enumerate()
+        .map(|(index, (entity, ..))| (entity, index as u32))
+        .collect();
+    let objects = objects
+        .iter()
+        .map(
+            |(
+                _,
+                &object_type,
+                player,
+                transform,
+                health,
+                target,
+                (battery, attacking, line, construction),
+            )| {
+                let mut object = SavedObject::new(
+                    object_type,
+                    player.copied(),
+                    transform,
+                    health.map(Health::fraction),
+                    target,
+                );
+                if let Some(battery) = battery {
+                    object = object.with_energy(battery.energy());
+                }
+                if let Some(&enemy) =
+                    attacking.and_then(|attacking| indices.get(&attacking.enemy()))
+                {
+                    object = object.with_attack(enemy);
+                }
+                if let Some(line) = line {
+                    object = object.with_production(line.queue().collect());
+                }
+                if let Some(construction) = construction {
+                    object = object.with_construction(construction.progress());
+                }
+                object
+            },
+        )
+        .collect();
+    let data = SaveFile::new(config.as_ref(), objects).encode();
+
+    info!("Saving the game.");
+    let task = IoTaskPool::get().spawn(store_save(data));
+    commands.insert_resource(SaveTask(task));
+}
+
+fn check_task(
+    mut commands: Commands,
+    mut task: ResMut<SaveTask>,
+    mut toasts: EventWriter<ToastEvent>,
+    localize: Res<Localize>,
+) {
+    let Some(result) = future::block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<SaveTask>();
+
+    match result {
+        Ok(()) => toasts.send(ToastEvent::new(localize.get("toast-game-saved"))),
+        Err(err) => {
+            error!("Failed to save the game: {err:?}");
+            toasts.send(ToastEvent::new(
+                localize.format("toast-game-saving-failed", &[("error", &err)]),
+            ));
+        }
+    }
+}
+
+async fn store_save(data: Vec<u8>) -> anyhow::Result<()> {
+    let dir = saves_dir()?;
+    fs::create_dir_all(&dir).await?;
+
+    // Milliseconds and a counter keep quickly repeated saves apart.
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let mut path = dir.join(format!("{timestamp}{SAVE_FILE_SUFFIX}"));
+    let mut counter = 1;
+    while path.exists().await {
+        path = dir.join(format!("{timestamp}-{counter}{SAVE_FILE_SUFFIX}"));
+        counter += 1;
+    }
+    fs::write(&path, data).await?;
+    info!("Game saved to {path:?}.");
+    Ok(())
+}
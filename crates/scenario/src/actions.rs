@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet,
+    cleanup::DespawnOnGameExit,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    gresult::GameResult,
+    objects::{ActiveObjectType, ObjectType},
+    projection::ToAltitude,
+    state::AppState,
+};
+use de_gui::ToastEvent;
+use de_map::{scenario::Action, size::MapBounds};
+use de_pathing::{PathQueryProps, PathTarget, UpdateEntityPath};
+use de_spawner::SpawnBundle;
+use glam::Vec2;
+
+use crate::{triggers::ActionEvent, ScenarioSet};
+
+/// Distance between neighboring units of a spawned wave.
+const WAVE_SPACING: f32 = 5.;
+
+pub(crate) struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            execute
+                .in_base_set(GameSet::Update)
+                .in_set(ScenarioSet::Actions)
+                .after(ScenarioSet::Triggers)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn execute(
+    mut commands: Commands,
+    conf: Res<GameConfig>,
+    bounds: Res<MapBounds>,
+    mut events: EventReader<ActionEvent>,
+    mut path_events: EventWriter<UpdateEntityPath>,
+    mut toasts: EventWriter<ToastEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for event in events.iter() {
+        match event.action() {
+            Action::SpawnWave {
+                player,
+                unit,
+                count,
+                position,
+                target,
+            } => {
+                if !conf.players().contains(*player) {
+                    warn!("Ignoring a wave of {player}, who is not in the game.");
+                    continue;
+                }
+
+                let object_type = ObjectType::Active(ActiveObjectType::Unit(*unit));
+                for point in formation(*position, *count, bounds.as_ref()) {
+                    let entity = commands
+                        .spawn((
+                            SpawnBundle::new(
+                                object_type,
+                                Transform::from_translation(point.to_msl()),
+                            ),
+                            *player,
+                            DespawnOnGameExit,
+                        ))
+                        .id();
+
+                    if let Some(target) = target {
+                        path_events.send(UpdateEntityPath::new(
+                            entity,
+                            PathTarget::new(*target, PathQueryProps::new(0., f32::INFINITY), false),
+                        ));
+                    }
+                }
+            }
+            Action::ShowObjective(text) => {
+                toasts.send(ToastEvent::new(text));
+            }
+            Action::EndGame(won) => {
+                commands.insert_resource(GameResult::finished(*won));
+                next_state.set(AppState::InMenu);
+            }
+        }
+    }
+}
+
+/// Returns positions of units of a wave arranged in a square grid centered
+/// at `position`. The positions are clamped to the map bounds.
+fn formation(position: Vec2, count: u32, bounds: &MapBounds) -> Vec<Vec2> {
+    let side = (count as f32).sqrt().ceil() as u32;
+    let offset = Vec2::splat((side - 1) as f32 * WAVE_SPACING / 2.);
+    (0..count)
+        .map(|index| {
+            let cell = Vec2::new((index % side) as f32, (index / side) as f32);
+            (position - offset + WAVE_SPACING * cell).clamp(bounds.min(), bounds.max())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formation() {
+        let bounds = MapBounds::new(Vec2::splat(100.));
+
+        assert_eq!(formation(Vec2::ZERO, 1, &bounds), vec![Vec2::ZERO]);
+        assert_eq!(
+            formation(Vec2::new(10., 20.), 3, &bounds),
+            vec![
+                Vec2::new(7.5, 17.5),
+                Vec2::new(12.5, 17.5),
+                Vec2::new(7.5, 22.5)
+            ]
+        );
+
+        let clamped = formation(Vec2::new(49., -49.), 9, &bounds);
+        assert_eq!(clamped.len(), 9);
+        assert!(clamped
+            .iter()
+            .all(|&point| point.x <= 50. && point.y >= -50.));
+        assert_eq!(clamped[0], Vec2::new(44., -50.));
+    }
+}
@@ -0,0 +1,33 @@
+//! Scripted scenarios (missions) of maps.
+//!
+//! A map may come with a [`de_map::scenario::Scenario`], i.e. a list of
+//! triggers. Each trigger has a condition, for example "all enemy buildings
+//! destroyed", and a list of actions, for example "spawn a wave of units" or
+//! "show an objective". The conditions are evaluated every frame and actions
+//! of newly satisfied triggers are executed.
+//!
+//! Scenarios are meant for single-player games and they are not continued
+//! in loaded saved games.
+
+use actions::ActionsPlugin;
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use triggers::TriggersPlugin;
+
+mod actions;
+mod triggers;
+
+pub struct ScenarioPluginGroup;
+
+impl PluginGroup for ScenarioPluginGroup {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(TriggersPlugin)
+            .add(ActionsPlugin)
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+enum ScenarioSet {
+    Triggers,
+    Actions,
+}
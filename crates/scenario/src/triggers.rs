@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet, diplomacy::Diplomacy, gamestate::GameState, gconfig::GameConfig,
+    player::Player, state::AppState,
+};
+use de_map::scenario::{Action, Condition, Scenario};
+use de_spawner::ObjectCounter;
+
+use crate::ScenarioSet;
+
+pub(crate) struct TriggersPlugin;
+
+impl Plugin for TriggersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ActionEvent>()
+            .add_system(
+                setup
+                    .in_schedule(OnEnter(GameState::Playing))
+                    .run_if(resource_exists::<Scenario>()),
+            )
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                evaluate
+                    .in_base_set(GameSet::Update)
+                    .in_set(ScenarioSet::Triggers)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_exists::<ScenarioState>()),
+            );
+    }
+}
+
+/// An action of a just fired trigger to be executed.
+pub(crate) struct ActionEvent(Action);
+
+impl ActionEvent {
+    pub(crate) fn action(&self) -> &Action {
+        &self.0
+    }
+}
+
+#[derive(Resource)]
+struct ScenarioState {
+    /// Time (since app start) at which the game started.
+    start: f32,
+    fired: Vec<bool>,
+}
+
+/// State of the game relevant to trigger conditions.
+struct Snapshot<'a> {
+    elapsed: f32,
+    fired: &'a [bool],
+    players: Vec<PlayerSnapshot>,
+}
+
+struct PlayerSnapshot {
+    player: Player,
+    enemy: bool,
+    buildings: u32,
+    units: u32,
+}
+
+impl<'a> Snapshot<'a> {
+    fn is_satisfied(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Elapsed(secs) => self.elapsed >= *secs,
+            Condition::EnemyBuildingsDestroyed => self
+                .players
+                .iter()
+                .all(|player| !player.enemy || player.buildings == 0),
+            Condition::BuildingsDestroyed(player) => {
+                self.player(*player).map_or(true, |p| p.buildings == 0)
+            }
+            Condition::UnitsDestroyed(player) => {
+                self.player(*player).map_or(true, |p| p.units == 0)
+            }
+            Condition::Fired(index) => self.fired[*index],
+            Condition::All(conditions) => conditions
+                .iter()
+                .all(|condition| self.is_satisfied(condition)),
+        }
+    }
+
+    /// Returns None for players not taking part in the game.
+    fn player(&self, player: Player) -> Option<&PlayerSnapshot> {
+        self.players.iter().find(|p| p.player == player)
+    }
+}
+
+fn setup(mut commands: Commands, time: Res<Time>, scenario: Res<Scenario>) {
+    info!(
+        "Starting a scenario with {} triggers.",
+        scenario.triggers().len()
+    );
+    commands.insert_resource(ScenarioState {
+        start: time.elapsed_seconds(),
+        fired: vec![false; scenario.triggers().len()],
+    });
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<ScenarioState>();
+}
+
+fn evaluate(
+    time: Res<Time>,
+    conf: Res<GameConfig>,
+    diplomacy: Res<Diplomacy>,
+    counter: Res<ObjectCounter>,
+    scenario: Res<Scenario>,
+    mut state: ResMut<ScenarioState>,
+    mut events: EventWriter<ActionEvent>,
+) {
+    let playable = conf.locals().playable();
+    let players = (1..=conf.max_player().to_num())
+        .map(|num| Player::try_from(num).unwrap())
+        .filter_map(|player| {
+            counter.player(player).map(|count| PlayerSnapshot {
+                player,
+                enemy: !diplomacy.are_allies(playable, player),
+                buildings: count.building_count(),
+                units: count.unit_count(),
+            })
+        })
+        .collect();
+
+    // Triggers fired during this frame are visible to other triggers only
+    // from the next frame.
+    let snapshot = Snapshot {
+        elapsed: time.elapsed_seconds() - state.start,
+        fired: &state.fired,
+        players,
+    };
+    let newly_fired: Vec<usize> = scenario
+        .triggers()
+        .iter()
+        .enumerate()
+        .filter(|&(index, trigger)| {
+            !snapshot.fired[index] && snapshot.is_satisfied(trigger.condition())
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    for index in newly_fired {
+        info!("Scenario trigger {index} fired.");
+        state.fired[index] = true;
+        for action in scenario.triggers()[index].actions() {
+            events.send(ActionEvent(action.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let snapshot = Snapshot {
+            elapsed: 12.,
+            fired: &[true, false],
+            players: vec![
+                PlayerSnapshot {
+                    player: Player::Player1,
+                    enemy: false,
+                    buildings: 2,
+                    units: 0,
+                },
+                PlayerSnapshot {
+                    player: Player::Player2,
+                    enemy: true,
+                    buildings: 0,
+                    units: 3,
+                },
+            ],
+        };
+
+        assert!(snapshot.is_satisfied(&Condition::Elapsed(10.)));
+        assert!(!snapshot.is_satisfied(&Condition::Elapsed(15.)));
+        assert!(snapshot.is_satisfied(&Condition::EnemyBuildingsDestroyed));
+        assert!(!snapshot.is_satisfied(&Condition::BuildingsDestroyed(Player::Player1)));
+        assert!(snapshot.is_satisfied(&Condition::UnitsDestroyed(Player::Player1)));
+        assert!(!snapshot.is_satisfied(&Condition::UnitsDestroyed(Player::Player2)));
+        // Players not taking part in the game have nothing left.
+        assert!(snapshot.is_satisfied(&Condition::UnitsDestroyed(Player::Player3)));
+        assert!(snapshot.is_satisfied(&Condition::Fired(0)));
+        assert!(!snapshot.is_satisfied(&Condition::Fired(1)));
+        assert!(snapshot.is_satisfied(&Condition::All(vec![
+            Condition::Fired(0),
+            Condition::Elapsed(5.),
+        ])));
+        assert!(!snapshot.is_satisfied(&Condition::All(vec![
+            Condition::Fired(0),
+            Condition::Fired(1),
+        ])));
+    }
+}
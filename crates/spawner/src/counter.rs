@@ -46,6 +46,7 @@ impl ObjectCounter {
 pub struct PlayerObjectCounter {
     building_count: Count,
     unit_count: Count,
+    lost_count: Count,
 }
 
 impl PlayerObjectCounter {
@@ -61,6 +62,15 @@ impl PlayerObjectCounter {
         self.unit_count.0
     }
 
+    /// Number of buildings and units of the player destroyed so far.
+    pub fn lost_count(&self) -> u32 {
+        self.lost_count.0
+    }
+
+    pub(crate) fn record_loss(&mut self) {
+        self.lost_count += 1;
+    }
+
     /// Updates number of objects by a given amount.
     ///
     /// # Panics
@@ -1,5 +1,11 @@
 use bevy::prelude::*;
-use de_core::{baseset::GameSet, objects::ObjectType, player::Player, state::AppState};
+use de_core::{
+    baseset::GameSet,
+    objects::{ActiveObjectType, ObjectType},
+    player::Player,
+    state::AppState,
+    summary::{MatchStat, MatchStatEvent},
+};
 use de_objects::Health;
 
 use crate::{ObjectCounter, SpawnerSet};
@@ -20,12 +26,18 @@ impl Plugin for DestroyerPlugin {
 fn destroy(
     mut commands: Commands,
     mut counter: ResMut<ObjectCounter>,
+    mut stat_events: EventWriter<MatchStatEvent>,
     entities: Query<(Entity, &Player, &ObjectType, &Health), Changed<Health>>,
 ) {
     for (entity, &player, &object_type, health) in entities.iter() {
         if health.destroyed() {
             if let ObjectType::Active(active_type) = object_type {
-                counter.player_mut(player).unwrap().update(active_type, -1);
+                let player_counter = counter.player_mut(player).unwrap();
+                player_counter.update(active_type, -1);
+                player_counter.record_loss();
+                if let ActiveObjectType::Unit(_) = active_type {
+                    stat_events.send(MatchStatEvent::new(player, MatchStat::UnitLost));
+                }
             }
             commands.entity(entity).despawn_recursive();
         }
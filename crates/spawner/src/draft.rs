@@ -6,11 +6,11 @@
 
 use bevy::pbr::NotShadowReceiver;
 use bevy::scene::SceneInstance;
-use bevy::{pbr::NotShadowCaster, prelude::*};
+use bevy::{ecs::system::SystemParam, pbr::NotShadowCaster, prelude::*};
 use de_core::{
     baseset::GameSet,
     gamestate::GameState,
-    objects::{ActiveObjectType, BuildingType, MovableSolid, ObjectType, StaticSolid},
+    objects::{ActiveObjectType, BuildingType, Flying, MovableSolid, ObjectType, StaticSolid},
     projection::ToFlat,
     state::AppState,
 };
@@ -99,7 +99,46 @@ impl DraftAllowed {
 #[derive(Component, Default)]
 struct DraftReady(bool);
 
-type Solids<'w, 's> = SpatialQuery<'w, 's, Entity, Or<(With<StaticSolid>, With<MovableSolid>)>>;
+/// Flying objects do not block placement of buildings.
+type Solids<'w, 's> =
+    SpatialQuery<'w, 's, Entity, Or<(With<StaticSolid>, (With<MovableSolid>, Without<Flying>))>>;
+
+/// System parameter validating placement of new buildings.
+#[derive(SystemParam)]
+pub struct PlacementValidator<'w, 's> {
+    solids: Solids<'w, 's>,
+    solid_objects: SolidObjects<'w>,
+    bounds: Res<'w, MapBounds>,
+}
+
+impl<'w, 's> PlacementValidator<'w, 's> {
+    /// Returns true if an object of the given type may be placed at the
+    /// given location, i.e. it is fully inside the map and it does not
+    /// overlap with any other solid object on the ground.
+    ///
+    /// The terrain is flat everywhere, thus the placement is not validated
+    /// against terrain slope.
+    pub fn is_allowed(&self, object_type: ObjectType, transform: &Transform) -> bool {
+        if !transform.translation.is_finite() || !transform.rotation.is_finite() {
+            return false;
+        }
+
+        let collider = QueryCollider::new(
+            self.solid_objects.get(object_type).collider(),
+            Isometry::new(
+                transform.translation.into(),
+                transform.rotation.to_scaled_axis().into(),
+            ),
+        );
+
+        let flat_aabb = collider.world_aabb().to_flat();
+        let shrinked_map = {
+            let aabb = self.bounds.aabb();
+            Aabb::new(aabb.mins + MAP_OFFSET, aabb.maxs - MAP_OFFSET)
+        };
+        shrinked_map.contains(&flat_aabb) && !self.solids.collides(&collider)
+    }
+}
 
 fn new_draft(
     mut commands: Commands,
@@ -118,25 +157,10 @@ fn new_draft(
 
 fn update_draft(
     mut drafts: Query<(&Transform, &ObjectType, &mut DraftAllowed)>,
-    solids: Solids,
-    solid_objects: SolidObjects,
-    bounds: Res<MapBounds>,
+    validator: PlacementValidator,
 ) {
     for (transform, &object_type, mut draft) in drafts.iter_mut() {
-        let collider = QueryCollider::new(
-            solid_objects.get(object_type).collider(),
-            Isometry::new(
-                transform.translation.into(),
-                transform.rotation.to_scaled_axis().into(),
-            ),
-        );
-
-        let flat_aabb = collider.world_aabb().to_flat();
-        let shrinked_map = {
-            let aabb = bounds.aabb();
-            Aabb::new(aabb.mins + MAP_OFFSET, aabb.maxs - MAP_OFFSET)
-        };
-        let allowed = shrinked_map.contains(&flat_aabb) && !solids.collides(&collider);
+        let allowed = validator.is_allowed(object_type, transform);
         if allowed != draft.0 {
             // Access the component mutably only when really needed for optimal
             // Bevy change detection.
@@ -1,7 +1,16 @@
-use bevy::prelude::*;
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
 use de_core::{
-    baseset::GameSet, gamestate::GameState, gconfig::GameConfig, gresult::GameResult,
+    baseset::GameSet,
+    diplomacy::Diplomacy,
+    gamestate::GameState,
+    gconfig::GameConfig,
+    gresult::GameResult,
+    objects::{ActiveObjectType, BuildingType, ObjectType},
+    player::Player,
     state::AppState,
+    victory::{GameEndedEvent, VictoryCondition, VictorySet},
 };
 
 use crate::ObjectCounter;
@@ -10,31 +19,244 @@ pub(crate) struct GameEndPlugin;
 
 impl Plugin for GameEndPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(
-            game_end_detection_system
-                .in_base_set(GameSet::PostUpdate)
-                .run_if(in_state(GameState::Playing)),
-        );
+        app.add_system(setup.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                game_end_detection_system
+                    .in_base_set(GameSet::PostUpdate)
+                    .in_set(VictorySet::Detect)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Time at which the game started.
+#[derive(Resource)]
+struct GameStart(Duration);
+
+#[derive(SystemParam)]
+struct GameStatus<'w, 's> {
+    time: Res<'w, Time>,
+    start: Res<'w, GameStart>,
+    conf: Res<'w, GameConfig>,
+    diplomacy: Res<'w, Diplomacy>,
+    counter: Res<'w, ObjectCounter>,
+    objects: Query<'w, 's, (&'static Player, &'static ObjectType)>,
+}
+
+impl<'w, 's> GameStatus<'w, 's> {
+    fn players(&self) -> Vec<PlayerStatus> {
+        let victory = self.conf.victory();
+        let annihilation = victory.contains(&VictoryCondition::Annihilation);
+        let destroy_hq = victory.contains(&VictoryCondition::DestroyHq);
+
+        let players: Vec<Player> = (1..=self.conf.max_player().to_num())
+            .map(|num| Player::try_from(num).unwrap())
+            .collect();
+        players
+            .iter()
+            .map(|&player| {
+                let counter = self.counter.player(player).unwrap();
+                let eliminated = (annihilation && counter.total() == 0)
+                    || (destroy_hq && !self.has_base(player));
+                // Score is the number of objects lost by the enemies.
+                let score = players
+                    .iter()
+                    .filter(|&&other| !self.diplomacy.are_allies(player, other))
+                    .map(|&other| self.counter.player(other).unwrap().lost_count())
+                    .sum();
+
+                PlayerStatus {
+                    player,
+                    eliminated,
+                    score,
+                }
+            })
+            .collect()
+    }
+
+    fn has_base(&self, player: Player) -> bool {
+        self.objects.iter().any(|(&owner, &object_type)| {
+            owner == player
+                && object_type == ObjectType::Active(ActiveObjectType::Building(BuildingType::Base))
+        })
     }
+
+    fn elapsed(&self) -> Duration {
+        self.time.elapsed().saturating_sub(self.start.0)
+    }
+}
+
+struct PlayerStatus {
+    player: Player,
+    eliminated: bool,
+    score: u32,
+}
+
+fn setup(mut commands: Commands, time: Res<Time>) {
+    commands.insert_resource(GameStart(time.elapsed()));
+}
+
+fn cleanup(mut commands: Commands) {
+    commands.remove_resource::<GameStart>();
 }
 
 fn game_end_detection_system(
     mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
-    conf: Res<GameConfig>,
-    counter: Res<ObjectCounter>,
+    status: GameStatus,
+    mut events: EventWriter<GameEndedEvent>,
 ) {
-    let mut result = None;
-    if counter.player(conf.locals().playable()).unwrap().total() == 0 {
-        result = Some(GameResult::finished(false));
-    } else if conf.players().all(|player| {
-        conf.locals().is_playable(player) || counter.player(player).unwrap().total() == 0
-    }) {
-        result = Some(GameResult::finished(true));
+    let players = status.players();
+    let conditions = status.conf.victory();
+    let diplomacy = status.diplomacy.as_ref();
+
+    if let Some(winner) = detect_end(conditions, &players, diplomacy, status.elapsed()) {
+        events.send(GameEndedEvent::new(winner));
+        return;
     }
 
-    if let Some(result) = result {
-        commands.insert_resource(result);
+    // The local player leaves the game once they and all their allies are
+    // eliminated, even though the other players may continue.
+    let playable = status.conf.locals().playable();
+    if players
+        .iter()
+        .all(|status| status.eliminated || !diplomacy.are_allies(playable, status.player))
+    {
+        commands.insert_resource(GameResult::finished(false));
         next_state.set(AppState::InMenu);
     }
 }
+
+/// Returns None if the game has not ended yet, otherwise returns the winner
+/// (None in the case of a draw).
+fn detect_end(
+    conditions: &[VictoryCondition],
+    players: &[PlayerStatus],
+    diplomacy: &Diplomacy,
+    elapsed: Duration,
+) -> Option<Option<Player>> {
+    // Allies win or lose together.
+    let common_winner = |candidates: &[&PlayerStatus]| {
+        let first = candidates.first()?.player;
+        if candidates
+            .iter()
+            .all(|status| diplomacy.are_allies(first, status.player))
+        {
+            Some(first)
+        } else {
+            None
+        }
+    };
+
+    let alive: Vec<&PlayerStatus> = players.iter().filter(|s| !s.eliminated).collect();
+    if alive.is_empty() {
+        return Some(None);
+    }
+    if let Some(winner) = common_winner(&alive) {
+        return Some(Some(winner));
+    }
+
+    for condition in conditions {
+        match *condition {
+            VictoryCondition::Annihilation | VictoryCondition::DestroyHq => (),
+            VictoryCondition::Score(limit) => {
+                if let Some(status) = alive.iter().find(|status| status.score >= limit) {
+                    return Some(Some(status.player));
+                }
+            }
+            VictoryCondition::TimeLimit(limit) => {
+                if elapsed >= limit {
+                    let best = alive.iter().map(|status| status.score).max().unwrap();
+                    let leaders: Vec<&PlayerStatus> = alive
+                        .iter()
+                        .copied()
+                        .filter(|status| status.score == best)
+                        .collect();
+                    return Some(common_winner(&leaders));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use de_core::player::{Team, Teams};
+
+    use super::*;
+
+    fn status(player: Player, eliminated: bool, score: u32) -> PlayerStatus {
+        PlayerStatus {
+            player,
+            eliminated,
+            score,
+        }
+    }
+
+    #[test]
+    fn test_detect_end() {
+        let mut teams = Teams::default();
+        teams.set(Player::Player3, Team::new(1));
+        teams.set(Player::Player4, Team::new(1));
+        let diplomacy = Diplomacy::new(&teams);
+
+        let conditions = [
+            VictoryCondition::Annihilation,
+            VictoryCondition::Score(10),
+            VictoryCondition::TimeLimit(Duration::from_secs(600)),
+        ];
+        let early = Duration::from_secs(10);
+        let late = Duration::from_secs(900);
+
+        let players = [
+            status(Player::Player1, false, 3),
+            status(Player::Player2, false, 5),
+        ];
+        assert_eq!(detect_end(&conditions, &players, &diplomacy, early), None);
+        assert_eq!(
+            detect_end(&conditions, &players, &diplomacy, late),
+            Some(Some(Player::Player2))
+        );
+
+        let players = [
+            status(Player::Player1, false, 5),
+            status(Player::Player2, false, 5),
+        ];
+        assert_eq!(
+            detect_end(&conditions, &players, &diplomacy, late),
+            Some(None)
+        );
+
+        let players = [
+            status(Player::Player1, false, 12),
+            status(Player::Player2, false, 5),
+        ];
+        assert_eq!(
+            detect_end(&conditions, &players, &diplomacy, early),
+            Some(Some(Player::Player1))
+        );
+
+        let players = [
+            status(Player::Player1, true, 0),
+            status(Player::Player2, true, 0),
+            status(Player::Player3, false, 0),
+            status(Player::Player4, false, 0),
+        ];
+        assert_eq!(
+            detect_end(&conditions, &players, &diplomacy, early),
+            Some(Some(Player::Player3))
+        );
+
+        let players = [
+            status(Player::Player1, true, 0),
+            status(Player::Player2, true, 0),
+        ];
+        assert_eq!(
+            detect_end(&conditions, &players, &diplomacy, early),
+            Some(None)
+        );
+    }
+}
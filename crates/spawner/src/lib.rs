@@ -5,7 +5,7 @@ use counter::CounterPlugin;
 pub use counter::ObjectCounter;
 use destroyer::DestroyerPlugin;
 use draft::DraftPlugin;
-pub use draft::{DraftAllowed, DraftBundle};
+pub use draft::{DraftAllowed, DraftBundle, PlacementValidator};
 use gameend::GameEndPlugin;
 pub use spawner::SpawnBundle;
 use spawner::SpawnerPlugin;
@@ -14,18 +14,23 @@ mod counter;
 mod destroyer;
 mod draft;
 mod gameend;
+#[cfg(feature = "dev")]
+mod reload;
 mod spawner;
 
 pub struct SpawnerPluginGroup;
 
 impl PluginGroup for SpawnerPluginGroup {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>()
+        let group = PluginGroupBuilder::start::<Self>()
             .add(CounterPlugin)
             .add(SpawnerPlugin)
             .add(DraftPlugin)
             .add(DestroyerPlugin)
-            .add(GameEndPlugin)
+            .add(GameEndPlugin);
+        #[cfg(feature = "dev")]
+        let group = group.add(reload::ReloadPlugin);
+        group
     }
 }
 
@@ -0,0 +1,100 @@
+//! Hot reloading of object models and configuration of already spawned
+//! objects. It is intended for development only and it has an effect only
+//! when asset file watching is enabled.
+
+use ahash::AHashSet;
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet,
+    gamestate::GameState,
+    objects::{Active, ObjectType},
+};
+use de_objects::{
+    ArmorClass, Cloak, Detector, LaserCannon, SceneType, Scenes, Shield, SolidObject, SolidObjects,
+};
+
+use crate::spawner::insert_parameters;
+
+pub(crate) struct ReloadPlugin;
+
+impl Plugin for ReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            reload_scenes
+                .in_base_set(GameSet::Update)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_system(
+            reload_solids
+                .in_base_set(GameSet::Update)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn reload_scenes(
+    mut events: EventReader<AssetEvent<Scene>>,
+    scenes: Res<Scenes>,
+    mut objects: Query<(&ObjectType, &mut Handle<Scene>)>,
+) {
+    let reloaded: AHashSet<ObjectType> = events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => match scenes.scene_type(handle) {
+                Some(SceneType::Solid(object_type)) => Some(object_type),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    for object_type in reloaded.iter() {
+        info!("Reloading model of {object_type}");
+    }
+
+    for (object_type, mut handle) in objects.iter_mut() {
+        if reloaded.contains(object_type) {
+            // Scenes are re-spawned by Bevy once the handle is changed.
+            handle.set_changed();
+        }
+    }
+}
+
+fn reload_solids(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<SolidObject>>,
+    solids: SolidObjects,
+    objects: Query<(Entity, &ObjectType, &GlobalTransform), With<Active>>,
+) {
+    let reloaded: AHashSet<ObjectType> = events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => solids.object_type(handle),
+            _ => None,
+        })
+        .collect();
+
+    for object_type in reloaded.iter() {
+        info!("Reloading configuration of {object_type}");
+    }
+
+    for (entity, &object_type, transform) in objects.iter() {
+        if !reloaded.contains(&object_type) {
+            continue;
+        }
+        let ObjectType::Active(active_type) = object_type else {
+            continue;
+        };
+
+        let mut entity_commands = commands.entity(entity);
+        // The new configuration might lack some of the previously present
+        // parameters.
+        entity_commands.remove::<(ArmorClass, Shield, LaserCannon, Cloak, Detector)>();
+        insert_parameters(
+            &mut entity_commands,
+            active_type,
+            solids.get(object_type),
+            transform,
+        );
+    }
+}
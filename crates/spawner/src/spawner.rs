@@ -1,15 +1,17 @@
 #![allow(clippy::forget_non_drop)] // Needed because of #[derive(Bundle)]
 
-use bevy::prelude::*;
+use bevy::{ecs::system::EntityCommands, prelude::*};
 use de_core::{
     baseset::GameSet,
     gamestate::GameState,
     gconfig::GameConfig,
-    objects::{Active, ActiveObjectType, MovableSolid, ObjectType, Playable, StaticSolid},
+    objects::{Active, ActiveObjectType, Flying, MovableSolid, ObjectType, Playable, StaticSolid},
     player::Player,
 };
 use de_energy::Battery;
-use de_objects::{AssetCollection, InitialHealths, SceneType, Scenes, SolidObjects};
+use de_objects::{
+    AssetCollection, Health, InitialHealths, SceneType, Scenes, SolidObject, SolidObjects,
+};
 use de_terrain::{CircleMarker, MarkerVisibility, RectangleMarker};
 
 use crate::ObjectCounter;
@@ -52,6 +54,13 @@ impl SpawnBundle {
 #[derive(Component)]
 struct Spawn;
 
+/// Optional components of objects to be spawned.
+type SpawnOptions = (
+    Option<&'static Player>,
+    Option<&'static Health>,
+    Option<&'static Battery>,
+);
+
 fn spawn(
     mut commands: Commands,
     game_config: Res<GameConfig>,
@@ -59,9 +68,9 @@ fn spawn(
     solids: SolidObjects,
     healths: Res<InitialHealths>,
     mut counter: ResMut<ObjectCounter>,
-    to_spawn: Query<(Entity, &ObjectType, &GlobalTransform, Option<&Player>), With<Spawn>>,
+    to_spawn: Query<(Entity, &ObjectType, &GlobalTransform, SpawnOptions), With<Spawn>>,
 ) {
-    for (entity, &object_type, transform, player) in to_spawn.iter() {
+    for (entity, &object_type, transform, (player, health, battery)) in to_spawn.iter() {
         info!("Spawning object {}", object_type);
 
         let mut entity_commands = commands.entity(entity);
@@ -73,7 +82,10 @@ fn spawn(
         match object_type {
             ObjectType::Active(active_type) => {
                 entity_commands.insert(Active);
-                entity_commands.insert(Battery::default());
+                // Objects of a continued saved game keep their energy.
+                if battery.is_none() {
+                    entity_commands.insert(Battery::default());
+                }
 
                 let player = *player.expect("Active object without an associated was spawned.");
                 counter.player_mut(player).unwrap().update(active_type, 1);
@@ -83,27 +95,19 @@ fn spawn(
                 }
 
                 match active_type {
-                    ActiveObjectType::Building(_) => {
-                        entity_commands.insert(StaticSolid);
-
-                        let local_aabb = solid.ichnography().local_aabb();
-                        entity_commands
-                            .insert(RectangleMarker::from_aabb_transform(local_aabb, transform));
-                    }
-                    ActiveObjectType::Unit(_) => {
-                        entity_commands.insert(MovableSolid);
-
-                        let radius = solid.ichnography().radius();
-                        entity_commands.insert(CircleMarker::new(radius));
-                    }
-                }
+                    ActiveObjectType::Building(_) => entity_commands.insert(StaticSolid),
+                    ActiveObjectType::Unit(_) => entity_commands.insert(MovableSolid),
+                };
 
                 entity_commands.insert(MarkerVisibility::default());
 
-                entity_commands.insert(healths.health(active_type).clone());
-                if let Some(cannon) = solid.cannon() {
-                    entity_commands.insert(cannon.clone());
+                // Objects might be spawned with already decreased health,
+                // e.g. from a multiplayer game state snapshot.
+                if health.is_none() {
+                    entity_commands.insert(healths.health(active_type).clone());
                 }
+
+                insert_parameters(&mut entity_commands, active_type, solid, transform);
             }
             ObjectType::Inactive(_) => {
                 entity_commands.insert(StaticSolid);
@@ -111,3 +115,42 @@ fn spawn(
         }
     }
 }
+
+/// Inserts components of an active object whose parameters are given by the
+/// object configuration, see [`SolidObject`].
+pub(crate) fn insert_parameters(
+    entity_commands: &mut EntityCommands,
+    active_type: ActiveObjectType,
+    solid: &SolidObject,
+    transform: &GlobalTransform,
+) {
+    match active_type {
+        ActiveObjectType::Building(_) => {
+            let local_aabb = solid.ichnography().local_aabb();
+            entity_commands.insert(RectangleMarker::from_aabb_transform(local_aabb, transform));
+        }
+        ActiveObjectType::Unit(_) => {
+            let radius = solid.ichnography().radius();
+            entity_commands.insert(CircleMarker::new(radius));
+            if solid.flight().is_some() {
+                entity_commands.insert(Flying);
+            }
+        }
+    }
+
+    if let Some(armor) = solid.armor() {
+        entity_commands.insert(armor);
+    }
+    if let Some(shield) = solid.shield() {
+        entity_commands.insert(shield.clone());
+    }
+    if let Some(cannon) = solid.cannon() {
+        entity_commands.insert(cannon.clone());
+    }
+    if let Some(cloak) = solid.cloak() {
+        entity_commands.insert(cloak.clone());
+    }
+    if let Some(detector) = solid.detector() {
+        entity_commands.insert(detector.clone());
+    }
+}
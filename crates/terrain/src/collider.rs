@@ -2,6 +2,8 @@ use bevy::{
     ecs::system::SystemParam,
     prelude::{Query, Transform},
 };
+use de_core::projection::ToAltitude;
+use glam::Vec2;
 use parry3d::{
     math::Isometry,
     na::{Unit, Vector3},
@@ -9,7 +11,7 @@ use parry3d::{
     shape::HalfSpace,
 };
 
-use crate::terrain::Terrain;
+use crate::{terrain::Terrain, MAX_ELEVATION};
 
 #[derive(SystemParam)]
 pub struct TerrainCollider<'w, 's> {
@@ -24,6 +26,17 @@ impl<'w, 's> TerrainCollider<'w, 's> {
             .or_else(|| ray_msl_intersection(ray, max_toi))
     }
 
+    /// Returns elevation of the terrain at a point of the map. Mean sea level
+    /// (zero) is returned at points without terrain.
+    pub fn elevation(&self, point: Vec2) -> f32 {
+        let ray = Ray::new(
+            point.to_altitude(MAX_ELEVATION).into(),
+            Vector3::new(0., -1., 0.),
+        );
+        self.cast_ray(&ray, f32::INFINITY)
+            .map_or(0., |intersection| ray.point_at(intersection.toi).y)
+    }
+
     pub fn cast_ray(&self, ray: &Ray, max_toi: f32) -> Option<RayIntersection> {
         self.terrains
             .iter()
@@ -88,7 +101,7 @@ fn ray_msl_intersection(ray: &Ray, max_toi: f32) -> Option<RayIntersection> {
 #[cfg(test)]
 mod test {
     use bevy::prelude::*;
-    use de_map::size::MapBounds;
+    use de_map::{heightmap::Heightmap, size::MapBounds};
     use glam::{Vec2, Vec3};
     use parry3d::query::Ray;
 
@@ -121,4 +134,36 @@ mod test {
         let intersection = app.world.get_resource::<Vec3Wrap>().unwrap();
         assert!(Vec3::new(13.6, 3.2, 6.8).distance(intersection.0) < 0.00001);
     }
+
+    #[test]
+    fn test_elevation() {
+        #[derive(Resource)]
+        struct Elevations(Vec<f32>);
+
+        let mut app = App::new();
+        app.world.spawn(TerrainBundle::from_heightmap(
+            MapBounds::new(Vec2::new(100., 200.)),
+            &Heightmap::new(2, 2, vec![0., 0., 10., 10.]),
+        ));
+
+        fn help_system(mut commands: Commands, terrain: super::TerrainCollider) {
+            let elevations = [
+                Vec2::new(0., -100.),
+                Vec2::new(20., 0.),
+                Vec2::new(-30., 50.),
+                Vec2::new(0., 100.),
+                Vec2::new(500., 0.),
+            ]
+            .map(|point| terrain.elevation(point));
+            commands.insert_resource(Elevations(elevations.to_vec()));
+        }
+
+        app.add_system(help_system);
+        app.update();
+
+        let elevations = &app.world.get_resource::<Elevations>().unwrap().0;
+        for (elevation, expected) in elevations.iter().zip([0., 5., 7.5, 10., 0.]) {
+            assert!((elevation - expected).abs() < 0.0001);
+        }
+    }
 }
@@ -0,0 +1,294 @@
+use bevy::prelude::*;
+use de_core::{baseset::GameSet, gamestate::GameState};
+use parry2d::{bounding_volume::Aabb, math::Point};
+
+use crate::terrain::{Chunk, Terrain};
+
+const CRATER_TAG: u8 = 32;
+
+/// Maximum radius of a crater in meters.
+pub const MAX_CRATER_RADIUS: f32 = 32.;
+/// Maximum depth of a crater in meters.
+pub const MAX_CRATER_DEPTH: f32 = 8.;
+
+pub(crate) struct DeformationPlugin;
+
+impl Plugin for DeformationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DeformTerrainEvent>()
+            .add_event::<ExecuteDeformationEvent>()
+            .add_event::<TerrainDeformedEvent>()
+            .add_system(
+                forward
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(resource_exists::<ScheduledDeformation>()))
+                    .before(DeformationSet::Execute),
+            )
+            .add_system(
+                execute
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(DeformationSet::Execute),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum DeformationSet {
+    /// Terrain and its meshes are deformed in this set.
+    Execute,
+}
+
+/// While this resource exists, [`DeformTerrainEvent`]s are not executed
+/// right away. Whoever inserted the resource is responsible for scheduling
+/// of the corresponding [`TerrainDeformation`]s and for their execution via
+/// [`ExecuteDeformationEvent`].
+///
+/// This is used to deform the terrain in the same simulation tick on the
+/// computers of all players of a multiplayer game.
+#[derive(Resource)]
+pub struct ScheduledDeformation;
+
+/// A bowl shaped crater dug into the terrain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainDeformation {
+    center: Vec2,
+    radius: f32,
+    depth: f32,
+}
+
+impl TerrainDeformation {
+    /// Maximum length of an encoded deformation in bytes.
+    pub const MAX_ENCODED_LEN: usize = 17;
+
+    /// # Arguments
+    ///
+    /// * `center` - flat position of the center of the crater.
+    ///
+    /// * `radius` - radius of the crater.
+    ///
+    /// * `depth` - depth of the crater at its center.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `center` is not finite or if `radius` or `depth` is not
+    /// positive or exceeds its maximum.
+    pub fn crater(center: Vec2, radius: f32, depth: f32) -> Self {
+        let deformation = Self {
+            center,
+            radius,
+            depth,
+        };
+        assert!(
+            deformation.is_valid(),
+            "Invalid crater at ({}, {}) with radius {radius} and depth {depth}.",
+            center.x,
+            center.y
+        );
+        deformation
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.center
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn depth(&self) -> f32 {
+        self.depth
+    }
+
+    /// Returns flat bounding box of the area affected by the deformation.
+    pub fn aabb(&self) -> Aabb {
+        Aabb::new(
+            Point::from(self.center - Vec2::splat(self.radius)),
+            Point::from(self.center + Vec2::splat(self.radius)),
+        )
+    }
+
+    /// Returns the (non-positive) change of terrain elevation at a point of
+    /// the map.
+    pub(crate) fn height_change(&self, point: Vec2) -> f32 {
+        let distance = self.center.distance_squared(point) / (self.radius * self.radius);
+        if distance >= 1. {
+            0.
+        } else {
+            -self.depth * (1. - distance)
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.center.is_finite()
+            && self.radius > 0.
+            && self.radius <= MAX_CRATER_RADIUS
+            && self.depth > 0.
+            && self.depth <= MAX_CRATER_DEPTH
+    }
+
+    /// Appends self-delimiting binary representation of the deformation to
+    /// `buf`.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(CRATER_TAG);
+        buf.extend_from_slice(&self.center.x.to_le_bytes());
+        buf.extend_from_slice(&self.center.y.to_le_bytes());
+        buf.extend_from_slice(&self.radius.to_le_bytes());
+        buf.extend_from_slice(&self.depth.to_le_bytes());
+    }
+
+    /// Decodes a deformation from the beginning of `bytes`. It returns the
+    /// deformation and the number of consumed bytes or None if the bytes do
+    /// not start with a valid deformation.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        let (&tag, rest) = bytes.split_first()?;
+        if tag != CRATER_TAG {
+            return None;
+        }
+
+        let x = decode_f32(rest.get(..4)?);
+        let y = decode_f32(rest.get(4..8)?);
+        let radius = decode_f32(rest.get(8..12)?);
+        let depth = decode_f32(rest.get(12..16)?);
+        let deformation = Self {
+            center: Vec2::new(x, y),
+            radius,
+            depth,
+        };
+        deformation
+            .is_valid()
+            .then_some((deformation, Self::MAX_ENCODED_LEN))
+    }
+}
+
+fn decode_f32(bytes: &[u8]) -> f32 {
+    f32::from_le_bytes(bytes.try_into().unwrap())
+}
+
+/// Send this event to deform the terrain, e.g. after a large explosion.
+pub struct DeformTerrainEvent(TerrainDeformation);
+
+impl DeformTerrainEvent {
+    pub fn new(deformation: TerrainDeformation) -> Self {
+        Self(deformation)
+    }
+
+    pub fn deformation(&self) -> TerrainDeformation {
+        self.0
+    }
+}
+
+/// Send this event to execute a [`TerrainDeformation`], see
+/// [`ScheduledDeformation`]. Deformations are applied in the order of the
+/// events.
+pub struct ExecuteDeformationEvent(TerrainDeformation);
+
+impl ExecuteDeformationEvent {
+    pub fn new(deformation: TerrainDeformation) -> Self {
+        Self(deformation)
+    }
+}
+
+/// This event is sent after the terrain is deformed.
+pub struct TerrainDeformedEvent(Aabb);
+
+impl TerrainDeformedEvent {
+    /// Flat bounding box of the deformed part of the terrain.
+    pub fn area(&self) -> &Aabb {
+        &self.0
+    }
+}
+
+fn forward(
+    mut in_events: EventReader<DeformTerrainEvent>,
+    mut out_events: EventWriter<ExecuteDeformationEvent>,
+) {
+    for event in in_events.iter() {
+        out_events.send(ExecuteDeformationEvent::new(event.deformation()));
+    }
+}
+
+fn execute(
+    mut in_events: EventReader<ExecuteDeformationEvent>,
+    mut out_events: EventWriter<TerrainDeformedEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut terrains: Query<(&mut Terrain, &Transform, &Children)>,
+    chunks: Query<(&Chunk, &Handle<Mesh>)>,
+) {
+    let deformations: Vec<TerrainDeformation> = in_events.iter().map(|event| event.0).collect();
+    if deformations.is_empty() {
+        return;
+    }
+
+    for (mut terrain, transform, children) in terrains.iter_mut() {
+        let dirty = terrain.deform(transform.translation, deformations.as_slice());
+        if dirty.is_empty() {
+            continue;
+        }
+
+        for &child in children.iter() {
+            let Ok((chunk, handle)) = chunks.get(child) else {
+                continue;
+            };
+            if dirty.contains(chunk) {
+                let mesh = meshes.get_mut(handle).unwrap();
+                *mesh = terrain.generate_mesh(transform.translation, *chunk);
+            }
+        }
+    }
+
+    for deformation in deformations {
+        out_events.send(TerrainDeformedEvent(deformation.aabb()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_height_change() {
+        let crater = TerrainDeformation::crater(Vec2::new(10., -5.), 4., 2.);
+        assert_eq!(crater.height_change(Vec2::new(10., -5.)), -2.);
+        assert_eq!(crater.height_change(Vec2::new(12., -5.)), -1.5);
+        assert_eq!(crater.height_change(Vec2::new(10., -1.)), 0.);
+        assert_eq!(crater.height_change(Vec2::new(100., 0.)), 0.);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let deformations = [
+            TerrainDeformation::crater(Vec2::new(-12.5, 300.), 6., 1.5),
+            TerrainDeformation::crater(Vec2::new(1., 2.), MAX_CRATER_RADIUS, MAX_CRATER_DEPTH),
+        ];
+
+        let mut buf = Vec::new();
+        for deformation in deformations {
+            let len = buf.len();
+            deformation.encode(&mut buf);
+            assert!(buf.len() - len <= TerrainDeformation::MAX_ENCODED_LEN);
+        }
+
+        let mut bytes = buf.as_slice();
+        for deformation in deformations {
+            let (decoded, len) = TerrainDeformation::decode(bytes).unwrap();
+            assert_eq!(decoded, deformation);
+            bytes = &bytes[len..];
+        }
+        assert!(bytes.is_empty());
+
+        assert!(TerrainDeformation::decode(&buf[..10]).is_none());
+        assert!(TerrainDeformation::decode(&buf[1..]).is_none());
+        assert!(TerrainDeformation::decode(&[]).is_none());
+
+        let mut invalid = Vec::new();
+        TerrainDeformation {
+            center: Vec2::ZERO,
+            radius: 2. * MAX_CRATER_RADIUS,
+            depth: 1.,
+        }
+        .encode(&mut invalid);
+        assert!(TerrainDeformation::decode(&invalid).is_none());
+    }
+}
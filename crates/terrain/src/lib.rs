@@ -1,4 +1,5 @@
 mod collider;
+mod deformation;
 mod marker;
 mod plugin;
 mod shader;
@@ -6,6 +7,11 @@ mod terrain;
 
 use bevy::{app::PluginGroupBuilder, prelude::*};
 pub use collider::TerrainCollider;
+use deformation::DeformationPlugin;
+pub use deformation::{
+    DeformTerrainEvent, DeformationSet, ExecuteDeformationEvent, ScheduledDeformation,
+    TerrainDeformation, TerrainDeformedEvent, MAX_CRATER_DEPTH, MAX_CRATER_RADIUS,
+};
 use marker::MarkerPlugin;
 pub use marker::{CircleMarker, MarkerVisibility, RectangleMarker};
 use plugin::TerrainPlugin;
@@ -20,5 +26,6 @@ impl PluginGroup for TerrainPluginGroup {
         PluginGroupBuilder::start::<Self>()
             .add(TerrainPlugin)
             .add(MarkerPlugin)
+            .add(DeformationPlugin)
     }
 }
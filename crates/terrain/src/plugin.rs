@@ -7,6 +7,7 @@ use bevy::{
     },
 };
 use de_core::{baseset::GameSet, gamestate::GameState, state::AppState};
+use de_map::zones::TerrainZones;
 use iyes_progress::prelude::*;
 
 use crate::{shader::TerrainMaterial, terrain::Terrain};
@@ -19,16 +20,23 @@ impl Plugin for TerrainPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(MaterialPlugin::<TerrainMaterial>::default())
             .add_system(load.in_schedule(OnEnter(AppState::InGame)))
+            .add_system(load.in_schedule(OnEnter(AppState::InEditor)))
             .add_system(cleanup.in_schedule(OnExit(AppState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(AppState::InEditor)))
             .add_system(
                 setup_textures
                     .track_progress()
                     .in_base_set(GameSet::Update)
                     .run_if(in_state(GameState::Loading)),
             )
+            .add_system(
+                setup_editor_textures
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(AppState::InEditor)),
+            )
             .add_system(
                 init.in_base_set(GameSet::Update)
-                    .run_if(in_state(AppState::InGame)),
+                    .run_if(in_state(AppState::InGame).or_else(in_state(AppState::InEditor))),
             );
     }
 }
@@ -50,17 +58,44 @@ fn setup_textures(
     mut images: ResMut<Assets<Image>>,
     textures: Option<Res<Textures>>,
 ) -> Progress {
+    configure_textures(server.as_ref(), images.as_mut(), textures.as_deref()).into()
+}
+
+/// The map editor has no loading progress, thus textures are configured as
+/// soon as they are loaded.
+fn setup_editor_textures(
+    server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    textures: Option<Res<Textures>>,
+) {
+    configure_textures(server.as_ref(), images.as_mut(), textures.as_deref());
+}
+
+/// Configures loaded terrain textures and returns true once they are ready.
+fn configure_textures(
+    server: &AssetServer,
+    images: &mut Assets<Image>,
+    textures: Option<&Textures>,
+) -> bool {
     let textures = match textures {
         Some(textures) => textures,
-        None => return false.into(),
+        None => return false,
     };
 
     match server.get_load_state(&textures.0) {
-        LoadState::NotLoaded => false.into(),
-        LoadState::Loading => false.into(),
+        LoadState::NotLoaded => false,
+        LoadState::Loading => false,
         LoadState::Failed => panic!("Texture loading has failed."),
         LoadState::Unloaded => unreachable!(),
         LoadState::Loaded => {
+            if matches!(
+                images.get(&textures.0).unwrap().sampler_descriptor,
+                ImageSampler::Descriptor(_)
+            ) {
+                // Avoid repeated re-upload of the image.
+                return true;
+            }
+
             // Ideally, this setup would happen in some kind of asset post
             // processing. This is however not yet supported by Bevy.
             //
@@ -72,7 +107,7 @@ fn setup_textures(
                 ..Default::default()
             });
 
-            true.into()
+            true
         }
     }
 }
@@ -82,14 +117,28 @@ fn init(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<TerrainMaterial>>,
     textures: Res<Textures>,
-    uninitialized: Query<(Entity, &Terrain, &Transform), Without<Handle<Mesh>>>,
+    zones: Option<Res<TerrainZones>>,
+    uninitialized: Query<(Entity, &Terrain, &Transform), Added<Terrain>>,
 ) {
+    let no_zones = TerrainZones::default();
+    let zones = zones.as_deref().unwrap_or(&no_zones);
     for (entity, terrain, transform) in uninitialized.iter() {
-        commands.entity(entity).insert(MaterialMeshBundle {
-            mesh: meshes.add(terrain.generate_mesh(transform.translation)),
-            material: materials.add(TerrainMaterial::new(textures.0.clone())),
-            transform: *transform,
-            ..Default::default()
-        });
+        let material = materials.add(TerrainMaterial::new(textures.0.clone(), zones));
+
+        commands
+            .entity(entity)
+            .insert((GlobalTransform::default(), VisibilityBundle::default()))
+            .with_children(|parent| {
+                for chunk in terrain.chunks() {
+                    parent.spawn((
+                        MaterialMeshBundle {
+                            mesh: meshes.add(terrain.generate_mesh(transform.translation, chunk)),
+                            material: material.clone(),
+                            ..Default::default()
+                        },
+                        chunk,
+                    ));
+                }
+            });
     }
 }
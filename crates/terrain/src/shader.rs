@@ -5,7 +5,8 @@ use bevy::{
     reflect::TypeUuid,
     render::render_resource::{AsBindGroup, ShaderRef, ShaderType},
 };
-use glam::{Mat3, Vec2};
+use de_map::zones::{TerrainZones, ZoneKind, MAX_ZONES, MAX_ZONE_VERTICES};
+use glam::{Mat3, Vec2, Vec4};
 
 // * Keep this in sync with terrain.wgsl.
 // * Keep this smaller or equal to de_core::objects::PLAYER_MAX_UNITS.
@@ -13,6 +14,9 @@ pub(crate) const CIRCLE_CAPACITY: usize = 127;
 // * Keep this in sync with terrain.wgsl.
 // * Keep this smaller or equal to de_core::objects::PLAYER_MAX_BUILDINGS.
 pub(crate) const RECTANGLE_CAPACITY: usize = 31;
+// Keep these in sync with terrain.wgsl.
+const ZONE_CAPACITY: usize = MAX_ZONES;
+const ZONE_VERTEX_CAPACITY: usize = MAX_ZONE_VERTICES;
 
 #[derive(AsBindGroup, TypeUuid, Debug, Clone)]
 #[uuid = "9e124e04-fdf1-4836-b82d-fa2f01fddb62"]
@@ -24,14 +28,17 @@ pub struct TerrainMaterial {
     #[texture(2)]
     #[sampler(3)]
     texture: Handle<Image>,
+    #[uniform(4)]
+    zones: Zones,
 }
 
 impl TerrainMaterial {
-    pub(crate) fn new(texture: Handle<Image>) -> Self {
+    pub(crate) fn new(texture: Handle<Image>, zones: &TerrainZones) -> Self {
         Self {
             circles: KdTree::empty(),
             rectangles: Rectangles::default(),
             texture,
+            zones: Zones::from(zones),
         }
     }
 
@@ -248,6 +255,44 @@ impl Default for Rectangles {
     }
 }
 
+#[derive(ShaderType, Debug, Clone, Copy, Default)]
+struct Zone {
+    /// Only x and y coordinates are used. Vec4 is used due to uniform array
+    /// alignment rules.
+    vertices: [Vec4; ZONE_VERTEX_CAPACITY],
+    count: u32,
+    kind: u32,
+}
+
+#[derive(ShaderType, Debug, Clone)]
+struct Zones {
+    items: [Zone; ZONE_CAPACITY],
+    count: u32,
+}
+
+impl From<&TerrainZones> for Zones {
+    fn from(zones: &TerrainZones) -> Self {
+        let mut items = [Zone::default(); ZONE_CAPACITY];
+        for (item, zone) in items.iter_mut().zip(zones.zones()) {
+            for (target, vertex) in item.vertices.iter_mut().zip(zone.vertices()) {
+                *target = vertex.extend(0.).extend(0.);
+            }
+            item.count = zone.vertices().len() as u32;
+            // Keep these in sync with terrain.wgsl.
+            item.kind = match zone.kind() {
+                ZoneKind::Water => 0,
+                ZoneKind::Cliff => 1,
+                ZoneKind::Crater => 2,
+            };
+        }
+
+        Self {
+            items,
+            count: zones.zones().len() as u32,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use itertools::Itertools;
@@ -1,11 +1,10 @@
-use ahash::AHashMap;
+use ahash::AHashSet;
 use bevy::{
     prelude::{Bundle, Component, Mesh, Transform},
     render::{mesh::Indices, render_resource::PrimitiveTopology},
-    utils::FloatOrd,
 };
 use de_core::projection::{ToAltitude, ToFlat};
-use de_map::size::MapBounds;
+use de_map::{heightmap::Heightmap, size::MapBounds};
 use glam::{Vec2, Vec3};
 use parry3d::{
     math::Isometry,
@@ -14,6 +13,18 @@ use parry3d::{
     shape::HeightField,
 };
 
+use crate::deformation::TerrainDeformation;
+
+/// Desired distance between neighbouring terrain samples. Even flat terrain
+/// is sampled this densely so that it can be deformed.
+const SAMPLE_SPACING: f32 = 4.;
+/// Maximum number of terrain cells along a side of the map.
+const MAX_CELLS: usize = 1024;
+/// Number of terrain cells along a side of a single terrain chunk.
+const CHUNK_CELLS: usize = 64;
+/// Terrain cannot be deformed below this elevation.
+const MIN_HEIGHT: f32 = -16.;
+
 #[derive(Bundle)]
 pub struct TerrainBundle {
     transform: Transform,
@@ -22,12 +33,49 @@ pub struct TerrainBundle {
 
 impl TerrainBundle {
     pub fn flat(bounds: MapBounds) -> Self {
+        Self::new(bounds, 1, 1, |_| 0.)
+    }
+
+    /// Creates a terrain whose elevation is given by a heightmap.
+    pub fn from_heightmap(bounds: MapBounds, heightmap: &Heightmap) -> Self {
+        Self::new(
+            bounds,
+            heightmap.columns() - 1,
+            heightmap.rows() - 1,
+            |point| heightmap.height(bounds, point),
+        )
+    }
+
+    /// # Arguments
+    ///
+    /// * `bounds` - bounds of the map.
+    ///
+    /// * `min_columns` - minimum number of terrain cells along x axis.
+    ///
+    /// * `min_rows` - minimum number of terrain cells along y axis.
+    ///
+    /// * `height` - elevation of the terrain at a given point of the map.
+    fn new<F>(bounds: MapBounds, min_columns: usize, min_rows: usize, height: F) -> Self
+    where
+        F: Fn(Vec2) -> f32,
+    {
         let transform = Transform::from_translation(Vec3::from(bounds.aabb().to_msl().center()));
         let size = bounds.size();
-        let terrain = Terrain::new(HeightField::new(
-            DMatrix::from_row_slice(2, 2, &[0., 0., 0., 0.]),
-            Vector3::new(size.x, 1., size.y),
-        ));
+
+        let cells =
+            |min: usize, size: f32| ((size / SAMPLE_SPACING).ceil() as usize).clamp(min, MAX_CELLS);
+        let columns = cells(min_columns, size.x) + 1;
+        let rows = cells(min_rows, size.y) + 1;
+
+        // Heightfield rows go along z axis, i.e. from north to south.
+        let heights = DMatrix::from_fn(rows, columns, |row, column| {
+            let relative = Vec2::new(
+                column as f32 / (columns - 1) as f32,
+                1. - row as f32 / (rows - 1) as f32,
+            );
+            height(bounds.min() + relative * size)
+        });
+        let terrain = Terrain::new(HeightField::new(heights, Vector3::new(size.x, 1., size.y)));
 
         Self { transform, terrain }
     }
@@ -53,32 +101,162 @@ impl Terrain {
             .cast_ray_and_get_normal(m, ray, max_toi, true)
     }
 
-    pub(crate) fn generate_mesh(&self, translation: Vec3) -> Mesh {
+    /// Returns all chunks of the terrain. Each chunk is rendered as a
+    /// separate mesh so that only small part of the terrain has to be
+    /// re-meshed after a deformation.
+    pub(crate) fn chunks(&self) -> impl Iterator<Item = Chunk> {
+        let (rows, columns) = self.heightfield.num_cells_ij();
+        let rows = (0..rows).step_by(CHUNK_CELLS).len();
+        let columns = (0..columns).step_by(CHUNK_CELLS).len();
+        (0..rows).flat_map(move |row| (0..columns).map(move |column| Chunk { row, column }))
+    }
+
+    /// Lowers the terrain according to the deformations and returns all
+    /// chunks whose mesh has to be regenerated.
+    ///
+    /// # Arguments
+    ///
+    /// * `translation` - translation of the terrain.
+    ///
+    /// * `deformations` - deformations to be applied in the given order.
+    pub(crate) fn deform(
+        &mut self,
+        translation: Vec3,
+        deformations: &[TerrainDeformation],
+    ) -> AHashSet<Chunk> {
+        let mut heights = self.heightfield.heights().clone();
+        let mut dirty = AHashSet::new();
+
+        for deformation in deformations {
+            let aabb = deformation.aabb();
+            let (Some(rows), Some(columns)) = (
+                // Heightfield z axis goes against the map y axis.
+                self.samples(
+                    -aabb.maxs.y - translation.z,
+                    -aabb.mins.y - translation.z,
+                    0,
+                ),
+                self.samples(aabb.mins.x - translation.x, aabb.maxs.x - translation.x, 1),
+            ) else {
+                continue;
+            };
+
+            let mut changed = false;
+            for row in rows.0..=rows.1 {
+                for column in columns.0..=columns.1 {
+                    let point = self.sample_position(row, column).to_flat() + translation.to_flat();
+                    let change = deformation.height_change(point);
+                    if change < 0. {
+                        let height = &mut heights[(row, column)];
+                        *height = (*height + change).max(MIN_HEIGHT.min(*height));
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                continue;
+            }
+
+            // Normals of vertices next to the deformed samples change too.
+            let rows = (rows.0.saturating_sub(1), rows.1 + 1);
+            let columns = (columns.0.saturating_sub(1), columns.1 + 1);
+            for chunk in self.chunks() {
+                let (row_start, column_start) = chunk.start();
+                if row_start <= rows.1
+                    && rows.0 <= row_start + CHUNK_CELLS
+                    && column_start <= columns.1
+                    && columns.0 <= column_start + CHUNK_CELLS
+                {
+                    dirty.insert(chunk);
+                }
+            }
+        }
+
+        if !dirty.is_empty() {
+            self.heightfield = HeightField::new(heights, *self.heightfield.scale());
+        }
+        dirty
+    }
+
+    /// Returns the inclusive range of sample indices whose local coordinate
+    /// along an axis lies between `min` and `max`.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - 0 for rows (z axis) and 1 for columns (x axis).
+    fn samples(&self, min: f32, max: f32, axis: usize) -> Option<(usize, usize)> {
+        let (size, cells) = match axis {
+            0 => (self.heightfield.scale().z, self.heightfield.nrows()),
+            1 => (self.heightfield.scale().x, self.heightfield.ncols()),
+            _ => unreachable!(),
+        };
+        let cells = cells as f32;
+
+        let start = (((min / size + 0.5) * cells).ceil()).max(0.);
+        let end = (((max / size + 0.5) * cells).floor()).min(cells);
+        if start > end {
+            None
+        } else {
+            Some((start as usize, end as usize))
+        }
+    }
+
+    fn sample_position(&self, row: usize, column: usize) -> Vec3 {
+        Vec3::new(
+            self.heightfield.x_at(column),
+            self.heightfield.heights()[(row, column)],
+            self.heightfield.z_at(row),
+        )
+    }
+
+    /// Returns normal of the terrain at a sample approximated from the
+    /// neighbouring samples.
+    fn sample_normal(&self, row: usize, column: usize) -> Vec3 {
+        let heights = self.heightfield.heights();
+        let last_row = heights.nrows() - 1;
+        let last_column = heights.ncols() - 1;
+
+        let (west, east) = (column.saturating_sub(1), (column + 1).min(last_column));
+        let (north, south) = (row.saturating_sub(1), (row + 1).min(last_row));
+
+        let slope_x = (heights[(row, east)] - heights[(row, west)])
+            / (self.heightfield.x_at(east) - self.heightfield.x_at(west));
+        let slope_z = (heights[(south, column)] - heights[(north, column)])
+            / (self.heightfield.z_at(south) - self.heightfield.z_at(north));
+        Vec3::new(-slope_x, 1., -slope_z).normalize()
+    }
+
+    pub(crate) fn generate_mesh(&self, translation: Vec3, chunk: Chunk) -> Mesh {
         let translation = translation.to_flat();
 
-        let mut point_to_index: AHashMap<[FloatOrd; 2], u32> = AHashMap::new();
-        let mut indices: Vec<u32> = Vec::new();
+        let (cells_i, cells_j) = self.heightfield.num_cells_ij();
+        let (row_start, column_start) = chunk.start();
+        let row_end = (row_start + CHUNK_CELLS).min(cells_i);
+        let column_end = (column_start + CHUNK_CELLS).min(cells_j);
+        let columns = (column_end - column_start + 1) as u32;
 
         let mut positions = Vec::<[f32; 3]>::new();
         let mut normals = Vec::<[f32; 3]>::new();
         let mut uvs = Vec::<[f32; 2]>::new();
+        for row in row_start..=row_end {
+            for column in column_start..=column_end {
+                let point = self.sample_position(row, column);
+                positions.push(point.to_array());
+                normals.push(self.sample_normal(row, column).to_array());
+                uvs.push((point.to_flat() + translation).to_array());
+            }
+        }
 
-        for triangle in self.heightfield.triangles() {
-            for point in [triangle.a, triangle.c, triangle.b] {
-                let key = [FloatOrd(point.x), FloatOrd(point.z)];
-                match point_to_index.get(&key) {
-                    Some(&index) => indices.push(index),
-                    None => {
-                        let index = point_to_index.len() as u32;
-                        point_to_index.insert(key, index);
-                        indices.push(index);
-
-                        let world = Vec2::new(point.x, point.z).to_msl();
-                        positions.push([world.x, point.y, world.z]);
-                        normals.push([0., 1., 0.]);
-                        uvs.push([point.x + translation.x, point.z + translation.y]);
-                    }
-                }
+        let mut indices: Vec<u32> = Vec::new();
+        for row in 0..(row_end - row_start) as u32 {
+            for column in 0..columns - 1 {
+                let index_00 = row * columns + column;
+                let index_10 = index_00 + columns;
+                let index_01 = index_00 + 1;
+                let index_11 = index_10 + 1;
+                // The cell is split in the same way as in the heightfield
+                // and both triangles face upwards.
+                indices.extend([index_00, index_10, index_01, index_10, index_11, index_01]);
             }
         }
 
@@ -90,3 +268,65 @@ impl Terrain {
         mesh
     }
 }
+
+/// A rectangular part of the terrain rendered as a single mesh.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Chunk {
+    row: usize,
+    column: usize,
+}
+
+impl Chunk {
+    /// Returns heightfield row and column of the first sample of the chunk.
+    fn start(&self) -> (usize, usize) {
+        (self.row * CHUNK_CELLS, self.column * CHUNK_CELLS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deform() {
+        let bounds = MapBounds::new(Vec2::new(400., 300.));
+        let bundle = TerrainBundle::flat(bounds);
+        let mut terrain = bundle.terrain;
+        assert_eq!(terrain.heightfield.heights().ncols(), 101);
+        assert_eq!(terrain.heightfield.heights().nrows(), 76);
+        assert_eq!(terrain.chunks().count(), 4);
+
+        let dirty = terrain.deform(
+            bundle.transform.translation,
+            &[TerrainDeformation::crater(Vec2::new(-72., 102.), 8., 2.)],
+        );
+        assert_eq!(dirty.len(), 1);
+        assert!(dirty.contains(&Chunk { row: 0, column: 0 }));
+
+        let heights = terrain.heightfield.heights();
+        // (-72, 102) is at sample (12, 32).
+        for (sample, expected) in [
+            ((12, 32), -2.),
+            ((12, 33), -1.5),
+            ((11, 32), -1.5),
+            ((13, 31), -1.),
+            ((12, 34), 0.),
+        ] {
+            assert!((heights[sample] - expected).abs() < 1e-4);
+        }
+        assert_eq!(heights.iter().filter(|&&height| height < 0.).count(), 9);
+
+        let dirty = terrain.deform(
+            bundle.transform.translation,
+            &[TerrainDeformation::crater(Vec2::new(56., -106.), 4., 1.)],
+        );
+        // The crater is close to the corner of four chunks.
+        assert_eq!(dirty.len(), 4);
+
+        let dirty = terrain.deform(
+            bundle.transform.translation,
+            &[TerrainDeformation::crater(Vec2::new(500., 0.), 8., 2.)],
+        );
+        assert!(dirty.is_empty());
+    }
+}
@@ -0,0 +1,543 @@
+//! Boarding of units into carriers (e.g. garrisoned buildings) and their
+//! unloading.
+//!
+//! Carried units are kept alive but they are hidden and stripped of their
+//! solid marker, thus they are not indexed, cannot be targeted or hit and do
+//! not move on their own.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use de_behaviour::{ChaseTarget, ChaseTargetEvent, OrderQueue};
+use de_combat::{AttackMoving, Guarding};
+use de_core::{
+    baseset::GameSet,
+    gamestate::GameState,
+    objects::{Active, MovableSolid, ObjectType, Playable},
+    player::Player,
+    projection::{ToAltitude, ToFlat},
+    state::AppState,
+};
+use de_objects::{LaserCannon, SolidObjects};
+use de_pathing::{PathTarget, ScheduledPath};
+use de_spawner::PlacementValidator;
+
+use crate::commands::{ExecuteTransportEvent, ScheduledTransport, TransportCommand};
+
+/// Units board a carrier once they are at most this far from its footprint.
+const BOARDING_DISTANCE: f32 = 4.;
+/// Unloaded units are placed on concentric circles around the carrier. This
+/// is the number of the circles.
+const UNLOAD_RINGS: usize = 4;
+/// Number of candidate positions on each of the circles.
+const UNLOAD_SLOTS: usize = 16;
+/// Minimum gap between an unloaded unit and the carrier (or another unloaded
+/// unit).
+const UNLOAD_GAP: f32 = 1.;
+
+pub(crate) struct CarrierPlugin;
+
+impl Plugin for CarrierPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LoadEvent>()
+            .add_event::<UnloadEvent>()
+            .add_system(
+                configure
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_system(
+                forward
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(not(resource_exists::<ScheduledTransport>()))
+                    .before(TransportSet::Execute),
+            )
+            .add_system(
+                load.in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(TransportSet::Execute),
+            )
+            .add_system(
+                unload
+                    .in_base_set(GameSet::PreUpdate)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(TransportSet::Execute),
+            )
+            .add_system(
+                cancel_boarding
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                board
+                    .in_base_set(GameSet::Update)
+                    .run_if(in_state(GameState::Playing))
+                    .in_set(TransportSet::Board)
+                    .after(cancel_boarding),
+            )
+            .add_system(
+                follow
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                evacuate
+                    .in_base_set(GameSet::PostUpdate)
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq, SystemSet)]
+pub enum TransportSet {
+    Execute,
+    Board,
+}
+
+/// Send this event to make a unit move to a carrier (owned by the same
+/// player) and board it.
+pub struct LoadEvent {
+    unit: Entity,
+    carrier: Entity,
+}
+
+impl LoadEvent {
+    pub fn new(unit: Entity, carrier: Entity) -> Self {
+        Self { unit, carrier }
+    }
+
+    pub fn unit(&self) -> Entity {
+        self.unit
+    }
+
+    pub fn carrier(&self) -> Entity {
+        self.carrier
+    }
+}
+
+/// Send this event to unload all units carried by a carrier.
+pub struct UnloadEvent(Entity);
+
+impl UnloadEvent {
+    pub fn new(carrier: Entity) -> Self {
+        Self(carrier)
+    }
+
+    pub fn carrier(&self) -> Entity {
+        self.0
+    }
+}
+
+/// Units carried by an object which other units can board, see
+/// [`de_objects::Carrier`].
+#[derive(Component)]
+pub struct Passengers {
+    capacity: usize,
+    entities: Vec<Entity>,
+}
+
+impl Passengers {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entities: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Maximum number of carried units.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Carried units in the order of their boarding.
+    pub fn entities(&self) -> &[Entity] {
+        self.entities.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.entities.len() >= self.capacity
+    }
+}
+
+/// This component is attached to units carried by another object.
+#[derive(Component)]
+pub struct Transported {
+    carrier: Entity,
+    /// Cannon of the unit. It is removed from the unit while it is carried.
+    cannon: Option<LaserCannon>,
+    playable: bool,
+}
+
+impl Transported {
+    pub fn carrier(&self) -> Entity {
+        self.carrier
+    }
+
+    pub(crate) fn cannon(&self) -> Option<&LaserCannon> {
+        self.cannon.as_ref()
+    }
+}
+
+/// This component is attached to units moving to a carrier in order to
+/// board it.
+#[derive(Component)]
+struct Boarding(Entity);
+
+fn configure(
+    mut commands: Commands,
+    solids: SolidObjects,
+    new: Query<(Entity, &ObjectType), Added<Active>>,
+) {
+    for (entity, &object_type) in new.iter() {
+        if let Some(carrier) = solids.get(object_type).carrier() {
+            commands
+                .entity(entity)
+                .insert(Passengers::new(carrier.capacity()));
+        }
+    }
+}
+
+fn forward(
+    mut load_events: EventReader<LoadEvent>,
+    mut unload_events: EventReader<UnloadEvent>,
+    mut out_events: EventWriter<ExecuteTransportEvent>,
+) {
+    for event in load_events.iter() {
+        out_events.send(ExecuteTransportEvent::new(TransportCommand::Load {
+            unit: event.unit(),
+            carrier: event.carrier(),
+        }));
+    }
+    for event in unload_events.iter() {
+        out_events.send(ExecuteTransportEvent::new(TransportCommand::Unload {
+            carrier: event.carrier(),
+        }));
+    }
+}
+
+fn load(
+    mut commands: Commands,
+    solids: SolidObjects,
+    mut events: EventReader<ExecuteTransportEvent>,
+    units: Query<&Player, (With<MovableSolid>, Without<Transported>)>,
+    carriers: Query<(&Player, &ObjectType, &Passengers)>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+) {
+    for event in events.iter() {
+        let TransportCommand::Load { unit, carrier } = event.command() else {
+            continue;
+        };
+        if unit == carrier {
+            continue;
+        }
+        let (Ok(&unit_player), Ok((&carrier_player, &carrier_type, passengers))) =
+            (units.get(unit), carriers.get(carrier))
+        else {
+            continue;
+        };
+        if unit_player != carrier_player || passengers.is_full() {
+            continue;
+        }
+
+        commands
+            .entity(unit)
+            .remove::<(OrderQueue, Guarding, AttackMoving)>()
+            .insert(Boarding(carrier));
+        let max_distance = solids.get(carrier_type).ichnography().radius() + BOARDING_DISTANCE;
+        chase_events.send(ChaseTargetEvent::new(
+            unit,
+            Some(ChaseTarget::new(carrier, 0., max_distance)),
+        ));
+    }
+}
+
+fn unload(
+    mut commands: Commands,
+    solids: SolidObjects,
+    placement: PlacementValidator,
+    mut events: EventReader<ExecuteTransportEvent>,
+    units: Query<(&ObjectType, &Transported)>,
+    mut carriers: Query<(&ObjectType, &Transform, &mut Passengers)>,
+) {
+    for event in events.iter() {
+        let TransportCommand::Unload { carrier } = event.command() else {
+            continue;
+        };
+        let Ok((&carrier_type, transform, mut passengers)) = carriers.get_mut(carrier) else {
+            continue;
+        };
+
+        let center = transform.translation.to_flat();
+        let radius = solids.get(carrier_type).ichnography().radius();
+        let mut unloader = Unloader::new(&solids, &placement, center, radius);
+        passengers.entities.retain(|&unit| {
+            let Ok((&unit_type, transported)) = units.get(unit) else {
+                // The unit no longer exists.
+                return false;
+            };
+            match unloader.place(unit_type) {
+                Some(position) => {
+                    release(&mut commands, unit, transported, position);
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+}
+
+/// Cancels boarding of units which were given other orders.
+fn cancel_boarding(
+    mut commands: Commands,
+    units: Query<Entity, (With<Boarding>, Added<OrderQueue>)>,
+) {
+    for unit in units.iter() {
+        commands.entity(unit).remove::<Boarding>();
+    }
+}
+
+type BoardingComponents<'a> = (
+    Entity,
+    &'a Transform,
+    &'a ObjectType,
+    &'a Boarding,
+    Option<&'a LaserCannon>,
+    Option<&'a Playable>,
+);
+
+fn board(
+    mut commands: Commands,
+    solids: SolidObjects,
+    units: Query<BoardingComponents>,
+    mut carriers: Query<(&Transform, &ObjectType, &mut Passengers)>,
+    mut chase_events: EventWriter<ChaseTargetEvent>,
+) {
+    for (unit, transform, &unit_type, boarding, cannon, playable) in units.iter() {
+        let Ok((carrier_transform, &carrier_type, mut passengers)) = carriers.get_mut(boarding.0)
+        else {
+            commands.entity(unit).remove::<Boarding>();
+            continue;
+        };
+
+        let distance = transform
+            .translation
+            .to_flat()
+            .distance(carrier_transform.translation.to_flat());
+        let max_distance = solids.get(unit_type).ichnography().radius()
+            + solids.get(carrier_type).ichnography().radius()
+            + BOARDING_DISTANCE;
+        if distance > max_distance {
+            continue;
+        }
+
+        chase_events.send(ChaseTargetEvent::new(unit, None));
+        let mut entity_commands = commands.entity(unit);
+        entity_commands.remove::<Boarding>();
+        if passengers.is_full() {
+            continue;
+        }
+
+        info!("Unit {:?} boarded carrier {:?}.", unit, boarding.0);
+        passengers.entities.push(unit);
+        entity_commands
+            .remove::<(
+                MovableSolid,
+                Playable,
+                PathTarget,
+                ScheduledPath,
+                LaserCannon,
+            )>()
+            .insert((
+                Transported {
+                    carrier: boarding.0,
+                    cannon: cannon.cloned(),
+                    playable: playable.is_some(),
+                },
+                Visibility::Hidden,
+            ));
+    }
+}
+
+/// Keeps carried units at the position of their carrier.
+fn follow(
+    mut units: Query<(&Transported, &mut Transform)>,
+    carriers: Query<&Transform, (With<Passengers>, Without<Transported>)>,
+) {
+    for (transported, mut transform) in units.iter_mut() {
+        if let Ok(carrier_transform) = carriers.get(transported.carrier()) {
+            if transform.translation != carrier_transform.translation {
+                transform.translation = carrier_transform.translation;
+            }
+        }
+    }
+}
+
+/// Unloads units whose carrier no longer exists (e.g. it was destroyed).
+fn evacuate(
+    mut commands: Commands,
+    solids: SolidObjects,
+    placement: PlacementValidator,
+    units: Query<(Entity, &ObjectType, &Transform, &Transported)>,
+    carriers: Query<(), With<Passengers>>,
+) {
+    let mut unloaders: Vec<(Entity, Unloader)> = Vec::new();
+    for (unit, &unit_type, transform, transported) in units.iter() {
+        if carriers.contains(transported.carrier()) {
+            continue;
+        }
+
+        let index = match unloaders
+            .iter()
+            .position(|(carrier, _)| *carrier == transported.carrier())
+        {
+            Some(index) => index,
+            None => {
+                let center = transform.translation.to_flat();
+                let unloader = Unloader::new(&solids, &placement, center, 0.);
+                unloaders.push((transported.carrier(), unloader));
+                unloaders.len() - 1
+            }
+        };
+        if let Some(position) = unloaders[index].1.place(unit_type) {
+            release(&mut commands, unit, transported, position);
+        }
+    }
+}
+
+/// Makes a carried unit leave its carrier.
+fn release(commands: &mut Commands, unit: Entity, transported: &Transported, position: Vec2) {
+    info!("Unit {:?} left carrier {:?}.", unit, transported.carrier());
+
+    let mut entity_commands = commands.entity(unit);
+    entity_commands.remove::<Transported>().insert((
+        Transform::from_translation(position.to_msl()),
+        MovableSolid,
+        Visibility::Inherited,
+    ));
+    if let Some(cannon) = transported.cannon.as_ref() {
+        entity_commands.insert(cannon.clone());
+    }
+    if transported.playable {
+        entity_commands.insert(Playable);
+    }
+}
+
+/// Searches for free positions of units unloaded around a carrier.
+struct Unloader<'a, 'w, 's> {
+    solids: &'a SolidObjects<'w>,
+    placement: &'a PlacementValidator<'w, 's>,
+    center: Vec2,
+    radius: f32,
+    /// Positions and radii of units unloaded so far. They are not yet
+    /// indexed, thus they are not considered by the placement validator.
+    taken: Vec<(Vec2, f32)>,
+}
+
+impl<'a, 'w, 's> Unloader<'a, 'w, 's> {
+    /// # Arguments
+    ///
+    /// * `center` - flat position of the carrier.
+    ///
+    /// * `radius` - radius of the carrier.
+    fn new(
+        solids: &'a SolidObjects<'w>,
+        placement: &'a PlacementValidator<'w, 's>,
+        center: Vec2,
+        radius: f32,
+    ) -> Self {
+        Self {
+            solids,
+            placement,
+            center,
+            radius,
+            taken: Vec::new(),
+        }
+    }
+
+    /// Returns a free position for a unit of the given type or None if no
+    /// free position was found near the carrier.
+    fn place(&mut self, unit_type: ObjectType) -> Option<Vec2> {
+        let unit_radius = self.solids.get(unit_type).ichnography().radius();
+        let position = candidates(
+            self.center,
+            self.radius + unit_radius + UNLOAD_GAP,
+            unit_radius,
+        )
+        .filter(|&position| {
+            self.taken.iter().all(|&(other, other_radius)| {
+                other.distance(position) >= unit_radius + other_radius + UNLOAD_GAP
+            })
+        })
+        .find(|&position| {
+            self.placement
+                .is_allowed(unit_type, &Transform::from_translation(position.to_msl()))
+        })?;
+        self.taken.push((position, unit_radius));
+        Some(position)
+    }
+}
+
+/// Returns candidate positions of an unloaded unit. The positions lie on
+/// concentric circles around `center`, the closest circle first.
+///
+/// # Arguments
+///
+/// * `center` - center of the circles.
+///
+/// * `distance` - radius of the first circle.
+///
+/// * `unit_radius` - radius of the unloaded unit.
+fn candidates(center: Vec2, distance: f32, unit_radius: f32) -> impl Iterator<Item = Vec2> {
+    let spacing = 2. * unit_radius + UNLOAD_GAP;
+    (0..UNLOAD_RINGS).flat_map(move |ring| {
+        let radius = distance + ring as f32 * spacing;
+        (0..UNLOAD_SLOTS).map(move |slot| {
+            let angle = TAU * slot as f32 / UNLOAD_SLOTS as f32;
+            center + radius * Vec2::from_angle(angle)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passengers() {
+        let mut passengers = Passengers::new(2);
+        assert!(passengers.is_empty());
+        assert!(!passengers.is_full());
+
+        passengers.entities.push(Entity::from_raw(1));
+        passengers.entities.push(Entity::from_raw(2));
+        assert_eq!(passengers.len(), 2);
+        assert!(passengers.is_full());
+        assert_eq!(
+            passengers.entities(),
+            &[Entity::from_raw(1), Entity::from_raw(2)]
+        );
+    }
+
+    #[test]
+    fn test_candidates() {
+        let center = Vec2::new(10., -5.);
+        let positions: Vec<Vec2> = candidates(center, 20., 1.5).collect();
+        assert_eq!(positions.len(), UNLOAD_RINGS * UNLOAD_SLOTS);
+
+        assert!((positions[0] - Vec2::new(30., -5.)).length() < 1e-4);
+        for (i, position) in positions.iter().enumerate() {
+            let ring = i / UNLOAD_SLOTS;
+            let expected = 20. + ring as f32 * (3. + UNLOAD_GAP);
+            assert!((position.distance(center) - expected).abs() < 1e-4);
+        }
+    }
+}
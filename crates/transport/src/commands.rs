@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+/// Tags of encoded transport commands. They are distinct from tags of other
+/// commands scheduled for multiplayer simulation (e.g. movement commands).
+const LOAD_TAG: u8 = 48;
+const UNLOAD_TAG: u8 = 49;
+
+pub(crate) struct CommandsPlugin;
+
+impl Plugin for CommandsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExecuteTransportEvent>();
+    }
+}
+
+/// While this resource exists, [`crate::LoadEvent`] and [`crate::UnloadEvent`]
+/// are not executed right away. Whoever inserted the resource is responsible
+/// for scheduling of the corresponding [`TransportCommand`]s and for their
+/// execution via [`ExecuteTransportEvent`].
+///
+/// This is used to execute the commands in a particular simulation tick of a
+/// multiplayer game.
+#[derive(Resource)]
+pub struct ScheduledTransport;
+
+/// A transport command to be executed, see [`ScheduledTransport`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransportCommand {
+    /// Move a unit to a carrier and board it.
+    Load { unit: Entity, carrier: Entity },
+    /// Unload all units carried by a carrier.
+    Unload { carrier: Entity },
+}
+
+impl TransportCommand {
+    /// Maximum length of an encoded command in bytes.
+    pub const MAX_ENCODED_LEN: usize = 17;
+
+    /// Appends self-delimiting binary representation of the command to
+    /// `buf`.
+    ///
+    /// Units and carriers are encoded as local entities, thus the encoded
+    /// commands are meaningful only on the computer which encoded them.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        match *self {
+            Self::Load { unit, carrier } => {
+                buf.push(LOAD_TAG);
+                buf.extend_from_slice(&unit.to_bits().to_le_bytes());
+                buf.extend_from_slice(&carrier.to_bits().to_le_bytes());
+            }
+            Self::Unload { carrier } => {
+                buf.push(UNLOAD_TAG);
+                buf.extend_from_slice(&carrier.to_bits().to_le_bytes());
+            }
+        }
+    }
+
+    /// Decodes a command from the beginning of `bytes`. It returns the
+    /// command and the number of consumed bytes or None if the bytes do not
+    /// start with a valid command.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        let (&tag, rest) = bytes.split_first()?;
+
+        match tag {
+            LOAD_TAG => Some((
+                Self::Load {
+                    unit: decode_entity(rest)?,
+                    carrier: decode_entity(rest.get(8..)?)?,
+                },
+                17,
+            )),
+            UNLOAD_TAG => Some((
+                Self::Unload {
+                    carrier: decode_entity(rest)?,
+                },
+                9,
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn decode_entity(bytes: &[u8]) -> Option<Entity> {
+    Some(Entity::from_bits(u64::from_le_bytes(
+        bytes.get(..8)?.try_into().unwrap(),
+    )))
+}
+
+/// Send this event to execute a [`TransportCommand`]. The command is
+/// validated (e.g. against ownership and capacity of the carrier) before it
+/// is executed.
+pub struct ExecuteTransportEvent(TransportCommand);
+
+impl ExecuteTransportEvent {
+    pub fn new(command: TransportCommand) -> Self {
+        Self(command)
+    }
+
+    pub(crate) fn command(&self) -> TransportCommand {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        let commands = [
+            TransportCommand::Load {
+                unit: Entity::from_raw(7),
+                carrier: Entity::from_raw(1234),
+            },
+            TransportCommand::Unload {
+                carrier: Entity::from_raw(1234),
+            },
+        ];
+
+        let mut buf = Vec::new();
+        for command in commands {
+            let len = buf.len();
+            command.encode(&mut buf);
+            assert!(buf.len() - len <= TransportCommand::MAX_ENCODED_LEN);
+        }
+
+        let mut bytes = buf.as_slice();
+        for command in commands {
+            let (decoded, len) = TransportCommand::decode(bytes).unwrap();
+            assert_eq!(decoded, command);
+            bytes = &bytes[len..];
+        }
+        assert!(bytes.is_empty());
+
+        assert!(TransportCommand::decode(&buf[..12]).is_none());
+        assert!(TransportCommand::decode(&[16, 0, 0, 0, 0, 0, 0, 0, 0]).is_none());
+        assert!(TransportCommand::decode(&[]).is_none());
+    }
+}
@@ -0,0 +1,76 @@
+//! Buildings garrisoned by armed units fire at enemies.
+//!
+//! The cannon of a garrisoned building is derived from the strongest cannon
+//! among the carried units. Its damage scales with the number of carried
+//! armed units, see [`LaserCannon::garrisoned`].
+
+use bevy::prelude::*;
+use de_core::{
+    baseset::GameSet,
+    gamestate::GameState,
+    objects::{ObjectType, StaticSolid},
+};
+use de_objects::{LaserCannon, SolidObjects};
+
+use crate::carrier::{Passengers, Transported};
+
+/// Muzzle of a garrisoned building is this high above the top of its
+/// collider.
+const MUZZLE_ELEVATION: f32 = 1.;
+
+pub(crate) struct GarrisonPlugin;
+
+impl Plugin for GarrisonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            update
+                .in_base_set(GameSet::PostUpdate)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+type BuildingComponents<'a> = (Entity, &'a ObjectType, &'a Passengers);
+
+fn update(
+    mut commands: Commands,
+    solids: SolidObjects,
+    buildings: Query<BuildingComponents, (Changed<Passengers>, With<StaticSolid>)>,
+    units: Query<&Transported>,
+) {
+    for (entity, &object_type, passengers) in buildings.iter() {
+        let solid = solids.get(object_type);
+        let Some(carrier) = solid.carrier() else {
+            continue;
+        };
+
+        let cannons: Vec<&LaserCannon> = passengers
+            .entities()
+            .iter()
+            .filter_map(|&unit| units.get(unit).ok())
+            .filter_map(|transported| transported.cannon())
+            .collect();
+        let strongest = cannons
+            .iter()
+            .max_by(|a, b| a.damage().total_cmp(&b.damage()));
+
+        let mut entity_commands = commands.entity(entity);
+        match strongest {
+            Some(cannon) => {
+                let height = solid.collider().aabb().maxs.y;
+                let muzzle = Vec3::Y * (height + MUZZLE_ELEVATION);
+                entity_commands.insert(cannon.garrisoned(muzzle, cannons.len(), carrier));
+            }
+            // The building falls back to its own cannon (if any) once there
+            // are no armed units inside.
+            None => match solid.cannon() {
+                Some(cannon) => {
+                    entity_commands.insert(cannon.clone());
+                }
+                None => {
+                    entity_commands.remove::<LaserCannon>();
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use carrier::CarrierPlugin;
+pub use carrier::{LoadEvent, Passengers, TransportSet, Transported, UnloadEvent};
+use commands::CommandsPlugin;
+pub use commands::{ExecuteTransportEvent, ScheduledTransport, TransportCommand};
+use garrison::GarrisonPlugin;
+
+mod carrier;
+mod commands;
+mod garrison;
+
+pub struct TransportPluginGroup;
+
+impl PluginGroup for TransportPluginGroup {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(CommandsPlugin)
+            .add(CarrierPlugin)
+            .add(GarrisonPlugin)
+    }
+}
@@ -0,0 +1,286 @@
+//! Benchmark mode runs a synthetic battle of many units in the headless mode
+//! and reports frame times and times spent in individual game sets once the
+//! game ends.
+
+use std::{
+    env,
+    f32::consts::TAU,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use de_combat::AttackEvent;
+use de_core::{
+    baseset::GameSet,
+    gamestate::GameState,
+    objects::{Active, ActiveObjectType, UnitType},
+    player::{Player, PlayerRange},
+    projection::ToFlat,
+    state::AppState,
+    victory::VictoryCondition,
+};
+use de_map::{
+    content::{ActiveObject, InnerObject, Object},
+    description::MapDescription,
+    io::store_map,
+    map::Map,
+    meta::MapMetadata,
+    size::MapBounds,
+};
+use futures_lite::future;
+
+/// The benchmark game ends after this game time unless one of the armies is
+/// annihilated sooner.
+const DURATION: Duration = Duration::from_secs(120);
+/// Distance between neighbouring units of an army.
+const UNIT_SPACING: f32 = 4.;
+/// Minimum distance between two armies.
+const ARMY_GAP: f32 = 60.;
+/// Distance between the armies and the edge of the map.
+const MAP_MARGIN: f32 = 50.;
+
+/// Names of measured parts of each frame, see [`Timings`].
+const SECTIONS: [&str; 8] = [
+    "input",
+    "pre-movement",
+    "movement",
+    "post-movement",
+    "pre-update",
+    "update",
+    "post-update",
+    "other",
+];
+
+/// Victory conditions of the benchmark game.
+pub(crate) fn victory() -> Vec<VictoryCondition> {
+    vec![
+        VictoryCondition::Annihilation,
+        VictoryCondition::TimeLimit(DURATION),
+    ]
+}
+
+/// Generates a map with an army of `units` units for each player and
+/// stores it to a temporary file.
+///
+/// # Panics
+///
+/// Panics if the map cannot be stored.
+pub(crate) fn store_battle_map(units: u32, max_player: Player) -> PathBuf {
+    let map = battle_map(units, max_player);
+    let path = env::temp_dir().join("de-benchmark.dem.tar");
+    future::block_on(store_map(&map, path.as_path())).expect("Failed to store benchmark map.");
+    path
+}
+
+/// Returns a map with a square army of attackers of each player. The armies
+/// are evenly distributed around the center of the map.
+fn battle_map(units: u32, max_player: Player) -> Map {
+    let columns = (units as f32).sqrt().ceil() as u32;
+    let half_side = 0.5 * UNIT_SPACING * columns as f32;
+    let distance = 2. * half_side + ARMY_GAP;
+    let size = 2. * (distance + half_side * 2f32.sqrt() + MAP_MARGIN);
+
+    let bounds = MapBounds::new(Vec2::splat(size));
+    let mut map = Map::empty(MapMetadata::new("Benchmark".to_owned(), bounds, max_player));
+    map.set_description(MapDescription::new(
+        String::new(),
+        format!("Benchmark battle of {units} units per player."),
+        Some(max_player),
+    ));
+
+    let players = PlayerRange::up_to(max_player);
+    let count = players.len() as f32;
+    for (i, player) in players.enumerate() {
+        let angle = TAU * i as f32 / count;
+        let center = distance * Vec2::new(angle.cos(), angle.sin());
+        let corner = center - Vec2::splat(half_side - 0.5 * UNIT_SPACING);
+
+        for unit in 0..units {
+            let offset = Vec2::new((unit % columns) as f32, (unit / columns) as f32);
+            let object = Object::new(
+                map.new_placement(corner + UNIT_SPACING * offset, 0.),
+                InnerObject::Active(ActiveObject::new(
+                    ActiveObjectType::Unit(UnitType::Attacker),
+                    player,
+                )),
+            );
+            map.insert_object(object);
+        }
+    }
+
+    map
+}
+
+pub(crate) struct BenchmarkPlugin;
+
+impl Plugin for BenchmarkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timings>()
+            .add_system(attack.in_schedule(OnEnter(GameState::Playing)))
+            .add_system(report.in_schedule(OnExit(AppState::InGame)))
+            .add_system(
+                start_frame
+                    .in_base_set(CoreSet::First)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                end_frame
+                    .in_base_set(CoreSet::Last)
+                    .run_if(in_state(GameState::Playing)),
+            );
+
+        // Each game set is measured from the end of the previous one.
+        // Application of commands is included.
+        let sets = [
+            GameSet::InputFlush,
+            GameSet::PreMovementFlush,
+            GameSet::MovementFlush,
+            GameSet::PostMovementFlush,
+            GameSet::PreUpdateFlush,
+            GameSet::UpdateFlush,
+            GameSet::PostUpdateFlush,
+        ];
+        for (section, set) in sets.into_iter().enumerate() {
+            app.add_system(
+                (move |mut timings: ResMut<Timings>| timings.mark(section))
+                    .in_base_set(set)
+                    .run_if(in_state(GameState::Playing)),
+            );
+        }
+    }
+}
+
+/// Durations of all frames and total durations of individual sections of
+/// the frames.
+#[derive(Resource, Default)]
+struct Timings {
+    frame_start: Option<Instant>,
+    last_mark: Option<Instant>,
+    frames: Vec<Duration>,
+    sections: [Duration; SECTIONS.len()],
+}
+
+impl Timings {
+    fn start_frame(&mut self) {
+        let now = Instant::now();
+        self.frame_start = Some(now);
+        self.last_mark = Some(now);
+    }
+
+    /// Marks the end of the section with index `section`, i.e. the time
+    /// since the previous mark is added to the section.
+    fn mark(&mut self, section: usize) {
+        let now = Instant::now();
+        // Frames during which the game started are not measured.
+        if let Some(last) = self.last_mark {
+            self.sections[section] += now - last;
+            self.last_mark = Some(now);
+        }
+    }
+
+    fn end_frame(&mut self) {
+        if let Some(start) = self.frame_start.take() {
+            self.mark(SECTIONS.len() - 1);
+            self.frames.push(start.elapsed());
+        }
+        self.last_mark = None;
+    }
+}
+
+/// Returns the duration at the given percentile of durations sorted in
+/// ascending order.
+fn percentile(sorted: &[Duration], percentile: f32) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let index = ((sorted.len() - 1) as f32 * percentile / 100.).round() as usize;
+    sorted[index]
+}
+
+fn start_frame(mut timings: ResMut<Timings>) {
+    timings.start_frame();
+}
+
+fn end_frame(mut timings: ResMut<Timings>) {
+    timings.end_frame();
+}
+
+/// Commands each unit to attack a unit of the army of the next player.
+fn attack(
+    units: Query<(Entity, &Player, &Transform), With<Active>>,
+    mut events: EventWriter<AttackEvent>,
+) {
+    let mut armies: Vec<(Player, Vec<(Entity, Vec2)>)> = Vec::new();
+    for (entity, &player, transform) in units.iter() {
+        let position = transform.translation.to_flat();
+        match armies.iter_mut().find(|(other, _)| *other == player) {
+            Some((_, army)) => army.push((entity, position)),
+            None => armies.push((player, vec![(entity, position)])),
+        }
+    }
+    armies.sort_by_key(|(player, _)| *player);
+    for (_, army) in armies.iter_mut() {
+        army.sort_by(|(_, a), (_, b)| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    }
+
+    for (i, (_, army)) in armies.iter().enumerate() {
+        let (_, enemies) = &armies[(i + 1) % armies.len()];
+        for (j, &(unit, _)) in army.iter().enumerate() {
+            events.send(AttackEvent::new(unit, enemies[j % enemies.len()].0));
+        }
+    }
+}
+
+fn report(timings: Res<Timings>) {
+    let mut frames = timings.frames.clone();
+    frames.sort_unstable();
+    let total: Duration = frames.iter().sum();
+    let mean = total.checked_div(frames.len() as u32).unwrap_or_default();
+
+    info!("Benchmark finished after {} frames.", frames.len());
+    info!(
+        "Frame time: mean {:.2} ms, median {:.2} ms, 95th percentile {:.2} ms, max {:.2} ms",
+        mean.as_secs_f64() * 1000.,
+        percentile(&frames, 50.).as_secs_f64() * 1000.,
+        percentile(&frames, 95.).as_secs_f64() * 1000.,
+        percentile(&frames, 100.).as_secs_f64() * 1000.,
+    );
+    for (name, &duration) in SECTIONS.iter().zip(timings.sections.iter()) {
+        let share = if total.is_zero() {
+            0.
+        } else {
+            100. * duration.as_secs_f64() / total.as_secs_f64()
+        };
+        let per_frame = duration
+            .checked_div(frames.len() as u32)
+            .unwrap_or_default();
+        info!(
+            "  {name}: {:.2} ms per frame ({share:.1} %)",
+            per_frame.as_secs_f64() * 1000.
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[], 50.), Duration::ZERO);
+
+        let sorted: Vec<Duration> = (1..=5).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&sorted, 0.), Duration::from_millis(1));
+        assert_eq!(percentile(&sorted, 50.), Duration::from_millis(3));
+        assert_eq!(percentile(&sorted, 95.), Duration::from_millis(5));
+        assert_eq!(percentile(&sorted, 100.), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_battle_map() {
+        let map = battle_map(100, Player::Player4);
+        map.validate().unwrap();
+        assert_eq!(map.content().objects().len(), 400);
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use de_ai::Difficulty;
+#[cfg(feature = "benchmark")]
+use de_core::objects::PLAYER_MAX_UNITS;
+use de_core::player::Player;
+
+#[derive(Parser)]
+#[clap(author, version, about)]
+pub(crate) struct Cli {
+    #[clap(
+        long,
+        requires = "map",
+        help = "Run a game of AI players without a window and rendering."
+    )]
+    pub(crate) headless: bool,
+    #[clap(
+        short,
+        long,
+        value_parser,
+        help = "Path of a Digital Extinction map file to play in headless mode."
+    )]
+    pub(crate) map: Option<PathBuf>,
+    #[clap(
+        short,
+        long,
+        default_value = "2",
+        value_parser = parse_players,
+        help = "Number of AI players (2 to 4)."
+    )]
+    pub(crate) players: Player,
+    #[clap(
+        short,
+        long,
+        default_value = "normal",
+        value_parser = parse_difficulty,
+        help = "Difficulty of AI players: easy, normal or hard."
+    )]
+    pub(crate) difficulty: Difficulty,
+    #[clap(
+        short,
+        long,
+        default_value = "60",
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Number of simulation updates per second in headless mode."
+    )]
+    pub(crate) tick_rate: u16,
+    #[cfg(feature = "benchmark")]
+    #[clap(
+        long,
+        value_name = "UNITS",
+        conflicts_with = "map",
+        value_parser = clap::value_parser!(u32).range(1..=i64::from(PLAYER_MAX_UNITS)),
+        help = "Run a headless benchmark battle with the given number of units per player."
+    )]
+    pub(crate) benchmark: Option<u32>,
+}
+
+/// Parses number of players to the last player of the game.
+fn parse_players(value: &str) -> Result<Player, String> {
+    let count: u8 = value.parse().map_err(|error| format!("{error}"))?;
+    if count < 2 {
+        return Err("at least 2 players are needed".to_owned());
+    }
+    Player::try_from(count)
+}
+
+fn parse_difficulty(value: &str) -> Result<Difficulty, String> {
+    match value {
+        "easy" => Ok(Difficulty::Easy),
+        "normal" => Ok(Difficulty::Normal),
+        "hard" => Ok(Difficulty::Hard),
+        _ => Err(format!("unknown difficulty: {value}")),
+    }
+}
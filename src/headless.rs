@@ -0,0 +1,153 @@
+//! Headless mode runs a single game of AI players without a window and
+//! without rendering, e.g. for dedicated hosting or testing of the AI. The
+//! application exits once the game ends.
+
+use std::{path::PathBuf, time::Duration};
+
+use bevy::{
+    app::{AppExit, PluginGroupBuilder, ScheduleRunnerPlugin, ScheduleRunnerSettings},
+    log::LogPlugin,
+    prelude::*,
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use de_ai::{AiConf, Difficulty};
+use de_core::{
+    gconfig::{GameConfig, LocalPlayers},
+    gresult::GameResult,
+    player::{Player, PlayerRange},
+    state::AppState,
+    summary::MatchSummary,
+    victory::VictoryCondition,
+};
+
+/// Returns Bevy default plugins configured to run without a window and
+/// without a renderer. The app update rate is configured by
+/// [`HeadlessPlugin`].
+pub(crate) fn default_plugins() -> PluginGroupBuilder {
+    DefaultPlugins
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        })
+        .set(RenderPlugin {
+            wgpu_settings: WgpuSettings {
+                backends: None,
+                ..default()
+            },
+        })
+        .disable::<WinitPlugin>()
+        .disable::<LogPlugin>()
+        .add(ScheduleRunnerPlugin)
+}
+
+pub(crate) struct HeadlessPlugin {
+    conf: HeadlessConf,
+}
+
+impl HeadlessPlugin {
+    /// # Arguments
+    ///
+    /// * `map_path` - path of the map to be played.
+    ///
+    /// * `max_player` - the last player of the game. All players are
+    ///   controlled by the AI.
+    ///
+    /// * `difficulty` - difficulty of all AI players.
+    ///
+    /// * `tick_rate` - number of app updates per second.
+    pub(crate) fn new(
+        map_path: PathBuf,
+        max_player: Player,
+        difficulty: Difficulty,
+        tick_rate: u16,
+    ) -> Self {
+        Self {
+            conf: HeadlessConf {
+                map_path,
+                max_player,
+                difficulty,
+                tick_rate,
+                victory: None,
+            },
+        }
+    }
+
+    /// Overrides the default victory conditions of the game.
+    pub(crate) fn with_victory(mut self, victory: Vec<VictoryCondition>) -> Self {
+        self.conf.victory = Some(victory);
+        self
+    }
+}
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1. / f64::from(self.conf.tick_rate),
+        )))
+        .insert_resource(self.conf.clone())
+        .add_system(start.in_schedule(OnEnter(AppState::InMenu)));
+    }
+}
+
+#[derive(Resource, Clone)]
+struct HeadlessConf {
+    map_path: PathBuf,
+    max_player: Player,
+    difficulty: Difficulty,
+    tick_rate: u16,
+    victory: Option<Vec<VictoryCondition>>,
+}
+
+/// Starts the game once the app is loaded and exits the app once the game
+/// ends and the menu would be entered again.
+fn start(
+    mut commands: Commands,
+    conf: Res<HeadlessConf>,
+    result: Option<Res<GameResult>>,
+    summary: Option<Res<MatchSummary>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(result) = result else {
+        info!(
+            "Starting a headless game on {} with {} players.",
+            conf.map_path.display(),
+            conf.max_player.to_num()
+        );
+        let players = PlayerRange::up_to(conf.max_player);
+        // The game is observed from the point of view of the first player,
+        // thus it ends once the first player and their allies are
+        // eliminated.
+        let mut config = GameConfig::new(
+            conf.map_path.as_path(),
+            conf.max_player,
+            LocalPlayers::new(Player::Player1),
+        );
+        if let Some(victory) = conf.victory.clone() {
+            config = config.with_victory(victory);
+        }
+        commands.insert_resource(config);
+        commands.insert_resource(AiConf::new(conf.difficulty, players));
+        next_state.set(AppState::InGame);
+        return;
+    };
+
+    match result.as_ref() {
+        GameResult::Finished(result) => {
+            info!("Headless game finished, player 1 won: {}.", result.won())
+        }
+        GameResult::Error(message) => error!("Headless game failed: {message}"),
+    }
+
+    if let Some(summary) = summary {
+        info!("Game duration: {:?}", summary.duration());
+        for player in summary.players() {
+            info!("{}: {:?}", player.player(), player.totals());
+        }
+    }
+
+    exit.send(AppExit);
+}
@@ -8,6 +8,9 @@ use bevy::{
     prelude::*,
     window::WindowMode,
 };
+use clap::Parser;
+use cli::Cli;
+use de_ai::AiPluginGroup;
 use de_audio::AudioPluginGroup;
 use de_behaviour::BehaviourPluginGroup;
 use de_camera::CameraPluginGroup;
@@ -16,22 +19,33 @@ use de_conf::ConfigPluginGroup;
 use de_construction::ConstructionPluginGroup;
 use de_controller::ControllerPluginGroup;
 use de_core::{state::AppState, transition::DeStateTransition, CorePluginGroup};
+use de_editor::EditorPluginGroup;
 use de_energy::EnergyPluginGroup;
 use de_gui::GuiPluginGroup;
 use de_index::IndexPluginGroup;
 use de_loader::LoaderPluginGroup;
 use de_lobby_client::LobbyClientPluginGroup;
+use de_loc::LocPluginGroup;
 use de_log::LogPluginGroup;
 use de_menu::MenuPluginGroup;
 use de_movement::MovementPluginGroup;
 use de_multiplayer::MultiplayerPluginGroup;
 use de_objects::ObjectsPluginGroup;
 use de_pathing::PathingPluginGroup;
+use de_persistence::PersistencePluginGroup;
+use de_scenario::ScenarioPluginGroup;
 use de_signs::SignsPluginGroup;
 use de_spawner::SpawnerPluginGroup;
 use de_terrain::TerrainPluginGroup;
+use de_transport::TransportPluginGroup;
+use headless::HeadlessPlugin;
 use tracing::{span, Level};
 
+#[cfg(feature = "benchmark")]
+mod benchmark;
+mod cli;
+mod headless;
+
 const CARGO_PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 const GIT_SHA: &str = env!("GIT_SHA");
 
@@ -40,6 +54,8 @@ fn main() {
     // we want logging as early as possible
     app.add_plugins(LogPluginGroup);
 
+    let cli = Cli::parse();
+
     info!(
         "Starting Digital Extinction {{ \"Version\": \"{}\", \"GitSha\": \"{}\" }}",
         CARGO_PKG_VERSION, GIT_SHA
@@ -49,52 +65,97 @@ fn main() {
         let span = span!(Level::TRACE, "Startup");
         let _enter = span.enter();
 
-        app.insert_resource(Msaa::Sample4)
-            .add_plugins(
-                DefaultPlugins
-                    .set(WindowPlugin {
-                        primary_window: Some(Window {
-                            title: "Digital Extinction".to_string(),
-                            mode: WindowMode::BorderlessFullscreen,
-                            ..Default::default()
-                        }),
-                        ..default()
-                    })
-                    .disable::<LogPlugin>(),
-            )
-            .add_plugin(LogDiagnosticsPlugin {
-                debug: false,
-                wait_duration: Duration::from_secs(10),
-                filter: None,
-            })
-            .add_plugin(FrameTimeDiagnosticsPlugin)
-            .add_plugin(GamePlugin)
-            .add_plugins(ConfigPluginGroup)
-            .add_plugins(GuiPluginGroup)
-            .add_plugins(LobbyClientPluginGroup)
-            .add_plugins(MenuPluginGroup)
-            .add_plugins(CorePluginGroup)
-            .add_plugins(EnergyPluginGroup)
-            .add_plugins(ObjectsPluginGroup)
-            .add_plugins(TerrainPluginGroup)
-            .add_plugins(LoaderPluginGroup)
-            .add_plugins(IndexPluginGroup)
-            .add_plugins(PathingPluginGroup)
-            .add_plugins(SignsPluginGroup)
-            .add_plugins(SpawnerPluginGroup)
-            .add_plugins(MovementPluginGroup)
-            .add_plugins(ControllerPluginGroup)
-            .add_plugins(CameraPluginGroup)
-            .add_plugins(BehaviourPluginGroup)
-            .add_plugins(CombatPluginGroup)
-            .add_plugins(ConstructionPluginGroup)
-            .add_plugins(AudioPluginGroup)
-            .add_plugins(MultiplayerPluginGroup);
+        if let Some(headless) = headless_plugin(&cli) {
+            app.add_plugins(headless::default_plugins())
+                .add_plugin(headless)
+                .add_state_with_set::<AppState>();
+
+            #[cfg(feature = "benchmark")]
+            if cli.benchmark.is_some() {
+                app.add_plugin(benchmark::BenchmarkPlugin);
+            }
+        } else {
+            app.insert_resource(Msaa::Sample4)
+                .add_plugins(
+                    DefaultPlugins
+                        .set(WindowPlugin {
+                            primary_window: Some(Window {
+                                title: "Digital Extinction".to_string(),
+                                mode: WindowMode::BorderlessFullscreen,
+                                ..Default::default()
+                            }),
+                            ..default()
+                        })
+                        .set(AssetPlugin {
+                            // Object models and configuration are hot reloaded in
+                            // development builds.
+                            watch_for_changes: cfg!(feature = "dev"),
+                            ..default()
+                        })
+                        .disable::<LogPlugin>(),
+                )
+                .add_plugin(LogDiagnosticsPlugin {
+                    debug: false,
+                    wait_duration: Duration::from_secs(10),
+                    filter: None,
+                })
+                .add_plugin(FrameTimeDiagnosticsPlugin)
+                .add_plugin(GamePlugin)
+                .add_plugins(LobbyClientPluginGroup)
+                .add_plugins(MenuPluginGroup)
+                .add_plugins(ControllerPluginGroup)
+                .add_plugins(EditorPluginGroup);
+        }
+        add_simulation_plugins(&mut app);
     }
 
     app.run();
 }
 
+/// Returns the headless plugin if the game is run in the headless mode.
+fn headless_plugin(cli: &Cli) -> Option<HeadlessPlugin> {
+    #[cfg(feature = "benchmark")]
+    if let Some(units) = cli.benchmark {
+        let map_path = benchmark::store_battle_map(units, cli.players);
+        let plugin = HeadlessPlugin::new(map_path, cli.players, cli.difficulty, cli.tick_rate)
+            .with_victory(benchmark::victory());
+        return Some(plugin);
+    }
+
+    cli.headless.then(|| {
+        let map_path = cli.map.clone().expect("Map is required in headless mode.");
+        HeadlessPlugin::new(map_path, cli.players, cli.difficulty, cli.tick_rate)
+    })
+}
+
+/// Adds plugins of the game which do not need user interaction, i.e. all
+/// plugins needed by the headless mode.
+fn add_simulation_plugins(app: &mut App) {
+    app.add_plugins(ConfigPluginGroup)
+        .add_plugins(LocPluginGroup)
+        .add_plugins(GuiPluginGroup)
+        .add_plugins(CorePluginGroup)
+        .add_plugins(EnergyPluginGroup)
+        .add_plugins(ObjectsPluginGroup)
+        .add_plugins(TerrainPluginGroup)
+        .add_plugins(LoaderPluginGroup)
+        .add_plugins(PersistencePluginGroup)
+        .add_plugins(ScenarioPluginGroup)
+        .add_plugins(IndexPluginGroup)
+        .add_plugins(PathingPluginGroup)
+        .add_plugins(SignsPluginGroup)
+        .add_plugins(SpawnerPluginGroup)
+        .add_plugins(MovementPluginGroup)
+        .add_plugins(CameraPluginGroup)
+        .add_plugins(BehaviourPluginGroup)
+        .add_plugins(CombatPluginGroup)
+        .add_plugins(ConstructionPluginGroup)
+        .add_plugins(TransportPluginGroup)
+        .add_plugins(AudioPluginGroup)
+        .add_plugins(AiPluginGroup)
+        .add_plugins(MultiplayerPluginGroup);
+}
+
 struct GamePlugin;
 
 impl Plugin for GamePlugin {